@@ -0,0 +1,93 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! Benchmarks on a synthetic account with 100k outputs and 50k messages.
+//!
+//! `cargo bench --features bench`
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use iota_wallet::{bench, message::MessageType};
+
+const ADDRESS_COUNT: usize = 1_000;
+const OUTPUTS_PER_ADDRESS: usize = 100;
+const MESSAGE_COUNT: usize = 50_000;
+
+fn large_account(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let storage_path = std::env::temp_dir().join(format!("wallet-bench-{}", std::process::id()));
+
+    let addresses = bench::generate_addresses(ADDRESS_COUNT, OUTPUTS_PER_ADDRESS);
+    let (manager, account_handle) = runtime.block_on(async {
+        let messages = bench::generate_messages(MESSAGE_COUNT, &addresses).await.unwrap();
+        let manager = bench::account_manager(&storage_path).await.unwrap();
+        let account_handle = bench::create_account(&manager, addresses.clone(), messages)
+            .await
+            .unwrap();
+        (manager, account_handle)
+    });
+
+    // the state of a sync that found one new output on every tenth address
+    let synced_addresses = {
+        let mut synced_addresses = addresses.clone();
+        let new_outputs = bench::generate_addresses(ADDRESS_COUNT, 1);
+        for (address, new_output) in synced_addresses.iter_mut().zip(new_outputs).step_by(10) {
+            let mut outputs = address.outputs().clone();
+            for (output_id, output) in new_output.outputs() {
+                let mut output = output.clone();
+                output.address = address.address().clone();
+                outputs.insert(*output_id, output);
+            }
+            *address = iota_wallet::address::AddressBuilder::new()
+                .address(address.address().clone())
+                .key_index(*address.key_index())
+                .outputs(outputs.into_values().collect())
+                .build()
+                .unwrap();
+        }
+        synced_addresses
+    };
+    c.bench_function("sync: diff 100k outputs", |b| {
+        b.to_async(&runtime).iter(|| async {
+            bench::diff_synced_addresses(&addresses, &synced_addresses)
+                .await
+                .unwrap()
+        })
+    });
+
+    c.bench_function("input selection: 10 Mi from 100k outputs", |b| {
+        b.iter(|| bench::select_inputs(10_000_000, &addresses, 127).unwrap())
+    });
+
+    let input = addresses[0].outputs().values().next().unwrap().clone();
+    c.bench_function("message parsing: transaction payload", |b| {
+        b.to_async(&runtime).iter_batched(
+            || bench::transaction_payload(&input, addresses[1].address()),
+            |payload| async { bench::parse_payload(payload, &addresses).await.unwrap() },
+            BatchSize::SmallInput,
+        )
+    });
+
+    c.bench_function("storage: list 100 received messages", |b| {
+        b.to_async(&runtime).iter(|| async {
+            account_handle
+                .list_messages(100, 0, Some(MessageType::Received))
+                .await
+                .unwrap()
+        })
+    });
+    c.bench_function("storage: balance", |b| {
+        b.to_async(&runtime)
+            .iter(|| async { account_handle.balance().await.unwrap() })
+    });
+
+    drop(account_handle);
+    drop(manager);
+    let _ = std::fs::remove_dir_all(storage_path);
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = large_account
+}
+criterion_main!(benches);
@@ -13,7 +13,7 @@ use iota_wallet::{
 use std::{
     cell::RefCell,
     num::NonZeroU64,
-    path::{Path, PathBuf},
+    path::PathBuf,
     rc::Rc,
     time::Duration,
 };
@@ -147,7 +147,7 @@ pub struct AccountManager {
 }
 
 impl AccountManager {
-    pub fn storage_path(&self) -> &Path {
+    pub fn storage_path(&self) -> PathBuf {
         self.manager.storage_path()
     }
 
@@ -180,6 +180,17 @@ impl AccountManager {
         }
     }
 
+    pub fn change_storage_password(&mut self, current_password: &str, new_password: &str) -> Result<()> {
+        match crate::block_on(async move {
+            self.manager
+                .change_storage_password(current_password, new_password)
+                .await
+        }) {
+            Err(e) => Err(anyhow!(e.to_string())),
+            Ok(_) => Ok(()),
+        }
+    }
+
     pub fn set_stronghold_password(&mut self, password: &str) -> Result<()> {
         match crate::block_on(async move { self.manager.set_stronghold_password(password).await }) {
             Err(e) => Err(anyhow!(e.to_string())),
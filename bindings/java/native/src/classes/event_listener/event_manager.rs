@@ -83,6 +83,8 @@ pub enum TransferProgressType {
     SigningTransaction = 4,
     PerformingPoW = 5,
     Broadcasting = 6,
+    ChainedTransfer = 7,
+    RemainderAddressVerification = 8,
 }
 
 pub fn transfer_progress_type_enum_to_type(transfer_type: &WalletTransferProgressType) -> TransferProgressType {
@@ -96,6 +98,10 @@ pub fn transfer_progress_type_enum_to_type(transfer_type: &WalletTransferProgres
         WalletTransferProgressType::SigningTransaction { .. } => TransferProgressType::SigningTransaction,
         WalletTransferProgressType::PerformingPoW { .. } => TransferProgressType::PerformingPoW,
         WalletTransferProgressType::Broadcasting { .. } => TransferProgressType::Broadcasting,
+        WalletTransferProgressType::ChainedTransfer { .. } => TransferProgressType::ChainedTransfer,
+        WalletTransferProgressType::RemainderAddressVerification { .. } => {
+            TransferProgressType::RemainderAddressVerification
+        }
     }
 }
 
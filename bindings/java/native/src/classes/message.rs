@@ -148,9 +148,12 @@ impl TransferBuilder {
         TransferBuilder::new_with_builder(new_builder)
     }
 
-    /// Builds the transfer.
-    pub fn finish(&self) -> Transfer {
-        Transfer(self.builder.borrow_mut().take().unwrap().finish())
+    /// Validates the transfer and builds it.
+    pub fn finish(&self) -> Result<Transfer> {
+        match self.builder.borrow_mut().take().unwrap().finish() {
+            Ok(transfer) => Ok(Transfer(transfer)),
+            Err(e) => Err(anyhow::anyhow!(e.to_string())),
+        }
     }
 }
 
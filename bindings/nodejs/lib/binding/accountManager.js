@@ -16,6 +16,7 @@ const {
     backup,
     importAccounts,
     setStoragePassword,
+    changeStoragePassword,
     changeStrongholdPassword,
     generateMnemonic,
     removeAccount,
@@ -152,6 +153,13 @@ class AccountManager {
         return setStoragePassword.apply(this.accountManager, [password]);
     }
 
+    changeStoragePassword(currentPassword, newPassword) {
+        return changeStoragePassword.apply(this.accountManager, [
+            currentPassword,
+            newPassword,
+        ]);
+    }
+
     changeStrongholdPassword(currentPassword, oldPassword) {
         return changeStrongholdPassword.apply(this.accountManager, [
             currentPassword,
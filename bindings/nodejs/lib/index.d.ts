@@ -11,6 +11,14 @@ export declare enum MessageType {
   Value = 5,
   /// Message confirmed.
   Confirmed = 6,
+  /// Transaction sending value to the account's own addresses.
+  SelfTransfer = 7,
+  /// Transaction merging several account outputs into one.
+  Consolidation = 8,
+  /// Transaction holding a participation payload.
+  Participation = 9,
+  /// Message without a transaction, e.g. an indexation-only data message.
+  Data = 10,
 }
 
 export declare interface RegularEssence {
@@ -150,6 +158,18 @@ export declare interface Message {
   confirmed: boolean;
   broadcasted: boolean;
   reattachmentMessageId?: string | null;
+  reattachmentOf?: string;
+  reattachments?: string[];
+  fundingSources?: FundingSource[];
+}
+
+export declare interface FundingSource {
+  address: string;
+  keyIndex: number;
+  internal: boolean;
+  output: string;
+  messageId: string;
+  amount: number;
 }
 
 export declare interface AddressOutput {
@@ -253,6 +273,7 @@ export declare class RemainderValueStrategy {
   static changeAddress(): RemainderValueStrategy;
   static reuseAddress(): RemainderValueStrategy;
   static accountAddress(address: string): RemainderValueStrategy;
+  static splitChange(count: number): RemainderValueStrategy;
 }
 
 export declare class TransferOptions {
@@ -263,6 +284,7 @@ export declare class TransferOptions {
   };
   skipSync?: boolean;
   outputKind?: OutputKind;
+  context?: any;
 }
 
 export declare interface TransferOutput {
@@ -329,6 +351,7 @@ export declare interface ManagerOptions {
   allowCreateMultipleEmptyAccounts?: boolean;
   pollingInterval?: number;
   skipPolling?: boolean;
+  authTokens?: { [token: string]: 'ReadOnly' | 'ReceiveAddressOnly' | 'Full' };
 }
 
 export declare interface BalanceChangeEvent {
@@ -361,6 +384,7 @@ export declare class AccountManager {
   ): Promise<void>;
   stopBackgroundSync(): void;
   setStoragePassword(password: string): void;
+  changeStoragePassword(currentPassword: string, newPassword: string): void;
   setStrongholdPassword(password: string): void;
   changeStrongholdPassword(currentPassword: string, newPassword: string): void;
   generateMnemonic(): string;
@@ -41,5 +41,9 @@ module.exports = {
         Unconfirmed: 4,
         Value: 5,
         Confirmed: 6,
+        SelfTransfer: 7,
+        Consolidation: 8,
+        Participation: 9,
+        Data: 10,
     },
 };
@@ -39,6 +39,13 @@ class RemainderValueStrategy {
             value: address,
         };
     }
+
+    splitChange(count) {
+        return {
+            strategy: 'SplitChange',
+            value: count,
+        };
+    }
 }
 
 class OutputKind {
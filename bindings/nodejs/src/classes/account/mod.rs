@@ -28,6 +28,8 @@ struct TransferOptions {
     skip_sync: bool,
     #[serde(rename = "outputKind", default)]
     output_kind: Option<OutputKind>,
+    #[serde(default)]
+    context: Option<serde_json::Value>,
 }
 
 #[derive(Deserialize, Default)]
@@ -579,8 +581,14 @@ pub fn send(mut cx: FunctionContext) -> JsResult<JsUndefined> {
     if options.skip_sync {
         transfer_builder = transfer_builder.with_skip_sync();
     }
+    if let Some(context) = options.context {
+        transfer_builder = transfer_builder.with_context(context);
+    }
 
-    let transfer = transfer_builder.finish();
+    let transfer = match transfer_builder.finish() {
+        Ok(transfer) => transfer,
+        Err(e) => return cx.throw_error(e.to_string()),
+    };
 
     let id = account_wrapper.account_id.clone();
     crate::RUNTIME.spawn(async move {
@@ -657,7 +665,7 @@ pub fn send_to_many(mut cx: FunctionContext) -> JsResult<JsUndefined> {
     };
 
     let mut transfer_builder = Transfer::builder_with_outputs(outputs)
-        .expect("Outputs must be less then 125")
+        .expect("outputs are validated when the transfer is built")
         .with_remainder_value_strategy(options.remainder_value_strategy);
     if let Some(indexation) = options.indexation {
         transfer_builder = transfer_builder.with_indexation(
@@ -668,8 +676,14 @@ pub fn send_to_many(mut cx: FunctionContext) -> JsResult<JsUndefined> {
     if options.skip_sync {
         transfer_builder = transfer_builder.with_skip_sync();
     }
+    if let Some(context) = options.context {
+        transfer_builder = transfer_builder.with_context(context);
+    }
 
-    let transfer = transfer_builder.finish();
+    let transfer = match transfer_builder.finish() {
+        Ok(transfer) => transfer,
+        Err(e) => return cx.throw_error(e.to_string()),
+    };
     let id = account_wrapper.account_id.clone();
 
     crate::RUNTIME.spawn(async move {
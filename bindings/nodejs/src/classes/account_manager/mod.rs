@@ -248,6 +248,29 @@ pub fn set_storage_password(mut cx: FunctionContext) -> JsResult<JsUndefined> {
     Ok(cx.undefined())
 }
 
+pub fn change_storage_password(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+    let wrapper = Arc::clone(
+        &&cx.this()
+            .downcast_or_throw::<JsBox<Arc<AccountManagerWrapper>>, FunctionContext>(&mut cx)?,
+    );
+    let current_password = cx.argument::<JsString>(0)?.value(&mut cx);
+    let new_password = cx.argument::<JsString>(1)?.value(&mut cx);
+
+    let (sender, receiver) = channel();
+    crate::RUNTIME.spawn(async move {
+        let result = wrapper
+            .account_manager
+            .change_storage_password(current_password, new_password)
+            .await;
+        let _ = sender.send(result);
+    });
+
+    match receiver.recv().unwrap() {
+        Ok(_) => Ok(cx.undefined()),
+        Err(e) => cx.throw_error(e.to_string()),
+    }
+}
+
 pub fn set_stronghold_password(mut cx: FunctionContext) -> JsResult<JsUndefined> {
     let wrapper = Arc::clone(
         &&cx.this()
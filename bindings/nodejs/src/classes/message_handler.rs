@@ -33,6 +33,8 @@ const DUMMY_ID: &str = "1";
 pub(crate) struct DispatchMessage {
     #[serde(flatten)]
     pub(crate) message: MessageType,
+    #[serde(rename = "authToken", default)]
+    pub(crate) auth_token: Option<String>,
 }
 
 pub struct MessageHandler {
@@ -78,7 +80,10 @@ impl MessageHandler {
         let manager = crate::RUNTIME
             .block_on(manager.finish())
             .expect("error initializing account manager");
-        let message_handler = WalletMessageHandler::with_manager(manager);
+        let mut message_handler = WalletMessageHandler::with_manager(manager);
+        for (token, scope) in options.auth_tokens {
+            message_handler = message_handler.with_auth_token(token, scope);
+        }
 
         Arc::new(Self {
             channel,
@@ -91,7 +96,10 @@ impl MessageHandler {
         match serde_json::from_str::<DispatchMessage>(&serialized_message) {
             Ok(message) => {
                 let (response_tx, mut response_rx) = unbounded_channel();
-                let wallet_message = WalletMessage::new(DUMMY_ID, message.message.clone(), response_tx);
+                let mut wallet_message = WalletMessage::new(DUMMY_ID, message.message.clone(), response_tx);
+                if let Some(token) = message.auth_token {
+                    wallet_message = wallet_message.with_auth_token(token);
+                }
 
                 self.message_handler.handle(wallet_message).await;
                 let response = response_rx.recv().await;
@@ -163,6 +163,10 @@ fn main(mut cx: ModuleContext) -> NeonResult<()> {
     cx.export_function("backup", classes::account_manager::backup)?;
     cx.export_function("importAccounts", classes::account_manager::import_accounts)?;
     cx.export_function("setStoragePassword", classes::account_manager::set_storage_password)?;
+    cx.export_function(
+        "changeStoragePassword",
+        classes::account_manager::change_storage_password,
+    )?;
     cx.export_function(
         "changeStrongholdPassword",
         classes::account_manager::change_stronghold_password,
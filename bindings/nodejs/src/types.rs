@@ -176,6 +176,8 @@ pub struct ManagerOptions {
     pub skip_polling: bool,
     #[serde(rename = "pollingInterval")]
     pub polling_interval: Option<u64>,
+    #[serde(rename = "authTokens", default)]
+    pub auth_tokens: HashMap<String, iota_wallet::actor::TokenScope>,
 }
 
 fn default_automatic_output_consolidation() -> bool {
@@ -109,7 +109,7 @@ impl Transfer {
             builder = builder.with_skip_sync();
         }
         Ok(Transfer {
-            transfer: builder.finish(),
+            transfer: builder.finish()?,
         })
     }
 }
@@ -141,7 +141,7 @@ impl TransferWithOutputs {
             builder = builder.with_skip_sync();
         }
         Ok(TransferWithOutputs {
-            transfer: builder.finish(),
+            transfer: builder.finish()?,
         })
     }
 }
@@ -333,6 +333,10 @@ impl AccountHandle {
             Some("Unconfirmed") => Some(RustMessageType::Unconfirmed),
             Some("Value") => Some(RustMessageType::Value),
             Some("Confirmed") => Some(RustMessageType::Confirmed),
+            Some("SelfTransfer") => Some(RustMessageType::SelfTransfer),
+            Some("Consolidation") => Some(RustMessageType::Consolidation),
+            Some("Participation") => Some(RustMessageType::Participation),
+            Some("Data") => Some(RustMessageType::Data),
             _ => None,
         };
         crate::block_on(async {
@@ -363,6 +367,10 @@ impl AccountHandle {
             Some("Unconfirmed") => Some(RustMessageType::Unconfirmed),
             Some("Value") => Some(RustMessageType::Value),
             Some("Confirmed") => Some(RustMessageType::Confirmed),
+            Some("SelfTransfer") => Some(RustMessageType::SelfTransfer),
+            Some("Consolidation") => Some(RustMessageType::Consolidation),
+            Some("Participation") => Some(RustMessageType::Participation),
+            Some("Data") => Some(RustMessageType::Data),
             _ => None,
         };
         let messages = crate::block_on(async {
@@ -229,6 +229,18 @@ impl AccountManager {
         Ok(())
     }
 
+    /// Changes the password of the encrypted storage, re-encrypting the stored accounts and messages.
+    fn change_storage_password(&mut self, current_password: &str, new_password: &str) -> Result<()> {
+        crate::block_on(async {
+            self.account_manager
+                .as_ref()
+                .expect("account_manager got destroyed")
+                .change_storage_password(current_password, new_password)
+                .await
+        })?;
+        Ok(())
+    }
+
     /// Sets the stronghold password.
     fn set_stronghold_password(&mut self, password: &str) -> Result<()> {
         crate::block_on(async {
@@ -462,7 +474,7 @@ impl AccountManager {
                 .await
                 .map_err(iota_wallet::Error::StrongholdError)?;
 
-            iota_wallet::remove_storage(manager.storage_path())
+            iota_wallet::remove_storage(&manager.storage_path())
                 .await
                 .expect("couldn't remove storage instance");
 
@@ -51,6 +51,7 @@ impl From<Node> for RustNode {
             url: Url::parse(&node.url).expect("invalid url"),
             auth: node.auth.map(|a| a.into()),
             disabled: node.disabled,
+            proxy: None,
         }
     }
 }
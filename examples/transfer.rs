@@ -51,7 +51,7 @@ async fn main() -> iota_wallet::Result<()> {
                 NonZeroU64::new(10000000).unwrap(),
                 Some(OutputKind::SignatureLockedDustAllowance),
             )
-            .finish(),
+            .finish()?,
         )
         .await?;
     println!("Message sent: {}", message.id());
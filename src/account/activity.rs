@@ -0,0 +1,215 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::Account;
+use crate::{
+    address::AddressWrapper,
+    message::{MessagePayload, TransactionEssence, TransactionInput, TransactionOutput},
+};
+
+use chrono::prelude::{DateTime, Utc};
+use getset::Getters;
+use serde::{Deserialize, Serialize};
+
+use std::collections::{HashMap, HashSet};
+
+/// Maximum number of counterparties listed on the activity summary.
+const MAX_ACTIVITY_COUNTERPARTIES: usize = 10;
+
+/// The period covered by an activity summary. Unset bounds are open.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ActivityPeriod {
+    /// The period start.
+    #[serde(default)]
+    from: Option<DateTime<Utc>>,
+    /// The period end.
+    #[serde(default)]
+    to: Option<DateTime<Utc>>,
+}
+
+impl ActivityPeriod {
+    /// Creates a period between the given bounds.
+    pub fn new(from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> Self {
+        Self { from, to }
+    }
+
+    /// Creates a period covering the given duration until now.
+    pub fn last(duration: chrono::Duration) -> Self {
+        Self {
+            from: Some(Utc::now() - duration),
+            to: None,
+        }
+    }
+
+    fn contains(&self, timestamp: &DateTime<Utc>) -> bool {
+        self.from.map(|from| timestamp >= &from).unwrap_or(true) && self.to.map(|to| timestamp <= &to).unwrap_or(true)
+    }
+
+    /// Whether the UTC day overlaps the period.
+    pub(crate) fn contains_date(&self, date: chrono::naive::NaiveDate) -> bool {
+        self.from.map(|from| date >= from.naive_utc().date()).unwrap_or(true)
+            && self.to.map(|to| date <= to.naive_utc().date()).unwrap_or(true)
+    }
+}
+
+/// An address the account exchanged funds with.
+#[derive(Debug, Clone, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct Counterparty {
+    /// The counterparty address.
+    #[serde(with = "crate::serde::iota_address_serde")]
+    address: AddressWrapper,
+    /// Number of transfers sent to or received from the address.
+    transfers: usize,
+}
+
+/// The result of [AccountHandle#activity_summary](struct.AccountHandle.html#method.activity_summary).
+#[derive(Debug, Clone, Default, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct ActivitySummary {
+    /// Total value received.
+    received: u64,
+    /// Total value sent.
+    sent: u64,
+    /// Number of incoming transfers.
+    #[serde(rename = "incomingTransfers")]
+    incoming_transfers: usize,
+    /// Number of outgoing transfers.
+    #[serde(rename = "outgoingTransfers")]
+    outgoing_transfers: usize,
+    /// Average seconds between the broadcast and the confirmation of the outgoing transfers.
+    /// Only available if the account persists its events.
+    #[serde(rename = "averageConfirmationLatency")]
+    average_confirmation_latency: Option<u64>,
+    /// The most active counterparties, ordered by number of transfers.
+    counterparties: Vec<Counterparty>,
+}
+
+impl Account {
+    /// Computes the activity summary of the confirmed transfers in the given period from the stored messages.
+    pub(crate) async fn activity_summary(&self, period: ActivityPeriod) -> crate::Result<ActivitySummary> {
+        let account_addresses: HashSet<&AddressWrapper> = self.addresses().iter().map(|a| a.address()).collect();
+        let mut summary = ActivitySummary::default();
+        let mut counterparties: HashMap<AddressWrapper, usize> = HashMap::new();
+        let mut message_ids = HashSet::new();
+
+        for message in self.list_messages(0, 0, None).await? {
+            if message.confirmed != Some(true) || !period.contains(&message.timestamp) {
+                continue;
+            }
+            let essence = match &message.payload {
+                Some(MessagePayload::Transaction(tx)) => match tx.essence() {
+                    TransactionEssence::Regular(essence) => essence,
+                },
+                _ => continue,
+            };
+            message_ids.insert(message.id);
+
+            let mut message_counterparties = HashSet::new();
+            if essence.incoming() {
+                summary.received += essence.value();
+                summary.incoming_transfers += 1;
+                for input in essence.inputs() {
+                    if let TransactionInput::Utxo(utxo) = input {
+                        if let Some(metadata) = &utxo.metadata {
+                            if !account_addresses.contains(&metadata.address) {
+                                message_counterparties.insert(metadata.address.clone());
+                            }
+                        }
+                    }
+                }
+            } else {
+                summary.sent += essence.value();
+                summary.outgoing_transfers += 1;
+                for output in essence.outputs() {
+                    let address = match output {
+                        TransactionOutput::SignatureLockedSingle(output) if !output.remainder() => output.address(),
+                        TransactionOutput::SignatureLockedDustAllowance(output) => output.address(),
+                        _ => continue,
+                    };
+                    if !account_addresses.contains(address) {
+                        message_counterparties.insert(address.clone());
+                    }
+                }
+            }
+            for address in message_counterparties {
+                *counterparties.entry(address).or_default() += 1;
+            }
+        }
+
+        let latencies: Vec<i64> = crate::storage::get(&self.storage_path)
+            .await?
+            .lock()
+            .await
+            .get_confirmation_latencies(self.id())
+            .await?
+            .into_iter()
+            .filter(|(message_id, _)| message_ids.contains(message_id))
+            .map(|(_, latency)| latency)
+            .collect();
+        if !latencies.is_empty() {
+            summary.average_confirmation_latency =
+                Some(latencies.iter().map(|latency| (*latency).max(0) as u64).sum::<u64>() / latencies.len() as u64);
+        }
+
+        let mut counterparties: Vec<Counterparty> = counterparties
+            .into_iter()
+            .map(|(address, transfers)| Counterparty { address, transfers })
+            .collect();
+        counterparties.sort_by(|a, b| b.transfers.cmp(&a.transfers));
+        counterparties.truncate(MAX_ACTIVITY_COUNTERPARTIES);
+        summary.counterparties = counterparties;
+
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ActivityPeriod;
+
+    #[tokio::test]
+    async fn activity_summary() {
+        let manager = crate::testing::get_account_manager().await;
+        let address = crate::testing::generate_random_address();
+        let received = crate::testing::GenerateMessageBuilder::default()
+            .address(address.clone())
+            .value(500)
+            .input_address(Some(crate::testing::generate_random_iota_address()))
+            .confirmed(Some(true))
+            .build()
+            .await;
+        let pending = crate::testing::GenerateMessageBuilder::default()
+            .address(address.clone())
+            .value(200)
+            .input_address(Some(crate::testing::generate_random_iota_address()))
+            .confirmed(None)
+            .build()
+            .await;
+        let account_handle = crate::testing::AccountCreator::new(&manager)
+            .addresses(vec![address])
+            .messages(vec![received, pending])
+            .create()
+            .await;
+
+        let summary = account_handle
+            .activity_summary(ActivityPeriod::default())
+            .await
+            .unwrap();
+        assert_eq!(*summary.received(), 500);
+        assert_eq!(*summary.sent(), 0);
+        assert_eq!(*summary.incoming_transfers(), 1);
+        assert_eq!(*summary.outgoing_transfers(), 0);
+        assert_eq!(summary.counterparties().len(), 1);
+        assert!(summary.average_confirmation_latency().is_none());
+
+        let summary = account_handle
+            .activity_summary(ActivityPeriod::new(
+                Some(chrono::Utc::now() + chrono::Duration::days(1)),
+                None,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(*summary.incoming_transfers(), 0);
+    }
+}
@@ -0,0 +1,167 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{Account, AccountHandle, ActivityPeriod};
+use crate::message::MessageType;
+
+use chrono::{
+    naive::NaiveDate,
+    prelude::{Datelike, Utc},
+};
+use getset::CopyGetters;
+use serde::{Deserialize, Serialize};
+
+/// The interval between the points of a balance history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BalanceGranularity {
+    /// A point per day.
+    Day,
+    /// A point per ISO week.
+    Week,
+    /// A point per month.
+    Month,
+}
+
+impl BalanceGranularity {
+    fn bucket(&self, date: NaiveDate) -> (i32, u32) {
+        match self {
+            Self::Day => (date.year(), date.ordinal()),
+            Self::Week => (date.iso_week().year(), date.iso_week().week()),
+            Self::Month => (date.year(), date.month()),
+        }
+    }
+}
+
+/// The account balance at the end of a day, as of its last sync on that day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, CopyGetters)]
+#[getset(get_copy = "pub")]
+pub struct BalanceSnapshot {
+    /// The UTC day of the snapshot.
+    date: NaiveDate,
+    /// The account total balance.
+    total: u64,
+    /// The account available balance.
+    available: u64,
+}
+
+/// Records the snapshot in the history sorted by date, replacing the snapshot of the same day.
+pub(crate) fn record_snapshot(history: &mut Vec<BalanceSnapshot>, snapshot: BalanceSnapshot) {
+    match history.binary_search_by_key(&snapshot.date, |s| s.date) {
+        Ok(index) => history[index] = snapshot,
+        Err(index) => history.insert(index, snapshot),
+    }
+}
+
+/// Keeps the last snapshot of each granularity interval in the period.
+/// Days without a snapshot are skipped: the balance didn't change on them unless the account wasn't synced.
+pub(crate) fn aggregate(
+    history: &[BalanceSnapshot],
+    granularity: BalanceGranularity,
+    period: &ActivityPeriod,
+) -> Vec<BalanceSnapshot> {
+    let mut points: Vec<BalanceSnapshot> = Vec::new();
+    for snapshot in history.iter().filter(|s| period.contains_date(s.date)) {
+        match points.last_mut() {
+            Some(last) if granularity.bucket(last.date) == granularity.bucket(snapshot.date) => *last = *snapshot,
+            _ => points.push(*snapshot),
+        }
+    }
+    points
+}
+
+impl Account {
+    /// Updates the balance snapshot of the current day.
+    /// The balance is only recomputed if the sync changed it or if the day has no snapshot yet.
+    pub(crate) async fn record_balance_snapshot(&self, balance_changed: bool) -> crate::Result<()> {
+        if self.skip_persistence {
+            return Ok(());
+        }
+        let date = Utc::now().naive_utc().date();
+        let storage = crate::storage::get(&self.storage_path).await?;
+        let mut history = storage.lock().await.get_balance_history(&self.id).await?;
+        if !balance_changed && history.last().map(|s| s.date) == Some(date) {
+            return Ok(());
+        }
+        let sent_messages = self.list_messages(0, 0, Some(MessageType::Sent)).await?;
+        let balance = self.balance_internal(&sent_messages).await;
+        let snapshot = BalanceSnapshot {
+            date,
+            total: balance.total,
+            available: balance.available,
+        };
+        if history.last() == Some(&snapshot) {
+            return Ok(());
+        }
+        record_snapshot(&mut history, snapshot);
+        storage.lock().await.save_balance_history(&self.id, &history).await
+    }
+
+    /// Gets the account balance over the period, a point per granularity interval with the balance at its end.
+    /// The history is recorded after each sync, so it starts at the first sync of the account.
+    pub async fn balance_history(
+        &self,
+        granularity: BalanceGranularity,
+        period: ActivityPeriod,
+    ) -> crate::Result<Vec<BalanceSnapshot>> {
+        let history = crate::storage::get(&self.storage_path)
+            .await?
+            .lock()
+            .await
+            .get_balance_history(&self.id)
+            .await?;
+        Ok(aggregate(&history, granularity, &period))
+    }
+}
+
+impl AccountHandle {
+    /// Bridge to [Account#balance_history](struct.Account.html#method.balance_history).
+    pub async fn balance_history(
+        &self,
+        granularity: BalanceGranularity,
+        period: ActivityPeriod,
+    ) -> crate::Result<Vec<BalanceSnapshot>> {
+        self.inner.read().await.balance_history(granularity, period).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{aggregate, record_snapshot, BalanceGranularity, BalanceSnapshot};
+    use crate::account::ActivityPeriod;
+
+    use chrono::{naive::NaiveDate, prelude::*};
+
+    fn snapshot(month: u32, day: u32, total: u64) -> BalanceSnapshot {
+        BalanceSnapshot {
+            date: NaiveDate::from_ymd(2021, month, day),
+            total,
+            available: total,
+        }
+    }
+
+    #[test]
+    fn balance_history() {
+        let mut history = Vec::new();
+        record_snapshot(&mut history, snapshot(1, 5, 10));
+        record_snapshot(&mut history, snapshot(1, 1, 5));
+        record_snapshot(&mut history, snapshot(2, 3, 20));
+        // a later sync on the same day replaces the snapshot
+        record_snapshot(&mut history, snapshot(1, 5, 15));
+        assert_eq!(history, vec![snapshot(1, 1, 5), snapshot(1, 5, 15), snapshot(2, 3, 20)]);
+
+        let all = ActivityPeriod::default();
+        assert_eq!(aggregate(&history, BalanceGranularity::Day, &all), history);
+        assert_eq!(
+            aggregate(&history, BalanceGranularity::Month, &all),
+            vec![snapshot(1, 5, 15), snapshot(2, 3, 20)]
+        );
+        // 2021-01-01 is in the last ISO week of 2020
+        assert_eq!(aggregate(&history, BalanceGranularity::Week, &all).len(), 3);
+
+        let february = ActivityPeriod::new(Some(Utc.ymd(2021, 2, 1).and_hms(0, 0, 0)), None);
+        assert_eq!(
+            aggregate(&history, BalanceGranularity::Day, &february),
+            vec![snapshot(2, 3, 20)]
+        );
+    }
+}
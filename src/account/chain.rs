@@ -0,0 +1,136 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{AccountHandle, SyncedAccount};
+use crate::{
+    address::{AddressOutput, OutputKind},
+    event::{ChainedTransferData, TransferProgressType},
+    message::{Message, MessagePayload, TransactionEssence, TransactionOutput, Transfer},
+};
+
+/// Gets the remainder output the message created on the account, known locally before the message confirms.
+fn remainder_output(message: &Message) -> crate::Result<Option<AddressOutput>> {
+    let tx = match message.payload() {
+        Some(MessagePayload::Transaction(tx)) => tx,
+        _ => return Ok(None),
+    };
+    let TransactionEssence::Regular(essence) = tx.essence();
+    for (index, output) in essence.outputs().iter().enumerate() {
+        if let TransactionOutput::SignatureLockedSingle(output) = output {
+            if output.remainder() {
+                return Ok(Some(AddressOutput {
+                    transaction_id: tx.to_transaction_payload()?.id(),
+                    message_id: *message.id(),
+                    index: index as u16,
+                    amount: output.amount(),
+                    is_spent: false,
+                    address: output.address().clone(),
+                    kind: OutputKind::SignatureLockedSingle,
+                }));
+            }
+        }
+    }
+    Ok(None)
+}
+
+impl AccountHandle {
+    /// Sends the transfers one after the other. Each transfer spends the remainder output of the previous one when
+    /// it covers the transfer amount, without waiting for the previous transfer to confirm; otherwise its inputs
+    /// are selected as usual. If a transfer fails the following ones are abandoned, and the error
+    /// [TransferChainAbandoned](../enum.Error.html#variant.TransferChainAbandoned) lists the messages already sent,
+    /// which can't be rolled back.
+    pub async fn send_chain(&self, transfers: Vec<Transfer>) -> crate::Result<Vec<Message>> {
+        self.check_frozen().await?;
+        let account_id = self.id().await;
+        let synced = if transfers.iter().all(|transfer_obj| transfer_obj.skip_sync) {
+            SyncedAccount::from(self.clone()).await
+        } else {
+            self.sync_internal().await.execute().await?
+        };
+
+        let total = transfers.len();
+        let mut sent: Vec<Message> = Vec::new();
+        for (index, mut transfer_obj) in transfers.into_iter().enumerate() {
+            transfer_obj
+                .emit_event_if_needed(
+                    account_id.clone(),
+                    TransferProgressType::ChainedTransfer(ChainedTransferData { index, total }),
+                )
+                .await;
+            let previous_remainder = match sent.last() {
+                Some(previous) if transfer_obj.input.is_none() => remainder_output(previous),
+                _ => Ok(None),
+            };
+            let res = match previous_remainder {
+                Ok(Some(output)) if output.amount >= transfer_obj.amount() => {
+                    transfer_obj.input.replace(vec![(output.address.clone(), vec![output])]);
+                    synced.transfer(transfer_obj).await
+                }
+                Ok(_) => synced.transfer(transfer_obj).await,
+                Err(e) => Err(e),
+            };
+            match res {
+                Ok(message) => sent.push(message),
+                Err(error) => {
+                    log::debug!("[TRANSFER] abandoning the chain at transfer {}: {}", index, error);
+                    return Err(crate::Error::TransferChainAbandoned {
+                        sent: sent.iter().map(|message| *message.id()).collect(),
+                        index,
+                        error: Box::new(error),
+                    });
+                }
+            }
+        }
+        Ok(sent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::remainder_output;
+    use crate::message::{Message, Transfer};
+    use std::num::NonZeroU64;
+
+    #[tokio::test]
+    async fn previous_remainder_output() {
+        let message = crate::testing::GenerateMessageBuilder::default()
+            .value(1_000_000)
+            .build()
+            .await;
+        assert!(remainder_output(&message).unwrap().is_none());
+
+        let mut value = serde_json::to_value(&message).unwrap();
+        value["payload"]["data"]["essence"]["data"]["outputs"][0]["data"]["remainder"] = serde_json::Value::Bool(true);
+        let message: Message = serde_json::from_value(value).unwrap();
+        let output = remainder_output(&message).unwrap().unwrap();
+        assert_eq!(output.message_id, *message.id());
+        assert_eq!(output.index, 0);
+        assert_eq!(output.amount, 1_000_000);
+        assert!(!output.is_spent);
+    }
+
+    #[tokio::test]
+    async fn abandoned_chain() {
+        let manager = crate::testing::get_account_manager().await;
+        let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
+        let transfer = || {
+            Transfer::builder(
+                crate::testing::generate_random_iota_address(),
+                NonZeroU64::new(1_000_000).unwrap(),
+                None,
+            )
+            .with_skip_sync()
+            .finish()
+            .unwrap()
+        };
+
+        // the account has no balance so the first transfer fails and the second one isn't sent
+        match account_handle.send_chain(vec![transfer(), transfer()]).await {
+            Err(crate::Error::TransferChainAbandoned { sent, index, .. }) => {
+                assert!(sent.is_empty());
+                assert_eq!(index, 0);
+            }
+            _ => panic!("the chain should be abandoned"),
+        }
+    }
+}
@@ -0,0 +1,278 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::AccountHandle;
+use crate::{address::AddressWrapper, message::Message, signing::SignMessageMetadata};
+
+use getset::Getters;
+use iota_client::{
+    bee_message::{
+        address::Address as BeeAddress,
+        prelude::{Essence, Input, Payload, TransactionPayload, UnlockBlocks},
+    },
+    common::packable::Packable,
+};
+use serde::{Deserialize, Serialize};
+
+use std::{fs, path::Path};
+
+/// An input of a [SigningRequest](struct.SigningRequest.html).
+#[derive(Debug, Clone, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct SigningRequestInput {
+    /// The packed input, hex encoded.
+    input: String,
+    /// The input address.
+    #[serde(with = "crate::serde::iota_address_serde")]
+    address: AddressWrapper,
+    /// The input address index.
+    #[serde(rename = "addressIndex")]
+    address_index: usize,
+    /// Whether the input address is a change address or not.
+    #[serde(rename = "addressInternal")]
+    address_internal: bool,
+}
+
+/// The artifacts needed to sign a transfer offline.
+/// Created on the online side by [AccountHandle#prepare_signing_request](struct.AccountHandle.html#method.prepare_signing_request)
+/// and signed on the offline side by [AccountHandle#sign_signing_request](struct.AccountHandle.html#method.sign_signing_request).
+#[derive(Debug, Clone, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct SigningRequest {
+    /// The account identifier.
+    #[serde(rename = "accountId")]
+    account_id: String,
+    /// The packed transaction essence, hex encoded.
+    essence: String,
+    /// The transaction inputs.
+    inputs: Vec<SigningRequestInput>,
+    /// The address holding the remainder value, if any.
+    #[serde(rename = "remainderAddress")]
+    remainder_address: Option<AddressWrapper>,
+    /// The remainder value.
+    #[serde(rename = "remainderValue")]
+    remainder_value: u64,
+    /// The address receiving the remainder value, if any.
+    #[serde(rename = "remainderDepositAddress")]
+    remainder_deposit_address: Option<AddressWrapper>,
+}
+
+impl SigningRequest {
+    pub(crate) fn new(
+        account_id: String,
+        essence: &Essence,
+        inputs: Vec<(crate::signing::TransactionInput, AddressWrapper)>,
+        remainder_address: Option<AddressWrapper>,
+        remainder_value: u64,
+        remainder_deposit_address: Option<AddressWrapper>,
+    ) -> Self {
+        Self {
+            account_id,
+            essence: hex::encode(essence.pack_new()),
+            inputs: inputs
+                .into_iter()
+                .map(|(input, address)| SigningRequestInput {
+                    input: hex::encode(input.input.pack_new()),
+                    address,
+                    address_index: input.address_index,
+                    address_internal: input.address_internal,
+                })
+                .collect(),
+            remainder_address,
+            remainder_value,
+            remainder_deposit_address,
+        }
+    }
+
+    /// Encodes the request as a hex string, suitable for QR codes.
+    pub fn to_qr_payload(&self) -> crate::Result<String> {
+        Ok(hex::encode(serde_json::to_vec(self)?))
+    }
+
+    /// Decodes a request encoded with [to_qr_payload](#method.to_qr_payload).
+    pub fn from_qr_payload(payload: &str) -> crate::Result<Self> {
+        let bytes = hex::decode(payload).map_err(|e| crate::Error::InvalidSigningRequest(e.to_string()))?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    /// Writes the request to the given file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> crate::Result<()> {
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Reads a request written with [save](#method.save).
+    pub fn load<P: AsRef<Path>>(path: P) -> crate::Result<Self> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    fn unpack_essence(&self) -> crate::Result<Essence> {
+        let bytes = hex::decode(&self.essence).map_err(|e| crate::Error::InvalidSigningRequest(e.to_string()))?;
+        Ok(Essence::unpack(&mut bytes.as_slice())?)
+    }
+
+    fn unpack_inputs(&self) -> crate::Result<Vec<(crate::signing::TransactionInput, BeeAddress)>> {
+        let mut inputs = Vec::new();
+        for input in &self.inputs {
+            let bytes = hex::decode(&input.input).map_err(|e| crate::Error::InvalidSigningRequest(e.to_string()))?;
+            inputs.push((
+                crate::signing::TransactionInput {
+                    input: Input::unpack(&mut bytes.as_slice())?,
+                    address_index: input.address_index,
+                    address_internal: input.address_internal,
+                },
+                *input.address.as_ref(),
+            ));
+        }
+        Ok(inputs)
+    }
+}
+
+impl AccountHandle {
+    /// Signs a request prepared by the cold storage counterpart of this account, returning the packed transaction
+    /// payload to broadcast with [broadcast_signed_payload](#method.broadcast_signed_payload).
+    pub async fn sign_signing_request(&self, request: &SigningRequest) -> crate::Result<Vec<u8>> {
+        let account = self.inner.read().await;
+        if account.id() != &request.account_id {
+            return Err(crate::Error::InvalidSigningRequest(
+                "the request belongs to another account".to_string(),
+            ));
+        }
+
+        let essence = request.unpack_essence()?;
+        let (mut inputs, addresses): (Vec<crate::signing::TransactionInput>, Vec<BeeAddress>) =
+            request.unpack_inputs()?.into_iter().unzip();
+        let inputs_for_validation: Vec<(Input, BeeAddress)> = inputs
+            .iter()
+            .map(|input| input.input.clone())
+            .zip(addresses.into_iter())
+            .collect();
+        let find_address = |address: &Option<AddressWrapper>| {
+            address
+                .as_ref()
+                .and_then(|address| account.addresses().iter().find(|a| a.address() == address))
+        };
+
+        let unlock_blocks = crate::signing::get_signer(account.signer_type())
+            .await
+            .lock()
+            .await
+            .sign_message(
+                &account,
+                &essence,
+                &mut inputs,
+                SignMessageMetadata {
+                    remainder_address: find_address(&request.remainder_address),
+                    remainder_value: request.remainder_value,
+                    remainder_deposit_address: find_address(&request.remainder_deposit_address),
+                    network: account.network(),
+                },
+            )
+            .await?;
+
+        let transaction = TransactionPayload::builder()
+            .with_essence(essence)
+            .with_unlock_blocks(UnlockBlocks::new(unlock_blocks)?)
+            .finish()?;
+        super::sync::verify_unlock_blocks(&transaction, inputs_for_validation)?;
+
+        Ok(transaction.pack_new())
+    }
+
+    /// Verifies and broadcasts a transaction payload signed offline,
+    /// storing the message the same way a regular transfer does.
+    /// Fails if the node rejects the message, in which case nothing is stored.
+    pub async fn broadcast_signed_payload(&self, bytes: &[u8]) -> crate::Result<Message> {
+        self.check_frozen().await?;
+        let transaction = TransactionPayload::unpack(&mut &bytes[..])?;
+
+        let (client, client_options, network_parameters) = {
+            let account = self.inner.read().await;
+            let Essence::Regular(essence) = transaction.essence();
+            let mut inputs = Vec::new();
+            for input in essence.inputs() {
+                let address = match input {
+                    Input::Utxo(utxo) => account
+                        .addresses()
+                        .iter()
+                        .find(|address| address.outputs().contains_key(utxo.output_id())),
+                    _ => None,
+                }
+                .ok_or_else(|| {
+                    crate::Error::InvalidSigningRequest("input doesn't belong to the account".to_string())
+                })?;
+                inputs.push((input.clone(), *address.address().as_ref()));
+            }
+            super::sync::verify_unlock_blocks(&transaction, inputs)?;
+            (
+                crate::client::get_client(account.client_options()).await?,
+                account.client_options().clone(),
+                account.client_options().network_parameters().clone(),
+            )
+        };
+
+        let message = crate::client::finish_pow(
+            client.clone(),
+            Some(Payload::Transaction(Box::new(transaction))),
+            &network_parameters,
+            self.accounts.pow_pool(),
+        )
+        .await?;
+        // unlike a regular transfer, the message isn't stored before it's posted so the broadcast can be retried
+        let message_id = crate::client::post_message(&client_options, &message).await?;
+
+        let mut account = self.inner.write().await;
+        let message = Message::from_iota_message(
+            message_id,
+            message,
+            self.accounts.clone(),
+            account.id(),
+            account.addresses(),
+            account.client_options(),
+        )
+        .finish()
+        .await?;
+        account.save_messages(vec![message.clone()]).await?;
+
+        Ok(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SigningRequest;
+
+    #[test]
+    fn qr_payload() {
+        let request = SigningRequest {
+            account_id: "wallet-account://id".to_string(),
+            essence: "00".to_string(),
+            inputs: Vec::new(),
+            remainder_address: None,
+            remainder_value: 0,
+            remainder_deposit_address: Some(crate::testing::generate_random_iota_address()),
+        };
+        let decoded = SigningRequest::from_qr_payload(&request.to_qr_payload().unwrap()).unwrap();
+        assert_eq!(decoded.account_id(), request.account_id());
+        assert_eq!(decoded.remainder_deposit_address(), request.remainder_deposit_address());
+        assert!(SigningRequest::from_qr_payload("not hex").is_err());
+    }
+
+    #[tokio::test]
+    async fn signing_request_of_regular_account() {
+        let manager = crate::testing::get_account_manager().await;
+        let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
+        let transfer = crate::message::Transfer::builder(
+            crate::testing::generate_random_iota_address(),
+            std::num::NonZeroU64::new(1_000_000).unwrap(),
+            None,
+        )
+        .with_skip_sync()
+        .finish()
+        .unwrap();
+        assert!(matches!(
+            account_handle.prepare_signing_request(transfer).await,
+            Err(crate::Error::NotColdStorageAccount)
+        ));
+    }
+}
@@ -0,0 +1,110 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::AccountHandle;
+use crate::address::{AddressWrapper, IotaAddress};
+
+use crypto::hashes::{blake2b::Blake2b256, Digest};
+use getset::{CopyGetters, Getters};
+use serde::{Serialize, Serializer};
+
+/// The BIP44 coin type of IOTA.
+const IOTA_COIN_TYPE: u32 = 4218;
+
+/// The data an auditor needs to check that an address was derived from the wallet seed.
+/// Ed25519 SLIP-10 only has hardened derivation, so there is no extended public key to derive the addresses from:
+/// each address discloses the public key of its path instead, and the address is the Blake2b-256 hash of that key.
+#[derive(Debug, Clone, Getters, CopyGetters, Serialize)]
+pub struct AddressDerivationProof {
+    /// The derived address.
+    #[getset(get = "pub")]
+    #[serde(serialize_with = "serialize_address")]
+    address: AddressWrapper,
+    /// The hardened derivation path, e.g. `m/44'/4218'/0'/0'/3'`.
+    #[getset(get = "pub")]
+    path: String,
+    /// The Ed25519 public key of the path.
+    #[getset(get_copy = "pub")]
+    #[serde(rename = "publicKey", serialize_with = "serialize_public_key")]
+    public_key: [u8; 32],
+}
+
+fn serialize_address<S: Serializer>(address: &AddressWrapper, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&address.to_bech32())
+}
+
+fn serialize_public_key<S: Serializer>(public_key: &[u8; 32], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&hex::encode(public_key))
+}
+
+pub(crate) fn derivation_path(account_index: usize, internal: bool, key_index: usize) -> String {
+    format!(
+        "m/44'/{}'/{}'/{}'/{}'",
+        IOTA_COIN_TYPE, account_index, internal as u8, key_index
+    )
+}
+
+impl AddressDerivationProof {
+    /// Checks that the address is the hash of the disclosed public key.
+    pub fn verify(&self) -> bool {
+        let IotaAddress::Ed25519(address) = self.address.as_ref();
+        Blake2b256::digest(&self.public_key).as_slice() == address.as_ref()
+    }
+}
+
+impl AccountHandle {
+    /// Exports the derivation path and public key of every account address, so an auditor can verify the addresses
+    /// belong to the wallet without any private key material leaving the signer.
+    /// Fails with [SignerOperationNotSupported](../enum.Error.html#variant.SignerOperationNotSupported) if the signer
+    /// can't export public keys.
+    pub async fn export_address_derivation_proofs(&self) -> crate::Result<Vec<AddressDerivationProof>> {
+        let account = self.inner.read().await;
+        let signer = crate::signing::get_signer(account.signer_type()).await;
+        let mut signer = signer.lock().await;
+
+        let mut proofs = Vec::with_capacity(account.addresses().len());
+        for address in account.addresses() {
+            let public_key = signer
+                .public_key(&account, *address.key_index(), *address.internal())
+                .await?;
+            let proof = AddressDerivationProof {
+                address: address.address().clone(),
+                path: derivation_path(*account.index(), *address.internal(), *address.key_index()),
+                public_key,
+            };
+            // an address not matching the seed means the stored account is corrupted, don't hand it to the auditor
+            if !proof.verify() {
+                return Err(crate::Error::InvalidAddress);
+            }
+            proofs.push(proof);
+        }
+        Ok(proofs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{derivation_path, AddressDerivationProof};
+    use crate::address::{AddressWrapper, IotaAddress};
+
+    use crypto::hashes::{blake2b::Blake2b256, Digest};
+    use iota_client::bee_message::address::Ed25519Address;
+
+    use std::convert::TryInto;
+
+    #[test]
+    fn derivation_proof() {
+        let public_key = rand::random::<[u8; 32]>();
+        let hash: [u8; 32] = Blake2b256::digest(&public_key).as_slice().try_into().unwrap();
+        let mut proof = AddressDerivationProof {
+            address: AddressWrapper::new(IotaAddress::Ed25519(Ed25519Address::new(hash)), "atoi".to_string()),
+            path: derivation_path(2, true, 7),
+            public_key,
+        };
+        assert_eq!(proof.path(), "m/44'/4218'/2'/1'/7'");
+        assert!(proof.verify());
+
+        proof.public_key[0] ^= 1;
+        assert!(!proof.verify());
+    }
+}
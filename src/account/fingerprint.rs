@@ -0,0 +1,101 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::AccountHandle;
+use crate::{
+    address::AddressWrapper,
+    signing::{GenerateAddressMetadata, SignerType},
+};
+
+/// Domain separator of the fingerprint, so it differs from the account id, which hashes the same address.
+const SEED_FINGERPRINT_DOMAIN: &[u8] = b"iota-wallet-seed-fingerprint";
+
+/// Whether the signer derives the addresses from a seed it holds, which a wrong mnemonic restore replaces.
+/// Ledger devices are validated on the device and custom signers are managed by the application.
+pub(super) fn is_seed_signer(signer_type: &SignerType) -> bool {
+    match signer_type {
+        #[cfg(feature = "stronghold")]
+        SignerType::Stronghold => true,
+        #[cfg(feature = "test-signing")]
+        SignerType::Mnemonic => true,
+        _ => false,
+    }
+}
+
+/// Hashes the first public address of an account, which only the seed the account was created with derives.
+pub(super) fn seed_fingerprint(address: &AddressWrapper) -> String {
+    let mut raw = SEED_FINGERPRINT_DOMAIN.to_vec();
+    match address.as_ref() {
+        iota_client::bee_message::address::Address::Ed25519(a) => raw.extend_from_slice(a.as_ref()),
+    }
+    let mut digest = [0; 32];
+    crypto::hashes::sha::SHA256(&raw, &mut digest);
+    hex::encode(digest)
+}
+
+impl AccountHandle {
+    /// Verifies that the signer derives the first address of the account, failing with
+    /// [SeedMismatch](../enum.Error.html#variant.SeedMismatch) if it holds another seed, e.g. after restoring the wrong
+    /// mnemonic. Only accounts whose first address was generated by a seed signer have a fingerprint; accounts
+    /// created with imported addresses or before fingerprints were persisted aren't verified.
+    pub(crate) async fn verify_seed_fingerprint(&self) -> crate::Result<()> {
+        let account = self.inner.read().await;
+        let fingerprint = match account.seed_fingerprint() {
+            Some(fingerprint) if !account.cold_storage() && is_seed_signer(account.signer_type()) => fingerprint,
+            _ => return Ok(()),
+        };
+
+        let address = crate::address::get_iota_address(
+            &account,
+            0,
+            false,
+            account.bech32_hrp(),
+            GenerateAddressMetadata {
+                syncing: true,
+                network: account.network(),
+            },
+        )
+        .await?;
+        if &seed_fingerprint(&address) != fingerprint {
+            return Err(crate::Error::SeedMismatch(account.id().clone()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "stronghold")]
+    #[tokio::test]
+    async fn seed_fingerprint() {
+        let manager = crate::testing::get_account_manager().await;
+        let account_handle = manager
+            .create_account(
+                crate::client::ClientOptionsBuilder::new()
+                    .with_node("https://api.lb-0.h.chrysalis-devnet.iota.cafe")
+                    .unwrap()
+                    .with_network("testnet")
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .signer_type(crate::signing::SignerType::Stronghold)
+            .alias("fingerprint")
+            .initialise()
+            .await
+            .unwrap();
+        assert!(account_handle.read().await.seed_fingerprint().is_some());
+        assert!(account_handle.verify_seed_fingerprint().await.is_ok());
+
+        // a fingerprint the signer doesn't reproduce
+        account_handle
+            .write()
+            .await
+            .seed_fingerprint
+            .replace(super::seed_fingerprint(&crate::testing::generate_random_iota_address()));
+        assert!(matches!(
+            account_handle.verify_seed_fingerprint().await,
+            Err(crate::Error::SeedMismatch(_))
+        ));
+    }
+}
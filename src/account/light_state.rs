@@ -0,0 +1,117 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{Account, AccountBalance};
+use crate::{
+    address::AddressWrapper,
+    message::{Message, MessagePayload, MessageType, TransactionEssence},
+};
+
+use getset::Getters;
+use serde::Serialize;
+
+/// Number of messages included on the light state.
+const LIGHT_STATE_MESSAGE_COUNT: usize = 50;
+
+/// An account address and its balance.
+#[derive(Debug, Clone, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct LightAddress {
+    /// The address.
+    #[serde(with = "crate::serde::iota_address_serde")]
+    address: AddressWrapper,
+    /// The address balance.
+    balance: u64,
+    /// The address key index.
+    #[serde(rename = "keyIndex")]
+    key_index: usize,
+    /// Whether the address is a change address or not.
+    internal: bool,
+}
+
+/// A compact snapshot of the account, holding everything a client needs to render it on startup.
+/// The result of [AccountHandle#light_state](struct.AccountHandle.html#method.light_state).
+#[derive(Debug, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct LightState {
+    /// The account identifier.
+    id: String,
+    /// The account index.
+    index: usize,
+    /// The account alias.
+    alias: String,
+    /// The account balance.
+    balance: AccountBalance,
+    /// The account addresses and their balances.
+    addresses: Vec<LightAddress>,
+    /// The latest messages, ordered by timestamp.
+    #[serde(rename = "latestMessages")]
+    latest_messages: Vec<Message>,
+    /// The outgoing transactions that aren't confirmed yet.
+    #[serde(rename = "pendingTransfers")]
+    pending_transfers: Vec<Message>,
+}
+
+impl Account {
+    /// Builds the light state from the account addresses and the stored messages.
+    pub(crate) async fn light_state(&self) -> crate::Result<LightState> {
+        let sent_messages = self.list_messages(0, 0, Some(MessageType::Sent)).await?;
+        let balance = self.balance_internal(&sent_messages).await;
+        let pending_transfers = self
+            .list_messages(0, 0, Some(MessageType::Unconfirmed))
+            .await?
+            .into_iter()
+            .filter(|message| match &message.payload {
+                Some(MessagePayload::Transaction(tx)) => match tx.essence() {
+                    TransactionEssence::Regular(essence) => !essence.incoming(),
+                },
+                _ => false,
+            })
+            .collect();
+
+        Ok(LightState {
+            id: self.id.clone(),
+            index: self.index,
+            alias: self.alias.clone(),
+            balance,
+            addresses: self
+                .addresses
+                .iter()
+                .map(|address| LightAddress {
+                    address: address.address().clone(),
+                    balance: address.balance(),
+                    key_index: *address.key_index(),
+                    internal: *address.internal(),
+                })
+                .collect(),
+            latest_messages: self.list_messages(LIGHT_STATE_MESSAGE_COUNT, 0, None).await?,
+            pending_transfers,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[tokio::test]
+    async fn light_state() {
+        let manager = crate::testing::get_account_manager().await;
+        let address = crate::testing::generate_random_address();
+        let received = crate::testing::GenerateMessageBuilder::default()
+            .address(address.clone())
+            .confirmed(Some(true))
+            .build()
+            .await;
+        let account_handle = crate::testing::AccountCreator::new(&manager)
+            .addresses(vec![address.clone()])
+            .messages(vec![received.clone()])
+            .create()
+            .await;
+
+        let state = account_handle.light_state().await.unwrap();
+        assert_eq!(state.id(), &account_handle.id().await);
+        assert_eq!(state.addresses().len(), 1);
+        assert_eq!(state.addresses()[0].address(), address.address());
+        assert_eq!(state.latest_messages(), &vec![received]);
+        assert!(state.pending_transfers().is_empty());
+    }
+}
@@ -2,12 +2,12 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    account_manager::{AccountOptions, AccountStore},
+    account_manager::{AccountOptions, AccountStore, ConsolidationSettings},
     address::{Address, AddressBuilder, AddressOutput, AddressWrapper},
     client::{ClientOptions, Node},
-    event::TransferProgressType,
+    event::{AccountEvent, TransferProgressType},
     message::{Message, MessageType, Transfer},
-    signing::{GenerateAddressMetadata, SignerType},
+    signing::{GenerateAddressMetadata, SignerCapabilities, SignerType},
     storage::{MessageIndexation, MessageQueryFilter},
 };
 #[cfg(any(feature = "ledger-nano", feature = "ledger-nano-simulator"))]
@@ -16,8 +16,9 @@ use crate::{client::ClientOptionsBuilder, event::emit_ledger_address_generation}
 use iota_client::NodeInfoWrapper;
 
 use chrono::prelude::{DateTime, Local};
+use futures::{Stream, StreamExt};
 use getset::{Getters, Setters};
-use iota_client::bee_message::prelude::MessageId;
+use iota_client::{bee_message::prelude::MessageId, common::packable::Packable};
 use serde::{Deserialize, Deserializer, Serialize};
 use tokio::sync::{Mutex, RwLock, RwLockWriteGuard};
 
@@ -28,15 +29,58 @@ use std::{
     path::PathBuf,
     sync::{
         atomic::{AtomicBool, Ordering},
-        Arc,
+        Arc, Weak,
     },
 };
 
+mod activity;
+mod balance_history;
+mod chain;
+mod cold_storage;
+mod derivation_proof;
+mod fingerprint;
+mod light_state;
+mod payment_watch;
+mod payout;
+mod pending_addresses;
+mod pre_derivation;
+mod reconcile;
+mod repair;
+mod retry_advice;
+mod snapshot;
+mod status;
 mod sync;
-pub(crate) use sync::{AccountSynchronizeStep, SyncedAccountData};
-pub use sync::{AccountSynchronizer, SyncedAccount};
+mod tags;
+mod validation;
+pub use activity::{ActivityPeriod, ActivitySummary, Counterparty};
+pub use balance_history::{BalanceGranularity, BalanceSnapshot};
+pub use cold_storage::{SigningRequest, SigningRequestInput};
+pub use derivation_proof::AddressDerivationProof;
+pub use light_state::{LightAddress, LightState};
+pub use payout::{PayoutReport, PayoutRow, PayoutStatus};
+pub use reconcile::{BalanceDiscrepancy, ReconciliationReport};
+pub use repair::{AddressRepairReport, RepairedAddress};
+pub use retry_advice::RetryAdvice;
+pub use snapshot::{SnapshotFormat, SnapshotImport};
+pub use status::TransactionStatus;
+pub(crate) use sync::MessageCache;
+#[cfg(feature = "bench")]
+pub(crate) use sync::{select_input, SelectionInput};
+pub(crate) use sync::{
+    verify_unlock_blocks, AccountSynchronizeStep, InFlightTransfers, KnownIds, LockedOutputs, SyncedAccountData,
+    DEFAULT_OUTPUT_LOCK_LEASE,
+};
+pub use sync::{
+    AccountSynchronizer, ConsolidationOptions, ConsolidationTarget, DustAnalysis, OutputChange, OutputChangeKind,
+    OutputDiagnostic, ReservationId, SendCheck, SyncPlanReport, SyncRetryOptions, SyncedAccount, TransactionEstimate,
+    TransferDiagnostics,
+};
+pub use tags::AccountTags;
+pub use validation::{ValidationIssue, ValidationIssueKind, ValidationReport};
 
-const ACCOUNT_ID_PREFIX: &str = "wallet-account://";
+pub(crate) const ACCOUNT_ID_PREFIX: &str = "wallet-account://";
+/// How many messages the [message stream](struct.Account.html#method.message_stream) reads from the storage at once.
+const MESSAGE_STREAM_PAGE_SIZE: usize = 100;
 
 /// The account identifier.
 #[derive(Debug, Clone, Serialize, Eq)]
@@ -58,8 +102,29 @@ impl<'de> Deserialize<'de> for AccountIdentifier {
     where
         D: Deserializer<'de>,
     {
-        let s = String::deserialize(deserializer)?;
-        Ok(AccountIdentifier::from(s))
+        // the message interface addresses accounts by index (a number) or by id, alias or address (a string)
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum AccountIdentifierDto {
+            Index(usize),
+            String(String),
+        }
+
+        match AccountIdentifierDto::deserialize(deserializer)? {
+            AccountIdentifierDto::Index(index) => Ok(AccountIdentifier::Index(index)),
+            AccountIdentifierDto::String(s) => Ok(AccountIdentifier::from(s)),
+        }
+    }
+}
+
+impl std::fmt::Display for AccountIdentifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Address(address) => write!(f, "address {}", address.to_bech32()),
+            Self::Id(id) => write!(f, "id {}", id),
+            Self::Alias(alias) => write!(f, "alias \"{}\"", alias),
+            Self::Index(index) => write!(f, "index {}", index),
+        }
     }
 }
 
@@ -134,6 +199,11 @@ pub struct AccountInitialiser {
     skip_persistence: bool,
     index: Option<usize>,
     allow_create_multiple_empty_accounts: bool,
+    cold_storage: bool,
+    rehearsal_of: Option<String>,
+    alias_scheme: Option<String>,
+    gap_limit_hint: Option<usize>,
+    consolidation: Option<ConsolidationSettings>,
 }
 
 impl AccountInitialiser {
@@ -162,6 +232,11 @@ impl AccountInitialiser {
             skip_persistence: false,
             index: None,
             allow_create_multiple_empty_accounts: false,
+            cold_storage: false,
+            rehearsal_of: None,
+            alias_scheme: None,
+            gap_limit_hint: None,
+            consolidation: None,
         }
     }
 
@@ -216,25 +291,84 @@ impl AccountInitialiser {
         self
     }
 
+    /// Creates a cold storage account: its transfers stop after the essence construction and must be signed offline.
+    /// See [AccountHandle#prepare_signing_request](struct.AccountHandle.html#method.prepare_signing_request).
+    pub fn cold_storage(mut self) -> Self {
+        self.cold_storage = true;
+        self
+    }
+
+    /// Defines the alias from a scheme, where `{index}` is replaced with the account index.
+    /// An alias set with [alias](#method.alias) takes precedence.
+    pub(crate) fn alias_scheme(mut self, alias_scheme: String) -> Self {
+        self.alias_scheme.replace(alias_scheme);
+        self
+    }
+
+    /// Sets the initial gap limit of the account syncs.
+    pub(crate) fn gap_limit_hint(mut self, gap_limit: usize) -> Self {
+        self.gap_limit_hint.replace(gap_limit);
+        self
+    }
+
+    /// Overrides the account manager output consolidation settings for the account.
+    pub(crate) fn consolidation(mut self, consolidation: ConsolidationSettings) -> Self {
+        self.consolidation.replace(consolidation);
+        self
+    }
+
+    /// Marks the account as a rehearsal copy of the given account, living on another network.
+    pub(crate) fn rehearsal_of(mut self, account_id: String) -> Self {
+        self.rehearsal_of.replace(account_id);
+        self
+    }
+
     /// Initialises the account.
     pub async fn initialise(mut self) -> crate::Result<AccountHandle> {
         let signer_type = self.signer_type.ok_or(crate::Error::AccountInitialiseRequiredField(
             crate::error::AccountInitialiseRequiredField::SignerType,
         ))?;
 
+        // the accounts evicted with `close_account` are only in the storage,
+        // but their indexes and aliases are still taken
+        let evicted_accounts = if self.skip_persistence {
+            Vec::new()
+        } else {
+            let mut loaded_ids = Vec::new();
+            for account_handle in self.accounts.read().await.values() {
+                loaded_ids.push(account_handle.read().await.id().clone());
+            }
+            crate::storage::get(&self.storage_path)
+                .await?
+                .lock()
+                .await
+                .get_accounts_except(&loaded_ids)
+                .await?
+        };
+
         let index = if let Some(index) = self.index {
             index
         } else {
             let mut account_index = 0;
             for account in self.accounts.read().await.values() {
-                if account.read().await.signer_type() == &signer_type {
+                let account = account.read().await;
+                // the rehearsal copies share the index of their original account
+                if account.signer_type() == &signer_type && account.rehearsal_of.is_none() {
                     account_index += 1;
                 }
             }
             account_index
+                + evicted_accounts
+                    .iter()
+                    .filter(|account| account.signer_type() == &signer_type && account.rehearsal_of.is_none())
+                    .count()
         };
 
-        let alias = self.alias.unwrap_or_else(|| format!("Account {}", index + 1));
+        let alias = match (self.alias, &self.alias_scheme) {
+            (Some(alias), _) => alias,
+            (None, Some(alias_scheme)) => crate::account_manager::interpolate_alias(alias_scheme, index),
+            (None, None) => format!("Account {}", index + 1),
+        };
         let created_at = self.created_at.unwrap_or_else(Local::now);
 
         let mut latest_account_handle: Option<AccountHandle> = None;
@@ -244,14 +378,35 @@ impl AccountInitialiser {
             if account.alias() == &alias {
                 return Err(crate::Error::AccountAliasAlreadyExists);
             }
-            if *account.index() >= latest_account_index {
+            if account.rehearsal_of.is_none() && *account.index() >= latest_account_index {
                 latest_account_index = *account.index();
                 latest_account_handle.replace(account_handle.clone());
             }
         }
+        let mut latest_evicted_account: Option<&Account> = None;
+        for account in &evicted_accounts {
+            if account.alias() == &alias {
+                return Err(crate::Error::AccountAliasAlreadyExists);
+            }
+            if account.rehearsal_of.is_none()
+                && (*account.index() > latest_account_index
+                    || (latest_account_handle.is_none() && latest_evicted_account.is_none()))
+            {
+                latest_account_index = *account.index();
+                latest_evicted_account.replace(account);
+            }
+        }
         if !self.account_options.allow_create_multiple_empty_accounts && !self.allow_create_multiple_empty_accounts {
-            if let Some(ref latest_account_handle) = latest_account_handle {
-                let latest_account = latest_account_handle.read().await;
+            let latest_account_guard;
+            let latest_account = match (latest_evicted_account, &latest_account_handle) {
+                (Some(account), _) => Some(account),
+                (None, Some(latest_account_handle)) => {
+                    latest_account_guard = latest_account_handle.read().await;
+                    Some(&*latest_account_guard)
+                }
+                (None, None) => None,
+            };
+            if let Some(latest_account) = latest_account {
                 if latest_account.with_messages(|messages| messages.is_empty()).await
                     && latest_account.addresses().iter().all(|a| a.outputs.is_empty())
                 {
@@ -273,14 +428,20 @@ impl AccountInitialiser {
             client_options: self.client_options,
             storage_path: self.storage_path.clone(),
             skip_persistence: self.skip_persistence,
+            cold_storage: self.cold_storage,
+            frozen: false,
+            rehearsal_of: self.rehearsal_of,
+            seed_fingerprint: None,
+            tags: Default::default(),
+            gap_limit_hint: self.gap_limit_hint,
+            consolidation: self.consolidation,
+            pre_derived_addresses: Default::default(),
             cached_messages: Default::default(),
         };
 
-        let bech32_hrp = match account.client_options.network().as_deref() {
-            Some("testnet") => "atoi".to_string(),
-            Some("mainnet") => "iota".to_string(),
-            Some("chrysalis-mainnet") => "iota".to_string(),
-            _ => {
+        let bech32_hrp = match crate::client::known_bech32_hrp(&account.client_options) {
+            Some(bech32_hrp) => bech32_hrp,
+            None => {
                 let client_options = account.client_options.clone();
                 let get_from_client_task = async {
                     let hrp = crate::client::get_client(&client_options)
@@ -350,6 +511,11 @@ impl AccountInitialiser {
                         .build()
                         .unwrap(), // safe to unwrap since we provide all required fields
                 );
+                if fingerprint::is_seed_signer(&signer_type) {
+                    account
+                        .seed_fingerprint
+                        .replace(fingerprint::seed_fingerprint(&address));
+                }
                 address
             }
         };
@@ -400,6 +566,14 @@ impl AccountInitialiser {
                                 client_options: ClientOptionsBuilder::new().build()?,
                                 storage_path: PathBuf::new(),
                                 skip_persistence: true,
+                                cold_storage: false,
+                                frozen: false,
+                                rehearsal_of: None,
+                                seed_fingerprint: None,
+                                tags: Default::default(),
+                                gap_limit_hint: None,
+                                consolidation: None,
+                                pre_derived_addresses: Default::default(),
                                 cached_messages: Arc::new(Mutex::new(HashMap::new())),
                             },
                             0,
@@ -445,6 +619,14 @@ impl AccountInitialiser {
                                 client_options: ClientOptionsBuilder::new().build()?,
                                 storage_path: PathBuf::new(),
                                 skip_persistence: true,
+                                cold_storage: false,
+                                frozen: false,
+                                rehearsal_of: None,
+                                seed_fingerprint: None,
+                                tags: Default::default(),
+                                gap_limit_hint: None,
+                                consolidation: None,
+                                pre_derived_addresses: Default::default(),
                                 cached_messages: Arc::new(Mutex::new(HashMap::new())),
                             },
                             0,
@@ -473,9 +655,13 @@ impl AccountInitialiser {
             }
         }
         let mut digest = [0; 32];
-        let raw = match address.as_ref() {
+        let mut raw = match address.as_ref() {
             iota_client::bee_message::address::Address::Ed25519(a) => a.as_ref().to_vec(),
         };
+        // a rehearsal account shares its addresses with the original account, so the network scopes its id
+        if account.rehearsal_of.is_some() {
+            raw.extend_from_slice(address.bech32_hrp().as_bytes());
+        }
         crypto::hashes::sha::SHA256(&raw, &mut digest);
         account.set_id(format!("{}{}", ACCOUNT_ID_PREFIX, hex::encode(digest)));
 
@@ -492,6 +678,7 @@ impl AccountInitialiser {
                 account.save_messages(self.messages).await?;
             }
             let account_id = account.id().clone();
+            self.accounts.index_addresses(&account);
             let guard = AccountHandle::new(
                 account,
                 self.accounts.clone(),
@@ -500,6 +687,7 @@ impl AccountInitialiser {
             );
             self.accounts.write().await.insert(account_id, guard.clone());
             // monitor on a non-async function to prevent cycle computing the `monitor_address_balance` fn type
+            guard.set_monitored();
             monitor_address(guard.clone());
             guard
         };
@@ -548,6 +736,31 @@ pub struct Account {
     #[getset(set = "pub(crate)", get = "pub(crate)")]
     #[serde(skip)]
     skip_persistence: bool,
+    /// Whether the account is a cold storage account, which can't sign its transfers.
+    #[serde(rename = "coldStorage", default)]
+    cold_storage: bool,
+    /// Whether the account is frozen, which blocks its transfers.
+    #[serde(default)]
+    frozen: bool,
+    /// The identifier of the account this account rehearses on a test network, if any.
+    #[serde(rename = "rehearsalOf", default)]
+    rehearsal_of: Option<String>,
+    /// The fingerprint of the seed the account addresses are derived from, if the account generated them.
+    #[serde(rename = "seedFingerprint", default)]
+    seed_fingerprint: Option<String>,
+    /// The local accounting tags of the account outputs and transfers.
+    #[serde(default)]
+    tags: AccountTags,
+    /// The gap limit of the full syncs, raised when used addresses are found far apart.
+    #[serde(rename = "gapLimitHint", default)]
+    gap_limit_hint: Option<usize>,
+    /// The output consolidation settings of the account, overriding the account manager ones.
+    #[serde(default)]
+    consolidation: Option<ConsolidationSettings>,
+    /// The addresses derived ahead of their use by the background pre-derivation.
+    #[getset(get = "pub(crate)")]
+    #[serde(rename = "preDerivedAddresses", default, skip_serializing_if = "Vec::is_empty")]
+    pre_derived_addresses: Vec<pre_derivation::PreDerivedAddress>,
     #[getset(get = "pub(crate)")]
     #[serde(skip)]
     pub(crate) cached_messages: Arc<Mutex<HashMap<MessageId, Message>>>,
@@ -564,11 +777,62 @@ impl PartialEq for Account {
 pub struct AccountHandle {
     inner: Arc<RwLock<Account>>,
     pub(crate) accounts: AccountStore,
-    pub(crate) locked_outputs: Arc<Mutex<Vec<AddressOutput>>>,
+    pub(crate) locked_outputs: Arc<Mutex<LockedOutputs>>,
     pub(crate) account_options: AccountOptions,
     is_mqtt_enabled: Arc<AtomicBool>,
     pub(crate) change_addresses_to_sync: Arc<Mutex<HashSet<AddressWrapper>>>,
+    /// The addresses left unsynced by the last sync because it ran out of its request budget.
+    pub(crate) deferred_sync_addresses: Arc<Mutex<HashSet<AddressWrapper>>>,
     pub(crate) sync_accounts_lock: Arc<Mutex<()>>,
+    payment_watches: Arc<Mutex<Vec<payment_watch::PaymentWatch>>>,
+    /// The ids sync doesn't need to request again, loaded on the first sync.
+    pub(crate) known_ids: Arc<Mutex<Option<Arc<KnownIds>>>>,
+    /// The address derivations skipped by the syncs while Stronghold was locked.
+    pub(crate) pending_address_derivations: Arc<Mutex<pending_addresses::PendingAddressDerivations>>,
+    /// Whether the account addresses are monitored with MQTT while the background sync runs.
+    is_monitored: Arc<AtomicBool>,
+    /// The diagnostics of the last transfer that failed on input selection or dust protection.
+    pub(crate) last_transfer_diagnostics: Arc<Mutex<Option<TransferDiagnostics>>>,
+}
+
+/// A weak reference to an account, which doesn't keep the account in memory.
+/// Created with [AccountHandle#downgrade](struct.AccountHandle.html#method.downgrade).
+#[derive(Debug, Clone)]
+pub struct WeakAccountHandle {
+    inner: Weak<RwLock<Account>>,
+    accounts: AccountStore,
+    locked_outputs: Arc<Mutex<LockedOutputs>>,
+    account_options: AccountOptions,
+    is_mqtt_enabled: Arc<AtomicBool>,
+    change_addresses_to_sync: Arc<Mutex<HashSet<AddressWrapper>>>,
+    deferred_sync_addresses: Arc<Mutex<HashSet<AddressWrapper>>>,
+    sync_accounts_lock: Arc<Mutex<()>>,
+    payment_watches: Arc<Mutex<Vec<payment_watch::PaymentWatch>>>,
+    known_ids: Arc<Mutex<Option<Arc<KnownIds>>>>,
+    pending_address_derivations: Arc<Mutex<pending_addresses::PendingAddressDerivations>>,
+    is_monitored: Arc<AtomicBool>,
+    last_transfer_diagnostics: Arc<Mutex<Option<TransferDiagnostics>>>,
+}
+
+impl WeakAccountHandle {
+    /// Gets a handle to the account, or `None` if it was evicted from memory and all its handles were dropped.
+    pub fn upgrade(&self) -> Option<AccountHandle> {
+        Some(AccountHandle {
+            inner: self.inner.upgrade()?,
+            accounts: self.accounts.clone(),
+            locked_outputs: self.locked_outputs.clone(),
+            account_options: self.account_options,
+            is_mqtt_enabled: self.is_mqtt_enabled.clone(),
+            change_addresses_to_sync: self.change_addresses_to_sync.clone(),
+            deferred_sync_addresses: self.deferred_sync_addresses.clone(),
+            sync_accounts_lock: self.sync_accounts_lock.clone(),
+            payment_watches: self.payment_watches.clone(),
+            known_ids: self.known_ids.clone(),
+            pending_address_derivations: self.pending_address_derivations.clone(),
+            is_monitored: self.is_monitored.clone(),
+            last_transfer_diagnostics: self.last_transfer_diagnostics.clone(),
+        })
+    }
 }
 
 impl AccountHandle {
@@ -578,6 +842,10 @@ impl AccountHandle {
         account_options: AccountOptions,
         sync_accounts_lock: Arc<Mutex<()>>,
     ) -> Self {
+        let account_options = match account.consolidation {
+            Some(consolidation) => consolidation.apply(account_options),
+            None => account_options,
+        };
         Self {
             inner: Arc::new(RwLock::new(account)),
             accounts,
@@ -585,10 +853,44 @@ impl AccountHandle {
             account_options,
             is_mqtt_enabled: Arc::new(AtomicBool::new(true)),
             change_addresses_to_sync: Default::default(),
+            deferred_sync_addresses: Default::default(),
             sync_accounts_lock,
+            payment_watches: Default::default(),
+            known_ids: Default::default(),
+            pending_address_derivations: Default::default(),
+            is_monitored: Default::default(),
+            last_transfer_diagnostics: Default::default(),
         }
     }
 
+    /// Creates a weak reference to the account, which doesn't prevent
+    /// [AccountManager#close_account](../account_manager/struct.AccountManager.html#method.close_account)
+    /// from releasing its memory.
+    pub fn downgrade(&self) -> WeakAccountHandle {
+        WeakAccountHandle {
+            inner: Arc::downgrade(&self.inner),
+            accounts: self.accounts.clone(),
+            locked_outputs: self.locked_outputs.clone(),
+            account_options: self.account_options,
+            is_mqtt_enabled: self.is_mqtt_enabled.clone(),
+            change_addresses_to_sync: self.change_addresses_to_sync.clone(),
+            deferred_sync_addresses: self.deferred_sync_addresses.clone(),
+            sync_accounts_lock: self.sync_accounts_lock.clone(),
+            payment_watches: self.payment_watches.clone(),
+            known_ids: self.known_ids.clone(),
+            pending_address_derivations: self.pending_address_derivations.clone(),
+            is_monitored: self.is_monitored.clone(),
+            last_transfer_diagnostics: self.last_transfer_diagnostics.clone(),
+        }
+    }
+
+    /// Gets the diagnostics of the last transfer that failed with an insufficient funds or dust error:
+    /// the balance breakdown, the available, locked and pending outputs and the dust protection state.
+    /// Cleared when a new transfer starts.
+    pub async fn last_transfer_diagnostics(&self) -> Option<TransferDiagnostics> {
+        self.last_transfer_diagnostics.lock().await.clone()
+    }
+
     /// Returns the addresses that need output consolidation.
     #[cfg(any(feature = "ledger-nano", feature = "ledger-nano-simulator"))]
     pub(crate) async fn output_consolidation_addresses(&self) -> crate::Result<Vec<AddressWrapper>> {
@@ -614,6 +916,15 @@ impl AccountHandle {
     pub(crate) fn enable_mqtt(&self) {
         self.is_mqtt_enabled.store(true, Ordering::Relaxed);
     }
+
+    pub(crate) fn is_monitored(&self) -> bool {
+        self.is_monitored.load(Ordering::SeqCst)
+    }
+
+    /// Flags the account addresses as monitored, returning whether they already were.
+    pub(crate) fn set_monitored(&self) -> bool {
+        self.is_monitored.swap(true, Ordering::SeqCst)
+    }
 }
 
 impl Deref for AccountHandle {
@@ -647,7 +958,9 @@ guard_field_getters!(
     #[doc = "Bridge to [Account#addresses](struct.Account.html#method.addresses).
     This method clones the addresses so prefer the using the `read` method to access the account instance."] => addresses => Vec<Address>,
     #[doc = "Bridge to [Account#client_options](struct.Account.html#method.client_options)."] => client_options => ClientOptions,
-    #[doc = "Bridge to [Account#bech32_hrp](struct.Account.html#method.bech32_hrp)."] => bech32_hrp => String
+    #[doc = "Bridge to [Account#bech32_hrp](struct.Account.html#method.bech32_hrp)."] => bech32_hrp => String,
+    #[doc = "Bridge to [Account#frozen](struct.Account.html#method.frozen)."] => frozen => bool,
+    #[doc = "Bridge to [Account#rehearsal_of](struct.Account.html#method.rehearsal_of)."] => rehearsal_of => Option<String>
 );
 
 impl AccountHandle {
@@ -660,18 +973,29 @@ impl AccountHandle {
         AccountSynchronizer::new(self.clone()).await.skip_change_addresses()
     }
 
-    /// Consolidate account outputs.
+    /// Consolidate account outputs, using the consolidation options configured on the account manager.
     pub async fn consolidate_outputs(&self, include_dust_allowance_outputs: bool) -> crate::Result<Vec<Message>> {
+        self.consolidate_outputs_with_options(ConsolidationOptions::from_account_options(
+            &self.account_options,
+            include_dust_allowance_outputs,
+        ))
+        .await
+    }
+
+    /// Consolidate account outputs with the given options.
+    pub async fn consolidate_outputs_with_options(&self, options: ConsolidationOptions) -> crate::Result<Vec<Message>> {
+        self.check_frozen().await?;
         self.sync_internal()
             .await
             .execute()
             .await?
-            .consolidate_outputs(include_dust_allowance_outputs)
+            .consolidate_outputs(options)
             .await
     }
 
     /// Send messages.
     pub async fn transfer(&self, transfer_obj: Transfer) -> crate::Result<Message> {
+        self.check_frozen().await?;
         let account_id = self.id().await;
         let synced = if transfer_obj.skip_sync {
             SyncedAccount::from(self.clone()).await
@@ -684,6 +1008,70 @@ impl AccountHandle {
         synced.transfer(transfer_obj).await
     }
 
+    /// Estimates the size and dust implications of a transfer before signing it,
+    /// selecting the inputs from the local account state without syncing or reserving them.
+    pub async fn estimate_transaction(&self, transfer_obj: &Transfer) -> crate::Result<TransactionEstimate> {
+        SyncedAccount::from(self.clone())
+            .await
+            .estimate_transaction(transfer_obj)
+            .await
+    }
+
+    /// Gets the limits and features of the account signer, e.g. to limit the outputs of a transfer form on a Ledger.
+    pub async fn signer_capabilities(&self) -> SignerCapabilities {
+        let signer_type = self.inner.read().await.signer_type().clone();
+        crate::signing::signer_capabilities(&signer_type).await
+    }
+
+    /// Checks whether a transfer of `amount` split in `output_count` outputs can be sent, answering from the local
+    /// account state without syncing, e.g. to enable the send button of a UI while the user types the amount.
+    /// The outputs reserved by transfers in progress, the dust protection and the input limits of the signer are
+    /// considered; the dust allowance of the recipients isn't, since it needs a node request.
+    pub async fn can_send(&self, amount: u64, output_count: usize) -> crate::Result<SendCheck> {
+        SyncedAccount::from(self.clone())
+            .await
+            .can_send(amount, output_count)
+            .await
+    }
+
+    /// Selects the inputs and builds the transfer essence without signing it,
+    /// returning the artifacts to sign it offline with [sign_signing_request](#method.sign_signing_request).
+    /// This is the transfer flow of cold storage accounts.
+    pub async fn prepare_signing_request(&self, transfer_obj: Transfer) -> crate::Result<SigningRequest> {
+        self.check_frozen().await?;
+        if !*self.inner.read().await.cold_storage() {
+            return Err(crate::Error::NotColdStorageAccount);
+        }
+        let account_id = self.id().await;
+        let synced = if transfer_obj.skip_sync {
+            SyncedAccount::from(self.clone()).await
+        } else {
+            transfer_obj
+                .emit_event_if_needed(account_id.clone(), TransferProgressType::SyncingAccount)
+                .await;
+            self.sync_internal().await.execute().await?
+        };
+        synced.prepare_signing_request(transfer_obj).await
+    }
+
+    /// Freezes the account: it still syncs, but every API producing a transfer fails with
+    /// [AccountFrozen](../enum.Error.html#variant.AccountFrozen) until the account is unfrozen.
+    pub async fn freeze(&self) -> crate::Result<()> {
+        self.inner.write().await.set_frozen(true).await
+    }
+
+    /// Unfreezes the account, allowing transfers again.
+    pub async fn unfreeze(&self) -> crate::Result<()> {
+        self.inner.write().await.set_frozen(false).await
+    }
+
+    pub(crate) async fn check_frozen(&self) -> crate::Result<()> {
+        if *self.inner.read().await.frozen() {
+            return Err(crate::Error::AccountFrozen);
+        }
+        Ok(())
+    }
+
     /// Retry message.
     pub async fn retry(&self, message_id: &MessageId) -> crate::Result<Message> {
         self.sync_internal().await.execute().await?.retry(message_id).await
@@ -710,6 +1098,8 @@ impl AccountHandle {
         &self,
         account: &mut RwLockWriteGuard<'_, Account>,
     ) -> crate::Result<Address> {
+        self.account_options
+            .check_address_index(crate::address::next_public_address_index(account))?;
         let address = crate::address::get_new_address(
             account,
             GenerateAddressMetadata {
@@ -720,6 +1110,7 @@ impl AccountHandle {
         .await?;
 
         account.append_addresses(vec![address.clone()]);
+        self.accounts.index_addresses(account);
         account.save().await?;
 
         // monitor on a non-async function to prevent cycle computing the `monitor_address_balance` fn type
@@ -741,17 +1132,12 @@ impl AccountHandle {
         amount: usize,
     ) -> crate::Result<Vec<Address>> {
         let key_index = account.addresses().iter().filter(|a| !a.internal()).count();
+        if amount > 0 {
+            self.account_options.check_address_index(key_index + amount - 1)?;
+        }
         let bech32_hrp = match account.addresses().first() {
             Some(address) => address.address().bech32_hrp().to_string(),
-            None => {
-                crate::client::get_client(account.client_options())
-                    .await?
-                    .read()
-                    .await
-                    .get_network_info()
-                    .await?
-                    .bech32_hrp
-            }
+            None => crate::client::bech32_hrp(account.client_options()).await?,
         };
 
         let mut addresses = Vec::new();
@@ -771,6 +1157,7 @@ impl AccountHandle {
         }
 
         account.append_addresses(addresses.clone());
+        self.accounts.index_addresses(account);
         account.save().await?;
 
         // Don't monitor if too many addresses
@@ -850,12 +1237,14 @@ impl AccountHandle {
         let bech32_hrp = latest_address.address().bech32_hrp().to_string();
         let address_wrapper = latest_address.address().clone();
         sync::sync_address(
+            &self.accounts,
             messages,
             &client_options,
             latest_address.outputs_mut(),
             address_wrapper,
             bech32_hrp,
             self.account_options,
+            &Default::default(),
         )
         .await?;
         let is_unused = latest_address.balance() == 0 && latest_address.outputs().is_empty();
@@ -879,8 +1268,18 @@ impl AccountHandle {
     }
 
     /// Bridge to [Account#set_client_options](struct.Account.html#method.set_client_options).
+    /// The MQTT subscriptions of the account are moved to the new broker.
     pub async fn set_client_options(&self, options: ClientOptions) -> crate::Result<()> {
-        self.inner.write().await.set_client_options(options).await
+        let previous_options = {
+            let mut account = self.inner.write().await;
+            let previous_options = account.client_options().clone();
+            account.set_client_options(options).await?;
+            previous_options
+        };
+        if &previous_options != self.inner.read().await.client_options() {
+            crate::monitor::resubscribe(self.clone(), previous_options).await?;
+        }
+        Ok(())
     }
 
     /// Bridge to [Account#list_messages](struct.Account.html#method.list_messages).
@@ -895,6 +1294,16 @@ impl AccountHandle {
         self.inner.read().await.list_messages(count, from, message_type).await
     }
 
+    /// Bridge to [Account#message_stream](struct.Account.html#method.message_stream).
+    pub async fn message_stream(
+        &self,
+        message_type: Option<MessageType>,
+    ) -> crate::Result<impl Stream<Item = crate::Result<Message>>> {
+        let account = self.inner.read().await;
+        let pages = account.message_stream_pages(message_type).await?;
+        Ok(message_page_stream(account.storage_path.clone(), pages))
+    }
+
     /// Bridge to [Account#list_spent_addresses](struct.Account.html#method.list_spent_addresses).
     /// This method clones the account's addresses so when querying a large list of addresses
     /// prefer using the `read` method to access the account instance.
@@ -930,6 +1339,66 @@ impl AccountHandle {
         self.inner.read().await.get_message(message_id).await
     }
 
+    /// Verifies a stored message against its raw bytes with [Message#verify](../message/struct.Message.html#method.verify),
+    /// fetching the bytes from the node and storing them if the message was stored without them.
+    pub async fn verify_message(&self, message_id: &MessageId) -> crate::Result<()> {
+        let mut message = self
+            .get_message(message_id)
+            .await
+            .ok_or(crate::Error::MessageNotFound)?;
+        if message.raw.is_none() {
+            let client_options = self.client_options().await;
+            let iota_message = crate::client::get_client(&client_options)
+                .await?
+                .read()
+                .await
+                .get_message()
+                .data(message_id)
+                .await?;
+            message.raw = Some(iota_message.pack_new());
+            // only cache the bytes of the requested message, the node could answer with another one
+            if &iota_message.id().0 == message_id {
+                self.inner.write().await.save_messages(vec![message.clone()]).await?;
+            }
+        }
+        message.verify()
+    }
+
+    /// Re-verifies the signatures and recomputes the value and direction of every stored message,
+    /// persisting the recomputed data if `repair` is set.
+    pub async fn revalidate_messages(&self, repair: bool) -> crate::Result<ValidationReport> {
+        self.inner
+            .write()
+            .await
+            .revalidate_messages(self.accounts.clone(), repair)
+            .await
+    }
+
+    /// Computes totals, transfer counts, average confirmation latency and most active counterparties
+    /// of the confirmed transfers in the given period from the stored messages.
+    pub async fn activity_summary(&self, period: ActivityPeriod) -> crate::Result<ActivitySummary> {
+        self.inner.read().await.activity_summary(period).await
+    }
+
+    /// Returns the addresses with their balances, the latest messages and the pending transfers in a single read,
+    /// so clients can hydrate their state on startup.
+    pub async fn light_state(&self) -> crate::Result<LightState> {
+        self.inner.read().await.light_state().await
+    }
+
+    /// Gets the persisted events of the account with a sequence number greater than `sequence`, ordered by sequence.
+    /// Events can be delivered more than once, e.g. after a re-sync; consumers should track the last sequence number
+    /// or the deduplication key of the processed events.
+    pub async fn get_events_since(&self, sequence: u64) -> crate::Result<Vec<AccountEvent>> {
+        let account = self.inner.read().await;
+        crate::storage::get(account.storage_path())
+            .await?
+            .lock()
+            .await
+            .get_events_since(account.id(), sequence)
+            .await
+    }
+
     /// Bridge to [Account#get_node_info](struct.Account.html#method.get_node_info).
     pub async fn get_node_info(
         &self,
@@ -946,6 +1415,7 @@ impl AccountHandle {
         &self,
         participations: Vec<crate::participation::types::Participation>,
     ) -> crate::Result<Vec<Message>> {
+        self.check_frozen().await?;
         self.sync_internal()
             .await
             .address_index(0)
@@ -958,6 +1428,7 @@ impl AccountHandle {
     #[cfg(feature = "participation")]
     /// Stop participating from provided staking or voting events
     pub async fn stop_participating(&self, event_ids: Vec<String>) -> crate::Result<Vec<Message>> {
+        self.check_frozen().await?;
         let account = self.read().await;
         let storage = crate::storage::get(&account.storage_path).await?;
         let mut storage = storage.lock().await;
@@ -1216,6 +1687,59 @@ impl AccountHandle {
         Ok(total_staking_status)
     }
 
+    #[cfg(feature = "participation")]
+    /// Calculates the staking rewards per event and address locally, from the output participations stored by
+    /// [get_participation_overview](struct.AccountHandle.html#method.get_participation_overview). Only the event
+    /// information and the confirmed milestone are requested from the node.
+    pub async fn staking_overview(&self) -> crate::Result<crate::participation::rewards::StakingOverview> {
+        let account = self.read().await;
+        let participation_outputs = match crate::storage::get(&account.storage_path)
+            .await?
+            .lock()
+            .await
+            .get_participation_outputs(*account.index())
+            .await
+        {
+            Ok(res) => res,
+            Err(_) => crate::participation::types::OutputStatusResponses {
+                spent: HashMap::new(),
+                unspent: HashMap::new(),
+            },
+        };
+
+        let mut output_addresses = HashMap::new();
+        for address in account.addresses() {
+            for output in address.outputs().values() {
+                output_addresses.insert(output.id()?, address.address().to_bech32());
+            }
+        }
+
+        let client = crate::client::get_client(&account.client_options).await?;
+        let client = client.read().await;
+        let node = client.get_node().await?;
+        let milestone_index = client.get_info().await?.nodeinfo.confirmed_milestone_index;
+
+        let event_ids: HashSet<&String> = participation_outputs
+            .spent
+            .values()
+            .chain(participation_outputs.unspent.values())
+            .flat_map(|status| status.participations.keys())
+            .collect();
+        let mut events = HashMap::new();
+        for event_id in event_ids {
+            let information =
+                crate::participation::endpoints::get_event_information(node.url.clone(), event_id).await?;
+            events.insert(event_id.clone(), information);
+        }
+
+        Ok(crate::participation::rewards::calculate_staking_rewards(
+            &participation_outputs,
+            &output_addresses,
+            &events,
+            milestone_index,
+        ))
+    }
+
     #[cfg(feature = "participation")]
     /// Get an overview of the staked funds and the accumulated rewards.
     pub async fn get_participation_events(&self) -> crate::Result<Vec<crate::participation::types::EventData>> {
@@ -1223,12 +1747,13 @@ impl AccountHandle {
         let client = crate::client::get_client(&account.client_options).await?;
         let client = client.read().await;
         let node = client.get_node().await?;
-        let event_ids = crate::participation::endpoints::get_events(node.clone(), None).await?;
+        let event_ids = crate::participation::endpoints::get_events(node.url.clone(), None).await?;
         log::debug!("[get_participation_events] event_ids {:?}", event_ids);
         let mut events_data = Vec::new();
         for id in event_ids.event_ids {
-            let event_information = crate::participation::endpoints::get_event_information(node.clone(), &id).await?;
-            let event_status = crate::participation::endpoints::get_event_status(node.clone(), &id).await?;
+            let event_information =
+                crate::participation::endpoints::get_event_information(node.url.clone(), &id).await?;
+            let event_status = crate::participation::endpoints::get_event_status(node.url.clone(), &id).await?;
             events_data.push(crate::participation::types::EventData {
                 event_id: id,
                 information: event_information,
@@ -1240,7 +1765,7 @@ impl AccountHandle {
 }
 
 /// Account balance information.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct AccountBalance {
     /// Account's total balance.
     pub total: u64,
@@ -1377,6 +1902,11 @@ impl Account {
         self.save().await
     }
 
+    pub(crate) async fn set_frozen(&mut self, frozen: bool) -> crate::Result<()> {
+        self.frozen = frozen;
+        self.save().await
+    }
+
     /// Updates the account's client options.
     pub async fn set_client_options(&mut self, options: ClientOptions) -> crate::Result<()> {
         let client_guard = crate::client::get_client(&options).await?;
@@ -1399,7 +1929,10 @@ impl Account {
             }
         }
 
-        let bech32_hrp = client.get_network_info().await?.bech32_hrp;
+        let bech32_hrp = match options.network_parameters().bech32_hrp() {
+            Some(bech32_hrp) => bech32_hrp.clone(),
+            None => client.get_network_info().await?.bech32_hrp,
+        };
         for address in &mut self.addresses {
             address.set_bech32_hrp(bech32_hrp.to_string());
         }
@@ -1479,6 +2012,37 @@ impl Account {
         Ok(messages)
     }
 
+    /// Streams the messages of the given type without loading them all in memory, e.g. to export or scan accounts
+    /// with many messages. Only the message ids are gathered up front; the messages are read from the storage in
+    /// pages of `MESSAGE_STREAM_PAGE_SIZE` as the stream is polled, in the storage order instead of the timestamp
+    /// order of [list_messages](#method.list_messages). Records that can't be read are yielded as errors.
+    pub async fn message_stream(
+        &self,
+        message_type: Option<MessageType>,
+    ) -> crate::Result<impl Stream<Item = crate::Result<Message>>> {
+        let pages = self.message_stream_pages(message_type).await?;
+        Ok(message_page_stream(self.storage_path.clone(), pages))
+    }
+
+    /// The ids of the messages of the given type, split in the pages read by the message stream.
+    async fn message_stream_pages(
+        &self,
+        message_type: Option<MessageType>,
+    ) -> crate::Result<Vec<Vec<(MessageId, Option<bool>)>>> {
+        let message_ids: Vec<(MessageId, Option<bool>)> = crate::storage::get(&self.storage_path)
+            .await?
+            .lock()
+            .await
+            .query_message_indexation(self, &MessageQueryFilter::message_type(message_type))?
+            .into_iter()
+            .map(|index| (index.key, index.incoming))
+            .collect();
+        Ok(message_ids
+            .chunks(MESSAGE_STREAM_PAGE_SIZE)
+            .map(|page| page.to_vec())
+            .collect())
+    }
+
     /// Gets the spent addresses.
     pub async fn list_spent_addresses(&self) -> crate::Result<Vec<&Address>> {
         let sent_messages = self.list_messages(0, 0, Some(MessageType::Sent)).await?;
@@ -1561,6 +2125,29 @@ impl Account {
             .await
     }
 
+    /// Links the reattachment to the message it reattaches and saves both.
+    /// The chain is kept on the first message, so listings only show it once.
+    pub(crate) async fn link_reattachment(
+        &mut self,
+        message_id: &MessageId,
+        reattachment: &mut Message,
+    ) -> crate::Result<()> {
+        let message = self
+            .get_message(message_id)
+            .await
+            .ok_or(crate::Error::MessageNotFound)?;
+        let mut original = match message.reattachment_of {
+            Some(original_id) => self.get_message(&original_id).await.unwrap_or(message),
+            None => message,
+        };
+        reattachment.reattachment_of = Some(*original.id());
+        if !original.reattachments.contains(reattachment.id()) {
+            original.reattachments.push(*reattachment.id());
+        }
+        original.reattachment_message_id = Some(*reattachment.id());
+        self.save_messages(vec![original, reattachment.clone()]).await
+    }
+
     /// Gets a message with the given id associated with this account.
     pub async fn get_message(&self, message_id: &MessageId) -> Option<Message> {
         crate::storage::get(&self.storage_path)
@@ -1579,6 +2166,24 @@ impl Account {
     }
 }
 
+/// Reads the pages of messages from the storage as the stream is polled.
+fn message_page_stream(
+    storage_path: PathBuf,
+    pages: Vec<Vec<(MessageId, Option<bool>)>>,
+) -> impl Stream<Item = crate::Result<Message>> {
+    futures::stream::iter(pages)
+        .then(move |page| {
+            let storage_path = storage_path.clone();
+            async move {
+                match crate::storage::get(&storage_path).await {
+                    Ok(storage) => storage.lock().await.read_messages_page(&page).await,
+                    Err(e) => vec![Err(e)],
+                }
+            }
+        })
+        .flat_map(futures::stream::iter)
+}
+
 #[cfg(test)]
 mod tests {
     use super::AccountHandle;
@@ -1586,16 +2191,43 @@ mod tests {
         account_manager::AccountManager,
         address::{Address, AddressBuilder, AddressOutput, OutputKind},
         client::ClientOptionsBuilder,
-        message::{Message, MessagePayload, MessageType, TransactionEssence},
+        message::{Message, MessagePayload, MessageType, TransactionEssence, Transfer},
     };
     use iota_client::bee_message::prelude::{MessageId, TransactionId};
-    use std::collections::HashMap;
+    use std::{collections::HashMap, num::NonZeroU64};
+
+    #[tokio::test]
+    async fn account_identifier() {
+        let manager = crate::testing::get_account_manager().await;
+        let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
+        let (index, alias) = {
+            let account = account_handle.read().await;
+            (*account.index(), account.alias().clone())
+        };
+
+        // the message interface sends indexes as numbers and aliases as strings
+        let by_index: super::AccountIdentifier = serde_json::from_value(serde_json::json!(index)).unwrap();
+        let by_alias: super::AccountIdentifier = serde_json::from_value(serde_json::json!(alias)).unwrap();
+        for identifier in [by_index, by_alias] {
+            assert_eq!(
+                manager.get_account(identifier).await.unwrap().id().await,
+                account_handle.id().await
+            );
+        }
+
+        assert!(matches!(
+            manager.get_account("missing").await,
+            Err(crate::Error::AccountNotFound {
+                by: super::AccountIdentifier::Alias(_)
+            })
+        ));
+    }
 
     // asserts that the `set_alias` function updates the account alias in storage
     #[tokio::test]
     async fn set_alias() {
-        crate::test_utils::with_account_manager(crate::test_utils::TestType::Storage, |manager, _| async move {
-            let account_handle = crate::test_utils::AccountCreator::new(&manager).create().await;
+        crate::testing::with_account_manager(crate::testing::TestType::Storage, |manager, _| async move {
+            let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
 
             let updated_alias = "updated alias";
 
@@ -1610,11 +2242,44 @@ mod tests {
         .await;
     }
 
+    // asserts that a frozen account rejects transfers until it's unfrozen
+    #[tokio::test]
+    async fn freeze() {
+        let manager = crate::testing::get_account_manager().await;
+        let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
+
+        account_handle.freeze().await.unwrap();
+        assert!(account_handle.frozen().await);
+        let transfer = Transfer::builder(
+            crate::testing::generate_random_iota_address(),
+            NonZeroU64::new(1_000_000).unwrap(),
+            None,
+        )
+        .with_skip_sync()
+        .finish()
+        .unwrap();
+        assert!(matches!(
+            account_handle.transfer(transfer).await,
+            Err(crate::Error::AccountFrozen)
+        ));
+
+        account_handle.unfreeze().await.unwrap();
+        assert!(account_handle.check_frozen().await.is_ok());
+    }
+
     // asserts that the `set_client_options` function updates the account client options in storage
     #[tokio::test]
     async fn set_client_options() {
-        crate::test_utils::with_account_manager(crate::test_utils::TestType::Storage, |manager, _| async move {
-            let account_handle = crate::test_utils::AccountCreator::new(&manager).create().await;
+        crate::testing::with_account_manager(crate::testing::TestType::Storage, |manager, _| async move {
+            let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
+            let account_id = account_handle.read().await.id().clone();
+            let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+            let listener = crate::event::on_monitoring_resubscribed(move |event| {
+                if event.account_id() == &account_id {
+                    let _ = tx.send(());
+                }
+            })
+            .await;
 
             let updated_client_options = ClientOptionsBuilder::new()
                 .with_nodes(&[
@@ -1635,15 +2300,17 @@ mod tests {
                 .await
                 .expect("failed to get account from storage");
             assert_eq!(account_in_storage.client_options().await, updated_client_options);
+            assert!(rx.try_recv().is_ok());
+            crate::event::remove_monitoring_resubscribed_listener(&listener).await;
         })
         .await;
     }
 
     #[tokio::test]
     async fn account_handle_bridge_getters() {
-        let manager = crate::test_utils::get_account_manager().await;
+        let manager = crate::testing::get_account_manager().await;
 
-        let account_handle = crate::test_utils::AccountCreator::new(&manager).create().await;
+        let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
 
         macro_rules! assert_bridge_method {
             ($($x:ident),+) => {
@@ -1667,7 +2334,7 @@ mod tests {
             index: 0,
             amount: value,
             is_spent: false,
-            address: crate::test_utils::generate_random_iota_address(),
+            address: crate::testing::generate_random_iota_address(),
             kind: OutputKind::SignatureLockedSingle,
         }
     }
@@ -1675,20 +2342,20 @@ mod tests {
     async fn _generate_account(manager: &AccountManager, messages: Vec<Message>) -> (AccountHandle, Address, u64) {
         let balance = 30;
         let first_address = AddressBuilder::new()
-            .address(crate::test_utils::generate_random_iota_address())
+            .address(crate::testing::generate_random_iota_address())
             .key_index(0)
             .outputs(vec![_generate_address_output(balance / 2_u64)])
             .build()
             .unwrap();
         let second_address = AddressBuilder::new()
-            .address(crate::test_utils::generate_random_iota_address())
+            .address(crate::testing::generate_random_iota_address())
             .key_index(1)
             .outputs(vec![_generate_address_output(balance / 2_u64)])
             .build()
             .unwrap();
 
         let addresses = vec![second_address.clone(), first_address];
-        let account_handle = crate::test_utils::AccountCreator::new(manager)
+        let account_handle = crate::testing::AccountCreator::new(manager)
             .addresses(addresses)
             .messages(messages)
             .create()
@@ -1698,51 +2365,48 @@ mod tests {
 
     #[tokio::test]
     async fn generate_address() {
-        crate::test_utils::with_account_manager(
-            crate::test_utils::TestType::Signing,
-            |manager, signer_type| async move {
-                let account_handle = crate::test_utils::AccountCreator::new(&manager)
-                    .signer_type(signer_type)
-                    .create()
-                    .await;
-                let account_next_address = {
-                    let account = account_handle.read().await;
-                    crate::address::get_new_address(
-                        &account,
-                        crate::signing::GenerateAddressMetadata {
-                            syncing: false,
-                            network: account.network(),
-                        },
-                    )
-                    .await
-                    .unwrap()
-                };
-                let generated_address = account_handle.generate_address().await.unwrap();
+        crate::testing::with_account_manager(crate::testing::TestType::Signing, |manager, signer_type| async move {
+            let account_handle = crate::testing::AccountCreator::new(&manager)
+                .signer_type(signer_type)
+                .create()
+                .await;
+            let account_next_address = {
+                let account = account_handle.read().await;
+                crate::address::get_new_address(
+                    &account,
+                    crate::signing::GenerateAddressMetadata {
+                        syncing: false,
+                        network: account.network(),
+                    },
+                )
+                .await
+                .unwrap()
+            };
+            let generated_address = account_handle.generate_address().await.unwrap();
 
-                assert_eq!(generated_address, account_next_address);
-                assert_eq!(account_handle.latest_address().await, generated_address);
-            },
-        )
+            assert_eq!(generated_address, account_next_address);
+            assert_eq!(account_handle.latest_address().await, generated_address);
+        })
         .await;
     }
 
     #[tokio::test]
     async fn latest_address() {
-        let manager = crate::test_utils::get_account_manager().await;
+        let manager = crate::testing::get_account_manager().await;
         let (account_handle, latest_address, _) = _generate_account(&manager, vec![]).await;
         assert_eq!(account_handle.read().await.latest_address(), &latest_address);
     }
 
     #[tokio::test]
     async fn total_balance() {
-        let manager = crate::test_utils::get_account_manager().await;
+        let manager = crate::testing::get_account_manager().await;
         let (account_handle, _, balance) = _generate_account(&manager, vec![]).await;
         assert_eq!(account_handle.read().await.balance().await.unwrap().total, balance);
     }
 
     #[tokio::test]
     async fn available_balance() {
-        let manager = crate::test_utils::get_account_manager().await;
+        let manager = crate::testing::get_account_manager().await;
         let (account_handle, _, balance) = _generate_account(&manager, vec![]).await;
         assert_eq!(account_handle.read().await.balance().await.unwrap().available, balance);
 
@@ -1768,7 +2432,7 @@ mod tests {
             address.clone()
         };
 
-        let unconfirmed_message = crate::test_utils::GenerateMessageBuilder::default()
+        let unconfirmed_message = crate::testing::GenerateMessageBuilder::default()
             .address(first_address.clone())
             .value(15)
             .input_transaction_id(first_address.outputs.values().next().unwrap().transaction_id)
@@ -1777,7 +2441,7 @@ mod tests {
             .confirmed(None)
             .build()
             .await;
-        let confirmed_message = crate::test_utils::GenerateMessageBuilder::default()
+        let confirmed_message = crate::testing::GenerateMessageBuilder::default()
             .address(second_address.clone())
             .value(10)
             .input_transaction_id(second_address.outputs.values().next().unwrap().transaction_id)
@@ -1806,28 +2470,28 @@ mod tests {
 
     #[tokio::test]
     async fn list_all_messages() {
-        let manager = crate::test_utils::get_account_manager().await;
-        let account_handle = crate::test_utils::AccountCreator::new(&manager)
-            .addresses(vec![crate::test_utils::generate_random_address()])
+        let manager = crate::testing::get_account_manager().await;
+        let account_handle = crate::testing::AccountCreator::new(&manager)
+            .addresses(vec![crate::testing::generate_random_address()])
             .create()
             .await;
         let latest_address = account_handle.read().await.latest_address().clone();
-        let received_message = crate::test_utils::GenerateMessageBuilder::default()
+        let received_message = crate::testing::GenerateMessageBuilder::default()
             .address(latest_address.clone())
-            .input_address(Some(crate::test_utils::generate_random_iota_address()))
+            .input_address(Some(crate::testing::generate_random_iota_address()))
             .build()
             .await;
-        let failed_message = crate::test_utils::GenerateMessageBuilder::default()
+        let failed_message = crate::testing::GenerateMessageBuilder::default()
             .address(latest_address.clone())
             .broadcasted(false)
             .build()
             .await;
-        let unconfirmed_message = crate::test_utils::GenerateMessageBuilder::default()
+        let unconfirmed_message = crate::testing::GenerateMessageBuilder::default()
             .address(latest_address.clone())
             .confirmed(None)
             .build()
             .await;
-        let value_message = crate::test_utils::GenerateMessageBuilder::default()
+        let value_message = crate::testing::GenerateMessageBuilder::default()
             .address(latest_address.clone())
             .build()
             .await;
@@ -1847,25 +2511,56 @@ mod tests {
         assert_eq!(txs.len(), 4);
     }
 
+    #[tokio::test]
+    async fn message_stream() {
+        use futures::TryStreamExt;
+
+        let manager = crate::testing::get_account_manager().await;
+        let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
+        let latest_address = account_handle.read().await.latest_address().clone();
+
+        let mut messages = Vec::new();
+        for _ in 0..super::MESSAGE_STREAM_PAGE_SIZE + 1 {
+            messages.push(
+                crate::testing::GenerateMessageBuilder::default()
+                    .address(latest_address.clone())
+                    .build()
+                    .await,
+            );
+        }
+        account_handle.write().await.save_messages(messages).await.unwrap();
+
+        let streamed: Vec<Message> = account_handle
+            .message_stream(None)
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        let listed = account_handle.list_messages(0, 0, None).await.unwrap();
+        assert_eq!(streamed.len(), super::MESSAGE_STREAM_PAGE_SIZE + 1);
+        assert!(listed.iter().all(|message| streamed.contains(message)));
+    }
+
     #[tokio::test]
     async fn list_messages_by_type() {
-        let manager = crate::test_utils::get_account_manager().await;
-        let account_handle = crate::test_utils::AccountCreator::new(&manager)
-            .addresses(vec![crate::test_utils::generate_random_address()])
+        let manager = crate::testing::get_account_manager().await;
+        let account_handle = crate::testing::AccountCreator::new(&manager)
+            .addresses(vec![crate::testing::generate_random_address()])
             .create()
             .await;
 
-        let external_address = crate::test_utils::generate_random_address();
+        let external_address = crate::testing::generate_random_address();
         let latest_address = account_handle.read().await.latest_address().clone();
 
-        let received_message = crate::test_utils::GenerateMessageBuilder::default()
+        let received_message = crate::testing::GenerateMessageBuilder::default()
             .address(latest_address.clone())
             .input_address(Some(external_address.address().clone()))
             .confirmed(Some(true))
             .broadcasted(true)
             .build()
             .await;
-        let sent_message = crate::test_utils::GenerateMessageBuilder::default()
+        let sent_message = crate::testing::GenerateMessageBuilder::default()
             .address(external_address.clone())
             .input_address(Some(latest_address.address().clone()))
             .account_addresses(account_handle.addresses().await)
@@ -1873,19 +2568,19 @@ mod tests {
             .broadcasted(true)
             .build()
             .await;
-        let failed_message = crate::test_utils::GenerateMessageBuilder::default()
+        let failed_message = crate::testing::GenerateMessageBuilder::default()
             .address(latest_address.clone())
             .confirmed(Some(true))
             .broadcasted(false)
             .build()
             .await;
-        let unconfirmed_message = crate::test_utils::GenerateMessageBuilder::default()
+        let unconfirmed_message = crate::testing::GenerateMessageBuilder::default()
             .address(latest_address.clone())
             .confirmed(None)
             .broadcasted(true)
             .build()
             .await;
-        let value_message = crate::test_utils::GenerateMessageBuilder::default()
+        let value_message = crate::testing::GenerateMessageBuilder::default()
             .address(latest_address.clone())
             .confirmed(Some(true))
             .broadcasted(true)
@@ -1928,13 +2623,63 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn reattachment_history() {
+        let manager = crate::testing::get_account_manager().await;
+        let address = crate::testing::generate_random_address();
+        let message = crate::testing::GenerateMessageBuilder::default()
+            .address(address.clone())
+            .confirmed(None)
+            .build()
+            .await;
+        let account_handle = crate::testing::AccountCreator::new(&manager)
+            .addresses(vec![address.clone()])
+            .messages(vec![message.clone()])
+            .create()
+            .await;
+
+        let mut reattachments = Vec::new();
+        let mut reposted_id = *message.id();
+        for _ in 0..2 {
+            let mut reattachment = crate::testing::GenerateMessageBuilder::default()
+                .address(address.clone())
+                .confirmed(None)
+                .build()
+                .await;
+            account_handle
+                .write()
+                .await
+                .link_reattachment(&reposted_id, &mut reattachment)
+                .await
+                .unwrap();
+            assert_eq!(reattachment.reattachment_of(), &Some(*message.id()));
+            reposted_id = *reattachment.id();
+            reattachments.push(reattachment);
+        }
+
+        // a synced copy without the links keeps them
+        let mut synced = reattachments[1].clone();
+        synced.reattachment_of = None;
+        account_handle.write().await.save_messages(vec![synced]).await.unwrap();
+
+        let original = account_handle.get_message(message.id()).await.unwrap();
+        let reattachment_ids: Vec<MessageId> = reattachments.iter().map(|m| *m.id()).collect();
+        assert_eq!(original.reattachments(), &reattachment_ids);
+        assert_eq!(original.reattachment_message_id(), &Some(reattachment_ids[1]));
+        let stored = account_handle.get_message(&reattachment_ids[1]).await.unwrap();
+        assert_eq!(stored.reattachment_of(), &Some(*message.id()));
+
+        let messages = account_handle.list_messages(0, 0, None).await.unwrap();
+        assert_eq!(messages, vec![original]);
+    }
+
     #[tokio::test]
     async fn get_message_by_id() {
-        let manager = crate::test_utils::get_account_manager().await;
-        let account_handle = crate::test_utils::AccountCreator::new(&manager).create().await;
+        let manager = crate::testing::get_account_manager().await;
+        let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
 
-        let m1 = crate::test_utils::GenerateMessageBuilder::default().build().await;
-        let m2 = crate::test_utils::GenerateMessageBuilder::default().build().await;
+        let m1 = crate::testing::GenerateMessageBuilder::default().build().await;
+        let m2 = crate::testing::GenerateMessageBuilder::default().build().await;
         account_handle
             .write()
             .await
@@ -1947,10 +2692,10 @@ mod tests {
     #[tokio::test]
     #[ignore]
     async fn list_addresses() {
-        crate::test_utils::with_account_manager(
-            crate::test_utils::TestType::SigningAndStorage,
+        crate::testing::with_account_manager(
+            crate::testing::TestType::SigningAndStorage,
             |manager, signer_type| async move {
-                let account_handle = crate::test_utils::AccountCreator::new(&manager)
+                let account_handle = crate::testing::AccountCreator::new(&manager)
                     .signer_type(signer_type)
                     .create()
                     .await;
@@ -1959,7 +2704,7 @@ mod tests {
                 let unspent_address1 = account_handle.generate_address().await.unwrap();
                 let unspent_address2 = account_handle.generate_address().await.unwrap();
 
-                let spent_tx = crate::test_utils::GenerateMessageBuilder::default()
+                let spent_tx = crate::testing::GenerateMessageBuilder::default()
                     .address(spent_address.clone())
                     .input_address(Some(unspent_address1.address().clone()))
                     .build()
@@ -1993,9 +2738,9 @@ mod tests {
 
     #[tokio::test]
     async fn test_get_info() {
-        let manager = crate::test_utils::get_account_manager().await;
-        let account_handle = crate::test_utils::AccountCreator::new(&manager)
-            .addresses(vec![crate::test_utils::generate_random_address()])
+        let manager = crate::testing::get_account_manager().await;
+        let account_handle = crate::testing::AccountCreator::new(&manager)
+            .addresses(vec![crate::testing::generate_random_address()])
             .create()
             .await;
 
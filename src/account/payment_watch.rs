@@ -0,0 +1,129 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::AccountHandle;
+use crate::address::{AddressOutput, AddressWrapper};
+
+use std::time::{Duration, Instant};
+
+/// An address watched for an incoming payment.
+#[derive(Debug, Clone)]
+pub(crate) struct PaymentWatch {
+    address: AddressWrapper,
+    min_amount: u64,
+    expires_at: Instant,
+}
+
+impl AccountHandle {
+    /// Watches the given account address for an incoming output of at least `min_amount`.
+    /// When the monitor detects a matching output, a [PaymentReceived](../event/struct.PaymentReceived.html) event
+    /// is emitted and the address is unwatched. The watch is dropped without an event after `expiry`.
+    ///
+    /// The detection relies on MQTT; payments aren't reported if MQTT is disabled.
+    pub async fn watch_address_for_payment(
+        &self,
+        address: AddressWrapper,
+        min_amount: u64,
+        expiry: Duration,
+    ) -> crate::Result<()> {
+        if !self
+            .inner
+            .read()
+            .await
+            .addresses()
+            .iter()
+            .any(|a| a.address() == &address)
+        {
+            return Err(crate::Error::InvalidAddress);
+        }
+
+        let now = Instant::now();
+        let mut watches = self.payment_watches.lock().await;
+        watches.retain(|watch| watch.expires_at > now);
+        watches.push(PaymentWatch {
+            address: address.clone(),
+            min_amount,
+            expires_at: now + expiry,
+        });
+        drop(watches);
+
+        self.monitor_address(address);
+        Ok(())
+    }
+
+    /// Emits the `PaymentReceived` event and unwatches the address if the output matches a watch.
+    /// Takes the account id since it's called while the account is locked.
+    pub(crate) async fn process_payment_watches(&self, account_id: &str, output: &AddressOutput) -> bool {
+        if output.is_spent {
+            return false;
+        }
+        let now = Instant::now();
+        let mut watches = self.payment_watches.lock().await;
+        watches.retain(|watch| watch.expires_at > now);
+        let position = watches
+            .iter()
+            .position(|watch| watch.address == output.address && output.amount >= watch.min_amount);
+        match position {
+            Some(position) => {
+                let watch = watches.remove(position);
+                drop(watches);
+                crate::event::emit_payment_received(
+                    account_id.to_string(),
+                    watch.address,
+                    output.message_id,
+                    output.amount,
+                )
+                .await;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::address::{AddressOutput, OutputKind};
+    use iota_client::bee_message::prelude::{MessageId, TransactionId};
+
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn payment_watch() {
+        let manager = crate::testing::get_account_manager().await;
+        let address = crate::testing::generate_random_address();
+        let account_handle = crate::testing::AccountCreator::new(&manager)
+            .addresses(vec![address.clone()])
+            .create()
+            .await;
+
+        assert!(account_handle
+            .watch_address_for_payment(
+                crate::testing::generate_random_iota_address(),
+                0,
+                Duration::from_secs(60)
+            )
+            .await
+            .is_err());
+        account_handle
+            .watch_address_for_payment(address.address().clone(), 100, Duration::from_secs(60))
+            .await
+            .unwrap();
+
+        let mut output = AddressOutput {
+            transaction_id: TransactionId::new([0; 32]),
+            message_id: MessageId::new([0; 32]),
+            index: 0,
+            amount: 50,
+            is_spent: false,
+            address: address.address().clone(),
+            kind: OutputKind::SignatureLockedSingle,
+        };
+        let account_id = account_handle.id().await;
+        assert!(!account_handle.process_payment_watches(&account_id, &output).await);
+        output.amount = 100;
+        assert!(account_handle.process_payment_watches(&account_id, &output).await);
+        // the address is unwatched after the first payment
+        assert!(!account_handle.process_payment_watches(&account_id, &output).await);
+    }
+}
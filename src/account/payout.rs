@@ -0,0 +1,215 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::AccountHandle;
+use crate::{
+    address::AddressWrapper,
+    amount::{parse_amount, AmountLocale},
+    message::{Transfer, TransferLimits, TransferOutput, ValueUnit},
+    signing::SignerCapabilities,
+};
+
+use getset::Getters;
+use iota_client::bee_message::MessageId;
+use serde::{Serialize, Serializer};
+
+use std::{
+    io::{BufRead, BufReader, Read},
+    num::NonZeroU64,
+};
+
+/// The CSV amounts can't use a group separator since `,` separates the columns.
+const PAYOUT_AMOUNT_LOCALE: AmountLocale = AmountLocale {
+    decimal_separator: '.',
+    group_separator: None,
+};
+
+/// The outcome of a payout row.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", content = "data")]
+pub enum PayoutStatus {
+    /// The row was sent in the message.
+    Sent(MessageId),
+    /// The transaction holding the row failed.
+    Failed(String),
+}
+
+/// A row of a payout batch.
+#[derive(Debug, Clone, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct PayoutRow {
+    /// The line of the row in the CSV, starting at 1.
+    line: usize,
+    /// The recipient address.
+    #[serde(serialize_with = "serialize_address")]
+    address: AddressWrapper,
+    /// The amount in base units (`i`).
+    amount: u64,
+    /// The row outcome.
+    status: PayoutStatus,
+}
+
+fn serialize_address<S: Serializer>(address: &AddressWrapper, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&address.to_bech32())
+}
+
+/// The result of [AccountHandle#batch_payout](struct.AccountHandle.html#method.batch_payout).
+#[derive(Debug, Clone, Default, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct PayoutReport {
+    /// The rows in the CSV order.
+    rows: Vec<PayoutRow>,
+    /// The messages sent, one per transaction.
+    messages: Vec<MessageId>,
+    /// The sum of the sent rows amounts.
+    #[serde(rename = "sentAmount")]
+    sent_amount: u64,
+    /// The sum of the failed rows amounts.
+    #[serde(rename = "failedAmount")]
+    failed_amount: u64,
+}
+
+fn invalid_row(line: usize, reason: impl std::fmt::Display) -> crate::Error {
+    crate::Error::InvalidPayoutBatch(format!("line {}: {}", line, reason))
+}
+
+/// Parses the `address,amount` rows of a payout CSV. Blank lines, `#` comments and an `address,amount` header are
+/// skipped; the amount defaults to `i` and accepts a unit suffix, e.g. `1.5 Mi`.
+/// Every row is validated before anything is sent, so a typo fails the whole batch.
+pub(crate) fn parse_payout_csv<R: BufRead>(reader: R, bech32_hrp: &str) -> crate::Result<Vec<(usize, TransferOutput)>> {
+    let mut rows = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let columns: Vec<&str> = line.split(',').map(|column| column.trim().trim_matches('"')).collect();
+        if rows.is_empty() && columns[0].eq_ignore_ascii_case("address") {
+            continue;
+        }
+        if columns.len() != 2 {
+            return Err(invalid_row(
+                line_number,
+                format!("expected 2 columns, found {}", columns.len()),
+            ));
+        }
+        let address = crate::address::parse(columns[0]).map_err(|e| invalid_row(line_number, e))?;
+        if address.bech32_hrp() != bech32_hrp {
+            return Err(invalid_row(
+                line_number,
+                format!("address {} isn't a {} address", columns[0], bech32_hrp),
+            ));
+        }
+        let amount =
+            parse_amount(columns[1], ValueUnit::I, &PAYOUT_AMOUNT_LOCALE).map_err(|e| invalid_row(line_number, e))?;
+        let amount = NonZeroU64::new(amount).ok_or_else(|| invalid_row(line_number, "amount can't be zero"))?;
+        rows.push((line_number, TransferOutput::new(address, amount, None)));
+    }
+    if rows.is_empty() {
+        return Err(crate::Error::InvalidPayoutBatch("no payout rows".to_string()));
+    }
+    Ok(rows)
+}
+
+/// The maximum number of payout rows of a transaction, leaving room for an input and the remainder output on the
+/// signers with a combined inputs and outputs limit.
+pub(crate) fn max_payout_outputs(signer_capabilities: &SignerCapabilities) -> usize {
+    match signer_capabilities.max_input_output_count() {
+        Some(max_input_output_count) => max_input_output_count - 2,
+        None => TransferLimits::default().max_outputs,
+    }
+}
+
+impl AccountHandle {
+    /// Pays out the `address,amount` rows of a CSV. Blank lines, `#` comments and a header are skipped; the amounts
+    /// are in `i` unless they have a unit suffix, e.g. `1.5 Mi`.
+    /// All rows are validated first, then grouped into as few transactions as the signer allows and sent one after
+    /// the other. A failed transaction doesn't stop the batch: its rows are reported as failed so they can be retried.
+    pub async fn batch_payout<R: Read>(&self, reader: R) -> crate::Result<PayoutReport> {
+        self.check_frozen().await?;
+        let (bech32_hrp, signer_type) = {
+            let account = self.inner.read().await;
+            (account.bech32_hrp(), account.signer_type().clone())
+        };
+        let signer_capabilities = crate::signing::signer_capabilities(&signer_type).await;
+        let rows = parse_payout_csv(BufReader::new(reader), &bech32_hrp)?;
+
+        let mut report = PayoutReport::default();
+        for chunk in rows.chunks(max_payout_outputs(&signer_capabilities)) {
+            let outputs: Vec<TransferOutput> = chunk.iter().map(|(_, output)| output.clone()).collect();
+            let result = match Transfer::builder_with_outputs(outputs).and_then(|builder| builder.finish()) {
+                Ok(transfer) => self.transfer(transfer).await,
+                Err(e) => Err(e),
+            };
+            let status = match result {
+                Ok(message) => {
+                    report.messages.push(*message.id());
+                    PayoutStatus::Sent(*message.id())
+                }
+                Err(e) => {
+                    log::warn!("[PAYOUT] transaction of {} rows failed: {}", chunk.len(), e);
+                    PayoutStatus::Failed(e.to_string())
+                }
+            };
+            for (line, output) in chunk {
+                match status {
+                    PayoutStatus::Sent(_) => report.sent_amount += output.amount.get(),
+                    PayoutStatus::Failed(_) => report.failed_amount += output.amount.get(),
+                }
+                report.rows.push(PayoutRow {
+                    line: *line,
+                    address: output.address.clone(),
+                    amount: output.amount.get(),
+                    status: status.clone(),
+                });
+            }
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{max_payout_outputs, parse_payout_csv};
+    use crate::signing::SignerCapabilities;
+
+    #[test]
+    fn payout_csv() {
+        let first = crate::testing::generate_random_iota_address();
+        let second = crate::testing::generate_random_iota_address();
+        let csv = format!(
+            "address,amount\n{},1000000\n\n# bonus\n\"{}\", 1.5 Mi\n",
+            first.to_bech32(),
+            second.to_bech32()
+        );
+        let rows = parse_payout_csv(csv.as_bytes(), "atoi").unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].0, 2);
+        assert_eq!(rows[0].1.address, first);
+        assert_eq!(rows[0].1.amount.get(), 1_000_000);
+        assert_eq!(rows[1].0, 5);
+        assert_eq!(rows[1].1.amount.get(), 1_500_000);
+
+        // a single invalid row rejects the batch
+        let csv = format!("{},1000000\n{},0\n", first.to_bech32(), second.to_bech32());
+        assert!(matches!(
+            parse_payout_csv(csv.as_bytes(), "atoi"),
+            Err(crate::Error::InvalidPayoutBatch(reason)) if reason.starts_with("line 2")
+        ));
+        let csv = format!("{},1000000\n", first.to_bech32());
+        assert!(parse_payout_csv(csv.as_bytes(), "iota").is_err());
+        assert!(parse_payout_csv("address,amount\n".as_bytes(), "atoi").is_err());
+    }
+
+    #[test]
+    fn payout_chunks() {
+        assert_eq!(max_payout_outputs(&SignerCapabilities::default()), 124);
+        let ledger = SignerCapabilities {
+            max_input_output_count: Some(17),
+            ..Default::default()
+        };
+        assert_eq!(max_payout_outputs(&ledger), 15);
+    }
+}
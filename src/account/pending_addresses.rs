@@ -0,0 +1,163 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::AccountHandle;
+use crate::address::{Address, AddressWrapper, GenerateAddressMetadata};
+
+use std::collections::BTreeSet;
+
+/// The address derivations a sync couldn't do because Stronghold was locked, by key index and internal flag.
+pub(crate) type PendingAddressDerivations = BTreeSet<(usize, bool)>;
+
+/// Whether the address generation failed because the Stronghold password isn't set.
+pub(crate) fn is_stronghold_locked(error: &crate::Error) -> bool {
+    #[cfg(feature = "stronghold")]
+    {
+        matches!(
+            error,
+            crate::Error::StrongholdError(crate::stronghold::Error::PasswordNotSet)
+        )
+    }
+    #[cfg(not(feature = "stronghold"))]
+    {
+        let _ = error;
+        false
+    }
+}
+
+impl AccountHandle {
+    /// Generates an address for the sync, without a device prompt. If Stronghold is locked and `queue_if_locked` is
+    /// set, the derivation is queued and resumed once the password is set, instead of waiting for the next sync.
+    pub(crate) async fn generate_sync_address(
+        &self,
+        account: &super::Account,
+        key_index: usize,
+        internal: bool,
+        bech32_hrp: String,
+        queue_if_locked: bool,
+    ) -> Option<AddressWrapper> {
+        match crate::address::get_iota_address(
+            account,
+            key_index,
+            internal,
+            bech32_hrp,
+            GenerateAddressMetadata {
+                syncing: true,
+                network: account.network(),
+            },
+        )
+        .await
+        {
+            Ok(address) => Some(address),
+            Err(e) => {
+                if queue_if_locked && is_stronghold_locked(&e) {
+                    log::debug!(
+                        "[SYNC] Stronghold is locked, queueing the derivation of the {} address at index {}",
+                        if internal { "internal" } else { "public" },
+                        key_index
+                    );
+                    self.pending_address_derivations
+                        .lock()
+                        .await
+                        .insert((key_index, internal));
+                }
+                None
+            }
+        }
+    }
+
+    /// Derives the addresses queued while Stronghold was locked, emitting an
+    /// [AddressDerivationResumed](../event/struct.AddressDerivationResumed.html) event with the new addresses.
+    /// Derivations that still fail stay queued.
+    pub(crate) async fn resume_address_derivations(&self) -> crate::Result<()> {
+        let pending = std::mem::take(&mut *self.pending_address_derivations.lock().await);
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let mut account = self.write().await;
+        let bech32_hrp = account.bech32_hrp();
+        let mut generated = Vec::new();
+        let mut still_pending = PendingAddressDerivations::new();
+        for (key_index, internal) in pending {
+            if account
+                .addresses()
+                .iter()
+                .any(|a| *a.key_index() == key_index && *a.internal() == internal)
+            {
+                continue;
+            }
+            match crate::address::get_iota_address(
+                &account,
+                key_index,
+                internal,
+                bech32_hrp.clone(),
+                GenerateAddressMetadata {
+                    syncing: true,
+                    network: account.network(),
+                },
+            )
+            .await
+            {
+                Ok(address) => generated.push(Address {
+                    address,
+                    key_index,
+                    internal,
+                    outputs: Default::default(),
+                }),
+                Err(e) => {
+                    log::warn!(
+                        "[SYNC] couldn't resume the address derivation at index {}: {}",
+                        key_index,
+                        e
+                    );
+                    still_pending.insert((key_index, internal));
+                }
+            }
+        }
+        self.pending_address_derivations.lock().await.extend(still_pending);
+
+        if generated.is_empty() {
+            return Ok(());
+        }
+        let addresses: Vec<String> = generated.iter().map(|a| a.address().to_bech32()).collect();
+        account.append_addresses(generated);
+        self.accounts.index_addresses(&account);
+        account.save().await?;
+        let account_id = account.id().clone();
+        drop(account);
+
+        log::debug!(
+            "[SYNC] resumed the derivation of {} addresses of account {}",
+            addresses.len(),
+            account_id
+        );
+        crate::event::emit_address_derivation_resumed(account_id, addresses).await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[tokio::test]
+    async fn resume_address_derivations() {
+        let manager = crate::testing::get_account_manager().await;
+        let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
+        let address_count = account_handle.read().await.addresses().len();
+
+        account_handle
+            .pending_address_derivations
+            .lock()
+            .await
+            .extend(vec![(0, false), (address_count + 5, false)]);
+        account_handle.resume_address_derivations().await.unwrap();
+
+        let account = account_handle.read().await;
+        assert!(account_handle.pending_address_derivations.lock().await.is_empty());
+        assert_eq!(account.addresses().len(), address_count + 1);
+        assert!(account
+            .addresses()
+            .iter()
+            .any(|a| *a.key_index() == address_count + 5 && !a.internal()));
+    }
+}
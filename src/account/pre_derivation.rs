@@ -0,0 +1,133 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{pending_addresses::is_stronghold_locked, Account, AccountHandle};
+use crate::address::{AddressWrapper, GenerateAddressMetadata};
+
+use serde::{Deserialize, Serialize};
+
+/// An address derived ahead of its use, so the syncs and the change address generation don't need the signer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct PreDerivedAddress {
+    #[serde(rename = "keyIndex")]
+    pub(crate) key_index: usize,
+    pub(crate) internal: bool,
+    #[serde(with = "crate::serde::iota_address_serde")]
+    pub(crate) address: AddressWrapper,
+}
+
+impl Account {
+    /// Gets the pre-derived address at the key index, if any.
+    pub(crate) fn pre_derived_address(&self, key_index: usize, internal: bool) -> Option<&AddressWrapper> {
+        self.pre_derived_addresses()
+            .iter()
+            .find(|a| a.key_index == key_index && a.internal == internal)
+            .map(|a| &a.address)
+    }
+
+    /// The key index following the last address of the chain.
+    fn next_key_index(&self, internal: bool) -> usize {
+        self.addresses
+            .iter()
+            .filter(|a| *a.internal() == internal)
+            .map(|a| a.key_index() + 1)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+impl AccountHandle {
+    /// Derives and persists the next `buffer` public and internal addresses of the account, dropping the pre-derived
+    /// addresses the account now uses. Stops without error when the signer is locked, keeping what it derived.
+    pub(crate) async fn pre_derive_addresses(&self, buffer: usize) -> crate::Result<()> {
+        let (missing, bech32_hrp) = {
+            let account = self.read().await;
+            let index_end = self.account_options.address_index_end(usize::MAX);
+            let mut missing = Vec::new();
+            for internal in [false, true] {
+                let start = account.next_key_index(internal);
+                for key_index in start..(start + buffer).min(index_end) {
+                    if account.pre_derived_address(key_index, internal).is_none() {
+                        missing.push((key_index, internal));
+                    }
+                }
+            }
+            (missing, account.bech32_hrp())
+        };
+
+        let mut derived = Vec::new();
+        for (key_index, internal) in missing {
+            // lock the account per address, so the writers aren't blocked for the whole run
+            let result = {
+                let account = self.read().await;
+                crate::address::get_iota_address(
+                    &account,
+                    key_index,
+                    internal,
+                    bech32_hrp.clone(),
+                    GenerateAddressMetadata {
+                        syncing: true,
+                        network: account.network(),
+                    },
+                )
+                .await
+            };
+            match result {
+                Ok(address) => derived.push(PreDerivedAddress {
+                    key_index,
+                    internal,
+                    address,
+                }),
+                Err(e) => {
+                    if !is_stronghold_locked(&e) {
+                        log::debug!("[ADDRESS] pre-derivation stopped at index {}: {}", key_index, e);
+                    }
+                    break;
+                }
+            }
+        }
+
+        let mut account = self.write().await;
+        // the addresses generated meanwhile moved the buffers forward
+        let starts = [account.next_key_index(false), account.next_key_index(true)];
+        let previous_len = account.pre_derived_addresses.len();
+        account
+            .pre_derived_addresses
+            .retain(|a| a.key_index >= starts[a.internal as usize]);
+        if derived.is_empty() && account.pre_derived_addresses.len() == previous_len {
+            return Ok(());
+        }
+        log::debug!(
+            "[ADDRESS] pre-derived {} addresses of account {}",
+            derived.len(),
+            account.id()
+        );
+        account.pre_derived_addresses.extend(derived);
+        account.save().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[tokio::test]
+    async fn pre_derive_addresses() {
+        let manager = crate::testing::get_account_manager().await;
+        let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
+        account_handle.pre_derive_addresses(3).await.unwrap();
+
+        let account = account_handle.read().await;
+        assert_eq!(account.pre_derived_addresses.len(), 6);
+        let next_index = crate::address::next_public_address_index(&account);
+        let pre_derived = account.pre_derived_address(next_index, false).unwrap().clone();
+        drop(account);
+
+        // the signer derives the pre-derived address
+        let address = account_handle.generate_address().await.unwrap();
+        assert_eq!(address.address(), &pre_derived);
+        // and the next run drops it from the buffer
+        account_handle.pre_derive_addresses(3).await.unwrap();
+        let account = account_handle.read().await;
+        assert!(account.pre_derived_address(next_index, false).is_none());
+        assert_eq!(account.pre_derived_addresses.len(), 6);
+    }
+}
@@ -0,0 +1,179 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{AccountHandle, AccountSynchronizeStep};
+use crate::address::{Address, AddressWrapper};
+
+use getset::Getters;
+use serde::Serialize;
+
+use std::collections::HashMap;
+
+/// Number of address balances requested from the node at once.
+const RECONCILE_CHUNK_SIZE: usize = 100;
+
+/// An address whose local balance doesn't match the node.
+#[derive(Debug, Clone, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct BalanceDiscrepancy {
+    /// The address.
+    #[serde(with = "crate::serde::iota_address_serde")]
+    address: AddressWrapper,
+    /// The address key index.
+    #[serde(rename = "keyIndex")]
+    key_index: usize,
+    /// Whether the address is a change address or not.
+    internal: bool,
+    /// The balance of the local unspent outputs.
+    #[serde(rename = "localBalance")]
+    local_balance: u64,
+    /// The balance reported by the node.
+    #[serde(rename = "nodeBalance")]
+    node_balance: u64,
+    /// Whether the re-sync of the auto-repair mode fixed the discrepancy.
+    repaired: bool,
+}
+
+/// The result of [AccountHandle#reconcile_with_node](struct.AccountHandle.html#method.reconcile_with_node).
+#[derive(Debug, Default, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct ReconciliationReport {
+    /// Number of addresses compared with the node.
+    #[serde(rename = "checkedAddresses")]
+    checked_addresses: usize,
+    /// The addresses whose balance doesn't match.
+    discrepancies: Vec<BalanceDiscrepancy>,
+}
+
+impl ReconciliationReport {
+    /// Whether the local state matched the node or not.
+    pub fn is_consistent(&self) -> bool {
+        self.discrepancies.is_empty()
+    }
+}
+
+fn find_discrepancies(addresses: &[Address], node_balances: &HashMap<AddressWrapper, u64>) -> Vec<BalanceDiscrepancy> {
+    addresses
+        .iter()
+        .filter_map(|address| {
+            let node_balance = *node_balances.get(address.address())?;
+            if node_balance == address.balance() {
+                return None;
+            }
+            Some(BalanceDiscrepancy {
+                address: address.address().clone(),
+                key_index: *address.key_index(),
+                internal: *address.internal(),
+                local_balance: address.balance(),
+                node_balance,
+                repaired: false,
+            })
+        })
+        .collect()
+}
+
+impl AccountHandle {
+    /// Fetches the balance of every account address from the node and compares it with the local outputs, e.g. when
+    /// the wallet balance doesn't match the explorer. With `repair`, only the mismatching addresses are re-synced and
+    /// [repaired](struct.BalanceDiscrepancy.html#method.repaired) tells which discrepancies it fixed.
+    pub async fn reconcile_with_node(&self, repair: bool) -> crate::Result<ReconciliationReport> {
+        let account = self.read().await.clone();
+        let client = crate::client::get_client(account.client_options()).await?;
+
+        let mut node_balances = HashMap::new();
+        for addresses_chunk in account.addresses().chunks(RECONCILE_CHUNK_SIZE) {
+            let mut tasks = Vec::new();
+            for address in addresses_chunk {
+                let client = client.clone();
+                let address = address.address().clone();
+                tasks.push(async move {
+                    let balance = client
+                        .read()
+                        .await
+                        .get_address()
+                        .balance(&address.to_bech32())
+                        .await?
+                        .balance;
+                    crate::Result::Ok((address, balance))
+                });
+            }
+            for (address, balance) in futures::future::try_join_all(tasks).await? {
+                node_balances.insert(address, balance);
+            }
+        }
+
+        let mut discrepancies = find_discrepancies(account.addresses(), &node_balances);
+        for discrepancy in &discrepancies {
+            log::warn!(
+                "[RECONCILE] address {} has a local balance of {} but {} on the node",
+                discrepancy.address.to_bech32(),
+                discrepancy.local_balance,
+                discrepancy.node_balance
+            );
+        }
+
+        if repair && !discrepancies.is_empty() {
+            self.sync()
+                .await
+                .steps(vec![AccountSynchronizeStep::SyncAddresses(Some(
+                    discrepancies.iter().map(|d| d.address.clone()).collect(),
+                ))])
+                .execute()
+                .await?;
+            let account = self.read().await;
+            for discrepancy in discrepancies.iter_mut() {
+                discrepancy.repaired = account
+                    .addresses()
+                    .iter()
+                    .any(|a| a.address() == &discrepancy.address && a.balance() == discrepancy.node_balance);
+            }
+        }
+
+        Ok(ReconciliationReport {
+            checked_addresses: node_balances.len(),
+            discrepancies,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::address::{AddressBuilder, AddressOutput, OutputKind};
+    use iota_client::bee_message::prelude::{MessageId, TransactionId};
+
+    #[test]
+    fn balance_discrepancies() {
+        let address_with_balance = |key_index: usize, amount: u64| {
+            let address = crate::testing::generate_random_iota_address();
+            AddressBuilder::new()
+                .address(address.clone())
+                .key_index(key_index)
+                .outputs(vec![AddressOutput {
+                    transaction_id: TransactionId::new([key_index as u8; 32]),
+                    message_id: MessageId::new([0; 32]),
+                    index: 0,
+                    amount,
+                    is_spent: false,
+                    address,
+                    kind: OutputKind::SignatureLockedSingle,
+                }])
+                .build()
+                .unwrap()
+        };
+        let matching = address_with_balance(0, 1_000_000);
+        let missing_output = address_with_balance(1, 1_000_000);
+        let addresses = vec![matching.clone(), missing_output.clone()];
+        let node_balances = vec![
+            (matching.address().clone(), 1_000_000),
+            (missing_output.address().clone(), 3_000_000),
+        ]
+        .into_iter()
+        .collect();
+
+        let discrepancies = super::find_discrepancies(&addresses, &node_balances);
+        assert_eq!(discrepancies.len(), 1);
+        assert_eq!(discrepancies[0].address(), missing_output.address());
+        assert_eq!(*discrepancies[0].local_balance(), 1_000_000);
+        assert_eq!(*discrepancies[0].node_balance(), 3_000_000);
+    }
+}
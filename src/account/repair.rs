@@ -0,0 +1,234 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{Account, AccountHandle};
+use crate::{
+    address::{Address, AddressWrapper},
+    signing::GenerateAddressMetadata,
+};
+
+use getset::Getters;
+use serde::Serialize;
+
+use std::collections::BTreeMap;
+
+/// An address slot touched by [AccountHandle#repair_address_space](struct.AccountHandle.html#method.repair_address_space).
+#[derive(Debug, Clone, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct RepairedAddress {
+    /// The address.
+    #[serde(with = "crate::serde::iota_address_serde")]
+    address: AddressWrapper,
+    /// The address key index.
+    #[serde(rename = "keyIndex")]
+    key_index: usize,
+    /// Whether the address is a change address or not.
+    internal: bool,
+}
+
+impl From<&Address> for RepairedAddress {
+    fn from(address: &Address) -> Self {
+        Self {
+            address: address.address().clone(),
+            key_index: *address.key_index(),
+            internal: *address.internal(),
+        }
+    }
+}
+
+/// The result of [AccountHandle#repair_address_space](struct.AccountHandle.html#method.repair_address_space).
+#[derive(Debug, Default, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct AddressRepairReport {
+    /// The addresses generated for the missing key indexes.
+    generated: Vec<RepairedAddress>,
+    /// The entries merged into another entry holding the same address on the same key index.
+    merged: Vec<RepairedAddress>,
+    /// The entries removed because their address doesn't match the one derived from their key index.
+    removed: Vec<RepairedAddress>,
+}
+
+impl AddressRepairReport {
+    /// Whether the address space was already consistent or not.
+    pub fn is_empty(&self) -> bool {
+        self.generated.is_empty() && self.merged.is_empty() && self.removed.is_empty()
+    }
+}
+
+async fn derive_address(
+    account: &Account,
+    key_index: usize,
+    internal: bool,
+    bech32_hrp: String,
+) -> crate::Result<AddressWrapper> {
+    crate::address::get_iota_address(
+        account,
+        key_index,
+        internal,
+        bech32_hrp,
+        GenerateAddressMetadata {
+            syncing: true,
+            network: account.network(),
+        },
+    )
+    .await
+}
+
+impl Account {
+    /// Removes duplicated and conflicting address entries and generates the addresses missing in the key index
+    /// ranges of the public and internal chains.
+    /// The repaired address list is built aside and only replaces the account addresses if every derivation succeeds.
+    async fn repair_address_space(&mut self) -> crate::Result<AddressRepairReport> {
+        let mut report = AddressRepairReport::default();
+        let bech32_hrp = match self.addresses.first() {
+            Some(address) => address.address().bech32_hrp().to_string(),
+            None => return Ok(report),
+        };
+
+        // group the entries by key index, merging the entries holding the same address
+        let mut slots: BTreeMap<(bool, usize), Vec<Address>> = BTreeMap::new();
+        for address in self.addresses.iter().cloned() {
+            let slot = slots.entry((address.internal, address.key_index)).or_default();
+            match slot.iter_mut().find(|a| a.address == address.address) {
+                Some(existing) => {
+                    report.merged.push((&address).into());
+                    existing.outputs.extend(address.outputs);
+                }
+                None => slot.push(address),
+            }
+        }
+
+        // every entry must match the derivation of its key index, which also resolves the addresses used on more
+        // than one key index
+        for ((internal, key_index), entries) in slots.iter_mut() {
+            let expected = derive_address(self, *key_index, *internal, bech32_hrp.clone()).await?;
+            let (kept, removed): (Vec<Address>, Vec<Address>) =
+                std::mem::take(entries).into_iter().partition(|a| a.address == expected);
+            report.removed.extend(removed.iter().map(RepairedAddress::from));
+            *entries = kept;
+            if entries.is_empty() {
+                let address = Address {
+                    address: expected,
+                    key_index: *key_index,
+                    internal: *internal,
+                    outputs: Default::default(),
+                };
+                report.generated.push((&address).into());
+                entries.push(address);
+            }
+        }
+
+        // fill the gaps of both chains
+        for internal in [false, true] {
+            let max_index = slots
+                .keys()
+                .filter(|(i, _)| *i == internal)
+                .map(|(_, index)| *index)
+                .max();
+            if let Some(max_index) = max_index {
+                for key_index in 0..max_index {
+                    if slots.contains_key(&(internal, key_index)) {
+                        continue;
+                    }
+                    let address = Address {
+                        address: derive_address(self, key_index, internal, bech32_hrp.clone()).await?,
+                        key_index,
+                        internal,
+                        outputs: Default::default(),
+                    };
+                    log::debug!(
+                        "[REPAIR] generated missing address {} at index {}",
+                        address.address().to_bech32(),
+                        key_index
+                    );
+                    report.generated.push((&address).into());
+                    slots.insert((internal, key_index), vec![address]);
+                }
+            }
+        }
+
+        if !report.is_empty() {
+            self.addresses = slots.into_values().flatten().collect();
+        }
+        Ok(report)
+    }
+}
+
+impl AccountHandle {
+    /// Checks the account addresses for missing key indexes, duplicated entries and addresses that don't match the
+    /// derivation of their key index, fixing them with the account signer.
+    /// The address of every key index is derived again, which takes a while on a Ledger device.
+    ///
+    /// Note that the signer must be available: Stronghold must be unlocked or the Ledger device connected.
+    pub async fn repair_address_space(&self) -> crate::Result<AddressRepairReport> {
+        let mut account = self.inner.write().await;
+        let report = account.repair_address_space().await?;
+        if !report.is_empty() {
+            account.save().await?;
+            self.accounts.unindex_addresses(account.id());
+            self.accounts.index_addresses(&account);
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::derive_address;
+    use crate::{address::AddressBuilder, signing::SignerType};
+
+    #[tokio::test]
+    async fn repair_address_space() {
+        let manager = crate::testing::get_account_manager().await;
+        let account_handle = crate::testing::AccountCreator::new(&manager)
+            .signer_type(SignerType::Custom("".to_string()))
+            .create()
+            .await;
+        {
+            let mut account = account_handle.write().await;
+            let bech32_hrp = account.bech32_hrp();
+            let mut derived = Vec::new();
+            for key_index in 0..5 {
+                derived.push(
+                    derive_address(&account, key_index, false, bech32_hrp.clone())
+                        .await
+                        .unwrap(),
+                );
+            }
+            let build_address = |address, key_index| {
+                AddressBuilder::new()
+                    .key_index(key_index)
+                    .address(address)
+                    .outputs(Vec::new())
+                    .build()
+                    .unwrap()
+            };
+            account.addresses = vec![
+                build_address(derived[0].clone(), 0),
+                build_address(derived[0].clone(), 0),
+                // a single entry that doesn't match the derivation of its key index
+                build_address(crate::testing::generate_random_iota_address(), 2),
+                build_address(derived[4].clone(), 4),
+            ];
+        }
+
+        let report = account_handle.repair_address_space().await.unwrap();
+        assert_eq!(report.merged().len(), 1);
+        assert_eq!(report.removed().len(), 1);
+        assert_eq!(*report.removed()[0].key_index(), 2);
+        assert_eq!(report.generated().len(), 3);
+
+        let mut indexes: Vec<usize> = account_handle
+            .read()
+            .await
+            .addresses()
+            .iter()
+            .map(|a| *a.key_index())
+            .collect();
+        indexes.sort_unstable();
+        assert_eq!(indexes, vec![0, 1, 2, 3, 4]);
+
+        // the address space is consistent now
+        assert!(account_handle.repair_address_space().await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,133 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::AccountHandle;
+use crate::message::{Message, MessagePayload, TransactionEssence, TransactionInput};
+
+use iota_client::{bee_message::MessageId, bee_rest_api::types::dtos::LedgerInclusionStateDto, Client};
+use serde::Serialize;
+
+/// Whether a message can be reposted, as decided by
+/// [AccountHandle#should_retry](struct.AccountHandle.html#method.should_retry).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "type")]
+pub enum RetryAdvice {
+    /// The transaction was included in the ledger, there's nothing to repost.
+    Confirmed,
+    /// One of the transaction inputs was spent by another transaction, a repost would conflict.
+    InputSpent,
+    /// The message is a valid tip waiting for a milestone.
+    Wait,
+    /// The message is a lazy tip and should be promoted.
+    Promote,
+    /// The message is below the max depth and should be reattached.
+    Reattach,
+}
+
+impl RetryAdvice {
+    /// Whether a retry would repost the message.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::Promote | Self::Reattach)
+    }
+}
+
+/// Checks if one of the transaction inputs of the message was spent.
+pub(crate) async fn has_spent_input(client: &Client, message: &Message) -> crate::Result<bool> {
+    if let Some(MessagePayload::Transaction(tx)) = message.payload() {
+        let TransactionEssence::Regular(essence) = tx.essence();
+        for input in essence.inputs() {
+            if let TransactionInput::Utxo(input) = input {
+                match client.get_output(&input.input).await {
+                    Ok(output) => {
+                        if output.is_spent {
+                            return Ok(true);
+                        }
+                    }
+                    // if the node doesn't know about this output, then it got spent already and pruned
+                    Err(iota_client::Error::ResponseError(_, message)) if message.contains("output not found") => {
+                        return Ok(true);
+                    }
+                    Err(err) => return Err(err.into()),
+                }
+            }
+        }
+    }
+    Ok(false)
+}
+
+/// Checks if the transaction of the message was included in the ledger.
+pub(crate) async fn is_included(client: &Client, message: &Message) -> crate::Result<bool> {
+    if let Some(MessagePayload::Transaction(tx_payload)) = message.payload() {
+        return Ok(client
+            .get_included_message(&tx_payload.to_transaction_payload()?.id())
+            .await
+            .is_ok());
+    }
+    Ok(false)
+}
+
+impl AccountHandle {
+    /// Decides whether the message should be reposted, running the node checks of
+    /// [retry](struct.AccountHandle.html#method.retry) without reposting anything.
+    pub async fn should_retry(&self, message_id: &MessageId) -> crate::Result<RetryAdvice> {
+        let account = self.inner.read().await;
+        let message = account
+            .get_message(message_id)
+            .await
+            .ok_or(crate::Error::MessageNotFound)?;
+        let client = crate::client::get_client(account.client_options()).await?;
+        let client = client.read().await;
+
+        if has_spent_input(&client, &message).await? {
+            return Ok(RetryAdvice::InputSpent);
+        }
+        if is_included(&client, &message).await? {
+            return Ok(RetryAdvice::Confirmed);
+        }
+
+        let metadata = client.get_message().metadata(message_id).await?;
+        Ok(tip_advice(
+            metadata.ledger_inclusion_state,
+            metadata.should_promote,
+            metadata.should_reattach,
+        ))
+    }
+}
+
+/// Maps the node tip status of a message to the advice.
+fn tip_advice(
+    ledger_inclusion_state: Option<LedgerInclusionStateDto>,
+    should_promote: Option<bool>,
+    should_reattach: Option<bool>,
+) -> RetryAdvice {
+    match ledger_inclusion_state {
+        Some(LedgerInclusionStateDto::Conflicting) => RetryAdvice::InputSpent,
+        Some(_) => RetryAdvice::Confirmed,
+        None if should_reattach.unwrap_or(false) => RetryAdvice::Reattach,
+        None if should_promote.unwrap_or(false) => RetryAdvice::Promote,
+        None => RetryAdvice::Wait,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{tip_advice, RetryAdvice};
+    use iota_client::bee_rest_api::types::dtos::LedgerInclusionStateDto;
+
+    #[test]
+    fn tip_advice_from_metadata() {
+        assert_eq!(
+            tip_advice(Some(LedgerInclusionStateDto::Included), None, None),
+            RetryAdvice::Confirmed
+        );
+        assert_eq!(
+            tip_advice(Some(LedgerInclusionStateDto::Conflicting), None, None),
+            RetryAdvice::InputSpent
+        );
+        assert_eq!(tip_advice(None, Some(true), Some(true)), RetryAdvice::Reattach);
+        assert_eq!(tip_advice(None, Some(true), Some(false)), RetryAdvice::Promote);
+        assert_eq!(tip_advice(None, Some(false), Some(false)), RetryAdvice::Wait);
+        assert!(!RetryAdvice::Wait.is_retryable());
+        assert!(RetryAdvice::Reattach.is_retryable());
+    }
+}
@@ -0,0 +1,298 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::AccountHandle;
+use crate::address::{AddressOutput, AddressWrapper, IotaAddress, OutputKind};
+
+use getset::{CopyGetters, Getters};
+use iota_client::{
+    bee_message::prelude::{MessageId, TransactionId},
+    bee_rest_api::types::responses::OutputResponse,
+};
+use serde::{Deserialize, Serialize, Serializer};
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+};
+
+/// The only Hornet snapshot version with the Chrysalis output layout.
+const HORNET_SNAPSHOT_VERSION: u8 = 1;
+/// The Hornet snapshot type holding the whole ledger.
+const HORNET_FULL_SNAPSHOT: u8 = 0;
+
+/// The format of a ledger dump.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SnapshotFormat {
+    /// A Hornet full local snapshot (`full_snapshot.bin`). Delta snapshots only hold ledger diffs and are rejected.
+    HornetFull,
+    /// Newline delimited JSON of the node output responses, e.g. exported from a Chronicle permanode.
+    /// Spent outputs are skipped.
+    ChronicleOutputs,
+}
+
+/// The result of [AccountHandle#import_snapshot](struct.AccountHandle.html#method.import_snapshot).
+#[derive(Debug, Clone, Default, Getters, CopyGetters, Serialize)]
+pub struct SnapshotImport {
+    /// The ledger milestone index of a Hornet snapshot.
+    #[serde(rename = "ledgerMilestoneIndex")]
+    #[getset(get_copy = "pub")]
+    ledger_milestone_index: Option<u32>,
+    /// Number of outputs of the dump on the account addresses.
+    #[serde(rename = "foundOutputs")]
+    #[getset(get_copy = "pub")]
+    found_outputs: usize,
+    /// Number of outputs the account didn't have yet.
+    #[serde(rename = "importedOutputs")]
+    #[getset(get_copy = "pub")]
+    imported_outputs: usize,
+    /// The addresses holding imported outputs.
+    #[getset(get = "pub")]
+    #[serde(serialize_with = "serialize_addresses")]
+    addresses: Vec<AddressWrapper>,
+}
+
+fn serialize_addresses<S: Serializer>(addresses: &[AddressWrapper], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(addresses.iter().map(|address| address.to_bech32()))
+}
+
+/// The Ed25519 address bytes the dumps are matched against.
+fn ed25519_bytes(address: &AddressWrapper) -> [u8; 32] {
+    let IotaAddress::Ed25519(address) = address.as_ref();
+    let mut bytes = [0; 32];
+    bytes.copy_from_slice(address.as_ref());
+    bytes
+}
+
+fn invalid(reason: impl Into<String>) -> crate::Error {
+    crate::Error::InvalidSnapshot(reason.into())
+}
+
+fn read_array<R: Read, const N: usize>(reader: &mut R) -> crate::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader
+        .read_exact(&mut bytes)
+        .map_err(|e| invalid(format!("unexpected end of snapshot: {}", e)))?;
+    Ok(bytes)
+}
+
+fn read_u8<R: Read>(reader: &mut R) -> crate::Result<u8> {
+    Ok(read_array::<R, 1>(reader)?[0])
+}
+
+fn read_u16<R: Read>(reader: &mut R) -> crate::Result<u16> {
+    Ok(u16::from_le_bytes(read_array(reader)?))
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> crate::Result<u32> {
+    Ok(u32::from_le_bytes(read_array(reader)?))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> crate::Result<u64> {
+    Ok(u64::from_le_bytes(read_array(reader)?))
+}
+
+/// Reads the unspent outputs of a Hornet full snapshot owned by the given Ed25519 addresses.
+/// Returns the ledger milestone index and the outputs; the snapshot is read up to its last output.
+pub(crate) fn parse_hornet_snapshot<R: Read>(
+    reader: &mut R,
+    addresses: &HashMap<[u8; 32], AddressWrapper>,
+) -> crate::Result<(u32, Vec<AddressOutput>)> {
+    let version = read_u8(reader)?;
+    if version != HORNET_SNAPSHOT_VERSION {
+        return Err(invalid(format!("unsupported snapshot version {}", version)));
+    }
+    if read_u8(reader)? != HORNET_FULL_SNAPSHOT {
+        return Err(invalid("only full snapshots hold the ledger outputs"));
+    }
+    let _timestamp = read_u64(reader)?;
+    let _network_id = read_u64(reader)?;
+    let _sep_milestone_index = read_u32(reader)?;
+    let ledger_milestone_index = read_u32(reader)?;
+    let sep_count = read_u64(reader)?;
+    let output_count = read_u64(reader)?;
+    let _milestone_diff_count = read_u64(reader)?;
+    // treasury output: milestone id and amount
+    let _treasury = read_array::<R, 40>(reader)?;
+    for _ in 0..sep_count {
+        read_array::<R, 32>(reader)?;
+    }
+
+    let mut outputs = Vec::new();
+    for _ in 0..output_count {
+        let message_id = MessageId::new(read_array(reader)?);
+        let transaction_id = TransactionId::new(read_array(reader)?);
+        let index = read_u16(reader)?;
+        let kind = match read_u8(reader)? {
+            0 => OutputKind::SignatureLockedSingle,
+            1 => OutputKind::SignatureLockedDustAllowance,
+            kind => return Err(invalid(format!("unknown output type {}", kind))),
+        };
+        let address_type = read_u8(reader)?;
+        if address_type != 0 {
+            return Err(invalid(format!("unknown address type {}", address_type)));
+        }
+        let address: [u8; 32] = read_array(reader)?;
+        let amount = read_u64(reader)?;
+        if let Some(address) = addresses.get(&address) {
+            outputs.push(AddressOutput {
+                transaction_id,
+                message_id,
+                index,
+                amount,
+                is_spent: false,
+                address: address.clone(),
+                kind,
+            });
+        }
+    }
+    Ok((ledger_milestone_index, outputs))
+}
+
+/// Reads the unspent outputs of a newline delimited output responses dump owned by the given addresses.
+pub(crate) fn parse_output_responses<R: BufRead>(
+    reader: R,
+    addresses: &HashMap<[u8; 32], AddressWrapper>,
+    bech32_hrp: &str,
+) -> crate::Result<Vec<AddressOutput>> {
+    let mut outputs = Vec::new();
+    for (line_number, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response: OutputResponse = serde_json::from_str(&line)
+            .map_err(|e| invalid(format!("invalid output on line {}: {}", line_number + 1, e)))?;
+        if response.is_spent {
+            continue;
+        }
+        let output = AddressOutput::from_output_response(response, bech32_hrp.to_string())?;
+        if let Some(address) = addresses.get(&ed25519_bytes(&output.address)) {
+            outputs.push(AddressOutput {
+                address: address.clone(),
+                ..output
+            });
+        }
+    }
+    Ok(outputs)
+}
+
+impl AccountHandle {
+    /// Bootstraps the output set of the account addresses from a ledger dump, so the first sync against a fresh node
+    /// doesn't request every output. Only the addresses the account already has are looked up and the outputs it
+    /// already knows are kept; the messages that created the outputs aren't imported.
+    pub async fn import_snapshot<P: AsRef<Path>>(
+        &self,
+        path: P,
+        format: SnapshotFormat,
+    ) -> crate::Result<SnapshotImport> {
+        let (addresses, bech32_hrp) = {
+            let account = self.inner.read().await;
+            let addresses: HashMap<[u8; 32], AddressWrapper> = account
+                .addresses()
+                .iter()
+                .map(|address| (ed25519_bytes(address.address()), address.address().clone()))
+                .collect();
+            (addresses, account.bech32_hrp())
+        };
+
+        let path: PathBuf = path.as_ref().to_path_buf();
+        let (ledger_milestone_index, outputs) =
+            tokio::task::spawn_blocking(move || -> crate::Result<(Option<u32>, Vec<AddressOutput>)> {
+                let mut reader = BufReader::new(File::open(path)?);
+                match format {
+                    SnapshotFormat::HornetFull => {
+                        let (index, outputs) = parse_hornet_snapshot(&mut reader, &addresses)?;
+                        Ok((Some(index), outputs))
+                    }
+                    SnapshotFormat::ChronicleOutputs => {
+                        Ok((None, parse_output_responses(reader, &addresses, &bech32_hrp)?))
+                    }
+                }
+            })
+            .await??;
+
+        let mut report = SnapshotImport {
+            ledger_milestone_index,
+            found_outputs: outputs.len(),
+            ..Default::default()
+        };
+        let mut account = self.inner.write().await;
+        for output in outputs {
+            let address = match account.addresses.iter_mut().find(|a| a.address() == &output.address) {
+                Some(address) => address,
+                None => continue,
+            };
+            let output_id = output.id()?;
+            if address.outputs.contains_key(&output_id) {
+                continue;
+            }
+            if !report.addresses.contains(&output.address) {
+                report.addresses.push(output.address.clone());
+            }
+            address.outputs.insert(output_id, output);
+            report.imported_outputs += 1;
+        }
+        if report.imported_outputs > 0 {
+            account.save().await?;
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ed25519_bytes, parse_hornet_snapshot};
+
+    use std::collections::HashMap;
+
+    fn write_output(snapshot: &mut Vec<u8>, address: &[u8; 32], amount: u64) {
+        snapshot.extend_from_slice(&rand::random::<[u8; 32]>()); // message id
+        snapshot.extend_from_slice(&rand::random::<[u8; 32]>()); // transaction id
+        snapshot.extend_from_slice(&1u16.to_le_bytes()); // output index
+        snapshot.push(0); // signature locked single
+        snapshot.push(0); // ed25519
+        snapshot.extend_from_slice(address);
+        snapshot.extend_from_slice(&amount.to_le_bytes());
+    }
+
+    #[test]
+    fn hornet_snapshot() {
+        let address = crate::testing::generate_random_iota_address();
+        let address_bytes = ed25519_bytes(&address);
+        let mut addresses = HashMap::new();
+        addresses.insert(address_bytes, address.clone());
+
+        let mut snapshot = vec![1, 0];
+        snapshot.extend_from_slice(&0u64.to_le_bytes()); // timestamp
+        snapshot.extend_from_slice(&0u64.to_le_bytes()); // network id
+        snapshot.extend_from_slice(&10u32.to_le_bytes()); // SEP milestone index
+        snapshot.extend_from_slice(&12u32.to_le_bytes()); // ledger milestone index
+        snapshot.extend_from_slice(&1u64.to_le_bytes()); // SEP count
+        snapshot.extend_from_slice(&2u64.to_le_bytes()); // output count
+        snapshot.extend_from_slice(&0u64.to_le_bytes()); // milestone diff count
+        snapshot.extend_from_slice(&[0; 40]); // treasury output
+        snapshot.extend_from_slice(&[0; 32]); // SEP
+        write_output(&mut snapshot, &rand::random::<[u8; 32]>(), 5_000_000);
+        write_output(&mut snapshot, &address_bytes, 1_000_000);
+
+        let (ledger_index, outputs) = parse_hornet_snapshot(&mut snapshot.as_slice(), &addresses).unwrap();
+        assert_eq!(ledger_index, 12);
+        assert_eq!(outputs.len(), 1);
+        assert_eq!(outputs[0].address, address);
+        assert_eq!(outputs[0].amount, 1_000_000);
+        assert_eq!(outputs[0].index, 1);
+
+        // a truncated snapshot fails instead of importing a partial ledger
+        let truncated = &snapshot[..snapshot.len() - 4];
+        assert!(matches!(
+            parse_hornet_snapshot(&mut &truncated[..], &addresses),
+            Err(crate::Error::InvalidSnapshot(_))
+        ));
+        // delta snapshots are rejected
+        snapshot[1] = 1;
+        assert!(parse_hornet_snapshot(&mut snapshot.as_slice(), &addresses).is_err());
+    }
+}
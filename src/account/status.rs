@@ -0,0 +1,145 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::AccountHandle;
+use crate::message::Message;
+
+use chrono::prelude::{DateTime, Utc};
+use iota_client::{bee_message::MessageId, bee_rest_api::types::dtos::LedgerInclusionStateDto};
+use serde::Serialize;
+
+/// The state of a transfer, for UIs needing more than the `confirmed` flag.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", content = "data")]
+pub enum TransactionStatus {
+    /// The message was broadcasted and waits for a milestone.
+    Pending {
+        /// The message timestamp.
+        since: DateTime<Utc>,
+        /// Number of reattachments of the message.
+        retries: usize,
+    },
+    /// The message (or one of its reattachments) was referenced by a milestone.
+    Confirmed {
+        /// The referencing milestone, known after a node check.
+        milestone: Option<u32>,
+    },
+    /// The transaction conflicts with the ledger, e.g. an input was spent by another transaction.
+    Conflicting {
+        /// The conflict description.
+        reason: String,
+    },
+    /// The message isn't stored or was never broadcasted.
+    Unknown,
+}
+
+/// Describes the conflict reasons of the node message metadata.
+fn conflict_reason(reason: Option<u8>) -> String {
+    match reason {
+        Some(1) => "input already spent",
+        Some(2) => "input already spent in the same milestone",
+        Some(3) => "input not found",
+        Some(4) => "input and output sums don't match",
+        Some(5) => "invalid signature",
+        Some(6) => "invalid dust allowance",
+        Some(255) => "semantic validation failed",
+        _ => "conflicting transaction",
+    }
+    .to_string()
+}
+
+fn stored_status(message: &Message) -> TransactionStatus {
+    match message.confirmed() {
+        Some(true) => TransactionStatus::Confirmed { milestone: None },
+        Some(false) => TransactionStatus::Conflicting {
+            reason: conflict_reason(None),
+        },
+        None if !*message.broadcasted() => TransactionStatus::Unknown,
+        None => TransactionStatus::Pending {
+            since: *message.timestamp(),
+            retries: message.reattachments().len(),
+        },
+    }
+}
+
+impl AccountHandle {
+    /// Gets the status of a message from the stored state.
+    /// With `check_node`, the node metadata of the message and its reattachments is queried; unreachable nodes fall
+    /// back to the stored state.
+    pub async fn transaction_status(
+        &self,
+        message_id: &MessageId,
+        check_node: bool,
+    ) -> crate::Result<TransactionStatus> {
+        let account = self.inner.read().await;
+        let message = match account.get_message(message_id).await {
+            Some(message) => message,
+            None => return Ok(TransactionStatus::Unknown),
+        };
+        let status = stored_status(&message);
+        if !check_node || !*message.broadcasted() {
+            return Ok(status);
+        }
+
+        let client = crate::client::get_client(account.client_options()).await?;
+        let client = client.read().await;
+        let mut conflict = None;
+        // the reattachments carry the same transaction, only one of them can be included
+        for id in std::iter::once(message.id()).chain(message.reattachments().iter()) {
+            let metadata = match client.get_message().metadata(id).await {
+                Ok(metadata) => metadata,
+                Err(_) => continue,
+            };
+            match metadata.ledger_inclusion_state {
+                Some(LedgerInclusionStateDto::Conflicting) => {
+                    conflict.replace(conflict_reason(metadata.conflict_reason));
+                }
+                Some(_) => {
+                    return Ok(TransactionStatus::Confirmed {
+                        milestone: metadata.referenced_by_milestone_index,
+                    });
+                }
+                None => {}
+            }
+        }
+        Ok(match conflict {
+            Some(reason) => TransactionStatus::Conflicting { reason },
+            None => status,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{stored_status, TransactionStatus};
+
+    #[tokio::test]
+    async fn transaction_status() {
+        let address = crate::testing::generate_random_address();
+        let mut message = crate::testing::GenerateMessageBuilder::default()
+            .address(address)
+            .confirmed(None)
+            .broadcasted(true)
+            .build()
+            .await;
+        message.reattachments = vec![*message.id()];
+        assert_eq!(
+            stored_status(&message),
+            TransactionStatus::Pending {
+                since: *message.timestamp(),
+                retries: 1
+            }
+        );
+
+        message.set_confirmed(Some(true));
+        assert_eq!(
+            stored_status(&message),
+            TransactionStatus::Confirmed { milestone: None }
+        );
+        message.set_confirmed(Some(false));
+        assert!(matches!(stored_status(&message), TransactionStatus::Conflicting { .. }));
+        message.set_confirmed(None);
+        message.set_broadcasted(false);
+        assert_eq!(stored_status(&message), TransactionStatus::Unknown);
+    }
+}
@@ -0,0 +1,221 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::RequestBudget;
+use crate::client::{log_request, ClientOptions};
+
+use iota_client::{bee_message::prelude::OutputId, bee_rest_api::types::responses::OutputResponse, Client};
+
+use std::collections::HashMap;
+
+#[cfg(feature = "batch-outputs")]
+use super::retry::parse_retry_after;
+#[cfg(feature = "batch-outputs")]
+use iota_client::bee_message::prelude::TransactionId;
+#[cfg(feature = "batch-outputs")]
+use once_cell::sync::Lazy;
+#[cfg(feature = "batch-outputs")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "batch-outputs")]
+use tokio::sync::Mutex;
+
+#[cfg(feature = "batch-outputs")]
+use std::{
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+/// The node feature advertising the batch outputs endpoint.
+#[cfg(feature = "batch-outputs")]
+const BATCH_OUTPUTS_FEATURE: &str = "outputs-batch";
+/// The batch outputs endpoint. It isn't part of the Chrysalis node API: only nodes advertising the
+/// `outputs-batch` feature, through a plugin, serve it.
+#[cfg(feature = "batch-outputs")]
+const BATCH_OUTPUTS_PATH: &str = "/api/v1/outputs";
+/// Maximum number of outputs requested at once with the batch endpoint.
+#[cfg(feature = "batch-outputs")]
+const BATCH_OUTPUTS_CHUNK_SIZE: usize = 100;
+/// How long the batch endpoint support of a node is cached before its info is requested again.
+#[cfg(feature = "batch-outputs")]
+const BATCH_SUPPORT_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// The result of an output request, keeping the node error so the caller can handle pruned outputs.
+pub(crate) type OutputResult = std::result::Result<OutputResponse, iota_client::Error>;
+
+/// Whether each node (by url) supports the batch outputs endpoint, and when it was checked.
+#[cfg(feature = "batch-outputs")]
+fn batch_support() -> &'static Mutex<HashMap<String, (bool, Instant)>> {
+    static BATCH_SUPPORT: Lazy<Mutex<HashMap<String, (bool, Instant)>>> = Lazy::new(Default::default);
+    &BATCH_SUPPORT
+}
+
+#[cfg(feature = "batch-outputs")]
+async fn supports_batch_outputs(client: &Client, node_url: &str, budget: &RequestBudget) -> bool {
+    if let Some((supported, checked)) = batch_support().lock().await.get(node_url) {
+        if checked.elapsed() < BATCH_SUPPORT_TTL {
+            return *supported;
+        }
+    }
+    if !budget.spend() {
+        return false;
+    }
+    let supported = match client.get_info().await {
+        Ok(info) => info
+            .nodeinfo
+            .features
+            .iter()
+            .any(|feature| feature.eq_ignore_ascii_case(BATCH_OUTPUTS_FEATURE)),
+        Err(_) => return false,
+    };
+    batch_support()
+        .lock()
+        .await
+        .insert(node_url.to_string(), (supported, Instant::now()));
+    supported
+}
+
+/// Forgets the batch endpoint support of the node, so its info is requested again on the next sync.
+#[cfg(feature = "batch-outputs")]
+async fn invalidate_batch_support(node_url: &str) {
+    batch_support().lock().await.remove(node_url);
+}
+
+/// POST /api/v1/outputs : Returns the outputs with the given ids the node knows.
+/// The request goes through the proxy and timeout of the client options, with the node credentials.
+#[cfg(feature = "batch-outputs")]
+async fn post_outputs_batch(
+    http_client: &reqwest::Client,
+    mut node: iota_client::node_manager::Node,
+    output_ids: &[OutputId],
+) -> crate::Result<Vec<OutputResponse>> {
+    node.url.set_path(BATCH_OUTPUTS_PATH);
+
+    #[derive(Debug, Serialize)]
+    struct Request {
+        #[serde(rename = "outputIds")]
+        output_ids: Vec<String>,
+    }
+    #[derive(Debug, Deserialize)]
+    struct ResponseData {
+        outputs: Vec<OutputResponse>,
+    }
+    #[derive(Debug, Deserialize)]
+    struct ResponseWrapper {
+        data: ResponseData,
+    }
+    let mut request = http_client.post(node.url).json(&Request {
+        output_ids: output_ids.iter().map(|id| id.to_string()).collect(),
+    });
+    if let Some(jwt) = &node.jwt {
+        request = request.bearer_auth(jwt);
+    }
+    let res = request.send().await?;
+    let status_code = res.status().as_u16();
+    let retry_after = parse_retry_after(res.headers());
+    let res_text = res.text().await?;
+    match status_code {
+        200 => {
+            let resp: ResponseWrapper = serde_json::from_str(&res_text)?;
+            Ok(resp.data.outputs)
+        }
+        429 | 503 => Err(crate::Error::NodeOverloaded(status_code, retry_after)),
+        _ => Err(iota_client::Error::ResponseError(status_code, res_text).into()),
+    }
+}
+
+/// Requests the outputs with the batch endpoint, if the node supports it.
+/// Returns the outputs found and the ids that must be requested one by one.
+#[cfg(feature = "batch-outputs")]
+async fn get_outputs_batched(
+    client: &Client,
+    client_options: &ClientOptions,
+    output_ids: &[OutputId],
+    budget: &RequestBudget,
+) -> crate::Result<(HashMap<OutputId, OutputResult>, Vec<OutputId>)> {
+    let mut outputs = HashMap::new();
+    let node = client.get_node().await?;
+    if output_ids.len() <= 1 || !supports_batch_outputs(client, node.url.as_str(), budget).await {
+        return Ok((outputs, output_ids.to_vec()));
+    }
+
+    let http_client = crate::client::http_client(client_options)?;
+    let mut pending = Vec::new();
+    for chunk in output_ids.chunks(BATCH_OUTPUTS_CHUNK_SIZE) {
+        if !budget.spend() {
+            pending.extend_from_slice(chunk);
+            continue;
+        }
+        match budget
+            .retry(|| {
+                log_request(
+                    "POST",
+                    BATCH_OUTPUTS_PATH.to_string(),
+                    None,
+                    post_outputs_batch(&http_client, node.clone(), chunk),
+                )
+            })
+            .await
+        {
+            Ok(responses) => {
+                for response in responses {
+                    let output_id = OutputId::new(
+                        TransactionId::from_str(&response.transaction_id)?,
+                        response.output_index,
+                    )?;
+                    outputs.insert(output_id, Ok(response));
+                }
+                pending.extend(chunk.iter().filter(|id| !outputs.contains_key(*id)));
+            }
+            Err(e) => {
+                log::debug!("[SYNC] batch outputs request failed, requesting them one by one: {}", e);
+                if !matches!(e, crate::Error::NodeOverloaded(..)) {
+                    // the node may have dropped the endpoint
+                    invalidate_batch_support(node.url.as_str()).await;
+                }
+                pending.extend_from_slice(chunk);
+            }
+        }
+    }
+    Ok((outputs, pending))
+}
+
+/// Gets the outputs with the given ids, requesting them in parallel.
+/// With the `batch-outputs` feature, the outputs are requested in batches if the node supports it; outputs missing
+/// from a batch response and every output of a failed batch are then requested one by one,
+/// so pruned outputs still get their 404 response.
+/// The outputs that couldn't be requested because the budget ran out are missing from the result.
+#[cfg_attr(not(feature = "batch-outputs"), allow(unused_variables))]
+pub(crate) async fn get_outputs(
+    client: &Client,
+    client_options: &ClientOptions,
+    output_ids: &[OutputId],
+    budget: &RequestBudget,
+) -> crate::Result<HashMap<OutputId, OutputResult>> {
+    if output_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    #[cfg(feature = "batch-outputs")]
+    let (mut outputs, pending) = get_outputs_batched(client, client_options, output_ids, budget).await?;
+    #[cfg(not(feature = "batch-outputs"))]
+    let (mut outputs, pending) = (HashMap::new(), output_ids.to_vec());
+
+    let mut tasks = Vec::new();
+    for output_id in pending {
+        if !budget.spend() {
+            break;
+        }
+        tasks.push(async move {
+            let output = budget
+                .retry(|| async move {
+                    let endpoint = format!("/api/v1/outputs/{}", output_id);
+                    log_request("GET", endpoint, None, client.get_output(&output_id.into())).await
+                })
+                .await;
+            (output_id, output)
+        });
+    }
+    outputs.extend(futures::future::join_all(tasks).await);
+
+    Ok(outputs)
+}
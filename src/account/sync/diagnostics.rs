@@ -0,0 +1,150 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::SyncedAccount;
+use crate::{
+    account::AccountBalance,
+    address::{AddressOutput, AddressWrapper, OutputKind},
+    message::MessageType,
+};
+
+use getset::Getters;
+use serde::Serialize;
+
+/// An output considered by a failed transfer.
+#[derive(Debug, Clone, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct OutputDiagnostic {
+    /// The output identifier.
+    #[serde(rename = "outputId")]
+    output_id: String,
+    /// The output address.
+    #[serde(with = "crate::serde::iota_address_serde")]
+    address: AddressWrapper,
+    /// The output amount.
+    amount: u64,
+    /// The output kind.
+    kind: OutputKind,
+}
+
+impl OutputDiagnostic {
+    fn new(output: &AddressOutput) -> crate::Result<Self> {
+        Ok(Self {
+            output_id: output.id()?.to_string(),
+            address: output.address.clone(),
+            amount: output.amount,
+            kind: output.kind.clone(),
+        })
+    }
+}
+
+/// The dust protection state of an address, as if the transfer was confirmed.
+#[derive(Debug, Clone, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct DustAnalysis {
+    /// The bech32 address.
+    pub(crate) address: String,
+    /// Whether the node allows new dust outputs on the address.
+    #[serde(rename = "dustAllowed")]
+    pub(crate) dust_allowed: bool,
+    /// The balance of the dust allowance outputs of the address.
+    #[serde(rename = "dustAllowanceBalance")]
+    pub(crate) dust_allowance_balance: i64,
+    /// The number of dust outputs on the address.
+    #[serde(rename = "dustOutputs")]
+    pub(crate) dust_outputs: i64,
+    /// The number of dust outputs the dust allowance balance allows.
+    #[serde(rename = "allowedDustOutputs")]
+    pub(crate) allowed_dust_outputs: i64,
+}
+
+/// The account state a failed transfer was evaluated against.
+#[derive(Debug, Default, Clone, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct TransferDiagnostics {
+    /// The account balance breakdown.
+    balance: Option<AccountBalance>,
+    /// The outputs the input selection could use.
+    #[serde(rename = "availableOutputs")]
+    available_outputs: Vec<OutputDiagnostic>,
+    /// The outputs reserved by transfers in progress.
+    #[serde(rename = "lockedOutputs")]
+    locked_outputs: Vec<OutputDiagnostic>,
+    /// The unspent outputs already used as input by a sent message.
+    #[serde(rename = "pendingOutputs")]
+    pending_outputs: Vec<OutputDiagnostic>,
+    /// The dust protection state of the address that rejected the transfer, if any.
+    #[serde(rename = "dustAnalysis")]
+    dust_analysis: Option<DustAnalysis>,
+}
+
+impl SyncedAccount {
+    /// Records the account balance and outputs when a transfer fails on input selection or dust protection,
+    /// so they can be read with
+    /// [AccountHandle#last_transfer_diagnostics](../struct.AccountHandle.html#method.last_transfer_diagnostics).
+    /// The error is returned unchanged. Must be called after the transfer released the locked outputs.
+    pub(super) async fn with_transfer_diagnostics<T>(&self, result: crate::Result<T>) -> crate::Result<T> {
+        let mut last_diagnostics = self.account_handle.last_transfer_diagnostics.lock().await;
+        // the dust analysis is recorded by the transfer itself
+        let dust_analysis = last_diagnostics
+            .take()
+            .and_then(|diagnostics| diagnostics.dust_analysis);
+        let error = match result {
+            Ok(value) => return Ok(value),
+            Err(e)
+                if matches!(
+                    e,
+                    crate::Error::InsufficientFunds(..)
+                        | crate::Error::DustError(_)
+                        | crate::Error::LeavingDustError(_)
+                ) =>
+            {
+                e
+            }
+            Err(e) => return Err(e),
+        };
+
+        let account = self.account_handle.read().await;
+        let sent_messages = account.list_messages(0, 0, Some(MessageType::Sent)).await?;
+        let locked_outputs = self.account_handle.locked_outputs.lock().await;
+        let mut diagnostics = TransferDiagnostics {
+            balance: Some(account.balance_internal(&sent_messages).await),
+            dust_analysis,
+            ..Default::default()
+        };
+        for address in account.addresses() {
+            let available_outputs = address.available_outputs(&sent_messages);
+            for output in address.outputs().values().filter(|output| !output.is_spent) {
+                let diagnostic = OutputDiagnostic::new(output)?;
+                if locked_outputs.is_locked(output) {
+                    diagnostics.locked_outputs.push(diagnostic);
+                } else if available_outputs.contains(&output) {
+                    diagnostics.available_outputs.push(diagnostic);
+                } else {
+                    diagnostics.pending_outputs.push(diagnostic);
+                }
+            }
+        }
+
+        log::warn!(
+            "[TRANSFER] {}, diagnostics: {}",
+            error,
+            serde_json::to_string(&diagnostics).unwrap_or_default()
+        );
+        last_diagnostics.replace(diagnostics);
+        Err(error)
+    }
+}
+
+impl super::AccountHandle {
+    /// Records the dust protection state of the address that rejected the running transfer.
+    pub(super) async fn record_dust_analysis(&self, dust_analysis: DustAnalysis) {
+        self.last_transfer_diagnostics
+            .lock()
+            .await
+            .replace(TransferDiagnostics {
+                dust_analysis: Some(dust_analysis),
+                ..Default::default()
+            });
+    }
+}
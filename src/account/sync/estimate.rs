@@ -0,0 +1,241 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{input_selection, SyncedAccount};
+use crate::{
+    address::{AddressOutput, OutputKind},
+    message::{MessageType, Transfer},
+};
+
+use getset::Getters;
+use iota_client::{
+    bee_message::{
+        prelude::{
+            Ed25519Signature, Essence, Input, Output, Payload, RegularEssence, SignatureLockedDustAllowanceOutput,
+            SignatureLockedSingleOutput, SignatureUnlock, UtxoInput,
+        },
+        unlock::{ReferenceUnlock, UnlockBlock},
+    },
+    common::packable::Packable,
+};
+use serde::Serialize;
+
+use std::collections::HashSet;
+
+/// Maximum number of parents of a message.
+const MAX_MESSAGE_PARENTS: usize = 8;
+/// Size of the message fields around the payload: network id, parents count, payload length and nonce.
+const MESSAGE_OVERHEAD_SIZE: usize = 8 + 1 + 4 + 8;
+/// Size of a message parent.
+const MESSAGE_PARENT_SIZE: usize = 32;
+
+/// The projected costs of a transfer,
+/// the result of [AccountHandle#estimate_transaction](struct.AccountHandle.html#method.estimate_transaction).
+#[derive(Debug, Clone, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct TransactionEstimate {
+    /// Number of inputs the transaction would consume.
+    inputs: usize,
+    /// Number of outputs the transaction would create, including the remainder output.
+    outputs: usize,
+    /// The value sent back to the account.
+    #[serde(rename = "remainderValue")]
+    remainder_value: u64,
+    /// Serialized size of the transaction essence, in bytes.
+    #[serde(rename = "essenceSize")]
+    essence_size: usize,
+    /// Serialized size of the transaction payload, including the unlock blocks, in bytes.
+    #[serde(rename = "payloadSize")]
+    payload_size: usize,
+    /// Serialized size of the message holding the transaction when it has the maximum number of parents, in bytes.
+    #[serde(rename = "messageSize")]
+    message_size: usize,
+    /// Number of created outputs below the dust threshold.
+    #[serde(rename = "dustOutputs")]
+    dust_outputs: usize,
+    /// The dust allowance the receiving addresses need to hold to accept the dust outputs.
+    #[serde(rename = "requiredDustAllowance")]
+    required_dust_allowance: u64,
+    /// The transaction fee. Always 0 on the current protocol, which has no fees.
+    fee: u64,
+}
+
+impl SyncedAccount {
+    /// Selects the inputs and builds the transfer essence like a transfer would, without reserving the inputs or
+    /// generating a remainder address.
+    pub(crate) async fn estimate_transaction(&self, transfer_obj: &Transfer) -> crate::Result<TransactionEstimate> {
+        let account = self.account_handle.read().await;
+        let dust_protection = *account.client_options().dust_protection();
+        let value = transfer_obj.amount();
+        let sent_messages = account.list_messages(0, 0, Some(MessageType::Sent)).await?;
+        let balance = account.balance_internal(&sent_messages).await;
+        if value > balance.total {
+            return Err(crate::Error::InsufficientFunds(balance.total, value));
+        }
+
+        let inputs: Vec<AddressOutput> = match &transfer_obj.input {
+            Some(address_inputs) => address_inputs
+                .iter()
+                .flat_map(|(_, outputs)| outputs.iter().cloned())
+                .collect(),
+            None => {
+                let available_outputs = account
+                    .addresses()
+                    .iter()
+                    .map(|address| input_selection::AddressInputs {
+                        address: address.address().clone(),
+                        internal: *address.internal(),
+                        outputs: address
+                            .available_outputs(&sent_messages)
+                            .iter()
+                            .map(|o| (*o).clone())
+                            .collect(),
+                    })
+                    .collect();
+                let signer_capabilities = crate::signing::signer_capabilities(account.signer_type()).await;
+                let mut locked_outputs = self.account_handle.locked_outputs.lock().await;
+                let locked_outputs_count = locked_outputs.len();
+                let selection = self.select_inputs(
+                    &mut locked_outputs,
+                    &std::sync::Weak::new(),
+                    transfer_obj,
+                    available_outputs,
+                    &signer_capabilities,
+                    dust_protection.allowance_value(),
+                    account.client_options().network_parameters().max_input_output_count(),
+                );
+                // the estimate doesn't reserve the selected outputs
+                locked_outputs.truncate(locked_outputs_count);
+                selection?
+                    .0
+                    .into_iter()
+                    .flat_map(|address_inputs| address_inputs.outputs)
+                    .collect()
+            }
+        };
+        let inputs_amount: u64 = inputs.iter().map(|output| output.amount).sum();
+        let remainder_value = inputs_amount.saturating_sub(value);
+
+        let mut outputs_for_essence: Vec<Output> = Vec::new();
+        for output in transfer_obj.outputs.iter() {
+            match output.output_kind {
+                OutputKind::SignatureLockedSingle => {
+                    outputs_for_essence
+                        .push(SignatureLockedSingleOutput::new(*output.address.as_ref(), output.amount.get())?.into());
+                }
+                OutputKind::SignatureLockedDustAllowance => {
+                    outputs_for_essence.push(
+                        SignatureLockedDustAllowanceOutput::new(*output.address.as_ref(), output.amount.get())?.into(),
+                    );
+                }
+                _ => return Err(crate::error::Error::InvalidOutputKind("Treasury".to_string())),
+            }
+        }
+        if remainder_value > 0 {
+            // every address has the same size, so the remainder target doesn't change the estimate
+            let remainder_address = &inputs.last().expect("inputs can't be empty with a remainder").address;
+            outputs_for_essence
+                .push(SignatureLockedSingleOutput::new(*remainder_address.as_ref(), remainder_value)?.into());
+        }
+
+        let mut inputs_for_essence: Vec<Input> = Vec::new();
+        for output in &inputs {
+            inputs_for_essence.push(UtxoInput::new(*output.transaction_id(), *output.index())?.into());
+        }
+        let outputs_count = outputs_for_essence.len();
+        let mut essence_builder = RegularEssence::builder()
+            .with_inputs(inputs_for_essence)
+            .with_outputs(outputs_for_essence);
+        if let Some(indexation) = &transfer_obj.indexation {
+            essence_builder = essence_builder.with_payload(Payload::Indexation(Box::new(indexation.clone())));
+        }
+        let essence_size = Essence::Regular(essence_builder.finish()?).packed_len();
+
+        // one signature per input address, the other inputs of the address reference it
+        let signatures = inputs
+            .iter()
+            .map(|output| &output.address)
+            .collect::<HashSet<_>>()
+            .len();
+        let signature_size =
+            UnlockBlock::Signature(SignatureUnlock::Ed25519(Ed25519Signature::new([0; 32], [0; 64]))).packed_len();
+        let reference_size = UnlockBlock::Reference(ReferenceUnlock::new(0)?).packed_len();
+        let unlock_blocks_size = 2 + signatures * signature_size + (inputs.len() - signatures) * reference_size;
+        // payload type + essence + unlock blocks
+        let payload_size = 4 + essence_size + unlock_blocks_size;
+
+        let dust_outputs = transfer_obj
+            .outputs
+            .iter()
+            .filter(|output| output.output_kind == OutputKind::SignatureLockedSingle)
+            .map(|output| output.amount.get())
+            .chain(Some(remainder_value).filter(|value| *value > 0))
+            .filter(|amount| *amount < dust_protection.allowance_value())
+            .count();
+
+        Ok(TransactionEstimate {
+            inputs: inputs.len(),
+            outputs: outputs_count,
+            remainder_value,
+            essence_size,
+            payload_size,
+            message_size: MESSAGE_OVERHEAD_SIZE + MAX_MESSAGE_PARENTS * MESSAGE_PARENT_SIZE + payload_size,
+            dust_outputs,
+            required_dust_allowance: dust_outputs as u64 * dust_protection.divisor(),
+            fee: 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        address::{AddressBuilder, AddressOutput, OutputKind},
+        message::Transfer,
+    };
+    use iota_client::bee_message::prelude::{MessageId, TransactionId};
+
+    use std::num::NonZeroU64;
+
+    #[tokio::test]
+    async fn estimate_transaction() {
+        let manager = crate::testing::get_account_manager().await;
+        let address = crate::testing::generate_random_iota_address();
+        let outputs = (0..2u16)
+            .map(|index| AddressOutput {
+                transaction_id: TransactionId::new([index as u8 + 1; 32]),
+                message_id: MessageId::new([0; 32]),
+                index,
+                amount: 2_000_000,
+                is_spent: false,
+                address: address.clone(),
+                kind: OutputKind::SignatureLockedSingle,
+            })
+            .collect();
+        let account_handle = crate::testing::AccountCreator::new(&manager)
+            .addresses(vec![AddressBuilder::new()
+                .key_index(0)
+                .address(address)
+                .outputs(outputs)
+                .build()
+                .unwrap()])
+            .create()
+            .await;
+
+        let transfer = Transfer::builder(
+            crate::testing::generate_random_iota_address(),
+            NonZeroU64::new(3_500_000).unwrap(),
+            None,
+        )
+        .finish()
+        .unwrap();
+        let estimate = account_handle.estimate_transaction(&transfer).await.unwrap();
+        assert_eq!(*estimate.inputs(), 2);
+        assert_eq!(*estimate.outputs(), 2);
+        assert_eq!(*estimate.remainder_value(), 500_000);
+        assert_eq!(*estimate.dust_outputs(), 1);
+        assert!(estimate.payload_size() > estimate.essence_size());
+        // the estimate doesn't lock the inputs
+        assert!(account_handle.locked_outputs.lock().await.is_empty());
+    }
+}
@@ -0,0 +1,97 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{account::Account, address::Address};
+
+/// The highest gap limit the auto-tuning raises the full syncs to.
+const MAX_TUNED_GAP_LIMIT: usize = 100;
+
+/// The longest run of unused address indexes followed by a used address, on either address space.
+fn largest_address_gap(addresses: &[Address]) -> usize {
+    let mut largest_gap = 0;
+    for internal in [false, true] {
+        let mut used_indexes: Vec<usize> = addresses
+            .iter()
+            .filter(|address| *address.internal() == internal && !address.outputs().is_empty())
+            .map(|address| *address.key_index())
+            .collect();
+        used_indexes.sort_unstable();
+        let mut next_index = 0;
+        for index in used_indexes {
+            largest_gap = largest_gap.max(index.saturating_sub(next_index));
+            next_index = index + 1;
+        }
+    }
+    largest_gap
+}
+
+impl Account {
+    /// The gap limit a sync scanning with the given gap limit uses, raised to the tuned gap limit on full syncs.
+    pub(super) fn tuned_gap_limit(&self, gap_limit: usize) -> usize {
+        if gap_limit > 1 {
+            gap_limit.max(self.gap_limit_hint.unwrap_or_default())
+        } else {
+            gap_limit
+        }
+    }
+
+    /// Raises the gap limit of the next full syncs if the account has used addresses after a run of unused
+    /// addresses longer than half the scanned gap limit, since the next used address could be past the scan
+    /// window. Returns whether the tuned gap limit changed.
+    pub(super) fn tune_gap_limit(&mut self, scanned_gap_limit: usize) -> bool {
+        let largest_gap = largest_address_gap(self.addresses());
+        if scanned_gap_limit <= 1 || largest_gap * 2 <= scanned_gap_limit {
+            return false;
+        }
+        let hint = (largest_gap * 2).min(MAX_TUNED_GAP_LIMIT);
+        if self.gap_limit_hint.map_or(false, |current| current >= hint) {
+            return false;
+        }
+        log::debug!("[SYNC] raising the gap limit of the next full syncs to {}", hint);
+        self.gap_limit_hint.replace(hint);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[tokio::test]
+    async fn gap_limit_tuning() {
+        use crate::address::{AddressBuilder, AddressOutput, OutputKind};
+        use iota_client::bee_message::prelude::{MessageId, TransactionId};
+
+        let used_address = |key_index: usize| {
+            let address = crate::testing::generate_random_iota_address();
+            AddressBuilder::new()
+                .address(address.clone())
+                .key_index(key_index)
+                .outputs(vec![AddressOutput {
+                    transaction_id: TransactionId::new([key_index as u8; 32]),
+                    message_id: MessageId::new([0; 32]),
+                    index: 0,
+                    amount: 1_000_000,
+                    is_spent: false,
+                    address,
+                    kind: OutputKind::SignatureLockedSingle,
+                }])
+                .build()
+                .unwrap()
+        };
+        let addresses = vec![used_address(0), used_address(8)];
+        assert_eq!(super::largest_address_gap(&addresses), 7);
+
+        let manager = crate::testing::get_account_manager().await;
+        let account_handle = crate::testing::AccountCreator::new(&manager)
+            .addresses(addresses)
+            .create()
+            .await;
+        let mut account = account_handle.write().await;
+        assert_eq!(account.tuned_gap_limit(10), 10);
+        // incremental syncs don't tune the gap limit
+        assert!(!account.tune_gap_limit(1));
+        assert!(account.tune_gap_limit(10));
+        assert_eq!(account.tuned_gap_limit(10), 14);
+        assert_eq!(account.tuned_gap_limit(1), 1);
+        assert!(!account.tune_gap_limit(14));
+    }
+}
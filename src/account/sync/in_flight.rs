@@ -0,0 +1,104 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Weak,
+    },
+    time::{Duration, Instant},
+};
+
+/// How often the shutdown checks whether the transfers finished.
+const IN_FLIGHT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The transfers of an account manager between their input selection and the storage of their message.
+#[derive(Debug, Default)]
+pub(crate) struct InFlightTransfers {
+    count: AtomicUsize,
+    /// Set when the manager starts shutting down; no transfer can start afterwards.
+    shutting_down: AtomicBool,
+}
+
+impl InFlightTransfers {
+    /// Refuses the new transfers, then waits until no transfer is in flight, returning `false` if some are still
+    /// running after the timeout.
+    pub(crate) async fn shutdown(&self, timeout: Duration) -> bool {
+        self.shutting_down.store(true, Ordering::SeqCst);
+        let deadline = Instant::now() + timeout;
+        loop {
+            let in_flight = self.count.load(Ordering::SeqCst);
+            if in_flight == 0 {
+                return true;
+            }
+            if Instant::now() >= deadline {
+                log::warn!("[TRANSFER] {} transfers still in flight after {:?}", in_flight, timeout);
+                return false;
+            }
+            tokio::time::sleep(IN_FLIGHT_POLL_INTERVAL).await;
+        }
+    }
+}
+
+/// Marks a transfer as in flight until dropped.
+pub(crate) struct InFlightTransfer {
+    transfers: Arc<InFlightTransfers>,
+    /// Owns the output locks of the transfer, which aren't released as stale while it's alive.
+    lock_owner: Arc<()>,
+}
+
+impl InFlightTransfer {
+    /// Fails with [ShuttingDown](../../enum.Error.html#variant.ShuttingDown) once the manager started shutting down.
+    pub(crate) fn start(transfers: &Arc<InFlightTransfers>) -> crate::Result<Self> {
+        // counted before the check, so the shutdown either sees the transfer or the transfer sees the shutdown
+        transfers.count.fetch_add(1, Ordering::SeqCst);
+        let transfer = Self {
+            transfers: transfers.clone(),
+            lock_owner: Arc::new(()),
+        };
+        if transfers.shutting_down.load(Ordering::SeqCst) {
+            return Err(crate::Error::ShuttingDown);
+        }
+        Ok(transfer)
+    }
+
+    /// The owner of the outputs locked by the transfer.
+    pub(crate) fn lock_owner(&self) -> Weak<()> {
+        Arc::downgrade(&self.lock_owner)
+    }
+}
+
+impl Drop for InFlightTransfer {
+    fn drop(&mut self) {
+        self.transfers.count.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{InFlightTransfer, InFlightTransfers};
+    use std::{sync::Arc, time::Duration};
+
+    #[tokio::test]
+    async fn in_flight_transfers() {
+        let transfers = Arc::new(InFlightTransfers::default());
+        let other_manager_transfers = Arc::new(InFlightTransfers::default());
+        let transfer = InFlightTransfer::start(&transfers).unwrap();
+        // the transfers of another manager don't delay its shutdown
+        let _other_transfer = InFlightTransfer::start(&other_manager_transfers).unwrap();
+
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            drop(transfer);
+        });
+        assert!(transfers.shutdown(Duration::from_secs(5)).await);
+        // no transfer starts once the shutdown began
+        assert!(matches!(
+            InFlightTransfer::start(&transfers),
+            Err(crate::Error::ShuttingDown)
+        ));
+        assert!(transfers.shutdown(Duration::from_millis(10)).await);
+
+        assert!(!other_manager_transfers.shutdown(Duration::from_millis(10)).await);
+    }
+}
@@ -8,7 +8,6 @@ use std::{
     sync::atomic::{AtomicI64, Ordering as AtomicOrdering},
 };
 
-const DUST_ALLOWANCE_VALUE: u64 = 1_000_000;
 const MAX_INPUT_SELECTION_TRIES: i64 = 10_000_000;
 
 #[derive(Debug, Clone)]
@@ -31,7 +30,12 @@ pub struct Remainder {
     pub amount: u64,
 }
 
-pub fn select_input(target: u64, available_utxos: Vec<Input>, max_inputs: usize) -> crate::Result<Vec<Input>> {
+pub fn select_input(
+    target: u64,
+    available_utxos: Vec<Input>,
+    max_inputs: usize,
+    dust_allowance_value: u64,
+) -> crate::Result<Vec<Input>> {
     let total_available_balance = available_utxos
         .iter()
         .fold(0, |acc, address| acc + address.output.amount);
@@ -40,7 +44,7 @@ pub fn select_input(target: u64, available_utxos: Vec<Input>, max_inputs: usize)
     }
 
     // Not insufficient funds, but still not possible to create this transaction because it would create dust
-    if target != total_available_balance && total_available_balance - target < DUST_ALLOWANCE_VALUE {
+    if target != total_available_balance && total_available_balance - target < dust_allowance_value {
         return Err(crate::Error::LeavingDustError(format!(
             "Transaction would leave dust behind ({}i)",
             total_available_balance - target
@@ -79,7 +83,7 @@ pub fn select_input(target: u64, available_utxos: Vec<Input>, max_inputs: usize)
     if result
         && selected_balance >= target
         && selected_coins.len() <= max_inputs
-        && (remaining_value == 0 || remaining_value > DUST_ALLOWANCE_VALUE)
+        && (remaining_value == 0 || remaining_value > dust_allowance_value)
     {
         Ok(selected_coins)
     } else {
@@ -88,7 +92,12 @@ pub fn select_input(target: u64, available_utxos: Vec<Input>, max_inputs: usize)
         // let mut dust_allowance_outputs_ = dust_allowance_outputs.clone();
         signature_locked_outputs.shuffle(&mut thread_rng());
         dust_allowance_outputs.shuffle(&mut thread_rng());
-        let mut inputs = single_draw(target, signature_locked_outputs.clone(), dust_allowance_outputs.clone());
+        let mut inputs = single_draw(
+            target,
+            signature_locked_outputs.clone(),
+            dust_allowance_outputs.clone(),
+            dust_allowance_value,
+        );
         if inputs.len() > max_inputs {
             // Sort inputs so we can get the biggest inputs first and don't reach the input limit, if we don't have the
             // funds spread over too many outputs
@@ -106,7 +115,12 @@ pub fn select_input(target: u64, available_utxos: Vec<Input>, max_inputs: usize)
             });
             // first time the inputs are shuffled, so if we had many outputs it could happen that we selected more than
             // max_inputs even if it would be possible with <=
-            inputs = single_draw(target, signature_locked_outputs, dust_allowance_outputs);
+            inputs = single_draw(
+                target,
+                signature_locked_outputs,
+                dust_allowance_outputs,
+                dust_allowance_value,
+            );
             if inputs.len() > max_inputs {
                 return Err(crate::Error::ConsolidationRequired(inputs.len(), max_inputs));
             }
@@ -119,6 +133,7 @@ fn single_draw(
     target: u64,
     available_signature_locked_utxos: Vec<Input>,
     available_dust_allowance_utxos: Vec<Input>,
+    dust_allowance_value: u64,
 ) -> Vec<Input> {
     let mut sum = 0;
 
@@ -131,7 +146,7 @@ fn single_draw(
             let value = input.output.amount;
             let old_sum = sum;
             sum += value;
-            old_sum < target || (old_sum - target < DUST_ALLOWANCE_VALUE && old_sum != target)
+            old_sum < target || (old_sum - target < dust_allowance_value && old_sum != target)
         })
         .collect()
 }
@@ -193,6 +208,8 @@ mod tests {
     use iota_client::bee_message::prelude::{MessageId, TransactionId};
     use rand::prelude::{Rng, SeedableRng, SliceRandom, StdRng};
 
+    const DUST_ALLOWANCE_VALUE: u64 = 1_000_000;
+
     fn generate_random_utxos(rng: &mut StdRng, utxos_number: usize) -> Vec<Input> {
         let mut available_utxos = Vec::new();
         for _ in 0..utxos_number {
@@ -204,7 +221,7 @@ mod tests {
                     index: 0,
                     amount: rng.gen_range(0..10_000_000),
                     is_spent: false,
-                    address: crate::test_utils::generate_random_iota_address(),
+                    address: crate::testing::generate_random_iota_address(),
                     kind: OutputKind::SignatureLockedSingle,
                 },
             });
@@ -227,7 +244,7 @@ mod tests {
         for _i in 0..20 {
             let mut available_utxos = generate_random_utxos(&mut rng, 25);
             let sum_utxos_picked = sum_random_utxos(&mut rng, &mut available_utxos);
-            let selected = select_input(sum_utxos_picked, available_utxos, 127).unwrap();
+            let selected = select_input(sum_utxos_picked, available_utxos, 127, DUST_ALLOWANCE_VALUE).unwrap();
             assert_eq!(
                 selected.iter().fold(0, |acc, input| { acc + input.output.amount }),
                 sum_utxos_picked
@@ -244,7 +261,7 @@ mod tests {
             let available_balance = available_utxos.iter().fold(0, |acc, input| acc + input.output.amount);
             let target = available_balance / 2;
             if available_balance - target >= DUST_ALLOWANCE_VALUE {
-                let selected = select_input(target, available_utxos, 127).unwrap();
+                let selected = select_input(target, available_utxos, 127, DUST_ALLOWANCE_VALUE).unwrap();
                 assert!(selected.into_iter().fold(0, |acc, input| acc + input.output.amount) >= target);
             }
         }
@@ -256,7 +273,7 @@ mod tests {
         let mut rng: StdRng = SeedableRng::from_seed(seed);
         let available_utxos = generate_random_utxos(&mut rng, 30);
         let target = available_utxos.iter().fold(0, |acc, input| acc + input.output.amount) + 1;
-        let response = select_input(target, available_utxos, 127);
+        let response = select_input(target, available_utxos, 127, DUST_ALLOWANCE_VALUE);
         assert!(response.is_err());
     }
 
@@ -268,7 +285,7 @@ mod tests {
             let available_utxos = generate_random_utxos(&mut rng, 30);
             let sum_utxos = available_utxos.iter().fold(0, |acc, input| acc + input.output.amount);
             let target = rng.gen_range(sum_utxos / 2..sum_utxos * 2);
-            let response = select_input(target, available_utxos, 127);
+            let response = select_input(target, available_utxos, 127, DUST_ALLOWANCE_VALUE);
             if target > sum_utxos {
                 assert!(response.is_err());
             } else {
@@ -287,7 +304,7 @@ mod tests {
             let available_utxos = generate_random_utxos(&mut rng, 30);
             let sum_utxos = available_utxos.iter().fold(0, |acc, input| acc + input.output.amount);
             let target = rng.gen_range(sum_utxos / 2..sum_utxos * 2);
-            let response = select_input(target, available_utxos, 127);
+            let response = select_input(target, available_utxos, 127, DUST_ALLOWANCE_VALUE);
 
             if target > sum_utxos
                 || (target != sum_utxos && target as i64 > (sum_utxos as i64 - DUST_ALLOWANCE_VALUE as i64))
@@ -301,4 +318,21 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn custom_dust_allowance_value() {
+        let seed: [u8; 32] = [1; 32];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let available_utxos = generate_random_utxos(&mut rng, 30);
+        let sum_utxos = available_utxos.iter().fold(0, |acc, input| acc + input.output.amount);
+        // a remainder of 1000i is dust on the mainnet but not on a network with a lower allowance value
+        let target = sum_utxos - 1000;
+        assert!(matches!(
+            select_input(target, available_utxos.clone(), 127, DUST_ALLOWANCE_VALUE),
+            Err(crate::Error::LeavingDustError(_))
+        ));
+        let selected = select_input(target, available_utxos, 127, 1000).unwrap();
+        let selected_balance = selected.into_iter().fold(0, |acc, input| acc + input.output.amount);
+        assert!(selected_balance == target || selected_balance >= target + 1000);
+    }
 }
@@ -0,0 +1,148 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    account::{Account, AccountHandle},
+    address::Address,
+    message::Message,
+};
+
+use iota_client::bee_message::prelude::{MessageId, OutputId};
+use serde::{Deserialize, Serialize};
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+/// The ids a sync doesn't need to request from the node again, persisted per account so they're looked up in
+/// constant time without scanning the account messages.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub(crate) struct KnownIds {
+    /// The confirmed messages.
+    #[serde(rename = "confirmedMessages")]
+    pub(crate) confirmed_messages: HashSet<MessageId>,
+    /// The spent outputs.
+    #[serde(rename = "spentOutputs")]
+    pub(crate) spent_outputs: HashSet<OutputId>,
+    /// The skip-list of fully spent addresses (bech32), with the milestone index they were first seen fully spent.
+    #[serde(rename = "spentAddresses", default)]
+    pub(crate) spent_addresses: HashMap<String, u32>,
+    /// Number of syncs run, used to schedule the skip-list spot-checks.
+    #[serde(rename = "syncCount", default)]
+    pub(crate) sync_count: u64,
+}
+
+impl KnownIds {
+    /// Adds the confirmed messages and spent outputs, returning whether any id was added.
+    fn extend<'a>(
+        &mut self,
+        confirmed_messages: impl Iterator<Item = MessageId>,
+        addresses: impl Iterator<Item = &'a Address>,
+    ) -> bool {
+        let mut changed = false;
+        for message_id in confirmed_messages {
+            changed |= self.confirmed_messages.insert(message_id);
+        }
+        for address in addresses {
+            for (output_id, _) in address.outputs().iter().filter(|(_, output)| output.is_spent) {
+                changed |= self.spent_outputs.insert(*output_id);
+            }
+        }
+        changed
+    }
+}
+
+impl AccountHandle {
+    /// Gets the known ids of the account, loading them from the storage or building them from the account state
+    /// on first use.
+    pub(crate) async fn known_ids(&self, account: &Account) -> crate::Result<Arc<KnownIds>> {
+        let mut known_ids = self.known_ids.lock().await;
+        if let Some(known_ids) = &*known_ids {
+            return Ok(known_ids.clone());
+        }
+
+        let storage = crate::storage::get(account.storage_path()).await?;
+        let loaded = match storage.lock().await.get_known_ids(account.id()).await? {
+            Some(loaded) => loaded,
+            None => {
+                let mut built = KnownIds::default();
+                account
+                    .with_messages(|messages| {
+                        built.extend(
+                            messages.iter().filter(|m| m.confirmed.unwrap_or(false)).map(|m| m.key),
+                            account.addresses().iter(),
+                        )
+                    })
+                    .await;
+                if !account.skip_persistence() {
+                    storage.lock().await.save_known_ids(account.id(), &built).await?;
+                }
+                built
+            }
+        };
+        let loaded = Arc::new(loaded);
+        known_ids.replace(loaded.clone());
+        Ok(loaded)
+    }
+
+    /// Adds the synced messages and addresses to the known ids, if they're loaded.
+    pub(crate) async fn extend_known_ids(
+        &self,
+        account: &Account,
+        messages: &[Message],
+        addresses: &[Address],
+    ) -> crate::Result<()> {
+        let mut known_ids = self.known_ids.lock().await;
+        if let Some(known_ids) = known_ids.as_mut() {
+            let confirmed_messages = messages
+                .iter()
+                .filter(|message| message.confirmed().unwrap_or(false))
+                .map(|message| *message.id());
+            if Arc::make_mut(known_ids).extend(confirmed_messages, addresses.iter()) && !account.skip_persistence() {
+                crate::storage::get(account.storage_path())
+                    .await?
+                    .lock()
+                    .await
+                    .save_known_ids(account.id(), known_ids.as_ref())
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[tokio::test]
+    async fn known_ids() {
+        let manager = crate::testing::get_account_manager().await;
+        let address = crate::testing::generate_random_address();
+        let message = crate::testing::GenerateMessageBuilder::default()
+            .address(address.clone())
+            .confirmed(Some(true))
+            .build()
+            .await;
+        let account_handle = crate::testing::AccountCreator::new(&manager)
+            .addresses(vec![address])
+            .messages(vec![message.clone()])
+            .create()
+            .await;
+
+        let account = account_handle.read().await.clone();
+        let known_ids = account_handle.known_ids(&account).await.unwrap();
+        assert!(known_ids.confirmed_messages.contains(message.id()));
+
+        // the ids are persisted, so a new handle doesn't need to scan the messages
+        let stored = crate::storage::get(account.storage_path())
+            .await
+            .unwrap()
+            .lock()
+            .await
+            .get_known_ids(account.id())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.confirmed_messages, known_ids.confirmed_messages);
+    }
+}
@@ -0,0 +1,265 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::input_selection;
+use crate::{account::AccountHandle, address::AddressOutput, message::MessageType};
+
+use std::{
+    sync::Weak,
+    time::{Duration, Instant},
+};
+
+/// The identifier of an output reservation.
+pub type ReservationId = [u8; 32];
+
+/// How long an output stays reserved for a transfer by default, once the transfer holding it isn't running anymore.
+/// A lock held longer means the transfer task died without releasing it.
+pub(crate) const DEFAULT_OUTPUT_LOCK_LEASE: Duration = Duration::from_secs(10 * 60);
+
+/// An output reserved by an ongoing transfer.
+#[derive(Debug, Clone)]
+pub(crate) struct LockedOutput {
+    pub(crate) output: AddressOutput,
+    pub(crate) locked_at: Instant,
+    /// The in-flight transfer holding the lock; the lock isn't stale while it's alive.
+    owner: Weak<()>,
+}
+
+/// Outputs earmarked by [AccountHandle#reserve_amount](../struct.AccountHandle.html#method.reserve_amount).
+#[derive(Debug, Clone)]
+struct Reservation {
+    id: ReservationId,
+    outputs: Vec<AddressOutput>,
+    expires_at: Instant,
+}
+
+/// The outputs reserved by the ongoing transfers of an account, so concurrent transfers don't select them.
+#[derive(Debug, Default)]
+pub(crate) struct LockedOutputs {
+    outputs: Vec<LockedOutput>,
+    reservations: Vec<Reservation>,
+}
+
+fn same_output(a: &AddressOutput, b: &AddressOutput) -> bool {
+    a.transaction_id == b.transaction_id && a.index == b.index
+}
+
+impl LockedOutputs {
+    /// Whether the output is reserved, by a transfer or by an unexpired reservation.
+    pub(crate) fn is_locked(&self, output: &AddressOutput) -> bool {
+        let now = Instant::now();
+        self.outputs.iter().any(|locked| same_output(&locked.output, output))
+            || self.reservations.iter().any(|reservation| {
+                reservation.expires_at > now && reservation.outputs.iter().any(|o| same_output(o, output))
+            })
+    }
+
+    /// Earmarks the outputs until the reservation is released or `ttl` elapses.
+    pub(crate) fn reserve(&mut self, outputs: Vec<AddressOutput>, ttl: Duration) -> crate::Result<ReservationId> {
+        let now = Instant::now();
+        self.reservations.retain(|reservation| reservation.expires_at > now);
+        let mut id = [0u8; 32];
+        crypto::utils::rand::fill(&mut id).map_err(|e| crate::Error::RandomGeneration(format!("{:?}", e)))?;
+        self.reservations.push(Reservation {
+            id,
+            outputs,
+            expires_at: now + ttl,
+        });
+        Ok(id)
+    }
+
+    /// Releases the reservation, returning its outputs, or `None` if it doesn't exist or expired.
+    pub(crate) fn release_reservation(&mut self, id: &ReservationId) -> Option<Vec<AddressOutput>> {
+        let now = Instant::now();
+        self.reservations.retain(|reservation| reservation.expires_at > now);
+        let index = self.reservations.iter().position(|reservation| &reservation.id == id)?;
+        Some(self.reservations.remove(index).outputs)
+    }
+
+    /// Reserves the outputs for the given owner, starting their lease.
+    pub(crate) fn lock(&mut self, outputs: impl IntoIterator<Item = AddressOutput>, owner: &Weak<()>) {
+        let locked_at = Instant::now();
+        self.outputs.extend(outputs.into_iter().map(|output| LockedOutput {
+            output,
+            locked_at,
+            owner: owner.clone(),
+        }));
+    }
+
+    /// Releases the output, returning `false` if it wasn't reserved anymore, e.g. because its lease expired.
+    pub(crate) fn unlock(&mut self, output: &AddressOutput) -> bool {
+        match self
+            .outputs
+            .iter()
+            .position(|locked| same_output(&locked.output, output))
+        {
+            Some(index) => {
+                self.outputs.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.outputs.len()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.outputs.is_empty()
+    }
+
+    /// Releases the outputs locked after the first `len`, in locking order.
+    pub(crate) fn truncate(&mut self, len: usize) {
+        self.outputs.truncate(len);
+    }
+
+    /// Releases the outputs locked for longer than the lease by a transfer that isn't running anymore, returning them.
+    /// The locks of a running transfer, e.g. waiting for a slow Ledger confirmation, are kept.
+    pub(crate) fn release_stale(&mut self, lease: Duration) -> Vec<LockedOutput> {
+        let (stale, active) = std::mem::take(&mut self.outputs)
+            .into_iter()
+            .partition(|locked| locked.locked_at.elapsed() > lease && locked.owner.upgrade().is_none());
+        self.outputs = active;
+        stale
+    }
+}
+
+impl AccountHandle {
+    /// Earmarks outputs covering `amount`, so the automatic input selection of the transfers skips them until the
+    /// reservation is released with [release_reservation](struct.AccountHandle.html#method.release_reservation) or
+    /// `ttl` elapses. No transaction is issued.
+    pub async fn reserve_amount(&self, amount: u64, ttl: Duration) -> crate::Result<ReservationId> {
+        let account = self.read().await;
+        let sent_messages = account.list_messages(0, 0, Some(MessageType::Sent)).await?;
+        let mut locked_outputs = self.locked_outputs.lock().await;
+        let mut available_inputs = Vec::new();
+        for address in account.addresses() {
+            for output in address.available_outputs(&sent_messages) {
+                if output.amount > 0 && !locked_outputs.is_locked(output) {
+                    available_inputs.push(input_selection::Input {
+                        internal: *address.internal(),
+                        output: output.clone(),
+                    });
+                }
+            }
+        }
+        let selected = input_selection::select_input(
+            amount,
+            available_inputs,
+            account.client_options().network_parameters().max_input_output_count(),
+            account.client_options().dust_protection().allowance_value(),
+        )?;
+        locked_outputs.reserve(selected.into_iter().map(|input| input.output).collect(), ttl)
+    }
+
+    /// Releases the outputs of a reservation created with
+    /// [reserve_amount](struct.AccountHandle.html#method.reserve_amount), returning `false` if it doesn't exist or
+    /// already expired.
+    pub async fn release_reservation(&self, id: &ReservationId) -> bool {
+        self.locked_outputs.lock().await.release_reservation(id).is_some()
+    }
+
+    /// Releases the output locks held longer than the configured lease, emitting an event for each forced release.
+    /// Returns the number of released outputs.
+    pub(crate) async fn release_stale_output_locks(&self) -> usize {
+        let stale = self
+            .locked_outputs
+            .lock()
+            .await
+            .release_stale(self.account_options.output_lock_lease);
+        if stale.is_empty() {
+            return 0;
+        }
+        let account_id = self.id().await;
+        for locked in &stale {
+            log::warn!(
+                "[TRANSFER] releasing output {}:{} locked for {:.2?}",
+                locked.output.transaction_id,
+                locked.output.index,
+                locked.locked_at.elapsed()
+            );
+            crate::event::emit_output_lock_released(
+                account_id.clone(),
+                locked.output.clone(),
+                locked.locked_at.elapsed(),
+            )
+            .await;
+        }
+        stale.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LockedOutputs;
+    use crate::address::{AddressOutput, OutputKind};
+
+    use iota_client::bee_message::prelude::{MessageId, TransactionId};
+
+    use std::{
+        sync::{Arc, Weak},
+        time::Duration,
+    };
+
+    fn output(index: u16) -> AddressOutput {
+        AddressOutput {
+            transaction_id: TransactionId::new([1; 32]),
+            message_id: MessageId::new([2; 32]),
+            index,
+            amount: 1_000_000,
+            is_spent: false,
+            address: crate::testing::generate_random_iota_address(),
+            kind: OutputKind::SignatureLockedSingle,
+        }
+    }
+
+    #[test]
+    fn stale_output_locks() {
+        let mut locked_outputs = LockedOutputs::default();
+        let transfer = Arc::new(());
+        locked_outputs.lock(vec![output(0), output(1)], &Arc::downgrade(&transfer));
+        assert!(locked_outputs.is_locked(&output(1)));
+        assert!(!locked_outputs.is_locked(&output(2)));
+
+        assert!(locked_outputs.release_stale(Duration::from_secs(60)).is_empty());
+        std::thread::sleep(Duration::from_millis(5));
+        // the transfer is still running
+        assert!(locked_outputs.release_stale(Duration::from_millis(1)).is_empty());
+        drop(transfer);
+        let stale = locked_outputs.release_stale(Duration::from_millis(1));
+        assert_eq!(stale.len(), 2);
+        assert!(locked_outputs.is_empty());
+        // the transfer finishing after the forced release doesn't fail
+        assert!(!locked_outputs.unlock(&output(0)));
+
+        locked_outputs.lock(vec![output(3)], &Weak::new());
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(locked_outputs.release_stale(Duration::from_millis(1)).len(), 1);
+    }
+
+    #[test]
+    fn output_reservations() {
+        let mut locked_outputs = LockedOutputs::default();
+        let id = locked_outputs
+            .reserve(vec![output(0)], Duration::from_secs(60))
+            .unwrap();
+        assert!(locked_outputs.is_locked(&output(0)));
+        // reservations aren't transfer locks
+        assert!(locked_outputs.is_empty());
+        assert!(locked_outputs.release_stale(Duration::from_millis(0)).is_empty());
+        assert!(locked_outputs.is_locked(&output(0)));
+
+        assert_eq!(locked_outputs.release_reservation(&id).map(|o| o.len()), Some(1));
+        assert!(!locked_outputs.is_locked(&output(0)));
+        assert!(locked_outputs.release_reservation(&id).is_none());
+
+        let id = locked_outputs
+            .reserve(vec![output(1)], Duration::from_millis(1))
+            .unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(!locked_outputs.is_locked(&output(1)));
+        assert!(locked_outputs.release_reservation(&id).is_none());
+    }
+}
@@ -0,0 +1,83 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{SyncedAccountData, SyncedMessage};
+use crate::{
+    account::AccountHandle,
+    address::Address,
+    event::{emit_confirmation_state_change, emit_transaction_event, TransactionEventType},
+};
+
+use std::collections::{HashMap, HashSet};
+
+/// Number of addresses synced between two message flushes of a low memory sync.
+pub(super) const LOW_MEMORY_SYNC_CHUNK_SIZE: usize = 50;
+
+/// Parses and saves a chunk of synced messages, emitting their transaction and confirmation events,
+/// so a low memory sync doesn't hold the messages until it finishes.
+/// `discovered_addresses` are the addresses found by the running discovery, which the account doesn't have yet.
+pub(super) async fn flush_synced_messages(
+    account_handle: &AccountHandle,
+    messages: Vec<SyncedMessage>,
+    discovered_addresses: &[Address],
+) -> crate::Result<()> {
+    if messages.is_empty() {
+        return Ok(());
+    }
+    log::debug!("[SYNC] flushing {} synced messages", messages.len());
+    let data = SyncedAccountData {
+        messages,
+        addresses: Vec::new(),
+        partial: false,
+        retries: 0,
+    };
+    // the messages are parsed with the read lock, so the account stays readable while the node is queried
+    let parsed_messages = if discovered_addresses.is_empty() {
+        let account = account_handle.read().await;
+        data.parse_messages(account_handle.accounts.clone(), &account).await?
+    } else {
+        // the addresses found by the discovery are saved when the sync finishes, but their messages are parsed now
+        let mut account = account_handle.read().await.clone();
+        for address in discovered_addresses {
+            if !account.addresses().iter().any(|a| a.address() == address.address()) {
+                account.addresses.push(address.clone());
+            }
+        }
+        data.parse_messages(account_handle.accounts.clone(), &account).await?
+    };
+    drop(data);
+
+    let mut account = account_handle.write().await;
+    let parsed_ids: HashSet<_> = parsed_messages.iter().map(|message| *message.id()).collect();
+    let messages_before_sync: HashMap<_, _> = account
+        .with_messages(|messages| {
+            messages
+                .iter()
+                .filter(|m| parsed_ids.contains(&m.key))
+                .map(|m| (m.key, m.confirmed))
+                .collect()
+        })
+        .await;
+    let mut new_messages = Vec::new();
+    let mut confirmation_changed_messages = Vec::new();
+    for message in &parsed_messages {
+        match messages_before_sync.get(message.id()) {
+            None => new_messages.push(message.clone()),
+            Some(confirmed) if confirmed != message.confirmed() => confirmation_changed_messages.push(message.clone()),
+            Some(_) => {}
+        }
+    }
+
+    account_handle.extend_known_ids(&account, &parsed_messages, &[]).await?;
+    account.save_messages(parsed_messages).await?;
+
+    let persist_events = account_handle.account_options.persist_events;
+    for message in new_messages {
+        emit_transaction_event(TransactionEventType::NewTransaction, &account, message, persist_events).await?;
+    }
+    for message in confirmation_changed_messages {
+        let confirmed = message.confirmed().unwrap_or(false);
+        emit_confirmation_state_change(&account, message, confirmed, persist_events).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,150 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::message::TransactionInput;
+
+use chrono::{DateTime, Utc};
+use iota_client::bee_message::prelude::{Message as IotaMessage, MessageId};
+
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+/// The number of messages kept in the cache; the oldest entries are dropped first.
+const MESSAGE_CACHE_CAPACITY: usize = 1000;
+/// How long an entry is reused: long enough for the syncs of every account of a sync round,
+/// after which the message is resolved again.
+const MESSAGE_CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// The cache key: the network (by its bech32 HRP) and the message id.
+type CacheKey = (String, MessageId);
+
+/// What the syncs learned about a message, shared between the accounts of an account manager so the messages
+/// exchanged by two accounts are requested and resolved once.
+#[derive(Debug, Clone)]
+pub(crate) struct CachedMessage {
+    /// The message fetched from the node.
+    pub(crate) message: Option<IotaMessage>,
+    /// The transaction inputs with their output metadata, which don't depend on the parsing account.
+    pub(crate) inputs: Option<Vec<TransactionInput>>,
+    /// The timestamp of the milestone referencing the message.
+    pub(crate) timestamp: Option<DateTime<Utc>>,
+    /// When the entry was inserted.
+    inserted_at: Instant,
+}
+
+impl CachedMessage {
+    fn new() -> Self {
+        Self {
+            message: None,
+            inputs: None,
+            timestamp: None,
+            inserted_at: Instant::now(),
+        }
+    }
+}
+
+/// The message cache of an account manager.
+#[derive(Debug)]
+pub(crate) struct MessageCache {
+    entries: HashMap<CacheKey, CachedMessage>,
+    /// The insertion order of the entries.
+    order: VecDeque<CacheKey>,
+    capacity: usize,
+    ttl: Duration,
+}
+
+impl Default for MessageCache {
+    fn default() -> Self {
+        Self::new(MESSAGE_CACHE_CAPACITY, MESSAGE_CACHE_TTL)
+    }
+}
+
+impl MessageCache {
+    fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            capacity,
+            ttl,
+        }
+    }
+
+    /// Gets the entry of the message on the network, unless it expired.
+    pub(crate) fn get(&self, bech32_hrp: &str, message_id: &MessageId) -> Option<&CachedMessage> {
+        self.entries
+            .get(&(bech32_hrp.to_string(), *message_id))
+            .filter(|entry| entry.inserted_at.elapsed() < self.ttl)
+    }
+
+    /// Gets the entry of the message to update it, inserting an empty one and evicting the oldest entry if needed.
+    /// An expired entry is replaced.
+    pub(crate) fn entry(&mut self, bech32_hrp: &str, message_id: MessageId) -> &mut CachedMessage {
+        let key = (bech32_hrp.to_string(), message_id);
+        let expired = self
+            .entries
+            .get(&key)
+            .map(|entry| entry.inserted_at.elapsed() >= self.ttl);
+        match expired {
+            Some(false) => {}
+            Some(true) => {
+                self.entries.insert(key.clone(), CachedMessage::new());
+            }
+            None => {
+                while self.order.len() >= self.capacity {
+                    match self.order.pop_front() {
+                        Some(oldest) => {
+                            self.entries.remove(&oldest);
+                        }
+                        None => break,
+                    }
+                }
+                self.order.push_back(key.clone());
+                self.entries.insert(key.clone(), CachedMessage::new());
+            }
+        }
+        self.entries.get_mut(&key).unwrap()
+    }
+
+    /// Removes every entry.
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MessageCache;
+    use iota_client::bee_message::prelude::MessageId;
+
+    use std::time::Duration;
+
+    #[test]
+    fn eviction() {
+        let mut cache = MessageCache::new(2, Duration::from_secs(60));
+        let ids: Vec<MessageId> = (0..3u8).map(|i| MessageId::new([i; 32])).collect();
+        for id in &ids {
+            cache.entry("atoi", *id).timestamp.replace(chrono::Utc::now());
+        }
+        // updating an entry doesn't insert it again
+        cache.entry("atoi", ids[2]).inputs.replace(Vec::new());
+        assert!(cache.get("atoi", &ids[0]).is_none());
+        assert!(cache.get("atoi", &ids[1]).is_some());
+        let entry = cache.get("atoi", &ids[2]).unwrap();
+        assert!(entry.timestamp.is_some() && entry.inputs.is_some());
+        // the entries are scoped to their network
+        assert!(cache.get("iota", &ids[2]).is_none());
+    }
+
+    #[test]
+    fn expiration() {
+        let mut cache = MessageCache::new(2, Duration::from_millis(0));
+        let id = MessageId::new([0; 32]);
+        cache.entry("atoi", id).inputs.replace(Vec::new());
+        assert!(cache.get("atoi", &id).is_none());
+        // the expired entry is replaced by an empty one
+        assert!(cache.entry("atoi", id).inputs.is_none());
+    }
+}
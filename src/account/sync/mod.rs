@@ -1,27 +1,30 @@
 // Copyright 2020 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
+#[cfg(any(feature = "ledger-nano", feature = "ledger-nano-simulator"))]
+use crate::signing::SignerType;
 use crate::{
-    account::{Account, AccountHandle},
+    account::{Account, AccountHandle, SigningRequest},
     account_manager::{AccountOptions, AccountStore},
     address::{Address, AddressBuilder, AddressOutput, AddressWrapper, OutputKind},
-    client::ClientOptions,
+    client::{log_request, ClientOptions, DustProtection},
     event::{
         emit_balance_change, emit_confirmation_state_change, emit_transaction_event, AddressData, BalanceChange,
-        PreparedTransactionData, TransactionEventType, TransactionIO, TransferProgressType,
+        PreparedTransactionData, RemainderAddressVerificationData, TransactionEventType, TransactionIO,
+        TransferProgressType,
     },
     message::{
-        Message, MessagePayload, MessageType, RemainderValueStrategy, TransactionEssence, TransactionInput, Transfer,
+        ChangeAddressVerification, Message, MessagePayload, MessageType, RemainderValueStrategy, TransactionEssence,
+        Transfer, TransferOutput,
     },
-    signing::{GenerateAddressMetadata, SignMessageMetadata, SignerType},
+    signing::{GenerateAddressMetadata, SignMessageMetadata, SignerCapabilities, SignerSession},
 };
 
+use futures::{Stream, StreamExt};
 use getset::Getters;
 use iota_client::{
-    api::finish_pow,
     bee_message::{
         address::Address as BeeAddress,
-        constants::INPUT_OUTPUT_COUNT_MAX,
         prelude::{
             Essence, Input, Message as IotaMessage, MessageId, Output, OutputId, Payload, RegularEssence,
             SignatureLockedDustAllowanceOutput, SignatureLockedSingleOutput, TransactionPayload, UnlockBlocks,
@@ -32,26 +35,52 @@ use iota_client::{
     common::packable::Packable,
     AddressOutputsOptions, Client,
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use tokio::sync::MutexGuard;
 
 use std::{
     collections::{HashMap, HashSet},
     num::NonZeroU64,
+    ops::Range,
+    sync::{Arc, Weak},
 };
 
+mod batch_outputs;
+mod diagnostics;
+mod estimate;
+mod gap_limit;
+mod in_flight;
 mod input_selection;
+mod known_ids;
+mod locked_outputs;
+mod low_memory;
+mod message_cache;
+mod request_budget;
+mod retry;
+mod send_check;
+mod spent_addresses;
+
+#[cfg(feature = "bench")]
+pub(crate) use input_selection::{select_input, Input as SelectionInput};
+
+pub use diagnostics::{DustAnalysis, OutputDiagnostic, TransferDiagnostics};
+pub use estimate::TransactionEstimate;
+pub(crate) use in_flight::InFlightTransfers;
+pub(crate) use known_ids::KnownIds;
+pub use locked_outputs::ReservationId;
+pub(crate) use locked_outputs::{LockedOutputs, DEFAULT_OUTPUT_LOCK_LEASE};
+pub(crate) use message_cache::MessageCache;
+pub(crate) use request_budget::RequestBudget;
+pub use retry::SyncRetryOptions;
+pub use send_check::SendCheck;
 
 // https://github.com/GalRogozinski/protocol-rfcs/blob/dust/text/0032-dust-protection/0032-dust-protection.md
-const MAX_ALLOWED_DUST_OUTPUTS: i64 = 100;
-const DUST_DIVISOR: i64 = 100_000;
-const DUST_ALLOWANCE_VALUE: u64 = 1_000_000;
 const DEFAULT_GAP_LIMIT: usize = 10;
 #[cfg(any(feature = "ledger-nano", feature = "ledger-nano-simulator"))]
 const DEFAULT_LEDGER_GAP_LIMIT: usize = 10;
-#[cfg(any(feature = "ledger-nano", feature = "ledger-nano-simulator"))]
-const LEDGER_MAX_IN_OUTPUTS: usize = 17;
 const SYNC_CHUNK_SIZE: usize = 500;
+/// Maximum number of messages parsed at the same time on sync.
+const PARSE_MESSAGES_CONCURRENCY: usize = 50;
 
 #[derive(Debug, Clone)]
 pub(crate) struct SyncedMessage {
@@ -64,7 +93,8 @@ async fn get_address_outputs(
     client: &Client,
     fetch_spent_outputs: bool,
 ) -> crate::Result<Vec<UtxoInput>> {
-    let outputs = {
+    let endpoint = format!("/api/v1/addresses/{}/outputs", address);
+    let outputs = log_request("GET", endpoint, Some(&address), async {
         if fetch_spent_outputs {
             client
                 .get_address()
@@ -75,7 +105,7 @@ async fn get_address_outputs(
                         ..Default::default()
                     },
                 )
-                .await?
+                .await
         } else {
             client
                 .get_address()
@@ -86,14 +116,16 @@ async fn get_address_outputs(
                         ..Default::default()
                     },
                 )
-                .await?
+                .await
         }
-    };
+    })
+    .await?;
     Ok(outputs.to_vec())
 }
 
 async fn get_message(client: &Client, message_id: &MessageId) -> crate::Result<Option<IotaMessage>> {
-    match client.get_message().data(message_id).await {
+    let endpoint = format!("/api/v1/messages/{}", message_id);
+    match log_request("GET", endpoint, None, client.get_message().data(message_id)).await {
         Ok(message) => Ok(Some(message)),
         Err(iota_client::Error::ResponseError(status_code, _)) if status_code == 404 => Ok(None),
         Err(e) => Err(e.into()),
@@ -101,17 +133,29 @@ async fn get_message(client: &Client, message_id: &MessageId) -> crate::Result<O
 }
 
 pub(crate) async fn sync_address(
+    accounts: &AccountStore,
     account_messages: Vec<(MessageId, Option<bool>)>,
     client_options: &ClientOptions,
     outputs: &mut HashMap<OutputId, AddressOutput>,
     iota_address: AddressWrapper,
     bech32_hrp: String,
     options: AccountOptions,
+    budget: &RequestBudget,
 ) -> crate::Result<Vec<SyncedMessage>> {
     let client_guard = crate::client::get_client(client_options).await?;
     let client = client_guard.read().await;
 
-    let address_outputs = get_address_outputs(iota_address.to_bech32(), &client, options.sync_spent_outputs).await?;
+    if !budget.spend() {
+        log::debug!(
+            "[SYNC] request budget exceeded, skipping address {}",
+            iota_address.to_bech32()
+        );
+        return Ok(Vec::new());
+    }
+
+    let address_outputs = budget
+        .retry(|| get_address_outputs(iota_address.to_bech32(), &client, options.sync_spent_outputs))
+        .await?;
     drop(client);
 
     let mut found_messages = vec![];
@@ -132,24 +176,27 @@ pub(crate) async fn sync_address(
         }
     }
 
-    let mut tasks = Vec::new();
-    for utxo_input in address_outputs.iter() {
-        let utxo_input = utxo_input.clone();
-        if let Some(existing_output) = outputs.get(utxo_input.output_id()) {
-            // If we have the output already and it got spent, then we don't need to get it again from the node
-            if existing_output.is_spent {
-                continue;
-            }
-        }
+    let output_ids: Vec<OutputId> = address_outputs
+        .iter()
+        .map(|utxo_input| *utxo_input.output_id())
+        // If we have the output already and it got spent, then we don't need to get it again from the node
+        .filter(|output_id| !outputs.get(output_id).map_or(false, |output| output.is_spent))
+        .collect();
+    let client = client_guard.read().await;
+    let requested_outputs = batch_outputs::get_outputs(&client, client_options, &output_ids, budget).await?;
+    drop(client);
 
+    let mut tasks = Vec::new();
+    for (_, output) in requested_outputs {
         let client_guard = client_guard.clone();
         let bech32_hrp = bech32_hrp.clone();
         let account_messages = account_messages.clone();
+        let budget = budget.clone();
+        let accounts = accounts.clone();
         tasks.push(async move {
             tokio::spawn(async move {
                 let client = client_guard.read().await;
-                let output = client.get_output(&utxo_input).await?;
-                let found_output = AddressOutput::from_output_response(output, bech32_hrp.to_string())?;
+                let found_output = AddressOutput::from_output_response(output?, bech32_hrp.to_string())?;
                 let message_id = *found_output.message_id();
 
                 // if we already have the message stored
@@ -159,20 +206,44 @@ pub(crate) async fn sync_address(
                     .iter()
                     .any(|(id, confirmed)| id == &message_id && confirmed.unwrap_or(false))
                 {
-                    return crate::Result::Ok((found_output, None));
+                    return crate::Result::Ok(Some((found_output, None)));
+                }
+
+                // the messages exchanged with another account of the wallet may have been fetched by its sync
+                let cached_message = accounts
+                    .message_cache()
+                    .get(&bech32_hrp, &message_id)
+                    .and_then(|cached| cached.message.clone());
+                if let Some(message) = cached_message {
+                    return Ok(Some((
+                        found_output,
+                        Some(SyncedMessage {
+                            id: message_id,
+                            inner: message,
+                        }),
+                    )));
+                }
+
+                if !budget.spend() {
+                    return Ok(Some((found_output, None)));
                 }
 
-                if let Some(message) = get_message(&client, &message_id).await? {
-                    return Ok((
+                if let Some(message) = budget.retry(|| get_message(&client, &message_id)).await? {
+                    accounts
+                        .message_cache()
+                        .entry(&bech32_hrp, message_id)
+                        .message
+                        .replace(message.clone());
+                    return Ok(Some((
                         found_output,
                         Some(SyncedMessage {
                             id: message_id,
                             inner: message,
                         }),
-                    ));
+                    )));
                 }
 
-                Ok((found_output, None))
+                Ok(Some((found_output, None)))
             })
             .await
         });
@@ -180,7 +251,8 @@ pub(crate) async fn sync_address(
 
     for res in futures::future::try_join_all(tasks).await? {
         match res {
-            Ok((found_output, found_message)) => {
+            Ok(None) => {}
+            Ok(Some((found_output, found_message))) => {
                 outputs.insert(found_output.id()?, found_output);
                 if let Some(m) = found_message {
                     found_messages.push(m);
@@ -200,57 +272,14 @@ pub(crate) async fn sync_address(
     crate::Result::Ok(found_messages)
 }
 
-// Gets an address for the sync process.
-// If the account already has the address with the given index + internal flag, we'll use it
-// otherwise we'll generate a new one.
-async fn get_address_for_sync(
-    account: &Account,
-    bech32_hrp: String,
-    index: usize,
-    internal: bool,
-) -> crate::Result<Option<AddressWrapper>> {
-    if let Some(address) = account
-        .addresses()
-        .iter()
-        .find(|a| *a.key_index() == index && *a.internal() == internal)
-    {
-        Ok(Some(address.address().clone()))
-    } else {
-        // if stronghold is locked, we skip address generation
-        #[cfg(feature = "stronghold")]
-        {
-            if account.signer_type() == &crate::signing::SignerType::Stronghold
-                && crate::stronghold::get_status(
-                    &crate::signing::stronghold::stronghold_path(account.storage_path()).await?,
-                )
-                .await
-                .snapshot
-                    == crate::stronghold::SnapshotStatus::Locked
-            {
-                return Ok(None);
-            }
-        }
-        let generated_address = crate::address::get_iota_address(
-            account,
-            index,
-            internal,
-            bech32_hrp,
-            GenerateAddressMetadata {
-                syncing: true,
-                network: account.network(),
-            },
-        )
-        .await?;
-        Ok(Some(generated_address))
-    }
-}
-
 async fn sync_address_list(
+    accounts: &AccountStore,
     addresses: Vec<Address>,
     account_messages: Vec<(MessageId, Option<bool>)>,
     options: AccountOptions,
     client_options: ClientOptions,
     return_all_addresses: bool,
+    budget: &RequestBudget,
 ) -> crate::Result<(Vec<Address>, Vec<SyncedMessage>)> {
     let mut found_addresses = Vec::new();
     let mut found_messages = Vec::new();
@@ -265,15 +294,19 @@ async fn sync_address_list(
             let account_messages = account_messages.clone();
             let mut outputs = address.outputs().clone();
             let client_options = client_options.clone();
+            let budget = budget.clone();
+            let accounts = accounts.clone();
             tasks.push(async move {
                 tokio::spawn(async move {
                     let messages = sync_address(
+                        &accounts,
                         account_messages,
                         &client_options,
                         &mut outputs,
                         address.address().clone(),
                         address.address().bech32_hrp.clone(),
                         options,
+                        &budget,
                     )
                     .await?;
                     address.set_outputs(outputs);
@@ -315,6 +348,8 @@ async fn check_for_new_used_addresses(
     gap_limit: usize,
     options: AccountOptions,
     return_all_addresses: bool,
+    budget: &RequestBudget,
+    flush_messages: bool,
 ) -> crate::Result<(Vec<Address>, Vec<SyncedMessage>)> {
     log::debug!("[SYNC] check_for_new_used_addresses internal: {}", internal);
     let account = account_handle.read().await.clone();
@@ -341,28 +376,61 @@ async fn check_for_new_used_addresses(
     let mut found_messages = vec![];
 
     let bech32_hrp = account.bech32_hrp().clone();
-    drop(account);
+    // the signer session is opened once and reused for every address derivation of the discovery
+    let session = SignerSession::open(&account).await?;
 
     // Generate addresses and check if they have outputs, if amount of gap_limit addresses don't have outputs in a row,
     // it breaks
     loop {
-        let mut address_generation_locked = false;
+        // the discovery continues on the next sync
+        if budget.is_exceeded() {
+            log::debug!("[SYNC] finishing check_for_new_used_addresses because the request budget was exceeded");
+            break;
+        }
+        if options.check_address_index(address_index_to_start_from).is_err() {
+            log::debug!("[SYNC] finishing check_for_new_used_addresses because the address index limit was reached");
+            break;
+        }
         let mut generated_iota_addresses = vec![]; // collection of (address_index, address) pairs
-        for i in address_index_to_start_from..(address_index_to_start_from + gap_limit) {
-            // generate addresses
-            let account = account_handle.read().await.clone();
-            if let Some(address) = get_address_for_sync(&account, bech32_hrp.to_string(), i, internal).await? {
-                generated_iota_addresses.push((i, address));
-            } else {
-                address_generation_locked = true;
-                break;
+        let mut missing_indexes = vec![];
+        {
+            // use the addresses the account already has, and generate the others
+            let account_ = account_handle.read().await;
+            for i in address_index_to_start_from..options.address_index_end(address_index_to_start_from + gap_limit) {
+                match account_
+                    .addresses()
+                    .iter()
+                    .find(|a| *a.key_index() == i && *a.internal() == internal)
+                {
+                    Some(address) => generated_iota_addresses.push((i, address.address().clone())),
+                    None => missing_indexes.push(i),
+                }
             }
-            drop(account);
         }
 
-        if address_generation_locked {
-            log::debug!("[SYNC] finishing check_for_new_used_addresses because stronghold is locked");
-            break;
+        if !missing_indexes.is_empty() {
+            // if stronghold is locked, we skip address generation
+            if session.is_locked() {
+                log::debug!("[SYNC] finishing check_for_new_used_addresses because stronghold is locked");
+                break;
+            }
+            let addresses = session
+                .generate_addresses(
+                    &missing_indexes,
+                    internal,
+                    GenerateAddressMetadata {
+                        syncing: true,
+                        network: account.network(),
+                    },
+                )
+                .await?;
+            generated_iota_addresses.extend(
+                missing_indexes
+                    .into_iter()
+                    .zip(addresses.into_iter())
+                    .map(|(i, address)| (i, AddressWrapper::new(address, bech32_hrp.clone()))),
+            );
+            generated_iota_addresses.sort_by_key(|(i, _)| *i);
         }
 
         let mut curr_generated_addresses = vec![];
@@ -397,11 +465,13 @@ async fn check_for_new_used_addresses(
         }
 
         let (found_addresses_, found_messages_) = sync_address_list(
+            &account_handle.accounts,
             addresses_to_sync,
             account_messages,
             options,
             client_options.clone(),
             return_all_addresses,
+            budget,
         )
         .await?;
         curr_generated_addresses.extend(found_addresses_);
@@ -416,6 +486,14 @@ async fn check_for_new_used_addresses(
 
         found_messages.extend(curr_found_messages.into_iter());
         generated_addresses.extend(curr_generated_addresses.into_iter());
+        if flush_messages {
+            low_memory::flush_synced_messages(
+                account_handle,
+                std::mem::take(&mut found_messages),
+                &generated_addresses,
+            )
+            .await?;
+        }
 
         if is_empty {
             log::debug!(
@@ -428,8 +506,103 @@ async fn check_for_new_used_addresses(
     Ok((generated_addresses, found_messages))
 }
 
+/// Generates the addresses of the given key index range and syncs them with the tangle, without the gap limit
+/// discovery nor the addresses below the range. Change addresses are scanned too unless `skip_change_addresses`.
+///
+/// Returns the used addresses of the range and their messages.
+async fn scan_address_range(
+    account_handle: &AccountHandle,
+    range: Range<usize>,
+    skip_change_addresses: bool,
+    options: AccountOptions,
+    budget: &RequestBudget,
+) -> crate::Result<(Vec<Address>, Vec<SyncedMessage>)> {
+    log::debug!("[SYNC] scan_address_range {:?}", range);
+    let account = account_handle.read().await.clone();
+    let range = range.start..options.address_index_end(range.end);
+    let bech32_hrp = account.bech32_hrp().clone();
+    let session = SignerSession::open(&account).await?;
+    let account_messages: Vec<(MessageId, Option<bool>)> = account
+        .with_messages(|messages| messages.iter().map(|m| (m.key, m.confirmed)).collect())
+        .await;
+
+    let mut found_addresses = Vec::new();
+    let mut found_messages = Vec::new();
+    let chains: &[bool] = if skip_change_addresses {
+        &[false]
+    } else {
+        &[false, true]
+    };
+    for internal in chains {
+        let indexes: Vec<usize> = range.clone().collect();
+        // derive and sync the addresses in chunks so a large range doesn't keep every address in memory
+        for chunk in indexes.chunks(SYNC_CHUNK_SIZE) {
+            if budget.is_exceeded() {
+                log::debug!("[SYNC] finishing scan_address_range because the request budget was exceeded");
+                return Ok((found_addresses, found_messages));
+            }
+            let mut addresses_to_sync = Vec::new();
+            let mut missing_indexes = Vec::new();
+            for key_index in chunk {
+                match account
+                    .addresses()
+                    .iter()
+                    .find(|a| a.key_index() == key_index && a.internal() == internal)
+                {
+                    Some(address) => addresses_to_sync.push(address.clone()),
+                    None => missing_indexes.push(*key_index),
+                }
+            }
+            if !missing_indexes.is_empty() {
+                if session.is_locked() {
+                    log::debug!("[SYNC] finishing scan_address_range because stronghold is locked");
+                    return Ok((found_addresses, found_messages));
+                }
+                let addresses = session
+                    .generate_addresses(
+                        &missing_indexes,
+                        *internal,
+                        GenerateAddressMetadata {
+                            syncing: true,
+                            network: account.network(),
+                        },
+                    )
+                    .await?;
+                for (key_index, address) in missing_indexes.into_iter().zip(addresses.into_iter()) {
+                    addresses_to_sync.push(
+                        AddressBuilder::new()
+                            .address(AddressWrapper::new(address, bech32_hrp.clone()))
+                            .key_index(key_index)
+                            .outputs(Vec::new())
+                            .internal(*internal)
+                            .build()?,
+                    );
+                }
+            }
+            let (addresses, messages) = sync_address_list(
+                &account_handle.accounts,
+                addresses_to_sync,
+                account_messages.clone(),
+                options,
+                account.client_options().clone(),
+                false,
+                budget,
+            )
+            .await?;
+            found_addresses.extend(addresses);
+            found_messages.extend(messages);
+        }
+    }
+
+    Ok((found_addresses, found_messages))
+}
+
 /// Syncs messages with the tangle.
 /// The method should ensures that the wallet local state has messages associated with the address history.
+///
+/// The addresses deferred by a previous sync are synced first.
+/// Returns the addresses that couldn't be fully synced because the request budget ran out.
+#[allow(clippy::too_many_arguments)]
 async fn sync_addresses_and_messages(
     account_handle: &AccountHandle,
     skip_addresses: &[Address],
@@ -438,7 +611,13 @@ async fn sync_addresses_and_messages(
     change_addresses_to_sync: HashSet<AddressWrapper>,
     // only sync messages for addresses >= this index
     address_start_index: usize,
-) -> crate::Result<(Vec<Address>, Vec<SyncedMessage>)> {
+    budget: &RequestBudget,
+    deferred_addresses: &HashSet<AddressWrapper>,
+    // save the messages of each address chunk instead of returning them
+    flush_messages: bool,
+    // don't update the sync count, the skip-list of spent addresses nor the deferred addresses
+    dry_run: bool,
+) -> crate::Result<(Vec<Address>, Vec<SyncedMessage>, Vec<AddressWrapper>)> {
     log::debug!("[SYNC] sync_addresses_and_messages");
     let syc_start_time = std::time::Instant::now();
     let mut messages = vec![];
@@ -446,36 +625,38 @@ async fn sync_addresses_and_messages(
     let account = account_handle.read().await.clone();
     let client_options = account.client_options().clone();
 
-    let known_confirmed_messages: Vec<MessageId> = account
-        .with_messages(|messages| {
-            messages
-                .iter()
-                .filter(|m| m.confirmed.unwrap_or(false))
-                .map(|m| m.key)
-                .collect()
-        })
-        .await;
+    let known_ids = account_handle.known_ids(&account).await?;
 
     let mut addresses = Vec::new();
+    let mut incomplete_addresses = Vec::new();
+    let mut synced_addresses = Vec::new();
 
     let client = crate::client::get_client(&client_options).await?;
+    let spot_check = account_handle.count_spent_addresses_sync(&account, dry_run).await?;
+    let milestone_index = spent_addresses::confirmed_milestone_index(&*client.read().await, budget).await;
 
     // We split the addresses into chunks so we don't get timeouts if we have thousands
-    let account_addresses: Vec<Address> = account
+    let mut account_addresses: Vec<Address> = account
         .addresses()
         .iter()
         .filter(|address| address.key_index() >= &address_start_index)
         .cloned()
         .collect();
+    account_addresses.sort_by_key(|address| !deferred_addresses.contains(address.address()));
     log::debug!(
         "[SYNC] sync_addresses_and_messages for {} addresses with spent_outputs: {}",
         account_addresses.len(),
         options.sync_spent_outputs
     );
     drop(account);
+    let chunk_size = if flush_messages {
+        low_memory::LOW_MEMORY_SYNC_CHUNK_SIZE
+    } else {
+        SYNC_CHUNK_SIZE
+    };
     for addresses_chunk in account_addresses
         .to_vec()
-        .chunks(SYNC_CHUNK_SIZE)
+        .chunks(chunk_size)
         .map(|x: &[Address]| x.to_vec())
     {
         let mut tasks = Vec::new();
@@ -490,16 +671,32 @@ async fn sync_addresses_and_messages(
             {
                 continue;
             }
+            // the fully spent addresses are only requested on the spot-check syncs
+            if !spot_check
+                && !deferred_addresses.contains(address.address())
+                && milestone_index.map_or(false, |index| known_ids.skips_address(&address, index))
+            {
+                log::debug!("[SYNC] skipping fully spent address {}", address.address().to_bech32());
+                continue;
+            }
             let client = client.clone();
-            let known_confirmed_messages = known_confirmed_messages.clone();
+            let client_options = client_options.clone();
+            let known_ids = known_ids.clone();
             let mut outputs = address.outputs.clone();
+            let budget = budget.clone();
 
             tasks.push(async move {
                 tokio::spawn(async move {
                     let client = client.read().await;
 
-                    let address_outputs =
-                        get_address_outputs(address.address().to_bech32(), &client, options.sync_spent_outputs).await?;
+                    if !budget.spend() {
+                        return crate::Result::Ok((address, Vec::new(), false, false));
+                    }
+                    let address_outputs = budget
+                        .retry(|| {
+                            get_address_outputs(address.address().to_bech32(), &client, options.sync_spent_outputs)
+                        })
+                        .await?;
                     let address_output_ids: Vec<OutputId> =
                         address_outputs.into_iter().map(|o| *o.output_id()).collect();
 
@@ -527,38 +724,56 @@ async fn sync_addresses_and_messages(
                         address_output_ids.len(),
                     );
 
-                    let mut messages = vec![];
-                    for output_id in address_output_ids.iter() {
-                        let mut address_output = None;
+                    let known_output = |output_id: &OutputId| {
                         // If we also get spent output ids, but we already have the output and it's spent, then don't
                         // request it again
-                        if let Some(output) = address.outputs.get(output_id) {
-                            if *output.is_spent() {
-                                // Only skip if we also sync spent outputs, otherwise if it's stored locally as spent,
-                                // but the node has it as unspent, the local state is wrong, which could happen if a
-                                // node returned 404 for an output request before
-                                if options.sync_spent_outputs {
-                                    log::debug!("[SYNC] skip requesting spent output {}", output_id);
-                                    address_output.replace(output);
-                                }
-                            } else if !options.sync_spent_outputs {
-                                log::debug!(
-                                    "[SYNC] skip requesting output {}, because we have it already",
-                                    output_id
-                                );
-                                // If we have the output and it's still unspent, then we also don't need to request
-                                // it again, because nothing changed
-                                address_output.replace(output);
+                        let output = address.outputs.get(output_id)?;
+                        if *output.is_spent() || known_ids.spent_outputs.contains(output_id) {
+                            // Only skip if we also sync spent outputs, otherwise if it's stored locally as spent,
+                            // but the node has it as unspent, the local state is wrong, which could happen if a
+                            // node returned 404 for an output request before
+                            if options.sync_spent_outputs {
+                                log::debug!("[SYNC] skip requesting spent output {}", output_id);
+                                return Some(output);
                             }
+                        } else if !options.sync_spent_outputs {
+                            log::debug!(
+                                "[SYNC] skip requesting output {}, because we have it already",
+                                output_id
+                            );
+                            // If we have the output and it's still unspent, then we also don't need to request
+                            // it again, because nothing changed
+                            return Some(output);
                         }
+                        None
+                    };
+                    let known_outputs: Vec<(&OutputId, Option<&AddressOutput>)> = address_output_ids
+                        .iter()
+                        .map(|output_id| (output_id, known_output(output_id)))
+                        .collect();
+                    // request the unknown outputs up front, so they can be batched
+                    let unknown_output_ids: Vec<OutputId> = known_outputs
+                        .iter()
+                        .filter(|(_, known_output)| known_output.is_none())
+                        .map(|(output_id, _)| **output_id)
+                        .collect();
+                    let mut requested_outputs =
+                        batch_outputs::get_outputs(&client, &client_options, &unknown_output_ids, &budget).await?;
 
+                    let mut messages = vec![];
+                    let mut complete = true;
+                    for (output_id, known_output) in known_outputs {
                         // Get the message id from the output
-                        let output_message_id = if let Some(address_output) = address_output {
+                        let output_message_id = if let Some(address_output) = known_output {
                             *address_output.message_id()
                         } else {
-                            // if the output isn't known already, request it first
-                            let output = match client.get_output(&((*output_id).into())).await {
-                                Ok(output) => {
+                            let output = match requested_outputs.remove(output_id) {
+                                // the budget ran out before the output could be requested
+                                None => {
+                                    complete = false;
+                                    continue;
+                                }
+                                Some(Ok(output)) => {
                                     let address_output = AddressOutput::from_output_response(
                                         output,
                                         address.address().bech32_hrp().to_string(),
@@ -568,7 +783,7 @@ async fn sync_addresses_and_messages(
                                     outputs.insert(*output_id, address_output);
                                     output_message_id
                                 }
-                                Err(err) => {
+                                Some(Err(err)) => {
                                     // Don't return errors if we sync spent outputs, because they could be pruned
                                     // already
                                     log::error!(
@@ -605,11 +820,15 @@ async fn sync_addresses_and_messages(
                         // if we already have the message stored
                         // and the confirmation state is confirmed
                         // we skip the `get_message` call
-                        if known_confirmed_messages.contains(&output_message_id) {
+                        if known_ids.confirmed_messages.contains(&output_message_id) {
                             continue;
                         }
 
-                        if let Some(message) = get_message(&client, &output_message_id).await? {
+                        if !budget.spend() {
+                            complete = false;
+                            break;
+                        }
+                        if let Some(message) = budget.retry(|| get_message(&client, &output_message_id)).await? {
                             address_or_message_data_changed = true;
                             messages.push(SyncedMessage {
                                 id: output_message_id,
@@ -620,13 +839,18 @@ async fn sync_addresses_and_messages(
 
                     address.set_outputs(outputs);
 
-                    crate::Result::Ok((address, messages, address_or_message_data_changed))
+                    Ok((address, messages, address_or_message_data_changed, complete))
                 })
                 .await
             });
         }
         for res in futures::future::try_join_all(tasks).await? {
-            let (address, found_messages, address_or_message_data_changed) = res?;
+            let (address, found_messages, address_or_message_data_changed, complete) = res?;
+            if !complete {
+                incomplete_addresses.push(address.address().clone());
+            } else if milestone_index.is_some() {
+                synced_addresses.push(address.clone());
+            }
             if address_or_message_data_changed {
                 if !address.outputs().is_empty() {
                     addresses.push(address);
@@ -634,25 +858,37 @@ async fn sync_addresses_and_messages(
                 messages.extend(found_messages);
             }
         }
+        if flush_messages {
+            low_memory::flush_synced_messages(account_handle, std::mem::take(&mut messages), &[]).await?;
+        }
+    }
+
+    if let Some(milestone_index) = milestone_index.filter(|_| !dry_run) {
+        account_handle
+            .update_spent_addresses(&*account_handle.read().await, &synced_addresses, milestone_index)
+            .await?;
     }
 
     log::debug!(
         "[SYNC] sync_addresses_and_messages took: {:.2?}",
         syc_start_time.elapsed()
     );
-    Ok((addresses, messages))
+    Ok((addresses, messages, incomplete_addresses))
 }
 
 #[allow(clippy::too_many_arguments)]
 async fn perform_sync(
     account_handle: AccountHandle,
     address_index: usize,
+    address_range: Option<Range<usize>>,
     gap_limit: usize,
     skip_change_addresses: bool,
     change_addresses_to_sync: HashSet<AddressWrapper>,
     steps: &[AccountSynchronizeStep],
     options: AccountOptions,
     return_all_addresses: bool,
+    flush_messages: bool,
+    dry_run: bool,
 ) -> crate::Result<SyncedAccountData> {
     log::debug!(
         "[SYNC] perform_sync: syncing account {} with address_index = {}, gap_limit = {}, return_all_addresses = {}",
@@ -661,6 +897,7 @@ async fn perform_sync(
         gap_limit,
         return_all_addresses
     );
+    let budget = RequestBudget::new(options.max_requests_per_sync).with_retry_options(options.sync_retry);
     let (mut found_addresses, found_messages) = if let Some(index) = steps
         .iter()
         .position(|s| matches!(s, AccountSynchronizeStep::SyncAddresses(_)))
@@ -690,20 +927,38 @@ async fn perform_sync(
                 }
                 drop(account);
                 sync_address_list(
+                    &account_handle.accounts,
                     addresses_to_sync,
                     account_messages,
                     options,
                     account_handle.read().await.clone().client_options().clone(),
                     return_all_addresses,
+                    &budget,
                 )
                 .await?
+            } else if let Some(address_range) = address_range.clone() {
+                scan_address_range(&account_handle, address_range, skip_change_addresses, options, &budget).await?
             } else {
-                let (found_public_addresses, mut messages) =
-                    check_for_new_used_addresses(&account_handle, false, gap_limit, options, return_all_addresses)
-                        .await?;
-                let (found_change_addresses, synced_messages) =
-                    check_for_new_used_addresses(&account_handle, true, gap_limit, options, return_all_addresses)
-                        .await?;
+                let (found_public_addresses, mut messages) = check_for_new_used_addresses(
+                    &account_handle,
+                    false,
+                    gap_limit,
+                    options,
+                    return_all_addresses,
+                    &budget,
+                    flush_messages,
+                )
+                .await?;
+                let (found_change_addresses, synced_messages) = check_for_new_used_addresses(
+                    &account_handle,
+                    true,
+                    gap_limit,
+                    options,
+                    return_all_addresses,
+                    &budget,
+                    flush_messages,
+                )
+                .await?;
                 let mut found_addresses = found_public_addresses;
                 found_addresses.extend(found_change_addresses);
                 messages.extend(synced_messages);
@@ -728,17 +983,26 @@ async fn perform_sync(
     }
 
     if steps.contains(&AccountSynchronizeStep::SyncMessages) {
-        let (synced_addresses, synced_messages) = sync_addresses_and_messages(
+        let deferred_addresses = account_handle.deferred_sync_addresses.lock().await.clone();
+        let (synced_addresses, synced_messages, incomplete_addresses) = sync_addresses_and_messages(
             &account_handle,
             &found_addresses,
             options,
             skip_change_addresses,
             change_addresses_to_sync,
             address_index,
+            &budget,
+            &deferred_addresses,
+            flush_messages,
+            dry_run,
         )
         .await?;
         found_addresses.extend(synced_addresses);
         new_messages.extend(synced_messages.into_iter());
+        // the addresses that ran out of budget are synced first on the next run
+        if !dry_run {
+            *account_handle.deferred_sync_addresses.lock().await = incomplete_addresses.into_iter().collect();
+        }
     }
     log::debug!("[SYNC] FOUND {:?}", found_addresses);
 
@@ -817,19 +1081,18 @@ async fn perform_sync(
 
     let bech32_hrp = match account.addresses().first() {
         Some(address) => address.address().bech32_hrp().to_string(),
-        None => {
-            crate::client::get_client(account.client_options())
-                .await?
-                .read()
-                .await
-                .get_network_info()
-                .await?
-                .bech32_hrp
-        }
+        None => crate::client::bech32_hrp(account.client_options()).await?,
+    };
+
+    // a range scan doesn't generate the addresses below the range, the next regular sync does
+    let (public_fill_start, internal_fill_start) = if address_range.is_some() {
+        (max_new_public_index, max_new_internal_index)
+    } else {
+        (latest_public_address_index, latest_internal_address_index)
     };
 
     // generate missing public addresses
-    for key_index in latest_public_address_index..max_new_public_index {
+    for key_index in public_fill_start..options.address_index_end(max_new_public_index) {
         if !account
             .addresses()
             .iter()
@@ -840,18 +1103,10 @@ async fn perform_sync(
                 .any(|a| a.key_index() == &key_index && !a.internal())
         {
             // generate address, ignore errors because Stronghold could be locked or a ledger not connected and we
-            // don't want to require an unlock for syncing
-            if let Ok(iota_address) = crate::address::get_iota_address(
-                &account,
-                key_index,
-                false,
-                bech32_hrp.clone(),
-                GenerateAddressMetadata {
-                    syncing: true,
-                    network: account.network(),
-                },
-            )
-            .await
+            // don't want to require an unlock for syncing; a locked Stronghold derivation is resumed on unlock
+            if let Some(iota_address) = account_handle
+                .generate_sync_address(&account, key_index, false, bech32_hrp.clone(), !dry_run)
+                .await
             {
                 log::debug!(
                     "[SYNC] generated missing public address {} at index {}",
@@ -869,7 +1124,7 @@ async fn perform_sync(
         }
     }
     // generate missing internal addresses
-    for key_index in latest_internal_address_index..max_new_internal_index {
+    for key_index in internal_fill_start..options.address_index_end(max_new_internal_index) {
         if !account
             .addresses()
             .iter()
@@ -880,18 +1135,10 @@ async fn perform_sync(
                 .any(|a| a.key_index() == &key_index && *a.internal())
         {
             // generate address, ignore errors because Stronghold could be locked or a ledger not connected and we
-            // don't want to require an unlock for syncing
-            if let Ok(iota_address) = crate::address::get_iota_address(
-                &account,
-                key_index,
-                true,
-                bech32_hrp.clone(),
-                GenerateAddressMetadata {
-                    syncing: true,
-                    network: account.network(),
-                },
-            )
-            .await
+            // don't want to require an unlock for syncing; a locked Stronghold derivation is resumed on unlock
+            if let Some(iota_address) = account_handle
+                .generate_sync_address(&account, key_index, true, bech32_hrp.clone(), !dry_run)
+                .await
             {
                 log::debug!(
                     "[SYNC] generated missing internal address {} at index {}",
@@ -939,21 +1186,17 @@ async fn perform_sync(
             == 0
     };
 
-    if !is_latest_public_address_empty {
+    if !is_latest_public_address_empty
+        && options
+            .check_address_index(std::cmp::max(latest_public_address_index, max_new_public_index) + 1)
+            .is_ok()
+    {
         let latest_index = std::cmp::max(latest_public_address_index, max_new_public_index);
         // generate address, ignore errors because Stronghold could be locked or a ledger not connected and we don't
         // want to require an unlock for syncing
-        if let Ok(iota_address) = crate::address::get_iota_address(
-            &account,
-            latest_index + 1,
-            false,
-            bech32_hrp.clone(),
-            GenerateAddressMetadata {
-                syncing: true,
-                network: account.network(),
-            },
-        )
-        .await
+        if let Some(iota_address) = account_handle
+            .generate_sync_address(&account, latest_index + 1, false, bech32_hrp.clone(), !dry_run)
+            .await
         {
             log::debug!(
                 "[SYNC] generated new unused public address {} at index {}",
@@ -970,19 +1213,15 @@ async fn perform_sync(
         };
     }
 
-    if !is_latest_internal_address_empty {
+    if !is_latest_internal_address_empty
+        && options
+            .check_address_index(std::cmp::max(latest_internal_address_index, max_new_internal_index) + 1)
+            .is_ok()
+    {
         let latest_index = std::cmp::max(latest_internal_address_index, max_new_internal_index);
-        if let Ok(iota_address) = crate::address::get_iota_address(
-            &account,
-            latest_index + 1,
-            true,
-            bech32_hrp.clone(),
-            GenerateAddressMetadata {
-                syncing: true,
-                network: account.network(),
-            },
-        )
-        .await
+        if let Some(iota_address) = account_handle
+            .generate_sync_address(&account, latest_index + 1, true, bech32_hrp.clone(), !dry_run)
+            .await
         {
             log::debug!(
                 "[SYNC] generated new unused internal address {} at index {}",
@@ -1021,6 +1260,8 @@ async fn perform_sync(
     Ok(SyncedAccountData {
         messages: new_messages,
         addresses: addresses_to_save,
+        partial: budget.is_exceeded(),
+        retries: budget.retries(),
     })
 }
 
@@ -1082,49 +1323,71 @@ pub(crate) struct ConfirmationChangeEventData {
 pub struct AccountSynchronizer {
     account_handle: AccountHandle,
     address_index: usize,
+    address_range: Option<Range<usize>>,
     gap_limit: usize,
     skip_persistence: bool,
     skip_change_addresses: bool,
     steps: Vec<AccountSynchronizeStep>,
+    low_memory: bool,
 }
 
 #[derive(Debug)]
 pub(crate) struct SyncedAccountData {
     pub(crate) messages: Vec<SyncedMessage>,
     pub(crate) addresses: Vec<Address>,
+    /// Whether the request budget ran out before the sync finished.
+    pub(crate) partial: bool,
+    /// The number of node requests retried after a transient failure.
+    pub(crate) retries: usize,
 }
 
 impl SyncedAccountData {
+    /// Parses the synced messages, yielding them in order.
+    /// At most `PARSE_MESSAGES_CONCURRENCY` messages are parsed at the same time,
+    /// and the account addresses are shared between the parsing tasks.
+    pub(crate) fn parse_messages_stream<'a>(
+        &'a self,
+        accounts: AccountStore,
+        account: &Account,
+    ) -> impl Stream<Item = crate::Result<Message>> + 'a {
+        let client_options = Arc::new(account.client_options().clone());
+        let account_id: Arc<str> = Arc::from(account.id().as_str());
+        let account_addresses: Arc<[Address]> = Arc::from(account.addresses().as_slice());
+        futures::stream::iter(self.messages.iter())
+            .map(move |new_message| {
+                let new_message = new_message.clone();
+                let client_options = client_options.clone();
+                let account_id = account_id.clone();
+                let account_addresses = account_addresses.clone();
+                let accounts = accounts.clone();
+                async move {
+                    tokio::spawn(async move {
+                        Message::from_iota_message(
+                            new_message.id,
+                            new_message.inner,
+                            accounts,
+                            &account_id,
+                            &account_addresses,
+                            &client_options,
+                        )
+                        .with_confirmed(Some(true))
+                        .finish()
+                        .await
+                    })
+                    .await?
+                }
+            })
+            .buffered(PARSE_MESSAGES_CONCURRENCY)
+    }
+
     pub(crate) async fn parse_messages(
         &self,
         accounts: AccountStore,
         account: &Account,
     ) -> crate::Result<Vec<Message>> {
-        let mut tasks = Vec::new();
-        for new_message in self.messages.iter().cloned() {
-            let client_options = account.client_options().clone();
-            let account_id = account.id().to_string();
-            let account_addresses = account.addresses().to_vec();
-            let accounts = accounts.clone();
-            tasks.push(async move {
-                tokio::spawn(async move {
-                    Message::from_iota_message(
-                        new_message.id,
-                        new_message.inner,
-                        accounts,
-                        &account_id,
-                        &account_addresses,
-                        &client_options,
-                    )
-                    .with_confirmed(Some(true))
-                    .finish()
-                    .await
-                })
-                .await
-            });
-        }
-        let mut parsed_messages = Vec::new();
-        for message in futures::future::try_join_all(tasks).await? {
+        let mut parsed_messages = Vec::with_capacity(self.messages.len());
+        let mut stream = Box::pin(self.parse_messages_stream(accounts, account));
+        while let Some(message) = stream.next().await {
             parsed_messages.push(message?);
         }
         Ok(parsed_messages)
@@ -1210,6 +1473,7 @@ impl AccountSynchronizer {
         Self {
             account_handle,
             address_index: 0,
+            address_range: None,
             gap_limit: if latest_address_index == 0 {
                 default_gap_limit
             } else {
@@ -1217,6 +1481,7 @@ impl AccountSynchronizer {
             },
             skip_persistence: false,
             skip_change_addresses: false,
+            low_memory: false,
             steps: vec![
                 AccountSynchronizeStep::SyncAddresses(None),
                 AccountSynchronizeStep::SyncMessages,
@@ -1243,12 +1508,31 @@ impl AccountSynchronizer {
         self
     }
 
+    /// Saves the synced messages per address chunk, and per gap limit round of the address discovery, instead of
+    /// collecting them until the sync finishes,
+    /// bounding the memory used to sync large accounts on constrained devices at the cost of a slower sync.
+    /// The messages are saved and their events emitted while syncing, so they aren't returned by
+    /// [execute](#method.execute). Ignored with [skip_persistence](#method.skip_persistence).
+    pub fn low_memory(mut self) -> Self {
+        self.low_memory = true;
+        self
+    }
+
     /// Initial address index to start syncing.
     pub fn address_index(mut self, address_index: usize) -> Self {
         self.address_index = address_index;
         self
     }
 
+    /// Scans the public and change addresses of the key index range instead of discovering new addresses from the
+    /// latest one with the gap limit, e.g. when the funds are known to sit at high indexes used by another tool.
+    /// The addresses below the range aren't derived nor requested; the used addresses found in the range are added
+    /// to the account and the indexes below them are generated by the next regular sync.
+    pub fn address_range(mut self, range: Range<usize>) -> Self {
+        self.address_range.replace(range);
+        self
+    }
+
     /// Sets the steps to run on the sync process.
     /// By default it runs all steps (check_for_new_used_addresses and sync_messages),
     /// but the library can pick what to run here.
@@ -1258,17 +1542,30 @@ impl AccountSynchronizer {
     }
 
     pub(crate) async fn get_new_history(&self, return_all_addresses: bool) -> crate::Result<SyncedAccountData> {
+        self.get_new_history_internal(return_all_addresses, false, false).await
+    }
+
+    async fn get_new_history_internal(
+        &self,
+        return_all_addresses: bool,
+        flush_messages: bool,
+        dry_run: bool,
+    ) -> crate::Result<SyncedAccountData> {
         log::debug!("get_new_history");
         let change_addresses_to_sync = self.account_handle.change_addresses_to_sync.lock().await.clone();
+        let gap_limit = self.account_handle.read().await.tuned_gap_limit(self.gap_limit);
         perform_sync(
             self.account_handle.clone(),
             self.address_index,
-            self.gap_limit,
+            self.address_range.clone(),
+            gap_limit,
             self.skip_change_addresses,
             change_addresses_to_sync,
             &self.steps,
             self.account_handle.account_options,
             return_all_addresses,
+            flush_messages,
+            dry_run,
         )
         .await
     }
@@ -1332,6 +1629,56 @@ impl AccountSynchronizer {
         })
     }
 
+    /// Performs the same node queries as [execute](#method.execute) but doesn't save anything or emit events.
+    /// Instead it returns what the sync would change on the account, useful to debug incorrect balances safely.
+    pub async fn dry_run(&self) -> crate::Result<SyncPlanReport> {
+        log::debug!("[SYNC] dry_run");
+        let data = self.get_new_history_internal(false, false, true).await?;
+        let account = self.account_handle.read().await;
+        let messages_before_sync: Vec<(MessageId, Option<bool>)> = account
+            .with_messages(|messages| messages.iter().map(|m| (m.key, m.confirmed)).collect())
+            .await;
+        let parsed_messages = data
+            .parse_messages(self.account_handle.accounts.clone(), &account)
+            .await?;
+
+        let mut report = SyncPlanReport::default();
+        for address in data.addresses {
+            match account
+                .addresses()
+                .iter()
+                .find(|a| a.key_index() == address.key_index() && a.internal() == address.internal())
+            {
+                Some(existing_address) => {
+                    let output_changes = get_output_changes(existing_address.outputs(), address.outputs());
+                    if !output_changes.is_empty() {
+                        report.output_changes.extend(output_changes);
+                        report.updated_addresses.push(address);
+                    }
+                }
+                None => {
+                    report
+                        .output_changes
+                        .extend(get_output_changes(&HashMap::new(), address.outputs()));
+                    report.new_addresses.push(address);
+                }
+            }
+        }
+
+        for message in parsed_messages {
+            match messages_before_sync.iter().find(|(id, _)| id == message.id()) {
+                Some((_, confirmed)) => {
+                    if confirmed != message.confirmed() {
+                        report.confirmation_changed_messages.push(message);
+                    }
+                }
+                None => report.new_messages.push(message),
+            }
+        }
+
+        Ok(report)
+    }
+
     /// Syncs account with the tangle.
     /// The account syncing process ensures that the latest metadata (balance, transactions)
     /// associated with an account is fetched from the tangle and is stored locally.
@@ -1339,7 +1686,8 @@ impl AccountSynchronizer {
         log::debug!("[SYNC] execute");
         self.account_handle.disable_mqtt();
         let syc_start_time = std::time::Instant::now();
-        let return_value = match self.get_new_history(false).await {
+        let flush_messages = self.low_memory && !self.skip_persistence;
+        let return_value = match self.get_new_history_internal(false, flush_messages, false).await {
             Ok(data) => {
                 let is_empty = data
                     .addresses
@@ -1365,9 +1713,15 @@ impl AccountSynchronizer {
 
                 if !self.skip_persistence && (!new_addresses.is_empty() || !parsed_messages.is_empty()) {
                     account.append_addresses(new_addresses.to_vec());
+                    self.account_handle.accounts.index_addresses(&account);
                     account.save_messages(parsed_messages.to_vec()).await?;
                     account.set_last_synced_at(Some(chrono::Local::now()));
+                    let gap_limit = account.tuned_gap_limit(self.gap_limit);
+                    account.tune_gap_limit(gap_limit);
                     account.save().await?;
+                    self.account_handle
+                        .extend_known_ids(&account, &parsed_messages, &new_addresses)
+                        .await?;
                 }
 
                 let mut new_messages = Vec::new();
@@ -1384,6 +1738,21 @@ impl AccountSynchronizer {
                     }
                 }
 
+                if !self.skip_persistence {
+                    let balance_changed = new_addresses.iter().any(|address| {
+                        let address_bech32 = address.address().to_bech32();
+                        addresses_before_sync
+                            .iter()
+                            .find(|(bech32, _, _)| bech32 == &address_bech32)
+                            .map_or(true, |(_, balance, _)| *balance != address.balance())
+                    });
+                    account
+                        .record_balance_snapshot(
+                            balance_changed || !new_messages.is_empty() || !confirmation_changed_messages.is_empty(),
+                        )
+                        .await?;
+                }
+
                 let persist_events = self.account_handle.account_options.persist_events;
                 let events = Self::get_events(
                     self.account_handle.account_options,
@@ -1416,6 +1785,7 @@ impl AccountSynchronizer {
                     )
                     .await?;
                 }
+                crate::event::flush_event_batch().await;
 
                 let mut updated_messages = new_messages;
                 updated_messages.extend(confirmation_changed_messages);
@@ -1428,6 +1798,8 @@ impl AccountSynchronizer {
                     is_empty,
                     addresses: new_addresses,
                     messages: updated_messages,
+                    partial: data.partial,
+                    retries: data.retries,
                 };
                 log::debug!("[SYNC] syncing took: {:.2?}", syc_start_time.elapsed());
                 Ok(synced_account)
@@ -1469,6 +1841,172 @@ pub struct SyncedAccount {
     /// The newly generated and updated account addresses.
     #[getset(get = "pub")]
     pub(crate) addresses: Vec<Address>,
+    /// Whether the sync ran out of its request budget, see
+    /// [AccountManagerBuilder#with_max_requests_per_sync](../account_manager/struct.AccountManagerBuilder.html#method.with_max_requests_per_sync).
+    /// The rest of the account is synced on the next run.
+    #[getset(get = "pub")]
+    pub(crate) partial: bool,
+    /// The number of node requests retried after a transient failure, see
+    /// [AccountManagerBuilder#with_sync_retry](../account_manager/struct.AccountManagerBuilder.html#method.with_sync_retry).
+    #[getset(get = "pub")]
+    pub(crate) retries: usize,
+}
+
+/// The destination of the consolidated outputs.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "strategy", content = "value")]
+pub enum ConsolidationTarget {
+    /// Consolidate the outputs into the address holding them.
+    SameAddress,
+    /// Consolidate the outputs into the latest unused change address, generating a new one if needed.
+    ChangeAddress,
+    /// Consolidate the outputs into an address that must belong to the account.
+    #[serde(with = "crate::serde::iota_address_serde")]
+    Address(AddressWrapper),
+}
+
+impl Default for ConsolidationTarget {
+    fn default() -> Self {
+        Self::SameAddress
+    }
+}
+
+/// Options for [AccountHandle#consolidate_outputs_with_options](struct.AccountHandle.html#method.consolidate_outputs_with_options).
+#[derive(Debug, Clone, Deserialize)]
+pub struct ConsolidationOptions {
+    /// The destination of the consolidated outputs.
+    #[serde(default)]
+    pub target: ConsolidationTarget,
+    /// The number of equal outputs each consolidation transaction creates.
+    /// It's lowered if the outputs would be below the dust threshold.
+    #[serde(rename = "outputCount", default = "default_consolidation_output_count")]
+    pub output_count: usize,
+    /// Whether the dust allowance outputs should be consolidated or not.
+    #[serde(rename = "includeDustAllowanceOutputs", default)]
+    pub include_dust_allowance_outputs: bool,
+}
+
+fn default_consolidation_output_count() -> usize {
+    1
+}
+
+impl ConsolidationOptions {
+    /// The options configured on the account manager.
+    pub(crate) fn from_account_options(account_options: &AccountOptions, include_dust_allowance_outputs: bool) -> Self {
+        Self {
+            target: if account_options.consolidate_to_change_address {
+                ConsolidationTarget::ChangeAddress
+            } else {
+                ConsolidationTarget::SameAddress
+            },
+            output_count: account_options.consolidation_output_count,
+            include_dust_allowance_outputs,
+        }
+    }
+}
+
+/// Splits the amount into at most `count` values of the same amount, adding the rest to the first one.
+/// The count is lowered so each value is at least the dust allowance value.
+fn split_amount(amount: u64, count: usize, dust_allowance_value: u64) -> Vec<u64> {
+    let count = std::cmp::max(1, std::cmp::min(count as u64, amount / dust_allowance_value));
+    let value = amount / count;
+    (0..count)
+        .map(|i| if i == 0 { value + amount % count } else { value })
+        .collect()
+}
+
+/// Splits the consolidated amount into outputs of the same value, see [split_amount](fn.split_amount.html).
+fn split_consolidation_amount(
+    address: AddressWrapper,
+    amount: u64,
+    count: usize,
+    output_kind: Option<OutputKind>,
+    dust_allowance_value: u64,
+) -> Vec<TransferOutput> {
+    split_amount(amount, count, dust_allowance_value)
+        .into_iter()
+        // safe to unwrap: the consolidated amount is never zero and each value is at least `amount / count`
+        .map(|value| TransferOutput::new(address.clone(), NonZeroU64::new(value).unwrap(), output_kind))
+        .collect()
+}
+
+/// The kind of change a sync would apply to an address output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum OutputChangeKind {
+    /// The output isn't stored yet.
+    New,
+    /// The stored output would be marked as spent.
+    Spent,
+    /// The stored output would be marked as unspent.
+    Unspent,
+    /// The stored output wasn't returned by the node and would be removed.
+    Removed,
+}
+
+/// An address output change found on a sync dry run.
+#[derive(Debug, Clone, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct OutputChange {
+    /// The output. For removed outputs this is the stored output, otherwise the output returned by the node.
+    output: AddressOutput,
+    /// The change kind.
+    kind: OutputChangeKind,
+}
+
+/// The changes a sync would apply to the account.
+/// Returned by [AccountSynchronizer#dry_run](struct.AccountSynchronizer.html#method.dry_run).
+#[derive(Debug, Clone, Default, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct SyncPlanReport {
+    /// The addresses that would be added to the account.
+    #[serde(rename = "newAddresses")]
+    new_addresses: Vec<Address>,
+    /// The existing addresses whose outputs would change.
+    #[serde(rename = "updatedAddresses")]
+    updated_addresses: Vec<Address>,
+    /// The address output changes.
+    #[serde(rename = "outputChanges")]
+    output_changes: Vec<OutputChange>,
+    /// The messages that would be stored.
+    #[serde(rename = "newMessages")]
+    new_messages: Vec<Message>,
+    /// The stored messages whose confirmation state would change.
+    #[serde(rename = "confirmationChangedMessages")]
+    confirmation_changed_messages: Vec<Message>,
+}
+
+fn get_output_changes(
+    before_sync_outputs: &HashMap<OutputId, AddressOutput>,
+    after_sync_outputs: &HashMap<OutputId, AddressOutput>,
+) -> Vec<OutputChange> {
+    let mut output_changes = Vec::new();
+    for (output_id, output) in after_sync_outputs {
+        let kind = match before_sync_outputs.get(output_id) {
+            Some(before_sync_output) => {
+                if before_sync_output.is_spent == output.is_spent {
+                    continue;
+                } else if output.is_spent {
+                    OutputChangeKind::Spent
+                } else {
+                    OutputChangeKind::Unspent
+                }
+            }
+            None => OutputChangeKind::New,
+        };
+        output_changes.push(OutputChange {
+            output: output.clone(),
+            kind,
+        });
+    }
+    for (output_id, output) in before_sync_outputs {
+        if !after_sync_outputs.contains_key(output_id) {
+            output_changes.push(OutputChange {
+                output: output.clone(),
+                kind: OutputChangeKind::Removed,
+            });
+        }
+    }
+    output_changes
 }
 
 #[derive(Debug, Clone, Getters)]
@@ -1478,6 +2016,34 @@ pub(crate) struct SyncedAccountEvents {
     pub(crate) confirmation_change_events: Vec<ConfirmationChangeEventData>,
 }
 
+/// The maximum number of inputs of a transaction with `output_count` outputs signed by a signer with the given
+/// capabilities.
+/// Signers backed by a device, e.g. a Ledger, may sign fewer inputs and outputs than the protocol allows.
+pub(crate) fn max_inputs(
+    output_count: usize,
+    signer_capabilities: &SignerCapabilities,
+    max_input_output_count: usize,
+) -> crate::Result<usize> {
+    match signer_capabilities.max_input_output_count() {
+        Some(signer_max_input_output_count) => {
+            // -1 because we need at least one input and the limit is for inputs and outputs together
+            if output_count >= signer_max_input_output_count - 1 {
+                return Err(crate::Error::TooManyOutputs(
+                    output_count,
+                    signer_max_input_output_count - 1,
+                ));
+            }
+            Ok(signer_max_input_output_count - output_count)
+        }
+        None => {
+            if output_count >= max_input_output_count {
+                return Err(crate::Error::TooManyOutputs(output_count, max_input_output_count));
+            }
+            Ok(max_input_output_count)
+        }
+    }
+}
+
 impl SyncedAccount {
     /// Emulates a synced account from an account handle.
     /// Should only be used if sync is guaranteed (e.g. when using MQTT)
@@ -1493,6 +2059,8 @@ impl SyncedAccount {
             is_empty: false,
             messages: Default::default(),
             addresses: Default::default(),
+            partial: false,
+            retries: 0,
         }
     }
 
@@ -1508,58 +2076,38 @@ impl SyncedAccount {
     ///
     /// Returns a (addresses, address) tuple representing the selected input addresses and the remainder address if
     /// needed.
+    #[allow(clippy::too_many_arguments)]
     fn select_inputs(
         &self,
-        locked_outputs: &mut MutexGuard<'_, Vec<AddressOutput>>,
+        locked_outputs: &mut MutexGuard<'_, LockedOutputs>,
+        lock_owner: &Weak<()>,
         transfer_obj: &Transfer,
         available_outputs: Vec<input_selection::AddressInputs>,
-        signer_type: SignerType,
+        signer_capabilities: &SignerCapabilities,
+        dust_allowance_value: u64,
+        max_input_output_count: usize,
     ) -> crate::Result<(Vec<input_selection::AddressInputs>, Option<input_selection::Remainder>)> {
-        let output_amount = transfer_obj.outputs.len();
-        let max_inputs = match signer_type {
-            #[cfg(feature = "ledger-nano")]
-            SignerType::LedgerNano => {
-                // -1 because we need at least one input and the limit is for inputs and outputs together
-                if output_amount >= LEDGER_MAX_IN_OUTPUTS - 1 {
-                    return Err(crate::Error::TooManyOutputs(output_amount, LEDGER_MAX_IN_OUTPUTS - 1));
-                }
-                LEDGER_MAX_IN_OUTPUTS - output_amount
-            }
-            #[cfg(feature = "ledger-nano-simulator")]
-            SignerType::LedgerNanoSimulator => {
-                // -1 because we need at least one input and the limit is for inputs and outputs together
-                if output_amount >= LEDGER_MAX_IN_OUTPUTS - 1 {
-                    return Err(crate::Error::TooManyOutputs(output_amount, LEDGER_MAX_IN_OUTPUTS - 1));
-                }
-                LEDGER_MAX_IN_OUTPUTS - output_amount
-            }
-            _ => {
-                if output_amount >= INPUT_OUTPUT_COUNT_MAX {
-                    return Err(crate::Error::TooManyOutputs(output_amount, INPUT_OUTPUT_COUNT_MAX));
-                }
-                INPUT_OUTPUT_COUNT_MAX
-            }
-        };
+        let max_inputs = max_inputs(transfer_obj.output_count(), signer_capabilities, max_input_output_count)?;
 
         let mut available_inputs: Vec<input_selection::Input> = Vec::new();
         for address_input in available_outputs {
-            let filtered: Vec<AddressOutput> = address_input.clone()
+            let filtered: Vec<AddressOutput> = address_input
+                .clone()
                 .outputs
                 .clone()
                 .into_iter()
                 .filter(|output| {
                     (!transfer_obj.outputs.iter().any(|transfer_output| transfer_output.address == output.address)
                         && *output.amount() > 0
-                        && !locked_outputs.iter().any(|locked_output| locked_output.transaction_id == output.transaction_id && locked_output.index == output.index)
+                        && !locked_outputs.is_locked(output)
                         // we allow an input equal to a deposit address only if it has balance <= transfer amount, so there
                         // can't be a remainder value with this address as input alone
                     || transfer_obj.outputs.iter().any(|o| &o.address == output.address())
                         && *output.amount() <= transfer_obj.amount())
                         && *output.amount() > 0
-                        && !locked_outputs.iter().any(|locked_output| {
-                            locked_output.transaction_id == output.transaction_id && locked_output.index == output.index
-                        })
-                }).collect();
+                        && !locked_outputs.is_locked(output)
+                })
+                .collect();
             for output in filtered {
                 available_inputs.push(input_selection::Input {
                     internal: address_input.internal,
@@ -1568,8 +2116,13 @@ impl SyncedAccount {
             }
         }
 
-        let selected_outputs = input_selection::select_input(transfer_obj.amount(), available_inputs, max_inputs)?;
-        locked_outputs.extend(selected_outputs.iter().map(|input| input.output.clone()));
+        let selected_outputs = input_selection::select_input(
+            transfer_obj.amount(),
+            available_inputs,
+            max_inputs,
+            dust_allowance_value,
+        )?;
+        locked_outputs.lock(selected_outputs.iter().map(|input| input.output.clone()), lock_owner);
 
         let inputs_amount = selected_outputs.iter().fold(0, |acc, a| acc + a.output.amount);
         let has_remainder = inputs_amount > transfer_obj.amount();
@@ -1616,35 +2169,76 @@ impl SyncedAccount {
         Ok((selected_address_outputs.into_values().collect(), remainder))
     }
 
-    async fn get_output_consolidation_transfers(
+    /// Resolves the address receiving the consolidated outputs, `None` meaning the address holding them.
+    async fn consolidation_target_address(
         &self,
-        include_dust_allowance_outputs: bool,
-    ) -> crate::Result<Vec<Transfer>> {
+        target: &ConsolidationTarget,
+    ) -> crate::Result<Option<AddressWrapper>> {
+        match target {
+            ConsolidationTarget::SameAddress => Ok(None),
+            ConsolidationTarget::Address(address) => {
+                let account = self.account_handle.read().await;
+                if !account.addresses().iter().any(|a| a.address() == address) {
+                    return Err(crate::Error::InvalidAddress);
+                }
+                Ok(Some(address.clone()))
+            }
+            ConsolidationTarget::ChangeAddress => {
+                let mut account = self.account_handle.write().await;
+                let key_index = match account.latest_change_address() {
+                    Some(address) if address.outputs().is_empty() => return Ok(Some(address.address().clone())),
+                    Some(address) => address.key_index() + 1,
+                    None => 0,
+                };
+                self.account_handle.account_options.check_address_index(key_index)?;
+                let change_address = crate::address::get_new_change_address(
+                    &account,
+                    key_index,
+                    account.bech32_hrp(),
+                    GenerateAddressMetadata {
+                        syncing: true,
+                        network: account.network(),
+                    },
+                )
+                .await?;
+                log::debug!(
+                    "[CONSOLIDATION] generated new change address as consolidation target: {}",
+                    change_address.address().to_bech32()
+                );
+                account.append_addresses(vec![change_address.clone()]);
+                self.account_handle.accounts.index_addresses(&account);
+                account.save().await?;
+                self.account_handle.monitor_address(change_address.address().clone());
+                Ok(Some(change_address.address().clone()))
+            }
+        }
+    }
+
+    async fn get_output_consolidation_transfers(&self, options: &ConsolidationOptions) -> crate::Result<Vec<Transfer>> {
+        let target_address = self.consolidation_target_address(&options.target).await?;
         let mut transfers: Vec<Transfer> = Vec::new();
         // collect the transactions we need to make
         {
             let account = self.account_handle.read().await;
             let sent_messages = account.list_messages(0, 0, Some(MessageType::Sent)).await?;
+            // take the signer limits into account, -1 because we will generate one output
+            let max_inputs = crate::signing::signer_capabilities(account.signer_type())
+                .await
+                .max_input_output_count()
+                .unwrap_or_else(|| account.client_options().network_parameters().max_input_output_count())
+                - 1;
             for address in account.addresses() {
                 if address.outputs().len() >= self.account_handle.account_options.output_consolidation_threshold {
                     let mut address_outputs = address.available_outputs(&sent_messages);
-                    if !include_dust_allowance_outputs {
+                    if !options.include_dust_allowance_outputs {
                         address_outputs.retain(|addr| addr.kind != OutputKind::SignatureLockedDustAllowance);
                     }
 
                     // the address outputs exceed the threshold, so we push a transfer to our vector
                     if address_outputs.len() >= self.account_handle.account_options.output_consolidation_threshold {
-                        // take hardware limits of ledger nano into account
-                        let max_inputs = match account.signer_type {
-                            #[cfg(feature = "ledger-nano")]
-                            SignerType::LedgerNano => LEDGER_MAX_IN_OUTPUTS - 1,
-                            #[cfg(feature = "ledger-nano-simulator")]
-                            SignerType::LedgerNanoSimulator => LEDGER_MAX_IN_OUTPUTS - 1,
-                            _ => INPUT_OUTPUT_COUNT_MAX - 1,
-                        };
                         for outputs in address_outputs.chunks(max_inputs) {
                             // Only create dust_allowance_output if an input is also a dust_allowance_outputs
-                            let output_kind = if include_dust_allowance_outputs
+                            let output_kind = if options.include_dust_allowance_outputs
                                 && outputs
                                     .iter()
                                     .any(|addr| addr.kind == OutputKind::SignatureLockedDustAllowance)
@@ -1653,18 +2247,22 @@ impl SyncedAccount {
                             } else {
                                 None
                             };
+                            let target = target_address.clone().unwrap_or_else(|| address.address().clone());
+                            let amount = outputs.iter().fold(0, |v, o| v + o.amount);
                             transfers.push(
-                                Transfer::builder(
-                                    address.address().clone(),
-                                    NonZeroU64::new(outputs.iter().fold(0, |v, o| v + o.amount)).unwrap(),
+                                Transfer::builder_with_outputs(split_consolidation_amount(
+                                    target,
+                                    amount,
+                                    std::cmp::min(options.output_count, max_inputs),
                                     output_kind,
-                                )
+                                    account.client_options().dust_protection().allowance_value(),
+                                ))?
                                 .with_input(
                                     address.address().clone(),
                                     outputs.iter().map(|o| (*o).clone()).collect(),
                                 )
                                 .with_events(false)
-                                .finish(),
+                                .finish()?,
                             );
                         }
                     }
@@ -1675,17 +2273,11 @@ impl SyncedAccount {
     }
 
     /// Consolidate account outputs.
-    pub(crate) async fn consolidate_outputs(
-        &self,
-        include_dust_allowance_outputs: bool,
-    ) -> crate::Result<Vec<Message>> {
+    pub(crate) async fn consolidate_outputs(&self, options: ConsolidationOptions) -> crate::Result<Vec<Message>> {
         log::debug!("consolidate_outputs");
         let mut tasks = Vec::new();
         // run the transfers in parallel
-        for transfer in self
-            .get_output_consolidation_transfers(include_dust_allowance_outputs)
-            .await?
-        {
+        for transfer in self.get_output_consolidation_transfers(&options).await? {
             let task = self.transfer(transfer);
             tasks.push(task);
         }
@@ -1752,13 +2344,11 @@ impl SyncedAccount {
         }
 
         // -1 because we will generate one output
-        let max_inputs = match account.signer_type {
-            #[cfg(feature = "ledger-nano")]
-            SignerType::LedgerNano => LEDGER_MAX_IN_OUTPUTS - 1,
-            #[cfg(feature = "ledger-nano-simulator")]
-            SignerType::LedgerNanoSimulator => LEDGER_MAX_IN_OUTPUTS - 1,
-            _ => INPUT_OUTPUT_COUNT_MAX - 1,
-        };
+        let max_inputs = crate::signing::signer_capabilities(account.signer_type())
+            .await
+            .max_input_output_count()
+            .unwrap_or_else(|| account.client_options().network_parameters().max_input_output_count())
+            - 1;
         let available_outputs = match custom_inputs {
             Some(inputs) => inputs,
             None => {
@@ -1822,7 +2412,7 @@ impl SyncedAccount {
                     .with_inputs(outputs.iter().map(|o| (*o).clone()).collect())
                     .with_events(true)
                     .with_indexation(indexation_payload.clone())
-                    .finish(),
+                    .finish()?,
                 );
             }
         } else {
@@ -1859,8 +2449,40 @@ impl SyncedAccount {
     }
 
     /// Send messages.
-    pub(crate) async fn transfer(&self, mut transfer_obj: Transfer) -> crate::Result<Message> {
+    pub(crate) async fn transfer(&self, transfer_obj: Transfer) -> crate::Result<Message> {
+        if *self.account_handle.read().await.cold_storage() {
+            return Err(crate::Error::ColdStorageAccount);
+        }
+        self.account_handle.last_transfer_diagnostics.lock().await.take();
+        let outcome = self.transfer_internal(transfer_obj, false).await;
+        match self.with_transfer_diagnostics(outcome).await? {
+            TransferOutcome::Sent(message) => Ok(message),
+            TransferOutcome::SigningRequested(_) => unreachable!("the transfer isn't prepare only"),
+        }
+    }
+
+    /// Selects the inputs and builds the transfer essence, returning the artifacts to sign it offline.
+    pub(crate) async fn prepare_signing_request(&self, transfer_obj: Transfer) -> crate::Result<SigningRequest> {
+        self.account_handle.last_transfer_diagnostics.lock().await.take();
+        let outcome = self.transfer_internal(transfer_obj, true).await;
+        match self.with_transfer_diagnostics(outcome).await? {
+            TransferOutcome::SigningRequested(request) => Ok(*request),
+            TransferOutcome::Sent(_) => unreachable!("the transfer is prepare only"),
+        }
+    }
+
+    async fn transfer_internal(
+        &self,
+        mut transfer_obj: Transfer,
+        prepare_only: bool,
+    ) -> crate::Result<TransferOutcome> {
         log::debug!("[TRANSFER] transfer");
+        // the shutdown waits for the transfer to store its message
+        let in_flight = in_flight::InFlightTransfer::start(self.account_handle.accounts.in_flight_transfers())?;
+        self.account_handle.check_frozen().await?;
+        if !prepare_only {
+            self.account_handle.verify_seed_fingerprint().await?;
+        }
         let account_ = self.account_handle.read().await;
 
         // validate ledger seed for ledger accounts
@@ -1901,6 +2523,9 @@ impl SyncedAccount {
             transfer_obj.remainder_value_strategy = RemainderValueStrategy::ReuseAddress;
         }
 
+        // release the outputs of transfers that died without unlocking them
+        self.account_handle.release_stale_output_locks().await;
+        let signer_capabilities = crate::signing::signer_capabilities(account_.signer_type()).await;
         // lock the transfer process until we select the input (outputs)
         // we do this to prevent multiple threads trying to transfer at the same time
         // so it doesn't consume the same outputs multiple times, which leads to a conflict state
@@ -1935,10 +2560,19 @@ impl SyncedAccount {
             Option<input_selection::Remainder>,
         ) = match transfer_obj.input.take() {
             Some(addresses_inputs) => {
+                let max_inputs = max_inputs(
+                    transfer_obj.output_count(),
+                    &signer_capabilities,
+                    account_.client_options().network_parameters().max_input_output_count(),
+                )?;
+                let inputs_count: usize = addresses_inputs.iter().map(|(_, outputs)| outputs.len()).sum();
+                if inputs_count > max_inputs {
+                    return Err(crate::Error::TooManyInputs(inputs_count, max_inputs));
+                }
                 let mut address_inputs = Vec::new();
                 for address_input in addresses_inputs {
                     if let Some(address) = account_.addresses().iter().find(|a| a.address() == &address_input.0) {
-                        locked_outputs.extend(address_input.1.iter().cloned());
+                        locked_outputs.lock(address_input.1.iter().cloned(), &in_flight.lock_owner());
                         address_inputs.push(input_selection::AddressInputs {
                             internal: *address.internal(),
                             address: address.address().clone(),
@@ -1948,7 +2582,22 @@ impl SyncedAccount {
                         return Err(crate::Error::InputAddressNotFound);
                     }
                 }
-                (address_inputs, None)
+                // keep the value the given inputs hold over the transfer amount on the first input address
+                let inputs_amount: u64 = address_inputs
+                    .iter()
+                    .flat_map(|address_input| address_input.outputs.iter())
+                    .map(|output| output.amount)
+                    .sum();
+                let remainder = if inputs_amount > transfer_obj.amount() {
+                    address_inputs.first().map(|address_input| input_selection::Remainder {
+                        address: address_input.address.clone(),
+                        internal: address_input.internal,
+                        amount: inputs_amount,
+                    })
+                } else {
+                    None
+                };
+                (address_inputs, remainder)
             }
             None => {
                 transfer_obj
@@ -1969,11 +2618,16 @@ impl SyncedAccount {
                     })
                     .collect();
 
-                let signer_type = account_.signer_type().clone();
-
                 // select the input addresses and check if a remainder address is needed
-                let (selected_inputs, remainder_address) =
-                    self.select_inputs(&mut locked_outputs, &transfer_obj, available_outputs, signer_type)?;
+                let (selected_inputs, remainder_address) = self.select_inputs(
+                    &mut locked_outputs,
+                    &in_flight.lock_owner(),
+                    &transfer_obj,
+                    available_outputs,
+                    &signer_capabilities,
+                    account_.client_options().dust_protection().allowance_value(),
+                    account_.client_options().network_parameters().max_input_output_count(),
+                )?;
                 (selected_inputs, remainder_address)
             }
         };
@@ -1993,21 +2647,22 @@ impl SyncedAccount {
             &input_addresses,
             self.account_handle.clone(),
             remainder_address,
+            prepare_only,
         )
         .await;
 
         let mut locked_outputs = account_outputs_locker.lock().await;
-        for input_address in &input_addresses {
-            let index = locked_outputs
-                .iter()
-                .position(|a| {
-                    input_address
-                        .outputs
-                        .iter()
-                        .any(|output| output.transaction_id == a.transaction_id && output.index == a.index)
-                })
-                .unwrap();
-            locked_outputs.remove(index);
+        for output in input_addresses
+            .iter()
+            .flat_map(|input_address| input_address.outputs.iter())
+        {
+            if !locked_outputs.unlock(output) {
+                log::warn!(
+                    "[TRANSFER] output {}:{} was released before the transfer finished",
+                    output.transaction_id,
+                    output.index
+                );
+            }
         }
 
         res
@@ -2029,22 +2684,83 @@ impl SyncedAccount {
     }
 }
 
+/// The result of a transfer.
+enum TransferOutcome {
+    /// The transfer was signed and sent.
+    Sent(Message),
+    /// The transfer stopped after the essence construction.
+    SigningRequested(Box<SigningRequest>),
+}
+
+/// Whether the remainder deposit address must be displayed on the signer device for the user to verify it, according to
+/// the change address verification policy. The decision is emitted as a transfer progress event.
+async fn verify_remainder_address_on_device(
+    transfer_obj: &Transfer,
+    account: &Account,
+    policy: ChangeAddressVerification,
+    address: &AddressWrapper,
+    remainder_value: u64,
+) -> bool {
+    let verify_on_device = crate::signing::signer_capabilities(account.signer_type())
+        .await
+        .internal_address_display()
+        && policy.requires_verification(remainder_value);
+    log::debug!(
+        "[TRANSFER] remainder address {} verified on device: {}",
+        address.to_bech32(),
+        verify_on_device
+    );
+    transfer_obj
+        .emit_event_if_needed(
+            account.id().to_string(),
+            TransferProgressType::RemainderAddressVerification(RemainderAddressVerificationData {
+                address: address.to_bech32(),
+                remainder_value,
+                verify_on_device,
+            }),
+        )
+        .await;
+    verify_on_device
+}
+
+/// Regenerates the change address with a device prompt so the user can verify it, failing if the device derives
+/// another address.
+async fn confirm_change_address_on_device(account: &Account, address: &Address) -> crate::Result<()> {
+    log::debug!("[TRANSFER] regenerate address so it's displayed on the ledger");
+    let regenerated_address = crate::address::get_new_change_address(
+        account,
+        *address.key_index(),
+        account.bech32_hrp(),
+        GenerateAddressMetadata {
+            syncing: false,
+            network: account.network(),
+        },
+    )
+    .await?;
+    if address.address().inner != regenerated_address.address().inner {
+        return Err(crate::Error::LedgerMnemonicMismatch);
+    }
+    Ok(())
+}
+
 async fn perform_transfer(
     transfer_obj: Transfer,
     input_addresses: &[input_selection::AddressInputs],
     account_handle: AccountHandle,
     remainder_address: Option<input_selection::Remainder>,
-) -> crate::Result<Message> {
+    prepare_only: bool,
+) -> crate::Result<TransferOutcome> {
     log::debug!("[TRANSFER] perform_transfer");
     let mut utxos = vec![];
     let mut transaction_inputs = vec![];
     // store (amount, address, new_created) to check later if dust is allowed
     let mut dust_and_allowance_recorders = Vec::new();
     let transfer_amount = transfer_obj.amount();
+    let dust_protection = *account_handle.read().await.client_options().dust_protection();
 
     let mut outputs_for_event: Vec<TransactionIO> = Vec::new();
     for output in transfer_obj.outputs.iter() {
-        if transfer_amount < DUST_ALLOWANCE_VALUE {
+        if transfer_amount < dust_protection.allowance_value() {
             dust_and_allowance_recorders.push((output.amount.get(), output.address.to_bech32(), true));
         }
         outputs_for_event.push(TransactionIO {
@@ -2101,7 +2817,7 @@ async fn perform_transfer(
     for (utxo, address_index, address_internal, bee_address) in utxos {
         let (amount, address) = match utxo.kind {
             OutputKind::SignatureLockedSingle => {
-                if utxo.amount < DUST_ALLOWANCE_VALUE {
+                if utxo.amount < dust_protection.allowance_value() {
                     dust_and_allowance_recorders.push((utxo.amount, utxo.address.to_bech32(), false));
                 }
                 (utxo.amount, utxo.address.to_bech32())
@@ -2152,7 +2868,7 @@ async fn perform_transfer(
             );
 
             let remaining_balance_on_source = current_output_sum - transfer_amount;
-            if remaining_balance_on_source < DUST_ALLOWANCE_VALUE && remaining_balance_on_source != 0 {
+            if remaining_balance_on_source < dust_protection.allowance_value() && remaining_balance_on_source != 0 {
                 dust_and_allowance_recorders.push((remaining_balance_on_source, utxo.address().to_bech32(), true));
             }
         } else {
@@ -2165,7 +2881,7 @@ async fn perform_transfer(
 
             if current_output_sum > transfer_amount {
                 let remaining_balance_on_source = current_output_sum - transfer_amount;
-                if remaining_balance_on_source < DUST_ALLOWANCE_VALUE && remaining_balance_on_source != 0 {
+                if remaining_balance_on_source < dust_protection.allowance_value() && remaining_balance_on_source != 0 {
                     dust_and_allowance_recorders.push((remaining_balance_on_source, utxo.address().to_bech32(), true));
                 }
             }
@@ -2179,6 +2895,11 @@ async fn perform_transfer(
 
     // if there's remainder value, we check the strategy defined in the transfer
     let mut remainder_value_deposit_address = None;
+    // the remainder outputs besides the remainder deposit address when the remainder value is split
+    let mut split_remainder_outputs: Vec<(AddressWrapper, u64)> = Vec::new();
+    // the change addresses generated to split the remainder value, stored once the essence is built
+    let mut split_change_addresses: Vec<Address> = Vec::new();
+    let mut new_split_change_addresses: Vec<Address> = Vec::new();
     let remainder_deposit_address = if remainder_value > 0 {
         let remainder_address = remainder_address.as_ref().expect("remainder address not defined");
         let remainder_address = account_
@@ -2214,34 +2935,22 @@ async fn perform_transfer(
                                 }),
                             )
                             .await;
-                        #[cfg(any(feature = "ledger-nano", feature = "ledger-nano-simulator"))]
+                        if verify_remainder_address_on_device(
+                            &transfer_obj,
+                            &account_,
+                            account_handle.account_options.change_address_verification,
+                            address.address(),
+                            remainder_value,
+                        )
+                        .await
                         {
-                            let ledger = match account_.signer_type() {
-                                #[cfg(feature = "ledger-nano")]
-                                SignerType::LedgerNano => true,
-                                #[cfg(feature = "ledger-nano-simulator")]
-                                SignerType::LedgerNanoSimulator => true,
-                                _ => false,
-                            };
-                            if ledger {
-                                log::debug!("[TRANSFER] regnerate address so it's displayed on the ledger");
-                                let regenerated_address = crate::address::get_new_change_address(
-                                    &account_,
-                                    *address.key_index(),
-                                    account_.bech32_hrp(),
-                                    GenerateAddressMetadata {
-                                        syncing: false,
-                                        network: account_.network(),
-                                    },
-                                )
-                                .await?;
-                                if address.address().inner != regenerated_address.address().inner {
-                                    return Err(crate::Error::LedgerMnemonicMismatch);
-                                }
-                            }
+                            confirm_change_address_on_device(&account_, address).await?;
                         }
                         address.clone()
                     } else {
+                        account_handle
+                            .account_options
+                            .check_address_index(address.key_index() + 1)?;
                         let address = crate::address::get_new_change_address(
                             &account_,
                             // Index +1 because we want a new address
@@ -2265,37 +2974,22 @@ async fn perform_transfer(
                                 }),
                             )
                             .await;
-                        #[cfg(any(feature = "ledger-nano", feature = "ledger-nano-simulator"))]
+                        if verify_remainder_address_on_device(
+                            &transfer_obj,
+                            &account_,
+                            account_handle.account_options.change_address_verification,
+                            address.address(),
+                            remainder_value,
+                        )
+                        .await
                         {
-                            let ledger = match account_.signer_type() {
-                                #[cfg(feature = "ledger-nano")]
-                                SignerType::LedgerNano => true,
-                                #[cfg(feature = "ledger-nano-simulator")]
-                                SignerType::LedgerNanoSimulator => true,
-                                _ => false,
-                            };
-                            if ledger {
-                                log::debug!("[TRANSFER] regnerate address so it's displayed on the ledger");
-                                let regenerated_address = crate::address::get_new_change_address(
-                                    &account_,
-                                    *address.key_index(),
-                                    account_.bech32_hrp(),
-                                    GenerateAddressMetadata {
-                                        syncing: false,
-                                        network: account_.network(),
-                                    },
-                                )
-                                .await?;
-                                if address.address().inner != regenerated_address.address().inner {
-                                    return Err(crate::Error::LedgerMnemonicMismatch);
-                                }
-                            }
+                            confirm_change_address_on_device(&account_, &address).await?;
                         }
                         address
                     }
                 } else {
                     // Generate an address with syncing: true so it doesn't get displayed, then generate it with
-                    // syncing:false so the user can verify it on the ledger
+                    // syncing:false so the user can verify it on the ledger if the policy requires it
                     let change_address_for_event = crate::address::get_new_change_address(
                         &account_,
                         // Index 0 because it's the first address
@@ -2315,24 +3009,25 @@ async fn perform_transfer(
                             }),
                         )
                         .await;
-                    let change_address = crate::address::get_new_change_address(
+                    if verify_remainder_address_on_device(
+                        &transfer_obj,
                         &account_,
-                        // Index 0 because it's the first address
-                        0,
-                        account_.bech32_hrp(),
-                        GenerateAddressMetadata {
-                            syncing: false,
-                            network: account_.network(),
-                        },
+                        account_handle.account_options.change_address_verification,
+                        change_address_for_event.address(),
+                        remainder_value,
                     )
-                    .await?;
+                    .await
+                    {
+                        confirm_change_address_on_device(&account_, &change_address_for_event).await?;
+                    }
                     log::debug!(
                         "[TRANSFER] generated new change address as remainder target: {}",
-                        change_address.address().to_bech32()
+                        change_address_for_event.address().to_bech32()
                     );
-                    change_address
+                    change_address_for_event
                 };
                 account_.append_addresses(vec![change_address.clone()]);
+                account_handle.accounts.index_addresses(&account_);
                 account_.save().await?;
                 addresses_to_watch.push(change_address.address().clone());
 
@@ -2343,6 +3038,76 @@ async fn perform_transfer(
                     .insert(change_address.address().clone());
                 change_address.address().clone()
             }
+            // generate new change addresses to split the remainder value across them
+            RemainderValueStrategy::SplitChange(count) => {
+                let amounts = split_amount(remainder_value, count.get(), dust_protection.allowance_value());
+                // the latest change address is reused if it never received funds
+                let reused_change_address = account_
+                    .latest_change_address()
+                    .filter(|address| address.outputs().is_empty())
+                    .cloned();
+                let start_index = match account_.latest_change_address() {
+                    Some(address) if reused_change_address.is_some() => *address.key_index(),
+                    Some(address) => address.key_index() + 1,
+                    None => 0,
+                };
+                account_handle
+                    .account_options
+                    .check_address_index(start_index + amounts.len() - 1)?;
+
+                let mut change_addresses: Vec<Address> = reused_change_address.into_iter().collect();
+                let mut new_change_addresses = Vec::new();
+                for key_index in start_index + change_addresses.len()..start_index + amounts.len() {
+                    let address = crate::address::get_new_change_address(
+                        &account_,
+                        key_index,
+                        account_.bech32_hrp(),
+                        GenerateAddressMetadata {
+                            syncing: true,
+                            network: account_.network(),
+                        },
+                    )
+                    .await?;
+                    new_change_addresses.push(address.clone());
+                    change_addresses.push(address);
+                }
+                log::debug!(
+                    "[TRANSFER] splitting the remainder value {} across {} change addresses",
+                    remainder_value,
+                    change_addresses.len()
+                );
+
+                let first_change_address = &change_addresses[0];
+                transfer_obj
+                    .emit_event_if_needed(
+                        account_id.clone(),
+                        TransferProgressType::GeneratingRemainderDepositAddress(AddressData {
+                            address: first_change_address.address().to_bech32(),
+                        }),
+                    )
+                    .await;
+                if verify_remainder_address_on_device(
+                    &transfer_obj,
+                    &account_,
+                    account_handle.account_options.change_address_verification,
+                    first_change_address.address(),
+                    amounts[0],
+                )
+                .await
+                {
+                    confirm_change_address_on_device(&account_, first_change_address).await?;
+                }
+
+                // the first address holds the remainder value signed as remainder, the others are extra outputs
+                for (change_address, amount) in change_addresses.iter().zip(amounts.iter()).skip(1) {
+                    split_remainder_outputs.push((change_address.address().clone(), *amount));
+                }
+                remainder_value = amounts[0];
+                let first_change_address = first_change_address.address().clone();
+                new_split_change_addresses = new_change_addresses;
+                split_change_addresses = change_addresses;
+                first_change_address
+            }
             // keep the remainder value on the address
             RemainderValueStrategy::ReuseAddress => {
                 let address = remainder_address.address().clone();
@@ -2359,7 +3124,7 @@ async fn perform_transfer(
     };
 
     if let Some(remainder_deposit_address) = &remainder_deposit_address {
-        if remainder_value < DUST_ALLOWANCE_VALUE {
+        if remainder_value < dust_protection.allowance_value() {
             dust_and_allowance_recorders.push((remainder_value, remainder_deposit_address.to_bech32(), true));
         }
         outputs_for_event.push(TransactionIO {
@@ -2368,6 +3133,14 @@ async fn perform_transfer(
             remainder: Some(true),
         });
     }
+    for (address, amount) in &split_remainder_outputs {
+        outputs_for_essence.push(SignatureLockedSingleOutput::new(*address.as_ref(), *amount)?.into());
+        outputs_for_event.push(TransactionIO {
+            address: address.to_bech32(),
+            amount: *amount,
+            remainder: Some(true),
+        });
+    }
 
     let client = crate::client::get_client(account_.client_options()).await?;
     let client_ = client.read().await;
@@ -2383,7 +3156,21 @@ async fn perform_transfer(
             .filter(|d| d.1 == address)
             .map(|(amount, _, flag)| (*amount, *flag))
             .collect();
-        is_dust_allowed(&account_, &client_, address, created_or_consumed_outputs).await?;
+        if let Some(dust_analysis) = dust_analysis(
+            &account_,
+            &client_,
+            &dust_protection,
+            address.clone(),
+            created_or_consumed_outputs,
+        )
+        .await?
+        {
+            account_handle.record_dust_analysis(dust_analysis).await;
+            return Err(crate::Error::DustError(format!(
+                "No dust output allowed on address {}",
+                address
+            )));
+        }
     }
 
     // Build transaction essence
@@ -2408,16 +3195,44 @@ async fn perform_transfer(
     let essence = essence_builder.finish()?;
     let essence = Essence::Regular(essence);
 
+    if !split_change_addresses.is_empty() {
+        account_.append_addresses(new_split_change_addresses);
+        account_handle.accounts.index_addresses(&account_);
+        account_.save().await?;
+        let mut change_addresses_to_sync = account_handle.change_addresses_to_sync.lock().await;
+        for change_address in &split_change_addresses {
+            addresses_to_watch.push(change_address.address().clone());
+            change_addresses_to_sync.insert(change_address.address().clone());
+        }
+    }
+
+    let prepared_transaction_data = PreparedTransactionData {
+        inputs: inputs_for_event,
+        outputs: outputs_for_event,
+        data: indexation_data.clone(),
+    };
+    transfer_obj.check_pre_sign_hook(&prepared_transaction_data)?;
     transfer_obj
         .emit_event_if_needed(
             account_id.clone(),
-            TransferProgressType::PreparedTransaction(PreparedTransactionData {
-                inputs: inputs_for_event,
-                outputs: outputs_for_event,
-                data: indexation_data.clone(),
-            }),
+            TransferProgressType::PreparedTransaction(prepared_transaction_data),
         )
         .await;
+    if prepare_only {
+        log::debug!("[TRANSFER] returning the signing request");
+        return Ok(TransferOutcome::SigningRequested(Box::new(SigningRequest::new(
+            account_id,
+            &essence,
+            transaction_inputs
+                .into_iter()
+                .zip(address_inputs_for_validation.into_iter())
+                .map(|(input, (_, address))| (input, AddressWrapper::new(address, account_.bech32_hrp())))
+                .collect(),
+            remainder_address.map(|remainder| remainder.address),
+            remainder_value,
+            remainder_deposit_address,
+        ))));
+    }
     transfer_obj
         .emit_event_if_needed(account_id.clone(), TransferProgressType::SigningTransaction)
         .await;
@@ -2455,41 +3270,53 @@ async fn perform_transfer(
         .emit_event_if_needed(account_id.clone(), TransferProgressType::PerformingPoW)
         .await;
 
-    // Drop account so we don't lock it during PoW and submitting
+    let network_parameters = account_.client_options().network_parameters().clone();
+    // Drop account and client so we don't lock them during PoW and submitting
     drop(account_);
+    drop(client_);
 
-    let message = finish_pow(&client_, Some(Payload::Transaction(Box::new(transaction)))).await?;
+    let message = crate::client::finish_pow(
+        client.clone(),
+        Some(Payload::Transaction(Box::new(transaction))),
+        &network_parameters,
+        account_handle.accounts.pow_pool(),
+    )
+    .await?;
 
     log::debug!("[TRANSFER] submitting message {:#?}", message);
     transfer_obj
         .emit_event_if_needed(account_id, TransferProgressType::Broadcasting)
         .await;
 
-    let message_id = match client_.post_message(&message).await {
+    let client_options = account_handle.client_options().await;
+    let message_id = match crate::client::post_message(&client_options, &message).await {
         Ok(message_id) => message_id,
         // Ignore errors from posting the message, the wallet will try to submit the message later during syncing again
         Err(_) => message.id().0,
     };
-
-    // drop the client ref so it doesn't lock the Message parsing
-    drop(client_);
+    let transfer_timings = transfer_obj.finish_timings();
+    log::debug!("[TRANSFER] timings: {:?}", transfer_timings);
 
     let new_client = client.clone();
     let new_account_handle = account_handle.clone();
     // Spawn a thread to monitor the new sent transaction so the account gets updated faster
     tokio::spawn(async move {
         log::debug!("[TRANSFER] Checking confirmation for {}", message_id);
-        let client = new_client.read().await;
-        let mut confirmed = false;
-        if let Ok(_messages) = client.retry_until_included(&message_id, None, None).await {
-            confirmed = true;
+        let client_options = new_account_handle.client_options().await;
+        // prefer the MQTT metadata topic and fallback to polling the node
+        let mut confirmed = crate::monitor::watch_confirmation(&client_options, message_id).await;
+        if !confirmed {
+            let client = new_client.read().await;
+            if let Ok(_messages) = client.retry_until_included(&message_id, None, None).await {
+                confirmed = true;
+            }
+            // drop client so it doesn't deadlock in syncing
+            drop(client);
         }
 
-        // drop client so it doesn't deadlock in syncing
-        drop(client);
-
         // Only sync account if the transaction got confirmed
         if confirmed {
+            crate::client::record_message_confirmation(&message_id);
             // Ignore result
             let _ = new_account_handle
                 .sync()
@@ -2508,7 +3335,17 @@ async fn perform_transfer(
     // value, we generate a new one to keep the latest address unused
     let latest_address = account_.latest_address().address();
     let latest_address_in_transfer_output = transfer_obj.outputs.iter().any(|o| &o.address == latest_address);
-    if latest_address_in_transfer_output
+    let next_address_index = crate::address::next_public_address_index(&account_);
+    if account_handle
+        .account_options
+        .check_address_index(next_address_index)
+        .is_err()
+    {
+        log::warn!(
+            "[TRANSFER] not generating a new address: index {} is beyond the account limit",
+            next_address_index
+        );
+    } else if latest_address_in_transfer_output
         || (remainder_value_deposit_address.is_some() && &remainder_value_deposit_address.unwrap() == latest_address)
     {
         log::debug!(
@@ -2530,9 +3367,10 @@ async fn perform_transfer(
         .await?;
         addresses_to_watch.push(addr.address().clone());
         account_.append_addresses(vec![addr]);
+        account_handle.accounts.index_addresses(&account_);
     }
 
-    let message = Message::from_iota_message(
+    let mut message = Message::from_iota_message(
         message_id,
         message,
         account_handle.accounts.clone(),
@@ -2542,6 +3380,8 @@ async fn perform_transfer(
     )
     .finish()
     .await?;
+    message.set_transfer_timings(Some(transfer_timings));
+    message.set_transfer_context(transfer_obj.context.clone());
     account_.save_messages(vec![message.clone()]).await?;
     for input_address in input_addresses {
         if input_address.internal {
@@ -2584,18 +3424,21 @@ async fn perform_transfer(
 
     log::debug!("[TRANSFER] perform_transfer finished");
 
-    Ok(message)
+    Ok(TransferOutcome::Sent(message))
 }
 
 // Calculate the outputs on this address after the transaction gets confirmed so we know if we can send dust or
 // dust allowance outputs (as input). the bool in the outputs defines if we consume this output (false) or create a new
-// one (true)
-async fn is_dust_allowed(
+// one (true). Returns the dust protection state of the address if the transaction isn't allowed.
+async fn dust_analysis(
     account: &Account,
     client: &iota_client::Client,
+    dust_protection: &DustProtection,
     address: String,
     outputs: Vec<(u64, bool)>,
-) -> crate::Result<()> {
+) -> crate::Result<Option<DustAnalysis>> {
+    let divisor = dust_protection.divisor() as i64;
+    let max_dust_outputs = dust_protection.max_dust_outputs() as i64;
     // balance of all dust allowance outputs
     let mut dust_allowance_balance: i64 = 0;
     // Amount of dust outputs
@@ -2604,7 +3447,7 @@ async fn is_dust_allowed(
     // Add outputs from this transaction
     for (dust, add_outputs) in outputs {
         let sign = if add_outputs { 1 } else { -1 };
-        if dust >= DUST_ALLOWANCE_VALUE {
+        if dust >= dust_protection.allowance_value() {
             dust_allowance_balance += sign * dust as i64;
         } else {
             dust_outputs_amount += sign;
@@ -2612,19 +3455,22 @@ async fn is_dust_allowed(
     }
 
     let address_data = client.get_address().balance(&address).await?;
-    // If we create a dust output and a dust allowance output we don't need to check more outputs if the balance/100_000
-    // is < 100 because then we are sure that we didn't reach the max dust outputs
+    // If we create a dust output and a dust allowance output we don't need to check more outputs if the balance/divisor
+    // is < the max dust outputs because then we are sure that we didn't reach them
     if address_data.dust_allowed
         && dust_outputs_amount == 1
         && dust_allowance_balance >= 0
-        && address_data.balance as i64 / DUST_DIVISOR < MAX_ALLOWED_DUST_OUTPUTS
+        && address_data.balance as i64 / divisor < max_dust_outputs
     {
-        return Ok(());
+        return Ok(None);
     } else if !address_data.dust_allowed && dust_outputs_amount == 1 && dust_allowance_balance <= 0 {
-        return Err(crate::Error::DustError(format!(
-            "No dust output allowed on address {}",
-            address
-        )));
+        return Ok(Some(DustAnalysis {
+            address,
+            dust_allowed: false,
+            dust_allowance_balance,
+            dust_outputs: dust_outputs_amount,
+            allowed_dust_outputs: 0,
+        }));
     }
 
     // Get outputs from address and apply values
@@ -2652,7 +3498,7 @@ async fn is_dust_allowed(
                 dust_allowance_balance += amount as i64;
             }
             OutputKind::SignatureLockedSingle => {
-                if amount < DUST_ALLOWANCE_VALUE {
+                if amount < dust_protection.allowance_value() {
                     dust_outputs_amount += 1;
                 }
             }
@@ -2661,15 +3507,17 @@ async fn is_dust_allowed(
     }
 
     // Here dust_allowance_balance and dust_outputs_amount should be as if this transaction gets confirmed
-    // Max allowed dust outputs is 100
-    let allowed_dust_amount = std::cmp::min(dust_allowance_balance / DUST_DIVISOR, MAX_ALLOWED_DUST_OUTPUTS);
+    let allowed_dust_amount = std::cmp::min(dust_allowance_balance / divisor, max_dust_outputs);
     if dust_outputs_amount > allowed_dust_amount {
-        return Err(crate::Error::DustError(format!(
-            "No dust output allowed on address {}",
-            address
-        )));
+        return Ok(Some(DustAnalysis {
+            address,
+            dust_allowed: address_data.dust_allowed,
+            dust_allowance_balance,
+            dust_outputs: dust_outputs_amount,
+            allowed_dust_outputs: allowed_dust_amount,
+        }));
     }
-    Ok(())
+    Ok(None)
 }
 
 pub(crate) enum RepostAction {
@@ -2690,56 +3538,16 @@ pub(crate) async fn repost_message(
             let client = crate::client::get_client(account.client_options()).await?;
             let client = client.read().await;
 
-            // check if one of the inputs got spent
-            if let Some(MessagePayload::Transaction(tx)) = message_to_repost.payload() {
-                let TransactionEssence::Regular(essence) = tx.essence();
-                let mut spent_input = false;
-                for input in essence.inputs() {
-                    if let TransactionInput::Utxo(input) = input {
-                        match client.get_output(&input.input).await {
-                            Ok(output) => {
-                                if output.is_spent {
-                                    spent_input = true;
-                                }
-                            }
-                            Err(err) => {
-                                match &err {
-                                    iota_client::Error::ResponseError(_, message) => {
-                                        // if the node doesn't know about this output, then it got spent already and
-                                        // pruned
-                                        if message.contains("output not found") {
-                                            spent_input = true;
-                                        } else {
-                                            return Err(err.into());
-                                        }
-                                    }
-                                    _ => return Err(err.into()),
-                                }
-                            }
-                        }
-                    }
-                }
-                if spent_input {
-                    return Err(crate::Error::ClientError(Box::new(
-                        iota_client::Error::NoNeedPromoteOrReattach(message_id.to_string()),
-                    )));
-                }
+            // check if one of the inputs got spent or if the transaction got already confirmed, then we don't need
+            // to repost it
+            if super::retry_advice::has_spent_input(&client, &message_to_repost).await?
+                || super::retry_advice::is_included(&client, &message_to_repost).await?
+            {
+                return Err(crate::Error::ClientError(Box::new(
+                    iota_client::Error::NoNeedPromoteOrReattach(message_id.to_string()),
+                )));
             }
 
-            if let Some(crate::message::MessagePayload::Transaction(tx_payload)) = &message_to_repost.payload {
-                if client
-                    .get_included_message(&tx_payload.to_transaction_payload()?.id())
-                    .await
-                    .is_ok()
-                {
-                    // if the transaction got already confirmed, then we don't need to reattach it
-                    return Err(crate::Error::ClientError(Box::new(
-                        iota_client::Error::NoNeedPromoteOrReattach(message_id.to_string()),
-                    )));
-                } else {
-                }
-            };
-
             let (id, message) = match action {
                 RepostAction::Promote => client.promote(message_id).await?,
                 RepostAction::Reattach => match client.reattach(message_id).await {
@@ -2789,7 +3597,7 @@ pub(crate) async fn repost_message(
                     },
                 },
             };
-            let message = Message::from_iota_message(
+            let mut message = Message::from_iota_message(
                 id,
                 message,
                 account_handle.accounts.clone(),
@@ -2800,7 +3608,12 @@ pub(crate) async fn repost_message(
             .finish()
             .await?;
 
-            account.save_messages(vec![message.clone()]).await?;
+            // promotions don't have a payload, only reattachments are linked to the reposted message
+            if message.payload().is_some() {
+                account.link_reattachment(message_id, &mut message).await?;
+            } else {
+                account.save_messages(vec![message.clone()]).await?;
+            }
 
             Ok(message)
         }
@@ -2810,7 +3623,7 @@ pub(crate) async fn repost_message(
     Ok(message)
 }
 
-fn verify_unlock_blocks(
+pub(crate) fn verify_unlock_blocks(
     transaction_payload: &TransactionPayload,
     mut inputs: Vec<(Input, BeeAddress)>,
 ) -> crate::Result<()> {
@@ -2855,7 +3668,7 @@ mod tests {
 
     #[tokio::test]
     async fn account_sync() {
-        crate::test_utils::with_account_manager(crate::test_utils::TestType::Storage, |manager, _| async move {
+        crate::testing::with_account_manager(crate::testing::TestType::Storage, |manager, _| async move {
             let client_options = ClientOptionsBuilder::new()
                 .with_node("https://api.lb-0.h.chrysalis-devnet.iota.cafe")
                 .unwrap()
@@ -2878,10 +3691,10 @@ mod tests {
     // #[tokio::test]
     #[allow(dead_code)]
     async fn dust_transfer() {
-        let manager = crate::test_utils::get_account_manager().await;
+        let manager = crate::testing::get_account_manager().await;
 
         // first we create an address with balance - the source address
-        let mut address1 = crate::test_utils::generate_random_address();
+        let mut address1 = crate::testing::generate_random_address();
         let output = crate::address::AddressOutput {
             transaction_id: iota_client::bee_message::prelude::TransactionId::from([0; 32]),
             message_id: iota_client::bee_message::MessageId::from([0; 32]),
@@ -2894,9 +3707,9 @@ mod tests {
         address1.outputs.insert(output.id().unwrap(), output);
 
         // then we create an address without balance - the deposit address
-        let address2 = crate::test_utils::generate_random_address();
+        let address2 = crate::testing::generate_random_address();
 
-        let mut address3 = crate::test_utils::generate_random_address();
+        let mut address3 = crate::testing::generate_random_address();
         address3.set_key_index(0);
         address3.set_internal(true);
         let output = crate::address::AddressOutput {
@@ -2917,7 +3730,7 @@ mod tests {
             address3.address().to_bech32()
         );
 
-        let account_handle = crate::test_utils::AccountCreator::new(&manager)
+        let account_handle = crate::testing::AccountCreator::new(&manager)
             .addresses(vec![address1, address2.clone(), address3])
             .create()
             .await;
@@ -2927,10 +3740,12 @@ mod tests {
             id,
             index,
             account_handle,
-            deposit_address: crate::test_utils::generate_random_address(),
+            deposit_address: crate::testing::generate_random_address(),
             is_empty: false,
             messages: Vec::new(),
             addresses: Vec::new(),
+            partial: false,
+            retries: 0,
         };
         let res = synced
             .transfer(
@@ -2939,7 +3754,8 @@ mod tests {
                     std::num::NonZeroU64::new(999500).unwrap(),
                     None,
                 )
-                .finish(),
+                .finish()
+                .unwrap(),
             )
             .await;
         assert!(res.is_err());
@@ -2947,6 +3763,9 @@ mod tests {
             crate::Error::DustError(_) => {}
             _ => panic!("unexpected response"),
         }
+        let diagnostics = synced.account_handle.last_transfer_diagnostics().await.unwrap();
+        assert!(diagnostics.balance().is_some());
+        assert!(diagnostics.dust_analysis().is_some());
     }
 
     fn _generate_address_output(amount: u64, is_spent: bool) -> AddressOutput {
@@ -2958,14 +3777,14 @@ mod tests {
             index: 0,
             amount,
             is_spent,
-            address: crate::test_utils::generate_random_iota_address(),
+            address: crate::testing::generate_random_iota_address(),
             kind: OutputKind::SignatureLockedSingle,
         }
     }
 
     #[quickcheck]
     fn balance_change_event(old_balance: u32, new_balance: u32, outputs: Vec<(u64, bool)>) {
-        let address = crate::test_utils::generate_random_iota_address();
+        let address = crate::testing::generate_random_iota_address();
         let mut address_outputs = HashMap::new();
         for (amount, is_spent) in outputs {
             let output = _generate_address_output(amount, is_spent);
@@ -2987,6 +3806,37 @@ mod tests {
         );
     }
 
+    #[test]
+    fn output_changes() {
+        let unchanged = _generate_address_output(1_000_000, false);
+        let spent = _generate_address_output(2_000_000, false);
+        let removed = _generate_address_output(3_000_000, false);
+        let new = _generate_address_output(4_000_000, false);
+
+        let mut before_sync_outputs = HashMap::new();
+        for output in [&unchanged, &spent, &removed] {
+            before_sync_outputs.insert(output.id().unwrap(), output.clone());
+        }
+        let mut after_sync_outputs = HashMap::new();
+        let mut spent_after_sync = spent.clone();
+        spent_after_sync.is_spent = true;
+        for output in [&unchanged, &spent_after_sync, &new] {
+            after_sync_outputs.insert(output.id().unwrap(), output.clone());
+        }
+
+        let changes = super::get_output_changes(&before_sync_outputs, &after_sync_outputs);
+        assert_eq!(changes.len(), 3);
+        for (output, kind) in [
+            (spent, super::OutputChangeKind::Spent),
+            (removed, super::OutputChangeKind::Removed),
+            (new, super::OutputChangeKind::New),
+        ] {
+            assert!(changes
+                .iter()
+                .any(|change| change.output().id().unwrap() == output.id().unwrap() && *change.kind() == kind));
+        }
+    }
+
     #[test]
     fn signature_validation() {
         // Single input, single address
@@ -3016,4 +3866,41 @@ mod tests {
         .unwrap();
         assert!(verify_unlock_blocks(&transaction_payload, addresses).is_ok());
     }
+
+    #[test]
+    fn split_consolidation_amount() {
+        let address = crate::testing::generate_random_iota_address();
+        let outputs = super::split_consolidation_amount(address.clone(), 10_000_001, 3, None, 1_000_000);
+        assert_eq!(
+            outputs.iter().map(|o| o.amount.get()).collect::<Vec<u64>>(),
+            vec![3_333_335, 3_333_333, 3_333_333]
+        );
+        assert!(outputs.iter().all(|o| o.address == address));
+
+        // the outputs can't be below the dust allowance value
+        let outputs = super::split_consolidation_amount(address, 2_500_000, 5, None, 1_000_000);
+        assert_eq!(outputs.len(), 2);
+        // an amount below the dust allowance value isn't split
+        assert_eq!(super::split_amount(500_000, 3, 1_000_000), vec![500_000]);
+    }
+
+    #[test]
+    fn max_inputs() {
+        use crate::signing::SignerCapabilities;
+
+        let software = SignerCapabilities::default();
+        assert_eq!(super::max_inputs(2, &software, 127).unwrap(), 127);
+        assert!(super::max_inputs(127, &software, 127).is_err());
+
+        // the signer limit applies to the inputs and outputs together
+        let device = SignerCapabilities {
+            max_input_output_count: Some(17),
+            ..Default::default()
+        };
+        assert_eq!(super::max_inputs(2, &device, 127).unwrap(), 15);
+        assert!(matches!(
+            super::max_inputs(16, &device, 127),
+            Err(crate::Error::TooManyOutputs(16, 16))
+        ));
+    }
 }
@@ -0,0 +1,89 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::SyncRetryOptions;
+
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Arc,
+};
+
+/// The node requests a sync is still allowed to make, shared between the sync tasks.
+/// A budget without a limit never runs out.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RequestBudget {
+    remaining: Option<Arc<AtomicUsize>>,
+    exceeded: Arc<AtomicBool>,
+    retry_options: SyncRetryOptions,
+    retries: Arc<AtomicUsize>,
+}
+
+impl RequestBudget {
+    pub(crate) fn new(max_requests: Option<usize>) -> Self {
+        Self {
+            remaining: max_requests.map(|max_requests| Arc::new(AtomicUsize::new(max_requests))),
+            exceeded: Default::default(),
+            retry_options: Default::default(),
+            retries: Default::default(),
+        }
+    }
+
+    /// Sets how the failed requests are retried.
+    pub(crate) fn with_retry_options(mut self, retry_options: SyncRetryOptions) -> Self {
+        self.retry_options = retry_options;
+        self
+    }
+
+    pub(crate) fn retry_options(&self) -> SyncRetryOptions {
+        self.retry_options
+    }
+
+    pub(crate) fn record_retry(&self) {
+        self.retries.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// The number of requests retried so far.
+    pub(crate) fn retries(&self) -> usize {
+        self.retries.load(Ordering::SeqCst)
+    }
+
+    /// Takes a request from the budget, returning false if there's none left.
+    pub(crate) fn spend(&self) -> bool {
+        let remaining = match &self.remaining {
+            Some(remaining) => remaining,
+            None => return true,
+        };
+        let spent = remaining
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |remaining| remaining.checked_sub(1))
+            .is_ok();
+        if !spent {
+            self.exceeded.store(true, Ordering::SeqCst);
+        }
+        spent
+    }
+
+    /// Whether a request was denied because the budget ran out.
+    pub(crate) fn is_exceeded(&self) -> bool {
+        self.exceeded.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RequestBudget;
+
+    #[test]
+    fn request_budget() {
+        let unlimited = RequestBudget::default();
+        assert!((0..1000).all(|_| unlimited.spend()));
+        assert!(!unlimited.is_exceeded());
+
+        let budget = RequestBudget::new(Some(2));
+        let shared = budget.clone();
+        assert!(budget.spend());
+        assert!(shared.spend());
+        assert!(!budget.is_exceeded());
+        assert!(!shared.spend());
+        assert!(budget.is_exceeded());
+    }
+}
@@ -0,0 +1,212 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::RequestBudget;
+
+use getset::CopyGetters;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use std::{future::Future, time::Duration};
+
+/// The longest `Retry-After` delay honored; a node asking for more is treated as unavailable for this sync.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(30);
+
+/// How the node requests of a sync are retried after a transient failure (a 5xx or 429 response, or a timeout).
+/// The delay between the attempts doubles from `initial_backoff` up to `max_backoff`, with a random jitter so
+/// concurrent syncs don't retry in lockstep; a `Retry-After` response header takes precedence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, CopyGetters)]
+#[getset(get_copy = "pub")]
+pub struct SyncRetryOptions {
+    /// The maximum number of attempts of a request, including the first one. `1` disables the retries.
+    #[serde(rename = "maxAttempts")]
+    max_attempts: usize,
+    /// The delay before the first retry.
+    #[serde(rename = "initialBackoff")]
+    initial_backoff: Duration,
+    /// The maximum delay between two attempts.
+    #[serde(rename = "maxBackoff")]
+    max_backoff: Duration,
+}
+
+impl SyncRetryOptions {
+    /// Creates the retry options. `max_attempts` is at least 1.
+    pub fn new(max_attempts: usize, initial_backoff: Duration, max_backoff: Duration) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            initial_backoff,
+            max_backoff: max_backoff.max(initial_backoff),
+        }
+    }
+
+    /// Options that never retry.
+    pub fn disabled() -> Self {
+        Self::new(1, Duration::default(), Duration::default())
+    }
+
+    /// The delay before the given retry (starting at 1), with an equal jitter: between half and the whole backoff.
+    fn backoff(&self, retry: usize) -> Duration {
+        let exponent = (retry.saturating_sub(1)).min(16) as u32;
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(2u32.pow(exponent))
+            .min(self.max_backoff);
+        let half = backoff / 2;
+        half + rand::thread_rng().gen_range(Duration::default()..=half)
+    }
+}
+
+impl Default for SyncRetryOptions {
+    fn default() -> Self {
+        Self::new(3, Duration::from_millis(250), Duration::from_secs(5))
+    }
+}
+
+/// A request failure the sync can retry.
+pub(crate) trait TransientError {
+    /// Whether the request can succeed if it's sent again.
+    fn is_transient(&self) -> bool;
+
+    /// The delay requested by the node before the next attempt.
+    fn retry_after(&self) -> Option<Duration> {
+        None
+    }
+}
+
+impl TransientError for iota_client::Error {
+    fn is_transient(&self) -> bool {
+        match self {
+            iota_client::Error::ResponseError(status_code, _) => *status_code == 429 || *status_code >= 500,
+            iota_client::Error::ReqwestError(e) => e.is_timeout() || e.is_connect(),
+            _ => false,
+        }
+    }
+}
+
+impl TransientError for crate::Error {
+    fn is_transient(&self) -> bool {
+        match self {
+            crate::Error::ClientError(e) => e.is_transient(),
+            #[cfg(any(feature = "participation", feature = "cloud-backup", feature = "batch-outputs"))]
+            crate::Error::ReqwestError(e) => e.is_timeout() || e.is_connect(),
+            crate::Error::NodeOverloaded(_, _) => true,
+            _ => false,
+        }
+    }
+
+    fn retry_after(&self) -> Option<Duration> {
+        match self {
+            crate::Error::NodeOverloaded(_, retry_after) => retry_after.map(Duration::from_secs),
+            _ => None,
+        }
+    }
+}
+
+/// Parses the `Retry-After` header, which is only supported in seconds.
+#[cfg(feature = "batch-outputs")]
+pub(crate) fn parse_retry_after(headers: &reqwest::header::HeaderMap) -> Option<u64> {
+    headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+impl RequestBudget {
+    /// Sends the request, retrying it on transient failures as long as the retry options and the budget allow it.
+    /// The first attempt must have been taken from the budget already; each retry takes another request.
+    pub(crate) async fn retry<T, E, F, R>(&self, mut request: F) -> std::result::Result<T, E>
+    where
+        E: TransientError + std::fmt::Display,
+        F: FnMut() -> R,
+        R: Future<Output = std::result::Result<T, E>>,
+    {
+        let options = self.retry_options();
+        let mut attempt = 1;
+        loop {
+            match request().await {
+                Err(e) if e.is_transient() && attempt < options.max_attempts => {
+                    let delay = match e.retry_after() {
+                        Some(retry_after) if retry_after > MAX_RETRY_AFTER => return Err(e),
+                        Some(retry_after) => retry_after,
+                        None => options.backoff(attempt),
+                    };
+                    if !self.spend() {
+                        return Err(e);
+                    }
+                    log::debug!("[SYNC] request failed ({}), retrying in {:.2?}", e, delay);
+                    self.record_retry();
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RequestBudget, SyncRetryOptions};
+
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    #[test]
+    fn retry_backoff() {
+        let options = SyncRetryOptions::new(5, Duration::from_millis(100), Duration::from_millis(300));
+        for _ in 0..20 {
+            let first = options.backoff(1);
+            assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(100));
+            let capped = options.backoff(4);
+            assert!(capped >= Duration::from_millis(150) && capped <= Duration::from_millis(300));
+        }
+        assert_eq!(
+            SyncRetryOptions::new(0, Duration::default(), Duration::default()).max_attempts(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn retry_transient_errors() {
+        let options = SyncRetryOptions::new(3, Duration::from_millis(1), Duration::from_millis(1));
+        let budget = RequestBudget::new(None).with_retry_options(options);
+        let attempts = AtomicUsize::new(0);
+        let result: crate::Result<()> = budget
+            .retry(|| async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err(iota_client::Error::ResponseError(503, String::new()).into())
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+        assert_eq!(budget.retries(), 2);
+
+        // a missing output isn't retried
+        let attempts = AtomicUsize::new(0);
+        let result: crate::Result<()> = budget
+            .retry(|| async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err(iota_client::Error::ResponseError(404, String::new()).into())
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+
+        // the retries spend the request budget
+        let budget = RequestBudget::new(Some(1)).with_retry_options(options);
+        let attempts = AtomicUsize::new(0);
+        let _: crate::Result<()> = budget
+            .retry(|| async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err(crate::Error::NodeOverloaded(429, Some(0)))
+            })
+            .await;
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+        assert!(budget.is_exceeded());
+    }
+}
@@ -0,0 +1,141 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{input_selection, SyncedAccount};
+use crate::message::MessageType;
+
+use getset::{CopyGetters, Getters};
+use serde::Serialize;
+
+/// Whether a transfer can be sent from the local account state,
+/// the result of [AccountHandle#can_send](struct.AccountHandle.html#method.can_send).
+#[derive(Debug, Getters, CopyGetters, Serialize)]
+pub struct SendCheck {
+    /// Whether the transfer can be sent.
+    #[serde(rename = "canSend")]
+    #[getset(get_copy = "pub")]
+    can_send: bool,
+    /// The balance the transfers can spend: the outputs that aren't reserved by a transfer in progress or used by a
+    /// pending message.
+    #[getset(get_copy = "pub")]
+    available: u64,
+    /// Why the transfer can't be sent.
+    #[getset(get = "pub")]
+    reason: Option<Box<crate::Error>>,
+}
+
+impl SyncedAccount {
+    /// Checks the amount and output count of a transfer against the locked outputs, the dust protection and the input
+    /// limits of the signer, like the input selection of a transfer would, without any node request.
+    pub(crate) async fn can_send(&self, amount: u64, output_count: usize) -> crate::Result<SendCheck> {
+        let account = self.account_handle.read().await;
+        let sent_messages = account.list_messages(0, 0, Some(MessageType::Sent)).await?;
+        let signer_capabilities = crate::signing::signer_capabilities(account.signer_type()).await;
+        let locked_outputs = self.account_handle.locked_outputs.lock().await;
+        let available_inputs: Vec<input_selection::Input> = account
+            .addresses()
+            .iter()
+            .flat_map(|address| {
+                address
+                    .available_outputs(&sent_messages)
+                    .into_iter()
+                    .filter(|output| output.amount > 0 && !locked_outputs.is_locked(output))
+                    .map(move |output| input_selection::Input {
+                        internal: *address.internal(),
+                        output: output.clone(),
+                    })
+            })
+            .collect();
+        drop(locked_outputs);
+        let available = available_inputs.iter().map(|input| input.output.amount).sum();
+
+        let check = || -> crate::Result<()> {
+            if *account.frozen() {
+                return Err(crate::Error::AccountFrozen);
+            }
+            if amount == 0 {
+                return Err(crate::Error::InvalidAmount(
+                    "the amount must be greater than 0".to_string(),
+                ));
+            }
+            let max_inputs = super::max_inputs(
+                output_count,
+                &signer_capabilities,
+                account.client_options().network_parameters().max_input_output_count(),
+            )?;
+            input_selection::select_input(
+                amount,
+                available_inputs,
+                max_inputs,
+                account.client_options().dust_protection().allowance_value(),
+            )?;
+            Ok(())
+        };
+        let reason = check().err().map(Box::new);
+
+        Ok(SendCheck {
+            can_send: reason.is_none(),
+            available,
+            reason,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::address::{AddressBuilder, AddressOutput, OutputKind};
+    use iota_client::bee_message::prelude::{MessageId, TransactionId};
+
+    #[tokio::test]
+    async fn can_send() {
+        let manager = crate::testing::get_account_manager().await;
+        let address = crate::testing::generate_random_iota_address();
+        let outputs = (0..2u16)
+            .map(|index| AddressOutput {
+                transaction_id: TransactionId::new([index as u8 + 1; 32]),
+                message_id: MessageId::new([0; 32]),
+                index,
+                amount: 2_000_000,
+                is_spent: false,
+                address: address.clone(),
+                kind: OutputKind::SignatureLockedSingle,
+            })
+            .collect::<Vec<AddressOutput>>();
+        let account_handle = crate::testing::AccountCreator::new(&manager)
+            .addresses(vec![AddressBuilder::new()
+                .key_index(0)
+                .address(address)
+                .outputs(outputs.clone())
+                .build()
+                .unwrap()])
+            .create()
+            .await;
+
+        let check = account_handle.can_send(3_000_000, 1).await.unwrap();
+        assert!(check.can_send());
+        assert_eq!(check.available(), 4_000_000);
+
+        // the remainder would be dust
+        let check = account_handle.can_send(3_500_000, 1).await.unwrap();
+        assert!(matches!(
+            check.reason().as_deref(),
+            Some(crate::Error::LeavingDustError(_))
+        ));
+
+        // the outputs reserved by a transfer in progress can't be spent
+        let transfer = std::sync::Arc::new(());
+        account_handle
+            .locked_outputs
+            .lock()
+            .await
+            .lock(vec![outputs[0].clone()], &std::sync::Arc::downgrade(&transfer));
+        let check = account_handle.can_send(3_000_000, 1).await.unwrap();
+        assert_eq!(check.available(), 2_000_000);
+        assert!(matches!(
+            check.reason().as_deref(),
+            Some(crate::Error::InsufficientFunds(2_000_000, 3_000_000))
+        ));
+
+        assert!(!account_handle.can_send(0, 1).await.unwrap().can_send());
+    }
+}
@@ -0,0 +1,198 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{KnownIds, RequestBudget};
+use crate::{
+    account::{Account, AccountHandle},
+    address::Address,
+};
+
+use iota_client::Client;
+
+use std::sync::Arc;
+
+/// Number of milestones an address must stay fully spent before the syncs skip it (about one day).
+pub(crate) const SPENT_ADDRESS_SKIP_AGE: u32 = 8640;
+/// Every Nth sync also requests the skipped addresses, catching outputs they received meanwhile.
+pub(crate) const SPENT_ADDRESS_SPOT_CHECK_INTERVAL: u64 = 16;
+
+/// Whether the address has outputs and all of them are spent.
+fn is_fully_spent(address: &Address) -> bool {
+    !address.outputs().is_empty() && address.outputs().values().all(|output| output.is_spent)
+}
+
+impl KnownIds {
+    /// Whether the address is on the skip-list for long enough to be skipped at the given milestone.
+    pub(crate) fn skips_address(&self, address: &Address, milestone_index: u32) -> bool {
+        self.spent_addresses
+            .get(&address.address().to_bech32())
+            .map_or(false, |spent_since| {
+                milestone_index.saturating_sub(*spent_since) >= SPENT_ADDRESS_SKIP_AGE
+            })
+    }
+
+    /// Adds the fully spent addresses to the skip-list and removes the others, returning whether it changed.
+    fn update_spent_addresses<'a>(
+        &mut self,
+        addresses: impl Iterator<Item = &'a Address>,
+        milestone_index: u32,
+    ) -> bool {
+        let mut changed = false;
+        for address in addresses {
+            let key = address.address().to_bech32();
+            if is_fully_spent(address) {
+                if !self.spent_addresses.contains_key(&key) {
+                    self.spent_addresses.insert(key, milestone_index);
+                    changed = true;
+                }
+            } else {
+                changed |= self.spent_addresses.remove(&key).is_some();
+            }
+        }
+        changed
+    }
+}
+
+/// Gets the confirmed milestone index the skip-list ages are measured against.
+/// Returns `None` if the node can't be reached, in which case no address is skipped.
+pub(crate) async fn confirmed_milestone_index(client: &Client, budget: &RequestBudget) -> Option<u32> {
+    if !budget.spend() {
+        return None;
+    }
+    match client.get_info().await {
+        Ok(info) => Some(info.nodeinfo.confirmed_milestone_index),
+        Err(e) => {
+            log::debug!("[SYNC] couldn't get the confirmed milestone index: {}", e);
+            None
+        }
+    }
+}
+
+impl AccountHandle {
+    /// Counts the sync, returning whether it's a spot-check sync that also requests the skipped addresses.
+    /// A dry run sync isn't counted.
+    pub(crate) async fn count_spent_addresses_sync(&self, account: &Account, dry_run: bool) -> crate::Result<bool> {
+        self.known_ids(account).await?;
+        let mut known_ids = self.known_ids.lock().await;
+        // safe to unwrap: the known ids were loaded above
+        let spot_check = known_ids.as_ref().unwrap().sync_count % SPENT_ADDRESS_SPOT_CHECK_INTERVAL == 0;
+        if dry_run {
+            return Ok(spot_check);
+        }
+        let known_ids = Arc::make_mut(known_ids.as_mut().unwrap());
+        known_ids.sync_count += 1;
+        if !account.skip_persistence() {
+            crate::storage::get(account.storage_path())
+                .await?
+                .lock()
+                .await
+                .save_known_ids(account.id(), known_ids)
+                .await?;
+        }
+        Ok(spot_check)
+    }
+
+    /// Updates the skip-list of fully spent addresses with the synced addresses.
+    pub(crate) async fn update_spent_addresses(
+        &self,
+        account: &Account,
+        addresses: &[Address],
+        milestone_index: u32,
+    ) -> crate::Result<()> {
+        let mut known_ids = self.known_ids.lock().await;
+        if let Some(known_ids) = known_ids.as_mut() {
+            // the latest address is never skipped, since it's the one shared to receive funds
+            let addresses = addresses
+                .iter()
+                .filter(|address| address.address() != account.latest_address().address());
+            if Arc::make_mut(known_ids).update_spent_addresses(addresses, milestone_index)
+                && !account.skip_persistence()
+            {
+                crate::storage::get(account.storage_path())
+                    .await?
+                    .lock()
+                    .await
+                    .save_known_ids(account.id(), known_ids.as_ref())
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[tokio::test]
+    async fn spent_address_skip_list() {
+        use crate::address::{AddressBuilder, AddressOutput, OutputKind};
+        use iota_client::bee_message::prelude::{MessageId, TransactionId};
+
+        let address_with_output = |key_index: usize, is_spent: bool| {
+            let address = crate::testing::generate_random_iota_address();
+            AddressBuilder::new()
+                .address(address.clone())
+                .key_index(key_index)
+                .outputs(vec![AddressOutput {
+                    transaction_id: TransactionId::new([key_index as u8; 32]),
+                    message_id: MessageId::new([0; 32]),
+                    index: 0,
+                    amount: 1_000_000,
+                    is_spent,
+                    address,
+                    kind: OutputKind::SignatureLockedSingle,
+                }])
+                .build()
+                .unwrap()
+        };
+        let spent = address_with_output(0, true);
+        let unspent = address_with_output(1, false);
+        let latest = address_with_output(2, true);
+
+        let manager = crate::testing::get_account_manager().await;
+        let account_handle = crate::testing::AccountCreator::new(&manager)
+            .addresses(vec![spent.clone(), unspent.clone(), latest.clone()])
+            .create()
+            .await;
+        let account = account_handle.read().await.clone();
+
+        assert!(account_handle
+            .count_spent_addresses_sync(&account, false)
+            .await
+            .unwrap());
+        // a dry run doesn't count
+        assert!(!account_handle.count_spent_addresses_sync(&account, true).await.unwrap());
+        assert!(!account_handle
+            .count_spent_addresses_sync(&account, false)
+            .await
+            .unwrap());
+        assert!(!account_handle.count_spent_addresses_sync(&account, true).await.unwrap());
+
+        account_handle
+            .update_spent_addresses(&account, account.addresses(), 100)
+            .await
+            .unwrap();
+        let known_ids = account_handle.known_ids(&account).await.unwrap();
+        assert!(!known_ids.skips_address(&spent, 100 + super::SPENT_ADDRESS_SKIP_AGE - 1));
+        assert!(known_ids.skips_address(&spent, 100 + super::SPENT_ADDRESS_SKIP_AGE));
+        assert!(!known_ids.skips_address(&unspent, u32::MAX));
+        assert!(!known_ids.skips_address(&latest, u32::MAX));
+
+        // a spot-check found a new output on the address
+        let mut received = spent.clone();
+        let mut outputs: Vec<AddressOutput> = received.outputs().values().cloned().collect();
+        outputs[0].transaction_id = TransactionId::new([9; 32]);
+        outputs[0].is_spent = false;
+        received.set_outputs(
+            outputs
+                .into_iter()
+                .map(|output| (output.id().unwrap(), output))
+                .collect(),
+        );
+        account_handle
+            .update_spent_addresses(&account, &[received], 200)
+            .await
+            .unwrap();
+        let known_ids = account_handle.known_ids(&account).await.unwrap();
+        assert!(!known_ids.skips_address(&spent, u32::MAX));
+    }
+}
@@ -0,0 +1,218 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{Account, AccountHandle};
+use crate::{
+    address::AddressOutput,
+    message::{Message, MessagePayload, TransactionEssence},
+};
+
+use getset::Getters;
+use iota_client::bee_message::prelude::{MessageId, OutputId};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use std::collections::HashMap;
+
+/// The header of the transactions CSV export.
+const TRANSACTIONS_CSV_HEADER: &str = "messageId,timestamp,direction,value,confirmed,tag";
+
+/// The local accounting tags of the account outputs and transfers. They're never sent to the node.
+#[derive(Debug, Clone, Default, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct AccountTags {
+    /// The tags of the outputs, keyed by output id.
+    #[serde(default)]
+    outputs: HashMap<String, String>,
+    /// The tags of the transfers, keyed by message id.
+    #[serde(default)]
+    messages: HashMap<String, String>,
+}
+
+fn set_tag(tags: &mut HashMap<String, String>, key: String, tag: Option<String>) {
+    match tag {
+        Some(tag) => tags.insert(key, tag),
+        None => tags.remove(&key),
+    };
+}
+
+/// Quotes the CSV field if it holds a separator, a quote or a line break.
+/// A field starting like a spreadsheet formula is prefixed with a quote, so it's read as text.
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(&['=', '+', '-', '@', '\t', '\r'][..]) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+impl Account {
+    /// The tag of the first tagged output of each transfer that created outputs on the account.
+    fn output_tags_by_message(&self) -> HashMap<MessageId, &String> {
+        let mut tags = HashMap::new();
+        for output in self.addresses().iter().flat_map(|address| address.outputs().values()) {
+            if let Some(tag) = output.id().ok().and_then(|id| self.tags.outputs.get(&id.to_string())) {
+                tags.entry(output.message_id).or_insert(tag);
+            }
+        }
+        tags
+    }
+
+    /// The tag of the transfer, or the tag of the first tagged output it created on the account.
+    fn message_tag<'a>(
+        &'a self,
+        message: &Message,
+        output_tags: &HashMap<MessageId, &'a String>,
+    ) -> Option<&'a String> {
+        self.tags
+            .messages
+            .get(&message.id().to_string())
+            .or_else(|| output_tags.get(message.id()).copied())
+    }
+}
+
+impl AccountHandle {
+    /// Sets the local accounting tag of an output, or removes it if `tag` is `None`.
+    pub async fn tag_output(&self, output_id: OutputId, tag: Option<String>) -> crate::Result<()> {
+        let mut account = self.inner.write().await;
+        set_tag(&mut account.tags.outputs, output_id.to_string(), tag);
+        account.save().await
+    }
+
+    /// Sets the local accounting tag of a transfer, or removes it if `tag` is `None`.
+    pub async fn tag_message(&self, message_id: MessageId, tag: Option<String>) -> crate::Result<()> {
+        let mut account = self.inner.write().await;
+        set_tag(&mut account.tags.messages, message_id.to_string(), tag);
+        account.save().await
+    }
+
+    /// Lists the outputs of the account addresses, optionally only the ones with the given tag.
+    pub async fn list_outputs(&self, tag: Option<&str>) -> Vec<AddressOutput> {
+        let account = self.inner.read().await;
+        account
+            .addresses()
+            .iter()
+            .flat_map(|address| address.outputs().values())
+            .filter(|output| match tag {
+                Some(tag) => output
+                    .id()
+                    .ok()
+                    .and_then(|id| account.tags.outputs.get(&id.to_string()))
+                    .map_or(false, |output_tag| output_tag == tag),
+                None => true,
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Writes the account transfers as CSV, optionally only the ones with the given tag.
+    /// A transfer without a tag of its own takes the tag of the first tagged output it created on the account.
+    pub async fn export_transactions<W: AsyncWrite + Unpin>(
+        &self,
+        mut writer: W,
+        tag: Option<&str>,
+    ) -> crate::Result<()> {
+        let account = self.inner.read().await;
+        writer.write_all(TRANSACTIONS_CSV_HEADER.as_bytes()).await?;
+        writer.write_all(b"\n").await?;
+        let output_tags = account.output_tags_by_message();
+        for message in account.list_messages(0, 0, None).await? {
+            let essence = match message.payload() {
+                Some(MessagePayload::Transaction(tx)) => match tx.essence() {
+                    TransactionEssence::Regular(essence) => essence,
+                },
+                _ => continue,
+            };
+            let message_tag = account.message_tag(&message, &output_tags);
+            if let Some(tag) = tag {
+                if message_tag.map(|message_tag| message_tag.as_str()) != Some(tag) {
+                    continue;
+                }
+            }
+            let line = format!(
+                "{},{},{},{},{},{}\n",
+                message.id(),
+                message.timestamp().to_rfc3339(),
+                if essence.incoming() { "incoming" } else { "outgoing" },
+                essence.value(),
+                message.confirmed().unwrap_or(false),
+                csv_field(message_tag.map(|t| t.as_str()).unwrap_or_default()),
+            );
+            writer.write_all(line.as_bytes()).await?;
+        }
+        writer.flush().await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[tokio::test]
+    async fn tags() {
+        use crate::address::{AddressBuilder, AddressOutput, OutputKind};
+        use iota_client::bee_message::prelude::TransactionId;
+
+        let manager = crate::testing::get_account_manager().await;
+        let address_wrapper = crate::testing::generate_random_iota_address();
+        let address_builder = || AddressBuilder::new().address(address_wrapper.clone()).key_index(0);
+        let message = crate::testing::GenerateMessageBuilder::default()
+            .address(address_builder().outputs(Vec::new()).build().unwrap())
+            .build()
+            .await;
+        let output = AddressOutput {
+            transaction_id: TransactionId::new([1; 32]),
+            message_id: *message.id(),
+            index: 0,
+            amount: 1_000_000,
+            is_spent: false,
+            address: address_wrapper.clone(),
+            kind: OutputKind::SignatureLockedSingle,
+        };
+        let address = address_builder().outputs(vec![output.clone()]).build().unwrap();
+        let account_handle = crate::testing::AccountCreator::new(&manager)
+            .addresses(vec![address.clone()])
+            .messages(vec![message.clone()])
+            .create()
+            .await;
+
+        account_handle
+            .tag_output(output.id().unwrap(), Some("rent".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(account_handle.list_outputs(Some("rent")).await, vec![output]);
+        assert!(account_handle.list_outputs(Some("salary")).await.is_empty());
+
+        account_handle
+            .tag_message(*message.id(), Some("salary, march".to_string()))
+            .await
+            .unwrap();
+        let mut csv = Vec::new();
+        account_handle
+            .export_transactions(&mut csv, Some("salary, march"))
+            .await
+            .unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some(super::TRANSACTIONS_CSV_HEADER));
+        let line = lines.next().unwrap();
+        assert!(line.starts_with(&message.id().to_string()));
+        assert!(line.ends_with("\"salary, march\""));
+        assert!(lines.next().is_none());
+
+        // the tag falls back to the output tag, and formulas are neutralised
+        account_handle.tag_message(*message.id(), None).await.unwrap();
+        account_handle
+            .tag_output(output.id().unwrap(), Some("=HYPERLINK(\"x\")".to_string()))
+            .await
+            .unwrap();
+        let mut csv = Vec::new();
+        account_handle.export_transactions(&mut csv, None).await.unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert!(csv.lines().nth(1).unwrap().ends_with(",\"'=HYPERLINK(\"\"x\"\")\""));
+    }
+}
@@ -0,0 +1,312 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::Account;
+use crate::{
+    account_manager::AccountStore,
+    message::{Message, MessagePayload, TransactionBuilderMetadata, TransactionEssence, TransactionInput},
+    storage::MessageIndexation,
+};
+
+use getset::Getters;
+use iota_client::bee_message::{
+    input::Input,
+    payload::transaction::Essence,
+    prelude::{Address as BeeAddress, MessageId},
+};
+use serde::Serialize;
+
+/// The kind of inconsistency found on a stored message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", content = "data")]
+pub enum ValidationIssueKind {
+    /// The message couldn't be read from the storage.
+    Unreadable(String),
+    /// The message payload couldn't be decoded or its transaction recomputed.
+    InvalidPayload(String),
+    /// The transaction signature couldn't be verified against the input addresses.
+    InvalidSignature(String),
+    /// The input address metadata is missing so the signatures can't be verified.
+    MissingInputMetadata,
+    /// The stored transaction value doesn't match the recomputed value.
+    ValueMismatch {
+        /// The stored value.
+        stored: u64,
+        /// The recomputed value.
+        computed: u64,
+    },
+    /// The stored remainder value doesn't match the recomputed remainder value.
+    RemainderValueMismatch {
+        /// The stored remainder value.
+        stored: u64,
+        /// The recomputed remainder value.
+        computed: u64,
+    },
+    /// The stored direction (incoming/outgoing) doesn't match the recomputed direction.
+    DirectionMismatch {
+        /// The stored `incoming` flag.
+        stored: bool,
+        /// The recomputed `incoming` flag.
+        computed: bool,
+    },
+    /// The stored `internal` flag doesn't match the recomputed flag.
+    InternalMismatch {
+        /// The stored `internal` flag.
+        stored: bool,
+        /// The recomputed `internal` flag.
+        computed: bool,
+    },
+    /// The message indexation (used for filtering and balance) is out of sync with the stored message.
+    IndexationMismatch,
+}
+
+impl ValidationIssueKind {
+    fn is_repairable(&self) -> bool {
+        matches!(
+            self,
+            Self::ValueMismatch { .. }
+                | Self::RemainderValueMismatch { .. }
+                | Self::DirectionMismatch { .. }
+                | Self::InternalMismatch { .. }
+                | Self::IndexationMismatch
+        )
+    }
+}
+
+/// An inconsistency found on a stored message.
+#[derive(Debug, Clone, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct ValidationIssue {
+    /// The message identifier.
+    #[serde(rename = "messageId")]
+    message_id: MessageId,
+    /// The inconsistency.
+    kind: ValidationIssueKind,
+    /// Whether the inconsistency was repaired or not.
+    repaired: bool,
+}
+
+/// The result of [AccountHandle#revalidate_messages](struct.AccountHandle.html#method.revalidate_messages).
+#[derive(Debug, Clone, Default, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct ValidationReport {
+    /// Number of messages checked.
+    checked: usize,
+    /// The inconsistencies found.
+    issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    /// Whether no inconsistency was found.
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Number of inconsistencies that were repaired.
+    pub fn repaired(&self) -> usize {
+        self.issues.iter().filter(|issue| issue.repaired).count()
+    }
+}
+
+impl Account {
+    /// Re-verifies the signatures and recomputes the value and direction of every stored message.
+    /// If `repair` is set, the recomputed data is persisted, fixing messages stored with outdated data.
+    pub(crate) async fn revalidate_messages(
+        &mut self,
+        accounts: AccountStore,
+        repair: bool,
+    ) -> crate::Result<ValidationReport> {
+        let indexation = self.with_messages(|messages| messages.clone()).await;
+        let mut report = ValidationReport::default();
+        let mut repaired_messages = Vec::new();
+
+        for index in indexation {
+            report.checked += 1;
+            let mut message: Message = match crate::storage::get(&self.storage_path)
+                .await?
+                .lock()
+                .await
+                .get_message(self, &index.key)
+                .await
+            {
+                Ok(message) => message,
+                Err(e) => {
+                    report.issues.push(ValidationIssue {
+                        message_id: index.key,
+                        kind: ValidationIssueKind::Unreadable(e.to_string()),
+                        repaired: false,
+                    });
+                    continue;
+                }
+            };
+
+            let issues = match self.validate_message(&mut message, &index, &accounts, repair).await {
+                Ok(issues) => issues,
+                Err(e) => vec![ValidationIssueKind::InvalidPayload(e.to_string())],
+            };
+
+            let repaired = repair && issues.iter().any(|issue| issue.is_repairable());
+            if repaired {
+                repaired_messages.push(message);
+            }
+            report.issues.extend(issues.into_iter().map(|kind| ValidationIssue {
+                message_id: index.key,
+                repaired: repaired && kind.is_repairable(),
+                kind,
+            }));
+        }
+
+        if !repaired_messages.is_empty() {
+            self.save_messages(repaired_messages).await?;
+        }
+
+        Ok(report)
+    }
+
+    /// Checks the signatures and the recomputed data of a stored message, updating its essence if `repair` is set.
+    async fn validate_message(
+        &self,
+        message: &mut Message,
+        index: &MessageIndexation,
+        accounts: &AccountStore,
+        repair: bool,
+    ) -> crate::Result<Vec<ValidationIssueKind>> {
+        let mut issues = Vec::new();
+        if let Some(MessagePayload::Transaction(tx)) = message.payload.as_mut() {
+            let transaction_payload = tx.to_transaction_payload()?;
+            let TransactionEssence::Regular(essence) = tx.essence();
+
+            let mut inputs: Vec<(Input, BeeAddress)> = Vec::new();
+            let mut missing_metadata = false;
+            for input in essence.inputs() {
+                if let TransactionInput::Utxo(utxo) = input {
+                    match &utxo.metadata {
+                        Some(metadata) => inputs.push((Input::Utxo(utxo.input.clone()), *metadata.address.as_ref())),
+                        None => missing_metadata = true,
+                    }
+                }
+            }
+            if missing_metadata {
+                issues.push(ValidationIssueKind::MissingInputMetadata);
+            } else if let Err(e) = super::sync::verify_unlock_blocks(&transaction_payload, inputs) {
+                issues.push(ValidationIssueKind::InvalidSignature(e.to_string()));
+            }
+
+            let Essence::Regular(regular_essence) = transaction_payload.essence();
+            let computed = essence
+                .recompute(
+                    regular_essence,
+                    &TransactionBuilderMetadata {
+                        id: &index.key,
+                        bech32_hrp: self.bech32_hrp(),
+                        accounts: accounts.clone(),
+                        account_id: self.id(),
+                        account_addresses: self.addresses(),
+                        client_options: self.client_options(),
+                    },
+                )
+                .await?;
+            if computed.value() != essence.value() {
+                issues.push(ValidationIssueKind::ValueMismatch {
+                    stored: essence.value(),
+                    computed: computed.value(),
+                });
+            }
+            if computed.remainder_value() != essence.remainder_value() {
+                issues.push(ValidationIssueKind::RemainderValueMismatch {
+                    stored: essence.remainder_value(),
+                    computed: computed.remainder_value(),
+                });
+            }
+            if computed.incoming() != essence.incoming() {
+                issues.push(ValidationIssueKind::DirectionMismatch {
+                    stored: essence.incoming(),
+                    computed: computed.incoming(),
+                });
+            }
+            if computed.internal() != essence.internal() {
+                issues.push(ValidationIssueKind::InternalMismatch {
+                    stored: essence.internal(),
+                    computed: computed.internal(),
+                });
+            }
+            if index.value != computed.value() || index.incoming != Some(computed.incoming()) {
+                issues.push(ValidationIssueKind::IndexationMismatch);
+            }
+
+            if repair && issues.iter().any(|issue| issue.is_repairable()) {
+                *tx.essence_mut() = TransactionEssence::Regular(computed);
+            }
+        }
+        Ok(issues)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ValidationIssueKind;
+
+    #[tokio::test]
+    async fn revalidate_messages() {
+        let manager = crate::testing::get_account_manager().await;
+        let address = crate::testing::generate_random_address();
+        let message = crate::testing::GenerateMessageBuilder::default()
+            .address(address.clone())
+            .input_address(Some(crate::testing::generate_random_iota_address()))
+            .account_addresses(vec![address.clone()])
+            .build()
+            .await;
+        let account_handle = crate::testing::AccountCreator::new(&manager)
+            .addresses(vec![address])
+            .messages(vec![message])
+            .create()
+            .await;
+
+        let report = account_handle.revalidate_messages(false).await.unwrap();
+        assert_eq!(*report.checked(), 1);
+        assert_eq!(report.repaired(), 0);
+        // the generated message has a dummy signature
+        assert!(report
+            .issues()
+            .iter()
+            .any(|issue| matches!(issue.kind(), ValidationIssueKind::InvalidSignature(_))));
+    }
+
+    #[tokio::test]
+    async fn revalidate_invalid_payload() {
+        let manager = crate::testing::get_account_manager().await;
+        let address = crate::testing::generate_random_address();
+        let mut messages = Vec::new();
+        for _ in 0..2 {
+            messages.push(
+                crate::testing::GenerateMessageBuilder::default()
+                    .address(address.clone())
+                    .input_address(Some(crate::testing::generate_random_iota_address()))
+                    .account_addresses(vec![address.clone()])
+                    .build()
+                    .await,
+            );
+        }
+        // a transaction without unlock blocks can't be converted back to a bee payload
+        let mut json = serde_json::to_value(&messages[1]).unwrap();
+        json["payload"]["data"]["unlockBlocks"] = serde_json::json!([]);
+        messages[1] = serde_json::from_value(json).unwrap();
+        let invalid_id = *messages[1].id();
+        let account_handle = crate::testing::AccountCreator::new(&manager)
+            .addresses(vec![address])
+            .messages(messages)
+            .create()
+            .await;
+
+        // the invalid message doesn't abort the validation of the others
+        let report = account_handle.revalidate_messages(false).await.unwrap();
+        assert_eq!(*report.checked(), 2);
+        assert!(report
+            .issues()
+            .iter()
+            .any(|issue| *issue.message_id() == invalid_id
+                && matches!(issue.kind(), ValidationIssueKind::InvalidPayload(_))));
+        assert!(report.issues().iter().any(|issue| *issue.message_id() != invalid_id
+            && matches!(issue.kind(), ValidationIssueKind::InvalidSignature(_))));
+    }
+}
@@ -3,17 +3,20 @@
 
 use crate::{
     account::{
-        AccountHandle, AccountIdentifier, AccountInitialiser, AccountSynchronizeStep, AccountSynchronizer,
-        SyncedAccount, SyncedAccountData,
+        Account, AccountHandle, AccountIdentifier, AccountInitialiser, AccountSynchronizeStep, AccountSynchronizer,
+        ConsolidationOptions, InFlightTransfers, MessageCache, SyncRetryOptions, SyncedAccount, SyncedAccountData,
+        DEFAULT_OUTPUT_LOCK_LEASE,
     },
-    address::{AddressOutput, AddressWrapper},
-    client::ClientOptions,
+    address::{AddressBuilder, AddressOutput, AddressWrapper},
+    client::{ClientOptions, PowPool, RequestLogOptions},
     event::{
-        emit_balance_change, emit_confirmation_state_change, emit_reattachment_event, emit_transaction_event,
-        BalanceEvent, TransactionConfirmationChangeEvent, TransactionEvent, TransactionEventType,
-        TransactionReattachmentEvent,
+        emit_account_discovery_progress, emit_account_lifecycle, emit_balance_change, emit_confirmation_state_change,
+        emit_reattachment_event, emit_transaction_event, AccountDiscoveryStatus, AccountLifecycle, BalanceEvent,
+        TransactionConfirmationChangeEvent, TransactionEvent, TransactionEventType, TransactionReattachmentEvent,
+    },
+    message::{
+        ChangeAddressVerification, Message, MessagePayload, MessageType, TransactionEssence, TransactionInput, Transfer,
     },
-    message::{Message, MessagePayload, MessageType, TransactionEssence, TransactionInput, Transfer},
     signing::{GenerateAddressMetadata, SignerType},
     storage::{StorageAdapter, Timestamp},
 };
@@ -29,7 +32,7 @@ use std::{
     path::{Path, PathBuf},
     sync::{
         atomic::{AtomicBool, Ordering},
-        Arc, Mutex as StdMutex,
+        Arc, Mutex as StdMutex, RwLock as StdRwLock,
     },
     thread,
     time::Duration,
@@ -48,17 +51,49 @@ use tokio::{
     time::sleep,
 };
 use zeroize::Zeroize;
+#[cfg(feature = "cloud-backup")]
+mod cloud_backup;
+#[cfg(feature = "stronghold")]
+mod duress;
+mod export;
+mod health;
+mod integrity;
 pub(crate) mod migration;
+mod mnemonic;
+#[cfg(feature = "mqtt-events")]
+mod mqtt_events;
+mod session;
+mod template;
+mod user_data;
 pub use crate::account_manager::migration::MigrationAddress;
+#[cfg(feature = "cloud-backup")]
+#[cfg_attr(docsrs, doc(cfg(feature = "cloud-backup")))]
+pub use cloud_backup::{BlobStore, CloudBackup, CloudBackupOptions, CloudBackupReport, S3BlobStore, WebDavBlobStore};
+pub use export::{ExportOptions, ExportRecord, EXPORT_FORMAT_VERSION};
+pub use health::{HealthReport, SubsystemHealth};
+pub use integrity::{IntegrityIssue, IntegrityIssueKind, IntegrityReport};
 use iota_migration::client::migration::{
     add_tryte_checksum, decode_migration_address, encode_migration_address, get_trytes_from_bundle, mine_bundle,
 };
 pub use migration::*;
+pub use mnemonic::{detect_mnemonic_language, MnemonicLanguage};
+#[cfg(feature = "mqtt-events")]
+#[cfg_attr(docsrs, doc(cfg(feature = "mqtt-events")))]
+pub use mqtt_events::{
+    MqttClientKey, MqttEventPublisher, MqttEventPublisherOptions, MqttTlsOptions, ACCOUNT_ID_PLACEHOLDER,
+};
+pub(crate) use template::interpolate_alias;
+pub use template::{AccountTemplate, ConsolidationSettings};
+pub use user_data::UserData;
 
 /// The default storage folder.
 pub const DEFAULT_STORAGE_FOLDER: &str = "./storage";
 
 const DEFAULT_OUTPUT_CONSOLIDATION_THRESHOLD: usize = 100;
+/// The storage usage percentage of the quota that triggers the `StorageQuotaWarning` event.
+const STORAGE_QUOTA_WARNING_PERCENTAGE: u64 = 90;
+/// How many accounts the account discovery syncs at the same time by default.
+const DEFAULT_ACCOUNT_DISCOVERY_CONCURRENCY: usize = 4;
 
 /// The default stronghold storage file name.
 #[cfg(feature = "stronghold")]
@@ -68,22 +103,106 @@ pub const STRONGHOLD_FILENAME: &str = "wallet.stronghold";
 /// The default RocksDB storage path.
 pub const ROCKSDB_FILENAME: &str = "db";
 
+/// The default name of the in-memory storage, identifying it like a storage path without creating a file.
+pub const MEMORY_STORAGE_NAME: &str = "memory";
+
 type AccountsMap = HashMap<String, AccountHandle>;
 
+/// The account owning an address, looked up on the manager address index.
+#[derive(Debug, Clone, PartialEq, Eq, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct AddressOwner {
+    /// The account identifier.
+    #[serde(rename = "accountId")]
+    account_id: String,
+    /// The address key index.
+    #[serde(rename = "keyIndex")]
+    key_index: usize,
+    /// Whether the address is a change address or not.
+    internal: bool,
+}
+
 #[doc(hidden)]
 #[derive(Debug, Clone)]
-pub struct AccountStore(Arc<RwLock<AccountsMap>>);
+pub struct AccountStore {
+    accounts: Arc<RwLock<AccountsMap>>,
+    address_index: Arc<StdRwLock<HashMap<AddressWrapper, AddressOwner>>>,
+    pow_pool: PowPool,
+    message_cache: Arc<StdMutex<MessageCache>>,
+    in_flight_transfers: Arc<InFlightTransfers>,
+}
 
 impl AccountStore {
     pub(crate) fn new(inner: Arc<RwLock<AccountsMap>>) -> Self {
-        Self(inner)
+        Self {
+            accounts: inner,
+            address_index: Default::default(),
+            pow_pool: Default::default(),
+            message_cache: Default::default(),
+            in_flight_transfers: Default::default(),
+        }
+    }
+
+    /// Sets the PoW worker pool shared by the accounts of the store.
+    pub(crate) fn with_pow_pool(mut self, pow_pool: PowPool) -> Self {
+        self.pow_pool = pow_pool;
+        self
+    }
+
+    /// Gets the PoW worker pool mining the transactions of the accounts.
+    pub(crate) fn pow_pool(&self) -> &PowPool {
+        &self.pow_pool
+    }
+
+    /// Adds the account addresses to the address index.
+    /// Must be called whenever addresses are appended to an account that belongs to the store.
+    pub(crate) fn index_addresses(&self, account: &Account) {
+        let mut index = self.address_index.write().unwrap();
+        for address in account.addresses() {
+            index.insert(
+                address.address().clone(),
+                AddressOwner {
+                    account_id: account.id().clone(),
+                    key_index: *address.key_index(),
+                    internal: *address.internal(),
+                },
+            );
+        }
+    }
+
+    /// Removes the account addresses from the address index.
+    pub(crate) fn unindex_addresses(&self, account_id: &str) {
+        self.address_index
+            .write()
+            .unwrap()
+            .retain(|_, owner| owner.account_id != account_id);
+    }
+
+    /// Clears the address index.
+    pub(crate) fn clear_address_index(&self) {
+        self.address_index.write().unwrap().clear();
+    }
+
+    /// Gets the account owning the given address.
+    pub(crate) fn address_owner(&self, address: &AddressWrapper) -> Option<AddressOwner> {
+        self.address_index.read().unwrap().get(address).cloned()
+    }
+
+    /// Gets the transfers of the accounts that are in flight.
+    pub(crate) fn in_flight_transfers(&self) -> &Arc<InFlightTransfers> {
+        &self.in_flight_transfers
+    }
+
+    /// Gets the cache of the messages fetched and resolved by the syncs of the accounts.
+    pub(crate) fn message_cache(&self) -> std::sync::MutexGuard<'_, MessageCache> {
+        self.message_cache.lock().unwrap()
     }
 }
 
 impl Deref for AccountStore {
     type Target = RwLock<AccountsMap>;
     fn deref(&self) -> &Self::Target {
-        self.0.deref()
+        self.accounts.deref()
     }
 }
 
@@ -93,6 +212,8 @@ enum ManagerStorage {
     Stronghold,
     /// RocksDB storage.
     Rocksdb,
+    /// In-memory storage, with the snapshot it starts from.
+    Memory(Option<PathBuf>),
 }
 
 fn storage_password_to_encryption_key(password: &str) -> [u8; 32] {
@@ -112,6 +233,11 @@ pub struct AccountManagerBuilder {
     skip_polling: bool,
     storage_encryption_key: Option<[u8; 32]>,
     account_options: AccountOptions,
+    pow_core_budget: Option<usize>,
+    verify_integrity: bool,
+    max_clients: Option<usize>,
+    max_open_storage_files: Option<usize>,
+    request_log: Option<RequestLogOptions>,
 }
 
 impl Default for AccountManagerBuilder {
@@ -126,10 +252,26 @@ impl Default for AccountManagerBuilder {
             account_options: AccountOptions {
                 output_consolidation_threshold: DEFAULT_OUTPUT_CONSOLIDATION_THRESHOLD,
                 automatic_output_consolidation: true,
+                consolidate_to_change_address: false,
+                consolidation_output_count: 1,
+                storage_quota: None,
+                event_retention: None,
+                max_requests_per_sync: None,
                 sync_spent_outputs: false,
                 persist_events: false,
                 allow_create_multiple_empty_accounts: false,
+                change_address_verification: ChangeAddressVerification::Always,
+                output_lock_lease: DEFAULT_OUTPUT_LOCK_LEASE,
+                sync_retry: Default::default(),
+                max_address_index: None,
+                lazy_account_loading: false,
+                address_pre_derivation: None,
             },
+            pow_core_budget: None,
+            verify_integrity: false,
+            max_clients: None,
+            max_open_storage_files: None,
+            request_log: None,
         }
     }
 }
@@ -170,6 +312,21 @@ impl AccountManagerBuilder {
         self
     }
 
+    /// Keeps the records in memory instead of a database, so the manager doesn't write to disk. With a storage password
+    /// the records are encrypted. They're written to a file only on
+    /// [AccountManager#save_storage_snapshot](struct.AccountManager.html#method.save_storage_snapshot).
+    pub fn with_memory_storage(mut self) -> Self {
+        self.storage = ManagerStorage::Memory(None);
+        self
+    }
+
+    /// Keeps the records in memory, starting from a snapshot file written by
+    /// [AccountManager#save_storage_snapshot](struct.AccountManager.html#method.save_storage_snapshot).
+    pub fn with_memory_storage_snapshot(mut self, snapshot_path: impl AsRef<Path>) -> Self {
+        self.storage = ManagerStorage::Memory(Some(snapshot_path.as_ref().to_path_buf()));
+        self
+    }
+
     /// Sets the number of outputs an address must have to trigger the automatic consolidation process.
     pub fn with_output_consolidation_threshold(mut self, threshold: usize) -> Self {
         self.account_options.output_consolidation_threshold = threshold;
@@ -182,18 +339,91 @@ impl AccountManagerBuilder {
         self
     }
 
+    /// Consolidates the outputs into the latest unused change address instead of the address holding them.
+    pub fn with_output_consolidation_to_change_address(mut self) -> Self {
+        self.account_options.consolidate_to_change_address = true;
+        self
+    }
+
+    /// Sets the number of equal outputs the consolidation process creates.
+    pub fn with_output_consolidation_output_count(mut self, count: usize) -> Self {
+        self.account_options.consolidation_output_count = count;
+        self
+    }
+
     /// Enables fetching spent output history on sync.
     pub fn with_sync_spent_outputs(mut self) -> Self {
         self.account_options.sync_spent_outputs = true;
         self
     }
 
+    /// Sets the number of cores the manager can use to mine its transactions when using local PoW.
+    /// Each transaction is mined on at most the available cores, so a budget above the core count mines several
+    /// transactions concurrently. Defaults to the number of available cores.
+    pub fn with_pow_core_budget(mut self, budget: usize) -> Self {
+        self.pow_core_budget.replace(budget);
+        self
+    }
+
     /// Enables event persistence.
     pub fn with_event_persistence(mut self) -> Self {
         self.account_options.persist_events = true;
         self
     }
 
+    /// Sets the maximum size of the storage folder, in bytes.
+    /// After each background sync, a `StorageQuotaWarning` event is emitted when the storage uses most of the quota and
+    /// a `StorageQuotaExceeded` event when it goes over the quota.
+    pub fn with_storage_quota(mut self, max_bytes: u64) -> Self {
+        self.account_options.storage_quota.replace(max_bytes);
+        self
+    }
+
+    /// Prunes the persisted events older than `max_event_age` when the storage nears its quota.
+    pub fn with_storage_pruning(mut self, max_event_age: Duration) -> Self {
+        self.account_options.event_retention.replace(max_event_age);
+        self
+    }
+
+    /// Limits the node requests made by each account sync.
+    /// When the limit is reached, the sync finishes with the data fetched so far, flagging the synced account as
+    /// partial, and the next sync starts with the addresses that were left behind.
+    pub fn with_max_requests_per_sync(mut self, max_requests: usize) -> Self {
+        self.account_options.max_requests_per_sync.replace(max_requests);
+        self
+    }
+
+    /// Sets how the node requests of the account syncs are retried after a transient failure.
+    /// The number of retried requests is reported on the synced accounts.
+    pub fn with_sync_retry(mut self, options: SyncRetryOptions) -> Self {
+        self.account_options.sync_retry = options;
+        self
+    }
+
+    /// Limits the key index of the public and internal addresses the accounts derive, for deployments that allocate
+    /// fixed address ranges. Generating an address beyond the limit fails with
+    /// [AddressIndexLimitExceeded](../enum.Error.html#variant.AddressIndexLimitExceeded), and the sync stops its
+    /// address discovery at the limit.
+    pub fn with_max_address_index(mut self, max_address_index: usize) -> Self {
+        self.account_options.max_address_index.replace(max_address_index);
+        self
+    }
+
+    /// Sets when the remainder deposit addresses of Ledger accounts are displayed on the device for verification.
+    /// Defaults to [Always](../message/enum.ChangeAddressVerification.html#variant.Always).
+    pub fn with_change_address_verification(mut self, policy: ChangeAddressVerification) -> Self {
+        self.account_options.change_address_verification = policy;
+        self
+    }
+
+    /// Sets how long the inputs of a transfer that isn't running anymore, e.g. because its task died, stay reserved.
+    /// Locks held longer are released by the background sync or the next transfer, emitting an `OutputLockReleased`
+    /// event. The inputs of a running transfer are never released. Defaults to 10 minutes.
+    pub fn with_output_lock_lease(mut self, lease: Duration) -> Self {
+        self.account_options.output_lock_lease = lease;
+        self
+    }
+
     /// Enables creating multiple accounts without history.
     /// The wallet disables it by default to simplify account discovery.
     pub fn with_multiple_empty_accounts(mut self) -> Self {
@@ -201,8 +431,61 @@ impl AccountManagerBuilder {
         self
     }
 
+    /// Runs [AccountManager#verify_integrity](struct.AccountManager.html#method.verify_integrity) with repair on
+    /// startup, quarantining the corrupted records before they fail the account loading or a sync.
+    /// The check is skipped for the Stronghold storage, whose accounts are loaded when the password is set.
+    pub fn with_integrity_check(mut self) -> Self {
+        self.verify_integrity = true;
+        self
+    }
+
+    /// Writes the method, endpoint, duration and status of the node requests made by the syncs and the transfers to a
+    /// rotating log file, e.g. to attach it to the reports of sync failures. The addresses can be redacted.
+    pub fn with_request_log(mut self, options: RequestLogOptions) -> Self {
+        self.request_log.replace(options);
+        self
+    }
+
+    /// Limits the number of node clients kept open. Each distinct account client options use a client, holding its
+    /// node connections and MQTT connection. When the limit is reached, the least recently used idle client is dropped
+    /// to open a new one; its MQTT subscriptions are lost and the background polling keeps the accounts synced.
+    pub fn with_max_clients(mut self, max_clients: usize) -> Self {
+        self.max_clients.replace(max_clients);
+        self
+    }
+
+    /// Limits the number of files the RocksDB storage keeps open. Ignored by the Stronghold storage.
+    pub fn with_max_open_storage_files(mut self, max_open_files: usize) -> Self {
+        self.max_open_storage_files.replace(max_open_files);
+        self
+    }
+
+    /// Loads the accounts without subscribing their addresses to the MQTT topics. An account is subscribed when it's
+    /// first got with [AccountManager#get_account](struct.AccountManager.html#method.get_account) while the background
+    /// sync runs, so the wallets with many accounts only keep the subscriptions of the accounts in use.
+    pub fn with_lazy_account_loading(mut self) -> Self {
+        self.account_options.lazy_account_loading = true;
+        self
+    }
+
+    /// Derives and persists the next `buffer` public and internal addresses of each account on every background sync
+    /// run and when the Stronghold password is set, while the signer is available. The syncs and the change address
+    /// generation then use the stored addresses instead of waiting on a locked Stronghold or a disconnected Ledger.
+    /// The addresses displayed on a Ledger for verification are still derived by the device.
+    pub fn with_address_pre_derivation(mut self, buffer: usize) -> Self {
+        self.account_options.address_pre_derivation.replace(buffer);
+        self
+    }
+
     /// Builds the manager.
     pub async fn finish(self) -> crate::Result<AccountManager> {
+        if let Some(max_clients) = self.max_clients {
+            crate::client::set_max_clients(max_clients);
+        }
+        if let Some(request_log) = self.request_log {
+            crate::client::set_request_log(request_log)?;
+        }
+
         let (storage, storage_file_path, is_stronghold): (
             Option<Box<dyn StorageAdapter + Send + Sync>>,
             PathBuf,
@@ -229,7 +512,30 @@ impl AccountManagerBuilder {
                 let storage = if crate::storage::get(&path).await.is_ok() {
                     None
                 } else {
-                    let storage = crate::storage::rocksdb::RocksdbStorageAdapter::new(&path)?;
+                    let storage = match self.max_open_storage_files {
+                        Some(max_open_files) => {
+                            crate::storage::rocksdb::RocksdbStorageAdapter::with_max_open_files(&path, max_open_files)?
+                        }
+                        None => crate::storage::rocksdb::RocksdbStorageAdapter::new(&path)?,
+                    };
+                    Some(Box::new(storage) as Box<dyn StorageAdapter + Send + Sync>)
+                };
+                (storage, path, false)
+            }
+            ManagerStorage::Memory(snapshot_path) => {
+                let path = self
+                    .storage_folder
+                    .join(self.storage_file_name.as_deref().unwrap_or(MEMORY_STORAGE_NAME));
+                // like the RocksDB storage, a storage registered for the path is reused
+                let storage = if crate::storage::get(&path).await.is_ok() {
+                    None
+                } else {
+                    let storage = match snapshot_path {
+                        Some(snapshot_path) => {
+                            crate::storage::memory::MemoryStorageAdapter::from_snapshot(snapshot_path)?
+                        }
+                        None => crate::storage::memory::MemoryStorageAdapter::new(),
+                    };
                     Some(Box::new(storage) as Box<dyn StorageAdapter + Send + Sync>)
                 };
                 (storage, path, false)
@@ -242,11 +548,12 @@ impl AccountManagerBuilder {
 
         let sync_accounts_lock = Arc::new(Mutex::new(()));
 
+        let pow_pool = self.pow_core_budget.map(PowPool::new).unwrap_or_default();
         // with the stronghold storage, the accounts are loaded when the password is set
         let (accounts, loaded_accounts) = if is_stronghold {
-            (AccountStore::new(Default::default()), false)
+            (AccountStore::new(Default::default()).with_pow_pool(pow_pool), false)
         } else {
-            let accounts = AccountStore::new(Default::default());
+            let accounts = AccountStore::new(Default::default()).with_pow_pool(pow_pool);
             let res = AccountManager::load_accounts(
                 &accounts,
                 &storage_file_path,
@@ -260,16 +567,26 @@ impl AccountManagerBuilder {
             storage_folder: self.storage_folder,
             loaded_accounts: AtomicBool::new(loaded_accounts),
             storage_path: storage_file_path,
+            duress_session: Default::default(),
             accounts,
             stop_polling_sender: StdMutex::new(None),
             polling_handle: StdMutex::new(None),
+            background_sync_options: StdMutex::new(None),
+            paused: Mutex::new(false),
             generated_mnemonic: StdMutex::new(None),
             account_options: self.account_options,
             sync_accounts_lock,
             cached_migration_data: Default::default(),
             cached_migration_bundles: Default::default(),
+            sessions: Default::default(),
+            #[cfg(feature = "participation")]
+            participation_events: Default::default(),
         };
 
+        if self.verify_integrity && !is_stronghold {
+            instance.verify_integrity(true).await?;
+        }
+
         if !self.skip_polling {
             instance
                 .start_background_sync(
@@ -288,9 +605,38 @@ impl AccountManagerBuilder {
 pub(crate) struct AccountOptions {
     pub(crate) output_consolidation_threshold: usize,
     pub(crate) automatic_output_consolidation: bool,
+    pub(crate) consolidate_to_change_address: bool,
+    pub(crate) consolidation_output_count: usize,
+    pub(crate) storage_quota: Option<u64>,
+    pub(crate) event_retention: Option<Duration>,
+    pub(crate) max_requests_per_sync: Option<usize>,
     pub(crate) sync_spent_outputs: bool,
     pub(crate) persist_events: bool,
     pub(crate) allow_create_multiple_empty_accounts: bool,
+    pub(crate) change_address_verification: ChangeAddressVerification,
+    pub(crate) output_lock_lease: Duration,
+    pub(crate) sync_retry: SyncRetryOptions,
+    pub(crate) max_address_index: Option<usize>,
+    pub(crate) lazy_account_loading: bool,
+    pub(crate) address_pre_derivation: Option<usize>,
+}
+
+impl AccountOptions {
+    /// Checks that an address can be derived at the key index.
+    pub(crate) fn check_address_index(&self, key_index: usize) -> crate::Result<()> {
+        match self.max_address_index {
+            Some(max_address_index) if key_index > max_address_index => {
+                Err(crate::Error::AddressIndexLimitExceeded(key_index, max_address_index))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The key index range end the addresses can be derived below.
+    pub(crate) fn address_index_end(&self, end: usize) -> usize {
+        self.max_address_index
+            .map_or(end, |max_address_index| end.min(max_address_index + 1))
+    }
 }
 
 #[derive(Clone)]
@@ -338,6 +684,14 @@ pub struct MinedBundle {
 
 type CachedMigrationBundle = (Vec<BundledTransaction>, AddressWrapper, u64);
 
+/// The options of the running background sync, kept to restart it on [resume_all](struct.AccountManager.html#method.resume_all).
+#[derive(Clone, Copy)]
+struct BackgroundSyncOptions {
+    polling_interval: Duration,
+    automatic_output_consolidation: bool,
+    gap_limit: Option<usize>,
+}
+
 /// The account manager.
 ///
 /// Used to manage multiple accounts.
@@ -345,19 +699,24 @@ type CachedMigrationBundle = (Vec<BundledTransaction>, AddressWrapper, u64);
 pub struct AccountManager {
     storage_folder: PathBuf,
     loaded_accounts: AtomicBool,
-    /// the path to the storage.
-    #[getset(get = "pub")]
     storage_path: PathBuf,
+    /// The decoy storage folder while the duress password unlocked the wallet.
+    duress_session: Arc<StdRwLock<Option<PathBuf>>>,
     /// Returns a handle to the accounts store.
     #[getset(get = "pub")]
     accounts: AccountStore,
     stop_polling_sender: StdMutex<Option<BroadcastSender<()>>>,
     polling_handle: StdMutex<Option<thread::JoinHandle<()>>>,
+    background_sync_options: StdMutex<Option<BackgroundSyncOptions>>,
+    paused: Mutex<bool>,
     generated_mnemonic: StdMutex<Option<String>>,
     account_options: AccountOptions,
     sync_accounts_lock: Arc<Mutex<()>>,
     cached_migration_data: Mutex<HashMap<u64, CachedMigrationData>>,
     cached_migration_bundles: Mutex<HashMap<String, CachedMigrationBundle>>,
+    sessions: Arc<StdMutex<session::Sessions>>,
+    #[cfg(feature = "participation")]
+    participation_events: Arc<Mutex<Option<Vec<crate::participation::types::EventData>>>>,
 }
 
 impl Clone for AccountManager {
@@ -370,6 +729,7 @@ impl Clone for AccountManager {
             storage_folder: self.storage_folder.clone(),
             loaded_accounts: AtomicBool::new(self.loaded_accounts.load(Ordering::SeqCst)),
             storage_path: self.storage_path.clone(),
+            duress_session: self.duress_session.clone(),
             accounts: self.accounts.clone(),
             stop_polling_sender: StdMutex::new(
                 self.stop_polling_sender
@@ -378,11 +738,16 @@ impl Clone for AccountManager {
                     .clone(),
             ),
             polling_handle: StdMutex::new(None),
+            background_sync_options: StdMutex::new(None),
+            paused: Mutex::new(false),
             generated_mnemonic: StdMutex::new(None),
             account_options: self.account_options,
             sync_accounts_lock: self.sync_accounts_lock.clone(),
             cached_migration_data: Default::default(),
             cached_migration_bundles: Default::default(),
+            sessions: self.sessions.clone(),
+            #[cfg(feature = "participation")]
+            participation_events: self.participation_events.clone(),
         }
     }
 }
@@ -601,7 +966,7 @@ impl AccountManager {
             mine,
             timeout,
             offset,
-            self.storage_folder.join(log_file_name),
+            self.storage_folder().join(log_file_name),
         )
         .await?;
         let crackability = bundle_data.crackability;
@@ -702,6 +1067,8 @@ impl AccountManager {
 
     async fn unload_accounts(accounts: &AccountStore) -> crate::Result<()> {
         accounts.write().await.clear();
+        accounts.clear_address_index();
+        accounts.message_cache().clear();
         Ok(())
     }
 
@@ -718,10 +1085,13 @@ impl AccountManager {
             .get_accounts()
             .await?;
         for account in loaded_accounts {
+            let account_id = account.id().clone();
+            accounts.index_addresses(&account);
             accounts.write().await.insert(
-                account.id().clone(),
+                account_id.clone(),
                 AccountHandle::new(account, accounts.clone(), account_options, sync_accounts_lock.clone()),
             );
+            emit_account_lifecycle(account_id, AccountLifecycle::Loaded).await;
         }
 
         Ok(())
@@ -734,19 +1104,19 @@ impl AccountManager {
 
     pub(crate) async fn delete_internal(&self) -> crate::Result<()> {
         // safe to unwrap: we know the storage exists
-        let storage_id = crate::storage::remove(&self.storage_path).await.unwrap();
+        let storage_id = crate::storage::remove(&self.storage_path()).await.unwrap();
 
-        if self.storage_path.exists() {
-            if self.storage_path.is_file() {
-                std::fs::remove_file(&self.storage_path)?;
+        if self.storage_path().exists() {
+            if self.storage_path().is_file() {
+                std::fs::remove_file(&self.storage_path())?;
             } else {
-                std::fs::remove_dir_all(&self.storage_path)?;
+                std::fs::remove_dir_all(&self.storage_path())?;
             }
         }
 
         #[cfg(feature = "stronghold")]
         {
-            crate::stronghold::unload_snapshot(&self.storage_path, false).await?;
+            crate::stronghold::unload_snapshot(&self.storage_path(), false).await?;
 
             let stronghold_snapshot_path = self.stronghold_snapshot_path_internal(&storage_id).await?;
 
@@ -760,19 +1130,44 @@ impl AccountManager {
         Ok(())
     }
 
+    /// The path to the storage. During a duress session, it's the path to the decoy storage.
+    pub fn storage_path(&self) -> PathBuf {
+        match self
+            .duress_session
+            .read()
+            .expect("duress session lock poisoned")
+            .as_ref()
+        {
+            Some(decoy_folder) => decoy_folder.join(ROCKSDB_FILENAME),
+            None => self.storage_path.clone(),
+        }
+    }
+
+    fn storage_folder(&self) -> PathBuf {
+        match self
+            .duress_session
+            .read()
+            .expect("duress session lock poisoned")
+            .as_ref()
+        {
+            Some(decoy_folder) => decoy_folder.clone(),
+            None => self.storage_folder.clone(),
+        }
+    }
+
     /// Returns the path to the stronghold snapshot file.
     #[cfg(feature = "stronghold")]
     pub async fn stronghold_snapshot_path(&self) -> crate::Result<PathBuf> {
-        let storage_id = crate::storage::get(&self.storage_path).await?.lock().await.id();
+        let storage_id = crate::storage::get(&self.storage_path()).await?.lock().await.id();
         self.stronghold_snapshot_path_internal(storage_id).await
     }
 
     #[cfg(feature = "stronghold")]
     pub(crate) async fn stronghold_snapshot_path_internal(&self, storage_id: &str) -> crate::Result<PathBuf> {
         let stronghold_snapshot_path = if storage_id == crate::storage::stronghold::STORAGE_ID {
-            self.storage_path.clone()
+            self.storage_path()
         } else {
-            self.storage_folder.join(STRONGHOLD_FILENAME)
+            self.storage_folder().join(STRONGHOLD_FILENAME)
         };
         Ok(stronghold_snapshot_path)
     }
@@ -787,11 +1182,16 @@ impl AccountManager {
     }
 
     /// Starts monitoring the accounts with the node's mqtt topics.
+    /// With lazy account loading, only the accounts that were already got are monitored.
     async fn start_monitoring(accounts: AccountStore) {
         let mut account_handles = Vec::new();
         let account_store = accounts.read().await;
 
         for account_handle in account_store.values() {
+            if account_handle.account_options.lazy_account_loading && !account_handle.is_monitored() {
+                continue;
+            }
+            account_handle.set_monitored();
             account_handles.push(account_handle.clone());
         }
         drop(account_store);
@@ -820,11 +1220,29 @@ impl AccountManager {
             .lock()
             .map_err(|_| crate::Error::PoisonError)?
             .replace(stop_polling_sender);
+        self.background_sync_options
+            .lock()
+            .map_err(|_| crate::Error::PoisonError)?
+            .replace(BackgroundSyncOptions {
+                polling_interval,
+                automatic_output_consolidation,
+                gap_limit,
+            });
         Ok(())
     }
 
     /// Stops the background polling and MQTT monitoring.
     pub fn stop_background_sync(&self) -> crate::Result<()> {
+        self.background_sync_options
+            .lock()
+            .map_err(|_| crate::Error::PoisonError)?
+            .take();
+        self.stop_polling_and_monitoring()
+    }
+
+    /// Stops the polling thread, which also runs the reattachments and output consolidations, and unsubscribes
+    /// the accounts MQTT topics.
+    fn stop_polling_and_monitoring(&self) -> crate::Result<()> {
         if let Some(polling_handle) = self.polling_handle.lock().unwrap().take() {
             self.stop_polling_sender
                 .lock()
@@ -848,6 +1266,134 @@ impl AccountManager {
         Ok(())
     }
 
+    /// Pauses the background work of all accounts: the polling sync with its reattachments and output
+    /// consolidations, and the MQTT monitoring. Meant for when the app goes to background or the device enters a
+    /// low-power mode. Explicit calls such as syncs and transfers still run while paused.
+    /// Does nothing if the manager is already paused.
+    pub async fn pause_all(&self) -> crate::Result<()> {
+        let mut paused = self.paused.lock().await;
+        if *paused {
+            return Ok(());
+        }
+        // the accounts sync lock waits for a running sync to finish before stopping
+        let _sync_lock = self.sync_accounts_lock.lock().await;
+        if self
+            .polling_handle
+            .lock()
+            .map_err(|_| crate::Error::PoisonError)?
+            .is_some()
+        {
+            self.stop_polling_and_monitoring()?;
+        } else {
+            for account_handle in self.accounts.read().await.values() {
+                let _ = crate::monitor::unsubscribe(account_handle.clone()).await;
+            }
+        }
+        *paused = true;
+        log::debug!("[MANAGER] paused the background sync and monitoring");
+        Ok(())
+    }
+
+    /// Resumes the background work stopped by [pause_all](#method.pause_all), restarting the polling with the
+    /// options of the last [start_background_sync](#method.start_background_sync) call if it was running.
+    /// Does nothing if the manager isn't paused.
+    pub async fn resume_all(&self) -> crate::Result<()> {
+        let mut paused = self.paused.lock().await;
+        if !*paused {
+            return Ok(());
+        }
+        let options = *self
+            .background_sync_options
+            .lock()
+            .map_err(|_| crate::Error::PoisonError)?;
+        let polling = self
+            .polling_handle
+            .lock()
+            .map_err(|_| crate::Error::PoisonError)?
+            .is_some();
+        match options {
+            // the background sync wasn't restarted meanwhile with start_background_sync
+            Some(options) if !polling => {
+                self.start_background_sync(
+                    options.polling_interval,
+                    options.automatic_output_consolidation,
+                    options.gap_limit,
+                )
+                .await?
+            }
+            _ => Self::start_monitoring(self.accounts.clone()).await,
+        }
+        *paused = false;
+        log::debug!("[MANAGER] resumed the background sync and monitoring");
+        Ok(())
+    }
+
+    /// Whether the background work is paused with [pause_all](#method.pause_all).
+    pub async fn is_paused(&self) -> bool {
+        *self.paused.lock().await
+    }
+
+    /// Writes the records of the in-memory storage to a snapshot file, to start a manager from them later with
+    /// [AccountManagerBuilder#with_memory_storage_snapshot](struct.AccountManagerBuilder.html#method.with_memory_storage_snapshot).
+    /// The records stay encrypted if the storage has a password. Fails for the other storages, which persist their
+    /// records already.
+    pub async fn save_storage_snapshot(&self, path: impl AsRef<Path>) -> crate::Result<()> {
+        crate::storage::get(&self.storage_path())
+            .await?
+            .lock()
+            .await
+            .save_snapshot(path.as_ref())
+            .await
+    }
+
+    /// Shuts the manager down before the application exits, instead of dropping it in the middle of a write:
+    /// stops the background sync, refuses the new transfers and waits up to `timeout` for the in-flight ones to store
+    /// their message, delivers the pending event batch, flushes the storage, disconnects the MQTT brokers, closes the
+    /// API sessions and locks Stronghold, saving its snapshot.
+    /// Returns `false` if transfers were still in flight at the timeout; the other steps run anyway.
+    pub async fn shutdown(&self, timeout: Duration) -> crate::Result<bool> {
+        log::debug!("[MANAGER] shutting down");
+        self.stop_background_sync()?;
+        // the accounts sync lock waits for a running sync to finish
+        let _sync_lock = self.sync_accounts_lock.lock().await;
+        let transfers_finished = self.accounts.in_flight_transfers().shutdown(timeout).await;
+
+        crate::event::flush_event_batch().await;
+        crate::storage::get(&self.storage_path())
+            .await?
+            .lock()
+            .await
+            .flush()
+            .await?;
+
+        let mut client_options = Vec::new();
+        for account_handle in self.accounts.read().await.values() {
+            let _ = crate::monitor::unsubscribe(account_handle.clone()).await;
+            let options = account_handle.client_options().await;
+            if !client_options.contains(&options) {
+                client_options.push(options);
+            }
+        }
+        for options in client_options.iter().filter(|options| *options.mqtt_enabled()) {
+            let client = crate::client::get_client(options).await?;
+            let mut client = client.write().await;
+            if let Err(e) = client.subscriber().disconnect().await {
+                log::debug!("[MANAGER] MQTT disconnect error: {:?}", e);
+            }
+        }
+
+        self.close_all_sessions();
+        #[cfg(feature = "stronghold")]
+        {
+            let snapshot_path = self.stronghold_snapshot_path().await?;
+            if snapshot_path.exists() {
+                crate::lock_stronghold(&snapshot_path, true).await?;
+            }
+        }
+        log::debug!("[MANAGER] shut down");
+        Ok(transfers_finished)
+    }
+
     /// Clear the encryption key and then unload decrypted accounts in memory. Does nothing if storage is not encrypted
     pub async fn clear_storage_password(&self) -> crate::Result<()> {
         let is_encrypted = crate::storage::get(self.storage_path())
@@ -858,7 +1404,7 @@ impl AccountManager {
             .is_encrypted();
 
         if is_encrypted {
-            crate::storage::clear_encryption_key(&self.storage_path).await?;
+            crate::storage::clear_encryption_key(&self.storage_path()).await?;
             Self::unload_accounts(&self.accounts).await?;
             self.loaded_accounts.store(false, Ordering::SeqCst);
         }
@@ -867,19 +1413,21 @@ impl AccountManager {
     }
 
     /// Sets the password for the stored accounts.
+    /// Without loaded accounts, the password decrypts the storage and loads them. Otherwise the storage is encrypted
+    /// with it; if it is already encrypted, [change_storage_password](#method.change_storage_password) must be used.
     pub async fn set_storage_password<P: AsRef<str>>(&self, password: P) -> crate::Result<()> {
         let key = storage_password_to_encryption_key(password.as_ref());
 
         if self.accounts.read().await.is_empty() {
-            if !crate::storage::is_key_valid(&self.storage_path, &key).await? {
+            if !crate::storage::is_key_valid(&self.storage_path(), &key).await? {
                 return Err(crate::Error::RecordDecrypt("Invalid storage password".to_string()));
             }
 
-            crate::storage::set_encryption_key(&self.storage_path, key).await?;
+            crate::storage::set_encryption_key(&self.storage_path(), key).await?;
 
             Self::load_accounts(
                 &self.accounts,
-                &self.storage_path,
+                &self.storage_path(),
                 self.account_options,
                 self.sync_accounts_lock.clone(),
             )
@@ -887,32 +1435,51 @@ impl AccountManager {
             self.loaded_accounts.store(true, Ordering::SeqCst);
             crate::spawn(Self::start_monitoring(self.accounts.clone()));
         } else {
-            // first get the messages with the old encryption key
-            let mut account_messages = HashMap::new();
-            for account_handle in self.accounts.read().await.values() {
-                let account = account_handle.read().await;
-                let messages = account.list_messages(0, 0, None).await?;
-                account_messages.insert(account.id().clone(), messages);
+            if crate::storage::get(self.storage_path())
+                .await?
+                .lock()
+                .await
+                .is_encrypted()
+            {
+                return Err(crate::Error::CurrentStoragePasswordRequired);
             }
+            self.reencrypt_storage(key).await?;
+        }
 
-            crate::storage::set_encryption_key(&self.storage_path, key).await?;
+        Ok(())
+    }
 
-            // save the accounts and messages again to reencrypt with the new key
-            for account_handle in self.accounts.read().await.values() {
-                let mut account = account_handle.write().await;
-                account.save().await?;
-                let messages = account_messages
-                    .get(account.id())
-                    .ok_or_else(|| crate::Error::Storage("missing account messages".to_string()))?;
-                account.save_messages(messages.to_vec()).await?;
-            }
+    /// Changes the password of the encrypted storage, re-encrypting the stored records, including the evicted accounts.
+    pub async fn change_storage_password<P: AsRef<str>>(
+        &self,
+        current_password: P,
+        new_password: P,
+    ) -> crate::Result<()> {
+        let current_key = storage_password_to_encryption_key(current_password.as_ref());
+        let is_encrypted = crate::storage::get(self.storage_path())
+            .await?
+            .lock()
+            .await
+            .is_encrypted();
+        if !is_encrypted || !crate::storage::is_key_valid(&self.storage_path(), &current_key).await? {
+            return Err(crate::Error::RecordDecrypt("Invalid storage password".to_string()));
         }
+        self.reencrypt_storage(storage_password_to_encryption_key(new_password.as_ref()))
+            .await
+    }
 
-        Ok(())
+    /// Encrypts the stored records with the new encryption key, including the records of the accounts evicted with
+    /// [close_account](#method.close_account).
+    async fn reencrypt_storage(&self, key: [u8; 32]) -> crate::Result<()> {
+        crate::storage::reencrypt(&self.storage_path(), key).await
     }
 
     /// Sets the stronghold password.
+    /// Fails with [SeedMismatch](../enum.Error.html#variant.SeedMismatch) if the stronghold seed doesn't derive the
+    /// addresses of the loaded accounts. The addresses the syncs couldn't derive while Stronghold was locked are derived
+    /// then, emitting an [AddressDerivationResumed](../event/struct.AddressDerivationResumed.html) event.
     pub async fn set_stronghold_password<P: Into<String>>(&self, password: P) -> crate::Result<()> {
+        // the real snapshot is tried first, even during a duress session
         let stronghold_path = if crate::storage::get(&self.storage_path).await.unwrap().lock().await.id()
             == crate::storage::stronghold::STORAGE_ID
         {
@@ -920,12 +1487,23 @@ impl AccountManager {
         } else {
             self.storage_folder.join(STRONGHOLD_FILENAME)
         };
-        crate::stronghold::load_snapshot(&stronghold_path, stronghold_password(password)).await?;
+        let mut password = password.into();
+        if let Err(e) = crate::stronghold::load_snapshot(&stronghold_path, stronghold_password(password.clone())).await
+        {
+            // the duress password opens the decoy wallet
+            let is_duress = (self.accounts.read().await.is_empty() || self.is_duress_session())
+                && self.load_decoy_accounts(&password).await?;
+            password.zeroize();
+            return if is_duress { Ok(()) } else { Err(e.into()) };
+        }
+        password.zeroize();
+        // the real password ends a duress session, so the real accounts are loaded below
+        self.end_duress_session().await?;
 
         if self.accounts.read().await.is_empty() {
             Self::load_accounts(
                 &self.accounts,
-                &self.storage_path,
+                &self.storage_path(),
                 self.account_options,
                 self.sync_accounts_lock.clone(),
             )
@@ -934,9 +1512,33 @@ impl AccountManager {
             crate::spawn(Self::start_monitoring(self.accounts.clone()));
         }
 
+        // catch a restore of the wrong mnemonic before new addresses are generated from it
+        for account_handle in self.accounts.read().await.values() {
+            account_handle.verify_seed_fingerprint().await?;
+        }
+        // derive the addresses the syncs skipped while Stronghold was locked
+        for account_handle in self.accounts.read().await.values() {
+            account_handle.resume_address_derivations().await?;
+        }
+        if let Some(buffer) = self.account_options.address_pre_derivation {
+            crate::spawn(pre_derive_addresses(
+                self.accounts.read().await.values().cloned().collect(),
+                buffer,
+            ));
+        }
+
         Ok(())
     }
 
+    /// Locks the Stronghold snapshot.
+    /// A duress session ends, unloading the decoy accounts, so the real password loads the real accounts again.
+    #[cfg(feature = "stronghold")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "stronghold")))]
+    pub async fn lock_stronghold(&self) -> crate::Result<()> {
+        crate::stronghold::unload_snapshot(&self.stronghold_snapshot_path().await?, true).await?;
+        self.end_duress_session().await
+    }
+
     /// Changes the stronghold password.
     #[cfg(feature = "stronghold")]
     #[cfg_attr(docsrs, doc(cfg(feature = "stronghold")))]
@@ -981,7 +1583,7 @@ impl AccountManager {
         automatic_output_consolidation: bool,
         gap_limit: Option<usize>,
     ) -> crate::Result<()> {
-        let storage_file_path = self.storage_path.clone();
+        let storage_file_path = self.storage_path();
         let accounts = self.accounts.clone();
         let account_options = self.account_options;
         let sync_accounts_lock = self.sync_accounts_lock.clone();
@@ -1063,14 +1665,14 @@ impl AccountManager {
         let mnemonic = match mnemonic {
             Some(m) => {
                 self.verify_mnemonic(&m)?;
-                m
+                mnemonic::normalize_mnemonic(&m)?.0
             }
             None => self.generate_mnemonic()?,
         };
 
         let signer = crate::signing::get_signer(&signer_type).await;
         let mut signer = signer.lock().await;
-        signer.store_mnemonic(&self.storage_path, mnemonic).await?;
+        signer.store_mnemonic(&self.storage_path(), mnemonic).await?;
 
         if let Some(mut mnemonic) = self
             .generated_mnemonic
@@ -1084,12 +1686,16 @@ impl AccountManager {
         Ok(())
     }
 
-    /// Generates a new mnemonic.
+    /// Generates a new english mnemonic.
     pub fn generate_mnemonic(&self) -> crate::Result<String> {
+        self.generate_mnemonic_with_language(MnemonicLanguage::English)
+    }
+
+    /// Generates a new mnemonic using the wordlist of the given language.
+    pub fn generate_mnemonic_with_language(&self, language: MnemonicLanguage) -> crate::Result<String> {
         let mut entropy = [0u8; 32];
         crypto::utils::rand::fill(&mut entropy).map_err(|e| crate::Error::MnemonicEncode(format!("{:?}", e)))?;
-        let mnemonic = crypto::keys::bip39::wordlist::encode(&entropy, &crypto::keys::bip39::wordlist::ENGLISH)
-            .map_err(|e| crate::Error::MnemonicEncode(format!("{:?}", e)))?;
+        let mnemonic = mnemonic::encode_mnemonic(&entropy, language)?;
         self.generated_mnemonic
             .lock()
             .map_err(|_| crate::Error::PoisonError)?
@@ -1099,11 +1705,10 @@ impl AccountManager {
 
     /// Checks is the mnemonic is valid. If a mnemonic was generated with `generate_mnemonic()`, the mnemonic here
     /// should match the generated.
+    /// The wordlist language is detected from the mnemonic words, see [MnemonicLanguage](enum.MnemonicLanguage.html).
     pub fn verify_mnemonic<S: AsRef<str>>(&self, mnemonic: S) -> crate::Result<()> {
         // first we check if the mnemonic is valid to give meaningful errors
-        crypto::keys::bip39::wordlist::verify(mnemonic.as_ref(), &crypto::keys::bip39::wordlist::ENGLISH)
-            // TODO: crypto::bip39::wordlist::Error should impl Display
-            .map_err(|e| crate::Error::InvalidMnemonic(format!("{:?}", e)))?;
+        let (mnemonic, _) = mnemonic::normalize_mnemonic(mnemonic.as_ref())?;
 
         // then we check if the provided mnemonic matches the mnemonic generated with `generate_mnemonic`
         if let Some(generated_mnemonic) = self
@@ -1112,7 +1717,7 @@ impl AccountManager {
             .map_err(|_| crate::Error::PoisonError)?
             .as_ref()
         {
-            if generated_mnemonic != mnemonic.as_ref() {
+            if generated_mnemonic != &mnemonic {
                 return Err(crate::Error::InvalidMnemonic(
                     "doesn't match the generated mnemonic".to_string(),
                 ));
@@ -1127,12 +1732,49 @@ impl AccountManager {
         Ok(AccountInitialiser::new(
             client_options,
             self.accounts.clone(),
-            self.storage_path.clone(),
+            self.storage_path(),
             self.account_options,
             self.sync_accounts_lock.clone(),
         ))
     }
 
+    /// Creates a rehearsal copy of the account with the given index on a test network.
+    /// The copy has the signer type, account index and address key indexes of the account, but none of its
+    /// outputs or messages, so integrators can rehearse flows with an identical address layout before mainnet.
+    /// It's named after the original account and can't be looked up by index, since the index belongs to the
+    /// original account.
+    pub async fn clone_account_to_network<I: Into<AccountIdentifier>>(
+        &self,
+        account_id: I,
+        testnet_client_options: ClientOptions,
+    ) -> crate::Result<AccountHandle> {
+        let account_handle = self.get_account(account_id).await?;
+        let account = account_handle.read().await;
+        // the addresses are encoded with the HRP of the test network
+        let bech32_hrp = crate::client::bech32_hrp(&testnet_client_options).await?;
+        let mut addresses = Vec::new();
+        for address in account.addresses() {
+            addresses.push(
+                AddressBuilder::new()
+                    .address(AddressWrapper::new(*address.address().as_ref(), bech32_hrp.clone()))
+                    .key_index(*address.key_index())
+                    .internal(*address.internal())
+                    .outputs(Vec::new())
+                    .build()?,
+            );
+        }
+
+        self.create_account(testnet_client_options)?
+            .index(*account.index())
+            .signer_type(account.signer_type().clone())
+            .alias(format!("{} (testnet)", account.alias()))
+            .addresses(addresses)
+            .allow_create_multiple_empty_accounts()
+            .rehearsal_of(account.id().clone())
+            .initialise()
+            .await
+    }
+
     /// Deletes an account.
     pub async fn remove_account<I: Into<AccountIdentifier>>(&self, account_id: I) -> crate::Result<()> {
         self.check_storage_encryption()?;
@@ -1149,8 +1791,9 @@ impl AccountManager {
         };
 
         self.accounts.write().await.remove(&account_id);
+        self.accounts.unindex_addresses(&account_id);
 
-        crate::storage::get(&self.storage_path)
+        crate::storage::get(&self.storage_path())
             .await?
             .lock()
             .await
@@ -1160,13 +1803,72 @@ impl AccountManager {
         Ok(())
     }
 
+    /// Flushes the account to the storage and evicts it from memory, unsubscribing its MQTT topics.
+    /// Handles to the account stay usable until they're dropped; use
+    /// [AccountHandle#downgrade](../account/struct.AccountHandle.html#method.downgrade) to hold an account without
+    /// keeping it in memory. The account can be loaded again with [open_account](#method.open_account).
+    pub async fn close_account<I: Into<AccountIdentifier>>(&self, account_id: I) -> crate::Result<()> {
+        self.check_storage_encryption()?;
+        let account_handle = self.get_account(account_id).await?;
+        let account_id = {
+            let mut account = account_handle.write().await;
+            account.save().await?;
+            account.id().clone()
+        };
+
+        if account_handle.is_mqtt_enabled() {
+            let _ = crate::monitor::unsubscribe(account_handle).await;
+        }
+        self.accounts.write().await.remove(&account_id);
+        self.accounts.unindex_addresses(&account_id);
+        emit_account_lifecycle(account_id, AccountLifecycle::Evicted).await;
+
+        Ok(())
+    }
+
+    /// Loads an account evicted with [close_account](#method.close_account) from the storage.
+    pub async fn open_account(&self, index: usize) -> crate::Result<AccountHandle> {
+        self.check_storage_encryption()?;
+        if let Ok(account_handle) = self.get_account(index).await {
+            return Ok(account_handle);
+        }
+
+        let account = crate::storage::get(&self.storage_path())
+            .await?
+            .lock()
+            .await
+            .get_accounts()
+            .await?
+            .into_iter()
+            .find(|account| account.index() == &index && account.rehearsal_of().is_none())
+            .ok_or(crate::Error::AccountNotFound {
+                by: AccountIdentifier::Index(index),
+            })?;
+        let account_id = account.id().clone();
+        self.accounts.index_addresses(&account);
+        let account_handle = AccountHandle::new(
+            account,
+            self.accounts.clone(),
+            self.account_options,
+            self.sync_accounts_lock.clone(),
+        );
+        self.accounts
+            .write()
+            .await
+            .insert(account_id.clone(), account_handle.clone());
+        crate::monitor::monitor_account_addresses_balance(account_handle.clone()).await;
+        emit_account_lifecycle(account_id, AccountLifecycle::Loaded).await;
+
+        Ok(account_handle)
+    }
+
     /// Syncs all accounts.
     pub fn sync_accounts(&self) -> crate::Result<AccountsSynchronizer> {
         self.check_storage_encryption()?;
         Ok(AccountsSynchronizer::new(
             self.sync_accounts_lock.clone(),
             self.accounts.clone(),
-            self.storage_path.clone(),
+            self.storage_path(),
             self.account_options,
         ))
     }
@@ -1186,7 +1888,7 @@ impl AccountManager {
         let message = self
             .get_account(from_account_id)
             .await?
-            .transfer(Transfer::builder(to_address, amount, None).finish())
+            .transfer(Transfer::builder(to_address, amount, None).finish()?)
             .await?;
 
         // store the message on the receive account
@@ -1210,7 +1912,7 @@ impl AccountManager {
         let storage_path = {
             // create a account manager to setup the stronghold storage for the backup
             let mut manager = Self::builder()
-                .with_storage(&self.storage_folder, None)
+                .with_storage(&self.storage_folder(), None)
                 .unwrap() // safe to unwrap - password is None
                 .with_skip_polling()
                 .with_stronghold_storage()
@@ -1218,7 +1920,7 @@ impl AccountManager {
                 .await?;
             manager.accounts = self.accounts.clone(); // force manager to skip loading accounts
             manager.set_stronghold_password(stronghold_password).await?;
-            let stronghold_storage_path = self.storage_folder.join(STRONGHOLD_FILENAME);
+            let stronghold_storage_path = self.storage_folder().join(STRONGHOLD_FILENAME);
             let stronghold_storage = crate::storage::get(&stronghold_storage_path).await?;
 
             for (account_id, account_handle) in self.accounts.read().await.iter() {
@@ -1233,9 +1935,10 @@ impl AccountManager {
                 account.set_storage_path(stronghold_storage_path.clone());
                 account.save_messages(messages).await?;
                 // revert to original storage_path
-                account.set_storage_path(self.storage_path.clone());
+                account.set_storage_path(self.storage_path());
             }
-            self.storage_folder.join(STRONGHOLD_FILENAME)
+            Self::copy_user_data(&self.storage_path(), &stronghold_storage_path).await?;
+            self.storage_folder().join(STRONGHOLD_FILENAME)
         };
 
         let destination = if let Some(filename) = storage_path.file_name() {
@@ -1247,13 +1950,20 @@ impl AccountManager {
             let res = fs::copy(storage_path, &destination);
 
             let mut stronghold_storage = crate::storage::stronghold::StrongholdStorageAdapter::new(
-                &self.storage_folder.join(STRONGHOLD_FILENAME),
+                &self.storage_folder().join(STRONGHOLD_FILENAME),
             )
             // stronghold adapter `new` never fails
             .unwrap();
             for account_handle in self.accounts.read().await.values() {
                 stronghold_storage.remove(account_handle.read().await.id()).await?;
             }
+            let user_data_keys = self.user_data().list("").await?;
+            for key in &user_data_keys {
+                stronghold_storage.remove(&crate::storage::user_data_key(key)).await?;
+            }
+            if !user_data_keys.is_empty() {
+                stronghold_storage.remove(crate::storage::USER_DATA_INDEX_KEY).await?;
+            }
 
             res?;
             destination
@@ -1273,9 +1983,9 @@ impl AccountManager {
             return Err(crate::Error::StorageExists);
         }
 
-        let storage_file_path = self.storage_folder.join(ROCKSDB_FILENAME);
+        let storage_file_path = self.storage_folder().join(ROCKSDB_FILENAME);
 
-        fs::create_dir_all(&self.storage_folder)?;
+        fs::create_dir_all(&self.storage_folder())?;
 
         let stronghold_manager = Self::builder()
             .with_storage(source.parent().unwrap(), None)
@@ -1302,19 +2012,21 @@ impl AccountManager {
         self.set_stronghold_password(stronghold_password.clone()).await?;
         for (account_handle, messages) in import_data {
             let mut account = account_handle.write().await;
-            account.set_storage_path(self.storage_path.clone());
+            account.set_storage_path(self.storage_path());
+            self.accounts.index_addresses(&account);
             account.save().await?;
             account.save_messages(messages).await?;
             account.cached_messages = Default::default();
         }
+        Self::copy_user_data(&stronghold_manager.storage_path(), &self.storage_path()).await?;
         // wait for stronghold to finish its tasks
         let _ = crate::stronghold::actor_runtime().lock().await;
-        fs::copy(source, self.storage_folder.join(STRONGHOLD_FILENAME))?;
+        fs::copy(source, self.storage_folder().join(STRONGHOLD_FILENAME))?;
 
         #[cfg(feature = "stronghold")]
         {
             // force stronghold to read the snapshot again, ignoring any previous cached value
-            crate::stronghold::unload_snapshot(&self.storage_path, false).await?;
+            crate::stronghold::unload_snapshot(&self.storage_path(), false).await?;
             if let Err(e) = self.set_stronghold_password(stronghold_password).await {
                 fs::remove_file(&storage_file_path)?;
                 return Err(e);
@@ -1324,19 +2036,25 @@ impl AccountManager {
         Ok(())
     }
 
+    /// Gets the account owning the given address, if it belongs to any account of the manager.
+    pub fn address_owner(&self, address: &AddressWrapper) -> Option<AddressOwner> {
+        self.accounts.address_owner(address)
+    }
+
     /// Gets the account associated with the given identifier.
     pub async fn get_account<I: Into<AccountIdentifier>>(&self, account_id: I) -> crate::Result<AccountHandle> {
         self.check_storage_encryption()?;
         let account_id = account_id.into();
         let accounts = self.accounts.read().await;
 
-        let account = match account_id {
+        let account = match account_id.clone() {
             AccountIdentifier::Id(id) => accounts.get(&id),
             AccountIdentifier::Index(index) => {
                 let mut associated_account = None;
                 for account_handle in accounts.values() {
                     let account = account_handle.read().await;
-                    if account.index() == &index {
+                    // the index identifies the original account, not its rehearsal copies
+                    if account.index() == &index && account.rehearsal_of().is_none() {
                         // if we already found an account with this index,
                         // we error out since this is an incorrect usage of the API
                         // you can't use the index to get an account if you're using multiple signer types
@@ -1373,7 +2091,21 @@ impl AccountManager {
             }
         };
 
-        account.cloned().ok_or(crate::Error::RecordNotFound)
+        let account_handle = account
+            .cloned()
+            .ok_or(crate::Error::AccountNotFound { by: account_id })?;
+        drop(accounts);
+
+        // a lazily loaded account is monitored from its first use, or when the background sync starts
+        if account_handle.account_options.lazy_account_loading
+            && !account_handle.set_monitored()
+            && self.polling_handle.lock().unwrap().is_some()
+            && !*self.paused.lock().await
+        {
+            crate::monitor::monitor_account_addresses_balance(account_handle.clone()).await;
+        }
+
+        Ok(account_handle)
     }
 
     /// Gets all accounts from storage.
@@ -1421,6 +2153,20 @@ impl AccountManager {
             .map_err(|e| crate::Error::LegacyClientError(Box::new(e)))
     }
 
+    /// Queries the node for its health, milestone lag, pruning index, peers and plugins,
+    /// so the user can be warned when the node can't support the wallet features.
+    /// Uses the given client options or the client options of the first account.
+    pub async fn node_diagnostics(
+        &self,
+        client_options: Option<ClientOptions>,
+    ) -> crate::Result<crate::client::NodeDiagnostics> {
+        let client_options = match client_options {
+            Some(options) => options,
+            None => self.get_account(0).await?.client_options().await,
+        };
+        crate::client::node_diagnostics(&client_options).await
+    }
+
     // participation
     #[cfg(feature = "participation")]
     /// Participate in events
@@ -1465,11 +2211,73 @@ impl AccountManager {
     }
 
     #[cfg(feature = "participation")]
-    /// Get a participating events data
+    /// Get a participating events data.
+    /// Returns the events cached by
+    /// [register_participation_events](struct.AccountManager.html#method.register_participation_events) if any,
+    /// otherwise fetches them from the node of the first account.
     pub async fn get_participation_events(&self) -> crate::Result<Vec<crate::participation::types::EventData>> {
+        if let Some(events) = &*self.participation_events.lock().await {
+            return Ok(events.clone());
+        }
         let account = self.get_account(0).await?;
         account.get_participation_events().await
     }
+
+    #[cfg(feature = "participation")]
+    /// Fetches the events (name, milestones, payload type and status) known by the given participation nodes and
+    /// caches them, so [get_participation_events](struct.AccountManager.html#method.get_participation_events) returns
+    /// them without hard-coded event ids. Unreachable nodes are skipped; it fails only if no node answers.
+    pub async fn register_participation_events(
+        &self,
+        node_urls: Vec<String>,
+    ) -> crate::Result<Vec<crate::participation::types::EventData>> {
+        let mut events: Vec<crate::participation::types::EventData> = Vec::new();
+        let mut last_error = None;
+        let mut reached_node = false;
+        for node_url in node_urls {
+            let url = url::Url::parse(&node_url)?;
+            let event_ids = match crate::participation::endpoints::get_events(url.clone(), None).await {
+                Ok(event_ids) => event_ids,
+                Err(e) => {
+                    log::warn!("[PARTICIPATION] couldn't get the events of {}: {}", node_url, e);
+                    last_error.replace(e);
+                    continue;
+                }
+            };
+            reached_node = true;
+            for event_id in event_ids.event_ids {
+                if events.iter().any(|event| event.event_id == event_id) {
+                    continue;
+                }
+                let event = async {
+                    let information =
+                        crate::participation::endpoints::get_event_information(url.clone(), &event_id).await?;
+                    let status = crate::participation::endpoints::get_event_status(url.clone(), &event_id).await?;
+                    crate::Result::Ok(crate::participation::types::EventData {
+                        event_id: event_id.clone(),
+                        information,
+                        status,
+                    })
+                };
+                match event.await {
+                    Ok(event) => events.push(event),
+                    Err(e) => log::warn!(
+                        "[PARTICIPATION] couldn't get event {} from {}: {}",
+                        event_id,
+                        node_url,
+                        e
+                    ),
+                }
+            }
+        }
+        if !reached_node {
+            if let Some(e) = last_error {
+                return Err(e);
+            }
+        }
+        self.participation_events.lock().await.replace(events.clone());
+        Ok(events)
+    }
 }
 
 macro_rules! event_getters_impl {
@@ -1482,7 +2290,7 @@ macro_rules! event_getters_impl {
                 skip: usize,
                 from_timestamp: T,
             ) -> crate::Result<Vec<$event_ty>> {
-                crate::storage::get(&self.storage_path)
+                crate::storage::get(&self.storage_path())
                     .await?
                     .lock()
                     .await
@@ -1495,7 +2303,7 @@ macro_rules! event_getters_impl {
                 &self,
                 from_timestamp: T,
             ) -> crate::Result<usize> {
-                let count = crate::storage::get(&self.storage_path)
+                let count = crate::storage::get(&self.storage_path())
                     .await?
                     .lock()
                     .await
@@ -1535,6 +2343,7 @@ pub struct AccountsSynchronizer {
     account_options: AccountOptions,
     discover_accounts: bool,
     account_discovery_threshold: usize,
+    account_discovery_concurrency: usize,
     skip_change_addresses: bool,
     ran_account_discovery: bool,
     steps: Option<Vec<AccountSynchronizeStep>>,
@@ -1556,6 +2365,7 @@ impl AccountsSynchronizer {
             account_options,
             discover_accounts: true,
             account_discovery_threshold: 1,
+            account_discovery_concurrency: DEFAULT_ACCOUNT_DISCOVERY_CONCURRENCY,
             skip_change_addresses: false,
             ran_account_discovery: false,
             steps: None,
@@ -1592,6 +2402,13 @@ impl AccountsSynchronizer {
         self
     }
 
+    /// Sets how many accounts the discovery process syncs at the same time, 4 by default.
+    /// The discovery checks this many account indexes per round, so it can sync a few accounts past the last one.
+    pub fn account_discovery_concurrency(mut self, concurrency: usize) -> Self {
+        self.account_discovery_concurrency = concurrency;
+        self
+    }
+
     /// Sets the steps to run on the sync process.
     /// By default it runs all steps (check_for_new_used_addresses and sync_messages),
     /// but the library can pick what to run here.
@@ -1662,6 +2479,7 @@ impl AccountsSynchronizer {
                 .map(|a| (a.address().to_bech32(), a.balance(), a.outputs().clone()))
                 .collect();
             account.append_addresses(data.addresses.to_vec());
+            account_handle.accounts.index_addresses(&account);
             synced_data.push((account_handle, addresses_before_sync, data));
         }
         log::debug!("[AccountsSynchronizer] synced existing accounts");
@@ -1670,6 +2488,10 @@ impl AccountsSynchronizer {
         let mut last_account_index = 0;
         for (account_handle, _, _) in &synced_data {
             let account = account_handle.read().await;
+            // the rehearsal copies live on another network and share the index of their original account
+            if account.rehearsal_of().is_some() {
+                continue;
+            }
             if *account.index() >= last_account_index {
                 last_account_index = *account.index();
                 last_account.replace((
@@ -1699,6 +2521,7 @@ impl AccountsSynchronizer {
                             Some(signer_type),
                             self.account_options,
                             self.mutex.clone(),
+                            self.account_discovery_concurrency,
                         )
                         .await
                     } else {
@@ -1728,6 +2551,7 @@ impl AccountsSynchronizer {
                         account.set_addresses(synced_account_data.addresses.to_vec());
                     }
                     account.save().await?;
+                    account_handle.accounts.index_addresses(&account);
                     accounts.insert(account.id().clone(), account_handle.clone());
                     discovered_account_ids.push(account.id().clone());
                     synced_data.push((account_handle, Vec::new(), synced_account_data));
@@ -1805,6 +2629,8 @@ impl AccountsSynchronizer {
             let mut updated_messages = new_messages;
             updated_messages.extend(confirmation_changed_messages);
             synced_account.messages = updated_messages;
+            synced_account.partial = data.partial;
+            synced_account.retries = data.retries;
 
             let account = account_handle.read().await;
             synced_account.addresses = account.addresses().clone();
@@ -1830,8 +2656,23 @@ async fn poll(
 ) -> crate::Result<PollResponse> {
     log::debug!("[POLLING] poll");
     let polling_start_time = std::time::Instant::now();
-    let mut synchronizer =
-        AccountsSynchronizer::new(sync_accounts_lock, accounts.clone(), storage_file_path, account_options);
+    for account_handle in accounts.read().await.values() {
+        account_handle.release_stale_output_locks().await;
+    }
+    // probe the nodes so the misbehaving ones are quarantined before syncing
+    let mut probed_options = HashSet::new();
+    for account_handle in accounts.read().await.values() {
+        let client_options = account_handle.client_options().await;
+        if probed_options.insert(client_options.clone()) {
+            crate::client::probe_nodes(&client_options).await;
+        }
+    }
+    let mut synchronizer = AccountsSynchronizer::new(
+        sync_accounts_lock,
+        accounts.clone(),
+        storage_file_path.clone(),
+        account_options,
+    );
     synchronizer = synchronizer.skip_account_discovery().skip_change_addresses();
     if let Some(gap_limit) = gap_limit {
         synchronizer = synchronizer.gap_limit(gap_limit);
@@ -1965,6 +2806,7 @@ async fn poll(
             }
 
             if confirmed {
+                crate::client::record_message_confirmation(&message_id);
                 message.set_confirmed(Some(true));
                 account.save_messages(vec![message.clone()]).await?;
                 emit_confirmation_state_change(
@@ -1978,6 +2820,13 @@ async fn poll(
         }
         account.save().await?;
     }
+    // the sync succeeded, a failed quota check is only reported
+    if let Err(e) = check_storage_quota(&storage_file_path, account_options, Utc::now()).await {
+        log::warn!("[POLLING] couldn't check the storage quota: {}", e);
+    }
+    if let Some(buffer) = account_options.address_pre_derivation {
+        pre_derive_addresses(accounts.read().await.values().cloned().collect(), buffer).await;
+    }
     log::debug!("[POLLING] took: {:.2?}", polling_start_time.elapsed());
     Ok(PollResponse {
         ran_account_discovery: synchronizer.ran_account_discovery,
@@ -1985,6 +2834,82 @@ async fn poll(
     })
 }
 
+/// Fills the pre-derived address buffers of the accounts.
+async fn pre_derive_addresses(account_handles: Vec<AccountHandle>, buffer: usize) {
+    for account_handle in account_handles {
+        if let Err(e) = account_handle.pre_derive_addresses(buffer).await {
+            log::warn!("[ADDRESS] couldn't save the pre-derived addresses: {}", e);
+        }
+    }
+}
+
+/// Gets the size of the files under the given path.
+/// The files removed while they're listed, e.g. by a storage compaction, are ignored.
+fn storage_size(path: &Path) -> crate::Result<u64> {
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e.into()),
+    };
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e.into()),
+    };
+    let mut size = 0;
+    for entry in entries {
+        size += storage_size(&entry?.path())?;
+    }
+    Ok(size)
+}
+
+/// The storage size from which the quota warning is emitted.
+fn storage_quota_warning_bytes(max_bytes: u64) -> u64 {
+    (max_bytes as u128 * STORAGE_QUOTA_WARNING_PERCENTAGE as u128 / 100) as u64
+}
+
+/// Emits the storage quota events if the storage folder nears or exceeds the configured quota,
+/// pruning the events older than the retention period at `now` first if the pruning is enabled.
+async fn check_storage_quota(
+    storage_file_path: &Path,
+    account_options: AccountOptions,
+    now: DateTime<Utc>,
+) -> crate::Result<()> {
+    let max_bytes = match account_options.storage_quota {
+        Some(max_bytes) => max_bytes,
+        None => return Ok(()),
+    };
+    let storage_folder = storage_file_path.parent().unwrap_or(storage_file_path);
+    let warning_bytes = storage_quota_warning_bytes(max_bytes);
+
+    let mut used_bytes = storage_size(storage_folder)?;
+    if used_bytes >= warning_bytes {
+        if let Some(max_event_age) = account_options.event_retention {
+            let before = now.timestamp() - max_event_age.as_secs() as i64;
+            let pruned = crate::storage::get(storage_file_path)
+                .await?
+                .lock()
+                .await
+                .prune_events(before)
+                .await?;
+            log::debug!("[POLLING] pruned {} events to free storage", pruned);
+            if pruned > 0 {
+                used_bytes = storage_size(storage_folder)?;
+            }
+        }
+    }
+
+    if used_bytes >= max_bytes {
+        crate::event::emit_storage_quota_exceeded(used_bytes, max_bytes).await;
+    } else if used_bytes >= warning_bytes {
+        crate::event::emit_storage_quota_warning(used_bytes, max_bytes).await;
+    }
+    Ok(())
+}
+
 #[allow(clippy::too_many_arguments)]
 async fn discover_accounts(
     accounts: AccountStore,
@@ -1995,6 +2920,7 @@ async fn discover_accounts(
     signer_type: Option<SignerType>,
     account_options: AccountOptions,
     sync_accounts_lock: Arc<Mutex<()>>,
+    concurrency: usize,
 ) -> crate::Result<Vec<(AccountHandle, SyncedAccountData)>> {
     let mut synced_accounts = vec![];
     let mut empty_accounts = vec![];
@@ -2005,63 +2931,83 @@ async fn discover_accounts(
     }
     // start from 0 in case there are gaps in the accounts
     let mut index = 0;
-    loop {
-        // skip exisiting account indexes
-        while account_indexes.contains(&index) {
+    'discovery: loop {
+        // the next account indexes to check, skipping the existing ones
+        let mut batch = Vec::new();
+        while batch.len() < std::cmp::max(1, concurrency) {
+            if !account_indexes.contains(&index) {
+                batch.push(index);
+            }
             index += 1;
         }
 
-        let mut account_initialiser = AccountInitialiser::new(
-            client_options.clone(),
-            accounts.clone(),
-            storage_path.to_path_buf(),
-            account_options,
-            sync_accounts_lock.clone(),
-        )
-        .skip_persistence()
-        .index(index);
-        if let Some(signer_type) = &signer_type {
-            account_initialiser = account_initialiser.signer_type(signer_type.clone());
-        }
-        let account_handle = account_initialiser.initialise().await?;
-        {
-            let account = account_handle.read().await;
-            log::debug!(
-                "[SYNC] discovering account {}, signer type {:?}",
-                account.alias(),
-                account.signer_type()
-            );
-        }
-        let mut synchronizer = account_handle.sync().await;
-        if let Some(gap_limit) = gap_limit {
-            synchronizer = synchronizer.gap_limit(gap_limit);
+        let mut tasks = Vec::new();
+        for account_index in batch {
+            let mut account_initialiser = AccountInitialiser::new(
+                client_options.clone(),
+                accounts.clone(),
+                storage_path.to_path_buf(),
+                account_options,
+                sync_accounts_lock.clone(),
+            )
+            .skip_persistence()
+            .index(account_index);
+            if let Some(signer_type) = &signer_type {
+                account_initialiser = account_initialiser.signer_type(signer_type.clone());
+            }
+            let account_handle = account_initialiser.initialise().await?;
+            {
+                let account = account_handle.read().await;
+                log::debug!(
+                    "[SYNC] discovering account {}, signer type {:?}",
+                    account.alias(),
+                    account.signer_type()
+                );
+            }
+            emit_account_discovery_progress(account_index, AccountDiscoveryStatus::Syncing).await;
+            // the accounts share the client instance of the client options
+            tasks.push(tokio::spawn(async move {
+                let mut synchronizer = account_handle.sync().await;
+                if let Some(gap_limit) = gap_limit {
+                    synchronizer = synchronizer.gap_limit(gap_limit);
+                }
+                let result = synchronizer.get_new_history(true).await;
+                (account_index, account_handle, result)
+            }));
         }
-        match synchronizer.get_new_history(true).await {
-            Ok(synced_account_data) => {
-                let is_empty = synced_account_data
-                    .addresses
-                    .iter()
-                    .all(|a| a.balance() == 0 && a.outputs().is_empty());
-                log::debug!("[SYNC] discovered account {} is empty? {}", index, is_empty);
-                if is_empty {
-                    if index - (account_indexes.len() - 1) >= threshold {
-                        break;
-                    }
-                    empty_accounts.push((account_handle, synced_account_data));
-                } else {
-                    // add previous empty accounts, so we don't have gaps in the account list
-                    for empty_account in empty_accounts.drain(..) {
-                        synced_accounts.push(empty_account);
+
+        // the results are checked in index order, so the discovery stops on the same account as a sequential one
+        for task in futures::future::join_all(tasks).await {
+            let (account_index, account_handle, result) = task?;
+            match result {
+                Ok(synced_account_data) => {
+                    let is_empty = synced_account_data
+                        .addresses
+                        .iter()
+                        .all(|a| a.balance() == 0 && a.outputs().is_empty());
+                    log::debug!("[SYNC] discovered account {} is empty? {}", account_index, is_empty);
+                    emit_account_discovery_progress(account_index, AccountDiscoveryStatus::Synced { empty: is_empty })
+                        .await;
+                    if is_empty {
+                        if account_index - (account_indexes.len() - 1) >= threshold {
+                            break 'discovery;
+                        }
+                        empty_accounts.push((account_handle, synced_account_data));
+                    } else {
+                        // add previous empty accounts, so we don't have gaps in the account list
+                        for empty_account in empty_accounts.drain(..) {
+                            synced_accounts.push(empty_account);
+                        }
+                        synced_accounts.push((account_handle, synced_account_data));
                     }
-                    synced_accounts.push((account_handle, synced_account_data));
                 }
-                index += 1;
-            }
-            Err(e) => {
-                log::error!("[SYNC] failed to sync to discover account: {:?}", e);
-                // break if the account failed to sync
-                // this ensures that the previously discovered accounts get stored.
-                break;
+                Err(e) => {
+                    log::error!("[SYNC] failed to sync to discover account: {:?}", e);
+                    emit_account_discovery_progress(account_index, AccountDiscoveryStatus::Failed(e.to_string())).await;
+                    // break if the account failed to sync
+                    // this ensures that the previously discovered accounts get stored.
+                    break 'discovery;
+                }
             }
         }
     }
@@ -2103,8 +3049,13 @@ async fn consolidate_outputs_if_needed(
                 automatic_consolidation = false;
             }
         }
-        if automatic_consolidation {
-            synced.consolidate_outputs(false).await?;
+        if automatic_consolidation && synced.account_handle.account_options.automatic_output_consolidation {
+            synced
+                .consolidate_outputs(ConsolidationOptions::from_account_options(
+                    &synced.account_handle.account_options,
+                    false,
+                ))
+                .await?;
         }
     }
     Ok(())
@@ -2188,8 +3139,8 @@ mod tests {
 
     #[tokio::test]
     async fn store_accounts() {
-        crate::test_utils::with_account_manager(crate::test_utils::TestType::Storage, |manager, _| async move {
-            let account_handle = crate::test_utils::AccountCreator::new(&manager).create().await;
+        crate::testing::with_account_manager(crate::testing::TestType::Storage, |manager, _| async move {
+            let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
 
             manager
                 .remove_account(account_handle.read().await.id())
@@ -2201,8 +3152,8 @@ mod tests {
 
     #[tokio::test]
     async fn delete_storage() {
-        crate::test_utils::with_account_manager(crate::test_utils::TestType::Storage, |manager, _| async move {
-            crate::test_utils::AccountCreator::new(&manager).create().await;
+        crate::testing::with_account_manager(crate::testing::TestType::Storage, |manager, _| async move {
+            crate::testing::AccountCreator::new(&manager).create().await;
             manager
                 .delete()
                 .await
@@ -2212,9 +3163,46 @@ mod tests {
         .await;
     }
 
+    #[tokio::test]
+    async fn pause_and_resume() {
+        let manager = crate::testing::get_account_manager().await;
+        manager
+            .start_background_sync(std::time::Duration::from_secs(60), false, None)
+            .await
+            .unwrap();
+
+        manager.pause_all().await.unwrap();
+        assert!(manager.is_paused().await);
+        assert!(manager.polling_handle.lock().unwrap().is_none());
+        // pausing twice is a no-op
+        manager.pause_all().await.unwrap();
+
+        manager.resume_all().await.unwrap();
+        assert!(!manager.is_paused().await);
+        assert!(manager.polling_handle.lock().unwrap().is_some());
+
+        // a stopped background sync isn't restarted on resume
+        manager.stop_background_sync().unwrap();
+        manager.pause_all().await.unwrap();
+        manager.resume_all().await.unwrap();
+        assert!(manager.polling_handle.lock().unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn shutdown() {
+        let manager = crate::testing::get_account_manager().await;
+        manager
+            .start_background_sync(std::time::Duration::from_secs(60), false, None)
+            .await
+            .unwrap();
+
+        assert!(manager.shutdown(std::time::Duration::from_secs(5)).await.unwrap());
+        assert!(manager.polling_handle.lock().unwrap().is_none());
+    }
+
     #[tokio::test]
     async fn duplicated_alias() {
-        let manager = crate::test_utils::get_account_manager().await;
+        let manager = crate::testing::get_account_manager().await;
 
         let client_options = ClientOptionsBuilder::new()
             .with_node("https://api.lb-0.h.chrysalis-devnet.iota.cafe")
@@ -2247,7 +3235,7 @@ mod tests {
     #[tokio::test]
     #[ignore]
     async fn get_account() {
-        let manager = crate::test_utils::get_account_manager().await;
+        let manager = crate::testing::get_account_manager().await;
 
         let client_options = ClientOptionsBuilder::new()
             .with_node("https://api.lb-0.h.chrysalis-devnet.iota.cafe")
@@ -2273,7 +3261,7 @@ mod tests {
                 index: 0,
                 amount: 5,
                 is_spent: false,
-                address: crate::test_utils::generate_random_iota_address(),
+                address: crate::testing::generate_random_iota_address(),
                 kind: OutputKind::SignatureLockedSingle,
             };
             outputs.insert(output.id().unwrap(), output);
@@ -2341,7 +3329,7 @@ mod tests {
 
     #[tokio::test]
     async fn remove_account_with_message_history() {
-        crate::test_utils::with_account_manager(crate::test_utils::TestType::Storage, |manager, _| async move {
+        crate::testing::with_account_manager(crate::testing::TestType::Storage, |manager, _| async move {
             let client_options = ClientOptionsBuilder::new()
                 .with_node("https://api.lb-0.h.chrysalis-devnet.iota.cafe")
                 .expect("invalid node URL")
@@ -2354,7 +3342,7 @@ mod tests {
                 .messages(vec![Message::from_iota_message(
                     MessageId::new([0; 32]),
                     MessageBuilder::new()
-                        .with_nonce_provider(crate::test_utils::NoopNonceProvider {}, 4000f64)
+                        .with_nonce_provider(crate::testing::NoopNonceProvider {}, 4000f64)
                         .with_parents(Parents::new(vec![MessageId::new([0; 32])]).unwrap())
                         .with_payload(Payload::Indexation(Box::new(
                             IndexationPayload::new(b"index", &[0; 16]).unwrap(),
@@ -2364,7 +3352,7 @@ mod tests {
                         .unwrap(),
                     super::AccountStore::new(Default::default()),
                     "",
-                    &[crate::test_utils::generate_random_address()],
+                    &[crate::testing::generate_random_address()],
                     &ClientOptionsBuilder::new().build().unwrap(),
                 )
                 .with_confirmed(Some(true))
@@ -2383,7 +3371,7 @@ mod tests {
 
     #[tokio::test]
     async fn remove_account_with_balance() {
-        crate::test_utils::with_account_manager(crate::test_utils::TestType::Storage, |manager, _| async move {
+        crate::testing::with_account_manager(crate::testing::TestType::Storage, |manager, _| async move {
             let client_options = ClientOptionsBuilder::new()
                 .with_node("https://api.lb-0.h.chrysalis-devnet.iota.cafe")
                 .expect("invalid node URL")
@@ -2405,7 +3393,7 @@ mod tests {
                         index: 0,
                         amount: 5,
                         is_spent: false,
-                        address: crate::test_utils::generate_random_iota_address(),
+                        address: crate::testing::generate_random_iota_address(),
                         kind: OutputKind::SignatureLockedSingle,
                     }])
                     .build()
@@ -2423,7 +3411,7 @@ mod tests {
 
     #[tokio::test]
     async fn create_account_with_latest_without_history() {
-        crate::test_utils::with_account_manager(crate::test_utils::TestType::Storage, |manager, _| async move {
+        crate::testing::with_account_manager(crate::testing::TestType::Storage, |manager, _| async move {
             let client_options = ClientOptionsBuilder::new()
                 .with_node("https://api.lb-0.h.chrysalis-devnet.iota.cafe")
                 .expect("invalid node URL")
@@ -2446,7 +3434,7 @@ mod tests {
 
     #[tokio::test]
     async fn create_account_skip_persistence() {
-        crate::test_utils::with_account_manager(crate::test_utils::TestType::Storage, |manager, _| async move {
+        crate::testing::with_account_manager(crate::testing::TestType::Storage, |manager, _| async move {
             let client_options = ClientOptionsBuilder::new()
                 .with_node("https://api.lb-0.h.chrysalis-devnet.iota.cafe")
                 .expect("invalid node URL")
@@ -2465,8 +3453,10 @@ mod tests {
             let account_get_res = manager.get_account(account_handle.read().await.id()).await;
             assert!(account_get_res.is_err(), "{}", true);
             match account_get_res.unwrap_err() {
-                crate::Error::RecordNotFound => {}
-                _ => panic!("unexpected get_account response; expected RecordNotFound"),
+                crate::Error::AccountNotFound {
+                    by: crate::account::AccountIdentifier::Id(_),
+                } => {}
+                _ => panic!("unexpected get_account response; expected AccountNotFound"),
             }
         })
         .await;
@@ -2478,8 +3468,8 @@ mod tests {
         let _ = std::fs::remove_dir_all(backup_path);
         std::fs::create_dir_all(backup_path).unwrap();
 
-        crate::test_utils::with_account_manager(crate::test_utils::TestType::Storage, |manager, _| async move {
-            let account_handle = crate::test_utils::AccountCreator::new(&manager).create().await;
+        crate::testing::with_account_manager(crate::testing::TestType::Storage, |manager, _| async move {
+            let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
 
             // backup the stored accounts to ./backup/happy-path/${backup_name}
             let backup_path = manager.backup(backup_path, "password".to_string()).await.unwrap();
@@ -2496,7 +3486,7 @@ mod tests {
             };
             assert_eq!(backup_file_path.extension().unwrap_or_default(), "stronghold");
 
-            let is_encrypted = crate::storage::get(manager.storage_path())
+            let is_encrypted = crate::storage::get(&manager.storage_path())
                 .await
                 .unwrap()
                 .lock()
@@ -2505,7 +3495,7 @@ mod tests {
 
             // get another manager instance so we can import the accounts to a different storage
             #[allow(unused_mut)]
-            let mut manager = crate::test_utils::get_account_manager().await;
+            let mut manager = crate::testing::get_account_manager().await;
 
             #[cfg(feature = "stronghold")]
             {
@@ -2515,7 +3505,7 @@ mod tests {
                     .unwrap();
                 let _ = crate::stronghold::actor_runtime().lock().await;
 
-                if crate::storage::get(manager.storage_path())
+                if crate::storage::get(&manager.storage_path())
                     .await
                     .unwrap()
                     .lock()
@@ -2540,10 +3530,7 @@ mod tests {
 
             let imported_account = manager.get_account(account_handle.read().await.id()).await.unwrap();
             // set the account storage path field so the assert works
-            account_handle
-                .write()
-                .await
-                .set_storage_path(manager.storage_path().clone());
+            account_handle.write().await.set_storage_path(manager.storage_path());
             assert_eq!(&*account_handle.read().await, &*imported_account.read().await);
         })
         .await;
@@ -2551,19 +3538,19 @@ mod tests {
 
     #[tokio::test]
     async fn backup_and_restore_storage_already_exists() {
-        crate::test_utils::with_account_manager(crate::test_utils::TestType::Storage, |manager, _| async move {
+        crate::testing::with_account_manager(crate::testing::TestType::Storage, |manager, _| async move {
             let backup_path = PathBuf::from("./backup/account-exists");
             let _ = std::fs::remove_dir_all(&backup_path);
             std::fs::create_dir_all(&backup_path).unwrap();
             // first we'll create an example account
-            let address = crate::test_utils::generate_random_iota_address();
+            let address = crate::testing::generate_random_iota_address();
             let address = AddressBuilder::new()
                 .address(address.clone())
                 .key_index(0)
                 .outputs(vec![])
                 .build()
                 .unwrap();
-            crate::test_utils::AccountCreator::new(&manager)
+            crate::testing::AccountCreator::new(&manager)
                 .addresses(vec![address])
                 .create()
                 .await;
@@ -2582,13 +3569,35 @@ mod tests {
 
     #[tokio::test]
     async fn storage_password_reencrypt() {
-        crate::test_utils::with_account_manager(crate::test_utils::TestType::Storage, |manager, _| async move {
-            crate::test_utils::AccountCreator::new(&manager).create().await;
-            manager.set_storage_password("new-password").await.unwrap();
+        crate::testing::with_account_manager(crate::testing::TestType::Storage, |manager, _| async move {
+            crate::testing::AccountCreator::new(&manager).create().await;
+            let is_encrypted = crate::storage::get(&manager.storage_path())
+                .await
+                .unwrap()
+                .lock()
+                .await
+                .is_encrypted();
+            if is_encrypted {
+                // the storage was encrypted with "password" on creation
+                assert!(matches!(
+                    manager.set_storage_password("new-password").await,
+                    Err(crate::Error::CurrentStoragePasswordRequired)
+                ));
+                assert!(matches!(
+                    manager.change_storage_password("wrong-password", "new-password").await,
+                    Err(crate::Error::RecordDecrypt(_))
+                ));
+                manager
+                    .change_storage_password("password", "new-password")
+                    .await
+                    .unwrap();
+            } else {
+                manager.set_storage_password("new-password").await.unwrap();
+            }
             let account_store = super::AccountStore::new(Default::default());
             super::AccountManager::load_accounts(
                 &account_store,
-                manager.storage_path(),
+                &manager.storage_path(),
                 manager.account_options,
                 Default::default(),
             )
@@ -2599,12 +3608,84 @@ mod tests {
         .await;
     }
 
+    #[tokio::test]
+    async fn storage_password_change_with_evicted_account() {
+        let manager = crate::testing::get_account_manager().await;
+        manager.set_storage_password("password").await.unwrap();
+        let _loaded_account = crate::testing::AccountCreator::new(&manager).create().await;
+        let evicted_account = crate::testing::AccountCreator::new(&manager).create().await;
+        let (account_id, account_index, address) = {
+            let account = evicted_account.read().await;
+            account.record_balance_snapshot(true).await.unwrap();
+            (
+                account.id().clone(),
+                *account.index(),
+                account.latest_address().address().clone(),
+            )
+        };
+        let emit_event = |account_handle: crate::account::AccountHandle| {
+            let address = address.clone();
+            async move {
+                let account = account_handle.read().await;
+                emit_balance_change(
+                    &account,
+                    &address,
+                    Some(MessageId::new([1; 32])),
+                    BalanceChange::received(1),
+                    true,
+                )
+                .await
+                .unwrap();
+            }
+        };
+        emit_event(evicted_account.clone()).await;
+        manager.user_data().set("key", &"value").await.unwrap();
+        manager.close_account(account_index).await.unwrap();
+
+        manager
+            .change_storage_password("password", "new-password")
+            .await
+            .unwrap();
+
+        let account_handle = manager.open_account(account_index).await.unwrap();
+        assert_eq!(account_handle.get_events_since(0).await.unwrap().len(), 1);
+        // the deduplication record of the event was re-encrypted too
+        emit_event(account_handle.clone()).await;
+        assert_eq!(account_handle.get_events_since(0).await.unwrap().len(), 1);
+        let storage = crate::storage::get(&manager.storage_path()).await.unwrap();
+        assert_eq!(
+            storage
+                .lock()
+                .await
+                .get_balance_history(&account_id)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(
+            manager.user_data().get::<String>("key").await.unwrap().as_deref(),
+            Some("value")
+        );
+
+        let account_store = super::AccountStore::new(Default::default());
+        super::AccountManager::load_accounts(
+            &account_store,
+            &manager.storage_path(),
+            manager.account_options,
+            Default::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(account_store.read().await.len(), 2);
+    }
+
     #[tokio::test]
     async fn clear_storage_password() {
-        let manager = crate::test_utils::get_account_manager().await;
+        let manager = crate::testing::get_account_manager().await;
         manager.set_storage_password("password").await.unwrap();
 
-        let account = crate::test_utils::AccountCreator::new(&manager).create().await;
+        let account = crate::testing::AccountCreator::new(&manager).create().await;
         let account_id = account.id().await;
 
         manager.clear_storage_password().await.unwrap();
@@ -2613,9 +3694,9 @@ mod tests {
 
     #[tokio::test]
     async fn clear_non_existent_storage_password() {
-        let manager = crate::test_utils::get_account_manager().await;
+        let manager = crate::testing::get_account_manager().await;
 
-        let account = crate::test_utils::AccountCreator::new(&manager).create().await;
+        let account = crate::testing::AccountCreator::new(&manager).create().await;
         let account_id = account.id().await;
 
         manager.clear_storage_password().await.unwrap();
@@ -2624,10 +3705,10 @@ mod tests {
 
     #[tokio::test]
     async fn wrong_storage_password() {
-        let manager = crate::test_utils::get_account_manager().await;
+        let manager = crate::testing::get_account_manager().await;
         manager.set_storage_password("password").await.unwrap();
 
-        let _ = crate::test_utils::AccountCreator::new(&manager).create().await;
+        let _ = crate::testing::AccountCreator::new(&manager).create().await;
 
         manager.clear_storage_password().await.unwrap();
 
@@ -2639,10 +3720,10 @@ mod tests {
 
     #[tokio::test]
     async fn correct_storage_password() {
-        let manager = crate::test_utils::get_account_manager().await;
+        let manager = crate::testing::get_account_manager().await;
         manager.set_storage_password("password").await.unwrap();
 
-        let account = crate::test_utils::AccountCreator::new(&manager).create().await;
+        let account = crate::testing::AccountCreator::new(&manager).create().await;
         let account_id_1 = account.id().await;
 
         manager.clear_storage_password().await.unwrap();
@@ -2657,10 +3738,10 @@ mod tests {
 
     #[tokio::test]
     async fn wrong_storage_password_after_deleting_all_accounts() {
-        let manager = crate::test_utils::get_account_manager().await;
+        let manager = crate::testing::get_account_manager().await;
         manager.set_storage_password("password").await.unwrap();
 
-        let account_handle = crate::test_utils::AccountCreator::new(&manager).create().await;
+        let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
         assert_eq!(manager.get_accounts().await.unwrap().len(), 1);
 
         manager
@@ -2677,8 +3758,8 @@ mod tests {
 
     #[tokio::test]
     async fn get_balance_change_events() {
-        crate::test_utils::with_account_manager(crate::test_utils::TestType::Storage, |manager, _| async move {
-            let account_handle = crate::test_utils::AccountCreator::new(&manager).create().await;
+        crate::testing::with_account_manager(crate::testing::TestType::Storage, |manager, _| async move {
+            let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
             let account = account_handle.read().await;
             let change_events = vec![
                 BalanceChange::spent(0),
@@ -2718,18 +3799,15 @@ mod tests {
 
     #[tokio::test]
     async fn get_transaction_confirmation_events() {
-        crate::test_utils::with_account_manager(crate::test_utils::TestType::Storage, |manager, _| async move {
-            let account_handle = crate::test_utils::AccountCreator::new(&manager).create().await;
+        crate::testing::with_account_manager(crate::testing::TestType::Storage, |manager, _| async move {
+            let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
             let account = account_handle.read().await;
-            let m1 = crate::test_utils::GenerateMessageBuilder::default().build().await;
-            let m2 = crate::test_utils::GenerateMessageBuilder::default().build().await;
-            let m3 = crate::test_utils::GenerateMessageBuilder::default().build().await;
+            let m1 = crate::testing::GenerateMessageBuilder::default().build().await;
+            let m2 = crate::testing::GenerateMessageBuilder::default().build().await;
+            let m3 = crate::testing::GenerateMessageBuilder::default().build().await;
             let confirmation_change_events = vec![
                 (m1, true),
-                (
-                    crate::test_utils::GenerateMessageBuilder::default().build().await,
-                    false,
-                ),
+                (crate::testing::GenerateMessageBuilder::default().build().await, false),
                 (m2, false),
                 (m3, true),
             ];
@@ -2766,15 +3844,15 @@ mod tests {
 
     #[tokio::test]
     async fn get_reattachment_events() {
-        crate::test_utils::with_account_manager(crate::test_utils::TestType::Storage, |manager, _| async move {
-            let account_handle = crate::test_utils::AccountCreator::new(&manager).create().await;
+        crate::testing::with_account_manager(crate::testing::TestType::Storage, |manager, _| async move {
+            let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
             let account = account_handle.read().await;
-            let m1 = crate::test_utils::GenerateMessageBuilder::default().build().await;
-            let m2 = crate::test_utils::GenerateMessageBuilder::default().build().await;
-            let m3 = crate::test_utils::GenerateMessageBuilder::default().build().await;
+            let m1 = crate::testing::GenerateMessageBuilder::default().build().await;
+            let m2 = crate::testing::GenerateMessageBuilder::default().build().await;
+            let m3 = crate::testing::GenerateMessageBuilder::default().build().await;
             let reattachment_events = vec![
                 m1,
-                crate::test_utils::GenerateMessageBuilder::default().build().await,
+                crate::testing::GenerateMessageBuilder::default().build().await,
                 m2,
                 m3,
             ];
@@ -2812,44 +3890,41 @@ mod tests {
         ($event_type: expr, $count_get_fn: ident, $get_fn: ident) => {
             #[tokio::test]
             async fn $get_fn() {
-                crate::test_utils::with_account_manager(
-                    crate::test_utils::TestType::Storage,
-                    |manager, _| async move {
-                        let account_handle = crate::test_utils::AccountCreator::new(&manager).create().await;
-                        let account = account_handle.read().await;
-                        let m1 = crate::test_utils::GenerateMessageBuilder::default().build().await;
-                        let m2 = crate::test_utils::GenerateMessageBuilder::default().build().await;
-                        let m3 = crate::test_utils::GenerateMessageBuilder::default().build().await;
-                        let m4 = crate::test_utils::GenerateMessageBuilder::default().build().await;
-                        let events = vec![m1, m2, m3, m4];
-                        for message in &events {
-                            emit_transaction_event($event_type, &account, message.clone(), true)
-                                .await
-                                .unwrap();
-                        }
-                        assert!(
-                            manager.$count_get_fn(None).await.unwrap() == events.len(),
-                            "{}",
-                            true
-                        );
-                        for (take, skip) in &[(2, 0), (2, 2)] {
-                            let found = manager
-                                .$get_fn(*take, *skip, None)
-                                .await
-                                .unwrap()
-                                .into_iter()
-                                .map(|e| e.message)
-                                .collect::<Vec<Message>>();
-                            let expected = events
-                                .clone()
-                                .into_iter()
-                                .skip(*skip)
-                                .take(*take)
-                                .collect::<Vec<Message>>();
-                            assert!(found == expected, "{}", true);
-                        }
-                    },
-                )
+                crate::testing::with_account_manager(crate::testing::TestType::Storage, |manager, _| async move {
+                    let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
+                    let account = account_handle.read().await;
+                    let m1 = crate::testing::GenerateMessageBuilder::default().build().await;
+                    let m2 = crate::testing::GenerateMessageBuilder::default().build().await;
+                    let m3 = crate::testing::GenerateMessageBuilder::default().build().await;
+                    let m4 = crate::testing::GenerateMessageBuilder::default().build().await;
+                    let events = vec![m1, m2, m3, m4];
+                    for message in &events {
+                        emit_transaction_event($event_type, &account, message.clone(), true)
+                            .await
+                            .unwrap();
+                    }
+                    assert!(
+                        manager.$count_get_fn(None).await.unwrap() == events.len(),
+                        "{}",
+                        true
+                    );
+                    for (take, skip) in &[(2, 0), (2, 2)] {
+                        let found = manager
+                            .$get_fn(*take, *skip, None)
+                            .await
+                            .unwrap()
+                            .into_iter()
+                            .map(|e| e.message)
+                            .collect::<Vec<Message>>();
+                        let expected = events
+                            .clone()
+                            .into_iter()
+                            .skip(*skip)
+                            .take(*take)
+                            .collect::<Vec<Message>>();
+                        assert!(found == expected, "{}", true);
+                    }
+                })
                 .await;
             }
         };
@@ -2865,4 +3940,267 @@ mod tests {
         get_broadcast_event_count,
         get_broadcast_events
     );
+
+    #[tokio::test]
+    async fn storage_quota() {
+        crate::testing::with_account_manager(crate::testing::TestType::Storage, |manager, _| async move {
+            let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
+            {
+                let account = account_handle.read().await;
+                emit_balance_change(
+                    &account,
+                    account.latest_address().address(),
+                    None,
+                    BalanceChange::spent(1),
+                    true,
+                )
+                .await
+                .unwrap();
+            }
+            assert_eq!(manager.get_balance_change_event_count(None).await.unwrap(), 1);
+
+            let exceeded = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+            let exceeded_ = exceeded.clone();
+            let id =
+                on_storage_quota_exceeded(move |_| exceeded_.store(true, std::sync::atomic::Ordering::SeqCst)).await;
+
+            let options = super::AccountOptions {
+                storage_quota: Some(1),
+                event_retention: Some(std::time::Duration::from_secs(0)),
+                ..manager.account_options
+            };
+            // the events were persisted before the next second
+            super::check_storage_quota(
+                &manager.storage_path(),
+                options,
+                chrono::Utc::now() + chrono::Duration::seconds(1),
+            )
+            .await
+            .unwrap();
+            remove_storage_quota_exceeded_listener(&id).await;
+
+            assert!(exceeded.load(std::sync::atomic::Ordering::SeqCst));
+            assert_eq!(manager.get_balance_change_event_count(None).await.unwrap(), 0);
+        })
+        .await;
+    }
+
+    #[test]
+    fn storage_quota_thresholds() {
+        assert_eq!(super::storage_quota_warning_bytes(50), 45);
+        assert_eq!(super::storage_quota_warning_bytes(1000), 900);
+        // a storage removed while it's measured is empty
+        assert_eq!(
+            super::storage_size(std::path::Path::new("./test-storage/missing")).unwrap(),
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn max_address_index() {
+        let manager = crate::testing::get_account_manager().await;
+        let options = super::AccountOptions {
+            max_address_index: Some(10),
+            ..manager.account_options
+        };
+        assert!(options.check_address_index(10).is_ok());
+        assert!(matches!(
+            options.check_address_index(11),
+            Err(crate::Error::AddressIndexLimitExceeded(11, 10))
+        ));
+        assert_eq!(options.address_index_end(20), 11);
+        assert_eq!(options.address_index_end(5), 5);
+    }
+
+    #[tokio::test]
+    async fn address_owner() {
+        let manager = crate::testing::get_account_manager().await;
+        let address = crate::testing::generate_random_address();
+        let account_handle = crate::testing::AccountCreator::new(&manager)
+            .addresses(vec![address.clone()])
+            .create()
+            .await;
+
+        let owner = manager.address_owner(address.address()).unwrap();
+        assert_eq!(owner.account_id(), &account_handle.id().await);
+        assert_eq!(owner.key_index(), address.key_index());
+        assert!(manager
+            .address_owner(&crate::testing::generate_random_iota_address())
+            .is_none());
+
+        manager.remove_account(account_handle.id().await).await.unwrap();
+        assert!(manager.address_owner(address.address()).is_none());
+    }
+
+    #[tokio::test]
+    async fn close_account() {
+        let manager = crate::testing::get_account_manager().await;
+        let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
+        let account_id = account_handle.id().await;
+        let index = account_handle.index().await;
+
+        let lifecycle = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let lifecycle_ = lifecycle.clone();
+        let account_id_ = account_id.clone();
+        let listener = on_account_lifecycle(move |event| {
+            if event.account_id() == &account_id_ {
+                lifecycle_.lock().unwrap().push(event.lifecycle().clone());
+            }
+        })
+        .await;
+
+        let weak_handle = account_handle.downgrade();
+        manager.close_account(index).await.unwrap();
+        assert!(manager.get_account(index).await.is_err());
+        assert!(weak_handle.upgrade().is_some());
+        drop(account_handle);
+        // let the monitoring task spawned on the account creation release its handle
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        assert!(weak_handle.upgrade().is_none());
+
+        let account_handle = manager.open_account(index).await.unwrap();
+        assert_eq!(account_handle.id().await, account_id);
+        remove_account_lifecycle_listener(&listener).await;
+        assert_eq!(
+            *lifecycle.lock().unwrap(),
+            vec![AccountLifecycle::Evicted, AccountLifecycle::Loaded]
+        );
+    }
+
+    #[tokio::test]
+    async fn close_account_keeps_index_and_alias() {
+        let manager = crate::testing::get_account_manager().await;
+        let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
+        let index = account_handle.index().await;
+        let alias = account_handle.alias().await;
+        manager.close_account(index).await.unwrap();
+
+        let client_options = ClientOptionsBuilder::new()
+            .with_node("https://api.lb-0.h.chrysalis-devnet.iota.cafe")
+            .unwrap()
+            .build()
+            .unwrap();
+        let res = manager
+            .create_account(client_options.clone())
+            .unwrap()
+            .alias(&alias)
+            .allow_create_multiple_empty_accounts()
+            .initialise()
+            .await;
+        assert!(matches!(res, Err(crate::Error::AccountAliasAlreadyExists)));
+
+        let next_account_handle = manager
+            .create_account(client_options)
+            .unwrap()
+            .alias("next")
+            .allow_create_multiple_empty_accounts()
+            .initialise()
+            .await
+            .unwrap();
+        assert_eq!(next_account_handle.index().await, index + 1);
+    }
+
+    #[tokio::test]
+    async fn clone_account_to_network() {
+        let manager = crate::testing::get_account_manager().await;
+        let account_handle = crate::testing::AccountCreator::new(&manager)
+            .addresses(vec![
+                crate::testing::generate_random_address(),
+                AddressBuilder::new()
+                    .key_index(3)
+                    .address(crate::testing::generate_random_iota_address())
+                    .outputs(Vec::new())
+                    .internal(true)
+                    .build()
+                    .unwrap(),
+            ])
+            .create()
+            .await;
+        let index = account_handle.index().await;
+
+        let network_parameters = serde_json::from_value(serde_json::json!({ "bech32Hrp": "rms" })).unwrap();
+        let testnet_options = ClientOptionsBuilder::new()
+            .with_network("testnet")
+            .with_network_parameters(network_parameters)
+            .build()
+            .unwrap();
+        let clone = manager.clone_account_to_network(index, testnet_options).await.unwrap();
+        assert_ne!(clone.id().await, account_handle.id().await);
+        assert_eq!(clone.index().await, index);
+        assert_eq!(clone.rehearsal_of().await, Some(account_handle.id().await));
+        let layout = |addresses: Vec<crate::address::Address>| {
+            addresses
+                .into_iter()
+                .map(|a| (*a.address().as_ref(), *a.key_index(), *a.internal()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            layout(clone.addresses().await),
+            layout(account_handle.addresses().await)
+        );
+        assert!(clone.addresses().await.iter().all(|a| a.outputs().is_empty()));
+        // the addresses are encoded for the test network
+        assert!(clone
+            .addresses()
+            .await
+            .iter()
+            .all(|a| a.address().to_bech32().starts_with("rms1")));
+
+        // the index still identifies the original account
+        assert_eq!(
+            manager.get_account(index).await.unwrap().id().await,
+            account_handle.id().await
+        );
+        // and the rehearsal copy doesn't take an account index
+        let next_account = manager
+            .create_account(account_handle.client_options().await)
+            .unwrap()
+            .alias("next")
+            .allow_create_multiple_empty_accounts()
+            .initialise()
+            .await
+            .unwrap();
+        assert_eq!(next_account.index().await, index + 1);
+    }
+
+    #[tokio::test]
+    async fn lazy_account_loading() {
+        let manager = crate::testing::get_account_manager().await;
+        let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
+        assert!(account_handle.is_monitored());
+        let account_id = account_handle.id().await;
+
+        // the accounts loaded lazily aren't monitored until they're got
+        let lazy_manager = super::AccountManager::builder()
+            .with_storage(&manager.storage_folder, None)
+            .unwrap()
+            .with_skip_polling()
+            .with_lazy_account_loading()
+            .finish()
+            .await
+            .unwrap();
+        let loaded_handle = lazy_manager.accounts().read().await.get(&account_id).cloned().unwrap();
+        assert!(!loaded_handle.is_monitored());
+        lazy_manager.get_account(&account_id).await.unwrap();
+        assert!(loaded_handle.is_monitored());
+    }
+
+    #[tokio::test]
+    async fn pow_core_budget() {
+        let manager = crate::testing::get_account_manager().await;
+        let budget_manager = super::AccountManager::builder()
+            .with_storage(&manager.storage_folder, None)
+            .unwrap()
+            .with_skip_polling()
+            .with_pow_core_budget(1000)
+            .finish()
+            .await
+            .unwrap();
+        // each manager mines on its own budget
+        assert_eq!(budget_manager.accounts.pow_pool().free_cores(), 1000);
+        assert_eq!(
+            manager.accounts.pow_pool().free_cores(),
+            crate::client::PowPool::default().free_cores()
+        );
+    }
 }
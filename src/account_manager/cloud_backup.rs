@@ -0,0 +1,493 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{
+    export::{write_account_records, write_record},
+    AccountManager, ExportRecord, EXPORT_FORMAT_VERSION,
+};
+
+use chrono::Utc;
+use crypto::{keys::pbkdf::PBKDF2_HMAC_SHA512, macs::hmac::HMAC_SHA256};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use zeroize::Zeroize;
+
+use std::{
+    collections::HashSet,
+    convert::TryInto,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+mod s3;
+mod webdav;
+pub use s3::S3BlobStore;
+pub use webdav::WebDavBlobStore;
+
+/// The version of the cloud backup manifests.
+const MANIFEST_VERSION: u32 = 2;
+const MANIFEST_PREFIX: &str = "manifest-";
+const BLOB_PREFIX: &str = "blob-";
+/// The PBKDF2 iterations deriving the backup keys from the password.
+const KDF_ITERATIONS: u32 = 210_000;
+const KDF_SALT_LENGTH: usize = 16;
+
+/// A remote store holding the encrypted cloud backup blobs. The blob names are made of ASCII alphanumerics and `-`.
+#[async_trait::async_trait]
+pub trait BlobStore: Send + Sync {
+    /// Uploads the blob, replacing the blob with the same name.
+    async fn put(&self, name: &str, blob: Vec<u8>) -> crate::Result<()>;
+    /// Downloads the blob.
+    async fn get(&self, name: &str) -> crate::Result<Vec<u8>>;
+    /// Lists the names of the blobs starting with the prefix.
+    async fn list(&self, prefix: &str) -> crate::Result<Vec<String>>;
+}
+
+/// Options for [CloudBackup](struct.CloudBackup.html).
+#[derive(Debug, Clone)]
+pub struct CloudBackupOptions {
+    /// Identifies the device in the backup manifests, made of ASCII alphanumerics and `-`.
+    pub device_id: String,
+    /// The interval of the periodic uploads started with
+    /// [AccountManager#start_cloud_backup](struct.AccountManager.html#method.start_cloud_backup).
+    pub interval: Duration,
+}
+
+/// A stored manifest: the salt of the backup keys in clear, and the encrypted [Manifest](struct.Manifest.html).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ManifestEnvelope {
+    version: u32,
+    /// The hex encoded key derivation salt.
+    salt: String,
+    /// The hex encoded encrypted manifest.
+    manifest: String,
+}
+
+impl ManifestEnvelope {
+    fn salt(&self, name: &str) -> crate::Result<[u8; KDF_SALT_LENGTH]> {
+        hex::decode(&self.salt)
+            .ok()
+            .and_then(|salt| salt.try_into().ok())
+            .ok_or_else(|| invalid_manifest(name))
+    }
+}
+
+fn invalid_manifest(name: &str) -> crate::Error {
+    crate::Error::CloudBackup(format!("invalid manifest `{}`", name))
+}
+
+/// A backup snapshot: the blobs holding the account and event records at the time of the upload.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Manifest {
+    #[serde(rename = "deviceId")]
+    device_id: String,
+    #[serde(rename = "createdAt")]
+    created_at: i64,
+    /// The manifest the device knew about when it uploaded this one.
+    parent: Option<String>,
+    blobs: Vec<String>,
+}
+
+/// The result of a cloud backup upload.
+#[derive(Debug, Clone, Serialize)]
+pub struct CloudBackupReport {
+    /// The name of the uploaded manifest.
+    pub manifest: String,
+    /// The number of uploaded blobs.
+    #[serde(rename = "uploadedBlobs")]
+    pub uploaded_blobs: usize,
+    /// The number of blobs unchanged since a previous upload.
+    #[serde(rename = "reusedBlobs")]
+    pub reused_blobs: usize,
+}
+
+/// The keys derived from the backup password and a salt.
+#[derive(Clone, Zeroize)]
+#[zeroize(drop)]
+struct BackupKeys {
+    salt: [u8; KDF_SALT_LENGTH],
+    /// Encrypts the blobs and the manifests.
+    encryption_key: [u8; 32],
+    /// Names the blobs after their content.
+    blob_name_key: [u8; 32],
+}
+
+impl BackupKeys {
+    async fn derive(password: &str, salt: [u8; KDF_SALT_LENGTH]) -> crate::Result<Self> {
+        let mut password = password.to_string();
+        tokio::task::spawn_blocking(move || {
+            let mut dk = [0; 64];
+            // safe to unwrap (rounds > 0)
+            PBKDF2_HMAC_SHA512(password.as_bytes(), &salt, KDF_ITERATIONS, &mut dk).unwrap();
+            password.zeroize();
+            let mut keys = Self {
+                salt,
+                encryption_key: [0; 32],
+                blob_name_key: [0; 32],
+            };
+            keys.encryption_key.copy_from_slice(&dk[..32]);
+            keys.blob_name_key.copy_from_slice(&dk[32..]);
+            dk.zeroize();
+            keys
+        })
+        .await
+        .map_err(|e| crate::Error::CloudBackup(e.to_string()))
+    }
+
+    /// Names the blob with a MAC of its content, so the names don't reveal the content to the store.
+    fn blob_name(&self, plaintext: &[u8]) -> String {
+        let mut mac = [0; 32];
+        HMAC_SHA256(plaintext, &self.blob_name_key, &mut mac);
+        format!("{}{}", BLOB_PREFIX, hex::encode(mac))
+    }
+
+    fn encrypt(&self, plaintext: &[u8]) -> crate::Result<Vec<u8>> {
+        let mut blob = Vec::new();
+        crate::storage::encrypt_record(plaintext, &self.encryption_key, &mut blob)?;
+        Ok(blob)
+    }
+
+    fn decrypt(&self, blob: &[u8]) -> crate::Result<Vec<u8>> {
+        crate::storage::decrypt_bytes(blob, &self.encryption_key)
+    }
+}
+
+/// End-to-end encrypted backups of the accounts to a [BlobStore](trait.BlobStore.html).
+///
+/// Each upload stores a blob per account and a blob for the persisted events, named after a MAC of their content so
+/// the unchanged ones aren't uploaded again, and a manifest listing them. The keys are derived from the password with
+/// PBKDF2 and a random salt kept in the manifests. An upload fails with
+/// [CloudBackupConflict](../enum.Error.html#variant.CloudBackupConflict) when another device uploaded a manifest this
+/// device hasn't restored.
+pub struct CloudBackup {
+    store: Box<dyn BlobStore>,
+    options: CloudBackupOptions,
+    password: String,
+    /// The keys of the last salt used.
+    keys: Mutex<Option<BackupKeys>>,
+    /// The newest manifest this device uploaded or restored.
+    last_manifest: Mutex<Option<String>>,
+    stopped: AtomicBool,
+}
+
+impl Drop for CloudBackup {
+    fn drop(&mut self) {
+        self.password.zeroize();
+    }
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// Gets the device of a manifest name, `manifest-{timestamp}-{device_id}`.
+fn manifest_device(name: &str) -> Option<&str> {
+    name.strip_prefix(MANIFEST_PREFIX)?
+        .split_once('-')
+        .map(|(_, device)| device)
+}
+
+impl CloudBackup {
+    /// Creates the backup, encrypting the blobs with keys derived from the password.
+    pub fn new(store: Box<dyn BlobStore>, password: &str, options: CloudBackupOptions) -> crate::Result<Self> {
+        if !is_valid_name(&options.device_id) {
+            return Err(crate::Error::CloudBackup(format!(
+                "invalid device id `{}`",
+                options.device_id
+            )));
+        }
+        Ok(Self {
+            store,
+            options,
+            password: password.to_string(),
+            keys: Default::default(),
+            last_manifest: Default::default(),
+            stopped: AtomicBool::new(false),
+        })
+    }
+
+    /// Stops the periodic uploads.
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+    }
+
+    /// Gets the keys of the salt, a new random salt if `None`.
+    async fn keys(&self, salt: Option<[u8; KDF_SALT_LENGTH]>) -> crate::Result<BackupKeys> {
+        let mut keys = self.keys.lock().await;
+        if let Some(keys) = keys.as_ref().filter(|keys| salt.map_or(true, |salt| keys.salt == salt)) {
+            return Ok(keys.clone());
+        }
+        let salt = match salt {
+            Some(salt) => salt,
+            None => {
+                let mut salt = [0; KDF_SALT_LENGTH];
+                crypto::utils::rand::fill(&mut salt).map_err(|e| crate::Error::RandomGeneration(format!("{:?}", e)))?;
+                salt
+            }
+        };
+        let derived = BackupKeys::derive(&self.password, salt).await?;
+        keys.replace(derived.clone());
+        Ok(derived)
+    }
+
+    async fn download_envelope(&self, name: &str) -> crate::Result<ManifestEnvelope> {
+        let envelope: ManifestEnvelope = serde_json::from_slice(&self.store.get(name).await?)?;
+        if envelope.version != MANIFEST_VERSION {
+            return Err(crate::Error::CloudBackup(format!(
+                "unsupported manifest version {}",
+                envelope.version
+            )));
+        }
+        Ok(envelope)
+    }
+
+    /// Downloads the manifest, returning it with the keys of its salt.
+    async fn download_manifest(&self, name: &str) -> crate::Result<(Manifest, BackupKeys)> {
+        let envelope = self.download_envelope(name).await?;
+        let keys = self.keys(Some(envelope.salt(name)?)).await?;
+        let manifest = hex::decode(&envelope.manifest).map_err(|_| invalid_manifest(name))?;
+        Ok((serde_json::from_slice(&keys.decrypt(&manifest)?)?, keys))
+    }
+
+    async fn upload_manifest(&self, name: &str, manifest: &Manifest, keys: &BackupKeys) -> crate::Result<()> {
+        let envelope = ManifestEnvelope {
+            version: MANIFEST_VERSION,
+            salt: hex::encode(keys.salt),
+            manifest: hex::encode(keys.encrypt(&serde_json::to_vec(manifest)?)?),
+        };
+        self.store.put(name, serde_json::to_vec(&envelope)?).await
+    }
+
+    /// Gets the name of the newest remote manifest.
+    async fn newest_manifest(&self) -> crate::Result<Option<String>> {
+        // the names start with the zero padded creation timestamp
+        Ok(self.store.list(MANIFEST_PREFIX).await?.into_iter().max())
+    }
+}
+
+impl AccountManager {
+    /// Uploads an encrypted backup of the accounts, their messages and the persisted events.
+    /// With `force`, a manifest uploaded by another device doesn't make the upload fail.
+    pub async fn upload_cloud_backup(&self, backup: &CloudBackup, force: bool) -> crate::Result<CloudBackupReport> {
+        self.check_storage_encryption()?;
+        let mut last_manifest = backup.last_manifest.lock().await;
+        let newest = backup.newest_manifest().await?;
+        if let Some(newest) = &newest {
+            let known = last_manifest.as_ref() == Some(newest)
+                || manifest_device(newest) == Some(backup.options.device_id.as_str());
+            if !known && !force {
+                return Err(crate::Error::CloudBackupConflict(newest.clone()));
+            }
+        }
+
+        let mut plaintexts = Vec::new();
+        for account_handle in self.get_accounts().await? {
+            let mut records = Vec::new();
+            write_account_records(&mut records, &*account_handle.read().await).await?;
+            plaintexts.push(records);
+        }
+        let mut event_records = Vec::new();
+        for record in self.event_records().await? {
+            write_record(&mut event_records, &record).await?;
+        }
+        plaintexts.push(event_records);
+
+        // the backup keeps the salt of the newest manifest, so the unchanged blobs keep their name
+        let salt = match &newest {
+            Some(newest) => Some(backup.download_envelope(newest).await?.salt(newest)?),
+            None => None,
+        };
+        let keys = backup.keys(salt).await?;
+        let existing: HashSet<String> = backup.store.list(BLOB_PREFIX).await?.into_iter().collect();
+        let mut blobs = Vec::new();
+        let mut uploaded_blobs = 0;
+        for plaintext in plaintexts {
+            let name = keys.blob_name(&plaintext);
+            if !existing.contains(&name) && !blobs.contains(&name) {
+                backup.store.put(&name, keys.encrypt(&plaintext)?).await?;
+                uploaded_blobs += 1;
+            }
+            blobs.push(name);
+        }
+
+        let created_at = Utc::now().timestamp_millis();
+        let manifest = Manifest {
+            device_id: backup.options.device_id.clone(),
+            created_at,
+            parent: newest,
+            blobs,
+        };
+        let name = format!("{}{:020}-{}", MANIFEST_PREFIX, created_at, backup.options.device_id);
+        backup.upload_manifest(&name, &manifest, &keys).await?;
+        last_manifest.replace(name.clone());
+
+        Ok(CloudBackupReport {
+            manifest: name,
+            reused_blobs: manifest.blobs.len() - uploaded_blobs,
+            uploaded_blobs,
+        })
+    }
+
+    /// Restores the newest remote backup, returning the name of its manifest.
+    /// The manager must not have any account.
+    pub async fn restore_cloud_backup(&self, backup: &CloudBackup) -> crate::Result<String> {
+        let mut last_manifest = backup.last_manifest.lock().await;
+        let name = backup
+            .newest_manifest()
+            .await?
+            .ok_or_else(|| crate::Error::CloudBackup("no backup found".to_string()))?;
+        let (manifest, keys) = backup.download_manifest(&name).await?;
+
+        let mut stream = Vec::new();
+        write_record(
+            &mut stream,
+            &ExportRecord::Header {
+                version: EXPORT_FORMAT_VERSION,
+            },
+        )
+        .await?;
+        for blob in &manifest.blobs {
+            stream.extend(keys.decrypt(&backup.store.get(blob).await?)?);
+        }
+        self.import_all(stream.as_slice()).await?;
+        last_manifest.replace(name.clone());
+        Ok(name)
+    }
+
+    /// Uploads a backup every [interval](struct.CloudBackupOptions.html#structfield.interval) until
+    /// [CloudBackup#stop](struct.CloudBackup.html#method.stop) is called. Failed uploads, e.g. because of a conflict,
+    /// are logged and retried on the next interval.
+    pub fn start_cloud_backup(&self, backup: Arc<CloudBackup>) {
+        let manager = self.clone();
+        crate::spawn(async move {
+            loop {
+                tokio::time::sleep(backup.options.interval).await;
+                if backup.stopped.load(Ordering::SeqCst) {
+                    break;
+                }
+                if let Err(e) = manager.upload_cloud_backup(&backup, false).await {
+                    log::warn!("[CLOUD BACKUP] upload failed: {}", e);
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BlobStore, CloudBackup, CloudBackupOptions};
+
+    use std::{
+        collections::{HashMap, HashSet},
+        sync::Arc,
+        time::Duration,
+    };
+    use tokio::sync::Mutex;
+
+    #[derive(Default, Clone)]
+    struct MemoryBlobStore(Arc<Mutex<HashMap<String, Vec<u8>>>>);
+
+    #[async_trait::async_trait]
+    impl BlobStore for MemoryBlobStore {
+        async fn put(&self, name: &str, blob: Vec<u8>) -> crate::Result<()> {
+            self.0.lock().await.insert(name.to_string(), blob);
+            Ok(())
+        }
+        async fn get(&self, name: &str) -> crate::Result<Vec<u8>> {
+            self.0
+                .lock()
+                .await
+                .get(name)
+                .cloned()
+                .ok_or(crate::Error::RecordNotFound)
+        }
+        async fn list(&self, prefix: &str) -> crate::Result<Vec<String>> {
+            Ok(self
+                .0
+                .lock()
+                .await
+                .keys()
+                .filter(|name| name.starts_with(prefix))
+                .cloned()
+                .collect())
+        }
+    }
+
+    fn backup(store: &MemoryBlobStore, device_id: &str) -> CloudBackup {
+        CloudBackup::new(
+            Box::new(store.clone()),
+            "password",
+            CloudBackupOptions {
+                device_id: device_id.to_string(),
+                interval: Duration::from_secs(60),
+            },
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn cloud_backup() {
+        let store = MemoryBlobStore::default();
+        let manager = crate::testing::get_account_manager().await;
+        let message = crate::testing::GenerateMessageBuilder::default().build().await;
+        let account_handle = crate::testing::AccountCreator::new(&manager)
+            .messages(vec![message.clone()])
+            .create()
+            .await;
+
+        let laptop = backup(&store, "laptop");
+        let report = manager.upload_cloud_backup(&laptop, false).await.unwrap();
+        assert_eq!(report.uploaded_blobs, 2);
+        // the unchanged blobs aren't uploaded again
+        tokio::time::sleep(Duration::from_millis(2)).await;
+        let report = manager.upload_cloud_backup(&laptop, false).await.unwrap();
+        assert_eq!((report.uploaded_blobs, report.reused_blobs), (0, 2));
+        // the blobs are encrypted
+        let account_id = account_handle.id().await;
+        assert!(!store
+            .0
+            .lock()
+            .await
+            .values()
+            .any(|blob| String::from_utf8_lossy(blob).contains(account_id.as_str())));
+
+        // the blob names are keyed with a random salt: another backup of the same accounts names them differently
+        let other_store = MemoryBlobStore::default();
+        manager
+            .upload_cloud_backup(&backup(&other_store, "laptop"), false)
+            .await
+            .unwrap();
+        let blob_names = |blobs: &HashMap<String, Vec<u8>>| -> HashSet<String> {
+            blobs.keys().filter(|name| name.starts_with("blob-")).cloned().collect()
+        };
+        let other_names = blob_names(&*other_store.0.lock().await);
+        assert_eq!(other_names.len(), 2);
+        assert!(blob_names(&*store.0.lock().await).is_disjoint(&other_names));
+
+        // a device that didn't restore the newest backup can't overwrite it
+        let phone = backup(&store, "phone");
+        let restored = crate::testing::get_account_manager().await;
+        assert!(matches!(
+            restored.upload_cloud_backup(&phone, false).await,
+            Err(crate::Error::CloudBackupConflict(_))
+        ));
+        assert_eq!(restored.restore_cloud_backup(&phone).await.unwrap(), report.manifest);
+        let restored_account = restored.get_account(&account_id).await.unwrap();
+        assert_eq!(
+            restored_account.get_message(message.id()).await.unwrap().id(),
+            message.id()
+        );
+        tokio::time::sleep(Duration::from_millis(2)).await;
+        restored.upload_cloud_backup(&phone, false).await.unwrap();
+
+        // the laptop didn't see the phone backup
+        assert!(matches!(
+            manager.upload_cloud_backup(&laptop, false).await,
+            Err(crate::Error::CloudBackupConflict(_))
+        ));
+        manager.upload_cloud_backup(&laptop, true).await.unwrap();
+    }
+}
@@ -0,0 +1,250 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::BlobStore;
+
+use chrono::{DateTime, Utc};
+use crypto::{
+    hashes::sha::{SHA256, SHA256_LEN},
+    macs::hmac::HMAC_SHA256,
+};
+use reqwest::{Client, Method, StatusCode};
+use url::Url;
+
+fn request_err<E: ToString>(error: E) -> crate::Error {
+    crate::Error::CloudBackup(error.to_string())
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    let mut digest = [0; SHA256_LEN];
+    SHA256(data, &mut digest);
+    hex::encode(digest)
+}
+
+fn hmac(key: &[u8], data: &str) -> [u8; SHA256_LEN] {
+    let mut mac = [0; SHA256_LEN];
+    HMAC_SHA256(data.as_bytes(), key, &mut mac);
+    mac
+}
+
+/// Percent-encodes everything but the unreserved characters, as the AWS signature requires.
+fn uri_encode(value: &str, encode_slash: bool) -> String {
+    let mut encoded = String::new();
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+            b'/' if !encode_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Gets the text of the given XML elements.
+fn xml_values<'a>(body: &'a str, element: &str) -> Vec<&'a str> {
+    let (open, close) = (format!("<{}>", element), format!("</{}>", element));
+    let mut values = Vec::new();
+    let mut rest = body;
+    while let Some(start) = rest.find(&open) {
+        rest = &rest[start + open.len()..];
+        match rest.find(&close) {
+            Some(end) => {
+                values.push(&rest[..end]);
+                rest = &rest[end + close.len()..];
+            }
+            None => break,
+        }
+    }
+    values
+}
+
+/// A [BlobStore](trait.BlobStore.html) keeping the blobs in an S3 compatible bucket, with path style requests signed
+/// with AWS Signature Version 4.
+pub struct S3BlobStore {
+    client: Client,
+    endpoint: Url,
+    bucket: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+    prefix: String,
+}
+
+impl S3BlobStore {
+    /// Creates the store, e.g. for the `https://s3.eu-central-1.amazonaws.com` endpoint and the `eu-central-1` region.
+    pub fn new(endpoint: Url, bucket: String, region: String, access_key: String, secret_key: String) -> Self {
+        Self {
+            client: Client::new(),
+            endpoint,
+            bucket,
+            region,
+            access_key,
+            secret_key,
+            prefix: String::new(),
+        }
+    }
+
+    /// Prefixes the object keys, e.g. `wallet/` to keep the blobs in a folder of a shared bucket.
+    pub fn with_prefix(mut self, prefix: String) -> Self {
+        self.prefix = prefix;
+        self
+    }
+
+    fn host(&self) -> crate::Result<String> {
+        let host = self
+            .endpoint
+            .host_str()
+            .ok_or_else(|| crate::Error::CloudBackup("the endpoint has no host".to_string()))?;
+        Ok(match self.endpoint.port() {
+            Some(port) => format!("{}:{}", host, port),
+            None => host.to_string(),
+        })
+    }
+
+    /// Sends a request signed with AWS Signature Version 4. The query parameters must be sorted by name.
+    async fn send(
+        &self,
+        method: Method,
+        key: Option<&str>,
+        query: &[(&str, &str)],
+        body: Vec<u8>,
+        now: DateTime<Utc>,
+    ) -> crate::Result<reqwest::Response> {
+        let path = match key {
+            Some(key) => format!("/{}/{}{}", self.bucket, self.prefix, key),
+            None => format!("/{}", self.bucket),
+        };
+        let canonical_uri = uri_encode(&path, false);
+        let canonical_query = query
+            .iter()
+            .map(|(name, value)| format!("{}={}", uri_encode(name, true), uri_encode(value, true)))
+            .collect::<Vec<String>>()
+            .join("&");
+        let host = self.host()?;
+        let payload_hash = sha256_hex(&body);
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        let canonical_request = format!(
+            "{}\n{}\n{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method, canonical_uri, canonical_query, host, payload_hash, amz_date, signed_headers, payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            sha256_hex(canonical_request.as_bytes())
+        );
+        let signing_key = hmac(
+            &hmac(
+                &hmac(
+                    &hmac(format!("AWS4{}", self.secret_key).as_bytes(), &date),
+                    &self.region,
+                ),
+                "s3",
+            ),
+            "aws4_request",
+        );
+        let signature = hex::encode(hmac(&signing_key, &string_to_sign));
+
+        let mut url = self.endpoint.clone();
+        url.set_path(&canonical_uri);
+        url.set_query(if canonical_query.is_empty() {
+            None
+        } else {
+            Some(&canonical_query)
+        });
+        self.client
+            .request(method, url)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("x-amz-date", amz_date)
+            .header(
+                "Authorization",
+                format!(
+                    "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                    self.access_key, scope, signed_headers, signature
+                ),
+            )
+            .body(body)
+            .send()
+            .await
+            .map_err(request_err)
+    }
+}
+
+#[async_trait::async_trait]
+impl BlobStore for S3BlobStore {
+    async fn put(&self, name: &str, blob: Vec<u8>) -> crate::Result<()> {
+        self.send(Method::PUT, Some(name), &[], blob, Utc::now())
+            .await?
+            .error_for_status()
+            .map_err(request_err)?;
+        Ok(())
+    }
+
+    async fn get(&self, name: &str) -> crate::Result<Vec<u8>> {
+        let response = self.send(Method::GET, Some(name), &[], Vec::new(), Utc::now()).await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(crate::Error::RecordNotFound);
+        }
+        let blob = response
+            .error_for_status()
+            .map_err(request_err)?
+            .bytes()
+            .await
+            .map_err(request_err)?;
+        Ok(blob.to_vec())
+    }
+
+    async fn list(&self, prefix: &str) -> crate::Result<Vec<String>> {
+        let key_prefix = format!("{}{}", self.prefix, prefix);
+        let mut names = Vec::new();
+        let mut continuation_token: Option<String> = None;
+        loop {
+            let mut query = Vec::new();
+            if let Some(token) = &continuation_token {
+                query.push(("continuation-token", token.as_str()));
+            }
+            query.push(("list-type", "2"));
+            query.push(("prefix", key_prefix.as_str()));
+            let body = self
+                .send(Method::GET, None, &query, Vec::new(), Utc::now())
+                .await?
+                .error_for_status()
+                .map_err(request_err)?
+                .text()
+                .await
+                .map_err(request_err)?;
+            names.extend(
+                xml_values(&body, "Key")
+                    .into_iter()
+                    .filter_map(|key| key.strip_prefix(self.prefix.as_str()))
+                    .map(|name| name.to_string()),
+            );
+            match xml_values(&body, "NextContinuationToken").first() {
+                Some(token) => continuation_token = Some(token.to_string()),
+                None => break,
+            }
+        }
+        Ok(names)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn signing_helpers() {
+        assert_eq!(super::uri_encode("/bucket/a b", false), "/bucket/a%20b");
+        assert_eq!(super::uri_encode("a/b", true), "a%2Fb");
+        assert_eq!(
+            super::sha256_hex(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        let body = "<ListBucketResult><Contents><Key>w/blob-1</Key></Contents><Contents><Key>w/blob-2</Key>\
+                    </Contents><NextContinuationToken>t</NextContinuationToken></ListBucketResult>";
+        assert_eq!(super::xml_values(body, "Key"), vec!["w/blob-1", "w/blob-2"]);
+        assert_eq!(super::xml_values(body, "NextContinuationToken"), vec!["t"]);
+    }
+}
@@ -0,0 +1,141 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::BlobStore;
+
+use reqwest::{Client, Method, RequestBuilder, StatusCode};
+use url::Url;
+
+fn request_err<E: ToString>(error: E) -> crate::Error {
+    crate::Error::CloudBackup(error.to_string())
+}
+
+/// A [BlobStore](trait.BlobStore.html) keeping the blobs in a WebDAV collection.
+pub struct WebDavBlobStore {
+    client: Client,
+    collection: Url,
+    credentials: Option<(String, String)>,
+}
+
+impl WebDavBlobStore {
+    /// Creates the store for an existing collection, e.g. `https://cloud.example.com/remote.php/dav/files/me/wallet/`.
+    pub fn new(collection: Url) -> Self {
+        let collection = if collection.path().ends_with('/') {
+            collection
+        } else {
+            let mut collection = collection;
+            collection.set_path(&format!("{}/", collection.path()));
+            collection
+        };
+        Self {
+            client: Client::new(),
+            collection,
+            credentials: None,
+        }
+    }
+
+    /// Authenticates the requests with HTTP basic authentication.
+    pub fn with_basic_auth(mut self, username: String, password: String) -> Self {
+        self.credentials.replace((username, password));
+        self
+    }
+
+    fn request(&self, method: Method, url: Url) -> RequestBuilder {
+        let request = self.client.request(method, url);
+        match &self.credentials {
+            Some((username, password)) => request.basic_auth(username, Some(password)),
+            None => request,
+        }
+    }
+
+    fn blob_url(&self, name: &str) -> crate::Result<Url> {
+        self.collection.join(name).map_err(request_err)
+    }
+}
+
+/// Gets the last path segment of the `href` elements of a multistatus response, whatever the namespace prefix.
+fn hrefs(body: &str) -> Vec<String> {
+    let mut hrefs = Vec::new();
+    let mut rest = body;
+    while let Some(start) = rest.find("href>") {
+        rest = &rest[start + "href>".len()..];
+        // skip the closing tags
+        if rest.starts_with('<') || rest.is_empty() {
+            continue;
+        }
+        if let Some(end) = rest.find('<') {
+            let href = rest[..end].trim().trim_end_matches('/');
+            if let Some(segment) = href.rsplit('/').next() {
+                hrefs.push(segment.to_string());
+            }
+            rest = &rest[end..];
+        }
+    }
+    hrefs
+}
+
+#[async_trait::async_trait]
+impl BlobStore for WebDavBlobStore {
+    async fn put(&self, name: &str, blob: Vec<u8>) -> crate::Result<()> {
+        self.request(Method::PUT, self.blob_url(name)?)
+            .body(blob)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(request_err)?;
+        Ok(())
+    }
+
+    async fn get(&self, name: &str) -> crate::Result<Vec<u8>> {
+        let response = self
+            .request(Method::GET, self.blob_url(name)?)
+            .send()
+            .await
+            .map_err(request_err)?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(crate::Error::RecordNotFound);
+        }
+        let blob = response
+            .error_for_status()
+            .map_err(request_err)?
+            .bytes()
+            .await
+            .map_err(request_err)?;
+        Ok(blob.to_vec())
+    }
+
+    async fn list(&self, prefix: &str) -> crate::Result<Vec<String>> {
+        // safe to unwrap: PROPFIND is a valid method name
+        let propfind = Method::from_bytes(b"PROPFIND").unwrap();
+        let body = self
+            .request(propfind, self.collection.clone())
+            .header("Depth", "1")
+            .header("Content-Type", "application/xml")
+            .body(r#"<?xml version="1.0"?><d:propfind xmlns:d="DAV:"><d:prop><d:resourcetype/></d:prop></d:propfind>"#)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(request_err)?
+            .text()
+            .await
+            .map_err(request_err)?;
+        Ok(hrefs(&body)
+            .into_iter()
+            .filter(|name| name.starts_with(prefix))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn multistatus_hrefs() {
+        let body = r#"<?xml version="1.0"?>
+            <d:multistatus xmlns:d="DAV:">
+                <d:response><d:href>/dav/wallet/</d:href></d:response>
+                <d:response><d:href>/dav/wallet/blob-01</d:href></d:response>
+                <D:response><D:href>/dav/wallet/manifest-02-laptop</D:href></D:response>
+            </d:multistatus>"#;
+        assert_eq!(super::hrefs(body), vec!["wallet", "blob-01", "manifest-02-laptop"]);
+    }
+}
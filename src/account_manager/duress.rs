@@ -0,0 +1,238 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{
+    storage_password_to_encryption_key, stronghold_password, AccountManager, AccountOptions, ROCKSDB_FILENAME,
+    STRONGHOLD_FILENAME,
+};
+use crate::{
+    account::{AccountInitialiser, AccountStore},
+    signing::SignerType,
+    storage::StorageAdapter,
+};
+
+use zeroize::Zeroize;
+
+use std::{fs, path::Path, sync::atomic::Ordering};
+
+/// The salt of the decoy storage folder name derivation.
+const DECOY_FOLDER_SALT: &[u8] = b"wallet.rs::duress";
+
+/// Derives the decoy storage folder name from the duress password,
+/// so the name doesn't tell the folder apart and only the duress password leads to it.
+fn decoy_folder_name(password: &str) -> String {
+    let mut dk = [0; 64];
+    // safe to unwrap (rounds > 0)
+    crypto::keys::pbkdf::PBKDF2_HMAC_SHA512(password.as_bytes(), DECOY_FOLDER_SALT, 100, &mut dk).unwrap();
+    hex::encode(&dk[..16])
+}
+
+impl AccountManager {
+    /// Creates a decoy wallet that's opened instead of the real one when
+    /// [set_stronghold_password](#method.set_stronghold_password) receives the duress password.
+    /// The decoy has its own mnemonic and storage, holding a new account for each of the given aliases,
+    /// using the client options of the first account of the wallet. A previously configured decoy is replaced.
+    ///
+    /// While the decoy is open, the manager reads and writes the decoy storage only: the events, user data and
+    /// the accounts created in the session never reach the real storage.
+    /// [lock_stronghold](#method.lock_stronghold) or the real password end the session.
+    pub async fn configure_duress<P: Into<String>>(
+        &self,
+        password: P,
+        decoy_accounts: Vec<String>,
+    ) -> crate::Result<()> {
+        self.check_storage_encryption()?;
+        let mut password = password.into();
+        let client_options = {
+            let accounts = self.accounts.read().await;
+            let mut first_account = None;
+            for account_handle in accounts.values() {
+                let account = account_handle.read().await;
+                if first_account
+                    .as_ref()
+                    .map_or(true, |(index, _)| account.index() < index)
+                {
+                    first_account.replace((*account.index(), account.client_options().clone()));
+                }
+            }
+            first_account.ok_or(crate::Error::RecordNotFound)?.1
+        };
+
+        // the folder of the previous decoy is recorded in the wallet storage, which is encrypted
+        let folder_name = decoy_folder_name(&password);
+        let storage = crate::storage::get(&self.storage_path()).await?;
+        let previous_folder_name = storage.lock().await.get_duress_folder().await?;
+        for name in previous_folder_name.iter().chain(std::iter::once(&folder_name)) {
+            remove_decoy(&self.storage_folder.join(name)).await?;
+        }
+
+        let decoy_folder = self.storage_folder.join(&folder_name);
+        let storage_path = decoy_folder.join(ROCKSDB_FILENAME);
+        let snapshot_path = decoy_folder.join(STRONGHOLD_FILENAME);
+        fs::create_dir_all(&decoy_folder)?;
+
+        open_decoy_storage(&storage_path, &password).await?;
+        crate::stronghold::load_snapshot(&snapshot_path, stronghold_password(password.clone())).await?;
+        password.zeroize();
+        let mut entropy = [0u8; 32];
+        crypto::utils::rand::fill(&mut entropy).map_err(|e| crate::Error::MnemonicEncode(format!("{:?}", e)))?;
+        let mnemonic = super::mnemonic::encode_mnemonic(&entropy, Default::default())?;
+        entropy.zeroize();
+        crate::stronghold::store_mnemonic(&snapshot_path, mnemonic).await?;
+
+        let accounts = AccountStore::new(Default::default()).with_pow_pool(self.accounts.pow_pool().clone());
+        let account_options = AccountOptions {
+            allow_create_multiple_empty_accounts: true,
+            ..self.account_options
+        };
+        for alias in decoy_accounts {
+            AccountInitialiser::new(
+                client_options.clone(),
+                accounts.clone(),
+                storage_path.clone(),
+                account_options,
+                self.sync_accounts_lock.clone(),
+            )
+            .alias(alias)
+            .signer_type(SignerType::Stronghold)
+            .initialise()
+            .await?;
+        }
+
+        // persist the decoy snapshot and lock it
+        crate::stronghold::unload_snapshot(&snapshot_path, true).await?;
+        let _ = crate::storage::remove(&storage_path).await;
+
+        storage.lock().await.set_duress_folder(&folder_name).await
+    }
+
+    /// Whether the duress password unlocked the wallet.
+    pub(crate) fn is_duress_session(&self) -> bool {
+        self.duress_session
+            .read()
+            .expect("duress session lock poisoned")
+            .is_some()
+    }
+
+    /// Loads the decoy accounts if the password unlocks the decoy Stronghold snapshot,
+    /// switching the manager to the decoy storage until the session ends.
+    pub(crate) async fn load_decoy_accounts(&self, password: &str) -> crate::Result<bool> {
+        let decoy_folder = self.storage_folder.join(decoy_folder_name(password));
+        let snapshot_path = decoy_folder.join(STRONGHOLD_FILENAME);
+        if !snapshot_path.exists() {
+            return Ok(false);
+        }
+        if crate::stronghold::load_snapshot(&snapshot_path, stronghold_password(password))
+            .await
+            .is_err()
+        {
+            return Ok(false);
+        }
+        if self.is_duress_session() {
+            // the decoy snapshot was locked and is unlocked again
+            return Ok(true);
+        }
+
+        let storage_path = decoy_folder.join(ROCKSDB_FILENAME);
+        if crate::storage::get(&storage_path).await.is_err() {
+            open_decoy_storage(&storage_path, password).await?;
+        }
+        self.duress_session
+            .write()
+            .map_err(|_| crate::Error::PoisonError)?
+            .replace(decoy_folder);
+        Self::load_accounts(
+            &self.accounts,
+            &storage_path,
+            self.account_options,
+            self.sync_accounts_lock.clone(),
+        )
+        .await?;
+        self.loaded_accounts.store(true, Ordering::SeqCst);
+        crate::spawn(Self::start_monitoring(self.accounts.clone()));
+        Ok(true)
+    }
+
+    /// Ends the duress session, unloading the decoy accounts and storage. Does nothing outside of a session.
+    pub(crate) async fn end_duress_session(&self) -> crate::Result<()> {
+        let decoy_folder = match self
+            .duress_session
+            .write()
+            .map_err(|_| crate::Error::PoisonError)?
+            .take()
+        {
+            Some(decoy_folder) => decoy_folder,
+            None => return Ok(()),
+        };
+        Self::unload_accounts(&self.accounts).await?;
+        self.loaded_accounts.store(false, Ordering::SeqCst);
+        crate::storage::remove(&decoy_folder.join(ROCKSDB_FILENAME)).await;
+        Ok(())
+    }
+}
+
+async fn remove_decoy(decoy_folder: &Path) -> crate::Result<()> {
+    if decoy_folder.exists() {
+        let _ = crate::storage::remove(&decoy_folder.join(ROCKSDB_FILENAME)).await;
+        crate::stronghold::unload_snapshot(&decoy_folder.join(STRONGHOLD_FILENAME), false).await?;
+        fs::remove_dir_all(decoy_folder)?;
+    }
+    Ok(())
+}
+
+async fn open_decoy_storage(storage_path: &Path, password: &str) -> crate::Result<()> {
+    let storage = crate::storage::rocksdb::RocksdbStorageAdapter::new(storage_path)?;
+    crate::storage::set(
+        storage_path,
+        Some(storage_password_to_encryption_key(password)),
+        Box::new(storage) as Box<dyn StorageAdapter + Send + Sync>,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    #[tokio::test]
+    async fn duress() {
+        crate::testing::with_account_manager(crate::testing::TestType::Signing, |manager, _| async move {
+            let account_id = crate::testing::AccountCreator::new(&manager).create().await.id().await;
+            let storage_path = manager.storage_path();
+            manager
+                .configure_duress("duress-password", vec!["Savings".to_string(), "Daily".to_string()])
+                .await
+                .unwrap();
+            assert!(!manager.storage_folder.join("db-1").exists());
+
+            manager.lock_stronghold().await.unwrap();
+            crate::account_manager::AccountManager::unload_accounts(manager.accounts())
+                .await
+                .unwrap();
+            assert!(manager.set_stronghold_password("wrong-password").await.is_err());
+            manager.set_stronghold_password("duress-password").await.unwrap();
+            assert_ne!(manager.storage_path(), storage_path);
+
+            let accounts = manager.get_accounts().await.unwrap();
+            assert_eq!(accounts.len(), 2);
+            let mut aliases = Vec::new();
+            for account_handle in accounts {
+                assert_ne!(account_handle.read().await.storage_path(), &storage_path);
+                aliases.push(account_handle.alias().await);
+            }
+            aliases.sort();
+            assert_eq!(aliases, vec!["Daily".to_string(), "Savings".to_string()]);
+            // the session writes to the decoy storage only
+            manager.user_data().set("duress", &true).await.unwrap();
+
+            // locking ends the session, so the real password loads the real wallet
+            manager.lock_stronghold().await.unwrap();
+            assert!(manager.get_accounts().await.unwrap().is_empty());
+            manager.set_stronghold_password("password").await.unwrap();
+            assert_eq!(manager.storage_path(), storage_path);
+            let accounts = manager.get_accounts().await.unwrap();
+            assert_eq!(accounts.len(), 1);
+            assert_eq!(accounts[0].id().await, account_id);
+            assert_eq!(manager.user_data().get::<bool>("duress").await.unwrap(), None);
+        })
+        .await;
+    }
+}
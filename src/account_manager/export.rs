@@ -0,0 +1,286 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::AccountManager;
+use crate::{
+    account::{Account, AccountHandle},
+    address::Address,
+    event::{BalanceEvent, TransactionConfirmationChangeEvent, TransactionEvent, TransactionReattachmentEvent},
+    message::Message,
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+use std::collections::HashMap;
+
+/// The version of the export stream format.
+pub const EXPORT_FORMAT_VERSION: u32 = 1;
+
+/// Options for [AccountManager#export_all](struct.AccountManager.html#method.export_all).
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ExportOptions {
+    /// Whether the persisted events should be exported or not.
+    #[serde(rename = "includeEvents", default)]
+    pub include_events: bool,
+}
+
+/// A record of the export stream.
+///
+/// The stream is newline delimited JSON: each line holds one record, serialized as `{"type": "...", "data": ...}`.
+/// The first line is always a `Header` record, followed by each account record and its `Address` and `Message`
+/// records. The address records hold the address outputs. The event records, if any, come last.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data")]
+pub enum ExportRecord {
+    /// The stream header.
+    Header {
+        /// The stream format version.
+        version: u32,
+    },
+    /// An account, without its addresses.
+    Account(Box<Account>),
+    /// An account address and its outputs.
+    Address {
+        /// The account identifier.
+        #[serde(rename = "accountId")]
+        account_id: String,
+        /// The address.
+        address: Address,
+    },
+    /// An account message.
+    Message {
+        /// The account identifier.
+        #[serde(rename = "accountId")]
+        account_id: String,
+        /// The message.
+        message: Box<Message>,
+    },
+    /// A persisted balance change event.
+    BalanceChangeEvent(BalanceEvent),
+    /// A persisted transaction confirmation change event.
+    TransactionConfirmationChangeEvent(TransactionConfirmationChangeEvent),
+    /// A persisted new transaction event.
+    NewTransactionEvent(TransactionEvent),
+    /// A persisted reattachment event.
+    ReattachmentEvent(TransactionReattachmentEvent),
+    /// A persisted broadcast event.
+    BroadcastEvent(TransactionEvent),
+}
+
+pub(super) async fn write_record<W: AsyncWrite + Unpin>(writer: &mut W, record: &ExportRecord) -> crate::Result<()> {
+    let mut line = serde_json::to_string(record)?;
+    line.push('\n');
+    writer.write_all(line.as_bytes()).await?;
+    Ok(())
+}
+
+/// Writes the account record, without the addresses, followed by the address and message records of the account.
+pub(super) async fn write_account_records<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    account: &Account,
+) -> crate::Result<()> {
+    let mut account_data = account.clone();
+    account_data.set_addresses(Vec::new());
+    write_record(writer, &ExportRecord::Account(Box::new(account_data))).await?;
+    for address in account.addresses() {
+        write_record(
+            writer,
+            &ExportRecord::Address {
+                account_id: account.id().clone(),
+                address: address.clone(),
+            },
+        )
+        .await?;
+    }
+    for message in account.list_messages(0, 0, None).await? {
+        write_record(
+            writer,
+            &ExportRecord::Message {
+                account_id: account.id().clone(),
+                message: Box::new(message),
+            },
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+impl AccountManager {
+    /// Writes all accounts, addresses, outputs, messages and optionally the persisted events to the given writer,
+    /// as a newline delimited JSON stream of [ExportRecord](enum.ExportRecord.html).
+    pub async fn export_all<W: AsyncWrite + Unpin>(&self, mut writer: W, options: ExportOptions) -> crate::Result<()> {
+        write_record(
+            &mut writer,
+            &ExportRecord::Header {
+                version: EXPORT_FORMAT_VERSION,
+            },
+        )
+        .await?;
+
+        for account_handle in self.get_accounts().await? {
+            write_account_records(&mut writer, &*account_handle.read().await).await?;
+        }
+
+        if options.include_events {
+            for record in self.event_records().await? {
+                write_record(&mut writer, &record).await?;
+            }
+        }
+
+        writer.flush().await?;
+        Ok(())
+    }
+
+    /// Gets the persisted events as export records.
+    pub(super) async fn event_records(&self) -> crate::Result<Vec<ExportRecord>> {
+        let mut records = Vec::new();
+        records.extend(
+            self.get_balance_change_events(0, 0, None)
+                .await?
+                .into_iter()
+                .map(ExportRecord::BalanceChangeEvent),
+        );
+        records.extend(
+            self.get_transaction_confirmation_events(0, 0, None)
+                .await?
+                .into_iter()
+                .map(ExportRecord::TransactionConfirmationChangeEvent),
+        );
+        records.extend(
+            self.get_new_transaction_events(0, 0, None)
+                .await?
+                .into_iter()
+                .map(ExportRecord::NewTransactionEvent),
+        );
+        records.extend(
+            self.get_reattachment_events(0, 0, None)
+                .await?
+                .into_iter()
+                .map(ExportRecord::ReattachmentEvent),
+        );
+        records.extend(
+            self.get_broadcast_events(0, 0, None)
+                .await?
+                .into_iter()
+                .map(ExportRecord::BroadcastEvent),
+        );
+        Ok(records)
+    }
+
+    /// Imports a stream written by [export_all](#method.export_all).
+    /// The manager must not have any account.
+    pub async fn import_all<R: AsyncRead + Unpin>(&self, reader: R) -> crate::Result<()> {
+        self.check_storage_encryption()?;
+        if !self.accounts.read().await.is_empty() {
+            return Err(crate::Error::StorageExists);
+        }
+
+        let mut lines = BufReader::new(reader).lines();
+        let mut read_header = false;
+        let mut accounts: Vec<Account> = Vec::new();
+        let mut messages: HashMap<String, Vec<Message>> = HashMap::new();
+        let mut events = Vec::new();
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line)? {
+                ExportRecord::Header { version } => {
+                    if version != EXPORT_FORMAT_VERSION {
+                        return Err(crate::Error::InvalidExport(format!("unsupported version {}", version)));
+                    }
+                    read_header = true;
+                }
+                _ if !read_header => return Err(crate::Error::InvalidExport("missing header".to_string())),
+                ExportRecord::Account(account) => accounts.push(*account),
+                ExportRecord::Address { account_id, address } => accounts
+                    .iter_mut()
+                    .find(|account| account.id() == &account_id)
+                    .ok_or_else(|| crate::Error::InvalidExport(format!("unknown account {}", account_id)))?
+                    .append_addresses(vec![address]),
+                ExportRecord::Message { account_id, message } => messages.entry(account_id).or_default().push(*message),
+                event => events.push(event),
+            }
+        }
+        if let Some(account_id) = messages
+            .keys()
+            .find(|account_id| !accounts.iter().any(|account| account.id() == *account_id))
+        {
+            return Err(crate::Error::InvalidExport(format!("unknown account {}", account_id)));
+        }
+
+        for mut account in accounts {
+            account.set_storage_path(self.storage_path());
+            let account_messages = messages.remove(account.id()).unwrap_or_default();
+            let account_id = account.id().clone();
+            let account_handle = AccountHandle::new(
+                account,
+                self.accounts.clone(),
+                self.account_options,
+                self.sync_accounts_lock.clone(),
+            );
+            {
+                let mut account = account_handle.write().await;
+                account.save().await?;
+                account.save_messages(account_messages).await?;
+            }
+            self.accounts.index_addresses(&*account_handle.read().await);
+            self.accounts.write().await.insert(account_id, account_handle);
+        }
+
+        let storage_handle = crate::storage::get(&self.storage_path()).await?;
+        let mut storage = storage_handle.lock().await;
+        for event in events {
+            match event {
+                ExportRecord::BalanceChangeEvent(event) => storage.save_balance_change_event(&event).await?,
+                ExportRecord::TransactionConfirmationChangeEvent(event) => {
+                    storage.save_transaction_confirmation_event(&event).await?
+                }
+                ExportRecord::NewTransactionEvent(event) => storage.save_new_transaction_event(&event).await?,
+                ExportRecord::ReattachmentEvent(event) => storage.save_reattachment_event(&event).await?,
+                ExportRecord::BroadcastEvent(event) => storage.save_broadcast_event(&event).await?,
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ExportOptions;
+
+    #[tokio::test]
+    async fn export_import() {
+        let manager = crate::testing::get_account_manager().await;
+        let address = crate::testing::generate_random_address();
+        let message = crate::testing::GenerateMessageBuilder::default()
+            .address(address.clone())
+            .build()
+            .await;
+        let account_handle = crate::testing::AccountCreator::new(&manager)
+            .addresses(vec![address])
+            .messages(vec![message.clone()])
+            .create()
+            .await;
+
+        let mut export = Vec::new();
+        manager.export_all(&mut export, ExportOptions::default()).await.unwrap();
+        assert!(String::from_utf8(export.clone())
+            .unwrap()
+            .starts_with(r#"{"type":"Header""#));
+
+        let imported_manager = crate::testing::get_account_manager().await;
+        imported_manager.import_all(export.as_slice()).await.unwrap();
+        let imported_account = imported_manager.get_account(account_handle.id().await).await.unwrap();
+        assert_eq!(imported_account.addresses().await, account_handle.addresses().await);
+        assert_eq!(imported_account.get_message(message.id()).await, Some(message));
+
+        assert!(matches!(
+            imported_manager.import_all(export.as_slice()).await,
+            Err(crate::Error::StorageExists)
+        ));
+    }
+}
@@ -0,0 +1,282 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::AccountManager;
+use crate::{client::ClientOptions, signing::SignerType};
+
+use getset::Getters;
+use serde::Serialize;
+
+/// The state of a wallet subsystem in a [HealthReport](struct.HealthReport.html).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", content = "reason")]
+pub enum SubsystemHealth {
+    /// The subsystem works.
+    Healthy,
+    /// The wallet doesn't use the subsystem, e.g. MQTT when it's disabled.
+    NotUsed,
+    /// The subsystem doesn't work, with the reason.
+    Unhealthy(String),
+}
+
+impl SubsystemHealth {
+    /// Whether the subsystem works or isn't used.
+    pub fn is_ok(&self) -> bool {
+        !matches!(self, Self::Unhealthy(_))
+    }
+}
+
+/// The state of the wallet subsystems, returned by [AccountManager#health](struct.AccountManager.html#method.health).
+#[derive(Debug, Clone, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct HealthReport {
+    /// Whether a record can be written to the storage.
+    storage: SubsystemHealth,
+    /// Whether the Stronghold snapshot is unlocked.
+    stronghold: SubsystemHealth,
+    /// Whether the signers of the accounts can sign, e.g. the Ledger is connected and unlocked.
+    signers: SubsystemHealth,
+    /// Whether the node of the first account answers.
+    node: SubsystemHealth,
+    /// Whether the node is healthy and its confirmed milestone is close to the latest one.
+    #[serde(rename = "nodeSynced")]
+    node_synced: SubsystemHealth,
+    /// Whether the account addresses are subscribed on the MQTT broker.
+    mqtt: SubsystemHealth,
+    /// Whether the background sync is running and not paused.
+    #[serde(rename = "backgroundSync")]
+    background_sync: SubsystemHealth,
+}
+
+impl HealthReport {
+    /// Whether the wallet is alive, i.e. its storage is writable; meant for liveness probes.
+    pub fn is_live(&self) -> bool {
+        self.storage.is_ok()
+    }
+
+    /// Whether every subsystem the wallet uses works; meant for readiness probes.
+    pub fn is_ready(&self) -> bool {
+        [
+            &self.storage,
+            &self.stronghold,
+            &self.signers,
+            &self.node,
+            &self.node_synced,
+            &self.mqtt,
+            &self.background_sync,
+        ]
+        .iter()
+        .all(|health| health.is_ok())
+    }
+}
+
+impl AccountManager {
+    /// Checks the wallet subsystems: the storage, Stronghold, the account signers, the node, the MQTT subscriptions
+    /// and the background sync. The node and MQTT checks use the client options of the first account.
+    pub async fn health(&self) -> HealthReport {
+        let storage = match self.storage_health().await {
+            Ok(()) => SubsystemHealth::Healthy,
+            Err(e) => SubsystemHealth::Unhealthy(e.to_string()),
+        };
+        let stronghold = self.stronghold_health().await;
+
+        let (signer_types, client_options) = {
+            let accounts = self.accounts.read().await;
+            let mut signer_types = Vec::new();
+            let mut client_options = None;
+            for account_handle in accounts.values() {
+                let account = account_handle.read().await;
+                if !signer_types.contains(account.signer_type()) {
+                    signer_types.push(account.signer_type().clone());
+                }
+                if client_options.is_none() || *account.index() == 0 {
+                    client_options.replace(account.client_options().clone());
+                }
+            }
+            (signer_types, client_options)
+        };
+        let signers = signers_health(&signer_types, &stronghold).await;
+
+        let background_sync = if self.polling_handle.lock().unwrap().is_none() {
+            SubsystemHealth::NotUsed
+        } else if *self.paused.lock().await {
+            SubsystemHealth::Unhealthy("the background sync is paused".to_string())
+        } else {
+            SubsystemHealth::Healthy
+        };
+
+        let (node, node_synced, mqtt) = match client_options {
+            Some(client_options) => {
+                let (node, node_synced) = node_health(&client_options).await;
+                let mqtt = if background_sync == SubsystemHealth::Healthy
+                    && *client_options.mqtt_enabled()
+                    && !client_options.is_proxied()
+                {
+                    mqtt_health(&client_options).await
+                } else {
+                    SubsystemHealth::NotUsed
+                };
+                (node, node_synced, mqtt)
+            }
+            None => (
+                SubsystemHealth::NotUsed,
+                SubsystemHealth::NotUsed,
+                SubsystemHealth::NotUsed,
+            ),
+        };
+
+        HealthReport {
+            storage,
+            stronghold,
+            signers,
+            node,
+            node_synced,
+            mqtt,
+            background_sync,
+        }
+    }
+
+    async fn storage_health(&self) -> crate::Result<()> {
+        crate::storage::get(&self.storage_path())
+            .await?
+            .lock()
+            .await
+            .probe_write()
+            .await
+    }
+
+    #[cfg(feature = "stronghold")]
+    async fn stronghold_health(&self) -> SubsystemHealth {
+        let snapshot_path = match self.stronghold_snapshot_path().await {
+            Ok(path) => path,
+            Err(e) => return SubsystemHealth::Unhealthy(e.to_string()),
+        };
+        if !snapshot_path.exists() {
+            return SubsystemHealth::NotUsed;
+        }
+        match crate::stronghold::get_status(&snapshot_path).await.snapshot {
+            crate::stronghold::SnapshotStatus::Unlocked(_) => SubsystemHealth::Healthy,
+            crate::stronghold::SnapshotStatus::Locked => SubsystemHealth::Unhealthy("Stronghold is locked".to_string()),
+        }
+    }
+
+    #[cfg(not(feature = "stronghold"))]
+    async fn stronghold_health(&self) -> SubsystemHealth {
+        SubsystemHealth::NotUsed
+    }
+}
+
+async fn signers_health(signer_types: &[SignerType], stronghold: &SubsystemHealth) -> SubsystemHealth {
+    if signer_types.is_empty() {
+        return SubsystemHealth::NotUsed;
+    }
+    let mut issues = Vec::new();
+    for signer_type in signer_types {
+        if !crate::signing::has_signer(signer_type).await {
+            issues.push(format!("no signer is registered for {:?}", signer_type));
+            continue;
+        }
+        match signer_type {
+            #[cfg(feature = "stronghold")]
+            SignerType::Stronghold => {
+                if let SubsystemHealth::Unhealthy(reason) = stronghold {
+                    issues.push(reason.clone());
+                }
+            }
+            #[cfg(feature = "ledger-nano")]
+            SignerType::LedgerNano => {
+                if let Some(issue) = ledger_issue(false).await {
+                    issues.push(issue);
+                }
+            }
+            #[cfg(feature = "ledger-nano-simulator")]
+            SignerType::LedgerNanoSimulator => {
+                if let Some(issue) = ledger_issue(true).await {
+                    issues.push(issue);
+                }
+            }
+            _ => {}
+        }
+    }
+    #[cfg(not(feature = "stronghold"))]
+    let _ = stronghold;
+
+    if issues.is_empty() {
+        SubsystemHealth::Healthy
+    } else {
+        SubsystemHealth::Unhealthy(issues.join("; "))
+    }
+}
+
+#[cfg(any(feature = "ledger-nano", feature = "ledger-nano-simulator"))]
+async fn ledger_issue(is_simulator: bool) -> Option<String> {
+    let status = crate::get_ledger_status(is_simulator).await;
+    if !status.connected {
+        Some("the Ledger isn't connected".to_string())
+    } else if status.locked {
+        Some("the Ledger is locked".to_string())
+    } else {
+        None
+    }
+}
+
+/// Whether the node answers, and whether it's healthy and synced.
+async fn node_health(client_options: &ClientOptions) -> (SubsystemHealth, SubsystemHealth) {
+    let unreachable = |e: String| {
+        (
+            SubsystemHealth::Unhealthy(e),
+            SubsystemHealth::Unhealthy("the node is unreachable".to_string()),
+        )
+    };
+    let client = match crate::client::get_client(client_options).await {
+        Ok(client) => client,
+        Err(e) => return unreachable(e.to_string()),
+    };
+    let client = client.read().await;
+    let node_info = match client.get_info().await {
+        Ok(info) => info.nodeinfo,
+        Err(e) => return unreachable(e.to_string()),
+    };
+    let milestone_lag = node_info
+        .latest_milestone_index
+        .saturating_sub(node_info.confirmed_milestone_index);
+    let node_synced = if !node_info.is_healthy {
+        SubsystemHealth::Unhealthy("the node isn't healthy".to_string())
+    } else if milestone_lag > crate::client::MAX_MILESTONE_LAG {
+        SubsystemHealth::Unhealthy(format!(
+            "the node is {} milestones behind the latest milestone",
+            milestone_lag
+        ))
+    } else {
+        SubsystemHealth::Healthy
+    };
+    (SubsystemHealth::Healthy, node_synced)
+}
+
+async fn mqtt_health(client_options: &ClientOptions) -> SubsystemHealth {
+    match crate::client::get_client(client_options).await {
+        Ok(client) => {
+            if client.read().await.subscribed_topics().await.is_empty() {
+                SubsystemHealth::Unhealthy("no MQTT topic is subscribed".to_string())
+            } else {
+                SubsystemHealth::Healthy
+            }
+        }
+        Err(e) => SubsystemHealth::Unhealthy(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SubsystemHealth;
+
+    #[tokio::test]
+    async fn health() {
+        let manager = crate::testing::get_account_manager().await;
+        let report = manager.health().await;
+        assert_eq!(report.storage(), &SubsystemHealth::Healthy);
+        assert!(report.is_live());
+        assert_eq!(report.background_sync(), &SubsystemHealth::NotUsed);
+        assert_eq!(report.signers(), &SubsystemHealth::NotUsed);
+    }
+}
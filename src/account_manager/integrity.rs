@@ -0,0 +1,251 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::AccountManager;
+use crate::account::Account;
+
+use getset::Getters;
+use iota_client::bee_message::MessageId;
+use serde::Serialize;
+
+use std::{collections::HashMap, sync::atomic::Ordering};
+
+/// The kind of inconsistency found by [AccountManager#verify_integrity](struct.AccountManager.html#method.verify_integrity).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", content = "data")]
+pub enum IntegrityIssueKind {
+    /// The account record is missing or can't be parsed.
+    CorruptedAccount(String),
+    /// The message record can't be decoded.
+    CorruptedMessage {
+        /// The message id.
+        #[serde(rename = "messageId")]
+        message_id: MessageId,
+        /// The decoding error.
+        error: String,
+    },
+    /// The message is indexed by the account but its record is missing.
+    OrphanedMessage(MessageId),
+    /// An address entry holds an output of another address.
+    UnknownOutputAddress {
+        /// The output id.
+        #[serde(rename = "outputId")]
+        output_id: String,
+        /// The address of the output, moved to the matching account address if there's one.
+        address: String,
+    },
+    /// Several address entries have the same key index.
+    DuplicateAddressIndex {
+        /// The key index.
+        #[serde(rename = "keyIndex")]
+        key_index: usize,
+        /// Whether the entries are change addresses or not.
+        internal: bool,
+    },
+}
+
+/// An inconsistency found by [AccountManager#verify_integrity](struct.AccountManager.html#method.verify_integrity).
+#[derive(Debug, Clone, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct IntegrityIssue {
+    /// The account identifier.
+    #[serde(rename = "accountId")]
+    account_id: String,
+    /// The inconsistency.
+    kind: IntegrityIssueKind,
+    /// Whether the record was quarantined or repaired.
+    quarantined: bool,
+}
+
+impl IntegrityIssue {
+    pub(crate) fn new(account_id: String, kind: IntegrityIssueKind, quarantined: bool) -> Self {
+        Self {
+            account_id,
+            kind,
+            quarantined,
+        }
+    }
+}
+
+/// The result of [AccountManager#verify_integrity](struct.AccountManager.html#method.verify_integrity).
+#[derive(Debug, Clone, Default, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct IntegrityReport {
+    /// The inconsistencies found.
+    issues: Vec<IntegrityIssue>,
+}
+
+impl IntegrityReport {
+    /// Whether no inconsistency was found.
+    pub fn is_healthy(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Number of inconsistencies that were quarantined or repaired.
+    pub fn quarantined(&self) -> usize {
+        self.issues.iter().filter(|issue| issue.quarantined).count()
+    }
+}
+
+/// Moves the outputs held by the wrong address entry and merges the entries duplicating an address on the same key
+/// index. Entries with different addresses on the same key index are only reported, since fixing them needs the
+/// signer; see [AccountHandle#repair_address_space](../account/struct.AccountHandle.html#method.repair_address_space).
+fn verify_addresses(account: &mut Account, repair: bool) -> Vec<IntegrityIssue> {
+    let account_id = account.id().clone();
+    let mut issues = Vec::new();
+
+    let mut misplaced = Vec::new();
+    for address in account.addresses() {
+        for (output_id, output) in address.outputs() {
+            if &output.address != address.address() {
+                misplaced.push((address.address().clone(), *output_id, output.clone()));
+            }
+        }
+    }
+    for (holder, output_id, output) in misplaced {
+        if repair {
+            let addresses = account.addresses_mut();
+            if let Some(holder) = addresses.iter_mut().find(|a| a.address() == &holder) {
+                holder.outputs.remove(&output_id);
+            }
+            if let Some(owner) = addresses.iter_mut().find(|a| a.address() == &output.address) {
+                owner.outputs.entry(output_id).or_insert_with(|| output.clone());
+            }
+        }
+        issues.push(IntegrityIssue::new(
+            account_id.clone(),
+            IntegrityIssueKind::UnknownOutputAddress {
+                output_id: output_id.to_string(),
+                address: output.address.to_bech32(),
+            },
+            repair,
+        ));
+    }
+
+    let mut slots: HashMap<(usize, bool), Vec<usize>> = HashMap::new();
+    for (position, address) in account.addresses().iter().enumerate() {
+        slots
+            .entry((*address.key_index(), *address.internal()))
+            .or_default()
+            .push(position);
+    }
+    let mut removed = Vec::new();
+    for ((key_index, internal), positions) in slots {
+        if positions.len() < 2 {
+            continue;
+        }
+        let first = positions[0];
+        let mergeable = positions
+            .iter()
+            .all(|position| account.addresses()[*position].address() == account.addresses()[first].address());
+        if repair && mergeable {
+            for position in &positions[1..] {
+                let outputs = account.addresses()[*position].outputs().clone();
+                account.addresses_mut()[first].outputs.extend(outputs);
+                removed.push(*position);
+            }
+        }
+        issues.push(IntegrityIssue::new(
+            account_id.clone(),
+            IntegrityIssueKind::DuplicateAddressIndex { key_index, internal },
+            repair && mergeable,
+        ));
+    }
+    removed.sort_unstable();
+    for position in removed.into_iter().rev() {
+        account.addresses_mut().remove(position);
+    }
+
+    issues
+}
+
+impl AccountManager {
+    /// Checks the stored records and the loaded accounts: unreadable account and message records, indexed messages
+    /// without a record, outputs held by the wrong address and duplicated address key indexes.
+    /// With `repair`, the unreadable records are moved to quarantine keys and dropped from the indexation instead of
+    /// failing the account loading or a later sync, and the address inconsistencies are fixed where possible.
+    pub async fn verify_integrity(&self, repair: bool) -> crate::Result<IntegrityReport> {
+        let mut issues = crate::storage::get(&self.storage_path())
+            .await?
+            .lock()
+            .await
+            .verify_records(repair)
+            .await?;
+
+        // the quarantined records might have prevented the accounts from loading
+        if repair && !self.loaded_accounts.load(Ordering::SeqCst) {
+            Self::load_accounts(
+                &self.accounts,
+                &self.storage_path(),
+                self.account_options,
+                self.sync_accounts_lock.clone(),
+            )
+            .await?;
+            self.loaded_accounts.store(true, Ordering::SeqCst);
+        }
+
+        for account_handle in self.accounts.read().await.values() {
+            let mut account = account_handle.write().await;
+            let account_issues = verify_addresses(&mut account, repair);
+            if repair && !account_issues.is_empty() {
+                account.save().await?;
+            }
+            issues.extend(account_issues);
+        }
+
+        for issue in &issues {
+            log::warn!("[INTEGRITY] account {}: {:?}", issue.account_id, issue.kind);
+        }
+        Ok(IntegrityReport { issues })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IntegrityIssueKind;
+
+    #[tokio::test]
+    async fn verify_integrity() {
+        let manager = crate::testing::get_account_manager().await;
+        let message = crate::testing::GenerateMessageBuilder::default().build().await;
+        let account_handle = crate::testing::AccountCreator::new(&manager)
+            .addresses(vec![crate::testing::generate_random_address()])
+            .messages(vec![message.clone()])
+            .create()
+            .await;
+        assert!(manager.verify_integrity(false).await.unwrap().is_healthy());
+
+        let storage_handle = crate::storage::get(&manager.storage_path()).await.unwrap();
+        storage_handle
+            .lock()
+            .await
+            .set_raw_record(
+                crate::storage::RecordKind::Message,
+                &message.id().to_string(),
+                "corrupted".to_string(),
+            )
+            .await
+            .unwrap();
+        {
+            let mut account = account_handle.write().await;
+            let duplicate = account.addresses()[0].clone();
+            account.addresses_mut().push(duplicate);
+        }
+
+        let report = manager.verify_integrity(true).await.unwrap();
+        assert_eq!(report.issues().len(), 2);
+        assert_eq!(report.quarantined(), 2);
+        assert!(report
+            .issues()
+            .iter()
+            .any(|issue| matches!(issue.kind(), IntegrityIssueKind::CorruptedMessage { .. })));
+        assert!(account_handle
+            .read()
+            .await
+            .list_messages(0, 0, None)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(manager.verify_integrity(false).await.unwrap().is_healthy());
+    }
+}
@@ -0,0 +1,165 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use bip39::{Language, Mnemonic};
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+
+/// The BIP39 wordlist languages supported by the wallet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MnemonicLanguage {
+    /// The english wordlist.
+    English,
+    /// The simplified chinese wordlist.
+    ChineseSimplified,
+    /// The traditional chinese wordlist.
+    ChineseTraditional,
+    /// The czech wordlist.
+    Czech,
+    /// The french wordlist.
+    French,
+    /// The italian wordlist.
+    Italian,
+    /// The japanese wordlist. Its words are separated by an ideographic space.
+    Japanese,
+    /// The korean wordlist.
+    Korean,
+    /// The spanish wordlist.
+    Spanish,
+}
+
+impl Default for MnemonicLanguage {
+    fn default() -> Self {
+        Self::English
+    }
+}
+
+impl MnemonicLanguage {
+    /// Every supported language, in detection order.
+    /// A mnemonic valid on both chinese wordlists is detected as simplified chinese.
+    pub const ALL: [MnemonicLanguage; 9] = [
+        MnemonicLanguage::English,
+        MnemonicLanguage::ChineseSimplified,
+        MnemonicLanguage::ChineseTraditional,
+        MnemonicLanguage::Czech,
+        MnemonicLanguage::French,
+        MnemonicLanguage::Italian,
+        MnemonicLanguage::Japanese,
+        MnemonicLanguage::Korean,
+        MnemonicLanguage::Spanish,
+    ];
+
+    fn language(&self) -> Language {
+        match self {
+            Self::English => Language::English,
+            Self::ChineseSimplified => Language::SimplifiedChinese,
+            Self::ChineseTraditional => Language::TraditionalChinese,
+            Self::Czech => Language::Czech,
+            Self::French => Language::French,
+            Self::Italian => Language::Italian,
+            Self::Japanese => Language::Japanese,
+            Self::Korean => Language::Korean,
+            Self::Spanish => Language::Spanish,
+        }
+    }
+
+    fn separator(&self) -> &'static str {
+        match self {
+            Self::Japanese => "\u{3000}",
+            _ => " ",
+        }
+    }
+
+    /// Joins the mnemonic words with the language separator, in the NFKD form used to derive the BIP39 seed.
+    fn to_nfkd(&self, mnemonic: &Mnemonic) -> String {
+        mnemonic
+            .word_iter()
+            .map(|word| word.nfkd().collect::<String>())
+            .collect::<Vec<String>>()
+            .join(self.separator())
+    }
+}
+
+/// Encodes the entropy as a NFKD normalized mnemonic on the given language.
+pub(crate) fn encode_mnemonic(entropy: &[u8], language: MnemonicLanguage) -> crate::Result<String> {
+    Mnemonic::from_entropy_in(language.language(), entropy)
+        .map(|mnemonic| language.to_nfkd(&mnemonic))
+        .map_err(|e| crate::Error::MnemonicEncode(e.to_string()))
+}
+
+/// Finds the language of the mnemonic, returning the mnemonic NFKD normalized.
+/// Any amount of regular or ideographic whitespace between the words and uppercase letters are accepted.
+pub(crate) fn normalize_mnemonic(mnemonic: &str) -> crate::Result<(String, MnemonicLanguage)> {
+    let words = mnemonic
+        .split_whitespace()
+        .map(|word| word.nfkd().collect::<String>().to_lowercase())
+        .collect::<Vec<String>>()
+        .join(" ");
+    let mut first_error = None;
+    for language in MnemonicLanguage::ALL {
+        match Mnemonic::parse_in(language.language(), words.as_str()) {
+            Ok(parsed) => return Ok((language.to_nfkd(&parsed), language)),
+            Err(e) => {
+                first_error.get_or_insert_with(|| e.to_string());
+            }
+        }
+    }
+    Err(crate::Error::InvalidMnemonic(first_error.unwrap_or_default()))
+}
+
+/// Detects the wordlist language of the given mnemonic.
+pub fn detect_mnemonic_language<S: AsRef<str>>(mnemonic: S) -> crate::Result<MnemonicLanguage> {
+    normalize_mnemonic(mnemonic.as_ref()).map(|(_, language)| language)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MnemonicLanguage;
+    use unicode_normalization::UnicodeNormalization;
+
+    #[test]
+    fn mnemonic_languages() {
+        let entropy = [7u8; 32];
+        for language in MnemonicLanguage::ALL {
+            let mnemonic = super::encode_mnemonic(&entropy, language).unwrap();
+            assert_eq!(mnemonic, mnemonic.nfkd().collect::<String>());
+            // the chinese wordlists share characters, so a mnemonic may be valid on both
+            if language != MnemonicLanguage::ChineseTraditional {
+                assert_eq!(super::detect_mnemonic_language(&mnemonic).unwrap(), language);
+            }
+
+            // extra and mixed whitespace is normalized, and the NFC form is stored as NFKD
+            let spaced = format!(
+                "  {}  ",
+                mnemonic
+                    .nfc()
+                    .collect::<String>()
+                    .split_whitespace()
+                    .collect::<Vec<&str>>()
+                    .join("   ")
+            );
+            let (normalized, _) = super::normalize_mnemonic(&spaced).unwrap();
+            assert_eq!(normalized, mnemonic);
+        }
+
+        let english = super::encode_mnemonic(&entropy, MnemonicLanguage::English).unwrap();
+        assert_eq!(
+            super::detect_mnemonic_language(english.to_uppercase()).unwrap(),
+            MnemonicLanguage::English
+        );
+        assert!(super::detect_mnemonic_language("not a mnemonic").is_err());
+    }
+
+    #[test]
+    fn spanish_mnemonic() {
+        // BIP39 spanish wordlist, zero entropy: "ábaco" eleven times and a checksum word
+        let mnemonic = super::encode_mnemonic(&[0u8; 16], MnemonicLanguage::Spanish).unwrap();
+        let words: Vec<&str> = mnemonic.split(' ').collect();
+        assert_eq!(words.len(), 12);
+        assert!(words[..11].iter().all(|word| *word == "a\u{301}baco"));
+        assert_eq!(
+            super::normalize_mnemonic(&mnemonic.nfc().collect::<String>()).unwrap(),
+            (mnemonic, MnemonicLanguage::Spanish)
+        );
+    }
+}
@@ -0,0 +1,315 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{AccountManager, AccountStore};
+use crate::{
+    account::ACCOUNT_ID_PREFIX,
+    event::{
+        on_balance_change, on_confirmation_state_change, remove_balance_change_listener,
+        remove_confirmation_state_change_listener, AccountEvent, EventId,
+    },
+};
+
+use futures::Future;
+use rumqttc::{
+    AsyncClient, ClientError, ConnectionError, Event, Key, MqttOptions, Outgoing, QoS, TlsConfiguration, Transport,
+};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use std::time::Duration;
+
+/// The placeholder replaced by the account id in the [topic](struct.MqttEventPublisherOptions.html#structfield.topic).
+pub const ACCOUNT_ID_PLACEHOLDER: &str = "{accountId}";
+/// The number of publish requests the client queues while the broker is unreachable.
+const REQUEST_CAPACITY: usize = 100;
+/// The number of events waiting to be published; the oldest are dropped when the broker can't keep up.
+const EVENT_CHANNEL_CAPACITY: usize = 1000;
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The private key authenticating the wallet on the broker.
+#[derive(Clone)]
+pub enum MqttClientKey {
+    /// A PEM encoded RSA key.
+    Rsa(Vec<u8>),
+    /// A PEM encoded ECC key.
+    Ecc(Vec<u8>),
+}
+
+/// TLS settings of the broker connection.
+#[derive(Clone)]
+pub struct MqttTlsOptions {
+    /// The PEM encoded certificate authority of the broker.
+    pub ca_certificate: Vec<u8>,
+    /// The PEM encoded client certificate and its key, if the broker authenticates the clients with certificates.
+    pub client_auth: Option<(Vec<u8>, MqttClientKey)>,
+}
+
+/// Options for
+/// [AccountManager#start_mqtt_event_publisher](struct.AccountManager.html#method.start_mqtt_event_publisher).
+#[derive(Clone)]
+pub struct MqttEventPublisherOptions {
+    /// The broker host.
+    pub host: String,
+    /// The broker port.
+    pub port: u16,
+    /// The client id of the wallet on the broker.
+    pub client_id: String,
+    /// The topic of the events, e.g. `wallet/{accountId}/events`.
+    /// [ACCOUNT_ID_PLACEHOLDER](constant.ACCOUNT_ID_PLACEHOLDER.html) is replaced by the id of the event account,
+    /// without its `wallet-account://` prefix since the topic levels are separated by `/`.
+    pub topic: String,
+    /// The username and password, if the broker requires them.
+    pub credentials: Option<(String, String)>,
+    /// The TLS settings, or `None` for a plain TCP connection.
+    pub tls: Option<MqttTlsOptions>,
+}
+
+/// The publish topics can't be empty or have wildcards.
+fn validate_topic(topic: &str) -> crate::Result<()> {
+    if topic.is_empty() || topic.contains(|c| c == '+' || c == '#') {
+        return Err(crate::Error::MqttEventPublisher(format!(
+            "invalid topic `{}`, it must be non-empty and without wildcards",
+            topic
+        )));
+    }
+    Ok(())
+}
+
+/// Registers the event listeners and spawns the task handing the events of the manager accounts to `publish`, with
+/// their topic and JSON payload. At most `capacity` events wait to be published: when the channel is full, the oldest
+/// event is dropped.
+async fn forward_events<F, Fut>(
+    accounts: AccountStore,
+    topic: String,
+    capacity: usize,
+    mut publish: F,
+) -> (EventId, EventId)
+where
+    F: FnMut(String, Vec<u8>) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<(), ClientError>> + Send + 'static,
+{
+    let (sender, mut receiver) = broadcast::channel(capacity);
+    let balance_sender = sender.clone();
+    let balance_change_listener = on_balance_change(move |event| {
+        let _ = balance_sender.send(AccountEvent::BalanceChange(event.clone()));
+    })
+    .await;
+    let confirmation_state_change_listener = on_confirmation_state_change(move |event| {
+        let _ = sender.send(AccountEvent::ConfirmationStateChange(event.clone()));
+    })
+    .await;
+
+    // ends when the listeners are removed, dropping the senders
+    crate::spawn(async move {
+        loop {
+            let event = match receiver.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(dropped)) => {
+                    log::warn!(
+                        "[MQTT EVENTS] dropped {} events the broker couldn't keep up with",
+                        dropped
+                    );
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            };
+            // the listeners get the events of every account manager
+            if !accounts.read().await.contains_key(event.account_id()) {
+                continue;
+            }
+            let payload = match serde_json::to_vec(&event) {
+                Ok(payload) => payload,
+                Err(e) => {
+                    log::warn!("[MQTT EVENTS] failed to serialize the event: {}", e);
+                    continue;
+                }
+            };
+            let account_id = event.account_id().trim_start_matches(ACCOUNT_ID_PREFIX);
+            if let Err(e) = publish(topic.replace(ACCOUNT_ID_PLACEHOLDER, account_id), payload).await {
+                log::warn!("[MQTT EVENTS] failed to publish the event: {}", e);
+            }
+        }
+    });
+
+    (balance_change_listener, confirmation_state_change_listener)
+}
+
+/// Republishes the wallet events to an MQTT broker, started with
+/// [AccountManager#start_mqtt_event_publisher](struct.AccountManager.html#method.start_mqtt_event_publisher).
+pub struct MqttEventPublisher {
+    client: AsyncClient,
+    balance_change_listener: EventId,
+    confirmation_state_change_listener: EventId,
+}
+
+impl MqttEventPublisher {
+    /// Stops republishing the events and disconnects from the broker.
+    /// The events keep being republished until it's called, even if the publisher is dropped.
+    pub async fn stop(self) -> crate::Result<()> {
+        remove_balance_change_listener(&self.balance_change_listener).await;
+        remove_confirmation_state_change_listener(&self.confirmation_state_change_listener).await;
+        self.client
+            .disconnect()
+            .await
+            .map_err(|e| crate::Error::MqttEventPublisher(e.to_string()))
+    }
+}
+
+impl AccountManager {
+    /// Republishes the balance change and confirmation state change events of the manager accounts to an MQTT broker,
+    /// so other systems can follow the wallet activity with their MQTT infrastructure. The events are published as JSON
+    /// [AccountEvent](../event/enum.AccountEvent.html)s with QoS 1 on the topic of their account; the connection is
+    /// retried while the broker is unreachable. Up to 1000 events wait to be published; when the broker can't keep up,
+    /// the oldest are dropped.
+    pub async fn start_mqtt_event_publisher(
+        &self,
+        options: MqttEventPublisherOptions,
+    ) -> crate::Result<MqttEventPublisher> {
+        validate_topic(&options.topic)?;
+
+        let mut mqtt_options = MqttOptions::new(options.client_id, options.host, options.port);
+        if let Some((username, password)) = options.credentials {
+            mqtt_options.set_credentials(username, password);
+        }
+        if let Some(tls) = options.tls {
+            mqtt_options.set_transport(Transport::tls_with_config(TlsConfiguration::Simple {
+                ca: tls.ca_certificate,
+                alpn: None,
+                client_auth: tls.client_auth.map(|(certificate, key)| {
+                    let key = match key {
+                        MqttClientKey::Rsa(key) => Key::RSA(key),
+                        MqttClientKey::Ecc(key) => Key::ECC(key),
+                    };
+                    (certificate, key)
+                }),
+            }));
+        }
+        let (client, mut event_loop) = AsyncClient::new(mqtt_options, REQUEST_CAPACITY);
+
+        // the event loop sends the queued requests and reconnects after the connection errors
+        crate::spawn(async move {
+            loop {
+                match event_loop.poll().await {
+                    Ok(Event::Outgoing(Outgoing::Disconnect)) | Err(ConnectionError::RequestsDone) => break,
+                    Ok(_) => {}
+                    Err(e) => {
+                        log::warn!("[MQTT EVENTS] broker connection error: {}", e);
+                        tokio::time::sleep(RECONNECT_INTERVAL).await;
+                    }
+                }
+            }
+        });
+
+        let publish_client = client.clone();
+        let (balance_change_listener, confirmation_state_change_listener) = forward_events(
+            self.accounts.clone(),
+            options.topic,
+            EVENT_CHANNEL_CAPACITY,
+            move |topic, payload| {
+                let publish_client = publish_client.clone();
+                async move { publish_client.publish(topic, QoS::AtLeastOnce, false, payload).await }
+            },
+        )
+        .await;
+
+        Ok(MqttEventPublisher {
+            client,
+            balance_change_listener,
+            confirmation_state_change_listener,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{forward_events, validate_topic};
+    use crate::event::{
+        emit_balance_change, remove_balance_change_listener, remove_confirmation_state_change_listener, BalanceChange,
+    };
+    use rusty_fork::rusty_fork_test;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn topic() {
+        assert!(validate_topic("wallet/{accountId}/events").is_ok());
+        assert!(validate_topic("wallet/+/events").is_err());
+        assert!(validate_topic("wallet/#").is_err());
+        assert!(validate_topic("").is_err());
+    }
+
+    rusty_fork_test! {
+        #[test]
+        fn publish_events() {
+            let runtime = tokio::runtime::Runtime::new().unwrap();
+            runtime.block_on(async {
+                let manager = crate::testing::get_account_manager().await;
+                let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
+                let account = account_handle.read().await;
+                let other_manager = crate::testing::get_account_manager().await;
+                let other_account_handle = crate::testing::AccountCreator::new(&other_manager).create().await;
+                let other_account = other_account_handle.read().await;
+
+                // the publisher waits for the gate, so the events pile up in the channel
+                let gate = Arc::new(tokio::sync::Mutex::new(()));
+                let published = Arc::new(Mutex::new(Vec::new()));
+                let (started_sender, mut started) = tokio::sync::mpsc::unbounded_channel();
+                let gate_guard = gate.clone().lock_owned().await;
+                let published_ = published.clone();
+                let (balance_change_listener, confirmation_state_change_listener) = forward_events(
+                    manager.accounts.clone(),
+                    "wallet/{accountId}/events".to_string(),
+                    2,
+                    move |topic, payload| {
+                        published_.lock().unwrap().push((topic, payload));
+                        let _ = started_sender.send(());
+                        let gate = gate.clone();
+                        async move {
+                            drop(gate.lock().await);
+                            Ok(())
+                        }
+                    },
+                )
+                .await;
+
+                let address = crate::testing::generate_random_iota_address();
+                // the events of the other manager accounts aren't published
+                emit_balance_change(&other_account, &address, None, BalanceChange::received(10), false)
+                    .await
+                    .unwrap();
+                emit_balance_change(&account, &address, None, BalanceChange::received(1), false)
+                    .await
+                    .unwrap();
+                started.recv().await.unwrap();
+                // the channel keeps the two newest events
+                for received in 2..=5 {
+                    emit_balance_change(&account, &address, None, BalanceChange::received(received), false)
+                        .await
+                        .unwrap();
+                }
+                drop(gate_guard);
+                started.recv().await.unwrap();
+                started.recv().await.unwrap();
+
+                let topic = format!(
+                    "wallet/{}/events",
+                    account.id().trim_start_matches(crate::account::ACCOUNT_ID_PREFIX)
+                );
+                let published = published.lock().unwrap().clone();
+                let received: Vec<u64> = published
+                    .iter()
+                    .map(|(event_topic, payload)| {
+                        assert_eq!(event_topic, &topic);
+                        let event: serde_json::Value = serde_json::from_slice(payload).unwrap();
+                        assert_eq!(event["type"], "BalanceChange");
+                        assert_eq!(event["data"]["accountId"], account.id().as_str());
+                        event["data"]["balanceChange"]["received"].as_u64().unwrap()
+                    })
+                    .collect();
+                assert_eq!(received, vec![1, 4, 5]);
+
+                remove_balance_change_listener(&balance_change_listener).await;
+                remove_confirmation_state_change_listener(&confirmation_state_change_listener).await;
+            });
+        }
+    }
+}
@@ -0,0 +1,104 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{storage_password_to_encryption_key, AccountManager};
+
+use zeroize::Zeroize;
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// The open API sessions, by token, with their expiry.
+pub(crate) type Sessions = HashMap<String, Instant>;
+
+impl AccountManager {
+    /// Opens an API session with the storage password, returning the session token that the
+    /// [WalletMessageHandler](../actor/struct.WalletMessageHandler.html#method.with_session_required) requires for
+    /// transfers and backups. The session expires after `ttl`, after which the password must be given again.
+    /// The session is independent of the Stronghold password, which can still be cleared on its own interval.
+    pub async fn open_session<P: Into<String>>(&self, password: P, ttl: Duration) -> crate::Result<String> {
+        let mut password = password.into();
+        let key = storage_password_to_encryption_key(&password);
+        password.zeroize();
+
+        match crate::storage::get_encryption_key_checksum(&self.storage_path()).await {
+            Ok(_) => {}
+            Err(crate::Error::RecordNotFound) => {
+                return Err(crate::Error::Unauthorized("the storage has no password".to_string()))
+            }
+            Err(e) => return Err(e),
+        }
+        if !crate::storage::is_key_valid(&self.storage_path(), &key).await? {
+            return Err(crate::Error::Unauthorized("invalid password".to_string()));
+        }
+
+        let mut token = [0; 32];
+        crypto::utils::rand::fill(&mut token).map_err(|e| crate::Error::Unauthorized(format!("{:?}", e)))?;
+        let token = hex::encode(token);
+        let now = Instant::now();
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.retain(|_, expires_at| *expires_at > now);
+        sessions.insert(token.clone(), now + ttl);
+        Ok(token)
+    }
+
+    /// Closes the session, so its token is rejected from now on.
+    pub fn close_session(&self, token: &str) {
+        self.sessions.lock().unwrap().remove(token);
+    }
+
+    /// Closes every open session, e.g. when the application is locked.
+    pub fn close_all_sessions(&self) {
+        self.sessions.lock().unwrap().clear();
+    }
+
+    /// Checks that the session is open, failing with
+    /// [SessionExpired](../enum.Error.html#variant.SessionExpired) once its time to live elapsed or it was closed.
+    pub fn verify_session(&self, token: &str) -> crate::Result<()> {
+        let mut sessions = self.sessions.lock().unwrap();
+        match sessions.get(token) {
+            Some(expires_at) if *expires_at > Instant::now() => Ok(()),
+            Some(_) => {
+                sessions.remove(token);
+                Err(crate::Error::SessionExpired)
+            }
+            None => Err(crate::Error::SessionExpired),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn sessions() {
+        let manager = crate::testing::get_account_manager().await;
+        manager.set_storage_password("password").await.unwrap();
+
+        assert!(matches!(
+            manager.open_session("wrong", Duration::from_secs(60)).await,
+            Err(crate::Error::Unauthorized(_))
+        ));
+
+        let token = manager.open_session("password", Duration::from_secs(60)).await.unwrap();
+        manager.verify_session(&token).unwrap();
+        manager.close_session(&token);
+        assert!(matches!(
+            manager.verify_session(&token),
+            Err(crate::Error::SessionExpired)
+        ));
+
+        let token = manager
+            .open_session("password", Duration::from_millis(10))
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(matches!(
+            manager.verify_session(&token),
+            Err(crate::Error::SessionExpired)
+        ));
+    }
+}
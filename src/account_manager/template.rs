@@ -0,0 +1,191 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{AccountManager, AccountOptions};
+use crate::{account::AccountInitialiser, client::ClientOptions, signing::SignerType};
+
+use getset::{CopyGetters, Getters};
+use serde::{Deserialize, Serialize};
+
+/// The placeholder of the alias scheme replaced with the account index.
+const ALIAS_INDEX_PLACEHOLDER: &str = "{index}";
+
+/// The output consolidation settings of an account, overriding the account manager ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, CopyGetters)]
+#[getset(get_copy = "pub")]
+pub struct ConsolidationSettings {
+    /// The number of outputs an address must have to trigger the consolidation.
+    threshold: usize,
+    /// Whether the polling consolidates the outputs automatically.
+    automatic: bool,
+    /// Whether the outputs are consolidated to a change address instead of the address holding them.
+    #[serde(rename = "toChangeAddress", default)]
+    to_change_address: bool,
+    /// The number of equal outputs the consolidation creates.
+    #[serde(rename = "outputCount", default = "default_output_count")]
+    output_count: usize,
+}
+
+fn default_output_count() -> usize {
+    1
+}
+
+impl ConsolidationSettings {
+    /// Creates the settings with the given threshold, consolidating automatically to a single output on the address.
+    pub fn new(threshold: usize) -> Self {
+        Self {
+            threshold,
+            automatic: true,
+            to_change_address: false,
+            output_count: default_output_count(),
+        }
+    }
+
+    /// Sets whether the polling consolidates the outputs automatically.
+    pub fn with_automatic(mut self, automatic: bool) -> Self {
+        self.automatic = automatic;
+        self
+    }
+
+    /// Consolidates the outputs to a change address.
+    pub fn with_change_address(mut self) -> Self {
+        self.to_change_address = true;
+        self
+    }
+
+    /// Sets the number of equal outputs the consolidation creates.
+    pub fn with_output_count(mut self, output_count: usize) -> Self {
+        self.output_count = output_count;
+        self
+    }
+
+    pub(crate) fn apply(&self, mut options: AccountOptions) -> AccountOptions {
+        options.output_consolidation_threshold = self.threshold;
+        options.automatic_output_consolidation = self.automatic;
+        options.consolidate_to_change_address = self.to_change_address;
+        options.consolidation_output_count = self.output_count;
+        options
+    }
+}
+
+/// The configuration shared by accounts created with
+/// [AccountManager#create_account_from_template](struct.AccountManager.html#method.create_account_from_template).
+#[derive(Debug, Clone, Serialize, Deserialize, Getters)]
+#[getset(get = "pub")]
+pub struct AccountTemplate {
+    /// The client options of the accounts.
+    #[serde(rename = "clientOptions")]
+    client_options: ClientOptions,
+    /// The signer type of the accounts.
+    #[serde(rename = "signerType", default)]
+    signer_type: Option<SignerType>,
+    /// The alias of the accounts, where `{index}` is replaced with the account index, e.g. `customer-{index}`.
+    #[serde(rename = "aliasScheme", default)]
+    alias_scheme: Option<String>,
+    /// The initial gap limit of the account syncs.
+    #[serde(rename = "gapLimit", default)]
+    gap_limit: Option<usize>,
+    /// The output consolidation settings of the accounts.
+    #[serde(default)]
+    consolidation: Option<ConsolidationSettings>,
+}
+
+impl AccountTemplate {
+    /// Creates a template for accounts using the given client options.
+    pub fn new(client_options: ClientOptions) -> Self {
+        Self {
+            client_options,
+            signer_type: None,
+            alias_scheme: None,
+            gap_limit: None,
+            consolidation: None,
+        }
+    }
+
+    /// Sets the signer type of the accounts.
+    pub fn with_signer_type(mut self, signer_type: SignerType) -> Self {
+        self.signer_type.replace(signer_type);
+        self
+    }
+
+    /// Sets the alias scheme of the accounts; `{index}` is replaced with the account index.
+    pub fn with_alias_scheme(mut self, alias_scheme: impl Into<String>) -> Self {
+        self.alias_scheme.replace(alias_scheme.into());
+        self
+    }
+
+    /// Sets the initial gap limit of the account syncs.
+    pub fn with_gap_limit(mut self, gap_limit: usize) -> Self {
+        self.gap_limit.replace(gap_limit);
+        self
+    }
+
+    /// Sets the output consolidation settings of the accounts.
+    pub fn with_consolidation(mut self, consolidation: ConsolidationSettings) -> Self {
+        self.consolidation.replace(consolidation);
+        self
+    }
+}
+
+/// Builds the account alias from the scheme.
+pub(crate) fn interpolate_alias(alias_scheme: &str, index: usize) -> String {
+    alias_scheme.replace(ALIAS_INDEX_PLACEHOLDER, &index.to_string())
+}
+
+impl AccountManager {
+    /// Adds a new account configured by the template.
+    /// The returned initialiser can still override the template, e.g. with an explicit alias.
+    pub fn create_account_from_template(&self, template: &AccountTemplate) -> crate::Result<AccountInitialiser> {
+        let mut initialiser = self.create_account(template.client_options.clone())?;
+        if let Some(signer_type) = &template.signer_type {
+            initialiser = initialiser.signer_type(signer_type.clone());
+        }
+        if let Some(alias_scheme) = &template.alias_scheme {
+            initialiser = initialiser.alias_scheme(alias_scheme.clone());
+        }
+        if let Some(gap_limit) = template.gap_limit {
+            initialiser = initialiser.gap_limit_hint(gap_limit);
+        }
+        if let Some(consolidation) = template.consolidation {
+            initialiser = initialiser.consolidation(consolidation);
+        }
+        Ok(initialiser)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AccountTemplate, ConsolidationSettings};
+    use crate::client::ClientOptionsBuilder;
+
+    #[tokio::test]
+    async fn account_templates() {
+        let manager = crate::testing::get_account_manager().await;
+        let client_options = ClientOptionsBuilder::new()
+            .with_node("https://api.lb-0.h.chrysalis-devnet.iota.cafe")
+            .unwrap()
+            .with_network("testnet")
+            .build()
+            .unwrap();
+        let template = AccountTemplate::new(client_options)
+            .with_signer_type(crate::signing::SignerType::Custom("".to_string()))
+            .with_alias_scheme("customer-{index}")
+            .with_gap_limit(50)
+            .with_consolidation(ConsolidationSettings::new(20).with_automatic(false));
+
+        for _ in 0..2 {
+            let account_handle = manager
+                .create_account_from_template(&template)
+                .unwrap()
+                .allow_create_multiple_empty_accounts()
+                .initialise()
+                .await
+                .unwrap();
+            let account = account_handle.read().await;
+            assert_eq!(account.alias(), &format!("customer-{}", account.index()));
+            assert_eq!(account.gap_limit_hint(), &Some(50));
+            assert_eq!(account_handle.account_options.output_consolidation_threshold, 20);
+            assert!(!account_handle.account_options.automatic_output_consolidation);
+        }
+    }
+}
@@ -0,0 +1,156 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::AccountManager;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Maximum length of a user data key.
+const USER_DATA_MAX_KEY_LENGTH: usize = 256;
+/// Maximum size of a serialized user data value.
+const USER_DATA_MAX_VALUE_SIZE: usize = 64 * 1024;
+
+/// A key-value store for application data, kept in the wallet storage.
+/// The records are encrypted along with the wallet data and included in backups.
+/// Created with [AccountManager#user_data](struct.AccountManager.html#method.user_data).
+pub struct UserData<'a> {
+    manager: &'a AccountManager,
+}
+
+impl<'a> UserData<'a> {
+    /// Stores the value under the given key, replacing any previous value.
+    pub async fn set<T: Serialize>(&self, key: &str, value: &T) -> crate::Result<()> {
+        validate_key(key)?;
+        let value = serde_json::to_value(value)?;
+        if serde_json::to_vec(&value)?.len() > USER_DATA_MAX_VALUE_SIZE {
+            return Err(crate::Error::InvalidUserData(format!(
+                "value exceeds {} bytes",
+                USER_DATA_MAX_VALUE_SIZE
+            )));
+        }
+        self.manager.check_storage_encryption()?;
+        crate::storage::get(&self.manager.storage_path())
+            .await?
+            .lock()
+            .await
+            .set_user_data(key, &value)
+            .await
+    }
+
+    /// Gets the value stored under the given key.
+    pub async fn get<T: DeserializeOwned>(&self, key: &str) -> crate::Result<Option<T>> {
+        validate_key(key)?;
+        self.manager.check_storage_encryption()?;
+        let value = crate::storage::get(&self.manager.storage_path())
+            .await?
+            .lock()
+            .await
+            .get_user_data(key)
+            .await?;
+        match value {
+            Some(value) => Ok(Some(serde_json::from_value(value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Removes the value stored under the given key, if any.
+    pub async fn remove(&self, key: &str) -> crate::Result<()> {
+        validate_key(key)?;
+        self.manager.check_storage_encryption()?;
+        crate::storage::get(&self.manager.storage_path())
+            .await?
+            .lock()
+            .await
+            .remove_user_data(key)
+            .await
+    }
+
+    /// Lists the stored keys starting with the given prefix, sorted.
+    pub async fn list(&self, prefix: &str) -> crate::Result<Vec<String>> {
+        self.manager.check_storage_encryption()?;
+        let mut keys: Vec<String> = crate::storage::get(&self.manager.storage_path())
+            .await?
+            .lock()
+            .await
+            .get_user_data_keys()
+            .await?
+            .into_iter()
+            .filter(|key| key.starts_with(prefix))
+            .collect();
+        keys.sort();
+        Ok(keys)
+    }
+}
+
+fn validate_key(key: &str) -> crate::Result<()> {
+    if key.is_empty() || key.len() > USER_DATA_MAX_KEY_LENGTH {
+        return Err(crate::Error::InvalidUserData(format!(
+            "key must have between 1 and {} bytes",
+            USER_DATA_MAX_KEY_LENGTH
+        )));
+    }
+    Ok(())
+}
+
+impl AccountManager {
+    /// Gets the user data store of the wallet.
+    pub fn user_data(&self) -> UserData<'_> {
+        UserData { manager: self }
+    }
+
+    /// Copies the user data records from the storage at `from` to the storage at `to`.
+    pub(crate) async fn copy_user_data(from: &std::path::Path, to: &std::path::Path) -> crate::Result<()> {
+        let records = {
+            let storage = crate::storage::get(from).await?;
+            let storage = storage.lock().await;
+            let mut records = Vec::new();
+            for key in storage.get_user_data_keys().await? {
+                if let Some(value) = storage.get_user_data(&key).await? {
+                    records.push((key, value));
+                }
+            }
+            records
+        };
+        let storage = crate::storage::get(to).await?;
+        let mut storage = storage.lock().await;
+        for (key, value) in records {
+            storage.set_user_data(&key, &value).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[tokio::test]
+    async fn user_data() {
+        let manager = crate::testing::get_account_manager().await;
+        let user_data = manager.user_data();
+
+        user_data.set("settings.theme", &"dark").await.unwrap();
+        user_data.set("settings.currency", &"EUR").await.unwrap();
+        user_data.set("onboarded", &true).await.unwrap();
+
+        assert_eq!(
+            user_data.get::<String>("settings.theme").await.unwrap(),
+            Some("dark".to_string())
+        );
+        assert_eq!(
+            user_data.list("settings.").await.unwrap(),
+            vec!["settings.currency".to_string(), "settings.theme".to_string()]
+        );
+
+        user_data.remove("settings.theme").await.unwrap();
+        assert_eq!(user_data.get::<String>("settings.theme").await.unwrap(), None);
+        assert_eq!(user_data.list("").await.unwrap().len(), 2);
+        assert!(user_data.set("", &0).await.is_err());
+
+        // a key matching the index suffix doesn't overwrite the index
+        user_data.set("index", &"value").await.unwrap();
+        assert_eq!(user_data.list("").await.unwrap().len(), 3);
+        assert_eq!(
+            user_data.get::<String>("index").await.unwrap(),
+            Some("value".to_string())
+        );
+    }
+}
@@ -11,7 +11,7 @@ use crate::{
     Error,
 };
 use chrono::{DateTime, Local};
-use iota_client::NodeInfoWrapper;
+use iota_client::{bee_message::MessageId, NodeInfoWrapper};
 use iota_migration::{ternary::T3B1Buf, transaction::bundled::BundledTransactionField};
 use serde::{ser::Serializer, Deserialize, Serialize};
 use tokio::sync::mpsc::UnboundedSender;
@@ -38,6 +38,9 @@ pub struct AccountToCreate {
     /// Allow to create an account with multiple empty accounts
     #[serde(rename = "allowCreateMultipleEmptyAccounts", default)]
     pub allow_create_multiple_empty_accounts: bool,
+    /// Whether to create a cold storage account or not.
+    #[serde(rename = "coldStorage", default)]
+    pub cold_storage: bool,
 }
 
 /// Each public account method.
@@ -81,6 +84,12 @@ pub enum AccountMethod {
         /// Whether to skip writing the account in storage or not (defaults to false).
         #[serde(rename = "skipPersistence")]
         skip_persistence: Option<bool>,
+        /// Whether to save the synced messages while syncing to bound the memory usage (defaults to false).
+        #[serde(rename = "lowMemory")]
+        low_memory: Option<bool>,
+        /// The key index range to scan instead of discovering new addresses with the gap limit.
+        #[serde(rename = "addressRange")]
+        address_range: Option<std::ops::Range<usize>>,
     },
     /// Checks if the account's latest address is unused after syncing with the Tangle.
     IsLatestAddressUnused,
@@ -90,6 +99,64 @@ pub enum AccountMethod {
     SetClientOptions(Box<ClientOptions>),
     /// Gets the node information.
     GetNodeInfo(Option<String>, Option<String>, Option<(String, String)>),
+    /// Re-verifies the stored messages, optionally repairing them.
+    RevalidateMessages {
+        /// Whether the inconsistencies should be repaired or only reported.
+        repair: bool,
+    },
+    /// Verifies a stored message against its raw bytes, fetching them from the node if they aren't stored.
+    VerifyMessage {
+        /// The message id.
+        #[serde(rename = "messageId")]
+        message_id: MessageId,
+    },
+    /// Computes the account activity summary.
+    GetActivitySummary(crate::account::ActivityPeriod),
+    /// Gets the account light state.
+    GetLightState,
+    /// Gets the limits and features of the account signer.
+    GetSignerCapabilities,
+    /// Gets the persisted account events after the given sequence number.
+    GetEventsSince {
+        /// The last processed sequence number.
+        sequence: u64,
+    },
+    /// Repairs the account address space.
+    RepairAddressSpace,
+    /// Freezes the account, blocking its transfers.
+    Freeze,
+    /// Unfreezes the account.
+    Unfreeze,
+    /// Estimates the size and dust implications of a transfer.
+    EstimateTransaction {
+        /// The transfer details.
+        transfer: Box<TransferBuilder>,
+    },
+    /// Checks whether a transfer can be sent from the local account state, without syncing.
+    CanSend {
+        /// The transfer amount.
+        amount: u64,
+        /// The number of outputs of the transfer.
+        #[serde(rename = "outputCount")]
+        output_count: usize,
+    },
+    /// Compares the address balances with the node, re-syncing the mismatching addresses if `repair` is set.
+    ReconcileWithNode {
+        /// Whether the mismatching addresses should be re-synced.
+        #[serde(default)]
+        repair: bool,
+    },
+    /// Gets the status of a message.
+    GetTransactionStatus {
+        /// The message id.
+        #[serde(rename = "messageId")]
+        message_id: MessageId,
+        /// Whether the message metadata should be checked on the node.
+        #[serde(rename = "checkNode", default)]
+        check_node: bool,
+    },
+    /// Checks on the node whether a message should be promoted or reattached.
+    ShouldRetry(MessageId),
 }
 
 /// The returned account.
@@ -169,6 +236,15 @@ pub enum MessageType {
     SetStoragePassword(String),
     /// Clears the password used to encrypt/decrypt the storage.
     ClearStoragePassword,
+    /// Changes the password of the encrypted storage.
+    ChangeStoragePassword {
+        /// The current storage password.
+        #[serde(rename = "currentPassword")]
+        current_password: String,
+        /// The new storage password.
+        #[serde(rename = "newPassword")]
+        new_password: String,
+    },
     /// Set stronghold snapshot password.
     #[cfg(feature = "stronghold")]
     #[cfg_attr(docsrs, doc(cfg(feature = "stronghold")))]
@@ -345,6 +421,10 @@ pub enum MessageType {
     },
     /// Stop background syncing.
     StopBackgroundSync,
+    /// Pause the background sync and monitoring of all accounts.
+    PauseAll,
+    /// Resume the background sync and monitoring paused with `PauseAll`.
+    ResumeAll,
     #[cfg(feature = "participation")]
     /// Participate in a voting or staking events
     Participate {
@@ -371,6 +451,64 @@ pub enum MessageType {
     #[cfg(feature = "participation")]
     /// Get participation events
     GetParticipationEvents,
+    #[cfg(feature = "participation")]
+    /// Fetch and cache the events of the given participation nodes
+    RegisterParticipationEvents {
+        /// The participation node urls
+        #[serde(rename = "nodeUrls")]
+        node_urls: Vec<String>,
+    },
+    /// Gets the diagnostics report of the node used by the given client options or the first account.
+    GetNodeDiagnostics(Option<Box<ClientOptions>>),
+    /// Stores a user data value.
+    SetUserData {
+        /// The record key.
+        key: String,
+        /// The record value.
+        value: serde_json::Value,
+    },
+    /// Gets a user data value.
+    GetUserData(String),
+    /// Removes a user data value.
+    RemoveUserData(String),
+    /// Lists the user data keys starting with the given prefix.
+    ListUserData(String),
+    /// Generates a new mnemonic using the wordlist of the given language.
+    GenerateMnemonicWithLanguage(crate::account_manager::MnemonicLanguage),
+    /// Creates the decoy wallet opened by the duress password.
+    #[cfg(feature = "stronghold")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "stronghold")))]
+    ConfigureDuress {
+        /// The duress password.
+        password: String,
+        /// The aliases of the decoy accounts.
+        #[serde(rename = "decoyAccounts")]
+        decoy_accounts: Vec<String>,
+    },
+    /// Creates a rehearsal copy of an account on a test network.
+    CloneAccountToNetwork {
+        /// The account to copy.
+        #[serde(rename = "accountId")]
+        account_id: AccountIdentifier,
+        /// The client options of the test network.
+        #[serde(rename = "clientOptions")]
+        client_options: Box<ClientOptions>,
+    },
+    /// Parses a wallet action deep link.
+    ParseDeepLink(String),
+    /// Opens an API session with the storage password.
+    OpenSession {
+        /// The storage password.
+        password: String,
+        /// The session time to live.
+        ttl: Duration,
+    },
+    /// Closes an API session.
+    CloseSession(String),
+    /// Shuts the manager down, waiting up to the given duration for the in-flight transfers.
+    Shutdown(Duration),
+    /// Checks the wallet subsystems.
+    GetHealth,
 }
 
 impl Serialize for MessageType {
@@ -473,6 +611,37 @@ impl Serialize for MessageType {
             MessageType::GetParticipationEvents => {
                 serializer.serialize_unit_variant("MessageType", 39, "GetParticipationEvents")
             }
+            MessageType::GetNodeDiagnostics(_) => {
+                serializer.serialize_unit_variant("MessageType", 40, "GetNodeDiagnostics")
+            }
+            MessageType::SetUserData { .. } => serializer.serialize_unit_variant("MessageType", 41, "SetUserData"),
+            MessageType::GetUserData(_) => serializer.serialize_unit_variant("MessageType", 42, "GetUserData"),
+            MessageType::RemoveUserData(_) => serializer.serialize_unit_variant("MessageType", 43, "RemoveUserData"),
+            MessageType::ListUserData(_) => serializer.serialize_unit_variant("MessageType", 44, "ListUserData"),
+            MessageType::GenerateMnemonicWithLanguage(_) => {
+                serializer.serialize_unit_variant("MessageType", 45, "GenerateMnemonicWithLanguage")
+            }
+            #[cfg(feature = "stronghold")]
+            MessageType::ConfigureDuress { .. } => {
+                serializer.serialize_unit_variant("MessageType", 46, "ConfigureDuress")
+            }
+            MessageType::CloneAccountToNetwork { .. } => {
+                serializer.serialize_unit_variant("MessageType", 47, "CloneAccountToNetwork")
+            }
+            MessageType::PauseAll => serializer.serialize_unit_variant("MessageType", 48, "PauseAll"),
+            MessageType::ResumeAll => serializer.serialize_unit_variant("MessageType", 49, "ResumeAll"),
+            MessageType::ParseDeepLink(_) => serializer.serialize_unit_variant("MessageType", 51, "ParseDeepLink"),
+            MessageType::OpenSession { .. } => serializer.serialize_unit_variant("MessageType", 52, "OpenSession"),
+            MessageType::CloseSession(_) => serializer.serialize_unit_variant("MessageType", 53, "CloseSession"),
+            MessageType::Shutdown(_) => serializer.serialize_unit_variant("MessageType", 54, "Shutdown"),
+            MessageType::GetHealth => serializer.serialize_unit_variant("MessageType", 55, "GetHealth"),
+            MessageType::ChangeStoragePassword { .. } => {
+                serializer.serialize_unit_variant("MessageType", 56, "ChangeStoragePassword")
+            }
+            #[cfg(feature = "participation")]
+            MessageType::RegisterParticipationEvents { .. } => {
+                serializer.serialize_unit_variant("MessageType", 50, "RegisterParticipationEvents")
+            }
         }
     }
 }
@@ -598,6 +767,8 @@ pub enum ResponseType {
     StoragePasswordSet,
     /// ClearStoragePassword response.
     StoragePasswordCleared,
+    /// ChangeStoragePassword response.
+    StoragePasswordChanged,
     /// SetStrongholdPassword response.
     #[cfg(feature = "stronghold")]
     #[cfg_attr(docsrs, doc(cfg(feature = "stronghold")))]
@@ -648,6 +819,24 @@ pub enum ResponseType {
     UpdatedAllClientOptions,
     /// GetNodeInfo response.
     NodeInfo(NodeInfoWrapper),
+    /// RevalidateMessages response.
+    ValidationReport(crate::account::ValidationReport),
+    /// GetActivitySummary response.
+    ActivitySummary(crate::account::ActivitySummary),
+    /// GetLightState response.
+    LightState(Box<crate::account::LightState>),
+    /// GetSignerCapabilities response.
+    SignerCapabilities(crate::signing::SignerCapabilities),
+    /// GetEventsSince response.
+    AccountEvents(Vec<crate::event::AccountEvent>),
+    /// RepairAddressSpace response.
+    AddressRepairReport(crate::account::AddressRepairReport),
+    /// ReconcileWithNode response.
+    ReconciliationReport(crate::account::ReconciliationReport),
+    /// GetTransactionStatus response.
+    TransactionStatus(crate::account::TransactionStatus),
+    /// ShouldRetry response.
+    RetryAdvice(crate::account::RetryAdvice),
     /// GetMigrationData response.
     MigrationData(MigrationDataDto),
     /// CreateMigrationBundle response (bundle hash).
@@ -673,6 +862,30 @@ pub enum ResponseType {
     #[cfg(feature = "participation")]
     /// Get data about participation events.
     EventsData(Vec<crate::participation::types::EventData>),
+    /// GetNodeDiagnostics response.
+    NodeDiagnostics(crate::client::NodeDiagnostics),
+    /// GetUserData response.
+    UserData(Option<serde_json::Value>),
+    /// ListUserData response.
+    UserDataKeys(Vec<String>),
+    /// EstimateTransaction response.
+    TransactionEstimate(crate::account::TransactionEstimate),
+    /// CanSend response.
+    SendCheck(crate::account::SendCheck),
+    /// ParseDeepLink response.
+    WalletIntent(crate::deeplink::WalletIntent),
+    /// OpenSession response, with the session token.
+    SessionOpened(String),
+    /// CloseSession response.
+    SessionClosed,
+    /// Shutdown response: whether the in-flight transfers finished before the timeout.
+    ShutDown(bool),
+    /// GetHealth response.
+    Health(crate::account_manager::HealthReport),
+    /// ConfigureDuress response.
+    #[cfg(feature = "stronghold")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "stronghold")))]
+    DuressConfigured,
 }
 
 /// The message type.
@@ -681,6 +894,8 @@ pub struct Message {
     id: String,
     pub(crate) message_type: MessageType,
     pub(crate) response_tx: UnboundedSender<Response>,
+    auth_token: Option<String>,
+    session_token: Option<String>,
 }
 
 impl Message {
@@ -690,9 +905,34 @@ impl Message {
             id: id.into(),
             message_type,
             response_tx,
+            auth_token: None,
+            session_token: None,
         }
     }
 
+    /// Sets the auth token checked against the tokens registered on the message handler.
+    pub fn with_auth_token<S: Into<String>>(mut self, token: S) -> Self {
+        self.auth_token.replace(token.into());
+        self
+    }
+
+    /// The auth token.
+    pub fn auth_token(&self) -> &Option<String> {
+        &self.auth_token
+    }
+
+    /// Sets the session token returned by `OpenSession`, required for the sensitive methods when the message handler
+    /// [requires a session](struct.WalletMessageHandler.html#method.with_session_required).
+    pub fn with_session_token<S: Into<String>>(mut self, token: S) -> Self {
+        self.session_token.replace(token.into());
+        self
+    }
+
+    /// The session token.
+    pub fn session_token(&self) -> &Option<String> {
+        &self.session_token
+    }
+
     /// The message type.
     pub fn message_type(&self) -> &MessageType {
         &self.message_type
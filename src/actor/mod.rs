@@ -19,6 +19,7 @@ use zeroize::Zeroize;
 
 use std::{
     any::Any,
+    collections::HashMap,
     convert::TryInto,
     num::NonZeroU64,
     panic::{catch_unwind, AssertUnwindSafe},
@@ -28,10 +29,14 @@ use std::{
 
 mod message;
 pub use message::*;
+mod scope;
+pub use scope::TokenScope;
 
 /// The Wallet message handler.
 pub struct WalletMessageHandler {
     account_manager: AccountManager,
+    auth_tokens: HashMap<String, TokenScope>,
+    session_required: bool,
 }
 
 fn panic_to_response_message(panic: Box<dyn Any>) -> ResponseType {
@@ -68,17 +73,79 @@ impl WalletMessageHandler {
     pub async fn new() -> Result<Self> {
         let instance = Self {
             account_manager: AccountManager::builder().finish().await?,
+            auth_tokens: Default::default(),
+            session_required: false,
         };
         Ok(instance)
     }
 
     /// Creates a new instance of the message handler with the specified account manager.
     pub fn with_manager(account_manager: AccountManager) -> Self {
-        Self { account_manager }
+        Self {
+            account_manager,
+            auth_tokens: Default::default(),
+            session_required: false,
+        }
+    }
+
+    /// Registers an auth token with the given scope.
+    /// Once a token is registered, messages without a token or with a token whose scope doesn't allow them are
+    /// rejected, so a monitoring dashboard can be connected to a wallet daemon without transfer capability.
+    pub fn with_auth_token<S: Into<String>>(mut self, token: S, scope: TokenScope) -> Self {
+        self.auth_tokens.insert(token.into(), scope);
+        self
+    }
+
+    /// Requires a session opened with `OpenSession` for the transfers, backups and the changes of the wallet secrets and
+    /// client options, so a daemon exposing the handler asks for the storage password again once the session expires.
+    pub fn with_session_required(mut self) -> Self {
+        self.session_required = true;
+        self
+    }
+
+    fn verify_session(&self, message: &Message) -> Result<()> {
+        if !self.session_required || !scope::requires_session(message.message_type()) {
+            return Ok(());
+        }
+        let token = message
+            .session_token()
+            .as_ref()
+            .ok_or_else(|| crate::Error::Unauthorized("missing session token".to_string()))?;
+        self.account_manager.verify_session(token)
+    }
+
+    fn authorize(&self, message: &Message) -> Result<()> {
+        if self.auth_tokens.is_empty() {
+            return Ok(());
+        }
+        let token = message
+            .auth_token()
+            .as_ref()
+            .ok_or_else(|| crate::Error::Unauthorized("missing auth token".to_string()))?;
+        let scope = self
+            .auth_tokens
+            .get(token)
+            .ok_or_else(|| crate::Error::Unauthorized("unknown auth token".to_string()))?;
+        if scope.allows(message.message_type()) {
+            Ok(())
+        } else {
+            Err(crate::Error::Unauthorized(format!(
+                "the {:?} scope doesn't allow the method",
+                scope
+            )))
+        }
     }
 
     /// Handles a message.
     pub async fn handle(&self, mut message: Message) {
+        if let Err(e) = self.authorize(&message).and_then(|_| self.verify_session(&message)) {
+            let _ = message.response_tx.send(Response::new(
+                message.id().to_string(),
+                message.message_type,
+                ResponseType::Error(e),
+            ));
+            return;
+        }
         let response: Result<ResponseType> = match message.message_type_mut() {
             MessageType::RemoveAccount(account_id) => {
                 convert_async_panics(|| async { self.remove_account(account_id).await }).await
@@ -128,6 +195,21 @@ impl WalletMessageHandler {
             MessageType::ClearStoragePassword => {
                 convert_async_panics(|| async { self.clear_storage_password().await }).await
             }
+            MessageType::ChangeStoragePassword {
+                current_password,
+                new_password,
+            } => {
+                let res = convert_async_panics(|| async {
+                    self.account_manager
+                        .change_storage_password(current_password.as_str(), new_password.as_str())
+                        .await?;
+                    Ok(ResponseType::StoragePasswordChanged)
+                })
+                .await;
+                current_password.zeroize();
+                new_password.zeroize();
+                res
+            }
             #[cfg(feature = "stronghold")]
             MessageType::SetStrongholdPassword(password) => {
                 convert_async_panics(|| async { self.set_stronghold_password(password).await }).await
@@ -152,7 +234,7 @@ impl WalletMessageHandler {
             #[cfg(feature = "stronghold")]
             MessageType::LockStronghold => {
                 convert_async_panics(|| async {
-                    crate::lock_stronghold(&self.account_manager.stronghold_snapshot_path().await?, true).await?;
+                    self.account_manager.lock_stronghold().await?;
                     Ok(ResponseType::LockedStronghold)
                 })
                 .await
@@ -162,6 +244,11 @@ impl WalletMessageHandler {
                     .generate_mnemonic()
                     .map(ResponseType::GeneratedMnemonic)
             }),
+            MessageType::GenerateMnemonicWithLanguage(language) => convert_panics(|| {
+                self.account_manager
+                    .generate_mnemonic_with_language(*language)
+                    .map(ResponseType::GeneratedMnemonic)
+            }),
             MessageType::VerifyMnemonic(mnemonic) => convert_panics(|| {
                 self.account_manager
                     .verify_mnemonic(mnemonic)
@@ -202,7 +289,8 @@ impl WalletMessageHandler {
                 .await
             }
             MessageType::SendTransfer { account_id, transfer } => {
-                convert_async_panics(|| async { self.send_transfer(account_id, transfer.clone().finish()).await }).await
+                convert_async_panics(|| async { self.send_transfer(account_id, transfer.clone().finish()?).await })
+                    .await
             }
             MessageType::InternalTransfer {
                 from_account_id,
@@ -227,6 +315,37 @@ impl WalletMessageHandler {
                 })
                 .await
             }
+            #[cfg(feature = "stronghold")]
+            MessageType::ConfigureDuress {
+                password,
+                decoy_accounts,
+            } => {
+                convert_async_panics(|| async {
+                    self.account_manager
+                        .configure_duress(password.to_string(), decoy_accounts.clone())
+                        .await?;
+                    password.zeroize();
+                    Ok(ResponseType::DuressConfigured)
+                })
+                .await
+            }
+            MessageType::CloneAccountToNetwork {
+                account_id,
+                client_options,
+            } => {
+                convert_async_panics(|| async {
+                    let account_handle = self
+                        .account_manager
+                        .clone_account_to_network(account_id.clone(), *client_options.clone())
+                        .await?;
+                    let account = account_handle.read().await;
+                    Ok(ResponseType::CreatedAccount(AccountDto::new(
+                        account.clone(),
+                        Vec::new(),
+                    )))
+                })
+                .await
+            }
             MessageType::SetClientOptions(options) => {
                 convert_async_panics(|| async {
                     self.account_manager.set_client_options(*options.clone()).await?;
@@ -423,6 +542,50 @@ impl WalletMessageHandler {
                 })
                 .await
             }
+            MessageType::PauseAll => {
+                convert_async_panics(|| async {
+                    self.account_manager.pause_all().await?;
+                    Ok(ResponseType::Ok(()))
+                })
+                .await
+            }
+            MessageType::ResumeAll => {
+                convert_async_panics(|| async {
+                    self.account_manager.resume_all().await?;
+                    Ok(ResponseType::Ok(()))
+                })
+                .await
+            }
+            MessageType::ParseDeepLink(uri) => {
+                convert_async_panics(|| async {
+                    let intent = crate::deeplink::parse_deeplink(uri)?;
+                    Ok(ResponseType::WalletIntent(intent))
+                })
+                .await
+            }
+            MessageType::OpenSession { password, ttl } => {
+                let res = convert_async_panics(|| async {
+                    let token = self.account_manager.open_session(password.to_string(), *ttl).await?;
+                    Ok(ResponseType::SessionOpened(token))
+                })
+                .await;
+                password.zeroize();
+                res
+            }
+            MessageType::Shutdown(timeout) => {
+                convert_async_panics(|| async {
+                    let transfers_finished = self.account_manager.shutdown(*timeout).await?;
+                    Ok(ResponseType::ShutDown(transfers_finished))
+                })
+                .await
+            }
+            MessageType::GetHealth => {
+                convert_async_panics(|| async { Ok(ResponseType::Health(self.account_manager.health().await)) }).await
+            }
+            MessageType::CloseSession(token) => convert_panics(|| {
+                self.account_manager.close_session(token);
+                Ok(ResponseType::SessionClosed)
+            }),
             #[cfg(feature = "participation")]
             MessageType::Participate {
                 account_identifier,
@@ -470,6 +633,55 @@ impl WalletMessageHandler {
                 })
                 .await
             }
+            #[cfg(feature = "participation")]
+            MessageType::RegisterParticipationEvents { node_urls } => {
+                convert_async_panics(|| async {
+                    let events_data = self
+                        .account_manager
+                        .register_participation_events(node_urls.clone())
+                        .await?;
+                    Ok(ResponseType::EventsData(events_data))
+                })
+                .await
+            }
+            MessageType::GetNodeDiagnostics(options) => {
+                convert_async_panics(|| async {
+                    let diagnostics = self
+                        .account_manager
+                        .node_diagnostics(options.as_ref().map(|o| *o.clone()))
+                        .await?;
+                    Ok(ResponseType::NodeDiagnostics(diagnostics))
+                })
+                .await
+            }
+            MessageType::SetUserData { key, value } => {
+                convert_async_panics(|| async {
+                    self.account_manager.user_data().set(key, &*value).await?;
+                    Ok(ResponseType::Ok(()))
+                })
+                .await
+            }
+            MessageType::GetUserData(key) => {
+                convert_async_panics(|| async {
+                    let value = self.account_manager.user_data().get(key).await?;
+                    Ok(ResponseType::UserData(value))
+                })
+                .await
+            }
+            MessageType::RemoveUserData(key) => {
+                convert_async_panics(|| async {
+                    self.account_manager.user_data().remove(key).await?;
+                    Ok(ResponseType::Ok(()))
+                })
+                .await
+            }
+            MessageType::ListUserData(prefix) => {
+                convert_async_panics(|| async {
+                    let keys = self.account_manager.user_data().list(prefix).await?;
+                    Ok(ResponseType::UserDataKeys(keys))
+                })
+                .await
+            }
         };
 
         let response = match response {
@@ -571,6 +783,8 @@ impl WalletMessageHandler {
                 address_index,
                 gap_limit,
                 skip_persistence,
+                low_memory,
+                address_range,
             } => {
                 let mut synchronizer = account_handle.sync().await;
                 if let Some(address_index) = address_index {
@@ -584,6 +798,12 @@ impl WalletMessageHandler {
                         synchronizer = synchronizer.skip_persistence();
                     }
                 }
+                if low_memory.unwrap_or_default() {
+                    synchronizer = synchronizer.low_memory();
+                }
+                if let Some(address_range) = address_range {
+                    synchronizer = synchronizer.address_range(address_range.clone());
+                }
                 let synced = synchronizer.execute().await?;
                 Ok(ResponseType::SyncedAccount(synced))
             }
@@ -606,6 +826,62 @@ impl WalletMessageHandler {
                     .await?;
                 Ok(ResponseType::NodeInfo(info))
             }
+            AccountMethod::RevalidateMessages { repair } => {
+                let report = account_handle.revalidate_messages(*repair).await?;
+                Ok(ResponseType::ValidationReport(report))
+            }
+            AccountMethod::VerifyMessage { message_id } => {
+                account_handle.verify_message(message_id).await?;
+                Ok(ResponseType::Ok(()))
+            }
+            AccountMethod::GetActivitySummary(period) => {
+                let summary = account_handle.activity_summary(period.clone()).await?;
+                Ok(ResponseType::ActivitySummary(summary))
+            }
+            AccountMethod::GetLightState => {
+                let state = account_handle.light_state().await?;
+                Ok(ResponseType::LightState(Box::new(state)))
+            }
+            AccountMethod::GetSignerCapabilities => {
+                let capabilities = account_handle.signer_capabilities().await;
+                Ok(ResponseType::SignerCapabilities(capabilities))
+            }
+            AccountMethod::GetEventsSince { sequence } => {
+                let events = account_handle.get_events_since(*sequence).await?;
+                Ok(ResponseType::AccountEvents(events))
+            }
+            AccountMethod::RepairAddressSpace => {
+                let report = account_handle.repair_address_space().await?;
+                Ok(ResponseType::AddressRepairReport(report))
+            }
+            AccountMethod::Freeze => {
+                account_handle.freeze().await?;
+                Ok(ResponseType::Ok(()))
+            }
+            AccountMethod::Unfreeze => {
+                account_handle.unfreeze().await?;
+                Ok(ResponseType::Ok(()))
+            }
+            AccountMethod::EstimateTransaction { transfer } => {
+                let estimate = account_handle.estimate_transaction(&transfer.clone().finish()?).await?;
+                Ok(ResponseType::TransactionEstimate(estimate))
+            }
+            AccountMethod::CanSend { amount, output_count } => {
+                let check = account_handle.can_send(*amount, *output_count).await?;
+                Ok(ResponseType::SendCheck(check))
+            }
+            AccountMethod::ReconcileWithNode { repair } => {
+                let report = account_handle.reconcile_with_node(*repair).await?;
+                Ok(ResponseType::ReconciliationReport(report))
+            }
+            AccountMethod::GetTransactionStatus { message_id, check_node } => {
+                let status = account_handle.transaction_status(message_id, *check_node).await?;
+                Ok(ResponseType::TransactionStatus(status))
+            }
+            AccountMethod::ShouldRetry(message_id) => {
+                let advice = account_handle.should_retry(message_id).await?;
+                Ok(ResponseType::RetryAdvice(advice))
+            }
         }
     }
 
@@ -636,6 +912,9 @@ impl WalletMessageHandler {
         if account.allow_create_multiple_empty_accounts {
             builder = builder.allow_create_multiple_empty_accounts();
         }
+        if account.cold_storage {
+            builder = builder.cold_storage();
+        }
 
         match builder.initialise().await {
             Ok(account_handle) => {
@@ -785,8 +1064,8 @@ mod tests {
 
     #[tokio::test]
     async fn create_and_remove_account() {
-        crate::test_utils::with_account_manager(
-            crate::test_utils::TestType::SigningAndStorage,
+        crate::testing::with_account_manager(
+            crate::testing::TestType::SigningAndStorage,
             |manager, signer_type| async move {
                 let tx = spawn_actor(manager);
 
@@ -802,6 +1081,7 @@ mod tests {
                     skip_persistence: false,
                     signer_type: Some(signer_type.clone()),
                     allow_create_multiple_empty_accounts: false,
+                    cold_storage: false,
                 };
                 #[cfg(feature = "stronghold")]
                 send_message(&tx, MessageType::SetStrongholdPassword("password".to_string())).await;
@@ -835,7 +1115,7 @@ mod tests {
 
     #[tokio::test]
     async fn legacy_address_checksum() {
-        crate::test_utils::with_account_manager(crate::test_utils::TestType::Signing, |manager, _| async move {
+        crate::testing::with_account_manager(crate::testing::TestType::Signing, |manager, _| async move {
             let tx = spawn_actor(manager);
             let response = send_message(
                 &tx,
@@ -0,0 +1,171 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{AccountMethod, MessageType};
+
+use serde::{Deserialize, Serialize};
+
+/// The permissions of an auth token registered on the
+/// [WalletMessageHandler](struct.WalletMessageHandler.html#method.with_auth_token).
+/// The scopes are ordered, each one allowing the methods of the previous ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum TokenScope {
+    /// Reads the accounts, balances and messages, and syncs them with the node.
+    ReadOnly,
+    /// Also generates receive addresses, e.g. for a payment page.
+    ReceiveAddressOnly,
+    /// Every method, including transfers and the wallet configuration.
+    Full,
+}
+
+impl TokenScope {
+    /// Whether the message can be dispatched with this scope.
+    pub fn allows(&self, message_type: &MessageType) -> bool {
+        *self >= required_scope(message_type)
+    }
+}
+
+/// The scope needed to dispatch the message; methods not listed here need the full scope.
+pub(crate) fn required_scope(message_type: &MessageType) -> TokenScope {
+    match message_type {
+        MessageType::CallAccountMethod { method, .. } => required_account_method_scope(method),
+        MessageType::GetAccount(_)
+        | MessageType::GetAccounts
+        | MessageType::SyncAccounts { .. }
+        | MessageType::IsLatestAddressUnused
+        | MessageType::GetNodeDiagnostics(_)
+        | MessageType::GetHealth
+        | MessageType::GetUserData(_)
+        | MessageType::ListUserData(_)
+        | MessageType::GetLegacyAddressChecksum(_)
+        | MessageType::ParseDeepLink(_) => TokenScope::ReadOnly,
+        #[cfg(feature = "stronghold")]
+        MessageType::GetStrongholdStatus => TokenScope::ReadOnly,
+        #[cfg(any(feature = "ledger-nano", feature = "ledger-nano-simulator"))]
+        MessageType::GetLedgerStatus(_) => TokenScope::ReadOnly,
+        #[cfg(feature = "participation")]
+        MessageType::GetParticipationOverview { .. } | MessageType::GetParticipationEvents => TokenScope::ReadOnly,
+        _ => TokenScope::Full,
+    }
+}
+
+/// Whether the message moves funds, exports or changes the wallet secrets, or changes where the funds are sent,
+/// needing an open session when the message handler
+/// [requires one](struct.WalletMessageHandler.html#method.with_session_required).
+/// `SetStoragePassword` isn't gated: the session is opened with the storage password, and on an encrypted storage
+/// `SetStoragePassword` only unlocks it with that same password, changing it requires `ChangeStoragePassword`.
+pub(crate) fn requires_session(message_type: &MessageType) -> bool {
+    match message_type {
+        MessageType::CallAccountMethod { method, .. } => {
+            matches!(method, AccountMethod::SetClientOptions(_) | AccountMethod::Unfreeze)
+        }
+        MessageType::SendTransfer { .. }
+        | MessageType::InternalTransfer { .. }
+        | MessageType::Backup { .. }
+        | MessageType::RestoreBackup { .. }
+        | MessageType::ClearStoragePassword
+        | MessageType::ChangeStoragePassword { .. }
+        | MessageType::StoreMnemonic { .. }
+        | MessageType::DeleteStorage
+        | MessageType::SetClientOptions(_)
+        | MessageType::CloneAccountToNetwork { .. }
+        | MessageType::SendMigrationBundle { .. }
+        | MessageType::SendLedgerMigrationBundle { .. } => true,
+        #[cfg(feature = "stronghold")]
+        MessageType::ChangeStrongholdPassword { .. } | MessageType::ConfigureDuress { .. } => true,
+        #[cfg(feature = "participation")]
+        MessageType::Participate { .. } | MessageType::StopParticipating { .. } => true,
+        _ => false,
+    }
+}
+
+fn required_account_method_scope(method: &AccountMethod) -> TokenScope {
+    match method {
+        AccountMethod::GenerateAddress | AccountMethod::GetUnusedAddress => TokenScope::ReceiveAddressOnly,
+        AccountMethod::ListMessages { .. }
+        | AccountMethod::ListAddresses
+        | AccountMethod::ListSpentAddresses
+        | AccountMethod::ListUnspentAddresses
+        | AccountMethod::GetBalance
+        | AccountMethod::GetLatestAddress
+        | AccountMethod::SyncAccount { .. }
+        | AccountMethod::IsLatestAddressUnused
+        | AccountMethod::GetNodeInfo(..)
+        | AccountMethod::GetActivitySummary(_)
+        | AccountMethod::GetLightState
+        | AccountMethod::GetSignerCapabilities
+        | AccountMethod::GetEventsSince { .. }
+        | AccountMethod::EstimateTransaction { .. }
+        | AccountMethod::CanSend { .. }
+        | AccountMethod::VerifyMessage { .. }
+        | AccountMethod::GetTransactionStatus { .. }
+        | AccountMethod::ShouldRetry(_) => TokenScope::ReadOnly,
+        AccountMethod::RevalidateMessages { repair } | AccountMethod::ReconcileWithNode { repair } => {
+            if *repair {
+                TokenScope::Full
+            } else {
+                TokenScope::ReadOnly
+            }
+        }
+        AccountMethod::SetAlias(_)
+        | AccountMethod::SetClientOptions(_)
+        | AccountMethod::RepairAddressSpace
+        | AccountMethod::Freeze
+        | AccountMethod::Unfreeze => TokenScope::Full,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TokenScope;
+    use crate::actor::{AccountMethod, MessageType};
+
+    #[test]
+    fn token_scopes() {
+        let account_method = |method| MessageType::CallAccountMethod {
+            account_id: 0usize.into(),
+            method,
+        };
+        let get_balance = account_method(AccountMethod::GetBalance);
+        let generate_address = account_method(AccountMethod::GenerateAddress);
+        let freeze = account_method(AccountMethod::Freeze);
+        let transfer = MessageType::InternalTransfer {
+            from_account_id: 0usize.into(),
+            to_account_id: 1usize.into(),
+            amount: std::num::NonZeroU64::new(1).unwrap(),
+        };
+
+        assert!(TokenScope::ReadOnly.allows(&get_balance));
+        assert!(!TokenScope::ReadOnly.allows(&generate_address));
+        assert!(!TokenScope::ReadOnly.allows(&MessageType::DeleteStorage));
+        assert!(!TokenScope::ReadOnly.allows(&account_method(AccountMethod::ReconcileWithNode { repair: true })));
+        assert!(TokenScope::ReadOnly.allows(&account_method(AccountMethod::ReconcileWithNode { repair: false })));
+
+        assert!(TokenScope::ReceiveAddressOnly.allows(&get_balance));
+        assert!(TokenScope::ReceiveAddressOnly.allows(&generate_address));
+        assert!(!TokenScope::ReceiveAddressOnly.allows(&freeze));
+        assert!(!TokenScope::ReceiveAddressOnly.allows(&transfer));
+
+        assert!(TokenScope::Full.allows(&freeze));
+        assert!(TokenScope::Full.allows(&transfer));
+
+        assert!(super::requires_session(&transfer));
+        assert!(!super::requires_session(&freeze));
+        assert!(super::requires_session(&account_method(AccountMethod::Unfreeze)));
+        assert!(super::requires_session(&MessageType::DeleteStorage));
+        assert!(super::requires_session(&MessageType::ChangeStoragePassword {
+            current_password: "password".to_string(),
+            new_password: "new-password".to_string(),
+        }));
+        assert!(super::requires_session(&MessageType::CloneAccountToNetwork {
+            account_id: 0usize.into(),
+            client_options: Box::new(crate::client::ClientOptionsBuilder::new().build().unwrap()),
+        }));
+        #[cfg(feature = "stronghold")]
+        assert!(super::requires_session(&MessageType::ConfigureDuress {
+            password: "duress-password".to_string(),
+            decoy_accounts: Vec::new(),
+        }));
+        assert!(!super::requires_session(&get_balance));
+    }
+}
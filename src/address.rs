@@ -6,6 +6,7 @@ use crate::{
     message::{Message, MessagePayload, TransactionEssence, TransactionInput},
     signing::GenerateAddressMetadata,
 };
+use chrono::{DateTime, TimeZone, Utc};
 use getset::{Getters, Setters};
 pub use iota_client::bee_message::prelude::{Address as IotaAddress, Ed25519Address, Input, UtxoInput};
 use iota_client::{
@@ -348,6 +349,18 @@ impl Address {
         &mut self.outputs
     }
 
+    /// Encodes the address as a [payment URI](struct.PaymentRequest.html) requesting the given amount,
+    /// to be shown as a QR code. The amount can't be zero and the tag must be 1 to 64 bytes long.
+    pub fn to_payment_uri(&self, amount: Option<u64>, tag: Option<&str>) -> crate::Result<String> {
+        PaymentRequest {
+            address: self.address.clone(),
+            amount,
+            tag: tag.map(|tag| tag.to_string()),
+            expiry: None,
+        }
+        .to_uri()
+    }
+
     /// Updates the Bech32 human readable part.
     #[doc(hidden)]
     pub fn set_bech32_hrp(&mut self, hrp: String) {
@@ -358,15 +371,174 @@ impl Address {
     }
 }
 
+/// Whether the bech32 checksum (BIP-173) of the address string is valid.
+/// Returns `None` if the string isn't bech32 shaped at all.
+fn has_valid_bech32_checksum(address: &str) -> Option<bool> {
+    const CHARSET: &str = "qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+    const GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+
+    if address.to_lowercase() != address && address.to_uppercase() != address {
+        return None;
+    }
+    let address = address.to_lowercase();
+    let separator = address.rfind('1')?;
+    let (hrp, data) = (&address[..separator], &address[separator + 1..]);
+    if hrp.is_empty() || data.len() < 6 || !hrp.bytes().all(|b| (33..=126).contains(&b)) {
+        return None;
+    }
+    let mut values: Vec<u8> = hrp.bytes().map(|b| b >> 5).collect();
+    values.push(0);
+    values.extend(hrp.bytes().map(|b| b & 31));
+    for c in data.chars() {
+        values.push(CHARSET.find(c)? as u8);
+    }
+    let mut checksum: u32 = 1;
+    for value in values {
+        let top = checksum >> 25;
+        checksum = ((checksum & 0x1ffffff) << 5) ^ value as u32;
+        for (i, generator) in GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                checksum ^= generator;
+            }
+        }
+    }
+    Some(checksum == 1)
+}
+
 /// Parses a bech32 address string.
+/// Fails with [InvalidAddressChecksum](../enum.Error.html#variant.InvalidAddressChecksum) if the address is well
+/// formed but mistyped.
 pub fn parse<A: AsRef<str>>(address: A) -> crate::Result<AddressWrapper> {
     let address = address.as_ref();
+    if has_valid_bech32_checksum(address) == Some(false) {
+        return Err(crate::Error::InvalidAddressChecksum(address.to_string()));
+    }
     let mut tokens = address.split('1');
     let hrp = tokens.next().ok_or(crate::Error::InvalidAddress)?;
     let address = iota_client::bee_message::address::Address::try_from_bech32(address)?;
     Ok(AddressWrapper::new(address, hrp.to_string()))
 }
 
+/// The scheme of the payment URIs.
+const PAYMENT_URI_SCHEME: &str = "iota";
+/// Maximum length of a payment tag, the length of an indexation index.
+const PAYMENT_TAG_MAX_LENGTH: usize = 64;
+
+/// A payment request, encoded as `iota:<bech32 address>?amount=<amount>&tag=<tag>&expiry=<unix timestamp>`.
+/// The query parameters are optional and percent-encoded; the amount is in IOTA and the tag is the index to attach
+/// to the transfer, so the receiver can match it to an invoice. Unknown parameters are ignored.
+#[derive(Debug, Clone, PartialEq, Eq, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct PaymentRequest {
+    /// The address to pay.
+    #[serde(with = "crate::serde::iota_address_serde")]
+    address: AddressWrapper,
+    /// The requested amount.
+    amount: Option<u64>,
+    /// The indexation index the transfer should carry.
+    tag: Option<String>,
+    /// The time the request expires at.
+    expiry: Option<DateTime<Utc>>,
+}
+
+impl PaymentRequest {
+    /// Creates a payment request to the given address.
+    pub fn new(address: AddressWrapper) -> Self {
+        Self {
+            address,
+            amount: None,
+            tag: None,
+            expiry: None,
+        }
+    }
+
+    /// Sets the requested amount.
+    pub fn with_amount(mut self, amount: u64) -> Self {
+        self.amount.replace(amount);
+        self
+    }
+
+    /// Sets the indexation index the transfer should carry.
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.tag.replace(tag.into());
+        self
+    }
+
+    /// Sets the time the request expires at.
+    pub fn with_expiry(mut self, expiry: DateTime<Utc>) -> Self {
+        self.expiry.replace(expiry);
+        self
+    }
+
+    /// Checks that the amount isn't zero and the tag length is within the indexation index bounds,
+    /// so the request can be parsed back with [parse_payment_uri](fn.parse_payment_uri.html).
+    fn validate(&self) -> crate::Result<()> {
+        let invalid = |reason: &str| crate::Error::InvalidPaymentUri(reason.to_string());
+        if self.amount == Some(0) {
+            return Err(invalid("invalid amount"));
+        }
+        if let Some(tag) = &self.tag {
+            if !(1..=PAYMENT_TAG_MAX_LENGTH).contains(&tag.len()) {
+                return Err(invalid("invalid tag length"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Encodes the payment request as a URI.
+    pub fn to_uri(&self) -> crate::Result<String> {
+        self.validate()?;
+        let mut uri = url::Url::parse(&format!("{}:{}", PAYMENT_URI_SCHEME, self.address.to_bech32()))
+            .expect("bech32 addresses are valid URI paths");
+        if self.amount.is_some() || self.tag.is_some() || self.expiry.is_some() {
+            let mut query = uri.query_pairs_mut();
+            if let Some(amount) = self.amount {
+                query.append_pair("amount", &amount.to_string());
+            }
+            if let Some(tag) = &self.tag {
+                query.append_pair("tag", tag);
+            }
+            if let Some(expiry) = self.expiry {
+                query.append_pair("expiry", &expiry.timestamp().to_string());
+            }
+        }
+        Ok(uri.to_string())
+    }
+}
+
+/// Parses and validates a [payment URI](struct.PaymentRequest.html).
+/// Expired requests are rejected with [Error::PaymentRequestExpired](../enum.Error.html#variant.PaymentRequestExpired).
+pub fn parse_payment_uri<U: AsRef<str>>(uri: U) -> crate::Result<PaymentRequest> {
+    let invalid = |reason: &str| crate::Error::InvalidPaymentUri(reason.to_string());
+    let uri = url::Url::parse(uri.as_ref()).map_err(|e| invalid(&e.to_string()))?;
+    if uri.scheme() != PAYMENT_URI_SCHEME {
+        return Err(invalid("unknown scheme"));
+    }
+    let mut request = PaymentRequest::new(parse(uri.path()).map_err(|_| invalid("invalid address"))?);
+    for (key, value) in uri.query_pairs() {
+        match key.as_ref() {
+            "amount" => {
+                request.amount = Some(value.parse::<u64>().map_err(|_| invalid("invalid amount"))?);
+            }
+            "tag" => request.tag = Some(value.into_owned()),
+            "expiry" => {
+                let expiry = value
+                    .parse::<i64>()
+                    .ok()
+                    .and_then(|timestamp| Utc.timestamp_opt(timestamp, 0).single())
+                    .ok_or_else(|| invalid("invalid expiry"))?;
+                request.expiry = Some(expiry);
+            }
+            _ => {}
+        }
+    }
+    request.validate()?;
+    if matches!(request.expiry, Some(expiry) if expiry <= Utc::now()) {
+        return Err(crate::Error::PaymentRequestExpired);
+    }
+    Ok(request)
+}
+
 pub(crate) async fn get_iota_address(
     account: &Account,
     address_index: usize,
@@ -374,34 +546,44 @@ pub(crate) async fn get_iota_address(
     bech32_hrp: String,
     metadata: GenerateAddressMetadata,
 ) -> crate::Result<AddressWrapper> {
+    // the addresses shown on a device for verification are always derived by the signer
+    if metadata.syncing {
+        if let Some(address) = account.pre_derived_address(address_index, internal) {
+            return Ok(AddressWrapper::new(*address.as_ref(), bech32_hrp));
+        }
+    }
     let signer = crate::signing::get_signer(account.signer_type()).await;
     let mut signer = signer.lock().await;
+    let max_derivation_index = signer.capabilities().max_derivation_index;
+    if address_index > max_derivation_index {
+        return Err(crate::Error::AddressIndexLimitExceeded(
+            address_index,
+            max_derivation_index,
+        ));
+    }
     let address = signer
         .generate_address(account, address_index, internal, metadata)
         .await?;
     Ok(AddressWrapper::new(address, bech32_hrp))
 }
 
-/// Gets an unused public address for the given account.
-pub(crate) async fn get_new_address(account: &Account, metadata: GenerateAddressMetadata) -> crate::Result<Address> {
-    let latest_public_address_index = account
+/// Gets the key index of the next public address of the account.
+pub(crate) fn next_public_address_index(account: &Account) -> usize {
+    account
         .addresses()
         .iter()
         .filter_map(|a| if !a.internal() { Some(a.key_index()) } else { None })
         .max()
-        .unwrap_or(&0);
-    let new_address_key_index = latest_public_address_index + 1;
+        .unwrap_or(&0)
+        + 1
+}
+
+/// Gets an unused public address for the given account.
+pub(crate) async fn get_new_address(account: &Account, metadata: GenerateAddressMetadata) -> crate::Result<Address> {
+    let new_address_key_index = next_public_address_index(account);
     let bech32_hrp = match account.addresses().first() {
         Some(address) => address.address().bech32_hrp().to_string(),
-        None => {
-            crate::client::get_client(account.client_options())
-                .await?
-                .read()
-                .await
-                .get_network_info()
-                .await?
-                .bech32_hrp
-        }
+        None => crate::client::bech32_hrp(account.client_options()).await?,
     };
     let iota_address = get_iota_address(account, new_address_key_index, false, bech32_hrp, metadata).await?;
     log::debug!(
@@ -464,11 +646,11 @@ mod tests {
 
     #[tokio::test]
     async fn is_unspent_false() {
-        let manager = crate::test_utils::get_account_manager().await;
-        let account_handle = crate::test_utils::AccountCreator::new(&manager).create().await;
+        let manager = crate::testing::get_account_manager().await;
+        let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
         let account_address = account_handle.generate_address().await.unwrap();
-        let address = crate::test_utils::generate_random_address();
-        let spent_tx = crate::test_utils::GenerateMessageBuilder::default()
+        let address = crate::testing::generate_random_address();
+        let spent_tx = crate::testing::GenerateMessageBuilder::default()
             .address(address.clone())
             .input_address(Some(account_address.address().clone()))
             .account_addresses(account_handle.addresses().await)
@@ -494,9 +676,9 @@ mod tests {
 
     #[tokio::test]
     async fn is_unspent_true() {
-        let manager = crate::test_utils::get_account_manager().await;
-        let account_handle = crate::test_utils::AccountCreator::new(&manager).create().await;
-        let address = crate::test_utils::generate_random_iota_address();
+        let manager = crate::testing::get_account_manager().await;
+        let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
+        let address = crate::testing::generate_random_iota_address();
 
         let response = super::is_unspent(
             &account_handle
@@ -507,4 +689,33 @@ mod tests {
         );
         assert!(response);
     }
+
+    #[test]
+    fn payment_uri() {
+        let address = crate::testing::generate_random_address();
+        let uri = address.to_payment_uri(Some(1_000_000), Some("invoice #42")).unwrap();
+        let request = super::parse_payment_uri(&uri).unwrap();
+        assert_eq!(request.address(), address.address());
+        assert_eq!(*request.amount(), Some(1_000_000));
+        assert_eq!(request.tag().as_deref(), Some("invoice #42"));
+
+        let expired = super::PaymentRequest::new(address.address().clone())
+            .with_expiry(chrono::Utc::now() - chrono::Duration::minutes(1))
+            .to_uri()
+            .unwrap();
+        assert!(matches!(
+            super::parse_payment_uri(&expired),
+            Err(crate::Error::PaymentRequestExpired)
+        ));
+        assert!(matches!(
+            super::parse_payment_uri(uri.replace("iota:", "bitcoin:")),
+            Err(crate::Error::InvalidPaymentUri(_))
+        ));
+
+        // the requests parsing rejects aren't encoded
+        assert!(address.to_payment_uri(Some(0), None).is_err());
+        assert!(address.to_payment_uri(None, Some("")).is_err());
+        assert!(address.to_payment_uri(None, Some(&"a".repeat(65))).is_err());
+        assert!(address.to_payment_uri(None, Some(&"a".repeat(64))).is_ok());
+    }
 }
@@ -0,0 +1,200 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::message::ValueUnit;
+
+use serde::{Deserialize, Serialize};
+
+/// The number formatting conventions used to render and parse amounts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AmountLocale {
+    /// The separator between the integer and the fractional digits.
+    #[serde(rename = "decimalSeparator")]
+    pub decimal_separator: char,
+    /// The separator between the thousands groups of the integer digits, if any.
+    #[serde(rename = "groupSeparator")]
+    pub group_separator: Option<char>,
+}
+
+impl Default for AmountLocale {
+    fn default() -> Self {
+        Self::ENGLISH
+    }
+}
+
+impl AmountLocale {
+    /// `1,234.56`
+    pub const ENGLISH: Self = Self {
+        decimal_separator: '.',
+        group_separator: Some(','),
+    };
+    /// `1.234,56`
+    pub const GERMAN: Self = Self {
+        decimal_separator: ',',
+        group_separator: Some('.'),
+    };
+    /// `1 234,56`, grouped with a no-break space.
+    pub const FRENCH: Self = Self {
+        decimal_separator: ',',
+        group_separator: Some('\u{a0}'),
+    };
+    /// `1'234.56`
+    pub const SWISS: Self = Self {
+        decimal_separator: '.',
+        group_separator: Some('\''),
+    };
+
+    /// Gets the conventions of a language tag, e.g. `en-US`, `de` or `fr-CH`.
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let tag = tag.replace('_', "-").to_lowercase();
+        let mut parts = tag.split('-');
+        let language = parts.next().unwrap_or_default();
+        let region = parts.next();
+        match (language, region) {
+            ("de" | "it" | "fr" | "rm", Some("ch")) => Some(Self::SWISS),
+            ("en" | "ja" | "zh" | "ko", _) => Some(Self::ENGLISH),
+            ("de" | "it" | "es" | "nl" | "pt" | "id" | "tr", _) => Some(Self::GERMAN),
+            ("fr" | "pl" | "cs" | "sk" | "sv" | "fi" | "nb" | "ru" | "uk", _) => Some(Self::FRENCH),
+            _ => None,
+        }
+    }
+}
+
+fn unit_decimals(unit: &ValueUnit) -> usize {
+    unit.multiplier().to_string().len() - 1
+}
+
+fn parse_unit(unit: &str) -> Option<ValueUnit> {
+    match unit {
+        "i" => Some(ValueUnit::I),
+        "Ki" => Some(ValueUnit::Ki),
+        "Mi" => Some(ValueUnit::Mi),
+        "Gi" => Some(ValueUnit::Gi),
+        "Ti" => Some(ValueUnit::Ti),
+        "Pi" => Some(ValueUnit::Pi),
+        _ => None,
+    }
+}
+
+/// Formats an amount of base units (`i`) in the given unit, e.g. `1,234.5 Mi`.
+/// The trailing zeros of the fractional part are dropped, so no precision is lost.
+pub fn format_amount(amount: u64, unit: ValueUnit, locale: &AmountLocale) -> String {
+    let multiplier = unit.multiplier();
+    let integer = (amount / multiplier).to_string();
+    let fraction = amount % multiplier;
+
+    let mut formatted = String::new();
+    for (index, digit) in integer.chars().enumerate() {
+        if index > 0 && (integer.len() - index) % 3 == 0 {
+            if let Some(separator) = locale.group_separator {
+                formatted.push(separator);
+            }
+        }
+        formatted.push(digit);
+    }
+    if fraction > 0 {
+        let fraction = format!("{:0width$}", fraction, width = unit_decimals(&unit));
+        formatted.push(locale.decimal_separator);
+        formatted.push_str(fraction.trim_end_matches('0'));
+    }
+    format!("{} {}", formatted, unit)
+}
+
+/// Parses a user entered amount back to base units (`i`), e.g. `1.234,5 Mi` with the German conventions.
+/// The unit suffix is optional and defaults to `default_unit`.
+/// Fails with [InvalidAmount](enum.Error.html#variant.InvalidAmount) if the input is malformed, is more precise than
+/// `1i` or overflows.
+pub fn parse_amount(input: &str, default_unit: ValueUnit, locale: &AmountLocale) -> crate::Result<u64> {
+    let invalid = || crate::Error::InvalidAmount(input.to_string());
+    let input = input.trim();
+    let (number, unit) = match input.find(|c: char| c.is_alphabetic()) {
+        Some(position) => (
+            input[..position].trim_end(),
+            parse_unit(&input[position..]).ok_or_else(invalid)?,
+        ),
+        None => (input, default_unit),
+    };
+
+    let (integer, fraction) = match number.split_once(locale.decimal_separator) {
+        Some((integer, fraction)) => (integer, fraction),
+        None => (number, ""),
+    };
+    let integer: String = integer
+        .chars()
+        .filter(|c| Some(*c) != locale.group_separator && !(c.is_whitespace() && locale.group_separator.is_some()))
+        .collect();
+    if integer.is_empty() && fraction.is_empty() {
+        return Err(invalid());
+    }
+    if !integer.chars().all(|c| c.is_ascii_digit()) || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return Err(invalid());
+    }
+
+    let decimals = unit_decimals(&unit);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.len() > decimals {
+        return Err(invalid());
+    }
+    let integer: u64 = if integer.is_empty() {
+        0
+    } else {
+        integer.parse().map_err(|_| invalid())?
+    };
+    let fraction: u64 = if fraction.is_empty() {
+        0
+    } else {
+        format!("{:0<width$}", fraction, width = decimals)
+            .parse()
+            .map_err(|_| invalid())?
+    };
+    integer
+        .checked_mul(unit.multiplier())
+        .and_then(|amount| amount.checked_add(fraction))
+        .ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_amount, parse_amount, AmountLocale};
+    use crate::message::ValueUnit;
+
+    #[test]
+    fn amount_formatting() {
+        assert_eq!(
+            format_amount(1_234_500_000, ValueUnit::Mi, &AmountLocale::ENGLISH),
+            "1,234.5 Mi"
+        );
+        assert_eq!(
+            format_amount(1_234_500_000, ValueUnit::Mi, &AmountLocale::GERMAN),
+            "1.234,5 Mi"
+        );
+        assert_eq!(
+            format_amount(1_000_000, ValueUnit::Gi, &AmountLocale::SWISS),
+            "0.001 Gi"
+        );
+        assert_eq!(format_amount(999, ValueUnit::I, &AmountLocale::ENGLISH), "999 i");
+        assert_eq!(AmountLocale::from_tag("de_CH"), Some(AmountLocale::SWISS));
+
+        assert_eq!(
+            parse_amount("1.234,5 Mi", ValueUnit::I, &AmountLocale::GERMAN).unwrap(),
+            1_234_500_000
+        );
+        assert_eq!(
+            parse_amount("1,234.5", ValueUnit::Mi, &AmountLocale::ENGLISH).unwrap(),
+            1_234_500_000
+        );
+        assert_eq!(
+            parse_amount("2Gi", ValueUnit::I, &AmountLocale::ENGLISH).unwrap(),
+            2_000_000_000
+        );
+        // more precise than 1i
+        assert!(parse_amount("0.0000001 Mi", ValueUnit::I, &AmountLocale::ENGLISH).is_err());
+        assert!(parse_amount("20000 Pi", ValueUnit::I, &AmountLocale::ENGLISH).is_err());
+        assert!(parse_amount("1.5 Xi", ValueUnit::I, &AmountLocale::ENGLISH).is_err());
+        assert_eq!(
+            parse_amount("1 234,5", ValueUnit::Ki, &AmountLocale::FRENCH).unwrap(),
+            1_234_500
+        );
+        assert!(parse_amount("", ValueUnit::I, &AmountLocale::ENGLISH).is_err());
+    }
+}
@@ -0,0 +1,259 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! Generators for synthetic large accounts and entry points into the wallet internals, used by the criterion
+//! benchmarks in `benches/`. Nothing here talks to a node: the generated messages only spend outputs of the generated
+//! addresses, so their inputs are resolved locally.
+
+use crate::{
+    account::{AccountHandle, AccountSynchronizer},
+    account_manager::{AccountManager, AccountOptions, AccountStore},
+    address::{Address, AddressOutput, AddressWrapper, OutputKind},
+    client::{ClientOptions, ClientOptionsBuilder},
+    message::{Message, MessagePayload, TransactionBuilderMetadata},
+    signing::SignerType,
+};
+
+use iota_client::bee_message::prelude::{
+    Address as IotaAddress, Ed25519Address, Ed25519Signature, Essence, MessageId, OutputId, Payload, RegularEssence,
+    SignatureLockedSingleOutput, SignatureUnlock, TransactionId, TransactionPayloadBuilder, UnlockBlock, UnlockBlocks,
+    UtxoInput,
+};
+
+use std::{collections::HashMap, path::Path};
+
+/// The bech32 human readable part of the generated addresses.
+const BENCH_BECH32_HRP: &str = "atoi";
+
+/// The signer type of the benchmark accounts, registered by [account_manager](fn.account_manager.html).
+pub fn signer_type() -> SignerType {
+    SignerType::Custom("bench".to_string())
+}
+
+/// Signer generating random addresses, so no mnemonic is needed. It can't sign.
+#[derive(Default)]
+struct BenchSigner;
+
+#[async_trait::async_trait]
+impl crate::signing::Signer for BenchSigner {
+    async fn get_ledger_status(&self, _is_simulator: bool) -> crate::LedgerStatus {
+        crate::LedgerStatus {
+            connected: false,
+            locked: false,
+            app: None,
+        }
+    }
+
+    async fn store_mnemonic(&mut self, _: &Path, _mnemonic: String) -> crate::Result<()> {
+        Ok(())
+    }
+
+    async fn generate_address(
+        &mut self,
+        _account: &crate::account::Account,
+        _address_index: usize,
+        _internal: bool,
+        _metadata: crate::signing::GenerateAddressMetadata,
+    ) -> crate::Result<IotaAddress> {
+        Ok(IotaAddress::Ed25519(Ed25519Address::new(rand::random::<[u8; 32]>())))
+    }
+
+    async fn sign_message<'a>(
+        &mut self,
+        _account: &crate::account::Account,
+        _essence: &Essence,
+        _inputs: &mut Vec<crate::signing::TransactionInput>,
+        _metadata: crate::signing::SignMessageMetadata<'a>,
+    ) -> crate::Result<Vec<UnlockBlock>> {
+        Ok(Vec::new())
+    }
+}
+
+fn client_options() -> ClientOptions {
+    ClientOptionsBuilder::new()
+        .with_node("http://localhost:14265")
+        .expect("invalid node URL")
+        .build()
+        .expect("invalid client options")
+}
+
+/// Creates an account manager storing its data in `storage_path`, with polling disabled and the benchmark signer
+/// registered.
+pub async fn account_manager<P: AsRef<Path>>(storage_path: P) -> crate::Result<AccountManager> {
+    let manager = AccountManager::builder()
+        .with_storage(storage_path.as_ref(), None)?
+        .with_skip_polling()
+        .finish()
+        .await?;
+    crate::signing::set_signer(signer_type(), BenchSigner::default()).await;
+    manager.store_mnemonic(signer_type(), None).await?;
+    Ok(manager)
+}
+
+/// Generates `address_count` public addresses with `outputs_per_address` outputs each, e.g. 1000 addresses with 100
+/// outputs for a 100k outputs account. Every fourth output is spent.
+pub fn generate_addresses(address_count: usize, outputs_per_address: usize) -> Vec<Address> {
+    (0..address_count)
+        .map(|key_index| {
+            let address = AddressWrapper::new(
+                IotaAddress::Ed25519(Ed25519Address::new(rand::random::<[u8; 32]>())),
+                BENCH_BECH32_HRP.to_string(),
+            );
+            let outputs = (0..outputs_per_address)
+                .map(|index| {
+                    let output = AddressOutput {
+                        transaction_id: TransactionId::new(rand::random::<[u8; 32]>()),
+                        message_id: MessageId::new(rand::random::<[u8; 32]>()),
+                        index: 0,
+                        amount: 1_000_000 + (index as u64 % 100) * 10_000,
+                        is_spent: index % 4 == 3,
+                        address: address.clone(),
+                        kind: OutputKind::SignatureLockedSingle,
+                    };
+                    (output.id().expect("valid output index"), output)
+                })
+                .collect();
+            Address {
+                address,
+                key_index,
+                internal: false,
+                outputs,
+            }
+        })
+        .collect()
+}
+
+/// Builds a transaction payload spending the `input` output to `address`.
+pub fn transaction_payload(input: &AddressOutput, address: &AddressWrapper) -> Payload {
+    let essence = RegularEssence::builder()
+        .add_output(
+            SignatureLockedSingleOutput::new(*address.as_ref(), input.amount)
+                .expect("valid output amount")
+                .into(),
+        )
+        .add_input(
+            UtxoInput::new(input.transaction_id, input.index)
+                .expect("valid input index")
+                .into(),
+        )
+        .finish()
+        .expect("valid essence");
+    Payload::Transaction(Box::new(
+        TransactionPayloadBuilder::new()
+            .with_essence(Essence::Regular(essence))
+            .with_unlock_blocks(
+                UnlockBlocks::new(vec![UnlockBlock::Signature(SignatureUnlock::Ed25519(
+                    Ed25519Signature::new([0; 32], [0; 64]),
+                ))])
+                .expect("valid unlock blocks"),
+            )
+            .finish()
+            .expect("valid transaction payload"),
+    ))
+}
+
+/// Parses a message payload the way the sync does, resolving the inputs against the account addresses.
+pub async fn parse_payload(payload: Payload, addresses: &[Address]) -> crate::Result<MessagePayload> {
+    MessagePayload::new(
+        payload,
+        &TransactionBuilderMetadata {
+            id: &MessageId::new([0; 32]),
+            bech32_hrp: BENCH_BECH32_HRP.to_string(),
+            accounts: AccountStore::new(Default::default()),
+            account_id: "",
+            account_addresses: addresses,
+            client_options: &client_options(),
+        },
+    )
+    .await
+}
+
+/// Generates `count` confirmed transaction messages spending the outputs of the addresses.
+pub async fn generate_messages(count: usize, addresses: &[Address]) -> crate::Result<Vec<Message>> {
+    let outputs: Vec<&AddressOutput> = addresses
+        .iter()
+        .flat_map(|address| address.outputs().values())
+        .collect();
+    if outputs.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut messages = Vec::with_capacity(count);
+    for index in 0..count {
+        let input = outputs[index % outputs.len()];
+        let address = addresses[(index + 1) % addresses.len()].address();
+        let payload = parse_payload(transaction_payload(input, address), addresses).await?;
+        messages.push(Message {
+            id: MessageId::new(rand::random::<[u8; 32]>()),
+            version: 1,
+            parents: vec![MessageId::new([0; 32])],
+            payload_length: 0,
+            payload: Some(payload),
+            timestamp: chrono::Utc::now(),
+            nonce: 0,
+            confirmed: Some(true),
+            broadcasted: true,
+            reattachment_message_id: None,
+            reattachment_of: None,
+            reattachments: Vec::new(),
+            funding_sources: Vec::new(),
+            custom_payload: None,
+            transfer_timings: None,
+            transfer_context: None,
+            raw: None,
+        });
+    }
+    Ok(messages)
+}
+
+/// Creates an account holding the given addresses and messages.
+pub async fn create_account(
+    manager: &AccountManager,
+    addresses: Vec<Address>,
+    messages: Vec<Message>,
+) -> crate::Result<AccountHandle> {
+    manager
+        .create_account(client_options())?
+        .signer_type(signer_type())
+        .alias("bench")
+        .addresses(addresses)
+        .messages(messages)
+        .initialise()
+        .await
+}
+
+/// Runs the input selection over the unspent outputs of the addresses, returning the number of selected inputs.
+pub fn select_inputs(target: u64, addresses: &[Address], max_inputs: usize) -> crate::Result<usize> {
+    let available = addresses
+        .iter()
+        .flat_map(|address| {
+            address
+                .outputs()
+                .values()
+                .filter(|output| !output.is_spent)
+                .map(move |output| crate::account::SelectionInput {
+                    internal: *address.internal(),
+                    output: output.clone(),
+                })
+        })
+        .collect();
+    let dust_allowance_value = client_options().dust_protection().allowance_value();
+    Ok(crate::account::select_input(target, available, max_inputs, dust_allowance_value)?.len())
+}
+
+/// Computes the sync events between the address states before and after a sync, returning the number of balance
+/// change events. This is the local part of a sync, done for every synced address.
+pub async fn diff_synced_addresses(before: &[Address], after: &[Address]) -> crate::Result<usize> {
+    let addresses_before_sync: Vec<(String, u64, HashMap<OutputId, AddressOutput>)> = before
+        .iter()
+        .map(|address| {
+            (
+                address.address().to_bech32(),
+                address.balance(),
+                address.outputs().clone(),
+            )
+        })
+        .collect();
+    let events =
+        AccountSynchronizer::get_events(AccountOptions::default(), &addresses_before_sync, after, &[], &[]).await?;
+    Ok(events.balance_change_events.len())
+}
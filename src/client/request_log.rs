@@ -0,0 +1,233 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+use std::{
+    fs::{File, OpenOptions},
+    future::Future,
+    io::Write,
+    path::PathBuf,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// The text replacing the addresses when the redaction is enabled.
+const REDACTED: &str = "<redacted>";
+
+/// Options of the node request log, enabled with
+/// [with_request_log](../account_manager/struct.AccountManagerBuilder.html#method.with_request_log).
+#[derive(Debug, Clone)]
+pub struct RequestLogOptions {
+    /// The log file, with a JSON record per line. When it would exceed `max_file_size`, it's renamed with a `.1`
+    /// suffix, shifting the suffix of the previous files.
+    pub path: PathBuf,
+    /// The size in bytes at which the log file is rotated.
+    pub max_file_size: u64,
+    /// The number of rotated files kept besides the current one.
+    pub max_rotated_files: usize,
+    /// Whether the addresses are replaced by `<redacted>` in the endpoints and the error messages.
+    pub redact_addresses: bool,
+}
+
+/// A line of the request log.
+#[derive(Serialize)]
+struct RequestRecord<'a> {
+    timestamp: String,
+    method: &'a str,
+    endpoint: String,
+    #[serde(rename = "durationMs")]
+    duration_ms: u128,
+    /// The status of the node response; the successful requests are logged with 200.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// A request failure, with the status of the node response if there was one.
+pub(crate) trait RequestError: std::fmt::Display {
+    fn status_code(&self) -> Option<u16>;
+}
+
+impl RequestError for iota_client::Error {
+    fn status_code(&self) -> Option<u16> {
+        match self {
+            iota_client::Error::ResponseError(status_code, _) => Some(*status_code),
+            iota_client::Error::ReqwestError(e) => e.status().map(|status| status.as_u16()),
+            _ => None,
+        }
+    }
+}
+
+impl RequestError for crate::Error {
+    fn status_code(&self) -> Option<u16> {
+        match self {
+            crate::Error::ClientError(e) => e.status_code(),
+            #[cfg(any(feature = "participation", feature = "cloud-backup", feature = "batch-outputs"))]
+            crate::Error::ReqwestError(e) => e.status().map(|status| status.as_u16()),
+            crate::Error::NodeOverloaded(status_code, _) => Some(*status_code),
+            _ => None,
+        }
+    }
+}
+
+struct RequestLogger {
+    options: RequestLogOptions,
+    file: File,
+    size: u64,
+}
+
+impl RequestLogger {
+    fn open(options: RequestLogOptions) -> crate::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&options.path)?;
+        let size = file.metadata()?.len();
+        Ok(Self { options, file, size })
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut path = self.options.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+        path.into()
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        if self.options.max_rotated_files == 0 {
+            self.file.set_len(0)?;
+        } else {
+            let _ = std::fs::remove_file(self.rotated_path(self.options.max_rotated_files));
+            for index in (1..self.options.max_rotated_files).rev() {
+                let path = self.rotated_path(index);
+                if path.exists() {
+                    std::fs::rename(path, self.rotated_path(index + 1))?;
+                }
+            }
+            std::fs::rename(&self.options.path, self.rotated_path(1))?;
+            self.file = OpenOptions::new().create(true).append(true).open(&self.options.path)?;
+        }
+        self.size = 0;
+        Ok(())
+    }
+
+    fn log<E: RequestError>(
+        &mut self,
+        method: &str,
+        endpoint: &str,
+        address: Option<&str>,
+        duration: Duration,
+        error: Option<&E>,
+    ) -> crate::Result<()> {
+        let redact = |text: String| match address {
+            Some(address) if self.options.redact_addresses => text.replace(address, REDACTED),
+            _ => text,
+        };
+        let record = RequestRecord {
+            timestamp: Utc::now().to_rfc3339(),
+            method,
+            endpoint: redact(endpoint.to_string()),
+            duration_ms: duration.as_millis(),
+            status: error.map_or(Some(200), |e| e.status_code()),
+            error: error.map(|e| redact(e.to_string())),
+        };
+        let mut line = serde_json::to_vec(&record)?;
+        line.push(b'\n');
+
+        if self.size > 0 && self.size + line.len() as u64 > self.options.max_file_size {
+            self.rotate()?;
+        }
+        self.file.write_all(&line)?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+}
+
+fn request_logger() -> &'static Mutex<Option<RequestLogger>> {
+    static REQUEST_LOGGER: Lazy<Mutex<Option<RequestLogger>>> = Lazy::new(Default::default);
+    &REQUEST_LOGGER
+}
+
+/// Enables the request log, replacing the previous one.
+pub(crate) fn set_request_log(options: RequestLogOptions) -> crate::Result<()> {
+    let logger = RequestLogger::open(options)?;
+    request_logger().lock().unwrap().replace(logger);
+    Ok(())
+}
+
+/// Sends the request, writing its method, endpoint, duration and status to the request log if it's enabled.
+/// `address` is the address in the endpoint, if any, redacted according to the log options.
+pub(crate) async fn log_request<T, E, R>(
+    method: &str,
+    endpoint: String,
+    address: Option<&str>,
+    request: R,
+) -> std::result::Result<T, E>
+where
+    E: RequestError,
+    R: Future<Output = std::result::Result<T, E>>,
+{
+    let enabled = request_logger().lock().unwrap().is_some();
+    if !enabled {
+        return request.await;
+    }
+    let start = Instant::now();
+    let result = request.await;
+    if let Some(logger) = request_logger().lock().unwrap().as_mut() {
+        if let Err(e) = logger.log(method, &endpoint, address, start.elapsed(), result.as_ref().err()) {
+            log::warn!("[CLIENT] failed to write the request log: {}", e);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RequestLogOptions, RequestLogger};
+    use std::time::Duration;
+
+    #[test]
+    fn request_log() {
+        let dir = std::env::temp_dir().join("iota-wallet-request-log");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("requests.log");
+        let mut logger = RequestLogger::open(RequestLogOptions {
+            path: path.clone(),
+            max_file_size: 300,
+            max_rotated_files: 1,
+            redact_addresses: true,
+        })
+        .unwrap();
+
+        let address = crate::testing::generate_random_iota_address().to_bech32();
+        let endpoint = format!("/api/v1/addresses/{}/outputs", address);
+        let error = iota_client::Error::ResponseError(404, format!("address {} not found", address));
+        logger
+            .log(
+                "GET",
+                &endpoint,
+                Some(&address),
+                Duration::from_millis(12),
+                Some(&error),
+            )
+            .unwrap();
+        let log = std::fs::read_to_string(&path).unwrap();
+        assert!(!log.contains(&address));
+        let record: serde_json::Value = serde_json::from_str(log.lines().next().unwrap()).unwrap();
+        assert_eq!(record["endpoint"], "/api/v1/addresses/<redacted>/outputs");
+        assert_eq!(record["status"], 404);
+        assert_eq!(record["durationMs"], 12);
+
+        // the file is rotated when it would exceed the size limit, keeping a single rotated file
+        for _ in 0..10 {
+            logger
+                .log::<iota_client::Error>("GET", "/api/v1/info", None, Duration::from_millis(1), None)
+                .unwrap();
+        }
+        assert!(std::fs::metadata(&path).unwrap().len() <= 300);
+        assert!(dir.join("requests.log.1").exists());
+        assert!(!dir.join("requests.log.2").exists());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,264 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    account::AccountHandle,
+    address::AddressWrapper,
+    message::{IndexationPayload, Message, Transfer},
+    signing::GenerateAddressMetadata,
+};
+
+use serde::Serialize;
+
+use std::num::NonZeroU64;
+
+/// The scheme of the wallet action links.
+const DEEP_LINK_SCHEME: &str = "iota";
+/// The host of the wallet action links.
+const DEEP_LINK_HOST: &str = "wallet";
+
+/// A wallet action parsed from a deep link.
+///
+/// The supported links are:
+/// - `iota://wallet/send/<bech32 address>?amount=<amount>&tag=<tag>`, or a
+///   [payment URI](../address/struct.PaymentRequest.html);
+/// - `iota://wallet/participate/<event id>?answers=<comma separated answers>`;
+/// - `iota://wallet/verify-address/<bech32 address>`.
+///
+/// Query parameters are optional and percent-encoded; the amount is in IOTA.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "action", content = "data")]
+pub enum WalletIntent {
+    /// Send funds to an address.
+    Send {
+        /// The address to send to.
+        #[serde(with = "crate::serde::iota_address_serde")]
+        address: AddressWrapper,
+        /// The amount to send, if the link sets it.
+        amount: Option<NonZeroU64>,
+        /// The indexation index the transfer should carry.
+        tag: Option<String>,
+    },
+    /// Participate in a voting or staking event.
+    Participate {
+        /// The hex encoded event id.
+        #[serde(rename = "eventId")]
+        event_id: String,
+        /// The answers for a voting event.
+        answers: Vec<u8>,
+    },
+    /// Show an address of the account on the signer, e.g. the Ledger display, to check it's the one shared.
+    VerifyAddress {
+        /// The address to verify.
+        #[serde(with = "crate::serde::iota_address_serde")]
+        address: AddressWrapper,
+    },
+}
+
+/// The result of an executed [WalletIntent](enum.WalletIntent.html).
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "action", content = "data")]
+pub enum IntentOutcome {
+    /// The transfer was sent.
+    Send(Message),
+    /// The participation messages were sent.
+    Participate(Vec<Message>),
+    /// Whether the signer generated the same address.
+    VerifyAddress(bool),
+}
+
+fn invalid(reason: &str) -> crate::Error {
+    crate::Error::InvalidDeepLink(reason.to_string())
+}
+
+fn parse_address(address: &str) -> crate::Result<AddressWrapper> {
+    crate::address::parse(address).map_err(|e| invalid(&e.to_string()))
+}
+
+/// Parses a wallet action deep link into a typed [WalletIntent](enum.WalletIntent.html).
+/// The link is only parsed; nothing is executed and an expired payment URI is rejected.
+pub fn parse_deeplink<U: AsRef<str>>(uri: U) -> crate::Result<WalletIntent> {
+    let uri = uri.as_ref().trim();
+    let url = url::Url::parse(uri).map_err(|e| invalid(&e.to_string()))?;
+    if url.scheme() != DEEP_LINK_SCHEME {
+        return Err(invalid("unknown scheme"));
+    }
+    // `iota:<address>` has no host, it's a payment URI
+    if url.cannot_be_a_base() {
+        let request = crate::address::parse_payment_uri(uri)?;
+        return Ok(WalletIntent::Send {
+            address: request.address().clone(),
+            amount: (*request.amount()).and_then(NonZeroU64::new),
+            tag: request.tag().clone(),
+        });
+    }
+    if url.host_str() != Some(DEEP_LINK_HOST) {
+        return Err(invalid("unknown host"));
+    }
+
+    let segments: Vec<&str> = url
+        .path_segments()
+        .map(|segments| segments.filter(|segment| !segment.is_empty()).collect())
+        .unwrap_or_default();
+    let query = |name: &str| {
+        url.query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    };
+    match segments.as_slice() {
+        ["send", address] => {
+            let amount = match query("amount") {
+                Some(amount) => Some(
+                    amount
+                        .parse::<u64>()
+                        .ok()
+                        .and_then(NonZeroU64::new)
+                        .ok_or_else(|| invalid("invalid amount"))?,
+                ),
+                None => None,
+            };
+            let tag = query("tag");
+            if matches!(&tag, Some(tag) if tag.is_empty() || tag.len() > 64) {
+                return Err(invalid("invalid tag length"));
+            }
+            Ok(WalletIntent::Send {
+                address: parse_address(address)?,
+                amount,
+                tag,
+            })
+        }
+        ["participate", event_id] => {
+            if event_id.len() != 64 || hex::decode(event_id).is_err() {
+                return Err(invalid("invalid event id"));
+            }
+            let answers = match query("answers") {
+                Some(answers) if !answers.is_empty() => answers
+                    .split(',')
+                    .map(|answer| answer.trim().parse::<u8>())
+                    .collect::<Result<Vec<u8>, _>>()
+                    .map_err(|_| invalid("invalid answers"))?,
+                _ => Vec::new(),
+            };
+            Ok(WalletIntent::Participate {
+                event_id: event_id.to_lowercase(),
+                answers,
+            })
+        }
+        ["verify-address", address] => Ok(WalletIntent::VerifyAddress {
+            address: parse_address(address)?,
+        }),
+        _ => Err(invalid("unknown action")),
+    }
+}
+
+/// Executes the intent with the matching account API.
+/// A send intent without an amount fails, the user must enter it first.
+pub async fn execute_intent(account_handle: &AccountHandle, intent: WalletIntent) -> crate::Result<IntentOutcome> {
+    match intent {
+        WalletIntent::Send { address, amount, tag } => {
+            let amount = amount.ok_or_else(|| invalid("the link doesn't set the amount"))?;
+            let bech32_hrp = account_handle.read().await.bech32_hrp();
+            let mut transfer = Transfer::builder(address, amount, None).with_network_hrp(bech32_hrp);
+            if let Some(tag) = tag {
+                transfer = transfer.with_indexation(IndexationPayload::new(tag.as_bytes(), &[])?);
+            }
+            let message = account_handle.transfer(transfer.finish()?).await?;
+            Ok(IntentOutcome::Send(message))
+        }
+        #[cfg(feature = "participation")]
+        WalletIntent::Participate { event_id, answers } => {
+            let messages = account_handle
+                .participate(vec![crate::participation::types::Participation { event_id, answers }])
+                .await?;
+            Ok(IntentOutcome::Participate(messages))
+        }
+        #[cfg(not(feature = "participation"))]
+        WalletIntent::Participate { .. } => Err(invalid("participation isn't enabled")),
+        WalletIntent::VerifyAddress { address } => {
+            let account = account_handle.read().await;
+            let account_address = match account.addresses().iter().find(|a| a.address() == &address) {
+                Some(account_address) => account_address,
+                None => return Ok(IntentOutcome::VerifyAddress(false)),
+            };
+            // not syncing, so the address is displayed on Ledger devices
+            let generated = crate::address::get_iota_address(
+                &account,
+                *account_address.key_index(),
+                *account_address.internal(),
+                address.bech32_hrp().to_string(),
+                GenerateAddressMetadata {
+                    syncing: false,
+                    network: account.network(),
+                },
+            )
+            .await?;
+            Ok(IntentOutcome::VerifyAddress(generated == address))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_deeplink, WalletIntent};
+
+    #[test]
+    fn deeplink_parsing() {
+        let address = crate::testing::generate_random_iota_address();
+        let bech32 = address.to_bech32();
+        let event_id = "09c2338f3acd51e626cc074d1abcb12d747076ddfccd5215d8f2f21af1aac111";
+
+        let intent = parse_deeplink(format!("iota://wallet/send/{}?amount=1000000&tag=invoice%201", bech32)).unwrap();
+        match intent {
+            WalletIntent::Send {
+                address: a,
+                amount,
+                tag,
+            } => {
+                assert_eq!(a, address);
+                assert_eq!(amount.unwrap().get(), 1_000_000);
+                assert_eq!(tag.as_deref(), Some("invoice 1"));
+            }
+            _ => panic!("expected a send intent"),
+        }
+        assert!(matches!(
+            parse_deeplink(format!("iota:{}?amount=5", bech32)).unwrap(),
+            WalletIntent::Send { amount: Some(_), .. }
+        ));
+        assert_eq!(
+            parse_deeplink(format!("iota://wallet/participate/{}?answers=0,1", event_id)).unwrap(),
+            WalletIntent::Participate {
+                event_id: event_id.to_string(),
+                answers: vec![0, 1],
+            }
+        );
+        assert_eq!(
+            parse_deeplink(format!("iota://wallet/verify-address/{}", bech32)).unwrap(),
+            WalletIntent::VerifyAddress { address }
+        );
+
+        for invalid in [
+            format!("iota://wallet/send/{}?amount=0", bech32),
+            format!("iota://wallet/participate/{}?answers=256", event_id),
+            "iota://wallet/participate/xyz".to_string(),
+            format!("iota://other/send/{}", bech32),
+            format!("https://wallet/send/{}", bech32),
+            "iota://wallet/unknown".to_string(),
+        ] {
+            assert!(matches!(
+                parse_deeplink(&invalid),
+                Err(crate::Error::InvalidDeepLink(_))
+            ));
+        }
+
+        // truncated and mutated links must fail cleanly rather than panic
+        let link = format!("iota://wallet/send/{}?amount=1000000&tag=%E2%82%AC", bech32);
+        for end in 0..link.len() {
+            let _ = parse_deeplink(&link[..end]);
+            for replacement in ['%', '/', '?', '&', '=', ',', '1', ':', '\u{fffd}'] {
+                let mut mutated: Vec<char> = link.chars().collect();
+                mutated[end] = replacement;
+                let _ = parse_deeplink(mutated.into_iter().collect::<String>());
+            }
+        }
+    }
+}
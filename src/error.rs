@@ -64,6 +64,66 @@ pub enum Error {
     /// failed to parse address.
     #[error("invalid address")]
     InvalidAddress,
+    /// The signer doesn't hold the seed the account was created with.
+    #[error("the signer seed doesn't match the seed of account {0}, was the right mnemonic restored?")]
+    SeedMismatch(String),
+    /// Invalid payment URI.
+    #[error("invalid payment URI: {0}")]
+    InvalidPaymentUri(String),
+    /// The payment request expired.
+    #[error("the payment request expired")]
+    PaymentRequestExpired,
+    /// A wallet with the same name is already in the registry.
+    #[error("a wallet named `{0}` is already registered")]
+    WalletAlreadyRegistered(String),
+    /// The amount couldn't be parsed, is more precise than `1i` or overflows.
+    #[error("invalid amount: {0}")]
+    InvalidAmount(String),
+    /// The bech32 checksum of the address doesn't match, it was probably mistyped.
+    #[error("invalid checksum for address {0}, it was probably mistyped")]
+    InvalidAddressChecksum(String),
+    /// The transfer output address belongs to another network.
+    #[error("address {address} doesn't belong to the `{expected}` network")]
+    AddressNetworkMismatch {
+        /// The output address.
+        address: String,
+        /// The expected bech32 human readable part.
+        expected: String,
+    },
+    /// An output address validator rejected the transfer.
+    #[error("output address {0} rejected: {1}")]
+    OutputAddressRejected(String, String),
+    /// Invalid wallet action deep link.
+    #[error("invalid deep link: {0}")]
+    InvalidDeepLink(String),
+    /// The network protocol has no timelock unlock conditions.
+    #[error("time-locked outputs aren't supported by the network protocol")]
+    TimelockNotSupported,
+    /// The network protocol has no expiration unlock conditions.
+    #[error("expiring outputs aren't supported by the network protocol")]
+    ExpirationNotSupported,
+    /// The network protocol has no output feature blocks of this kind.
+    #[error("{0} feature blocks aren't supported by the network protocol")]
+    FeatureBlockNotSupported(String),
+    /// The output unlock conditions and feature blocks can't be combined.
+    #[error("invalid output: {0}")]
+    InvalidOutput(String),
+    /// The message was stored without its raw bytes.
+    #[error("the raw bytes of the message aren't stored")]
+    MissingRawMessage,
+    /// The stored message doesn't match its raw bytes.
+    #[error("message verification failed: {0}")]
+    MessageVerificationFailed(String),
+    /// A transfer of a chain failed, so the following transfers weren't sent.
+    #[error("transfer {index} of the chain failed and the following transfers were abandoned: {error}")]
+    TransferChainAbandoned {
+        /// The messages of the transfers sent before the failure.
+        sent: Vec<iota_client::bee_message::prelude::MessageId>,
+        /// The index of the failed transfer.
+        index: usize,
+        /// The error of the failed transfer.
+        error: Box<Error>,
+    },
     /// Address length response invalid.
     #[error("invalid address length")]
     InvalidAddressLength,
@@ -73,6 +133,30 @@ pub enum Error {
     /// Insufficient funds to send transfer.
     #[error("insufficient funds {0}/{1} available or input address used as output")]
     InsufficientFunds(u64, u64),
+    /// The transfer outputs count is out of range.
+    #[error("invalid outputs count {0}, a transfer needs between 1 and {1} outputs")]
+    InvalidOutputCount(usize, usize),
+    /// The transfer indexation data is too large.
+    #[error("indexation data size {0} exceeds the limit of {1} bytes")]
+    IndexationDataTooLarge(usize, usize),
+    /// The transfer was rejected by its pre-sign hook.
+    #[error("transfer vetoed: {0}")]
+    TransferVetoed(String),
+    /// Cold storage accounts can't sign transfers.
+    #[error("cold storage accounts can't sign transfers, prepare a signing request instead")]
+    ColdStorageAccount,
+    /// Only cold storage accounts prepare signing requests.
+    #[error("only cold storage accounts can prepare signing requests")]
+    NotColdStorageAccount,
+    /// Invalid signing request or signed payload.
+    #[error("invalid signing request: {0}")]
+    InvalidSigningRequest(String),
+    /// The account is frozen.
+    #[error("the account is frozen, transfers are disabled")]
+    AccountFrozen,
+    /// Invalid user data key or value.
+    #[error("invalid user data: {0}")]
+    InvalidUserData(String),
     /// Account isn't empty (has history or balance) - can't delete account.
     #[error("can't delete account: account has history or balance")]
     AccountNotEmpty,
@@ -82,6 +166,12 @@ pub enum Error {
     /// Account not found
     #[error("account not found")]
     RecordNotFound,
+    /// No account matches the identifier.
+    #[error("account not found by {by}")]
+    AccountNotFound {
+        /// The identifier the account was looked up by.
+        by: crate::account::AccountIdentifier,
+    },
     /// invalid remainder value target address defined on `RemainderValueStrategy`.
     /// the address must belong to the account.
     #[error("the remainder value address doesn't belong to the account")]
@@ -122,6 +212,9 @@ pub enum Error {
     /// Can't import accounts because the storage already exist
     #[error("failed to restore backup: storage file already exists")]
     StorageExists,
+    /// Invalid stream provided to `import_all`.
+    #[error("invalid export stream: {0}")]
+    InvalidExport(String),
     /// Storage adapter not defined for the given storage path.
     #[error(
         "storage adapter not set for path `{0}`; please use the method `with_storage` on the AccountManager builder"
@@ -138,6 +231,9 @@ pub enum Error {
         "can't perform operation while storage is encrypted; use AccountManager::set_storage_password to decrypt storage"
     )]
     StorageIsEncrypted,
+    /// The storage is already encrypted, so changing its password needs the current one.
+    #[error("the storage is already encrypted; use AccountManager::change_storage_password to change its password")]
+    CurrentStoragePasswordRequired,
     /// cannot use index to get account - multiple index sequences found (two or more different signer types stored on
     /// accounts)
     #[error("cannot use index identifier when two signer types are used")]
@@ -197,6 +293,9 @@ pub enum Error {
     /// Too many outputs
     #[error("too many outputs: {0}, max is {1}")]
     TooManyOutputs(usize, usize),
+    /// Too many inputs
+    #[error("too many inputs: {0}, max is {1}")]
+    TooManyInputs(usize, usize),
     /// Funds are spread over too many outputs
     #[error("funds are spread over too many outputs {0}/{1}, consolidation required")]
     ConsolidationRequired(usize, usize),
@@ -241,11 +340,65 @@ pub enum Error {
     /// Couldn't get a spent output from a node.
     #[error("couldn't get a spent output from node")]
     SpentOutputNotFound,
+    /// Invalid proxy configuration.
+    #[error("invalid proxy options: {0}")]
+    InvalidProxyOptions(String),
+    /// Invalid dust protection parameters.
+    #[error("invalid dust protection parameters: {0}")]
+    InvalidDustProtection(String),
+    /// Invalid ledger snapshot or dump.
+    #[error("invalid snapshot: {0}")]
+    InvalidSnapshot(String),
+    /// Binary storage record encoding or decoding error.
+    #[error("binary record error: {0}")]
+    BinaryRecord(String),
+    /// The message handler rejected the message auth token.
+    #[error("unauthorized: {0}")]
+    Unauthorized(String),
+    /// The API session expired or was closed; a new one must be opened with the wallet password.
+    #[error("the session expired")]
+    SessionExpired,
+    /// Invalid payout batch row.
+    #[error("invalid payout batch: {0}")]
+    InvalidPayoutBatch(String),
+    /// The account signer can't perform the operation.
+    #[error("the account signer doesn't support {0}")]
+    SignerOperationNotSupported(String),
+    /// Invalid network parameters.
+    #[error("invalid network parameters: {0}")]
+    InvalidNetworkParameters(String),
+    /// The message exceeds the network maximum length.
+    #[error("message length {0} exceeds the network maximum of {1} bytes")]
+    MessageTooLarge(usize, usize),
+    /// The node asked to retry the request later.
+    #[error("node responded with status {0}, retry later")]
+    NodeOverloaded(u16, Option<u64>),
+    /// The address index is beyond the account address index limit.
+    #[error("address index {0} exceeds the account limit of {1}")]
+    AddressIndexLimitExceeded(usize, usize),
+    /// The random number generator failed.
+    #[error("failed to generate random bytes: {0}")]
+    RandomGeneration(String),
+    /// The account manager is shutting down, so no transfer can start.
+    #[error("the account manager is shutting down")]
+    ShuttingDown,
+    /// Cloud backup error.
+    #[cfg(feature = "cloud-backup")]
+    #[error("cloud backup error: {0}")]
+    CloudBackup(String),
+    /// Another device uploaded a cloud backup this device hasn't restored.
+    #[cfg(feature = "cloud-backup")]
+    #[error("the cloud backup `{0}` was uploaded by another device")]
+    CloudBackupConflict(String),
+    /// MQTT event publisher error.
+    #[cfg(feature = "mqtt-events")]
+    #[error("MQTT event publisher error: {0}")]
+    MqttEventPublisher(String),
     #[cfg(feature = "participation")]
     /// Participation is invalid
     #[error("participations is invalid")]
     InvalidParticipations,
-    #[cfg(feature = "participation")]
+    #[cfg(any(feature = "participation", feature = "cloud-backup", feature = "batch-outputs"))]
     /// reqwest error
     #[error("{0}")]
     ReqwestError(#[from] reqwest::Error),
@@ -332,12 +485,38 @@ impl serde::Serialize for Error {
             Self::MessageNotFound => serialize_variant(self, serializer, "MessageNotFound"),
             Self::InvalidMessageIdLength => serialize_variant(self, serializer, "InvalidMessageIdLength"),
             Self::InvalidAddress => serialize_variant(self, serializer, "InvalidAddress"),
+            Self::SeedMismatch(_) => serialize_variant(self, serializer, "SeedMismatch"),
+            Self::InvalidPaymentUri(_) => serialize_variant(self, serializer, "InvalidPaymentUri"),
+            Self::PaymentRequestExpired => serialize_variant(self, serializer, "PaymentRequestExpired"),
+            Self::WalletAlreadyRegistered(_) => serialize_variant(self, serializer, "WalletAlreadyRegistered"),
+            Self::InvalidAmount(_) => serialize_variant(self, serializer, "InvalidAmount"),
+            Self::InvalidAddressChecksum(_) => serialize_variant(self, serializer, "InvalidAddressChecksum"),
+            Self::AddressNetworkMismatch { .. } => serialize_variant(self, serializer, "AddressNetworkMismatch"),
+            Self::OutputAddressRejected(_, _) => serialize_variant(self, serializer, "OutputAddressRejected"),
+            Self::InvalidDeepLink(_) => serialize_variant(self, serializer, "InvalidDeepLink"),
+            Self::TimelockNotSupported => serialize_variant(self, serializer, "TimelockNotSupported"),
+            Self::ExpirationNotSupported => serialize_variant(self, serializer, "ExpirationNotSupported"),
+            Self::FeatureBlockNotSupported(_) => serialize_variant(self, serializer, "FeatureBlockNotSupported"),
+            Self::InvalidOutput(_) => serialize_variant(self, serializer, "InvalidOutput"),
+            Self::MissingRawMessage => serialize_variant(self, serializer, "MissingRawMessage"),
+            Self::MessageVerificationFailed(_) => serialize_variant(self, serializer, "MessageVerificationFailed"),
+            Self::TransferChainAbandoned { .. } => serialize_variant(self, serializer, "TransferChainAbandoned"),
             Self::InvalidAddressLength => serialize_variant(self, serializer, "InvalidAddressLength"),
             Self::StorageDoesntExist => serialize_variant(self, serializer, "StorageDoesntExist"),
             Self::InsufficientFunds(_, _) => serialize_variant(self, serializer, "InsufficientFunds"),
+            Self::InvalidOutputCount(_, _) => serialize_variant(self, serializer, "InvalidOutputCount"),
+            Self::IndexationDataTooLarge(_, _) => serialize_variant(self, serializer, "IndexationDataTooLarge"),
+            // serialized as the underlying error with an additional `diagnostics` field
+            Self::TransferVetoed(_) => serialize_variant(self, serializer, "TransferVetoed"),
+            Self::ColdStorageAccount => serialize_variant(self, serializer, "ColdStorageAccount"),
+            Self::NotColdStorageAccount => serialize_variant(self, serializer, "NotColdStorageAccount"),
+            Self::InvalidSigningRequest(_) => serialize_variant(self, serializer, "InvalidSigningRequest"),
+            Self::AccountFrozen => serialize_variant(self, serializer, "AccountFrozen"),
+            Self::InvalidUserData(_) => serialize_variant(self, serializer, "InvalidUserData"),
             Self::AccountNotEmpty => serialize_variant(self, serializer, "AccountNotEmpty"),
             Self::LatestAccountIsEmpty => serialize_variant(self, serializer, "LatestAccountIsEmpty"),
             Self::RecordNotFound => serialize_variant(self, serializer, "RecordNotFound"),
+            Self::AccountNotFound { .. } => serialize_variant(self, serializer, "AccountNotFound"),
             Self::InvalidRemainderValueAddress => serialize_variant(self, serializer, "InvalidRemainderValueAddress"),
             Self::Storage(_) => serialize_variant(self, serializer, "Storage"),
             Self::Panic(_) => serialize_variant(self, serializer, "Panic"),
@@ -353,10 +532,14 @@ impl serde::Serialize for Error {
             Self::InvalidBackupFile => serialize_variant(self, serializer, "InvalidBackupFile"),
             Self::InvalidBackupDestination => serialize_variant(self, serializer, "InvalidBackupDestination"),
             Self::StorageExists => serialize_variant(self, serializer, "StorageExists"),
+            Self::InvalidExport(_) => serialize_variant(self, serializer, "InvalidExport"),
             Self::StorageAdapterNotSet(_) => serialize_variant(self, serializer, "StorageAdapterNotSet"),
             Self::RecordDecrypt(_) => serialize_variant(self, serializer, "RecordDecrypt"),
             Self::RecordEncrypt(_) => serialize_variant(self, serializer, "RecordEncrypt"),
             Self::StorageIsEncrypted => serialize_variant(self, serializer, "StorageIsEncrypted"),
+            Self::CurrentStoragePasswordRequired => {
+                serialize_variant(self, serializer, "CurrentStoragePasswordRequired")
+            }
             Self::CannotUseIndexIdentifier => serialize_variant(self, serializer, "CannotUseIndexIdentifier"),
             Self::NoLedgerSignerError => serialize_variant(self, serializer, "NoLedgerSignerError"),
             #[cfg(any(feature = "ledger-nano", feature = "ledger-nano-simulator"))]
@@ -381,6 +564,7 @@ impl serde::Serialize for Error {
             Self::NodesNotSynced(_) => serialize_variant(self, serializer, "NodesNotSynced"),
             Self::FailedToGetRemainder => serialize_variant(self, serializer, "FailedToGetRemainder"),
             Self::TooManyOutputs(_, _) => serialize_variant(self, serializer, "TooManyOutputs"),
+            Self::TooManyInputs(_, _) => serialize_variant(self, serializer, "TooManyInputs"),
             Self::ConsolidationRequired(_, _) => serialize_variant(self, serializer, "ConsolidationRequired"),
             Self::InputAddressNotFound => serialize_variant(self, serializer, "InputAddressNotFound"),
             // #[cfg(feature = "migration")]
@@ -396,9 +580,29 @@ impl serde::Serialize for Error {
             Self::TaskJoinError(_) => serialize_variant(self, serializer, "TaskJoinError"),
             Self::StdThreadJoinError => serialize_variant(self, serializer, "StdThreadJoinError"),
             Self::SpentOutputNotFound => serialize_variant(self, serializer, "SpentOutputNotFound"),
+            Self::InvalidProxyOptions(_) => serialize_variant(self, serializer, "InvalidProxyOptions"),
+            Self::InvalidDustProtection(_) => serialize_variant(self, serializer, "InvalidDustProtection"),
+            Self::InvalidSnapshot(_) => serialize_variant(self, serializer, "InvalidSnapshot"),
+            Self::BinaryRecord(_) => serialize_variant(self, serializer, "BinaryRecord"),
+            Self::Unauthorized(_) => serialize_variant(self, serializer, "Unauthorized"),
+            Self::SessionExpired => serialize_variant(self, serializer, "SessionExpired"),
+            Self::InvalidPayoutBatch(_) => serialize_variant(self, serializer, "InvalidPayoutBatch"),
+            Self::SignerOperationNotSupported(_) => serialize_variant(self, serializer, "SignerOperationNotSupported"),
+            Self::InvalidNetworkParameters(_) => serialize_variant(self, serializer, "InvalidNetworkParameters"),
+            Self::MessageTooLarge(_, _) => serialize_variant(self, serializer, "MessageTooLarge"),
+            Self::NodeOverloaded(_, _) => serialize_variant(self, serializer, "NodeOverloaded"),
+            Self::AddressIndexLimitExceeded(_, _) => serialize_variant(self, serializer, "AddressIndexLimitExceeded"),
+            Self::RandomGeneration(_) => serialize_variant(self, serializer, "RandomGeneration"),
+            Self::ShuttingDown => serialize_variant(self, serializer, "ShuttingDown"),
+            #[cfg(feature = "cloud-backup")]
+            Self::CloudBackup(_) => serialize_variant(self, serializer, "CloudBackup"),
+            #[cfg(feature = "cloud-backup")]
+            Self::CloudBackupConflict(_) => serialize_variant(self, serializer, "CloudBackupConflict"),
+            #[cfg(feature = "mqtt-events")]
+            Self::MqttEventPublisher(_) => serialize_variant(self, serializer, "MqttEventPublisher"),
             #[cfg(feature = "participation")]
             Self::InvalidParticipations => serialize_variant(self, serializer, "InvalidParticipations"),
-            #[cfg(feature = "participation")]
+            #[cfg(any(feature = "participation", feature = "cloud-backup", feature = "batch-outputs"))]
             Self::ReqwestError(_) => serialize_variant(self, serializer, "ReqwestError"),
         }
     }
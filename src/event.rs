@@ -3,18 +3,27 @@
 
 use crate::{
     account::Account,
-    address::AddressWrapper,
+    address::{AddressOutput, AddressWrapper},
     message::{Message, MessageId},
+    storage::EventKind,
 };
 
+use chrono::{DateTime, Utc};
 use getset::Getters;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use tokio::sync::Mutex;
 
 use std::{
+    collections::HashMap,
     ops::Deref,
-    sync::{Arc, Mutex as StdMutex},
+    panic::{catch_unwind, AssertUnwindSafe},
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex as StdMutex,
+    },
+    time::{Duration, Instant},
 };
 
 /// The event identifier type.
@@ -58,6 +67,12 @@ pub struct BalanceEvent {
     /// Event unique identifier.
     #[serde(rename = "indexationId")]
     pub indexation_id: String,
+    /// The per-account sequence number. Re-emitted events keep their original sequence number.
+    #[serde(default)]
+    pub sequence: u64,
+    /// The key identifying re-emissions of the same event.
+    #[serde(rename = "dedupKey", default)]
+    pub dedup_key: String,
     /// The associated account identifier.
     #[serde(rename = "accountId")]
     pub account_id: String,
@@ -78,6 +93,100 @@ pub struct BalanceEvent {
     pub balance_change: BalanceChange,
 }
 
+/// A persisted account event.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data")]
+pub enum AccountEvent {
+    /// A balance change event.
+    BalanceChange(BalanceEvent),
+    /// A new transaction event.
+    NewTransaction(TransactionEvent),
+    /// A broadcast event.
+    Broadcast(TransactionEvent),
+    /// A transaction confirmation state change event.
+    ConfirmationStateChange(TransactionConfirmationChangeEvent),
+    /// A reattachment event.
+    Reattachment(TransactionReattachmentEvent),
+}
+
+impl AccountEvent {
+    /// The event sequence number.
+    pub fn sequence(&self) -> u64 {
+        match self {
+            Self::BalanceChange(event) => event.sequence,
+            Self::NewTransaction(event) | Self::Broadcast(event) => event.sequence,
+            Self::ConfirmationStateChange(event) => event.sequence,
+            Self::Reattachment(event) => event.sequence,
+        }
+    }
+
+    /// The event deduplication key.
+    pub fn dedup_key(&self) -> &str {
+        match self {
+            Self::BalanceChange(event) => &event.dedup_key,
+            Self::NewTransaction(event) | Self::Broadcast(event) => &event.dedup_key,
+            Self::ConfirmationStateChange(event) => &event.dedup_key,
+            Self::Reattachment(event) => &event.dedup_key,
+        }
+    }
+
+    /// The id of the event account.
+    pub fn account_id(&self) -> &str {
+        match self {
+            Self::BalanceChange(event) => &event.account_id,
+            Self::NewTransaction(event) | Self::Broadcast(event) => &event.account_id,
+            Self::ConfirmationStateChange(event) => &event.account_id,
+            Self::Reattachment(event) => &event.account_id,
+        }
+    }
+}
+
+/// An account event a listener failed to handle, kept until it's redelivered with
+/// [redeliver_failed](fn.redeliver_failed.html).
+#[derive(Clone, Debug, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct FailedDelivery {
+    /// The failed delivery identifier.
+    id: String,
+    /// The undelivered event.
+    event: AccountEvent,
+    /// The identifier of the listener that failed.
+    listener: EventId,
+    /// The panic message of the listener.
+    error: String,
+    /// Number of failed delivery attempts.
+    attempts: u32,
+}
+
+/// A batch of account events, delivered to the [on_event_batch](fn.on_event_batch.html) listeners.
+#[derive(Clone, Debug, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct EventBatch {
+    /// The events in emission order.
+    events: Vec<AccountEvent>,
+}
+
+/// The account events batching configuration, see [set_event_batching](fn.set_event_batching.html).
+#[derive(Debug, Clone, Copy)]
+pub struct EventBatchOptions {
+    /// The batch is delivered as soon as it holds this many events.
+    pub max_size: usize,
+    /// The batch is delivered once its oldest event waited this long, even if it isn't full.
+    pub flush_interval: Duration,
+    /// Compatibility mode: the individual event listeners are still called for each event.
+    pub emit_individual_events: bool,
+}
+
+impl Default for EventBatchOptions {
+    fn default() -> Self {
+        Self {
+            max_size: 100,
+            flush_interval: Duration::from_millis(500),
+            emit_individual_events: true,
+        }
+    }
+}
+
 /// The `address consolidation needed` data.
 #[derive(Clone, Debug, Getters, Serialize, Deserialize)]
 #[getset(get = "pub")]
@@ -109,6 +218,12 @@ pub struct TransactionEvent {
     /// Event unique identifier.
     #[serde(rename = "indexationId")]
     pub indexation_id: String,
+    /// The per-account sequence number. Re-emitted events keep their original sequence number.
+    #[serde(default)]
+    pub sequence: u64,
+    /// The key identifying re-emissions of the same event.
+    #[serde(rename = "dedupKey", default)]
+    pub dedup_key: String,
     #[serde(rename = "accountId")]
     /// The associated account identifier.
     pub account_id: String,
@@ -123,6 +238,12 @@ pub struct TransactionConfirmationChangeEvent {
     /// Event unique identifier.
     #[serde(rename = "indexationId")]
     pub indexation_id: String,
+    /// The per-account sequence number. Re-emitted events keep their original sequence number.
+    #[serde(default)]
+    pub sequence: u64,
+    /// The key identifying re-emissions of the same event.
+    #[serde(rename = "dedupKey", default)]
+    pub dedup_key: String,
     #[serde(rename = "accountId")]
     /// The associated account identifier.
     pub account_id: String,
@@ -139,6 +260,12 @@ pub struct TransactionReattachmentEvent {
     /// Event unique identifier.
     #[serde(rename = "indexationId")]
     pub indexation_id: String,
+    /// The per-account sequence number. Re-emitted events keep their original sequence number.
+    #[serde(default)]
+    pub sequence: u64,
+    /// The key identifying re-emissions of the same event.
+    #[serde(rename = "dedupKey", default)]
+    pub dedup_key: String,
     #[serde(rename = "accountId")]
     /// The associated account identifier.
     pub account_id: String,
@@ -182,6 +309,30 @@ pub struct TransactionIO {
     pub remainder: Option<bool>,
 }
 
+/// Remainder address verification event data.
+#[derive(Clone, Debug, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct RemainderAddressVerificationData {
+    /// The remainder deposit address.
+    pub address: String,
+    /// The remainder value.
+    #[serde(rename = "remainderValue")]
+    pub remainder_value: u64,
+    /// Whether the address is displayed on the device for the user to verify it.
+    #[serde(rename = "verifyOnDevice")]
+    pub verify_on_device: bool,
+}
+
+/// Chained transfer event data.
+#[derive(Clone, Debug, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct ChainedTransferData {
+    /// The index of the transfer in the chain.
+    pub index: usize,
+    /// The number of transfers in the chain.
+    pub total: usize,
+}
+
 /// Transfer event type.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(tag = "type")]
@@ -200,6 +351,11 @@ pub enum TransferProgressType {
     PerformingPoW,
     /// Broadcasting.
     Broadcasting,
+    /// Starting the next transfer of a chain.
+    ChainedTransfer(ChainedTransferData),
+    /// Deciding whether the remainder deposit address is verified on the Ledger display, according to the
+    /// change address verification policy.
+    RemainderAddressVerification(RemainderAddressVerificationData),
 }
 
 /// Transfer event data.
@@ -211,6 +367,126 @@ pub struct TransferProgress {
     pub account_id: String,
     /// The transfer event type.
     pub event: TransferProgressType,
+    /// When the event was emitted.
+    pub timestamp: DateTime<Utc>,
+    /// Milliseconds spent on the previous stage of the transfer, if any.
+    #[serde(rename = "previousStageDuration")]
+    pub previous_stage_duration: Option<u64>,
+    /// The application data attached to the transfer with
+    /// [TransferBuilder#with_context](../message/struct.TransferBuilder.html#method.with_context).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<serde_json::Value>,
+}
+
+/// Payment received event data.
+#[derive(Clone, Debug, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct PaymentReceived {
+    /// The associated account identifier.
+    #[serde(rename = "accountId")]
+    pub account_id: String,
+    /// The watched address.
+    #[serde(with = "crate::serde::iota_address_serde")]
+    pub address: AddressWrapper,
+    /// The identifier of the message holding the received output.
+    #[serde(rename = "messageId")]
+    pub message_id: MessageId,
+    /// The received amount.
+    pub amount: u64,
+}
+
+/// Storage quota event data.
+#[derive(Clone, Debug, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct StorageQuotaEvent {
+    /// The size of the storage folder, in bytes.
+    #[serde(rename = "usedBytes")]
+    pub used_bytes: u64,
+    /// The configured storage quota, in bytes.
+    #[serde(rename = "maxBytes")]
+    pub max_bytes: u64,
+}
+
+/// Output lock forced release event data.
+#[derive(Clone, Debug, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct OutputLockReleased {
+    /// The associated account identifier.
+    #[serde(rename = "accountId")]
+    pub account_id: String,
+    /// The output that was reserved by a transfer.
+    pub output: AddressOutput,
+    /// Milliseconds the output was locked for.
+    #[serde(rename = "lockedForMs")]
+    pub locked_for_ms: u64,
+}
+
+/// The addresses derived once Stronghold was unlocked, after a sync skipped them because it was locked.
+#[derive(Clone, Debug, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct AddressDerivationResumed {
+    /// The associated account identifier.
+    #[serde(rename = "accountId")]
+    pub account_id: String,
+    /// The derived addresses, bech32 encoded.
+    pub addresses: Vec<String>,
+}
+
+/// The state of an account index checked by the account discovery.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data")]
+pub enum AccountDiscoveryStatus {
+    /// The account addresses are being synced.
+    Syncing,
+    /// The account was synced; empty accounts past the discovery threshold aren't added to the wallet.
+    Synced {
+        /// Whether the account has no balance and no outputs.
+        empty: bool,
+    },
+    /// The account sync failed, which stops the discovery.
+    Failed(String),
+}
+
+/// The progress of the account discovery, emitted for each account index it checks.
+#[derive(Clone, Debug, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct AccountDiscoveryProgress {
+    /// The index of the checked account.
+    #[serde(rename = "accountIndex")]
+    pub account_index: usize,
+    /// The state of the account.
+    pub status: AccountDiscoveryStatus,
+}
+
+/// The in-memory lifecycle of an account.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AccountLifecycle {
+    /// The account was loaded from the storage.
+    Loaded,
+    /// The account was flushed to the storage and evicted from memory.
+    Evicted,
+}
+
+/// Account lifecycle event data.
+#[derive(Clone, Debug, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct AccountLifecycleEvent {
+    /// The associated account identifier.
+    #[serde(rename = "accountId")]
+    pub account_id: String,
+    /// The lifecycle change.
+    pub lifecycle: AccountLifecycle,
+}
+
+/// The account MQTT subscriptions were moved to the broker of the new client options.
+#[derive(Clone, Debug, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct MonitoringResubscribed {
+    /// The associated account identifier.
+    #[serde(rename = "accountId")]
+    pub account_id: String,
+    /// Number of topics subscribed on the new broker; zero if MQTT is disabled or the client options use a proxy.
+    pub topics: usize,
 }
 
 /// Migration event type.
@@ -356,6 +632,46 @@ struct TransferProgressHandler {
 
 event_handler_impl!(TransferProgressHandler);
 
+struct PaymentReceivedHandler {
+    id: EventId,
+    /// The on event callback.
+    on_event: Box<dyn Fn(&PaymentReceived) + Send>,
+}
+
+event_handler_impl!(PaymentReceivedHandler);
+
+struct StorageQuotaWarningHandler {
+    id: EventId,
+    /// The on event callback.
+    on_event: Box<dyn Fn(&StorageQuotaEvent) + Send>,
+}
+
+event_handler_impl!(StorageQuotaWarningHandler);
+
+struct StorageQuotaExceededHandler {
+    id: EventId,
+    /// The on event callback.
+    on_event: Box<dyn Fn(&StorageQuotaEvent) + Send>,
+}
+
+event_handler_impl!(StorageQuotaExceededHandler);
+
+struct AccountLifecycleHandler {
+    id: EventId,
+    /// The on event callback.
+    on_event: Box<dyn Fn(&AccountLifecycleEvent) + Send>,
+}
+
+event_handler_impl!(AccountLifecycleHandler);
+
+struct MonitoringResubscribedHandler {
+    id: EventId,
+    /// The on event callback.
+    on_event: Box<dyn Fn(&MonitoringResubscribed) + Send>,
+}
+
+event_handler_impl!(MonitoringResubscribedHandler);
+
 struct MigrationProgressHandler {
     id: EventId,
     /// The on event callback.
@@ -364,6 +680,38 @@ struct MigrationProgressHandler {
 
 event_handler_impl!(MigrationProgressHandler);
 
+struct OutputLockReleasedHandler {
+    id: EventId,
+    /// The on event callback.
+    on_event: Box<dyn Fn(&OutputLockReleased) + Send>,
+}
+
+event_handler_impl!(OutputLockReleasedHandler);
+
+struct AddressDerivationResumedHandler {
+    id: EventId,
+    /// The on event callback.
+    on_event: Box<dyn Fn(&AddressDerivationResumed) + Send>,
+}
+
+event_handler_impl!(AddressDerivationResumedHandler);
+
+struct AccountDiscoveryProgressHandler {
+    id: EventId,
+    /// The on event callback.
+    on_event: Box<dyn Fn(&AccountDiscoveryProgress) + Send>,
+}
+
+event_handler_impl!(AccountDiscoveryProgressHandler);
+
+struct EventBatchHandler {
+    id: EventId,
+    /// The on event callback.
+    on_event: Box<dyn Fn(&EventBatch) + Send>,
+}
+
+event_handler_impl!(EventBatchHandler);
+
 type BalanceListeners = Arc<Mutex<Vec<BalanceEventHandler>>>;
 type TransactionListeners = Arc<Mutex<Vec<TransactionEventHandler>>>;
 type TransactionConfirmationChangeListeners = Arc<Mutex<Vec<TransactionConfirmationChangeEventHandler>>>;
@@ -376,7 +724,16 @@ type AddressConsolidationNeededListeners = Arc<Mutex<Vec<AddressConsolidationNee
 #[cfg(any(feature = "ledger-nano", feature = "ledger-nano-simulator"))]
 type LedgerAddressGenerationListeners = Arc<Mutex<Vec<LedgerAddressGenerationHandler>>>;
 type TransferProgressListeners = Arc<Mutex<Vec<TransferProgressHandler>>>;
+type PaymentReceivedListeners = Arc<Mutex<Vec<PaymentReceivedHandler>>>;
+type StorageQuotaWarningListeners = Arc<Mutex<Vec<StorageQuotaWarningHandler>>>;
+type StorageQuotaExceededListeners = Arc<Mutex<Vec<StorageQuotaExceededHandler>>>;
+type AccountLifecycleListeners = Arc<Mutex<Vec<AccountLifecycleHandler>>>;
+type MonitoringResubscribedListeners = Arc<Mutex<Vec<MonitoringResubscribedHandler>>>;
 type MigrationProgressListeners = Arc<Mutex<Vec<MigrationProgressHandler>>>;
+type EventBatchListeners = Arc<Mutex<Vec<EventBatchHandler>>>;
+type OutputLockReleasedListeners = Arc<Mutex<Vec<OutputLockReleasedHandler>>>;
+type AddressDerivationResumedListeners = Arc<Mutex<Vec<AddressDerivationResumedHandler>>>;
+type AccountDiscoveryProgressListeners = Arc<Mutex<Vec<AccountDiscoveryProgressHandler>>>;
 
 fn generate_event_id() -> EventId {
     let mut id = [0; 32];
@@ -391,6 +748,279 @@ async fn remove_event_listener<T: EventHandler>(id: &EventId, listeners: &Arc<Mu
     }
 }
 
+/// Calls the listener, catching its panic so the remaining listeners still get the event.
+fn call_listener<E>(on_event: &(dyn Fn(&E) + Send), event: &E) -> Result<(), String> {
+    catch_unwind(AssertUnwindSafe(|| on_event(event))).map_err(|payload| {
+        payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "the listener panicked".to_string())
+    })
+}
+
+/// Persists the failed deliveries of the event so they can be redelivered.
+async fn save_failed_deliveries(
+    storage_path: &Path,
+    event: AccountEvent,
+    failures: Vec<(EventId, String)>,
+) -> crate::Result<()> {
+    if failures.is_empty() {
+        return Ok(());
+    }
+    let storage_handle = crate::storage::get(storage_path).await?;
+    let mut storage = storage_handle.lock().await;
+    let mut deliveries = storage.get_failed_deliveries().await?;
+    for (listener, error) in failures {
+        log::warn!(
+            "[EVENT] listener failed to handle event {}: {}",
+            event.dedup_key(),
+            error
+        );
+        deliveries.push(FailedDelivery {
+            id: generate_indexation_id(),
+            event: event.clone(),
+            listener,
+            error,
+            attempts: 1,
+        });
+    }
+    storage.save_failed_deliveries(&deliveries).await
+}
+
+/// Delivers the event to the listener that failed to handle it if it's still registered, otherwise to all
+/// listeners, since they're registered again with new identifiers after a restart.
+fn redeliver_to<E>(
+    listeners: Vec<(EventId, &Box<dyn Fn(&E) + Send>)>,
+    failed_listener: &EventId,
+    event: &E,
+) -> Vec<(EventId, String)> {
+    let targets: Vec<_> = if listeners.iter().any(|(id, _)| id == failed_listener) {
+        listeners.into_iter().filter(|(id, _)| id == failed_listener).collect()
+    } else {
+        listeners
+    };
+    if targets.is_empty() {
+        return vec![(*failed_listener, "no listener registered".to_string())];
+    }
+    targets
+        .into_iter()
+        .filter_map(|(id, on_event)| call_listener(&**on_event, event).err().map(|error| (id, error)))
+        .collect()
+}
+
+async fn redeliver(delivery: &FailedDelivery) -> Vec<(EventId, String)> {
+    match &delivery.event {
+        AccountEvent::BalanceChange(event) => {
+            let listeners = balance_listeners().lock().await;
+            let listeners = listeners.iter().map(|l| (l.id, &l.on_event)).collect();
+            redeliver_to(listeners, &delivery.listener, event)
+        }
+        AccountEvent::NewTransaction(event) | AccountEvent::Broadcast(event) => {
+            let event_type = match delivery.event {
+                AccountEvent::Broadcast(_) => TransactionEventType::Broadcast,
+                _ => TransactionEventType::NewTransaction,
+            };
+            let listeners = transaction_listeners().lock().await;
+            let listeners = listeners
+                .iter()
+                .filter(|l| l.event_type == event_type)
+                .map(|l| (l.id, &l.on_event))
+                .collect();
+            redeliver_to(listeners, &delivery.listener, event)
+        }
+        AccountEvent::ConfirmationStateChange(event) => {
+            let listeners = transaction_confirmation_change_listeners().lock().await;
+            let listeners = listeners.iter().map(|l| (l.id, &l.on_event)).collect();
+            redeliver_to(listeners, &delivery.listener, event)
+        }
+        AccountEvent::Reattachment(event) => {
+            let listeners = transaction_reattachment_listeners().lock().await;
+            let listeners = listeners.iter().map(|l| (l.id, &l.on_event)).collect();
+            redeliver_to(listeners, &delivery.listener, event)
+        }
+    }
+}
+
+/// Gets the account events listeners failed to handle, on all storages.
+pub async fn failed_deliveries() -> crate::Result<Vec<FailedDelivery>> {
+    let mut deliveries = Vec::new();
+    for storage_handle in crate::storage::get_all().await {
+        deliveries.extend(storage_handle.lock().await.get_failed_deliveries().await?);
+    }
+    Ok(deliveries)
+}
+
+/// Delivers again the account events listeners failed to handle (a listener failure is a panic of its
+/// callback). Events handled this time are removed from the dead-letter store, the others are kept with an
+/// increased attempt count. Returns the number of events delivered.
+/// A redelivered event keeps its sequence number and deduplication key, so listeners can ignore the events
+/// they already handled.
+pub async fn redeliver_failed() -> crate::Result<usize> {
+    let mut delivered = 0;
+    for storage_handle in crate::storage::get_all().await {
+        let deliveries = storage_handle.lock().await.get_failed_deliveries().await?;
+        if deliveries.is_empty() {
+            continue;
+        }
+        // the storage isn't locked while calling the listeners
+        let mut results = HashMap::new();
+        for delivery in &deliveries {
+            results.insert(delivery.id.clone(), redeliver(delivery).await);
+        }
+
+        let mut storage = storage_handle.lock().await;
+        // reloaded since events may have failed meanwhile
+        let mut deliveries = storage.get_failed_deliveries().await?;
+        let mut remaining = Vec::new();
+        for mut delivery in storage.get_failed_deliveries().await? {
+            match results.get(&delivery.id) {
+                Some(failures) if failures.is_empty() => delivered += 1,
+                Some(failures) => {
+                    let (listener, error) = failures[0].clone();
+                    delivery.listener = listener;
+                    delivery.error = error;
+                    delivery.attempts += 1;
+                    remaining.push(delivery);
+                }
+                None => remaining.push(delivery),
+            }
+        }
+        storage.save_failed_deliveries(&remaining).await?;
+    }
+    Ok(delivered)
+}
+
+/// The pending batch of account events, when batching is enabled.
+struct EventBatcher {
+    options: EventBatchOptions,
+    events: Vec<AccountEvent>,
+    oldest: Option<Instant>,
+    /// Identifies the configuration, so the flush task of a replaced configuration stops.
+    generation: u64,
+}
+
+fn event_batcher() -> &'static StdMutex<Option<EventBatcher>> {
+    static BATCHER: Lazy<StdMutex<Option<EventBatcher>>> = Lazy::new(Default::default);
+    &BATCHER
+}
+
+/// Whether the individual account event listeners are called, i.e. batching is disabled or in compatibility mode.
+fn emit_individual_events() -> bool {
+    event_batcher()
+        .lock()
+        .expect("event batcher poisoned")
+        .as_ref()
+        .map(|batcher| batcher.options.emit_individual_events)
+        .unwrap_or(true)
+}
+
+/// Enables batching of the account events (balance changes, new transactions, broadcasts, confirmation changes and
+/// reattachments) with the given options, or disables it with `None`, delivering the pending batch.
+/// Syncs emitting thousands of events then call the [on_event_batch](fn.on_event_batch.html) listeners once per
+/// batch instead of once per event. Unless `emit_individual_events` is set, the individual listeners aren't called
+/// for the batched events anymore.
+pub async fn set_event_batching(options: Option<EventBatchOptions>) {
+    let previous = {
+        static GENERATION: AtomicU64 = AtomicU64::new(0);
+        let mut batcher = event_batcher().lock().expect("event batcher poisoned");
+        let generation = GENERATION.fetch_add(1, Ordering::SeqCst);
+        let previous = batcher.take();
+        if let Some(options) = options {
+            batcher.replace(EventBatcher {
+                options,
+                events: Vec::new(),
+                oldest: None,
+                generation,
+            });
+            crate::spawn(flush_event_batches_periodically(generation));
+        }
+        previous
+    };
+    if let Some(previous) = previous {
+        deliver_event_batch(previous.events).await;
+    }
+}
+
+async fn flush_event_batches_periodically(generation: u64) {
+    loop {
+        let interval = {
+            let batcher = event_batcher().lock().expect("event batcher poisoned");
+            match batcher.as_ref() {
+                Some(batcher) if batcher.generation == generation => batcher.options.flush_interval,
+                _ => return,
+            }
+        };
+        tokio::time::sleep(interval).await;
+        let events = {
+            let mut batcher = event_batcher().lock().expect("event batcher poisoned");
+            match batcher.as_mut() {
+                Some(batcher) if batcher.generation == generation => match batcher.oldest {
+                    Some(oldest) if oldest.elapsed() >= batcher.options.flush_interval => {
+                        batcher.oldest = None;
+                        std::mem::take(&mut batcher.events)
+                    }
+                    _ => continue,
+                },
+                _ => return,
+            }
+        };
+        deliver_event_batch(events).await;
+    }
+}
+
+/// Adds the event to the pending batch, delivering it if it's full.
+async fn batch_event(event: &AccountEvent) {
+    let events = {
+        let mut batcher = event_batcher().lock().expect("event batcher poisoned");
+        let batcher = match batcher.as_mut() {
+            Some(batcher) => batcher,
+            None => return,
+        };
+        batcher.events.push(event.clone());
+        batcher.oldest.get_or_insert_with(Instant::now);
+        if batcher.events.len() < batcher.options.max_size {
+            return;
+        }
+        batcher.oldest = None;
+        std::mem::take(&mut batcher.events)
+    };
+    deliver_event_batch(events).await;
+}
+
+/// Delivers the pending batch of account events right away, e.g. at the end of a sync.
+pub async fn flush_event_batch() {
+    let events = {
+        let mut batcher = event_batcher().lock().expect("event batcher poisoned");
+        match batcher.as_mut() {
+            Some(batcher) => {
+                batcher.oldest = None;
+                std::mem::take(&mut batcher.events)
+            }
+            None => return,
+        }
+    };
+    deliver_event_batch(events).await;
+}
+
+async fn deliver_event_batch(events: Vec<AccountEvent>) {
+    if events.is_empty() {
+        return;
+    }
+    let listeners = event_batch_listeners().lock().await;
+    let batch = EventBatch { events };
+    for listener in listeners.deref() {
+        // the events are persisted individually, a failed batch is only logged
+        if let Err(error) = call_listener(&*listener.on_event, &batch) {
+            log::warn!(
+                "[EVENT] listener failed to handle a batch of {} events: {}",
+                batch.events.len(),
+                error
+            );
+        }
+    }
+}
+
 /// Gets the balance change listeners array.
 fn balance_listeners() -> &'static BalanceListeners {
     static LISTENERS: Lazy<BalanceListeners> = Lazy::new(Default::default);
@@ -447,11 +1077,56 @@ fn transfer_progress_listeners() -> &'static TransferProgressListeners {
     &LISTENERS
 }
 
+fn payment_received_listeners() -> &'static PaymentReceivedListeners {
+    static LISTENERS: Lazy<PaymentReceivedListeners> = Lazy::new(Default::default);
+    &LISTENERS
+}
+
+fn storage_quota_warning_listeners() -> &'static StorageQuotaWarningListeners {
+    static LISTENERS: Lazy<StorageQuotaWarningListeners> = Lazy::new(Default::default);
+    &LISTENERS
+}
+
+fn storage_quota_exceeded_listeners() -> &'static StorageQuotaExceededListeners {
+    static LISTENERS: Lazy<StorageQuotaExceededListeners> = Lazy::new(Default::default);
+    &LISTENERS
+}
+
+fn account_lifecycle_listeners() -> &'static AccountLifecycleListeners {
+    static LISTENERS: Lazy<AccountLifecycleListeners> = Lazy::new(Default::default);
+    &LISTENERS
+}
+
+fn monitoring_resubscribed_listeners() -> &'static MonitoringResubscribedListeners {
+    static LISTENERS: Lazy<MonitoringResubscribedListeners> = Lazy::new(Default::default);
+    &LISTENERS
+}
+
 fn migration_progress_listeners() -> &'static MigrationProgressListeners {
     static LISTENERS: Lazy<MigrationProgressListeners> = Lazy::new(Default::default);
     &LISTENERS
 }
 
+fn event_batch_listeners() -> &'static EventBatchListeners {
+    static LISTENERS: Lazy<EventBatchListeners> = Lazy::new(Default::default);
+    &LISTENERS
+}
+
+fn output_lock_released_listeners() -> &'static OutputLockReleasedListeners {
+    static LISTENERS: Lazy<OutputLockReleasedListeners> = Lazy::new(Default::default);
+    &LISTENERS
+}
+
+fn address_derivation_resumed_listeners() -> &'static AddressDerivationResumedListeners {
+    static LISTENERS: Lazy<AddressDerivationResumedListeners> = Lazy::new(Default::default);
+    &LISTENERS
+}
+
+fn account_discovery_progress_listeners() -> &'static AccountDiscoveryProgressListeners {
+    static LISTENERS: Lazy<AccountDiscoveryProgressListeners> = Lazy::new(Default::default);
+    &LISTENERS
+}
+
 /// Listen to balance changes.
 pub async fn on_balance_change<F: Fn(&BalanceEvent) + Send + 'static>(cb: F) -> EventId {
     let mut l = balance_listeners().lock().await;
@@ -488,8 +1163,26 @@ pub(crate) async fn emit_balance_change(
             None => None,
         }
     };
+    let indexation_id = generate_indexation_id();
+    // without the message id we can't tell two balance changes apart, so the event is never deduplicated
+    let dedup_key = match message_id {
+        Some(message_id) => format!(
+            "balance-{}-{}-{}-{}",
+            address.to_bech32(),
+            message_id,
+            balance_change.spent,
+            balance_change.received
+        ),
+        None => indexation_id.clone(),
+    };
+
+    let storage_handle = crate::storage::get(account.storage_path()).await?;
+    let mut storage = storage_handle.lock().await;
+    let (sequence, duplicate) = storage.next_event_sequence(account.id(), &dedup_key, persist).await?;
     let event = BalanceEvent {
-        indexation_id: generate_indexation_id(),
+        indexation_id,
+        sequence,
+        dedup_key,
         account_id: account.id().to_string(),
         address: address.clone(),
         message_id,
@@ -497,20 +1190,32 @@ pub(crate) async fn emit_balance_change(
         balance_change,
     };
 
-    if persist {
-        crate::storage::get(account.storage_path())
-            .await?
-            .lock()
-            .await
-            .save_balance_change_event(&event)
+    if persist && !duplicate {
+        storage.save_balance_change_event(&event).await?;
+        storage
+            .log_event(
+                account.id(),
+                sequence,
+                EventKind::BalanceChange,
+                &event.indexation_id,
+                &event.dedup_key,
+            )
             .await?;
     }
+    drop(storage);
 
-    for listener in listeners.deref() {
-        (listener.on_event)(&event);
+    let mut failures = Vec::new();
+    if emit_individual_events() {
+        for listener in listeners.deref() {
+            if let Err(error) = call_listener(&*listener.on_event, &event) {
+                failures.push((listener.id, error));
+            }
+        }
     }
-
-    Ok(())
+    drop(listeners);
+    let event = AccountEvent::BalanceChange(event);
+    batch_event(&event).await;
+    save_failed_deliveries(account.storage_path(), event, failures).await
 }
 
 /// Emits a transaction-related event.
@@ -521,15 +1226,25 @@ pub(crate) async fn emit_transaction_event(
     persist: bool,
 ) -> crate::Result<()> {
     let listeners = transaction_listeners().lock().await;
+    let (kind, dedup_key) = match event_type {
+        TransactionEventType::Broadcast => (EventKind::Broadcast, format!("broadcast-{}", message.id())),
+        TransactionEventType::NewTransaction => {
+            (EventKind::NewTransaction, format!("new-transaction-{}", message.id()))
+        }
+    };
+
+    let storage_handle = crate::storage::get(account.storage_path()).await?;
+    let mut storage = storage_handle.lock().await;
+    let (sequence, duplicate) = storage.next_event_sequence(account.id(), &dedup_key, persist).await?;
     let event = TransactionEvent {
         indexation_id: generate_indexation_id(),
+        sequence,
+        dedup_key,
         account_id: account.id().to_string(),
         message,
     };
 
-    if persist {
-        let storage_handle = crate::storage::get(account.storage_path()).await?;
-        let mut storage = storage_handle.lock().await;
+    if persist && !duplicate {
         match event_type {
             TransactionEventType::Broadcast => {
                 storage.save_broadcast_event(&event).await?;
@@ -538,15 +1253,29 @@ pub(crate) async fn emit_transaction_event(
                 storage.save_new_transaction_event(&event).await?;
             }
         }
+        storage
+            .log_event(account.id(), sequence, kind, &event.indexation_id, &event.dedup_key)
+            .await?;
     }
-
-    for listener in listeners.deref() {
-        if listener.event_type == event_type {
-            (listener.on_event)(&event);
+    drop(storage);
+
+    let mut failures = Vec::new();
+    if emit_individual_events() {
+        for listener in listeners.deref() {
+            if listener.event_type == event_type {
+                if let Err(error) = call_listener(&*listener.on_event, &event) {
+                    failures.push((listener.id, error));
+                }
+            }
         }
     }
-
-    Ok(())
+    drop(listeners);
+    let event = match event_type {
+        TransactionEventType::Broadcast => AccountEvent::Broadcast(event),
+        TransactionEventType::NewTransaction => AccountEvent::NewTransaction(event),
+    };
+    batch_event(&event).await;
+    save_failed_deliveries(account.storage_path(), event, failures).await
 }
 
 /// Emits a transaction confirmation state change event.
@@ -557,27 +1286,46 @@ pub(crate) async fn emit_confirmation_state_change(
     persist: bool,
 ) -> crate::Result<()> {
     let listeners = transaction_confirmation_change_listeners().lock().await;
+    let dedup_key = format!("confirmation-{}-{}", message.id(), confirmed);
+
+    let storage_handle = crate::storage::get(account.storage_path()).await?;
+    let mut storage = storage_handle.lock().await;
+    let (sequence, duplicate) = storage.next_event_sequence(account.id(), &dedup_key, persist).await?;
     let event = TransactionConfirmationChangeEvent {
         indexation_id: generate_indexation_id(),
+        sequence,
+        dedup_key,
         account_id: account.id().to_string(),
         message,
         confirmed,
     };
 
-    if persist {
-        crate::storage::get(account.storage_path())
-            .await?
-            .lock()
-            .await
-            .save_transaction_confirmation_event(&event)
+    if persist && !duplicate {
+        storage.save_transaction_confirmation_event(&event).await?;
+        storage
+            .log_event(
+                account.id(),
+                sequence,
+                EventKind::ConfirmationStateChange,
+                &event.indexation_id,
+                &event.dedup_key,
+            )
             .await?;
     }
+    drop(storage);
 
-    for listener in listeners.deref() {
-        (listener.on_event)(&event);
+    let mut failures = Vec::new();
+    if emit_individual_events() {
+        for listener in listeners.deref() {
+            if let Err(error) = call_listener(&*listener.on_event, &event) {
+                failures.push((listener.id, error));
+            }
+        }
     }
-
-    Ok(())
+    drop(listeners);
+    let event = AccountEvent::ConfirmationStateChange(event);
+    batch_event(&event).await;
+    save_failed_deliveries(account.storage_path(), event, failures).await
 }
 
 /// Emits a transaction reattachment change event.
@@ -588,27 +1336,46 @@ pub(crate) async fn emit_reattachment_event(
     persist: bool,
 ) -> crate::Result<()> {
     let listeners = transaction_reattachment_listeners().lock().await;
+    let dedup_key = format!("reattachment-{}-{}", reattached_message_id, message.id());
+
+    let storage_handle = crate::storage::get(account.storage_path()).await?;
+    let mut storage = storage_handle.lock().await;
+    let (sequence, duplicate) = storage.next_event_sequence(account.id(), &dedup_key, persist).await?;
     let event = TransactionReattachmentEvent {
         indexation_id: generate_indexation_id(),
+        sequence,
+        dedup_key,
         account_id: account.id().to_string(),
         message: message.clone(),
         reattached_message_id,
     };
 
-    if persist {
-        crate::storage::get(account.storage_path())
-            .await?
-            .lock()
-            .await
-            .save_reattachment_event(&event)
+    if persist && !duplicate {
+        storage.save_reattachment_event(&event).await?;
+        storage
+            .log_event(
+                account.id(),
+                sequence,
+                EventKind::Reattachment,
+                &event.indexation_id,
+                &event.dedup_key,
+            )
             .await?;
     }
+    drop(storage);
 
-    for listener in listeners.deref() {
-        (listener.on_event)(&event);
+    let mut failures = Vec::new();
+    if emit_individual_events() {
+        for listener in listeners.deref() {
+            if let Err(error) = call_listener(&*listener.on_event, &event) {
+                failures.push((listener.id, error));
+            }
+        }
     }
-
-    Ok(())
+    drop(listeners);
+    let event = AccountEvent::Reattachment(event);
+    batch_event(&event).await;
+    save_failed_deliveries(account.storage_path(), event, failures).await
 }
 
 /// Adds a transaction-related event listener.
@@ -819,15 +1586,242 @@ pub async fn remove_transfer_progress_listener(id: &EventId) {
 }
 
 /// Emit a transfer event.
-pub(crate) async fn emit_transfer_progress(account_id: String, event: TransferProgressType) {
+pub(crate) async fn emit_transfer_progress(
+    account_id: String,
+    event: TransferProgressType,
+    previous_stage_duration: Option<u64>,
+    context: Option<serde_json::Value>,
+) {
     let listeners = transfer_progress_listeners().lock().await;
-    let event = TransferProgress { account_id, event };
+    let event = TransferProgress {
+        account_id,
+        event,
+        timestamp: Utc::now(),
+        previous_stage_duration,
+        context,
+    };
+
+    for listener in listeners.deref() {
+        (listener.on_event)(&event);
+    }
+}
+
+/// Listen to payments received on the addresses watched with
+/// [AccountHandle#watch_address_for_payment](../account/struct.AccountHandle.html#method.watch_address_for_payment).
+pub async fn on_payment_received<F: Fn(&PaymentReceived) + Send + 'static>(cb: F) -> EventId {
+    let mut l = payment_received_listeners().lock().await;
+    let id = generate_event_id();
+    l.push(PaymentReceivedHandler {
+        id,
+        on_event: Box::new(cb),
+    });
+    id
+}
+
+/// Remove a payment received listener.
+pub async fn remove_payment_received_listener(id: &EventId) {
+    remove_event_listener(id, payment_received_listeners()).await;
+}
+
+/// Emit a payment received event.
+pub(crate) async fn emit_payment_received(
+    account_id: String,
+    address: AddressWrapper,
+    message_id: MessageId,
+    amount: u64,
+) {
+    let listeners = payment_received_listeners().lock().await;
+    let event = PaymentReceived {
+        account_id,
+        address,
+        message_id,
+        amount,
+    };
 
     for listener in listeners.deref() {
         (listener.on_event)(&event);
     }
 }
 
+/// Listen to the storage nearing its configured quota.
+pub async fn on_storage_quota_warning<F: Fn(&StorageQuotaEvent) + Send + 'static>(cb: F) -> EventId {
+    let mut l = storage_quota_warning_listeners().lock().await;
+    let id = generate_event_id();
+    l.push(StorageQuotaWarningHandler {
+        id,
+        on_event: Box::new(cb),
+    });
+    id
+}
+
+/// Remove a storage quota warning listener.
+pub async fn remove_storage_quota_warning_listener(id: &EventId) {
+    remove_event_listener(id, storage_quota_warning_listeners()).await;
+}
+
+/// Emit a storage quota warning event.
+pub(crate) async fn emit_storage_quota_warning(used_bytes: u64, max_bytes: u64) {
+    let listeners = storage_quota_warning_listeners().lock().await;
+    let event = StorageQuotaEvent { used_bytes, max_bytes };
+    for listener in listeners.deref() {
+        (listener.on_event)(&event);
+    }
+}
+
+/// Listen to the storage exceeding its configured quota.
+pub async fn on_storage_quota_exceeded<F: Fn(&StorageQuotaEvent) + Send + 'static>(cb: F) -> EventId {
+    let mut l = storage_quota_exceeded_listeners().lock().await;
+    let id = generate_event_id();
+    l.push(StorageQuotaExceededHandler {
+        id,
+        on_event: Box::new(cb),
+    });
+    id
+}
+
+/// Remove a storage quota exceeded listener.
+pub async fn remove_storage_quota_exceeded_listener(id: &EventId) {
+    remove_event_listener(id, storage_quota_exceeded_listeners()).await;
+}
+
+/// Emit a storage quota exceeded event.
+pub(crate) async fn emit_storage_quota_exceeded(used_bytes: u64, max_bytes: u64) {
+    let listeners = storage_quota_exceeded_listeners().lock().await;
+    let event = StorageQuotaEvent { used_bytes, max_bytes };
+    for listener in listeners.deref() {
+        (listener.on_event)(&event);
+    }
+}
+
+/// Listen to accounts being loaded or evicted from memory.
+pub async fn on_account_lifecycle<F: Fn(&AccountLifecycleEvent) + Send + 'static>(cb: F) -> EventId {
+    let mut l = account_lifecycle_listeners().lock().await;
+    let id = generate_event_id();
+    l.push(AccountLifecycleHandler {
+        id,
+        on_event: Box::new(cb),
+    });
+    id
+}
+
+/// Remove an account lifecycle listener.
+pub async fn remove_account_lifecycle_listener(id: &EventId) {
+    remove_event_listener(id, account_lifecycle_listeners()).await;
+}
+
+/// Emit an account lifecycle event.
+pub(crate) async fn emit_account_lifecycle(account_id: String, lifecycle: AccountLifecycle) {
+    let listeners = account_lifecycle_listeners().lock().await;
+    let event = AccountLifecycleEvent { account_id, lifecycle };
+    for listener in listeners.deref() {
+        (listener.on_event)(&event);
+    }
+}
+
+/// Listen to the account subscriptions moving to a new MQTT broker after a client options change.
+pub async fn on_monitoring_resubscribed<F: Fn(&MonitoringResubscribed) + Send + 'static>(cb: F) -> EventId {
+    let mut l = monitoring_resubscribed_listeners().lock().await;
+    let id = generate_event_id();
+    l.push(MonitoringResubscribedHandler {
+        id,
+        on_event: Box::new(cb),
+    });
+    id
+}
+
+/// Remove a monitoring resubscribed listener.
+pub async fn remove_monitoring_resubscribed_listener(id: &EventId) {
+    remove_event_listener(id, monitoring_resubscribed_listeners()).await;
+}
+
+/// Emit a monitoring resubscribed event.
+pub(crate) async fn emit_monitoring_resubscribed(account_id: String, topics: usize) {
+    let listeners = monitoring_resubscribed_listeners().lock().await;
+    let event = MonitoringResubscribed { account_id, topics };
+    for listener in listeners.deref() {
+        (listener.on_event)(&event);
+    }
+}
+
+/// Listen to the output locks released because the transfer holding them didn't finish within the lock lease.
+pub async fn on_output_lock_released<F: Fn(&OutputLockReleased) + Send + 'static>(cb: F) -> EventId {
+    let mut l = output_lock_released_listeners().lock().await;
+    let id = generate_event_id();
+    l.push(OutputLockReleasedHandler {
+        id,
+        on_event: Box::new(cb),
+    });
+    id
+}
+
+/// Remove an output lock released listener.
+pub async fn remove_output_lock_released_listener(id: &EventId) {
+    remove_event_listener(id, output_lock_released_listeners()).await;
+}
+
+/// Emit an output lock released event.
+pub(crate) async fn emit_output_lock_released(account_id: String, output: AddressOutput, locked_for: Duration) {
+    let listeners = output_lock_released_listeners().lock().await;
+    let event = OutputLockReleased {
+        account_id,
+        output,
+        locked_for_ms: locked_for.as_millis() as u64,
+    };
+    for listener in listeners.deref() {
+        (listener.on_event)(&event);
+    }
+}
+
+/// Listen to the address derivations resumed once Stronghold is unlocked.
+pub async fn on_address_derivation_resumed<F: Fn(&AddressDerivationResumed) + Send + 'static>(cb: F) -> EventId {
+    let mut l = address_derivation_resumed_listeners().lock().await;
+    let id = generate_event_id();
+    l.push(AddressDerivationResumedHandler {
+        id,
+        on_event: Box::new(cb),
+    });
+    id
+}
+
+/// Remove an address derivation resumed listener.
+pub async fn remove_address_derivation_resumed_listener(id: &EventId) {
+    remove_event_listener(id, address_derivation_resumed_listeners()).await;
+}
+
+/// Emit an address derivation resumed event.
+pub(crate) async fn emit_address_derivation_resumed(account_id: String, addresses: Vec<String>) {
+    let listeners = address_derivation_resumed_listeners().lock().await;
+    let event = AddressDerivationResumed { account_id, addresses };
+    for listener in listeners.deref() {
+        (listener.on_event)(&event);
+    }
+}
+
+/// Listen to the account indexes checked by the account discovery, e.g. to show the progress of a restore.
+pub async fn on_account_discovery_progress<F: Fn(&AccountDiscoveryProgress) + Send + 'static>(cb: F) -> EventId {
+    let mut l = account_discovery_progress_listeners().lock().await;
+    let id = generate_event_id();
+    l.push(AccountDiscoveryProgressHandler {
+        id,
+        on_event: Box::new(cb),
+    });
+    id
+}
+
+/// Remove an account discovery progress listener.
+pub async fn remove_account_discovery_progress_listener(id: &EventId) {
+    remove_event_listener(id, account_discovery_progress_listeners()).await;
+}
+
+/// Emit an account discovery progress event.
+pub(crate) async fn emit_account_discovery_progress(account_index: usize, status: AccountDiscoveryStatus) {
+    let listeners = account_discovery_progress_listeners().lock().await;
+    let event = AccountDiscoveryProgress { account_index, status };
+    for listener in listeners.deref() {
+        (listener.on_event)(&event);
+    }
+}
+
 /// Listen to a migration event.
 pub async fn on_migration_progress<F: Fn(&MigrationProgress) + Send + 'static>(cb: F) -> EventId {
     let mut l = migration_progress_listeners().lock().await;
@@ -844,6 +1838,22 @@ pub async fn remove_migration_progress_listener(id: &EventId) {
     remove_event_listener(id, migration_progress_listeners()).await;
 }
 
+/// Listen to the account event batches, see [set_event_batching](fn.set_event_batching.html).
+pub async fn on_event_batch<F: Fn(&EventBatch) + Send + 'static>(cb: F) -> EventId {
+    let mut l = event_batch_listeners().lock().await;
+    let id = generate_event_id();
+    l.push(EventBatchHandler {
+        id,
+        on_event: Box::new(cb),
+    });
+    id
+}
+
+/// Remove an event batch listener.
+pub async fn remove_event_batch_listener(id: &EventId) {
+    remove_event_listener(id, event_batch_listeners()).await;
+}
+
 /// Emit a migration event.
 pub(crate) async fn emit_migration_progress(event: MigrationProgressType) {
     let listeners = migration_progress_listeners().lock().await;
@@ -863,6 +1873,68 @@ mod tests {
         let _ = crate::Error::RecordNotFound;
     }
 
+    #[tokio::test]
+    async fn event_sequence() {
+        let manager = crate::testing::get_account_manager().await;
+        let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
+        let message = crate::testing::GenerateMessageBuilder::default().build().await;
+        {
+            let account = account_handle.read().await;
+            emit_confirmation_state_change(&account, message.clone(), true, true)
+                .await
+                .unwrap();
+            // re-emitted after a re-sync
+            emit_confirmation_state_change(&account, message.clone(), true, true)
+                .await
+                .unwrap();
+            emit_transaction_event(TransactionEventType::Broadcast, &account, message, true)
+                .await
+                .unwrap();
+        }
+
+        let events = account_handle.get_events_since(0).await.unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(matches!(events[0], AccountEvent::ConfirmationStateChange(_)));
+        assert_eq!(events[0].sequence(), 1);
+        assert_eq!(events[1].sequence(), 2);
+        assert_eq!(account_handle.get_events_since(1).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn transient_event_sequence() {
+        let manager = crate::testing::get_account_manager().await;
+        let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
+        let message = crate::testing::GenerateMessageBuilder::default().build().await;
+        {
+            let account = account_handle.read().await;
+            let storage_handle = crate::storage::get(account.storage_path()).await.unwrap();
+            let mut storage = storage_handle.lock().await;
+            // the events that aren't persisted are numbered and deduplicated in memory
+            assert_eq!(
+                storage
+                    .next_event_sequence(account.id(), "transient", false)
+                    .await
+                    .unwrap(),
+                (1, false)
+            );
+            assert_eq!(
+                storage
+                    .next_event_sequence(account.id(), "transient", false)
+                    .await
+                    .unwrap(),
+                (1, true)
+            );
+            drop(storage);
+            emit_confirmation_state_change(&account, message, true, true)
+                .await
+                .unwrap();
+        }
+
+        let events = account_handle.get_events_since(0).await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].sequence(), 2);
+    }
+
     // have to fork this test so other errors dropped doesn't affect it
     rusty_fork_test! {
         #[test]
@@ -879,8 +1951,8 @@ mod tests {
         fn balance_events() {
             let runtime = tokio::runtime::Runtime::new().unwrap();
             runtime.block_on(async {
-                let manager = crate::test_utils::get_account_manager().await;
-                let account_handle = crate::test_utils::AccountCreator::new(&manager).create().await;
+                let manager = crate::testing::get_account_manager().await;
+                let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
                 let account = account_handle.read().await;
                 let account_id = account.id().to_string();
                 on_balance_change(move |event| {
@@ -892,13 +1964,15 @@ mod tests {
 
                 emit_balance_change(
                     &account,
-                    &crate::test_utils::generate_random_iota_address(),
+                    &crate::testing::generate_random_iota_address(),
                     None,
                     BalanceChange::spent(5),
                     true,
                 )
                 .await
                 .unwrap();
+                // listener panics are caught and kept as failed deliveries
+                assert!(failed_deliveries().await.unwrap().is_empty());
             });
         }
 
@@ -906,11 +1980,11 @@ mod tests {
         fn on_new_transaction_event() {
             let runtime = tokio::runtime::Runtime::new().unwrap();
             runtime.block_on(async {
-                let manager = crate::test_utils::get_account_manager().await;
-                let account_handle = crate::test_utils::AccountCreator::new(&manager).create().await;
+                let manager = crate::testing::get_account_manager().await;
+                let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
                 let account = account_handle.read().await;
                 let account_id = account.id().to_string();
-                let message = crate::test_utils::GenerateMessageBuilder::default().build().await;
+                let message = crate::testing::GenerateMessageBuilder::default().build().await;
                 let message_ = message.clone();
 
                 on_new_transaction(move |event| {
@@ -922,6 +1996,7 @@ mod tests {
                 emit_transaction_event(TransactionEventType::NewTransaction, &account, message, true)
                     .await
                     .unwrap();
+                assert!(failed_deliveries().await.unwrap().is_empty());
             });
         }
 
@@ -929,11 +2004,11 @@ mod tests {
         fn on_reattachment_event() {
             let runtime = tokio::runtime::Runtime::new().unwrap();
             runtime.block_on(async {
-                let manager = crate::test_utils::get_account_manager().await;
-                let account_handle = crate::test_utils::AccountCreator::new(&manager).create().await;
+                let manager = crate::testing::get_account_manager().await;
+                let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
                 let account = account_handle.read().await;
                 let account_id = account.id().to_string();
-                let message = crate::test_utils::GenerateMessageBuilder::default().build().await;
+                let message = crate::testing::GenerateMessageBuilder::default().build().await;
                 let message_ = message.clone();
 
                 on_reattachment(move |event| {
@@ -945,6 +2020,7 @@ mod tests {
                 emit_reattachment_event(&account, *message.id(), &message, true)
                     .await
                     .unwrap();
+                assert!(failed_deliveries().await.unwrap().is_empty());
             });
         }
 
@@ -952,11 +2028,11 @@ mod tests {
         fn on_broadcast_event() {
             let runtime = tokio::runtime::Runtime::new().unwrap();
             runtime.block_on(async {
-                let manager = crate::test_utils::get_account_manager().await;
-                let account_handle = crate::test_utils::AccountCreator::new(&manager).create().await;
+                let manager = crate::testing::get_account_manager().await;
+                let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
                 let account = account_handle.read().await;
                 let account_id = account.id().to_string();
-                let message = crate::test_utils::GenerateMessageBuilder::default().build().await;
+                let message = crate::testing::GenerateMessageBuilder::default().build().await;
                 let message_ = message.clone();
 
                 on_broadcast(move |event| {
@@ -968,6 +2044,7 @@ mod tests {
                 emit_transaction_event(TransactionEventType::Broadcast, &account, message, true)
                     .await
                     .unwrap();
+                assert!(failed_deliveries().await.unwrap().is_empty());
             });
         }
 
@@ -975,11 +2052,11 @@ mod tests {
         fn on_confirmation_state_change_event() {
             let runtime = tokio::runtime::Runtime::new().unwrap();
             runtime.block_on(async {
-                let manager = crate::test_utils::get_account_manager().await;
-                let account_handle = crate::test_utils::AccountCreator::new(&manager).create().await;
+                let manager = crate::testing::get_account_manager().await;
+                let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
                 let account = account_handle.read().await;
                 let account_id = account.id().to_string();
-                let message = crate::test_utils::GenerateMessageBuilder::default().build().await;
+                let message = crate::testing::GenerateMessageBuilder::default().build().await;
                 let message_ = message.clone();
                 let confirmed = true;
 
@@ -993,6 +2070,81 @@ mod tests {
                 emit_confirmation_state_change(&account, message.clone(), confirmed, true)
                     .await
                     .unwrap();
+                assert!(failed_deliveries().await.unwrap().is_empty());
+            });
+        }
+
+        #[test]
+        fn failed_event_redelivery() {
+            let runtime = tokio::runtime::Runtime::new().unwrap();
+            runtime.block_on(async {
+                let manager = crate::testing::get_account_manager().await;
+                let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
+                let account = account_handle.read().await;
+                let failed = Arc::new(std::sync::atomic::AtomicBool::new(false));
+                let delivered = Arc::new(std::sync::atomic::AtomicBool::new(false));
+                let (failed_, delivered_) = (failed.clone(), delivered.clone());
+                on_balance_change(move |_| {
+                    if !failed_.swap(true, std::sync::atomic::Ordering::SeqCst) {
+                        panic!("listener unavailable");
+                    }
+                    delivered_.store(true, std::sync::atomic::Ordering::SeqCst);
+                })
+                .await;
+
+                emit_balance_change(
+                    &account,
+                    &crate::testing::generate_random_iota_address(),
+                    None,
+                    BalanceChange::received(5),
+                    true,
+                )
+                .await
+                .unwrap();
+                let deliveries = failed_deliveries().await.unwrap();
+                assert_eq!(deliveries.len(), 1);
+                assert_eq!(deliveries[0].error(), "listener unavailable");
+                assert!(!delivered.load(std::sync::atomic::Ordering::SeqCst));
+
+                assert_eq!(redeliver_failed().await.unwrap(), 1);
+                assert!(delivered.load(std::sync::atomic::Ordering::SeqCst));
+                assert!(failed_deliveries().await.unwrap().is_empty());
+            });
+        }
+
+        #[test]
+        fn event_batching() {
+            let runtime = tokio::runtime::Runtime::new().unwrap();
+            runtime.block_on(async {
+                let manager = crate::testing::get_account_manager().await;
+                let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
+                let account = account_handle.read().await;
+                let batches = Arc::new(StdMutex::new(Vec::new()));
+                let batches_ = batches.clone();
+                on_event_batch(move |batch| batches_.lock().unwrap().push(batch.events().len())).await;
+                on_balance_change(|_| panic!("batched events aren't emitted individually")).await;
+                set_event_batching(Some(EventBatchOptions {
+                    max_size: 2,
+                    flush_interval: Duration::from_secs(60),
+                    emit_individual_events: false,
+                }))
+                .await;
+
+                for _ in 0..3 {
+                    emit_balance_change(
+                        &account,
+                        &crate::testing::generate_random_iota_address(),
+                        None,
+                        BalanceChange::received(5),
+                        true,
+                    )
+                    .await
+                    .unwrap();
+                }
+                assert_eq!(*batches.lock().unwrap(), vec![2]);
+                flush_event_batch().await;
+                assert_eq!(*batches.lock().unwrap(), vec![2, 1]);
+                assert!(failed_deliveries().await.unwrap().is_empty());
             });
         }
     }
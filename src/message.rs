@@ -5,7 +5,7 @@ use crate::{
     account_manager::AccountStore,
     address::{Address, AddressOutput, AddressWrapper, IotaAddress, OutputKind},
     client::ClientOptions,
-    event::{emit_transfer_progress, TransferProgressType},
+    event::{emit_transfer_progress, PreparedTransactionData, TransferProgressType},
 };
 use getset::{CopyGetters, Getters, Setters};
 use iota_client::common::packable::Packable;
@@ -23,7 +23,7 @@ pub use iota_client::{
 };
 use once_cell::sync::Lazy;
 use serde::{de::Deserializer, ser::Serializer, Deserialize, Serialize};
-use serde_repr::Deserialize_repr;
+use serde_repr::{Deserialize_repr, Serialize_repr};
 use std::{
     cmp::Ordering,
     collections::{
@@ -33,8 +33,10 @@ use std::{
     convert::{TryFrom, TryInto},
     fmt,
     hash::{Hash, Hasher},
-    num::NonZeroU64,
+    num::{NonZeroU64, NonZeroUsize},
     ops::Range,
+    sync::{Arc, Mutex as StdMutex},
+    time::Instant,
 };
 use tokio::sync::RwLock;
 
@@ -49,6 +51,83 @@ fn milestone_cache() -> &'static MilestoneCache {
     &MILESTONE_CACHE
 }
 
+/// Parser for a custom indexation payload schema (e.g. chat messages or DIDs).
+pub trait PayloadParser {
+    /// Parses the indexation payload. Returns `None` if the payload doesn't match the parser schema.
+    fn parse(&self, index: &[u8], data: &[u8]) -> Option<serde_json::Value>;
+}
+
+type PayloadParsers = RwLock<Vec<(String, Box<dyn PayloadParser + Sync + Send>)>>;
+fn payload_parsers() -> &'static PayloadParsers {
+    static PAYLOAD_PARSERS: Lazy<PayloadParsers> = Lazy::new(Default::default);
+    &PAYLOAD_PARSERS
+}
+
+/// Registers a parser for custom indexation payloads, replacing the parser previously registered with the same name.
+/// Parsers are tried in registration order and the first match is attached to the message as
+/// [custom_payload](struct.Message.html#structfield.custom_payload).
+pub async fn register_payload_parser<P: PayloadParser + Sync + Send + 'static>(name: &str, parser: P) {
+    let mut parsers = payload_parsers().write().await;
+    match parsers.iter().position(|(parser_name, _)| parser_name == name) {
+        Some(position) => parsers[position].1 = Box::new(parser),
+        None => parsers.push((name.to_string(), Box::new(parser))),
+    }
+}
+
+/// Removes the payload parser registered with the given name.
+pub async fn remove_payload_parser(name: &str) {
+    payload_parsers()
+        .write()
+        .await
+        .retain(|(parser_name, _)| parser_name != name);
+}
+
+/// Data parsed from a message indexation payload by a registered [PayloadParser](trait.PayloadParser.html).
+#[derive(Debug, Clone, Getters, Serialize, Deserialize, PartialEq, Eq)]
+#[getset(get = "pub")]
+pub struct CustomPayload {
+    /// The name of the parser that parsed the payload.
+    parser: String,
+    /// The parsed data.
+    data: serde_json::Value,
+}
+
+async fn parse_custom_payload(payload: Option<&Payload>) -> Option<CustomPayload> {
+    let indexation = match payload {
+        Some(Payload::Indexation(indexation)) => indexation,
+        Some(Payload::Transaction(tx)) => match tx.essence() {
+            Essence::Regular(essence) => match essence.payload() {
+                Some(Payload::Indexation(indexation)) => indexation,
+                _ => return None,
+            },
+        },
+        _ => return None,
+    };
+    payload_parsers().read().await.iter().find_map(|(name, parser)| {
+        parser
+            .parse(indexation.index(), indexation.data())
+            .map(|data| CustomPayload {
+                parser: name.clone(),
+                data,
+            })
+    })
+}
+
+/// The reason a [PreSignHook](type.PreSignHook.html) rejected a transfer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Veto(pub String);
+
+/// Policy check run on the prepared transaction before it's signed. Returning an error vetoes the transfer.
+pub type PreSignHook = fn(&PreparedTransactionData) -> Result<(), Veto>;
+
+/// Output address check run when the transfer is built. Returning an error rejects the transfer with
+/// [OutputAddressRejected](../enum.Error.html#variant.OutputAddressRejected).
+pub type AddressValidator = fn(&AddressWrapper) -> Result<(), String>;
+
+/// Called for each transfer output sent to a known exchange deposit address, e.g. to ask the user to double check
+/// the memo the exchange requires. It doesn't reject the transfer.
+pub type ExchangeAddressWarning = fn(&TransferOutput);
+
 /// The strategy to use for the remainder value management when sending funds.
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
 #[serde(tag = "strategy", content = "value")]
@@ -60,6 +139,10 @@ pub enum RemainderValueStrategy {
     /// Move the remainder value to an address that must belong to the source account.
     #[serde(with = "crate::serde::iota_address_serde")]
     AccountAddress(AddressWrapper),
+    /// Split the remainder value across the given number of change addresses, so the change can't be linked to a
+    /// single output and future transfers can spend it in parallel. Fewer addresses are used when the remainder value
+    /// can't hold the dust allowance value on each of them.
+    SplitChange(NonZeroUsize),
 }
 
 impl Default for RemainderValueStrategy {
@@ -68,6 +151,47 @@ impl Default for RemainderValueStrategy {
     }
 }
 
+impl RemainderValueStrategy {
+    /// The number of outputs the strategy may add to the transaction besides the remainder output.
+    pub(crate) fn extra_output_count(&self) -> usize {
+        match self {
+            Self::SplitChange(count) => count.get() - 1,
+            _ => 0,
+        }
+    }
+}
+
+/// When the remainder deposit address of a transfer is shown on the Ledger display for the user to verify it.
+/// Verifying the address guards against a compromised host swapping the change address, at the cost of a device
+/// confirmation on every transfer with remainder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "policy", content = "value")]
+pub enum ChangeAddressVerification {
+    /// Always verify the address on the device.
+    Always,
+    /// Verify the address when the remainder value is at least the given amount.
+    AboveAmount(u64),
+    /// Never verify the address; it's generated without a device prompt.
+    Never,
+}
+
+impl Default for ChangeAddressVerification {
+    fn default() -> Self {
+        Self::Always
+    }
+}
+
+impl ChangeAddressVerification {
+    /// Whether a remainder of the given value must be verified on the device.
+    pub fn requires_verification(&self, remainder_value: u64) -> bool {
+        match self {
+            Self::Always => true,
+            Self::AboveAmount(threshold) => remainder_value >= *threshold,
+            Self::Never => false,
+        }
+    }
+}
+
 /// Transfer output.
 #[derive(Debug, Clone, Deserialize)]
 pub struct TransferOutput {
@@ -96,6 +220,145 @@ impl TransferOutput {
     }
 }
 
+/// The minimum amount of a dust allowance output.
+const MIN_DUST_ALLOWANCE_AMOUNT: u64 = 1_000_000;
+
+/// An unlock condition of an output composed with the [OutputBuilder](struct.OutputBuilder.html).
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type", content = "data")]
+pub enum UnlockCondition {
+    /// The output is unlocked by a signature of the address.
+    Address(#[serde(with = "crate::serde::iota_address_serde")] AddressWrapper),
+    /// The output can't be unlocked before the unix timestamp.
+    /// The current protocol has no timelock unlock condition, so time-locked transfers aren't supported: building
+    /// the output fails with [TimelockNotSupported](../enum.Error.html#variant.TimelockNotSupported), and since no
+    /// received output can be time-locked, the account balance has no "locked until" part.
+    Timelock(u64),
+    /// The output can be unlocked by the return address once the unix timestamp is reached.
+    /// The current protocol has no expiration unlock condition, so expiring deposits aren't supported: building the
+    /// output fails with [ExpirationNotSupported](../enum.Error.html#variant.ExpirationNotSupported), and there are
+    /// no expired outputs for the wallet to track or reclaim.
+    Expiration {
+        /// The address the output returns to.
+        #[serde(rename = "returnAddress", with = "crate::serde::iota_address_serde")]
+        return_address: AddressWrapper,
+        /// The unix timestamp at which the output expires.
+        #[serde(rename = "unixTimestamp")]
+        unix_timestamp: u64,
+    },
+}
+
+/// A feature block of an output composed with the [OutputBuilder](struct.OutputBuilder.html).
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type", content = "data")]
+pub enum FeatureBlock {
+    /// Makes the output a dust allowance, letting its address receive dust outputs.
+    DustAllowance,
+    /// The address that sent the output.
+    Sender(#[serde(with = "crate::serde::iota_address_serde")] AddressWrapper),
+    /// Data attached to the output.
+    Metadata(Vec<u8>),
+}
+
+/// Composes a transfer output from unlock conditions and feature blocks, validating that the network protocol
+/// supports them. The current protocol only has address unlock conditions and the dust allowance output kind,
+/// so the other conditions and blocks are rejected until the wallet supports a protocol with them.
+#[derive(Debug, Clone)]
+pub struct OutputBuilder {
+    amount: NonZeroU64,
+    unlock_conditions: Vec<UnlockCondition>,
+    feature_blocks: Vec<FeatureBlock>,
+}
+
+impl OutputBuilder {
+    /// Initialises an output with the given amount.
+    pub fn new(amount: NonZeroU64) -> Self {
+        Self {
+            amount,
+            unlock_conditions: Vec::new(),
+            feature_blocks: Vec::new(),
+        }
+    }
+
+    /// Adds an unlock condition.
+    pub fn with_unlock_condition(mut self, unlock_condition: UnlockCondition) -> Self {
+        self.unlock_conditions.push(unlock_condition);
+        self
+    }
+
+    /// Adds a feature block.
+    pub fn with_feature_block(mut self, feature_block: FeatureBlock) -> Self {
+        self.feature_blocks.push(feature_block);
+        self
+    }
+
+    /// Validates the unlock conditions and feature blocks and builds the transfer output.
+    /// The output needs exactly one address unlock condition, and each feature block at most once.
+    pub fn finish(self) -> crate::Result<TransferOutput> {
+        let mut address = None;
+        for unlock_condition in self.unlock_conditions {
+            match unlock_condition {
+                UnlockCondition::Address(unlock_address) => {
+                    if address.replace(unlock_address).is_some() {
+                        return Err(crate::Error::InvalidOutput(
+                            "the output has several address unlock conditions".to_string(),
+                        ));
+                    }
+                }
+                UnlockCondition::Timelock(_) => return Err(crate::Error::TimelockNotSupported),
+                UnlockCondition::Expiration { .. } => return Err(crate::Error::ExpirationNotSupported),
+            }
+        }
+        let address = address
+            .ok_or_else(|| crate::Error::InvalidOutput("the output has no address unlock condition".to_string()))?;
+
+        let mut output_kind = OutputKind::SignatureLockedSingle;
+        for feature_block in self.feature_blocks {
+            match feature_block {
+                FeatureBlock::DustAllowance => {
+                    if output_kind == OutputKind::SignatureLockedDustAllowance {
+                        return Err(crate::Error::InvalidOutput(
+                            "the dust allowance feature block is set several times".to_string(),
+                        ));
+                    }
+                    if self.amount.get() < MIN_DUST_ALLOWANCE_AMOUNT {
+                        return Err(crate::Error::InvalidOutput(format!(
+                            "a dust allowance output needs at least {} tokens",
+                            MIN_DUST_ALLOWANCE_AMOUNT
+                        )));
+                    }
+                    output_kind = OutputKind::SignatureLockedDustAllowance;
+                }
+                FeatureBlock::Sender(_) => return Err(crate::Error::FeatureBlockNotSupported("sender".to_string())),
+                FeatureBlock::Metadata(_) => {
+                    return Err(crate::Error::FeatureBlockNotSupported("metadata".to_string()))
+                }
+            }
+        }
+
+        Ok(TransferOutput::new(address, self.amount, Some(output_kind)))
+    }
+}
+
+/// The limits a transfer is validated against when it's built.
+#[derive(Debug, Clone, Copy)]
+pub struct TransferLimits {
+    /// Maximum size of the indexation payload data, in bytes.
+    pub max_indexation_data_size: usize,
+    /// Maximum number of transfer outputs, leaving room for the remainder output.
+    pub max_outputs: usize,
+}
+
+impl Default for TransferLimits {
+    fn default() -> Self {
+        Self {
+            // a message can't be larger than 32KiB
+            max_indexation_data_size: 32 * 1024,
+            max_outputs: 124,
+        }
+    }
+}
+
 /// A transfer to make a transaction.
 #[derive(Debug, Clone)]
 pub struct TransferBuilder {
@@ -111,6 +374,18 @@ pub struct TransferBuilder {
     with_events: bool,
     /// Whether the transfer should skip account syncing or not.
     skip_sync: bool,
+    /// Policy check run before signing the transaction.
+    pre_sign_hook: Option<PreSignHook>,
+    /// The limits validated when the transfer is built.
+    limits: TransferLimits,
+    /// The bech32 human readable part the output addresses must have.
+    network_hrp: Option<String>,
+    /// Custom output address checks.
+    address_validators: Vec<AddressValidator>,
+    /// The known exchange deposit addresses and the callback warned when an output is sent to one.
+    exchange_address_warning: Option<(Vec<AddressWrapper>, ExchangeAddressWarning)>,
+    /// Application data attached to the transfer events and the sent message.
+    context: Option<serde_json::Value>,
 }
 
 impl Default for TransferBuilder {
@@ -122,6 +397,12 @@ impl Default for TransferBuilder {
             input: None,
             with_events: true,
             skip_sync: false,
+            pre_sign_hook: None,
+            limits: Default::default(),
+            network_hrp: None,
+            address_validators: Vec::new(),
+            exchange_address_warning: None,
+            context: None,
         }
     }
 }
@@ -170,6 +451,9 @@ impl<'de> Deserialize<'de> for TransferBuilder {
             indexation: Option<IndexationPayloadBuilder>,
             /// The strategy to use for the remainder value.
             remainder_value_strategy: RemainderValueStrategy,
+            /// Application data attached to the transfer events and the sent message.
+            #[serde(default)]
+            context: Option<serde_json::Value>,
         }
 
         TransferBuilderWrapper::deserialize(deserializer).and_then(|mut builder| {
@@ -186,6 +470,12 @@ impl<'de> Deserialize<'de> for TransferBuilder {
                 input: None,
                 with_events: true,
                 skip_sync: false,
+                pre_sign_hook: None,
+                limits: Default::default(),
+                network_hrp: None,
+                address_validators: Vec::new(),
+                exchange_address_warning: None,
+                context: builder.context,
             })
         })
     }
@@ -205,12 +495,8 @@ impl TransferBuilder {
     }
 
     /// Creates a transfer with multiple outputs.
+    /// The outputs count is validated by [finish](struct.TransferBuilder.html#method.finish).
     pub fn with_outputs(outputs: Vec<TransferOutput>) -> crate::Result<Self> {
-        if !(1..125).contains(&outputs.len()) {
-            return Err(crate::Error::BeeMessage(
-                iota_client::bee_message::Error::InvalidInputOutputCount(outputs.len()),
-            ));
-        }
         Ok(Self {
             outputs,
             ..Default::default()
@@ -265,16 +551,99 @@ impl TransferBuilder {
         self
     }
 
-    /// Builds the transfer.
-    pub fn finish(self) -> Transfer {
-        Transfer {
+    /// Sets a policy check (e.g. address allowlist, amount sanity) run after the transaction essence is built
+    /// but before it's signed. If the hook returns a [Veto](struct.Veto.html), the transfer is aborted.
+    pub fn with_pre_sign_hook(mut self, hook: PreSignHook) -> Self {
+        self.pre_sign_hook.replace(hook);
+        self
+    }
+
+    /// Sets the limits the transfer is validated against.
+    pub fn with_limits(mut self, limits: TransferLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Rejects the transfer with [AddressNetworkMismatch](../enum.Error.html#variant.AddressNetworkMismatch) if an
+    /// output address doesn't have the given bech32 human readable part, e.g. a mainnet address on a testnet account.
+    pub fn with_network_hrp<H: Into<String>>(mut self, hrp: H) -> Self {
+        self.network_hrp.replace(hrp.into());
+        self
+    }
+
+    /// Adds a custom output address check, run after the network check.
+    pub fn with_address_validator(mut self, validator: AddressValidator) -> Self {
+        self.address_validators.push(validator);
+        self
+    }
+
+    /// Calls `warning` for the outputs sent to one of the given exchange deposit addresses.
+    pub fn with_exchange_address_warning(
+        mut self,
+        exchange_addresses: Vec<AddressWrapper>,
+        warning: ExchangeAddressWarning,
+    ) -> Self {
+        self.exchange_address_warning.replace((exchange_addresses, warning));
+        self
+    }
+
+    /// Attaches application data, e.g. an order or payout id, to the transfer progress events and to the sent
+    /// message, so the new transaction, broadcast and confirmation events carry it too.
+    pub fn with_context(mut self, context: serde_json::Value) -> Self {
+        self.context.replace(context);
+        self
+    }
+
+    fn validate_output_addresses(&self) -> crate::Result<()> {
+        for output in &self.outputs {
+            if let Some(hrp) = &self.network_hrp {
+                if output.address.bech32_hrp() != hrp {
+                    return Err(crate::Error::AddressNetworkMismatch {
+                        address: output.address.to_bech32(),
+                        expected: hrp.clone(),
+                    });
+                }
+            }
+            for validator in &self.address_validators {
+                validator(&output.address)
+                    .map_err(|reason| crate::Error::OutputAddressRejected(output.address.to_bech32(), reason))?;
+            }
+            if let Some((exchange_addresses, warning)) = &self.exchange_address_warning {
+                if exchange_addresses.contains(&output.address) {
+                    warning(output);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Validates the transfer against its limits and output address checks and builds it.
+    pub fn finish(self) -> crate::Result<Transfer> {
+        // the split change outputs take up room in the transaction like the transfer outputs
+        let output_count = self.outputs.len() + self.remainder_value_strategy.extra_output_count();
+        if self.outputs.is_empty() || output_count > self.limits.max_outputs {
+            return Err(crate::Error::InvalidOutputCount(output_count, self.limits.max_outputs));
+        }
+        if let Some(indexation) = &self.indexation {
+            if indexation.data().len() > self.limits.max_indexation_data_size {
+                return Err(crate::Error::IndexationDataTooLarge(
+                    indexation.data().len(),
+                    self.limits.max_indexation_data_size,
+                ));
+            }
+        }
+        self.validate_output_addresses()?;
+        Ok(Transfer {
             outputs: self.outputs,
             indexation: self.indexation,
             remainder_value_strategy: self.remainder_value_strategy,
             input: self.input,
             with_events: self.with_events,
             skip_sync: self.skip_sync,
-        }
+            pre_sign_hook: self.pre_sign_hook,
+            timer: Default::default(),
+            context: self.context,
+        })
     }
 }
 
@@ -293,6 +662,12 @@ pub struct Transfer {
     pub(crate) with_events: bool,
     /// Whether the transfer should skip account syncing or not.
     pub(crate) skip_sync: bool,
+    /// Policy check run before signing the transaction.
+    pub(crate) pre_sign_hook: Option<PreSignHook>,
+    /// Times the transfer stages.
+    pub(crate) timer: Arc<StdMutex<TransferTimer>>,
+    /// Application data attached to the transfer events and the sent message.
+    pub(crate) context: Option<serde_json::Value>,
 }
 
 impl Transfer {
@@ -306,15 +681,151 @@ impl Transfer {
         TransferBuilder::with_outputs(outputs)
     }
 
+    /// Starts timing the stage of the event, and emits it with the duration of the previous stage if the transfer
+    /// has events enabled.
     pub(crate) async fn emit_event_if_needed(&self, account_id: String, event: TransferProgressType) {
+        let previous_stage_duration = self
+            .timer
+            .lock()
+            .expect("transfer timer poisoned")
+            .start_stage(TransferStage::of(&event));
         if self.with_events {
-            emit_transfer_progress(account_id, event).await;
+            emit_transfer_progress(account_id, event, previous_stage_duration, self.context.clone()).await;
         }
     }
 
+    /// Ends the last stage, returning the time spent on each stage of the transfer.
+    pub(crate) fn finish_timings(&self) -> TransferTimings {
+        self.timer.lock().expect("transfer timer poisoned").finish()
+    }
+
+    /// Runs the pre-sign hook of the transfer, if any, on the prepared transaction.
+    pub(crate) fn check_pre_sign_hook(&self, prepared_transaction_data: &PreparedTransactionData) -> crate::Result<()> {
+        if let Some(pre_sign_hook) = self.pre_sign_hook {
+            if let Err(veto) = pre_sign_hook(prepared_transaction_data) {
+                log::debug!("[TRANSFER] transfer vetoed: {}", veto.0);
+                return Err(crate::Error::TransferVetoed(veto.0));
+            }
+        }
+        Ok(())
+    }
+
     pub(crate) fn amount(&self) -> u64 {
         self.outputs.iter().map(|o| o.amount.get()).sum()
     }
+
+    /// The number of outputs the transaction may have besides the remainder output, counting the split change
+    /// outputs.
+    pub(crate) fn output_count(&self) -> usize {
+        self.outputs.len() + self.remainder_value_strategy.extra_output_count()
+    }
+}
+
+/// The timed stages of a transfer.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum TransferStage {
+    SyncingAccount,
+    SelectingInputs,
+    GeneratingRemainderDepositAddress,
+    SigningTransaction,
+    PerformingPoW,
+    Broadcasting,
+}
+
+impl TransferStage {
+    /// The stage the event starts; events that aren't a stage of their own, like the prepared transaction, only end
+    /// the previous stage.
+    fn of(event: &TransferProgressType) -> Option<Self> {
+        match event {
+            TransferProgressType::SyncingAccount => Some(Self::SyncingAccount),
+            TransferProgressType::SelectingInputs => Some(Self::SelectingInputs),
+            TransferProgressType::GeneratingRemainderDepositAddress(_) => Some(Self::GeneratingRemainderDepositAddress),
+            TransferProgressType::SigningTransaction => Some(Self::SigningTransaction),
+            TransferProgressType::PerformingPoW => Some(Self::PerformingPoW),
+            TransferProgressType::Broadcasting => Some(Self::Broadcasting),
+            TransferProgressType::PreparedTransaction(_)
+            | TransferProgressType::ChainedTransfer(_)
+            | TransferProgressType::RemainderAddressVerification(_) => None,
+        }
+    }
+}
+
+/// The time spent on each stage of a sent transfer, in milliseconds.
+/// The signing time includes waiting for the user confirmation on a Ledger device.
+#[derive(Debug, Clone, Default, Getters, Serialize, Deserialize, PartialEq, Eq)]
+#[getset(get = "pub")]
+pub struct TransferTimings {
+    /// Syncing the account before the input selection.
+    #[serde(rename = "syncingAccount")]
+    syncing_account: Option<u64>,
+    /// Selecting the inputs.
+    #[serde(rename = "selectingInputs")]
+    selecting_inputs: Option<u64>,
+    /// Generating the remainder deposit address.
+    #[serde(rename = "generatingRemainderDepositAddress")]
+    generating_remainder_deposit_address: Option<u64>,
+    /// Signing the transaction.
+    #[serde(rename = "signingTransaction")]
+    signing_transaction: Option<u64>,
+    /// Performing the proof of work.
+    #[serde(rename = "performingPow")]
+    performing_pow: Option<u64>,
+    /// Broadcasting the message to the node.
+    broadcasting: Option<u64>,
+    /// The whole transfer, from the first stage.
+    total: u64,
+}
+
+impl TransferTimings {
+    fn stage_mut(&mut self, stage: TransferStage) -> &mut Option<u64> {
+        match stage {
+            TransferStage::SyncingAccount => &mut self.syncing_account,
+            TransferStage::SelectingInputs => &mut self.selecting_inputs,
+            TransferStage::GeneratingRemainderDepositAddress => &mut self.generating_remainder_deposit_address,
+            TransferStage::SigningTransaction => &mut self.signing_transaction,
+            TransferStage::PerformingPoW => &mut self.performing_pow,
+            TransferStage::Broadcasting => &mut self.broadcasting,
+        }
+    }
+}
+
+/// Times the stages of a transfer as its progress events are emitted.
+#[derive(Debug, Default)]
+pub(crate) struct TransferTimer {
+    started: Option<Instant>,
+    stage: Option<(Option<TransferStage>, Instant)>,
+    timings: TransferTimings,
+}
+
+impl TransferTimer {
+    /// Ends the current stage and starts the next one, returning the milliseconds spent on the ended stage.
+    fn start_stage(&mut self, stage: Option<TransferStage>) -> Option<u64> {
+        let now = Instant::now();
+        self.started.get_or_insert(now);
+        let previous_stage_duration = self.end_stage(now);
+        self.stage.replace((stage, now));
+        previous_stage_duration
+    }
+
+    fn end_stage(&mut self, now: Instant) -> Option<u64> {
+        let (stage, started) = self.stage.take()?;
+        let duration = now.duration_since(started).as_millis() as u64;
+        if let Some(stage) = stage {
+            // a stage may run more than once, e.g. when the input selection is retried
+            let timing = self.timings.stage_mut(stage);
+            *timing = Some(timing.unwrap_or_default() + duration);
+        }
+        Some(duration)
+    }
+
+    fn finish(&mut self) -> TransferTimings {
+        let now = Instant::now();
+        self.end_stage(now);
+        if let Some(started) = self.started {
+            self.timings.total = now.duration_since(started).as_millis() as u64;
+        }
+        self.timings.clone()
+    }
 }
 
 /// Possible Value units.
@@ -334,6 +845,20 @@ pub enum ValueUnit {
     Pi,
 }
 
+impl ValueUnit {
+    /// The number of base units (`i`) in one unit.
+    pub fn multiplier(&self) -> u64 {
+        match self {
+            ValueUnit::I => 1,
+            ValueUnit::Ki => 1000,
+            ValueUnit::Mi => 1000000,
+            ValueUnit::Gi => 1000000000,
+            ValueUnit::Ti => 1000000000000,
+            ValueUnit::Pi => 1000000000000000,
+        }
+    }
+}
+
 impl fmt::Display for ValueUnit {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match *self {
@@ -370,15 +895,7 @@ impl Value {
 
     /// The transaction value without its unit.
     pub fn without_denomination(&self) -> u64 {
-        let multiplier = match self.unit {
-            ValueUnit::I => 1,
-            ValueUnit::Ki => 1000,
-            ValueUnit::Mi => 1000000,
-            ValueUnit::Gi => 1000000000,
-            ValueUnit::Ti => 1000000000000,
-            ValueUnit::Pi => 1000000000000000,
-        };
-        self.value * multiplier
+        self.value * self.unit.multiplier()
     }
 }
 
@@ -497,6 +1014,9 @@ pub struct TransactionRegularEssence {
     value: u64,
     #[serde(rename = "remainderValue")]
     remainder_value: u64,
+    /// Whether the account sent the transaction to its own addresses.
+    #[serde(rename = "selfTransfer", default)]
+    self_transfer: bool,
 }
 
 impl TransactionRegularEssence {
@@ -539,6 +1059,59 @@ impl TransactionRegularEssence {
     pub fn remainder_value(&self) -> u64 {
         self.remainder_value
     }
+
+    /// Whether the account sent the transaction to its own addresses.
+    pub fn self_transfer(&self) -> bool {
+        self.self_transfer
+    }
+
+    /// The account addresses and outputs spent by an outgoing transaction.
+    pub(crate) fn funding_sources(&self, account_addresses: &[Address]) -> Vec<FundingSource> {
+        if self.incoming {
+            return Vec::new();
+        }
+        self.inputs
+            .iter()
+            .filter_map(|input| match input {
+                TransactionInput::Utxo(TransactionUtxoInput {
+                    input,
+                    metadata: Some(output),
+                }) => account_addresses
+                    .iter()
+                    .find(|address| address.address() == &output.address)
+                    .map(|address| FundingSource {
+                        address: output.address.clone(),
+                        key_index: *address.key_index(),
+                        internal: *address.internal(),
+                        output: input.clone(),
+                        message_id: output.message_id,
+                        amount: output.amount,
+                    }),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// An account output spent by an outgoing message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Getters)]
+#[getset(get = "pub")]
+pub struct FundingSource {
+    /// The account address holding the output.
+    #[serde(with = "crate::serde::iota_address_serde")]
+    address: AddressWrapper,
+    /// The address key index.
+    #[serde(rename = "keyIndex")]
+    key_index: usize,
+    /// Whether the address is a change address.
+    internal: bool,
+    /// The spent output.
+    output: UtxoInput,
+    /// The message that created the output, e.g. an incoming transfer.
+    #[serde(rename = "messageId")]
+    message_id: MessageId,
+    /// The output amount.
+    amount: u64,
 }
 
 impl TransactionRegularEssence {
@@ -553,6 +1126,16 @@ impl TransactionRegularEssence {
     }
 
     async fn new(regular_essence: &RegularEssence, metadata: &TransactionBuilderMetadata<'_>) -> crate::Result<Self> {
+        // the inputs of a transaction between two accounts are resolved once for both
+        let cached_inputs = metadata
+            .accounts
+            .message_cache()
+            .get(&metadata.bech32_hrp, metadata.id)
+            .and_then(|cached| cached.inputs.clone());
+        if let Some(inputs) = cached_inputs {
+            return Self::from_inputs(regular_essence, inputs, metadata).await;
+        }
+
         let mut inputs = Vec::new();
         for input in regular_essence.inputs() {
             let input = match input.clone() {
@@ -590,6 +1173,29 @@ impl TransactionRegularEssence {
             };
             inputs.push(input);
         }
+        metadata
+            .accounts
+            .message_cache()
+            .entry(&metadata.bech32_hrp, *metadata.id)
+            .inputs
+            .replace(inputs.clone());
+        Self::from_inputs(regular_essence, inputs, metadata).await
+    }
+
+    /// Recomputes the remainder flags, value and direction of the transaction, keeping the already resolved inputs.
+    pub(crate) async fn recompute(
+        &self,
+        regular_essence: &RegularEssence,
+        metadata: &TransactionBuilderMetadata<'_>,
+    ) -> crate::Result<Self> {
+        Self::from_inputs(regular_essence, self.inputs.to_vec(), metadata).await
+    }
+
+    async fn from_inputs(
+        regular_essence: &RegularEssence,
+        inputs: Vec<TransactionInput>,
+        metadata: &TransactionBuilderMetadata<'_>,
+    ) -> crate::Result<Self> {
         let mut outputs = Vec::new();
 
         let tx_outputs = regular_essence.outputs();
@@ -724,17 +1330,21 @@ impl TransactionRegularEssence {
             incoming: false,
             value,
             remainder_value,
+            self_transfer: false,
         };
 
-        let is_internal = is_internal(
-            &essence,
-            metadata.accounts.clone(),
-            metadata.account_id,
-            metadata.account_addresses,
-        )
-        .await;
-        essence.internal = is_internal;
+        essence.internal = is_internal(&essence, &metadata.accounts, metadata.account_addresses);
         essence.incoming = essence.is_incoming(metadata.account_addresses);
+        essence.self_transfer = !essence.incoming
+            && !essence.outputs.is_empty()
+            && essence.outputs.iter().all(|output| {
+                let address = match output {
+                    TransactionOutput::SignatureLockedSingle(o) => &o.address,
+                    TransactionOutput::SignatureLockedDustAllowance(o) => &o.address,
+                    TransactionOutput::Treasury(_) => return false,
+                };
+                metadata.account_addresses.iter().any(|a| a.address() == address)
+            });
 
         Ok(essence)
     }
@@ -1114,6 +1724,32 @@ pub struct Message {
     #[serde(rename = "reattachmentMessageId")]
     #[getset(set = "pub(crate)")]
     pub reattachment_message_id: Option<MessageId>,
+    /// The id of the message this message reattaches, if any.
+    /// A reattachment of a reattachment links to the first message.
+    #[serde(rename = "reattachmentOf", default, skip_serializing_if = "Option::is_none")]
+    pub reattachment_of: Option<MessageId>,
+    /// The ids of the messages reattaching this message, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reattachments: Vec<MessageId>,
+    /// The account addresses and the outputs of prior messages that funded the message, if it's outgoing.
+    #[serde(rename = "fundingSources", default, skip_serializing_if = "Vec::is_empty")]
+    pub funding_sources: Vec<FundingSource>,
+    /// Data parsed from the indexation payload by a registered [PayloadParser](trait.PayloadParser.html).
+    #[serde(rename = "customPayload", default, skip_serializing_if = "Option::is_none")]
+    pub custom_payload: Option<CustomPayload>,
+    /// The time spent on each stage of the transfer, if the message was sent by this wallet.
+    #[serde(rename = "transferTimings", default, skip_serializing_if = "Option::is_none")]
+    pub transfer_timings: Option<TransferTimings>,
+    /// The application data attached to the transfer that sent the message, if it was sent by this wallet.
+    #[serde(rename = "transferContext", default, skip_serializing_if = "Option::is_none")]
+    pub transfer_context: Option<serde_json::Value>,
+    /// The packed message as received from the node, to audit the stored data with [verify](#method.verify).
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "crate::serde::hex_bytes_option_serde"
+    )]
+    pub raw: Option<Vec<u8>>,
 }
 
 impl Message {
@@ -1156,6 +1792,66 @@ impl Message {
         }
         None
     }
+
+    /// Re-checks the stored message against its [raw](#structfield.raw) bytes: the message id is the hash of the
+    /// bytes, the parents, nonce and payload match, and the transaction signatures unlock the stored input addresses.
+    pub fn verify(&self) -> crate::Result<()> {
+        let raw = self.raw.as_ref().ok_or(crate::Error::MissingRawMessage)?;
+        let iota_message = IotaMessage::unpack(&mut raw.as_slice())?;
+        let mismatch = |field: &str| {
+            Err(crate::Error::MessageVerificationFailed(format!(
+                "the {} doesn't match the raw bytes",
+                field
+            )))
+        };
+
+        if iota_message.id().0 != self.id {
+            return mismatch("message id");
+        }
+        if !iota_message.parents().iter().eq(self.parents.iter()) {
+            return mismatch("parents");
+        }
+        if iota_message.nonce() != self.nonce {
+            return mismatch("nonce");
+        }
+        if iota_message.payload().pack_new().len() != self.payload_length {
+            return mismatch("payload length");
+        }
+
+        match (iota_message.payload(), &self.payload) {
+            (Some(Payload::Transaction(raw_tx)), Some(MessagePayload::Transaction(tx))) => {
+                if tx.to_transaction_payload()?.pack_new() != raw_tx.pack_new() {
+                    return mismatch("transaction");
+                }
+                let TransactionEssence::Regular(essence) = tx.essence();
+                let mut inputs = Vec::new();
+                for input in essence.inputs() {
+                    if let TransactionInput::Utxo(utxo) = input {
+                        let metadata = utxo.metadata.as_ref().ok_or_else(|| {
+                            crate::Error::MessageVerificationFailed(
+                                "the addresses of the transaction inputs aren't stored".to_string(),
+                            )
+                        })?;
+                        inputs.push((Input::Utxo(utxo.input.clone()), *metadata.address.as_ref()));
+                    }
+                }
+                crate::account::verify_unlock_blocks(raw_tx, inputs)
+                    .map_err(|e| crate::Error::MessageVerificationFailed(e.to_string()))
+            }
+            (Some(Payload::Indexation(raw_indexation)), Some(MessagePayload::Indexation(indexation))) => {
+                if raw_indexation != indexation {
+                    return mismatch("indexation");
+                }
+                Ok(())
+            }
+            (Some(Payload::Transaction(_)), _)
+            | (Some(Payload::Indexation(_)), _)
+            | (Some(_), None)
+            | (None, Some(_)) => mismatch("payload kind"),
+            // the milestone, receipt and treasury payloads are covered by the message id and length checks
+            _ => Ok(()),
+        }
+    }
 }
 
 impl Hash for Message {
@@ -1182,75 +1878,23 @@ impl PartialOrd for Message {
     }
 }
 
-fn transaction_inputs_belonging_to_account(
-    essence: &TransactionRegularEssence,
-    account_addresses: &[Address],
-) -> Vec<TransactionInput> {
-    let mut inputs = Vec::new();
-    for input in essence.inputs() {
-        if let TransactionInput::Utxo(i) = input {
-            if let Some(metadata) = &i.metadata {
-                if account_addresses
-                    .iter()
-                    .any(|address| address.address() == &metadata.address)
-                {
-                    inputs.push(input.clone());
-                }
-            }
-        }
-    }
-    inputs
-}
-
-fn transaction_outputs_belonging_to_account(
-    essence: &TransactionRegularEssence,
-    account_addresses: &[Address],
-) -> Vec<TransactionOutput> {
-    let mut outputs = Vec::new();
-    for output in essence.outputs() {
-        let output_address = match output {
-            TransactionOutput::SignatureLockedDustAllowance(o) => o.address(),
-            TransactionOutput::SignatureLockedSingle(o) => o.address(),
-            _ => unimplemented!(),
-        };
-        if account_addresses
-            .iter()
-            .any(|address| address.address() == output_address)
-        {
-            outputs.push(output.clone());
-        }
-    }
-    outputs
-}
-
-async fn is_internal(
-    essence: &TransactionRegularEssence,
-    accounts: AccountStore,
-    account_id: &str,
-    account_addresses: &[Address],
-) -> bool {
-    let mut inputs_belonging_to_account = Vec::new();
-    let mut outputs_belonging_to_account = Vec::new();
-    for (id, account_handle) in accounts.read().await.iter() {
-        if id == account_id {
-            inputs_belonging_to_account.extend(transaction_inputs_belonging_to_account(essence, account_addresses));
-            outputs_belonging_to_account.extend(transaction_outputs_belonging_to_account(essence, account_addresses));
-        } else {
-            let account = account_handle.read().await;
-            inputs_belonging_to_account.extend(transaction_inputs_belonging_to_account(essence, account.addresses()));
-            outputs_belonging_to_account.extend(transaction_outputs_belonging_to_account(essence, account.addresses()));
-        }
-
-        if essence.inputs().iter().all(|i| inputs_belonging_to_account.contains(i))
-            && essence
-                .outputs()
-                .iter()
-                .all(|o| outputs_belonging_to_account.contains(o))
-        {
-            return true;
-        }
-    }
-    false
+/// Whether all the transaction inputs and outputs belong to the wallet accounts.
+fn is_internal(essence: &TransactionRegularEssence, accounts: &AccountStore, account_addresses: &[Address]) -> bool {
+    let is_own_address = |address: &AddressWrapper| {
+        account_addresses.iter().any(|a| a.address() == address) || accounts.address_owner(address).is_some()
+    };
+    essence.inputs().iter().all(|input| match input {
+        TransactionInput::Utxo(i) => i
+            .metadata
+            .as_ref()
+            .map(|metadata| is_own_address(&metadata.address))
+            .unwrap_or(false),
+        _ => false,
+    }) && essence.outputs().iter().all(|output| match output {
+        TransactionOutput::SignatureLockedDustAllowance(o) => is_own_address(o.address()),
+        TransactionOutput::SignatureLockedSingle(o) => is_own_address(o.address()),
+        _ => false,
+    })
 }
 
 #[doc(hidden)]
@@ -1321,11 +1965,25 @@ impl<'a> MessageBuilder<'a> {
             ),
             None => None,
         };
+        let funding_sources = match &payload {
+            Some(MessagePayload::Transaction(tx)) => match tx.essence() {
+                TransactionEssence::Regular(essence) => essence.funding_sources(self.account_addresses),
+            },
+            _ => Vec::new(),
+        };
 
+        let cached_timestamp = self
+            .accounts
+            .message_cache()
+            .get(&self.bech32_hrp, &self.id)
+            .and_then(|cached| cached.timestamp);
         let mut timestamp = Utc::now();
         let client_guard = crate::client::get_client(self.client_options).await?;
         let client = client_guard.read().await;
-        if let Ok(metadata) = client.get_message().metadata(&self.id).await {
+        if let Some(cached_timestamp) = cached_timestamp {
+            // the message was already parsed for another account
+            timestamp = cached_timestamp;
+        } else if let Ok(metadata) = client.get_message().metadata(&self.id).await {
             timestamp = match metadata.referenced_by_milestone_index {
                 Some(ms_index) => {
                     let mut date_time = Utc::now();
@@ -1349,6 +2007,11 @@ impl<'a> MessageBuilder<'a> {
                             date_time = DateTime::from_utc(NaiveDateTime::from_timestamp(approx_timestamp, 0), Utc);
                         }
                     }
+                    self.accounts
+                        .message_cache()
+                        .entry(&self.bech32_hrp, self.id)
+                        .timestamp
+                        .replace(date_time);
                     date_time
                 }
                 _ => Utc::now(),
@@ -1366,6 +2029,13 @@ impl<'a> MessageBuilder<'a> {
             confirmed: self.confirmed,
             broadcasted: true,
             reattachment_message_id: None,
+            reattachment_of: None,
+            reattachments: Vec::new(),
+            funding_sources,
+            custom_payload: parse_custom_payload(self.iota_message.payload().as_ref()).await,
+            transfer_timings: None,
+            transfer_context: None,
+            raw: Some(self.iota_message.pack_new()),
         };
         Ok(message)
     }
@@ -1414,10 +2084,34 @@ impl Message {
             _ => vec![],
         }
     }
+
+    /// Classifies the message as a self transfer, a consolidation, a participation transaction or a data message.
+    /// Returns `None` for the transactions exchanging value with other accounts.
+    pub fn classification(&self) -> Option<MessageType> {
+        let essence = match &self.payload {
+            Some(MessagePayload::Transaction(tx)) => match tx.essence() {
+                TransactionEssence::Regular(essence) => essence,
+            },
+            _ => return Some(MessageType::Data),
+        };
+        match essence.payload() {
+            Some(Payload::Indexation(indexation)) if indexation.index() == PARTICIPATION_INDEX => {
+                Some(MessageType::Participation)
+            }
+            _ if essence.self_transfer() && essence.outputs().len() == 1 && essence.inputs().len() > 1 => {
+                Some(MessageType::Consolidation)
+            }
+            _ if essence.self_transfer() => Some(MessageType::SelfTransfer),
+            _ => None,
+        }
+    }
 }
 
+/// The indexation of the participation transactions.
+const PARTICIPATION_INDEX: &[u8] = b"PARTICIPATE";
+
 /// Message type.
-#[derive(Debug, Clone, Deserialize_repr, Eq, PartialEq)]
+#[derive(Debug, Clone, Serialize_repr, Deserialize_repr, Eq, PartialEq)]
 #[repr(u8)]
 pub enum MessageType {
     /// Message received.
@@ -1432,6 +2126,14 @@ pub enum MessageType {
     Value = 5,
     /// Message confirmed.
     Confirmed = 6,
+    /// Transaction sending value to the account's own addresses.
+    SelfTransfer = 7,
+    /// Transaction merging several account outputs into one.
+    Consolidation = 8,
+    /// Transaction holding a participation payload.
+    Participation = 9,
+    /// Message without a transaction, e.g. an indexation-only data message.
+    Data = 10,
 }
 
 #[cfg(test)]
@@ -1447,4 +2149,515 @@ mod tests {
         let message_2 = serde_json::from_str::<Message>(serialized_message_2).unwrap();
         assert_eq!(message_1, message_2);
     }
+
+    #[test]
+    fn funding_sources() {
+        use super::{MessagePayload, TransactionEssence};
+        use crate::address::AddressBuilder;
+
+        let serialized_message = r#"{"id":"263a908e9af5f76052d01e1196c645a0b3b68326c0cf6c83ceda9f284f81eb3f","version":1,"parents":[],"payloadLength":233,"payload":{"type":"Transaction","data":{"essence":{"type":"Regular","data":{"inputs":[{"type":"Utxo","data":{"input":"1ac3a0d8d2aa4b945bc18e5439d7a064bd15c45c6436ef3ed3e0933018a1b6ca0000","metadata":{"transactionId":"1ac3a0d8d2aa4b945bc18e5439d7a064bd15c45c6436ef3ed3e0933018a1b6ca","messageId":"72c54ded382cabf4c96fe39be7ed8712c090d019b813a1edb24e0e05fc2cd796","index":0,"amount":100000000,"isSpent":false,"address":"atoi1qql3gq76fg66v5w4ymrfgmncxg9q53w2tamzrxe5423gpcvxml2ak2w6w52","kind":"SignatureLockedSingle"}}}],"outputs":[{"type":"SignatureLockedSingle","data":{"address":"atoi1qzjvshnp0ef2kxqnrm9gn0wh46sp5avmpmpf5cdv9wwj4qjxjc99zu92z8d","amount":100000000,"remainder":false}}],"payload":null,"internal":false,"incoming":false,"value":100000000,"remainderValue":0}},"unlockBlocks":[]}},"timestamp":"2022-04-05T12:34:29.024451798Z","nonce":0,"broadcasted":true}"#;
+        let message = serde_json::from_str::<Message>(serialized_message).unwrap();
+        let essence = match message.payload() {
+            Some(MessagePayload::Transaction(tx)) => match tx.essence() {
+                TransactionEssence::Regular(essence) => essence.clone(),
+            },
+            _ => panic!("expected a transaction"),
+        };
+
+        let address = AddressBuilder::new()
+            .address(crate::address::parse("atoi1qql3gq76fg66v5w4ymrfgmncxg9q53w2tamzrxe5423gpcvxml2ak2w6w52").unwrap())
+            .key_index(3)
+            .internal(true)
+            .outputs(Vec::new())
+            .build()
+            .unwrap();
+        let sources = essence.funding_sources(&[address]);
+        assert_eq!(sources.len(), 1);
+        assert_eq!(*sources[0].key_index(), 3);
+        assert!(*sources[0].internal());
+        assert_eq!(*sources[0].amount(), 100_000_000);
+        assert_eq!(
+            sources[0].message_id().to_string(),
+            "72c54ded382cabf4c96fe39be7ed8712c090d019b813a1edb24e0e05fc2cd796"
+        );
+
+        let other_address = crate::testing::generate_random_address();
+        assert!(essence.funding_sources(&[other_address]).is_empty());
+    }
+
+    #[tokio::test]
+    async fn message_classification() {
+        use super::MessageType;
+
+        let message = crate::testing::GenerateMessageBuilder::default().build().await;
+        assert_eq!(message.classification(), None);
+
+        let mut value = serde_json::to_value(&message).unwrap();
+        value["payload"]["data"]["essence"]["data"]["selfTransfer"] = serde_json::Value::Bool(true);
+        let self_transfer: Message = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(self_transfer.classification(), Some(MessageType::SelfTransfer));
+
+        value["payload"] = serde_json::Value::Null;
+        let data: Message = serde_json::from_value(value).unwrap();
+        assert_eq!(data.classification(), Some(MessageType::Data));
+    }
+
+    #[tokio::test]
+    async fn transfer_timings() {
+        use super::{Transfer, TransferProgressType};
+        use std::num::NonZeroU64;
+
+        let transfer = Transfer::builder(
+            crate::testing::generate_random_iota_address(),
+            NonZeroU64::new(1_000_000).unwrap(),
+            None,
+        )
+        .with_events(false)
+        .finish()
+        .unwrap();
+        transfer
+            .emit_event_if_needed("account".to_string(), TransferProgressType::SelectingInputs)
+            .await;
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        transfer
+            .emit_event_if_needed("account".to_string(), TransferProgressType::PerformingPoW)
+            .await;
+        let timings = transfer.finish_timings();
+        assert!(timings.selecting_inputs().unwrap() >= 20);
+        assert!(timings.performing_pow().is_some());
+        assert!(timings.signing_transaction().is_none());
+        assert!(*timings.total() >= 20);
+
+        // the timings are kept when the message is synced again from the node
+        let manager = crate::testing::get_account_manager().await;
+        let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
+        let mut message = crate::testing::GenerateMessageBuilder::default().build().await;
+        message.set_transfer_timings(Some(timings.clone()));
+        let mut account = account_handle.write().await;
+        account.save_messages(vec![message.clone()]).await.unwrap();
+        message.set_transfer_timings(None);
+        account.save_messages(vec![message.clone()]).await.unwrap();
+        let stored = account.get_message(message.id()).await.unwrap();
+        assert_eq!(stored.transfer_timings(), &Some(timings));
+    }
+
+    #[tokio::test]
+    async fn transfer_context() {
+        use super::Transfer;
+        use std::num::NonZeroU64;
+
+        let context = serde_json::json!({ "orderId": 42 });
+        let transfer = Transfer::builder(
+            crate::testing::generate_random_iota_address(),
+            NonZeroU64::new(1_000_000).unwrap(),
+            None,
+        )
+        .with_context(context.clone())
+        .finish()
+        .unwrap();
+        assert_eq!(transfer.context, Some(context.clone()));
+
+        // the context is kept when the message is synced again from the node
+        let manager = crate::testing::get_account_manager().await;
+        let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
+        let mut message = crate::testing::GenerateMessageBuilder::default().build().await;
+        message.set_transfer_timings(Some(transfer.finish_timings()));
+        message.set_transfer_context(Some(context.clone()));
+        let mut account = account_handle.write().await;
+        account.save_messages(vec![message.clone()]).await.unwrap();
+        message.set_transfer_timings(None);
+        message.set_transfer_context(None);
+        account.save_messages(vec![message.clone()]).await.unwrap();
+        let stored = account.get_message(message.id()).await.unwrap();
+        assert_eq!(stored.transfer_context(), &Some(context));
+    }
+
+    #[test]
+    fn pre_sign_hook() {
+        use super::{Transfer, Veto};
+        use crate::event::{PreparedTransactionData, TransactionIO};
+        use std::num::NonZeroU64;
+
+        fn allowlist(data: &PreparedTransactionData) -> Result<(), Veto> {
+            match data
+                .outputs
+                .iter()
+                .find(|output| output.remainder != Some(true) && !output.address.starts_with("atoi1allowed"))
+            {
+                Some(output) => Err(Veto(format!("{} isn't allowlisted", output.address))),
+                None => Ok(()),
+            }
+        }
+        let prepared_transaction_data = |address: &str| PreparedTransactionData {
+            inputs: Vec::new(),
+            outputs: vec![
+                TransactionIO {
+                    address: address.to_string(),
+                    amount: 1_000_000,
+                    remainder: Some(false),
+                },
+                TransactionIO {
+                    address: "atoi1remainder".to_string(),
+                    amount: 1_000_000,
+                    remainder: Some(true),
+                },
+            ],
+            data: None,
+        };
+        let builder = || {
+            Transfer::builder(
+                crate::testing::generate_random_iota_address(),
+                NonZeroU64::new(1_000_000).unwrap(),
+                None,
+            )
+        };
+
+        let transfer = builder().with_pre_sign_hook(allowlist).finish().unwrap();
+        assert!(transfer
+            .check_pre_sign_hook(&prepared_transaction_data("atoi1allowed"))
+            .is_ok());
+        match transfer.check_pre_sign_hook(&prepared_transaction_data("atoi1other")) {
+            Err(crate::Error::TransferVetoed(reason)) => assert_eq!(reason, "atoi1other isn't allowlisted"),
+            _ => panic!("the transfer should be vetoed"),
+        }
+
+        // without hook every transaction is signed
+        let transfer = builder().finish().unwrap();
+        assert!(transfer
+            .check_pre_sign_hook(&prepared_transaction_data("atoi1other"))
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn verify() {
+        use super::{IndexationPayload, IotaMessage, Message, MessageId, Parents, Payload};
+        use crate::client::ClientOptionsBuilder;
+        use iota_client::bee_message::MessageBuilder;
+
+        let iota_message: IotaMessage = MessageBuilder::new()
+            .with_nonce_provider(crate::testing::NoopNonceProvider {}, 4000f64)
+            .with_parents(Parents::new(vec![MessageId::new([0; 32])]).unwrap())
+            .with_payload(Payload::Indexation(Box::new(
+                IndexationPayload::new(b"index", &[0; 16]).unwrap(),
+            )))
+            .with_network_id(0)
+            .finish()
+            .unwrap();
+        let message = Message::from_iota_message(
+            iota_message.id().0,
+            iota_message,
+            crate::account_manager::AccountStore::new(Default::default()),
+            "",
+            &[crate::testing::generate_random_address()],
+            &ClientOptionsBuilder::new().build().unwrap(),
+        )
+        .finish()
+        .await
+        .unwrap();
+        message.verify().unwrap();
+
+        let mut tampered = message.clone();
+        tampered.nonce += 1;
+        assert!(matches!(
+            tampered.verify(),
+            Err(crate::Error::MessageVerificationFailed(_))
+        ));
+
+        // the raw bytes survive the storage round trip
+        let value = serde_json::to_value(&message).unwrap();
+        let stored: Message = serde_json::from_value(value).unwrap();
+        assert_eq!(stored.raw(), message.raw());
+
+        let mut unverifiable = message;
+        unverifiable.raw = None;
+        assert!(matches!(unverifiable.verify(), Err(crate::Error::MissingRawMessage)));
+    }
+
+    #[test]
+    fn output_builder() {
+        use super::{FeatureBlock, OutputBuilder, OutputKind, UnlockCondition};
+        use std::num::NonZeroU64;
+
+        let address = crate::testing::generate_random_iota_address();
+        let output = OutputBuilder::new(NonZeroU64::new(1_000_000).unwrap())
+            .with_unlock_condition(UnlockCondition::Address(address.clone()))
+            .with_feature_block(FeatureBlock::DustAllowance)
+            .finish()
+            .unwrap();
+        assert_eq!(output.address, address);
+        assert_eq!(output.output_kind, OutputKind::SignatureLockedDustAllowance);
+
+        let output = OutputBuilder::new(NonZeroU64::new(1).unwrap())
+            .with_unlock_condition(UnlockCondition::Address(address.clone()))
+            .finish()
+            .unwrap();
+        assert_eq!(output.output_kind, OutputKind::SignatureLockedSingle);
+
+        assert!(matches!(
+            OutputBuilder::new(NonZeroU64::new(1).unwrap()).finish(),
+            Err(crate::Error::InvalidOutput(_))
+        ));
+        assert!(matches!(
+            OutputBuilder::new(NonZeroU64::new(1).unwrap())
+                .with_unlock_condition(UnlockCondition::Address(address.clone()))
+                .with_feature_block(FeatureBlock::DustAllowance)
+                .finish(),
+            Err(crate::Error::InvalidOutput(_))
+        ));
+        assert!(matches!(
+            OutputBuilder::new(NonZeroU64::new(1).unwrap())
+                .with_unlock_condition(UnlockCondition::Address(address.clone()))
+                .with_unlock_condition(UnlockCondition::Timelock(1_700_000_000))
+                .finish(),
+            Err(crate::Error::TimelockNotSupported)
+        ));
+        assert!(matches!(
+            OutputBuilder::new(NonZeroU64::new(1).unwrap())
+                .with_unlock_condition(UnlockCondition::Address(address))
+                .with_feature_block(FeatureBlock::Metadata(b"data".to_vec()))
+                .finish(),
+            Err(crate::Error::FeatureBlockNotSupported(_))
+        ));
+    }
+
+    struct ChatParser;
+
+    impl super::PayloadParser for ChatParser {
+        fn parse(&self, index: &[u8], data: &[u8]) -> Option<serde_json::Value> {
+            if index == b"WALLET_TEST_CHAT" {
+                Some(serde_json::json!({ "text": String::from_utf8_lossy(data) }))
+            } else {
+                None
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn custom_payload_parsing() {
+        super::register_payload_parser("chat", ChatParser).await;
+        let chat_payload = super::Payload::Indexation(Box::new(
+            super::IndexationPayload::new(b"WALLET_TEST_CHAT", b"hello").unwrap(),
+        ));
+        let other_payload =
+            super::Payload::Indexation(Box::new(super::IndexationPayload::new(b"OTHER", b"hello").unwrap()));
+
+        let custom_payload = super::parse_custom_payload(Some(&chat_payload)).await.unwrap();
+        assert_eq!(custom_payload.parser(), "chat");
+        assert_eq!(custom_payload.data()["text"], "hello");
+        assert!(super::parse_custom_payload(Some(&other_payload)).await.is_none());
+
+        super::remove_payload_parser("chat").await;
+        assert!(super::parse_custom_payload(Some(&chat_payload)).await.is_none());
+    }
+
+    #[test]
+    fn transfer_limits() {
+        use super::{IndexationPayload, RemainderValueStrategy, Transfer, TransferLimits};
+        use std::num::{NonZeroU64, NonZeroUsize};
+
+        let builder = Transfer::builder(
+            crate::testing::generate_random_iota_address(),
+            NonZeroU64::new(1_000_000).unwrap(),
+            None,
+        );
+        assert!(builder.clone().finish().is_ok());
+
+        let indexation = IndexationPayload::new(b"index", &[0; 101]).unwrap();
+        let res = builder
+            .clone()
+            .with_indexation(indexation)
+            .with_limits(TransferLimits {
+                max_indexation_data_size: 100,
+                ..Default::default()
+            })
+            .finish();
+        assert!(matches!(res, Err(crate::Error::IndexationDataTooLarge(101, 100))));
+
+        let res = Transfer::builder_with_outputs(Vec::new()).unwrap().finish();
+        assert!(matches!(res, Err(crate::Error::InvalidOutputCount(0, 124))));
+
+        let res = builder
+            .clone()
+            .with_remainder_value_strategy(RemainderValueStrategy::SplitChange(NonZeroUsize::new(125).unwrap()))
+            .finish();
+        assert!(matches!(res, Err(crate::Error::InvalidOutputCount(125, 124))));
+        let transfer = builder
+            .with_remainder_value_strategy(RemainderValueStrategy::SplitChange(NonZeroUsize::new(124).unwrap()))
+            .finish()
+            .unwrap();
+        assert_eq!(transfer.output_count(), 124);
+    }
+
+    #[test]
+    fn output_address_validation() {
+        use super::{Transfer, TransferOutput};
+        use std::{
+            num::NonZeroU64,
+            sync::atomic::{AtomicUsize, Ordering},
+        };
+
+        static WARNINGS: AtomicUsize = AtomicUsize::new(0);
+
+        let address = crate::testing::generate_random_iota_address();
+        let bech32 = address.to_bech32();
+        let builder = Transfer::builder(address.clone(), NonZeroU64::new(1_000_000).unwrap(), None);
+
+        let res = builder.clone().with_network_hrp("iota").finish();
+        assert!(matches!(res, Err(crate::Error::AddressNetworkMismatch { .. })));
+        assert!(builder
+            .clone()
+            .with_network_hrp(address.bech32_hrp().to_string())
+            .finish()
+            .is_ok());
+
+        let res = builder
+            .clone()
+            .with_address_validator(|_| Err("blocked".to_string()))
+            .finish();
+        assert!(matches!(res, Err(crate::Error::OutputAddressRejected(_, reason)) if reason == "blocked"));
+
+        let warning = |_: &TransferOutput| {
+            WARNINGS.fetch_add(1, Ordering::SeqCst);
+        };
+        assert!(builder
+            .with_exchange_address_warning(vec![address], warning)
+            .finish()
+            .is_ok());
+        assert_eq!(WARNINGS.load(Ordering::SeqCst), 1);
+
+        // swap the last checksum character
+        let last = if bech32.ends_with('q') { 'p' } else { 'q' };
+        let mistyped = format!("{}{}", &bech32[..bech32.len() - 1], last);
+        assert!(matches!(
+            crate::address::parse(&mistyped),
+            Err(crate::Error::InvalidAddressChecksum(_))
+        ));
+        assert!(crate::address::parse(&bech32).is_ok());
+    }
+
+    #[test]
+    fn change_address_verification() {
+        use super::ChangeAddressVerification;
+
+        assert!(ChangeAddressVerification::default().requires_verification(1));
+        assert!(!ChangeAddressVerification::Never.requires_verification(u64::MAX));
+        let policy = ChangeAddressVerification::AboveAmount(1_000_000);
+        assert!(!policy.requires_verification(999_999));
+        assert!(policy.requires_verification(1_000_000));
+    }
+
+    /// Parses the transaction for the account, returning its inputs.
+    async fn parse_transaction_inputs(
+        account_handle: &crate::account::AccountHandle,
+        id: &super::MessageId,
+        payload: super::Payload,
+    ) -> Vec<super::TransactionInput> {
+        let account = account_handle.read().await;
+        let payload = super::MessagePayload::new(
+            payload,
+            &super::TransactionBuilderMetadata {
+                id,
+                bech32_hrp: account.bech32_hrp(),
+                accounts: account_handle.accounts.clone(),
+                account_id: account.id(),
+                account_addresses: account.addresses(),
+                client_options: account.client_options(),
+            },
+        )
+        .await
+        .unwrap();
+        match payload {
+            super::MessagePayload::Transaction(tx) => {
+                let super::TransactionEssence::Regular(essence) = tx.essence();
+                essence.inputs().to_vec()
+            }
+            _ => panic!("unexpected payload"),
+        }
+    }
+
+    #[tokio::test]
+    async fn message_cache_shared_by_the_manager_accounts() {
+        use super::{MessageId, Payload, TransactionInput};
+        use crate::address::{AddressOutput, OutputKind};
+        use iota_client::bee_message::prelude::{
+            Ed25519Signature, Essence, RegularEssence, SignatureLockedSingleOutput, SignatureUnlock, TransactionId,
+            TransactionPayloadBuilder, UnlockBlock, UnlockBlocks, UtxoInput,
+        };
+
+        let manager = crate::testing::get_account_manager().await;
+        let sender_handle = crate::testing::AccountCreator::new(&manager)
+            .messages(vec![crate::testing::GenerateMessageBuilder::default().build().await])
+            .create()
+            .await;
+        let recipient_handle = manager
+            .create_account(sender_handle.read().await.client_options().clone())
+            .unwrap()
+            .alias("recipient")
+            .initialise()
+            .await
+            .unwrap();
+        let other_manager = crate::testing::get_account_manager().await;
+        let other_handle = crate::testing::AccountCreator::new(&other_manager).create().await;
+
+        let input = UtxoInput::new(TransactionId::new([1; 32]), 0).unwrap();
+        let recipient_address = recipient_handle.read().await.latest_address().address().clone();
+        let payload = Payload::Transaction(Box::new(
+            TransactionPayloadBuilder::new()
+                .with_essence(Essence::Regular(
+                    RegularEssence::builder()
+                        .add_input(input.clone().into())
+                        .add_output(
+                            SignatureLockedSingleOutput::new(*recipient_address.as_ref(), 1_000_000)
+                                .unwrap()
+                                .into(),
+                        )
+                        .finish()
+                        .unwrap(),
+                ))
+                .with_unlock_blocks(
+                    UnlockBlocks::new(vec![UnlockBlock::Signature(SignatureUnlock::Ed25519(
+                        Ed25519Signature::new([0; 32], [0; 64]),
+                    ))])
+                    .unwrap(),
+                )
+                .finish()
+                .unwrap(),
+        ));
+        let id = MessageId::new([1; 32]);
+
+        // the sender sync resolves the transaction inputs from the node
+        let resolved_inputs = vec![TransactionInput::Utxo(super::TransactionUtxoInput {
+            input: input.clone(),
+            metadata: Some(AddressOutput {
+                transaction_id: TransactionId::new([1; 32]),
+                message_id: MessageId::new([0; 32]),
+                index: 0,
+                amount: 1_000_000,
+                is_spent: true,
+                address: sender_handle.read().await.latest_address().address().clone(),
+                kind: OutputKind::SignatureLockedSingle,
+            }),
+        })];
+        let bech32_hrp = sender_handle.read().await.bech32_hrp();
+        sender_handle
+            .accounts
+            .message_cache()
+            .entry(&bech32_hrp, id)
+            .inputs
+            .replace(resolved_inputs.clone());
+
+        // the recipient sync reuses them instead of requesting the inputs again
+        assert_eq!(
+            parse_transaction_inputs(&recipient_handle, &id, payload.clone()).await,
+            resolved_inputs
+        );
+        // the accounts of another manager don't see them
+        assert_eq!(
+            parse_transaction_inputs(&other_handle, &id, payload).await,
+            vec![TransactionInput::Utxo(super::TransactionUtxoInput {
+                input,
+                metadata: None
+            })]
+        );
+    }
 }
@@ -2,27 +2,35 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    account::{AccountHandle, AccountSynchronizeStep},
+    account::{Account, AccountHandle, AccountSynchronizeStep},
     account_manager::{AccountStore, AccountsSynchronizer},
     address::{AddressOutput, AddressWrapper, IotaAddress},
     client::ClientOptions,
+    event::emit_monitoring_resubscribed,
     message::{Message, MessagePayload, TransactionEssence, TransactionInput, TransactionOutput},
 };
 
 use iota_client::{
-    bee_rest_api::types::{dtos::OutputDto, responses::OutputResponse},
+    bee_message::MessageId,
+    bee_rest_api::types::{
+        dtos::OutputDto,
+        responses::{MessageMetadataResponse, OutputResponse},
+    },
     Topic, TopicEvent,
 };
-use tokio::sync::RwLock;
+use once_cell::sync::Lazy;
+use tokio::sync::{mpsc::unbounded_channel, Mutex, RwLock};
 
-use std::{collections::HashMap, convert::TryInto, sync::Arc};
+use std::{collections::HashMap, convert::TryInto, sync::Arc, time::Duration};
 
-/// Unsubscribe from all topics associated with the account.
-pub async fn unsubscribe(account_handle: AccountHandle) -> crate::Result<()> {
-    let account = account_handle.read().await;
-    let client = crate::client::get_client(account.client_options()).await?;
-    let mut client = client.write().await;
+/// Time to wait for the message metadata through MQTT before falling back to polling the node.
+const CONFIRMATION_WATCH_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Serializes the re-subscriptions, so quick client options changes subscribe to the latest broker only.
+static RESUBSCRIBE_LOCK: Lazy<Mutex<()>> = Lazy::new(Default::default);
 
+/// The address outputs topics and the metadata topics of the unconfirmed messages of the account.
+async fn account_topics(account: &Account) -> crate::Result<Vec<Topic>> {
     let mut topics = Vec::new();
     for address in account.addresses() {
         topics.push(Topic::new(format!(
@@ -42,11 +50,48 @@ pub async fn unsubscribe(account_handle: AccountHandle) -> crate::Result<()> {
         })
         .await?;
     topics.extend(message_topics);
+    Ok(topics)
+}
 
+/// Unsubscribe from all topics associated with the account.
+pub async fn unsubscribe(account_handle: AccountHandle) -> crate::Result<()> {
+    let account = account_handle.read().await;
+    let topics = account_topics(&account).await?;
+    let client = crate::client::get_client(account.client_options()).await?;
+    let mut client = client.write().await;
     client.subscriber().with_topics(topics).unsubscribe().await?;
     Ok(())
 }
 
+/// Moves the account subscriptions from the broker of the previous client options to the current one and emits a
+/// [MonitoringResubscribed](../event/struct.MonitoringResubscribed.html) event.
+/// The confirmation watches of pending transfers aren't moved, they fall back to polling the node on timeout.
+pub(crate) async fn resubscribe(account_handle: AccountHandle, previous_options: ClientOptions) -> crate::Result<()> {
+    let _lock = RESUBSCRIBE_LOCK.lock().await;
+
+    if *previous_options.mqtt_enabled() && !previous_options.is_proxied() {
+        let topics = account_topics(&*account_handle.read().await).await?;
+        let client = crate::client::get_client(&previous_options).await?;
+        let mut client = client.write().await;
+        if let Err(err) = client.subscriber().with_topics(topics).unsubscribe().await {
+            // the previous broker might be gone, its subscriptions are dropped with the connection
+            log::debug!("[MQTT] unsubscribe error: {:?}", err);
+        }
+    }
+
+    let client_options = account_handle.client_options().await;
+    let topics = if account_handle.is_mqtt_enabled() && *client_options.mqtt_enabled() && !client_options.is_proxied() {
+        let address_count = account_handle.read().await.addresses().len();
+        monitor_account_addresses_balance(account_handle.clone()).await;
+        address_count
+    } else {
+        0
+    };
+    log::debug!("[MQTT] resubscribed {} topics", topics);
+    emit_monitoring_resubscribed(account_handle.read().await.id().clone(), topics).await;
+    Ok(())
+}
+
 #[cfg(test)]
 async fn subscribe_to_topics<C: Fn(&TopicEvent) + Send + Sync + 'static>(
     _client_options: ClientOptions,
@@ -74,6 +119,70 @@ async fn subscribe_to_topics<C: Fn(&TopicEvent) + Send + Sync + 'static>(
     }
 }
 
+/// Whether the message metadata published on the `messages/{id}/metadata` topic is referenced by a milestone.
+fn is_referenced(payload: &str) -> bool {
+    // we assume that the transaction is confirmed when it gets referenced by a milestone
+    serde_json::from_str::<MessageMetadataResponse>(payload)
+        .map(|metadata| metadata.ledger_inclusion_state.is_some())
+        .unwrap_or(false)
+}
+
+/// Watches the `messages/{id}/metadata` topic until the message is referenced by a milestone.
+/// Returns `false` if MQTT isn't available or the message wasn't referenced before the timeout,
+/// so the caller can fallback to polling the node.
+pub(crate) async fn watch_confirmation(client_options: &ClientOptions, message_id: MessageId) -> bool {
+    if !*client_options.mqtt_enabled() || client_options.is_proxied() {
+        return false;
+    }
+    let topic = format!("messages/{}/metadata", message_id);
+
+    let (sender, mut receiver) = unbounded_channel();
+    let sender_ = sender.clone();
+    subscribe_to_topics(
+        client_options.clone(),
+        // safe to unwrap: we know the topic is valid
+        vec![Topic::new(topic.clone()).unwrap()],
+        move |topic_event| {
+            if is_referenced(&topic_event.payload) {
+                let _ = sender_.send(());
+            }
+        },
+    )
+    .await;
+
+    let client = match crate::client::get_client(client_options).await {
+        Ok(client) => client,
+        Err(_) => return false,
+    };
+    // the message might have been referenced before the subscription
+    if let Ok(metadata) = client.read().await.get_message().metadata(&message_id).await {
+        if metadata.ledger_inclusion_state.is_some() {
+            let _ = sender.send(());
+        }
+    }
+
+    let confirmed = matches!(
+        tokio::time::timeout(CONFIRMATION_WATCH_TIMEOUT, receiver.recv()).await,
+        Ok(Some(()))
+    );
+    log::debug!(
+        "[MQTT] confirmation watch for {} finished, confirmed: {}",
+        message_id,
+        confirmed
+    );
+
+    // safe to unwrap: we know the topic is valid
+    let _ = client
+        .write()
+        .await
+        .subscriber()
+        .with_topics(vec![Topic::new(topic).unwrap()])
+        .unsubscribe()
+        .await;
+
+    confirmed
+}
+
 /// Monitor account addresses for balance changes.
 pub async fn monitor_account_addresses_balance(account_handle: AccountHandle) {
     let addresses = account_handle
@@ -91,7 +200,10 @@ pub async fn monitor_address_balance(account_handle: AccountHandle, addresses: V
     let client_options = account_handle.client_options().await;
     let client_options_ = client_options.clone();
 
-    if *client_options.mqtt_enabled() {
+    if *client_options.mqtt_enabled() && client_options.is_proxied() {
+        // the MQTT connection can't be routed through the proxy, so we rely on polling instead of leaking it
+        log::warn!("[MQTT] the client options use a proxy, the addresses are synced by polling instead of MQTT");
+    } else if *client_options.mqtt_enabled() {
         subscribe_to_topics(
             client_options_,
             // safe to unwrap: we know the topics are valid
@@ -146,6 +258,7 @@ async fn process_output(payload: String, account_handle: AccountHandle) -> crate
     };
 
     let output = AddressOutput::from_output_response(output, address.bech32_hrp().to_string())?;
+    account_handle.process_payment_watches(account.id(), &output).await;
     let message = match account.get_message(&output.message_id).await {
         Some(message) => {
             if !message.confirmed().unwrap_or(false) {
@@ -252,3 +365,99 @@ async fn process_output(payload: String, account_handle: AccountHandle) -> crate
 
     Ok(true)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{is_referenced, process_output, watch_confirmation};
+    use crate::{address::IotaAddress, client::ClientOptionsBuilder};
+    use iota_client::bee_message::MessageId;
+
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    #[test]
+    fn referenced_metadata() {
+        let metadata = |ledger_inclusion_state: &str| {
+            format!(
+                r#"{{"messageId":"{}","parentMessageIds":[],"isSolid":true{}}}"#,
+                MessageId::new([1; 32]),
+                ledger_inclusion_state
+            )
+        };
+        assert!(is_referenced(&metadata(
+            r#","referencedByMilestoneIndex":5,"ledgerInclusionState":"included""#
+        )));
+        assert!(is_referenced(&metadata(
+            r#","referencedByMilestoneIndex":5,"ledgerInclusionState":"conflicting","conflictReason":1"#
+        )));
+        assert!(!is_referenced(&metadata("")));
+        assert!(!is_referenced("not a metadata payload"));
+    }
+
+    #[tokio::test]
+    async fn watch_confirmation_without_mqtt() {
+        let client_options = ClientOptionsBuilder::new()
+            .with_node("https://api.lb-0.h.chrysalis-devnet.iota.cafe")
+            .unwrap()
+            .with_mqtt_disabled()
+            .build()
+            .unwrap();
+        // returns immediately so the caller falls back to polling the node
+        assert!(!watch_confirmation(&client_options, MessageId::new([1; 32])).await);
+    }
+
+    #[tokio::test]
+    async fn payment_watch_output() {
+        let manager = crate::testing::get_account_manager().await;
+        let address = crate::testing::generate_random_address();
+        // the output message is already confirmed so it isn't requested from the node
+        let message = crate::testing::GenerateMessageBuilder::default()
+            .address(address.clone())
+            .confirmed(Some(true))
+            .build()
+            .await;
+        let message_id = *message.id();
+        let account_handle = crate::testing::AccountCreator::new(&manager)
+            .addresses(vec![address.clone()])
+            .messages(vec![message])
+            .create()
+            .await;
+        let account_id = account_handle.id().await;
+        account_handle
+            .watch_address_for_payment(address.address().clone(), 100, Duration::from_secs(60))
+            .await
+            .unwrap();
+
+        let payments = Arc::new(Mutex::new(Vec::new()));
+        let payments_ = payments.clone();
+        crate::event::on_payment_received(move |event| {
+            if event.account_id == account_id {
+                payments_.lock().unwrap().push(event.amount);
+            }
+        })
+        .await;
+
+        let IotaAddress::Ed25519(ed25519_address) = address.address().as_ref();
+        let payload = serde_json::json!({
+            "messageId": message_id.to_string(),
+            "transactionId": "0".repeat(64),
+            "outputIndex": 0,
+            "isSpent": false,
+            "ledgerIndex": 0,
+            "output": {
+                "type": 0,
+                "address": { "type": 0, "address": hex::encode(ed25519_address) },
+                "amount": 150,
+            },
+        })
+        .to_string();
+        // the watch is processed while the account is locked by the output processing
+        let processed = tokio::time::timeout(Duration::from_secs(10), process_output(payload, account_handle.clone()))
+            .await
+            .expect("the output processing deadlocked");
+        assert!(!processed.unwrap());
+        assert_eq!(*payments.lock().unwrap(), vec![150]);
+    }
+}
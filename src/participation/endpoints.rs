@@ -7,28 +7,26 @@ use crate::participation::{
 };
 
 use serde::{Deserialize, Serialize};
+use url::Url;
 
 /// GET /api/plugins/participation/events : Lists all events, returning their EventID.
-pub(crate) async fn get_events(
-    mut node: iota_client::node_manager::Node,
-    event_type: Option<ParticipationEventType>,
-) -> crate::Result<EventIds> {
+pub(crate) async fn get_events(mut url: Url, event_type: Option<ParticipationEventType>) -> crate::Result<EventIds> {
     if let Some(event_type) = event_type {
         let query_string = match event_type {
             ParticipationEventType::Voting => "0",
             ParticipationEventType::Staking => "1",
         };
-        node.url.set_query(Some(query_string));
+        url.set_query(Some(query_string));
     }
 
     let path = "/api/plugins/participation/events";
-    node.url.set_path(path);
+    url.set_path(path);
 
     #[derive(Debug, Serialize, Deserialize)]
     struct ResponseWrapper {
         data: EventIds,
     }
-    let res = reqwest::get(node.url).await?;
+    let res = reqwest::get(url).await?;
     let status_code = res.status().as_u16();
     let res_text = res.text().await?;
     match status_code {
@@ -41,18 +39,15 @@ pub(crate) async fn get_events(
 }
 
 // GET /api/plugins/participation/events/{eventID} : Returns the event information as a JSON payload.
-pub(crate) async fn get_event_information(
-    mut node: iota_client::node_manager::Node,
-    event_id: &str,
-) -> crate::Result<EventInformation> {
+pub(crate) async fn get_event_information(mut url: Url, event_id: &str) -> crate::Result<EventInformation> {
     let path = &format!("/api/plugins/participation/events/{}", event_id);
-    node.url.set_path(path);
+    url.set_path(path);
 
     #[derive(Debug, Serialize, Deserialize)]
     struct ResponseWrapper {
         data: EventInformation,
     }
-    let res = reqwest::get(node.url).await?;
+    let res = reqwest::get(url).await?;
     let status_code = res.status().as_u16();
     let res_text = res.text().await?;
     match status_code {
@@ -67,18 +62,15 @@ pub(crate) async fn get_event_information(
 // GET /api/plugins/participation/events/{eventID}/status : Returns the status of the given event
 // (upcoming,commencing,holding,ended) and if it contains a Ballot, the current and accumulated answers for each
 // question.
-pub(crate) async fn get_event_status(
-    mut node: iota_client::node_manager::Node,
-    event_id: &str,
-) -> crate::Result<EventStatus> {
+pub(crate) async fn get_event_status(mut url: Url, event_id: &str) -> crate::Result<EventStatus> {
     let path = &format!("/api/plugins/participation/events/{}/status", event_id);
-    node.url.set_path(path);
+    url.set_path(path);
 
     #[derive(Debug, Serialize, Deserialize)]
     struct ResponseWrapper {
         data: EventStatus,
     }
-    let res = reqwest::get(node.url).await?;
+    let res = reqwest::get(url).await?;
     let status_code = res.status().as_u16();
     let res_text = res.text().await?;
     match status_code {
@@ -5,5 +5,7 @@ pub(crate) mod account_helpers;
 pub(crate) mod endpoints;
 /// Responses for the api endpoints related to participation
 pub mod response_types;
+/// Local staking rewards calculation
+pub mod rewards;
 /// Types related to participation
 pub mod types;
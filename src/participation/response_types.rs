@@ -1,6 +1,9 @@
 // Copyright 2021 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::participation::types::ParticipationEventType;
+
+use getset::{CopyGetters, Getters};
 use serde::{Deserialize, Serialize};
 
 use std::collections::HashMap;
@@ -13,15 +16,25 @@ pub(crate) struct EventIds {
 }
 
 /// Information about a voting or staking event
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Getters, CopyGetters)]
 pub struct EventInformation {
+    /// The event name.
+    #[getset(get = "pub")]
     name: String,
+    /// The milestone at which the event commences, accepting participations without counting them.
     #[serde(rename = "milestoneIndexCommence")]
+    #[getset(get_copy = "pub")]
     milestone_index_commence: u32,
+    /// The milestone at which the participations start to count.
     #[serde(rename = "milestoneIndexStart")]
+    #[getset(get_copy = "pub")]
     milestone_index_start: u32,
+    /// The milestone at which the event ends.
     #[serde(rename = "milestoneIndexEnd")]
+    #[getset(get_copy = "pub")]
     milestone_index_end: u32,
+    /// The voting or staking payload.
+    #[getset(get = "pub")]
     payload: EventPayload,
     #[serde(rename = "additionalInfo")]
     additional_info: String,
@@ -37,16 +50,34 @@ pub enum EventPayload {
     StakingEventPayload(StakingEventPayload),
 }
 
+impl EventPayload {
+    /// The type of the event.
+    pub fn event_type(&self) -> ParticipationEventType {
+        match self {
+            Self::VotingEventPayload(_) => ParticipationEventType::Voting,
+            Self::StakingEventPayload(_) => ParticipationEventType::Staking,
+        }
+    }
+}
+
 /// Payload for a staking event
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Getters, CopyGetters)]
 pub struct StakingEventPayload {
     #[serde(rename = "type")]
     kind: u32,
     text: String,
+    /// The symbol of the rewarded token.
+    #[getset(get = "pub")]
     symbol: String,
+    /// The numerator of the rewards per staked IOTA and milestone.
+    #[getset(get_copy = "pub")]
     numerator: u64,
+    /// The denominator of the rewards per staked IOTA and milestone.
+    #[getset(get_copy = "pub")]
     denominator: u64,
+    /// The rewards an address must reach to be paid out.
     #[serde(rename = "requiredMinimumRewards")]
+    #[getset(get_copy = "pub")]
     required_minimum_rewards: u64,
     #[serde(rename = "additionalInfo")]
     additional_info: String,
@@ -143,3 +174,35 @@ pub struct TrackedParticipation {
     #[serde(rename = "endMilestoneIndex")]
     pub end_milestone_index: u32,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::EventInformation;
+    use crate::participation::types::ParticipationEventType;
+
+    #[test]
+    fn event_metadata() {
+        let information: EventInformation = serde_json::from_str(
+            r#"{
+                "name": "Shimmer Staking",
+                "milestoneIndexCommence": 1000,
+                "milestoneIndexStart": 2000,
+                "milestoneIndexEnd": 3000,
+                "payload": {
+                    "type": 1,
+                    "text": "The rewards for staking IOTA",
+                    "symbol": "SMR",
+                    "numerator": 1,
+                    "denominator": 1,
+                    "requiredMinimumRewards": 10000000,
+                    "additionalInfo": ""
+                },
+                "additionalInfo": ""
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(information.name(), "Shimmer Staking");
+        assert_eq!(information.milestone_index_start(), 2000);
+        assert_eq!(information.payload().event_type(), ParticipationEventType::Staking);
+    }
+}
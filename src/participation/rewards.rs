@@ -0,0 +1,196 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::participation::{
+    response_types::{EventInformation, EventPayload, TrackedParticipation},
+    types::OutputStatusResponses,
+};
+
+use iota_client::bee_message::output::OutputId;
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+
+/// The staking rewards of an account.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StakingOverview {
+    /// The milestone the rewards are calculated at.
+    #[serde(rename = "milestoneIndex")]
+    pub milestone_index: u32,
+    /// The rewards per staking event id.
+    pub events: HashMap<String, EventStakingRewards>,
+}
+
+/// The rewards accrued for a staking event.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EventStakingRewards {
+    /// The symbol of the rewarded token.
+    pub symbol: String,
+    /// The rewards of all addresses.
+    pub rewards: u64,
+    /// The rewards of the addresses that reached the required minimum, which will be paid out.
+    #[serde(rename = "rewardsAboveMinimum")]
+    pub rewards_above_minimum: u64,
+    /// The rewards per bech32 address.
+    pub addresses: HashMap<String, u64>,
+}
+
+/// Rewards accrued by a staked output at the given milestone, mirroring the node plugin: the output earns
+/// `amount * numerator / denominator` for each milestone of the event it's staked at, from the milestone it started
+/// staking until the milestone it was spent at (exclusive).
+pub fn accrued_rewards(participation: &TrackedParticipation, event: &EventInformation, milestone_index: u32) -> u64 {
+    let payload = match event.payload() {
+        EventPayload::StakingEventPayload(payload) => payload,
+        EventPayload::VotingEventPayload(_) => return 0,
+    };
+    if payload.denominator() == 0 {
+        return 0;
+    }
+    let first = participation
+        .start_milestone_index
+        .max(event.milestone_index_start() + 1);
+    // an end milestone index of 0 means the output is still staking
+    let end = if participation.end_milestone_index == 0 {
+        milestone_index.saturating_add(1)
+    } else {
+        participation.end_milestone_index
+    };
+    let end = end.min(event.milestone_index_end().saturating_add(1));
+    let milestones = end.saturating_sub(first) as u128;
+    let per_milestone = participation.amount as u128 * payload.numerator() as u128 / payload.denominator() as u128;
+    (per_milestone * milestones).min(u64::MAX as u128) as u64
+}
+
+/// Calculates the staking rewards of the stored output participations.
+/// `output_addresses` maps the outputs to their bech32 address; outputs of unknown events are ignored.
+pub fn calculate_staking_rewards(
+    participation_outputs: &OutputStatusResponses,
+    output_addresses: &HashMap<OutputId, String>,
+    events: &HashMap<String, EventInformation>,
+    milestone_index: u32,
+) -> StakingOverview {
+    let mut overview = StakingOverview {
+        milestone_index,
+        events: HashMap::new(),
+    };
+    // an output spent since the last overview is in both maps, the spent entry is the up to date one
+    let outputs = participation_outputs.spent.iter().chain(
+        participation_outputs
+            .unspent
+            .iter()
+            .filter(|(output_id, _)| !participation_outputs.spent.contains_key(output_id)),
+    );
+    for (output_id, status) in outputs {
+        let address = match output_addresses.get(output_id) {
+            Some(address) => address,
+            None => continue,
+        };
+        for (event_id, participation) in &status.participations {
+            let event = match events.get(event_id) {
+                Some(event) => event,
+                None => continue,
+            };
+            let payload = match event.payload() {
+                EventPayload::StakingEventPayload(payload) => payload,
+                EventPayload::VotingEventPayload(_) => continue,
+            };
+            let rewards = overview
+                .events
+                .entry(event_id.clone())
+                .or_insert_with(|| EventStakingRewards {
+                    symbol: payload.symbol().clone(),
+                    ..Default::default()
+                });
+            let accrued = accrued_rewards(participation, event, milestone_index);
+            *rewards.addresses.entry(address.clone()).or_default() += accrued;
+            rewards.rewards += accrued;
+        }
+    }
+    for (event_id, rewards) in overview.events.iter_mut() {
+        if let Some(EventPayload::StakingEventPayload(payload)) = events.get(event_id).map(|event| event.payload()) {
+            rewards.rewards_above_minimum = rewards
+                .addresses
+                .values()
+                .filter(|rewards| **rewards >= payload.required_minimum_rewards())
+                .sum();
+        }
+    }
+    overview
+}
+
+#[cfg(test)]
+mod tests {
+    use super::calculate_staking_rewards;
+    use crate::participation::{
+        response_types::{EventInformation, OutputStatusResponse, TrackedParticipation},
+        types::OutputStatusResponses,
+    };
+    use iota_client::bee_message::prelude::{OutputId, TransactionId};
+
+    use std::collections::HashMap;
+
+    #[test]
+    fn staking_rewards() {
+        let event_id = "0".repeat(64);
+        let event: EventInformation = serde_json::from_str(
+            r#"{
+                "name": "Staking",
+                "milestoneIndexCommence": 100,
+                "milestoneIndexStart": 200,
+                "milestoneIndexEnd": 300,
+                "payload": {
+                    "type": 1,
+                    "text": "",
+                    "symbol": "SMR",
+                    "numerator": 1,
+                    "denominator": 2,
+                    "requiredMinimumRewards": 1000,
+                    "additionalInfo": ""
+                },
+                "additionalInfo": ""
+            }"#,
+        )
+        .unwrap();
+        let tracked = |start_milestone_index, end_milestone_index, amount| OutputStatusResponse {
+            participations: vec![(
+                event_id.clone(),
+                TrackedParticipation {
+                    message_id: String::new(),
+                    amount,
+                    start_milestone_index,
+                    end_milestone_index,
+                },
+            )]
+            .into_iter()
+            .collect(),
+        };
+        let output_id = |index| OutputId::new(TransactionId::new([index; 32]), 0).unwrap();
+
+        let mut participation_outputs = OutputStatusResponses {
+            spent: HashMap::new(),
+            unspent: HashMap::new(),
+        };
+        // staked before the event start and spent at milestone 211: 10 milestones
+        participation_outputs.spent.insert(output_id(0), tracked(150, 211, 100));
+        // staked at milestone 251 and still staking: 50 milestones at milestone 350, since the event ended at 300
+        participation_outputs.unspent.insert(output_id(1), tracked(251, 0, 10));
+        participation_outputs.unspent.insert(output_id(2), tracked(251, 0, 2));
+
+        let output_addresses = vec![
+            (output_id(0), "first".to_string()),
+            (output_id(1), "first".to_string()),
+            (output_id(2), "second".to_string()),
+        ]
+        .into_iter()
+        .collect();
+        let events = vec![(event_id.clone(), event)].into_iter().collect();
+
+        let overview = calculate_staking_rewards(&participation_outputs, &output_addresses, &events, 350);
+        let rewards = &overview.events[&event_id];
+        assert_eq!(rewards.symbol, "SMR");
+        assert_eq!(rewards.addresses["first"], 10 * 50 + 50 * 5);
+        assert_eq!(rewards.addresses["second"], 50);
+        assert_eq!(rewards.rewards, 800);
+        assert_eq!(rewards.rewards_above_minimum, 0);
+    }
+}
@@ -11,6 +11,7 @@ use std::{collections::HashMap, convert::TryInto, io::Read};
 pub(crate) const PARTICIPATE: &str = "PARTICIPATE";
 
 /// Possible participation event types
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ParticipationEventType {
     /// Voting event
     Voting,
@@ -31,3 +31,23 @@ pub(crate) mod iota_address_serde {
         deserializer.deserialize_str(AddressVisitor)
     }
 }
+
+pub(crate) mod hex_bytes_option_serde {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &Option<Vec<u8>>, s: S) -> std::result::Result<S::Ok, S::Error> {
+        match bytes {
+            Some(bytes) => s.serialize_some(&hex::encode(bytes)),
+            None => s.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Vec<u8>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Option::<String>::deserialize(deserializer)?
+            .map(|bytes| hex::decode(bytes).map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
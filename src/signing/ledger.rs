@@ -13,6 +13,8 @@ use tokio::sync::Mutex;
 // ledger status codes https://github.com/iotaledger/ledger-iota-app/blob/53c1f96d15f8b014ba8ba31a85f0401bb4d33e18/src/iota_io.h#L54
 
 pub const HARDENED: u32 = 0x80000000;
+/// The number of transaction inputs and outputs together the device app can sign.
+pub(crate) const MAX_IN_OUTPUTS: usize = 17;
 const MAX_POOL_SIZE: usize = 10_000;
 
 #[derive(Default)]
@@ -20,6 +22,8 @@ pub struct LedgerNanoSigner {
     pub is_simulator: bool,
     pub address_pool: Mutex<HashMap<iota_client::bee_message::address::Address, HashMap<AddressPoolEntry, [u8; 32]>>>,
     pub mutex: Mutex<()>,
+    /// Minimum number of addresses fetched from the device when an address isn't in the pool.
+    pub prefetch_count: usize,
 }
 
 /// A record matching an Input with its address.
@@ -190,7 +194,7 @@ impl super::Signer for LedgerNanoSigner {
                 *addr_pool = HashMap::new();
             }
 
-            let count = 15;
+            let count = std::cmp::max(15, self.prefetch_count);
             let ledger = iota_ledger::get_ledger(0x107a, bip32_account, self.is_simulator)?;
             /*
                         let compiled_for = match ledger.is_debug_app() {
@@ -230,6 +234,33 @@ impl super::Signer for LedgerNanoSigner {
         ))
     }
 
+    async fn generate_addresses(
+        &mut self,
+        account: &Account,
+        indexes: &[usize],
+        internal: bool,
+        meta: super::GenerateAddressMetadata,
+    ) -> crate::Result<Vec<iota_client::bee_message::address::Address>> {
+        // fetch the whole batch on the first pool miss, so the device is opened once
+        self.prefetch_count = match (indexes.iter().min(), indexes.iter().max()) {
+            (Some(min), Some(max)) => max - min + 1,
+            _ => 0,
+        };
+        let mut addresses = Vec::with_capacity(indexes.len());
+        let mut result = Ok(());
+        for index in indexes {
+            match self.generate_address(account, *index, internal, meta.clone()).await {
+                Ok(address) => addresses.push(address),
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            }
+        }
+        self.prefetch_count = 0;
+        result.map(|_| addresses)
+    }
+
     async fn sign_message<'a>(
         &mut self,
         account: &Account,
@@ -351,4 +382,14 @@ impl super::Signer for LedgerNanoSigner {
         }
         Ok(unlock_blocks)
     }
+
+    fn capabilities(&self) -> super::SignerCapabilities {
+        super::SignerCapabilities {
+            max_input_output_count: Some(MAX_IN_OUTPUTS),
+            // the essence is shown to the user before signing
+            blind_signing: false,
+            internal_address_display: true,
+            ..Default::default()
+        }
+    }
 }
@@ -0,0 +1,226 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! Deterministic in-memory mnemonic signer, meant for test vectors only.
+//! The seed is kept in memory, so this signer must never be used with real funds.
+
+use crate::account::Account;
+
+use crypto::{
+    hashes::{blake2b::Blake2b256, Digest},
+    keys::{
+        bip39,
+        slip10::{Chain, Curve, Seed},
+    },
+};
+use iota_client::bee_message::{
+    address::{Address, Ed25519Address},
+    prelude::Ed25519Signature,
+    unlock::{ReferenceUnlock, UnlockBlock},
+};
+use zeroize::Zeroize;
+
+use std::{
+    collections::HashMap,
+    convert::TryInto,
+    path::{Path, PathBuf},
+};
+
+/// Encodes the given entropy as an english mnemonic.
+pub fn mnemonic_from_entropy(entropy: &[u8]) -> crate::Result<String> {
+    bip39::wordlist::encode(entropy, &bip39::wordlist::ENGLISH)
+        .map_err(|e| crate::Error::MnemonicEncode(format!("{:?}", e)))
+}
+
+#[derive(Zeroize)]
+#[zeroize(drop)]
+struct SeedBytes([u8; 64]);
+
+/// Signer deriving the keys from a mnemonic held in memory.
+/// If the signer was created with [with_entropy](#method.with_entropy),
+/// the injected entropy replaces every stored mnemonic so the generated addresses and signatures are reproducible.
+#[derive(Default)]
+pub struct MnemonicSigner {
+    entropy: Option<Vec<u8>>,
+    seeds: HashMap<PathBuf, SeedBytes>,
+}
+
+impl MnemonicSigner {
+    /// Creates a signer that derives its seed from the given entropy.
+    pub fn with_entropy(entropy: &[u8]) -> Self {
+        Self {
+            entropy: Some(entropy.to_vec()),
+            seeds: HashMap::new(),
+        }
+    }
+
+    fn derive(
+        &self,
+        storage_path: &Path,
+        account_index: usize,
+        address_index: usize,
+        internal: bool,
+    ) -> crate::Result<crypto::signatures::ed25519::SecretKey> {
+        let seed = self.seeds.get(storage_path).ok_or(crate::Error::RecordNotFound)?;
+        let chain = Chain::from_u32_hardened(vec![
+            44,
+            4218,
+            account_index
+                .try_into()
+                .map_err(|_| crate::Error::InvalidMnemonic("account index out of range".to_string()))?,
+            internal as u32,
+            address_index
+                .try_into()
+                .map_err(|_| crate::Error::InvalidMnemonic("address index out of range".to_string()))?,
+        ]);
+        Seed::from_bytes(&seed.0)
+            .derive(Curve::Ed25519, &chain)
+            .and_then(|key| key.secret_key())
+            .map_err(|e| crate::Error::InvalidMnemonic(format!("{:?}", e)))
+    }
+}
+
+#[async_trait::async_trait]
+impl super::Signer for MnemonicSigner {
+    async fn get_ledger_status(&self, _is_simulator: bool) -> crate::LedgerStatus {
+        // dummy status, function is only required in the trait because we need it for the LedgerSigner
+        crate::LedgerStatus {
+            connected: false,
+            locked: false,
+            app: None,
+        }
+    }
+
+    async fn store_mnemonic(&mut self, storage_path: &Path, mut mnemonic: String) -> crate::Result<()> {
+        if let Some(entropy) = &self.entropy {
+            mnemonic.zeroize();
+            mnemonic = mnemonic_from_entropy(entropy)?;
+        }
+        let mut seed = SeedBytes([0u8; 64]);
+        bip39::mnemonic_to_seed(&mnemonic, "", &mut seed.0);
+        mnemonic.zeroize();
+        self.seeds.insert(storage_path.to_path_buf(), seed);
+        Ok(())
+    }
+
+    async fn generate_address(
+        &mut self,
+        account: &Account,
+        address_index: usize,
+        internal: bool,
+        _: super::GenerateAddressMetadata,
+    ) -> crate::Result<Address> {
+        let secret_key = self.derive(account.storage_path(), *account.index(), address_index, internal)?;
+        let hash = Blake2b256::digest(&secret_key.public_key().to_bytes());
+        // safe to unwrap since the Blake2b256 digest is 32 bytes long
+        Ok(Address::Ed25519(Ed25519Address::new(hash.try_into().unwrap())))
+    }
+
+    async fn public_key(&mut self, account: &Account, address_index: usize, internal: bool) -> crate::Result<[u8; 32]> {
+        let secret_key = self.derive(account.storage_path(), *account.index(), address_index, internal)?;
+        Ok(secret_key.public_key().to_bytes())
+    }
+
+    async fn sign_message<'a>(
+        &mut self,
+        account: &Account,
+        essence: &iota_client::bee_message::prelude::Essence,
+        inputs: &mut Vec<super::TransactionInput>,
+        _: super::SignMessageMetadata<'a>,
+    ) -> crate::Result<Vec<UnlockBlock>> {
+        let mut unlock_blocks = vec![];
+        let mut signature_indexes = HashMap::<String, usize>::new();
+        inputs.sort_by(|a, b| a.input.cmp(&b.input));
+        let hashed_essence = essence.hash();
+
+        for (current_block_index, recorder) in inputs.iter().enumerate() {
+            let signature_index = format!("{}{}", recorder.address_index, recorder.address_internal);
+            if let Some(block_index) = signature_indexes.get(&signature_index) {
+                unlock_blocks.push(UnlockBlock::Reference(ReferenceUnlock::new(*block_index as u16)?));
+            } else {
+                let secret_key = self.derive(
+                    account.storage_path(),
+                    *account.index(),
+                    recorder.address_index,
+                    recorder.address_internal,
+                )?;
+                let signature = Ed25519Signature::new(
+                    secret_key.public_key().to_bytes(),
+                    secret_key.sign(&hashed_essence).to_bytes(),
+                );
+                unlock_blocks.push(UnlockBlock::Signature(signature.into()));
+                signature_indexes.insert(signature_index, current_block_index);
+            }
+        }
+        Ok(unlock_blocks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signing::{GenerateAddressMetadata, Network, Signer};
+
+    #[tokio::test]
+    async fn known_answer() {
+        // BIP39 test vectors
+        assert_eq!(
+            mnemonic_from_entropy(&[0u8; 16]).unwrap(),
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about"
+        );
+        assert_eq!(
+            mnemonic_from_entropy(&[0x7f; 16]).unwrap(),
+            "legal winner thank year wave sausage worth useful legal winner thank yellow"
+        );
+        assert_eq!(
+            mnemonic_from_entropy(&[0xff; 32]).unwrap(),
+            format!("{} vote", vec!["zoo"; 23].join(" "))
+        );
+
+        // the injected entropy replaces the stored mnemonic, whose seed is derived without passphrase
+        let storage_path = Path::new("known-answer");
+        let mut signer = MnemonicSigner::with_entropy(&[0u8; 16]);
+        signer
+            .store_mnemonic(storage_path, "ignored".to_string())
+            .await
+            .unwrap();
+        assert_eq!(
+            hex::encode(signer.seeds[storage_path].0),
+            "5eb00bbddcf069084889a8ab9155568165f5c453ccb85e70811aaed6f6da5fc1\
+             9a5ac40b389cd370d086206dec8aa6c43daea6690f20ad3d8d48b2d2ce9e38e4"
+        );
+    }
+
+    #[tokio::test]
+    async fn deterministic_addresses() {
+        let manager = crate::testing::get_account_manager().await;
+        let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
+        let account = account_handle.read().await;
+
+        let entropy = [7u8; 32];
+        let mut first = MnemonicSigner::with_entropy(&entropy);
+        let mut second = MnemonicSigner::with_entropy(&entropy);
+        first
+            .store_mnemonic(account.storage_path(), manager.generate_mnemonic().unwrap())
+            .await
+            .unwrap();
+        second
+            .store_mnemonic(account.storage_path(), manager.generate_mnemonic().unwrap())
+            .await
+            .unwrap();
+
+        let metadata = GenerateAddressMetadata {
+            syncing: false,
+            network: Network::Testnet,
+        };
+        let address = first
+            .generate_address(&account, 0, false, metadata.clone())
+            .await
+            .unwrap();
+        assert_eq!(
+            address,
+            second.generate_address(&account, 0, false, metadata.clone()).await.unwrap()
+        );
+        assert_ne!(address, first.generate_address(&account, 0, true, metadata).await.unwrap());
+    }
+}
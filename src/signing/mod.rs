@@ -7,7 +7,7 @@ use crate::{
     account::Account,
     address::{Address, IotaAddress},
 };
-use getset::Getters;
+use getset::{CopyGetters, Getters};
 use iota_client::bee_message::input::Input;
 use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
@@ -19,6 +19,11 @@ pub(crate) mod ledger;
 #[cfg(feature = "stronghold")]
 pub(crate) mod stronghold;
 
+/// Deterministic mnemonic signer for test vectors.
+#[cfg(feature = "test-signing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "test-signing")))]
+pub mod mnemonic;
+
 type SignerHandle = Arc<Mutex<Box<dyn Signer + Sync + Send>>>;
 type Signers = Arc<Mutex<HashMap<SignerType, SignerHandle>>>;
 static SIGNERS_INSTANCE: OnceCell<Signers> = OnceCell::new();
@@ -37,6 +42,10 @@ pub enum SignerType {
     /// Ledger Speculos Simulator
     #[cfg(feature = "ledger-nano-simulator")]
     LedgerNanoSimulator,
+    /// In-memory mnemonic signer with optional injected entropy, for deterministic test vectors.
+    #[cfg(feature = "test-signing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "test-signing")))]
+    Mnemonic,
     /// Custom signer with its identifier.
     Custom(String),
 }
@@ -88,6 +97,39 @@ pub struct SignMessageMetadata<'a> {
     pub network: Network,
 }
 
+/// The highest hardened BIP32 index.
+pub(crate) const MAX_DERIVATION_INDEX: usize = 0x7FFF_FFFF;
+
+/// The limits and features of a signer, returned by [capabilities](trait.Signer.html#method.capabilities).
+#[derive(Debug, Clone, CopyGetters, Serialize, Eq, PartialEq)]
+#[getset(get_copy = "pub")]
+pub struct SignerCapabilities {
+    /// The maximum number of inputs and outputs of a transaction together, if the signer can't sign the network
+    /// limit.
+    #[serde(rename = "maxInputOutputCount")]
+    pub max_input_output_count: Option<usize>,
+    /// Whether the signer signs transaction essences without displaying their outputs for review.
+    #[serde(rename = "blindSigning")]
+    pub blind_signing: bool,
+    /// Whether the signer can display the change addresses for the user to verify them.
+    #[serde(rename = "internalAddressDisplay")]
+    pub internal_address_display: bool,
+    /// The highest key index the signer derives.
+    #[serde(rename = "maxDerivationIndex")]
+    pub max_derivation_index: usize,
+}
+
+impl Default for SignerCapabilities {
+    fn default() -> Self {
+        Self {
+            max_input_output_count: None,
+            blind_signing: true,
+            internal_address_display: false,
+            max_derivation_index: MAX_DERIVATION_INDEX,
+        }
+    }
+}
+
 /// Signer interface.
 #[async_trait::async_trait]
 pub trait Signer {
@@ -103,6 +145,32 @@ pub trait Signer {
         internal: bool,
         metadata: GenerateAddressMetadata,
     ) -> crate::Result<IotaAddress>;
+    /// Generates the addresses of the given key indexes, in the same order.
+    /// Used to derive many addresses at once, e.g. on account discovery; signers backed by a device or a vault
+    /// should override it to reuse a single context for the whole batch.
+    async fn generate_addresses(
+        &mut self,
+        account: &Account,
+        indexes: &[usize],
+        internal: bool,
+        metadata: GenerateAddressMetadata,
+    ) -> crate::Result<Vec<IotaAddress>> {
+        let mut addresses = Vec::with_capacity(indexes.len());
+        for index in indexes {
+            addresses.push(
+                self.generate_address(account, *index, internal, metadata.clone())
+                    .await?,
+            );
+        }
+        Ok(addresses)
+    }
+    /// Gets the Ed25519 public key of the given key index, whose Blake2b-256 hash is the address.
+    /// Signers that can't export public keys, e.g. hardware wallets only returning addresses, keep the default.
+    async fn public_key(&mut self, _account: &Account, _index: usize, _internal: bool) -> crate::Result<[u8; 32]> {
+        Err(crate::Error::SignerOperationNotSupported(
+            "public key export".to_string(),
+        ))
+    }
     /// Signs message.
     async fn sign_message<'a>(
         &mut self,
@@ -111,6 +179,11 @@ pub trait Signer {
         inputs: &mut Vec<TransactionInput>,
         metadata: SignMessageMetadata<'a>,
     ) -> crate::Result<Vec<iota_client::bee_message::prelude::UnlockBlock>>;
+    /// Gets the limits and features of the signer, consulted by the input selection and the transfers.
+    /// Software signers keep the default; signers backed by a device should override it.
+    fn capabilities(&self) -> SignerCapabilities {
+        SignerCapabilities::default()
+    }
 }
 
 fn default_signers() -> Signers {
@@ -148,6 +221,16 @@ fn default_signers() -> Signers {
         );
     }
 
+    #[cfg(feature = "test-signing")]
+    {
+        signers.insert(
+            SignerType::Mnemonic,
+            Arc::new(Mutex::new(
+                Box::<self::mnemonic::MnemonicSigner>::default() as Box<dyn Signer + Sync + Send>
+            )),
+        );
+    }
+
     Arc::new(Mutex::new(signers))
 }
 
@@ -160,6 +243,63 @@ pub async fn set_signer<S: Signer + Sync + Send + 'static>(signer_type: SignerTy
         .insert(signer_type, Arc::new(Mutex::new(Box::new(signer))));
 }
 
+/// Whether a signer interface is set for the given type.
+pub(crate) async fn has_signer(signer_type: &SignerType) -> bool {
+    SIGNERS_INSTANCE
+        .get_or_init(default_signers)
+        .lock()
+        .await
+        .contains_key(signer_type)
+}
+
+/// A signer session for an account, checking the signer state once and reusing it for many derivations.
+pub(crate) struct SignerSession<'a> {
+    account: &'a Account,
+    signer: SignerHandle,
+    locked: bool,
+}
+
+impl<'a> SignerSession<'a> {
+    /// Opens a session with the signer of the given account.
+    pub(crate) async fn open(account: &'a Account) -> crate::Result<SignerSession<'a>> {
+        #[cfg(feature = "stronghold")]
+        let locked = account.signer_type() == &SignerType::Stronghold
+            && crate::stronghold::get_status(&self::stronghold::stronghold_path(account.storage_path()).await?)
+                .await
+                .snapshot
+                == crate::stronghold::SnapshotStatus::Locked;
+        #[cfg(not(feature = "stronghold"))]
+        let locked = false;
+        Ok(Self {
+            account,
+            signer: get_signer(account.signer_type()).await,
+            locked,
+        })
+    }
+
+    /// Whether the signer was locked when the session was opened, in which case no address can be generated.
+    pub(crate) fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    /// Generates the addresses of the given key indexes holding the signer lock once.
+    pub(crate) async fn generate_addresses(
+        &self,
+        indexes: &[usize],
+        internal: bool,
+        metadata: GenerateAddressMetadata,
+    ) -> crate::Result<Vec<IotaAddress>> {
+        if indexes.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.signer
+            .lock()
+            .await
+            .generate_addresses(self.account, indexes, internal, metadata)
+            .await
+    }
+}
+
 /// Gets the signer interface.
 pub(crate) async fn get_signer(signer_type: &SignerType) -> SignerHandle {
     SIGNERS_INSTANCE
@@ -170,3 +310,64 @@ pub(crate) async fn get_signer(signer_type: &SignerType) -> SignerHandle {
         .cloned()
         .unwrap_or_else(|| panic!("signer not initialized for type {:?}", signer_type))
 }
+
+/// Gets the capabilities of the signer of the given type.
+pub(crate) async fn signer_capabilities(signer_type: &SignerType) -> SignerCapabilities {
+    get_signer(signer_type).await.lock().await.capabilities()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GenerateAddressMetadata, Network, SignerCapabilities, SignerSession, SignerType};
+
+    #[tokio::test]
+    async fn signer_session() {
+        let manager = crate::testing::get_account_manager().await;
+        let account_handle = crate::testing::AccountCreator::new(&manager)
+            .signer_type(SignerType::Custom("".to_string()))
+            .create()
+            .await;
+        let account = account_handle.read().await.clone();
+        let metadata = GenerateAddressMetadata {
+            syncing: true,
+            network: Network::Testnet,
+        };
+
+        let session = SignerSession::open(&account).await.unwrap();
+        assert!(!session.is_locked());
+        let addresses = session
+            .generate_addresses(&[3, 1, 2], false, metadata.clone())
+            .await
+            .unwrap();
+        for (index, address) in [3, 1, 2].iter().zip(addresses) {
+            let expected =
+                crate::address::get_iota_address(&account, *index, false, "atoi".to_string(), metadata.clone())
+                    .await
+                    .unwrap();
+            assert_eq!(expected.as_ref(), &address);
+        }
+    }
+    #[tokio::test]
+    async fn signer_capabilities() {
+        let manager = crate::testing::get_account_manager().await;
+        let account_handle = crate::testing::AccountCreator::new(&manager)
+            .signer_type(SignerType::Custom("".to_string()))
+            .create()
+            .await;
+        let capabilities = account_handle.signer_capabilities().await;
+        assert_eq!(capabilities, SignerCapabilities::default());
+        assert_eq!(capabilities.max_input_output_count(), None);
+
+        // the key indexes beyond the signer derivation range are rejected
+        let account = account_handle.read().await.clone();
+        let metadata = GenerateAddressMetadata {
+            syncing: false,
+            network: Network::Testnet,
+        };
+        let index = capabilities.max_derivation_index() + 1;
+        assert!(matches!(
+            crate::address::get_iota_address(&account, index, false, "atoi".to_string(), metadata).await,
+            Err(crate::Error::AddressIndexLimitExceeded(i, _)) if i == index
+        ));
+    }
+}
@@ -58,6 +58,17 @@ impl super::Signer for StrongholdSigner {
         Ok(address)
     }
 
+    async fn public_key(&mut self, account: &Account, address_index: usize, internal: bool) -> crate::Result<[u8; 32]> {
+        let public_key = crate::stronghold::get_address_public_key(
+            &stronghold_path(account.storage_path()).await?,
+            *account.index(),
+            address_index,
+            internal,
+        )
+        .await?;
+        Ok(public_key)
+    }
+
     async fn sign_message<'a>(
         &mut self,
         account: &Account,
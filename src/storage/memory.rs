@@ -0,0 +1,92 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::StorageAdapter;
+use std::{collections::HashMap, path::Path};
+
+/// The storage id.
+pub const STORAGE_ID: &str = "Memory";
+
+/// Storage adapter keeping the records in memory, for environments that shouldn't write to disk such as tests, CI
+/// runs or kiosk apps. The records are written to a file only on
+/// [AccountManager#save_storage_snapshot](../../account_manager/struct.AccountManager.html#method.save_storage_snapshot).
+/// With a storage password, the records are encrypted in memory and in the snapshot.
+#[derive(Default)]
+pub struct MemoryStorageAdapter {
+    records: HashMap<String, String>,
+}
+
+impl MemoryStorageAdapter {
+    /// Initialises an empty storage adapter.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Initialises the storage adapter with the records of a snapshot file.
+    pub fn from_snapshot(path: impl AsRef<Path>) -> crate::Result<Self> {
+        let snapshot = std::fs::read_to_string(path)?;
+        Ok(Self {
+            records: serde_json::from_str(&snapshot)?,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl StorageAdapter for MemoryStorageAdapter {
+    fn id(&self) -> &'static str {
+        STORAGE_ID
+    }
+
+    async fn get(&self, key: &str) -> crate::Result<String> {
+        self.records.get(key).cloned().ok_or(crate::Error::RecordNotFound)
+    }
+
+    async fn set(&mut self, key: &str, record: String) -> crate::Result<()> {
+        self.records.insert(key.to_string(), record);
+        Ok(())
+    }
+
+    async fn batch_set(&mut self, records: HashMap<String, String>) -> crate::Result<()> {
+        self.records.extend(records);
+        Ok(())
+    }
+
+    async fn remove(&mut self, key: &str) -> crate::Result<()> {
+        // like the RocksDB deletes, removing a missing record succeeds
+        self.records.remove(key);
+        Ok(())
+    }
+
+    async fn save_snapshot(&self, path: &Path) -> crate::Result<()> {
+        // write next to the destination first, so a failed write doesn't leave a truncated snapshot
+        let tmp_path = path.with_extension("tmp");
+        std::fs::write(&tmp_path, serde_json::to_string(&self.records)?)?;
+        std::fs::rename(tmp_path, path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MemoryStorageAdapter, StorageAdapter};
+
+    #[tokio::test]
+    async fn snapshot() {
+        let path = std::env::temp_dir().join("iota-wallet-memory-storage-snapshot.json");
+        let mut adapter = MemoryStorageAdapter::new();
+        adapter.set("key", "record".to_string()).await.unwrap();
+        adapter.save_snapshot(&path).await.unwrap();
+
+        let restored = MemoryStorageAdapter::from_snapshot(&path).unwrap();
+        assert_eq!(restored.get("key").await.unwrap(), "record");
+        assert!(matches!(
+            restored.get("missing").await,
+            Err(crate::Error::RecordNotFound)
+        ));
+        let _ = std::fs::remove_file(path);
+
+        adapter.remove("key").await.unwrap();
+        adapter.remove("key").await.unwrap();
+        assert!(matches!(adapter.get("key").await, Err(crate::Error::RecordNotFound)));
+    }
+}
@@ -1,6 +1,9 @@
 // Copyright 2020 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
+/// In-memory storage.
+pub mod memory;
+
 /// RocksDB storage.
 pub mod rocksdb;
 
@@ -10,8 +13,12 @@ pub mod stronghold;
 #[cfg(feature = "participation")]
 use crate::address::AddressWrapper;
 use crate::{
-    account::Account,
-    event::{BalanceEvent, TransactionConfirmationChangeEvent, TransactionEvent, TransactionReattachmentEvent},
+    account::{Account, BalanceSnapshot, KnownIds},
+    account_manager::{IntegrityIssue, IntegrityIssueKind},
+    event::{
+        AccountEvent, BalanceEvent, FailedDelivery, TransactionConfirmationChangeEvent, TransactionEvent,
+        TransactionReattachmentEvent,
+    },
     message::{Message, MessageId, MessagePayload, MessageType, TransactionEssence},
 };
 
@@ -22,6 +29,7 @@ use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use tokio::sync::{Mutex, RwLock};
 
 use std::{
+    borrow::Cow,
     collections::HashMap,
     convert::TryInto,
     io::{Read, Write},
@@ -31,6 +39,69 @@ use std::{
 
 const ACCOUNT_INDEXATION_KEY: &str = "iota-wallet-account-indexation";
 const KCV_KEY: &str = "iota-wallet-key-checksum_value";
+const EVENT_SEQUENCE_KEY_PREFIX: &str = "iota-wallet-event-sequence-";
+const EVENT_DEDUP_KEY_PREFIX: &str = "iota-wallet-event-dedup-";
+const EVENT_LOG_KEY_PREFIX: &str = "iota-wallet-event-log-";
+pub(crate) const USER_DATA_INDEX_KEY: &str = "iota-wallet-user-data-index";
+// the separator keeps the user data records apart from the index, whatever the user key
+const USER_DATA_KEY_PREFIX: &str = "iota-wallet-user-data/";
+const KNOWN_IDS_KEY_PREFIX: &str = "iota-wallet-known-ids-";
+const FAILED_DELIVERIES_KEY: &str = "iota-wallet-failed-event-deliveries";
+const MESSAGE_RECORD_VERSION_KEY: &str = "iota-wallet-message-record-version";
+const QUARANTINE_KEY_PREFIX: &str = "iota-wallet-quarantine-";
+const BALANCE_HISTORY_KEY_PREFIX: &str = "iota-wallet-balance-history-";
+const HEALTH_PROBE_KEY: &str = "iota-wallet-health-probe";
+const DURESS_FOLDER_KEY: &str = "iota-wallet-duress-folder";
+const FIRST_LEDGER_ADDRESS_KEY: &str = "FIRST_LEDGER_ADDRESS";
+const BALANCE_CHANGE_EVENTS_KEY: &str = "iota-wallet-balance-change-events";
+const CONFIRMATION_EVENTS_KEY: &str = "iota-wallet-tx-confirmation-events";
+const NEW_TRANSACTION_EVENTS_KEY: &str = "iota-wallet-new-tx-events";
+const REATTACHMENT_EVENTS_KEY: &str = "iota-wallet-tx-reattachment-events";
+const BROADCAST_EVENTS_KEY: &str = "iota-wallet-tx-broadcast-events";
+/// The keys of the persisted events indexations.
+const EVENT_INDEXATION_KEYS: [&str; 5] = [
+    BALANCE_CHANGE_EVENTS_KEY,
+    CONFIRMATION_EVENTS_KEY,
+    NEW_TRANSACTION_EVENTS_KEY,
+    REATTACHMENT_EVENTS_KEY,
+    BROADCAST_EVENTS_KEY,
+];
+
+/// The version of the message records; version 0 are the JSON records.
+const MESSAGE_RECORD_VERSION: u8 = 1;
+/// The first byte of a MessagePack message record. JSON records start with `{`, or `[` when encrypted.
+const BINARY_MESSAGE_RECORD: u8 = 1;
+/// The first byte of an encrypted MessagePack message record.
+const ENCRYPTED_BINARY_MESSAGE_RECORD: u8 = 2;
+
+/// The kind of a storage record, letting the storage adapters keep each kind in its own keyspace.
+/// The addresses and their outputs are part of the account records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RecordKind {
+    /// The indexations and the wallet metadata.
+    Metadata,
+    /// The account records.
+    Account,
+    /// The message records.
+    Message,
+    /// The persisted events.
+    Event,
+    /// The participation records.
+    Participation,
+}
+
+impl RecordKind {
+    /// The kind of the records the wallet reads and writes by key, i.e. all kinds except the messages and events.
+    fn of_key(key: &str) -> Self {
+        if key.starts_with(crate::account::ACCOUNT_ID_PREFIX) {
+            Self::Account
+        } else if key.starts_with("ACCOUNT-") && key.contains("-PARTICIPATION") {
+            Self::Participation
+        } else {
+            Self::Metadata
+        }
+    }
+}
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 struct AccountIndexation {
@@ -45,6 +116,35 @@ struct EventIndexation {
     timestamp: Timestamp,
 }
 
+/// The kind of a persisted account event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum EventKind {
+    BalanceChange,
+    NewTransaction,
+    Broadcast,
+    ConfirmationStateChange,
+    Reattachment,
+}
+
+/// The record of a persisted event on the account event log, stored under its sequence number.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct SequencedEventIndexation {
+    kind: EventKind,
+    key: String,
+    /// The deduplication key of the event, so its record can be found after the event is pruned.
+    #[serde(rename = "dedupKey", default)]
+    dedup_key: Option<String>,
+}
+
+/// The event sequence state of an account.
+#[derive(Debug, Clone, Default)]
+struct EventSequence {
+    /// The last assigned sequence number.
+    last: u64,
+    /// The sequence number assigned to the deduplication keys of the events that aren't persisted.
+    transient_keys: HashMap<String, u64>,
+}
+
 /// The indexation for account messages.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct MessageIndexation {
@@ -64,6 +164,18 @@ pub struct MessageIndexation {
     pub value: u64,
     /// Id of the message that reattached this message.
     pub reattachment_message_id: Option<MessageId>,
+    /// Id of the message this message reattaches.
+    #[serde(rename = "reattachmentOf", default)]
+    pub reattachment_of: Option<MessageId>,
+    /// Whether the message has reattachments.
+    #[serde(default)]
+    pub reattached: bool,
+    /// The message classification.
+    #[serde(default)]
+    pub classification: Option<MessageType>,
+    /// Whether the stored message has the timings of the transfer that sent it.
+    #[serde(rename = "transferTimings", default)]
+    pub transfer_timings: bool,
 }
 
 #[derive(Default)]
@@ -108,23 +220,89 @@ impl Storage {
     }
 
     async fn get(&self, key: &str) -> crate::Result<String> {
-        self.inner.get(key).await.and_then(|record| {
-            if let Some(key) = &self.encryption_key {
-                if serde_json::from_str::<Vec<u8>>(&record).is_ok() {
-                    decrypt_record(&record, key)
-                } else {
-                    Ok(record)
-                }
+        self.get_in(RecordKind::of_key(key), key).await
+    }
+
+    async fn get_in(&self, kind: RecordKind, key: &str) -> crate::Result<String> {
+        self.inner
+            .get_record(kind, key)
+            .await
+            .and_then(|record| self.decrypt(record))
+    }
+
+    fn decrypt(&self, record: String) -> crate::Result<String> {
+        if let Some(key) = &self.encryption_key {
+            if serde_json::from_str::<Vec<u8>>(&record).is_ok() {
+                decrypt_record(&record, key)
             } else {
                 Ok(record)
             }
-        })
+        } else {
+            Ok(record)
+        }
+    }
+
+    fn encode_message(&self, message: &Message) -> crate::Result<Vec<u8>> {
+        let body = rmp_serde::to_vec_named(message).map_err(|e| crate::Error::BinaryRecord(e.to_string()))?;
+        let mut record = Vec::with_capacity(body.len() + 1);
+        if let Some(key) = &self.encryption_key {
+            record.push(ENCRYPTED_BINARY_MESSAGE_RECORD);
+            encrypt_record(&body, key, &mut record)?;
+        } else {
+            record.push(BINARY_MESSAGE_RECORD);
+            record.extend_from_slice(&body);
+        }
+        Ok(record)
+    }
+
+    async fn read_message(&self, message_id: &MessageId) -> crate::Result<Message> {
+        let record = self
+            .inner
+            .get_record_bytes(RecordKind::Message, &message_id.to_string())
+            .await?;
+        self.decode_message(record)
+    }
+
+    /// Reads the messages with a single storage request, in the order of the ids.
+    async fn read_messages(&self, message_ids: &[MessageId]) -> Vec<crate::Result<Message>> {
+        let keys: Vec<String> = message_ids.iter().map(|id| id.to_string()).collect();
+        self.inner
+            .get_records_bytes(RecordKind::Message, &keys)
+            .await
+            .into_iter()
+            .map(|record| record.and_then(|record| self.decode_message(record)))
+            .collect()
+    }
+
+    fn decode_message(&self, record: Vec<u8>) -> crate::Result<Message> {
+        let decode = |body: &[u8]| rmp_serde::from_slice(body).map_err(|e| crate::Error::BinaryRecord(e.to_string()));
+        match record.split_first() {
+            Some((&BINARY_MESSAGE_RECORD, body)) => decode(body),
+            Some((&ENCRYPTED_BINARY_MESSAGE_RECORD, body)) => {
+                let key = self.encryption_key.as_ref().ok_or(crate::Error::StorageIsEncrypted)?;
+                decode(&decrypt_bytes(body, key)?)
+            }
+            // written before the binary records, until the migration runs
+            _ => {
+                let record = self.decrypt(String::from_utf8_lossy(&record).to_string())?;
+                Ok(serde_json::from_str(&record)?)
+            }
+        }
     }
 
     async fn set<T: Serialize>(&mut self, key: &str, record: T) -> crate::Result<()> {
-        let record = serde_json::to_string(&record)?;
+        self.set_in(RecordKind::of_key(key), key, record).await
+    }
+
+    async fn set_in<T: Serialize>(&mut self, kind: RecordKind, key: &str, record: T) -> crate::Result<()> {
+        self.set_json_in(kind, key, serde_json::to_string(&record)?).await
+    }
+
+    /// Writes a serialized JSON record, encrypting it if the storage is encrypted.
+    async fn set_json_in(&mut self, kind: RecordKind, key: &str, record: String) -> crate::Result<()> {
         self.inner
-            .set(
+            .set_record(
+                kind,
                 key,
                 if let Some(key) = &self.encryption_key {
                     let mut output = Vec::new();
@@ -137,24 +315,25 @@ impl Storage {
             .await
     }
 
-    async fn batch_set(&mut self, records: HashMap<String, String>) -> crate::Result<()> {
-        self.inner
-            .batch_set(if let Some(key) = &self.encryption_key {
-                let mut encrypted_records = HashMap::new();
-                for (id, record) in records {
-                    let mut output = Vec::new();
-                    encrypt_record(record.as_bytes(), key, &mut output)?;
-                    encrypted_records.insert(id, serde_json::to_string(&output)?);
-                }
-                encrypted_records
-            } else {
-                records
-            })
-            .await
+    /// Writes binary records; `encode_message` encrypts them.
+    async fn batch_set_bytes(&mut self, kind: RecordKind, records: HashMap<String, Vec<u8>>) -> crate::Result<()> {
+        self.inner.batch_set_record_bytes(kind, records).await
     }
 
     async fn remove(&mut self, key: &str) -> crate::Result<()> {
-        self.inner.remove(key).await
+        self.remove_in(RecordKind::of_key(key), key).await
+    }
+
+    async fn remove_in(&mut self, kind: RecordKind, key: &str) -> crate::Result<()> {
+        self.inner.remove_record(kind, key).await
+    }
+
+    async fn flush(&mut self) -> crate::Result<()> {
+        self.inner.flush().await
+    }
+
+    async fn save_snapshot(&self, path: &Path) -> crate::Result<()> {
+        self.inner.save_snapshot(path).await
     }
 
     async fn set_encryption_key(&mut self, encryption_key: [u8; 32]) -> crate::Result<()> {
@@ -189,6 +368,7 @@ pub(crate) struct StorageManager {
     new_transaction_indexation: Option<Vec<EventIndexation>>,
     reattachment_indexation: Option<Vec<EventIndexation>>,
     broadcast_indexation: Option<Vec<EventIndexation>>,
+    event_sequences: HashMap<String, EventSequence>,
 }
 
 macro_rules! load_account_dependency_index {
@@ -217,6 +397,10 @@ fn account_message_index_key(account_id: &str) -> String {
     format!("iota-wallet-{}-messages", account_id)
 }
 
+fn quarantine_key(key: &str) -> String {
+    format!("{}{}", QUARANTINE_KEY_PREFIX, key)
+}
+
 impl StorageManager {
     pub fn id(&self) -> &'static str {
         self.storage.id()
@@ -226,10 +410,77 @@ impl StorageManager {
         self.storage.encryption_key.is_some()
     }
 
+    /// Writes the buffered records to disk.
+    pub async fn flush(&mut self) -> crate::Result<()> {
+        self.storage.flush().await
+    }
+
+    /// Writes the records of an in-memory storage to a snapshot file.
+    pub(crate) async fn save_snapshot(&self, path: &Path) -> crate::Result<()> {
+        self.storage.save_snapshot(path).await
+    }
+
     pub async fn get(&self, key: &str) -> crate::Result<String> {
         self.storage.get(key).await
     }
 
+    /// Rewrites the JSON message records of the loaded accounts as binary records.
+    /// The records that can't be decoded are left as they are and reported, instead of failing the accounts loading;
+    /// [verify_integrity](../account_manager/struct.AccountManager.html#method.verify_integrity) can quarantine them.
+    async fn migrate_message_records(&mut self) -> crate::Result<()> {
+        let version: u8 = match self.storage.get(MESSAGE_RECORD_VERSION_KEY).await {
+            Ok(record) => serde_json::from_str(&record)?,
+            Err(crate::Error::RecordNotFound) => 0,
+            Err(e) => return Err(e),
+        };
+        if version >= MESSAGE_RECORD_VERSION {
+            return Ok(());
+        }
+        let account_count = self.message_indexation.len();
+        let mut skipped_records = 0;
+        for (account_number, (account_id, message_indexation)) in self.message_indexation.iter().enumerate() {
+            log::info!(
+                "[STORAGE] migrating the {} message records of account {} ({}/{})",
+                message_indexation.len(),
+                account_id,
+                account_number + 1,
+                account_count
+            );
+            let mut records = HashMap::new();
+            for index in message_indexation {
+                let record = match self.storage.read_message(&index.key).await {
+                    Ok(message) => self.storage.encode_message(&message),
+                    Err(crate::Error::RecordNotFound) => continue,
+                    Err(e) => Err(e),
+                };
+                match record {
+                    Ok(record) => {
+                        records.insert(index.key.to_string(), record);
+                    }
+                    Err(e) => {
+                        log::warn!(
+                            "[STORAGE] skipping the migration of the message record {} of account {}: {}",
+                            index.key,
+                            account_id,
+                            e
+                        );
+                        skipped_records += 1;
+                    }
+                }
+            }
+            self.storage.batch_set_bytes(RecordKind::Message, records).await?;
+        }
+        if skipped_records > 0 {
+            log::warn!(
+                "[STORAGE] {} message records couldn't be migrated and are still stored as JSON",
+                skipped_records
+            );
+        }
+        self.storage
+            .set(MESSAGE_RECORD_VERSION_KEY, MESSAGE_RECORD_VERSION)
+            .await
+    }
+
     pub async fn get_accounts(&mut self) -> crate::Result<Vec<Account>> {
         if self.account_indexation.is_empty() {
             if let Ok(record) = self.storage.get(ACCOUNT_INDEXATION_KEY).await {
@@ -247,6 +498,30 @@ impl StorageManager {
                 message_indexation
             );
         }
+        self.migrate_message_records().await?;
+        parse_accounts(&self.storage.storage_path, &accounts)
+    }
+
+    /// Gets the stored accounts that aren't in `loaded_ids`, such as the accounts evicted from memory.
+    pub(crate) async fn get_accounts_except(&mut self, loaded_ids: &[String]) -> crate::Result<Vec<Account>> {
+        if self.account_indexation.is_empty() {
+            if let Ok(record) = self.storage.get(ACCOUNT_INDEXATION_KEY).await {
+                self.account_indexation = serde_json::from_str(&record)?;
+            }
+        }
+
+        let mut accounts = Vec::new();
+        for account_index in self.account_indexation.clone() {
+            if !loaded_ids.contains(&account_index.key) {
+                accounts.push(self.get(&account_index.key).await?);
+                load_account_dependency_index!(
+                    self,
+                    account_index.key.clone(),
+                    &account_message_index_key(&account_index.key),
+                    message_indexation
+                );
+            }
+        }
         parse_accounts(&self.storage.storage_path, &accounts)
     }
 
@@ -270,14 +545,14 @@ impl StorageManager {
         &mut self,
         address: &iota_client::bee_message::address::Address,
     ) -> crate::Result<()> {
-        self.storage.set("FIRST_LEDGER_ADDRESS", address).await?;
+        self.storage.set(FIRST_LEDGER_ADDRESS_KEY, address).await?;
         Ok(())
     }
 
     #[cfg(any(feature = "ledger-nano", feature = "ledger-nano-simulator"))]
     pub async fn get_first_ledger_address(&self) -> crate::Result<iota_client::bee_message::address::Address> {
         let address: iota_client::bee_message::address::Address =
-            serde_json::from_str(&self.storage.get("FIRST_LEDGER_ADDRESS").await?)?;
+            serde_json::from_str(&self.storage.get(FIRST_LEDGER_ADDRESS_KEY).await?)?;
         Ok(address)
     }
 
@@ -370,12 +645,108 @@ impl StorageManager {
                 .set(ACCOUNT_INDEXATION_KEY, &self.account_indexation)
                 .await?;
             self.storage.remove(key).await?;
+            for record_key in &[known_ids_key(key), balance_history_key(key)] {
+                match self.storage.remove(record_key).await {
+                    Ok(_) | Err(crate::Error::RecordNotFound) => {}
+                    Err(e) => return Err(e),
+                }
+            }
             Ok(())
         } else {
             Err(crate::Error::RecordNotFound)
         }
     }
 
+    #[cfg(test)]
+    pub(crate) async fn set_raw_record(&mut self, kind: RecordKind, key: &str, record: String) -> crate::Result<()> {
+        self.storage.inner.set_record(kind, key, record).await
+    }
+
+    /// Moves the raw record to its quarantine key, keeping it for a manual recovery.
+    async fn quarantine(&mut self, kind: RecordKind, key: &str) -> crate::Result<()> {
+        let record = self.storage.inner.get_record_bytes(kind, key).await?;
+        let mut records = HashMap::new();
+        records.insert(quarantine_key(key), record);
+        self.storage.batch_set_bytes(RecordKind::Metadata, records).await?;
+        self.storage.remove_in(kind, key).await
+    }
+
+    /// Checks that the account and message records can be read.
+    /// With `repair`, the unreadable records are quarantined and removed from the indexation.
+    pub(crate) async fn verify_records(&mut self, repair: bool) -> crate::Result<Vec<IntegrityIssue>> {
+        if self.account_indexation.is_empty() {
+            if let Ok(record) = self.storage.get(ACCOUNT_INDEXATION_KEY).await {
+                self.account_indexation = serde_json::from_str(&record)?;
+            }
+        }
+
+        let mut issues = Vec::new();
+        for account_index in self.account_indexation.clone() {
+            let account_id = account_index.key;
+            let parsed = match self.storage.get(&account_id).await {
+                Ok(record) => parse_accounts(&self.storage.storage_path, &[record]).map(|_| ()),
+                Err(e) => Err(e),
+            };
+            match parsed {
+                Ok(()) => {}
+                Err(crate::Error::StorageIsEncrypted) => return Err(crate::Error::StorageIsEncrypted),
+                Err(e) => {
+                    if repair {
+                        if !matches!(e, crate::Error::RecordNotFound) {
+                            self.quarantine(RecordKind::Account, &account_id).await?;
+                        }
+                        self.account_indexation.retain(|index| index.key != account_id);
+                        self.storage
+                            .set(ACCOUNT_INDEXATION_KEY, &self.account_indexation)
+                            .await?;
+                        self.message_indexation.remove(&account_id);
+                    }
+                    issues.push(IntegrityIssue::new(
+                        account_id,
+                        IntegrityIssueKind::CorruptedAccount(e.to_string()),
+                        repair,
+                    ));
+                    continue;
+                }
+            }
+
+            let message_index_key = account_message_index_key(&account_id);
+            let message_indexation: Vec<MessageIndexation> = match self.storage.get(&message_index_key).await {
+                Ok(record) => serde_json::from_str(&record)?,
+                Err(crate::Error::RecordNotFound) => continue,
+                Err(e) => return Err(e),
+            };
+            let mut kept = Vec::new();
+            for index in message_indexation {
+                let kind = match self.storage.read_message(&index.key).await {
+                    Ok(_) => {
+                        kept.push(index);
+                        continue;
+                    }
+                    Err(crate::Error::StorageIsEncrypted) => return Err(crate::Error::StorageIsEncrypted),
+                    Err(crate::Error::RecordNotFound) => IntegrityIssueKind::OrphanedMessage(index.key),
+                    Err(e) => {
+                        if repair {
+                            self.quarantine(RecordKind::Message, &index.key.to_string()).await?;
+                        }
+                        IntegrityIssueKind::CorruptedMessage {
+                            message_id: index.key,
+                            error: e.to_string(),
+                        }
+                    }
+                };
+                issues.push(IntegrityIssue::new(account_id.clone(), kind, repair));
+            }
+            if repair && issues.iter().any(|issue| issue.account_id() == &account_id) {
+                self.storage.set(&message_index_key, &kept).await?;
+                if self.message_indexation.contains_key(&account_id) {
+                    self.message_indexation.insert(account_id, kept);
+                }
+            }
+        }
+        Ok(issues)
+    }
+
     pub fn message_indexation(&self, account: &Account) -> crate::Result<&Vec<MessageIndexation>> {
         self.message_indexation
             .get(account.id())
@@ -391,7 +762,8 @@ impl StorageManager {
 
         let mut filtered_message_indexation = Vec::new();
         for message in message_indexation {
-            if message.reattachment_message_id.is_some() {
+            // reattachments are listed through the message they reattach
+            if message.reattachment_message_id.is_some() || message.reattachment_of.is_some() {
                 continue;
             }
             let message_type_matches = if let Some(message_type) = filter.message_type.clone() {
@@ -402,6 +774,10 @@ impl StorageManager {
                     MessageType::Unconfirmed => message.confirmed.is_none(),
                     MessageType::Value => message.value > 0,
                     MessageType::Confirmed => message.confirmed.is_some(),
+                    MessageType::SelfTransfer
+                    | MessageType::Consolidation
+                    | MessageType::Participation
+                    | MessageType::Data => message.classification.as_ref() == Some(&message_type),
                 }
             } else {
                 true
@@ -421,7 +797,34 @@ impl StorageManager {
             .or_insert_with(Default::default);
         let mut messages_map = HashMap::new();
         for message in messages.iter() {
-            messages_map.insert(message.id().to_string(), serde_json::to_string(&message)?);
+            let previous = message_indexation.iter().find(|index| &index.key == message.id());
+            let previous_timings =
+                message.transfer_timings.is_none() && previous.map_or(false, |index| index.transfer_timings);
+            let previous_reattachments = message.reattachment_of.is_none()
+                && message.reattachments.is_empty()
+                && previous.map_or(false, |index| index.reattachment_of.is_some() || index.reattached);
+            let message = if previous_timings || previous_reattachments {
+                // the message synced from the node doesn't have the timings of the transfer that sent it or the
+                // reattachment links
+                let mut message = message.clone();
+                if let Ok(stored) = self.storage.read_message(message.id()).await {
+                    if previous_timings {
+                        // the transfer context is stored with the timings
+                        message.transfer_timings = stored.transfer_timings;
+                        message.transfer_context = message.transfer_context.or(stored.transfer_context);
+                    }
+                    if previous_reattachments {
+                        message.reattachment_of = stored.reattachment_of;
+                        message.reattachments = stored.reattachments;
+                        message.reattachment_message_id =
+                            message.reattachment_message_id.or(stored.reattachment_message_id);
+                    }
+                }
+                Cow::Owned(message)
+            } else {
+                Cow::Borrowed(message)
+            };
+            messages_map.insert(message.id().to_string(), self.storage.encode_message(&message)?);
             let (value, internal, incoming) = match message.payload() {
                 Some(MessagePayload::Transaction(tx)) => {
                     let TransactionEssence::Regular(essence) = tx.essence();
@@ -443,6 +846,10 @@ impl StorageManager {
                 confirmed: message.confirmed,
                 value,
                 reattachment_message_id: None,
+                reattachment_of: message.reattachment_of,
+                reattached: !message.reattachments.is_empty(),
+                classification: message.classification(),
+                transfer_timings: message.transfer_timings.is_some() || previous_timings,
             };
             if let Some(position) = message_indexation.iter().position(|i| i.key == index.key) {
                 message_indexation[position] = index.clone();
@@ -453,7 +860,7 @@ impl StorageManager {
         self.storage
             .set(&account_message_index_key(account.id()), &message_indexation)
             .await?;
-        self.storage.batch_set(messages_map).await?;
+        self.storage.batch_set_bytes(RecordKind::Message, messages_map).await?;
         Ok(())
     }
 
@@ -463,8 +870,7 @@ impl StorageManager {
             .iter()
             .find(|i| &i.key == message_id)
             .ok_or(crate::Error::RecordNotFound)?;
-        let message = self.get(&index.key.to_string()).await?;
-        serde_json::from_str(&message).map_err(Into::into)
+        self.storage.read_message(&index.key).await
     }
 
     pub async fn get_messages(
@@ -476,26 +882,165 @@ impl StorageManager {
     ) -> crate::Result<Vec<Message>> {
         let filtered_message_indexation = self.query_message_indexation(account, &filter)?;
 
-        let mut messages = Vec::new();
         let iter = filtered_message_indexation.into_iter().skip(skip);
-        for index in if count == 0 {
+        let indexation = if count == 0 {
             iter.collect::<Vec<&MessageIndexation>>()
         } else {
             iter.take(count).collect::<Vec<&MessageIndexation>>()
-        } {
-            if let Ok(mut message) = serde_json::from_str::<Message>(&self.get(&index.key.to_string()).await?) {
+        };
+        let page: Vec<(MessageId, Option<bool>)> = indexation.iter().map(|index| (index.key, index.incoming)).collect();
+        Ok(self
+            .read_messages_page(&page)
+            .await
+            .into_iter()
+            .filter_map(|record| record.ok())
+            .collect())
+    }
+
+    /// Reads the messages of the given `(id, incoming)` pairs of the indexation, in the same order.
+    pub async fn read_messages_page(&self, page: &[(MessageId, Option<bool>)]) -> Vec<crate::Result<Message>> {
+        let message_ids: Vec<MessageId> = page.iter().map(|(message_id, _)| *message_id).collect();
+        let records = self.storage.read_messages(&message_ids).await;
+        page.iter()
+            .zip(records)
+            .map(|((_, incoming), record)| {
+                let mut message = record?;
                 // we update the `incoming` prop because we store only one copy of the message on the db
                 // so on internal transactions the `incoming` prop is wrong without this
                 if let Some(MessagePayload::Transaction(tx)) = message.payload.as_mut() {
                     let TransactionEssence::Regular(essence) = tx.essence_mut();
-                    essence.incoming = index.incoming.unwrap_or_default();
+                    essence.incoming = incoming.unwrap_or_default();
                 }
+                Ok(message)
+            })
+            .collect()
+    }
+}
+
+impl StorageManager {
+    /// Gets the decrypted record, or `None` if it doesn't exist.
+    async fn get_optional_in(&self, kind: RecordKind, key: &str) -> crate::Result<Option<String>> {
+        match self.storage.get_in(kind, key).await {
+            Ok(record) => Ok(Some(record)),
+            Err(crate::Error::RecordNotFound) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
 
-                messages.push(message);
+    /// The keys of the records of the stored account, except its messages and events.
+    async fn account_record_keys(&self, account_id: &str) -> crate::Result<Vec<String>> {
+        let mut keys = vec![
+            account_id.to_string(),
+            account_message_index_key(account_id),
+            known_ids_key(account_id),
+            balance_history_key(account_id),
+            event_sequence_key(account_id),
+        ];
+        let last_sequence: u64 = load_optional_data(&self.storage, &event_sequence_key(account_id)).await?;
+        for sequence in 1..=last_sequence {
+            let log_key = event_log_key(account_id, sequence);
+            let index: SequencedEventIndexation = match self.storage.get(&log_key).await {
+                Ok(record) => serde_json::from_str(&record)?,
+                Err(crate::Error::RecordNotFound) => continue,
+                Err(e) => return Err(e),
+            };
+            // the events logged before the deduplication key was kept on the log have it on their record
+            let dedup_key = match index.dedup_key {
+                Some(dedup_key) => Some(dedup_key),
+                None => self
+                    .get_optional_in(RecordKind::Event, &index.key)
+                    .await?
+                    .map(|record| serde_json::from_str::<serde_json::Value>(&record))
+                    .transpose()?
+                    .and_then(|event| event["dedupKey"].as_str().map(|key| key.to_string())),
+            };
+            keys.push(log_key);
+            if let Some(dedup_key) = dedup_key {
+                keys.push(event_dedup_key(account_id, &dedup_key));
             }
         }
+        #[cfg(feature = "participation")]
+        {
+            // the participation records are stored by account index
+            if let Ok(record) = self.storage.get(account_id).await {
+                if let Some(index) = serde_json::from_str::<serde_json::Value>(&record)?["index"].as_u64() {
+                    keys.push(format!("ACCOUNT-{}-PARTICIPATIONS", index));
+                    keys.push(format!("ACCOUNT-{}-PARTICIPATIONADDRESS", index));
+                    keys.push(format!("ACCOUNT-{}-PARTICIPATION-OUTPUTS", index));
+                }
+            }
+        }
+        Ok(keys)
+    }
+
+    /// Encrypts the records of the wallet with the new key, including the records of the accounts that aren't
+    /// loaded, their messages, events and metadata. Every record is read before the first write, so a record that
+    /// can't be decrypted leaves the storage as it was. The quarantined records are kept as they are.
+    pub(crate) async fn reencrypt(&mut self, encryption_key: [u8; 32]) -> crate::Result<()> {
+        let mut keys: Vec<String> = EVENT_INDEXATION_KEYS
+            .iter()
+            .chain(&[
+                ACCOUNT_INDEXATION_KEY,
+                USER_DATA_INDEX_KEY,
+                FAILED_DELIVERIES_KEY,
+                MESSAGE_RECORD_VERSION_KEY,
+                DURESS_FOLDER_KEY,
+                FIRST_LEDGER_ADDRESS_KEY,
+            ])
+            .map(|key| key.to_string())
+            .collect();
+        keys.extend(self.get_user_data_keys().await?.iter().map(|key| user_data_key(key)));
+
+        let mut event_keys = Vec::new();
+        for index_key in EVENT_INDEXATION_KEYS.iter() {
+            let indexation: Vec<EventIndexation> = load_optional_data(&self.storage, index_key).await?;
+            event_keys.extend(indexation.into_iter().map(|index| index.key));
+        }
 
-        Ok(messages)
+        let mut messages = HashMap::new();
+        let account_indexation: Vec<AccountIndexation> =
+            load_optional_data(&self.storage, ACCOUNT_INDEXATION_KEY).await?;
+        for account_index in account_indexation {
+            keys.extend(self.account_record_keys(&account_index.key).await?);
+            let message_indexation: Vec<MessageIndexation> =
+                load_optional_data(&self.storage, &account_message_index_key(&account_index.key)).await?;
+            for index in message_indexation {
+                match self.storage.read_message(&index.key).await {
+                    Ok(message) => {
+                        messages.insert(index.key.to_string(), message);
+                    }
+                    Err(crate::Error::RecordNotFound) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+
+        let mut records = Vec::new();
+        for key in keys {
+            let kind = RecordKind::of_key(&key);
+            if let Some(record) = self.get_optional_in(kind, &key).await? {
+                records.push((kind, key, record));
+            }
+        }
+        for key in event_keys {
+            if let Some(record) = self.get_optional_in(RecordKind::Event, &key).await? {
+                records.push((RecordKind::Event, key, record));
+            }
+        }
+
+        self.storage.encryption_key.replace(encryption_key);
+        for (kind, key, record) in records {
+            self.storage.set_json_in(kind, &key, record).await?;
+        }
+        let mut message_records = HashMap::new();
+        for (key, message) in messages {
+            message_records.insert(key, self.storage.encode_message(&message)?);
+        }
+        self.storage
+            .batch_set_bytes(RecordKind::Message, message_records)
+            .await?;
+        // the key checksum is replaced last, once the records can be read with the new key
+        self.storage.set_encryption_key(encryption_key).await
     }
 }
 
@@ -593,11 +1138,11 @@ async fn load_optional_data<T: DeserializeOwned + Default>(storage: &Storage, ke
 }
 
 macro_rules! event_manager_impl {
-    ($event_ty:ty, $index_vec:ident, $index_key: expr, $save_fn_name: ident, $get_fn_name: ident, $get_count_fn_name: ident) => {
+    ($event_ty:ty, $index_vec:ident, $index_key: expr, $save_fn_name: ident, $get_fn_name: ident, $get_count_fn_name: ident, $prune_fn_name: ident) => {
         impl StorageManager {
             pub async fn $save_fn_name(&mut self, event: &$event_ty) -> crate::Result<()> {
                 let key = event.indexation_id.clone();
-                self.storage.set(&key, event).await?;
+                self.storage.set_in(RecordKind::Event, &key, event).await?;
                 let index = EventIndexation {
                     key: key.to_string(),
                     timestamp: Utc::now().timestamp(),
@@ -640,7 +1185,7 @@ macro_rules! event_manager_impl {
                 } else {
                     iter.take(count).collect::<Vec<&EventIndexation>>()
                 } {
-                    let event_json = self.get(&index.key).await?;
+                    let event_json = self.storage.get_in(RecordKind::Event, &index.key).await?;
                     events.push(serde_json::from_str(&event_json)?);
                 }
                 Ok(events)
@@ -671,6 +1216,24 @@ macro_rules! event_manager_impl {
                     });
                 Ok(count)
             }
+
+            /// Removes the events persisted before the given timestamp, returning the number of removed events.
+            pub(crate) async fn $prune_fn_name(&mut self, before: Timestamp) -> crate::Result<usize> {
+                let indexation: Vec<EventIndexation> = match self.$index_vec.take() {
+                    Some(indexation) => indexation,
+                    None => load_optional_data(&self.storage, $index_key).await?,
+                };
+                let (pruned, kept): (Vec<EventIndexation>, Vec<EventIndexation>) =
+                    indexation.into_iter().partition(|index| index.timestamp < before);
+                for index in &pruned {
+                    self.storage.remove_in(RecordKind::Event, &index.key).await?;
+                }
+                self.$index_vec.replace(kept);
+                if !pruned.is_empty() {
+                    self.storage.set($index_key, &self.$index_vec).await?;
+                }
+                Ok(pruned.len())
+            }
         }
     };
 }
@@ -678,44 +1241,314 @@ macro_rules! event_manager_impl {
 event_manager_impl!(
     BalanceEvent,
     balance_change_indexation,
-    "iota-wallet-balance-change-events",
+    BALANCE_CHANGE_EVENTS_KEY,
     save_balance_change_event,
     get_balance_change_events,
-    get_balance_change_event_count
+    get_balance_change_event_count,
+    prune_balance_change_events
 );
 event_manager_impl!(
     TransactionConfirmationChangeEvent,
     transaction_confirmation_indexation,
-    "iota-wallet-tx-confirmation-events",
+    CONFIRMATION_EVENTS_KEY,
     save_transaction_confirmation_event,
     get_transaction_confirmation_events,
-    get_transaction_confirmation_event_count
+    get_transaction_confirmation_event_count,
+    prune_transaction_confirmation_events
 );
 event_manager_impl!(
     TransactionEvent,
     new_transaction_indexation,
-    "iota-wallet-new-tx-events",
+    NEW_TRANSACTION_EVENTS_KEY,
     save_new_transaction_event,
     get_new_transaction_events,
-    get_new_transaction_event_count
+    get_new_transaction_event_count,
+    prune_new_transaction_events
 );
 event_manager_impl!(
     TransactionReattachmentEvent,
     reattachment_indexation,
-    "iota-wallet-tx-reattachment-events",
+    REATTACHMENT_EVENTS_KEY,
     save_reattachment_event,
     get_reattachment_events,
-    get_reattachment_event_count
+    get_reattachment_event_count,
+    prune_reattachment_events
 );
 event_manager_impl!(
     TransactionEvent,
     broadcast_indexation,
-    "iota-wallet-tx-broadcast-events",
+    BROADCAST_EVENTS_KEY,
     save_broadcast_event,
     get_broadcast_events,
-    get_broadcast_event_count
+    get_broadcast_event_count,
+    prune_broadcast_events
 );
 
+impl StorageManager {
+    /// Removes the persisted events older than the given timestamp, returning the number of removed events.
+    pub(crate) async fn prune_events(&mut self, before: Timestamp) -> crate::Result<usize> {
+        Ok(self.prune_balance_change_events(before).await?
+            + self.prune_transaction_confirmation_events(before).await?
+            + self.prune_new_transaction_events(before).await?
+            + self.prune_reattachment_events(before).await?
+            + self.prune_broadcast_events(before).await?)
+    }
+}
+
+impl StorageManager {
+    /// Gets the seconds elapsed between the persisted broadcast and confirmation events of the account messages.
+    pub(crate) async fn get_confirmation_latencies(
+        &self,
+        account_id: &str,
+    ) -> crate::Result<Vec<(MessageId, Timestamp)>> {
+        let mut broadcast_timestamps = HashMap::new();
+        let broadcast_indexation: Vec<EventIndexation> =
+            load_optional_data(&self.storage, BROADCAST_EVENTS_KEY).await?;
+        for index in broadcast_indexation {
+            let event: TransactionEvent =
+                serde_json::from_str(&self.storage.get_in(RecordKind::Event, &index.key).await?)?;
+            if event.account_id == account_id {
+                broadcast_timestamps.insert(event.message.id, index.timestamp);
+            }
+        }
+
+        let mut latencies = Vec::new();
+        let confirmation_indexation: Vec<EventIndexation> =
+            load_optional_data(&self.storage, CONFIRMATION_EVENTS_KEY).await?;
+        for index in confirmation_indexation {
+            let event: TransactionConfirmationChangeEvent =
+                serde_json::from_str(&self.storage.get_in(RecordKind::Event, &index.key).await?)?;
+            if event.account_id == account_id && event.confirmed {
+                if let Some(broadcast_timestamp) = broadcast_timestamps.get(&event.message.id) {
+                    latencies.push((event.message.id, index.timestamp - broadcast_timestamp));
+                }
+            }
+        }
+        Ok(latencies)
+    }
+}
+
+fn event_sequence_key(account_id: &str) -> String {
+    format!("{}{}", EVENT_SEQUENCE_KEY_PREFIX, account_id)
+}
+
+fn event_dedup_key(account_id: &str, dedup_key: &str) -> String {
+    format!("{}{}-{}", EVENT_DEDUP_KEY_PREFIX, account_id, dedup_key)
+}
+
+fn event_log_key(account_id: &str, sequence: u64) -> String {
+    format!("{}{}-{}", EVENT_LOG_KEY_PREFIX, account_id, sequence)
+}
+
+impl StorageManager {
+    async fn load_event_sequence(&mut self, account_id: &str) -> crate::Result<()> {
+        if !self.event_sequences.contains_key(account_id) {
+            let last: u64 = load_optional_data(&self.storage, &event_sequence_key(account_id)).await?;
+            self.event_sequences.insert(
+                account_id.to_string(),
+                EventSequence {
+                    last,
+                    ..Default::default()
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// Assigns the next sequence number of the account to the event with the given deduplication key.
+    /// If the key was already seen, its original sequence number is returned and the event is flagged as duplicate.
+    /// The sequence number and the key are only written to the storage if the event is persisted.
+    pub(crate) async fn next_event_sequence(
+        &mut self,
+        account_id: &str,
+        dedup_key: &str,
+        persist: bool,
+    ) -> crate::Result<(u64, bool)> {
+        self.load_event_sequence(account_id).await?;
+        if persist {
+            match self.storage.get(&event_dedup_key(account_id, dedup_key)).await {
+                Ok(record) => return Ok((serde_json::from_str(&record)?, true)),
+                Err(crate::Error::RecordNotFound) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        // safe to unwrap: the sequence was loaded above
+        let sequence = self.event_sequences.get_mut(account_id).unwrap();
+        if let Some(number) = sequence.transient_keys.get(dedup_key) {
+            return Ok((*number, true));
+        }
+        sequence.last += 1;
+        let number = sequence.last;
+        if persist {
+            self.storage.set(&event_sequence_key(account_id), &number).await?;
+            self.storage
+                .set(&event_dedup_key(account_id, dedup_key), &number)
+                .await?;
+        } else {
+            sequence.transient_keys.insert(dedup_key.to_string(), number);
+        }
+        Ok((number, false))
+    }
+
+    /// Records a persisted event on the account event log.
+    pub(crate) async fn log_event(
+        &mut self,
+        account_id: &str,
+        sequence: u64,
+        kind: EventKind,
+        key: &str,
+        dedup_key: &str,
+    ) -> crate::Result<()> {
+        self.storage
+            .set(
+                &event_log_key(account_id, sequence),
+                &SequencedEventIndexation {
+                    kind,
+                    key: key.to_string(),
+                    dedup_key: Some(dedup_key.to_string()),
+                },
+            )
+            .await
+    }
+
+    /// Gets the persisted events of the account with a sequence number greater than the given one.
+    pub(crate) async fn get_events_since(
+        &mut self,
+        account_id: &str,
+        sequence: u64,
+    ) -> crate::Result<Vec<AccountEvent>> {
+        self.load_event_sequence(account_id).await?;
+        let last = self.event_sequences[account_id].last;
+        let mut events = Vec::new();
+        for number in sequence.saturating_add(1)..=last {
+            let index: SequencedEventIndexation = match self.storage.get(&event_log_key(account_id, number)).await {
+                Ok(record) => serde_json::from_str(&record)?,
+                // the sequence number wasn't persisted
+                Err(crate::Error::RecordNotFound) => continue,
+                Err(e) => return Err(e),
+            };
+            let record = match self.storage.get_in(RecordKind::Event, &index.key).await {
+                Ok(record) => record,
+                // the event was pruned
+                Err(crate::Error::RecordNotFound) => continue,
+                Err(e) => return Err(e),
+            };
+            events.push(match index.kind {
+                EventKind::BalanceChange => AccountEvent::BalanceChange(serde_json::from_str(&record)?),
+                EventKind::NewTransaction => AccountEvent::NewTransaction(serde_json::from_str(&record)?),
+                EventKind::Broadcast => AccountEvent::Broadcast(serde_json::from_str(&record)?),
+                EventKind::ConfirmationStateChange => {
+                    AccountEvent::ConfirmationStateChange(serde_json::from_str(&record)?)
+                }
+                EventKind::Reattachment => AccountEvent::Reattachment(serde_json::from_str(&record)?),
+            });
+        }
+        Ok(events)
+    }
+}
+
+pub(crate) fn user_data_key(key: &str) -> String {
+    format!("{}{}", USER_DATA_KEY_PREFIX, key)
+}
+
+impl StorageManager {
+    /// Writes and removes a probe record, failing if the storage isn't writable.
+    pub(crate) async fn probe_write(&mut self) -> crate::Result<()> {
+        self.storage.set(HEALTH_PROBE_KEY, &Utc::now().timestamp()).await?;
+        self.storage.remove(HEALTH_PROBE_KEY).await
+    }
+
+    /// Gets the keys of the user data records.
+    pub(crate) async fn get_user_data_keys(&self) -> crate::Result<Vec<String>> {
+        load_optional_data(&self.storage, USER_DATA_INDEX_KEY).await
+    }
+
+    pub(crate) async fn get_user_data(&self, key: &str) -> crate::Result<Option<serde_json::Value>> {
+        match self.storage.get(&user_data_key(key)).await {
+            Ok(record) => Ok(Some(serde_json::from_str(&record)?)),
+            Err(crate::Error::RecordNotFound) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub(crate) async fn set_user_data(&mut self, key: &str, value: &serde_json::Value) -> crate::Result<()> {
+        self.storage.set(&user_data_key(key), value).await?;
+        let mut keys = self.get_user_data_keys().await?;
+        if !keys.iter().any(|k| k == key) {
+            keys.push(key.to_string());
+            self.storage.set(USER_DATA_INDEX_KEY, &keys).await?;
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn remove_user_data(&mut self, key: &str) -> crate::Result<()> {
+        let mut keys = self.get_user_data_keys().await?;
+        if let Some(position) = keys.iter().position(|k| k == key) {
+            keys.remove(position);
+            self.storage.remove(&user_data_key(key)).await?;
+            self.storage.set(USER_DATA_INDEX_KEY, &keys).await?;
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn get_duress_folder(&self) -> crate::Result<Option<String>> {
+        load_optional_data(&self.storage, DURESS_FOLDER_KEY).await
+    }
+
+    pub(crate) async fn set_duress_folder(&mut self, folder: &str) -> crate::Result<()> {
+        self.storage.set(DURESS_FOLDER_KEY, &folder).await
+    }
+}
+
+fn known_ids_key(account_id: &str) -> String {
+    format!("{}{}", KNOWN_IDS_KEY_PREFIX, account_id)
+}
+
+impl StorageManager {
+    /// Gets the ids the account sync doesn't need to request again.
+    pub(crate) async fn get_known_ids(&self, account_id: &str) -> crate::Result<Option<KnownIds>> {
+        match self.storage.get(&known_ids_key(account_id)).await {
+            Ok(record) => Ok(Some(serde_json::from_str(&record)?)),
+            Err(crate::Error::RecordNotFound) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub(crate) async fn save_known_ids(&mut self, account_id: &str, known_ids: &KnownIds) -> crate::Result<()> {
+        self.storage.set(&known_ids_key(account_id), known_ids).await
+    }
+}
+
+fn balance_history_key(account_id: &str) -> String {
+    format!("{}{}", BALANCE_HISTORY_KEY_PREFIX, account_id)
+}
+
+impl StorageManager {
+    /// Gets the daily balance snapshots of the account, sorted by date.
+    pub(crate) async fn get_balance_history(&self, account_id: &str) -> crate::Result<Vec<BalanceSnapshot>> {
+        load_optional_data(&self.storage, &balance_history_key(account_id)).await
+    }
+
+    pub(crate) async fn save_balance_history(
+        &mut self,
+        account_id: &str,
+        history: &[BalanceSnapshot],
+    ) -> crate::Result<()> {
+        self.storage.set(&balance_history_key(account_id), history).await
+    }
+}
+
+impl StorageManager {
+    /// Gets the events whose delivery to a listener failed.
+    pub(crate) async fn get_failed_deliveries(&self) -> crate::Result<Vec<FailedDelivery>> {
+        load_optional_data(&self.storage, FAILED_DELIVERIES_KEY).await
+    }
+
+    pub(crate) async fn save_failed_deliveries(&mut self, deliveries: &[FailedDelivery]) -> crate::Result<()> {
+        self.storage.set(FAILED_DELIVERIES_KEY, deliveries).await
+    }
+}
+
 pub(crate) type StorageHandle = Arc<Mutex<StorageManager>>;
 type Storages = Arc<RwLock<HashMap<PathBuf, StorageHandle>>>;
 static INSTANCES: OnceCell<Storages> = OnceCell::new();
@@ -740,6 +1573,7 @@ pub(crate) async fn set<P: AsRef<Path>>(
         new_transaction_indexation: Default::default(),
         reattachment_indexation: Default::default(),
         broadcast_indexation: Default::default(),
+        event_sequences: Default::default(),
     };
     instances.insert(
         storage_path.as_ref().to_path_buf(),
@@ -776,6 +1610,16 @@ pub(crate) async fn set_encryption_key(storage_path: &Path, encryption_key: [u8;
     }
 }
 
+/// Encrypts the records of the storage with the new key, see [StorageManager::reencrypt].
+pub(crate) async fn reencrypt(storage_path: &Path, encryption_key: [u8; 32]) -> crate::Result<()> {
+    let instances = INSTANCES.get_or_init(Default::default).read().await;
+    if let Some(instance) = instances.get(storage_path) {
+        instance.lock().await.reencrypt(encryption_key).await
+    } else {
+        Err(crate::Error::StorageAdapterNotSet(storage_path.to_path_buf()))
+    }
+}
+
 pub(crate) async fn get_encryption_key_checksum(storage_path: &Path) -> crate::Result<Vec<u8>> {
     let instances = INSTANCES.get_or_init(Default::default).read().await;
     if let Some(instance) = instances.get(storage_path) {
@@ -796,6 +1640,12 @@ pub(crate) async fn clear_encryption_key(storage_path: &Path) -> crate::Result<(
     }
 }
 
+/// Gets all storage instances.
+pub(crate) async fn get_all() -> Vec<StorageHandle> {
+    let instances = INSTANCES.get_or_init(Default::default).read().await;
+    instances.values().cloned().collect()
+}
+
 /// gets the storage adapter
 pub(crate) async fn get(storage_path: &Path) -> crate::Result<StorageHandle> {
     let instances = INSTANCES.get_or_init(Default::default).read().await;
@@ -821,9 +1671,75 @@ pub trait StorageAdapter {
     async fn batch_set(&mut self, records: HashMap<String, String>) -> crate::Result<()>;
     /// Removes a record from the storage.
     async fn remove(&mut self, key: &str) -> crate::Result<()>;
+    /// Writes the records buffered in memory to disk, called on
+    /// [AccountManager#shutdown](../account_manager/struct.AccountManager.html#method.shutdown).
+    /// The default implementation does nothing, for adapters writing through.
+    async fn flush(&mut self) -> crate::Result<()> {
+        Ok(())
+    }
+    /// Writes all the records to a snapshot file, for adapters keeping them in memory.
+    /// The default implementation fails, since the other adapters already persist their records.
+    async fn save_snapshot(&self, _path: &Path) -> crate::Result<()> {
+        Err(crate::Error::Storage(format!(
+            "the {} storage doesn't support snapshots",
+            self.id()
+        )))
+    }
+    /// Gets a binary record from the storage.
+    /// The default implementation reads the records hex encoded by `batch_set_bytes`.
+    async fn get_bytes(&self, key: &str) -> crate::Result<Vec<u8>> {
+        let record = self.get(key).await?;
+        Ok(hex::decode(&record).unwrap_or_else(|_| record.into_bytes()))
+    }
+    /// Batch write of binary records.
+    /// The default implementation stores the records hex encoded; adapters storing raw bytes should override it.
+    async fn batch_set_bytes(&mut self, records: HashMap<String, Vec<u8>>) -> crate::Result<()> {
+        self.batch_set(
+            records
+                .into_iter()
+                .map(|(key, record)| (key, hex::encode(record)))
+                .collect(),
+        )
+        .await
+    }
+    /// Gets a record of the given kind.
+    /// The wallet reads through the `*_record` methods; the default implementations ignore the kind and use the single
+    /// keyspace methods, adapters keeping the kinds apart should override them.
+    async fn get_record(&self, _kind: RecordKind, key: &str) -> crate::Result<String> {
+        self.get(key).await
+    }
+    /// Saves or updates a record of the given kind.
+    async fn set_record(&mut self, _kind: RecordKind, key: &str, record: String) -> crate::Result<()> {
+        self.set(key, record).await
+    }
+    /// Removes a record of the given kind.
+    async fn remove_record(&mut self, _kind: RecordKind, key: &str) -> crate::Result<()> {
+        self.remove(key).await
+    }
+    /// Gets a binary record of the given kind.
+    async fn get_record_bytes(&self, _kind: RecordKind, key: &str) -> crate::Result<Vec<u8>> {
+        self.get_bytes(key).await
+    }
+    /// Gets the binary records of the given kind, in the order of the keys.
+    /// The default implementation reads them one after the other.
+    async fn get_records_bytes(&self, kind: RecordKind, keys: &[String]) -> Vec<crate::Result<Vec<u8>>> {
+        let mut records = Vec::with_capacity(keys.len());
+        for key in keys {
+            records.push(self.get_record_bytes(kind, key).await);
+        }
+        records
+    }
+    /// Batch write of binary records of the given kind.
+    async fn batch_set_record_bytes(
+        &mut self,
+        _kind: RecordKind,
+        records: HashMap<String, Vec<u8>>,
+    ) -> crate::Result<()> {
+        self.batch_set_bytes(records).await
+    }
 }
 
-fn encrypt_record<O: Write>(record: &[u8], encryption_key: &[u8; 32], output: &mut O) -> crate::Result<()> {
+pub(crate) fn encrypt_record<O: Write>(record: &[u8], encryption_key: &[u8; 32], output: &mut O) -> crate::Result<()> {
     let mut nonce = [0; XChaCha20Poly1305::NONCE_LENGTH];
     crypto::utils::rand::fill(&mut nonce).map_err(|e| crate::Error::RecordEncrypt(format!("{:?}", e)))?;
 
@@ -849,8 +1765,11 @@ fn encrypt_record<O: Write>(record: &[u8], encryption_key: &[u8; 32], output: &m
 
 pub(crate) fn decrypt_record(record: &str, encryption_key: &[u8; 32]) -> crate::Result<String> {
     let record: Vec<u8> = serde_json::from_str(record)?;
-    let mut record: &[u8] = &record;
+    let pt = decrypt_bytes(&record, encryption_key)?;
+    Ok(String::from_utf8_lossy(&pt).to_string())
+}
 
+pub(crate) fn decrypt_bytes(mut record: &[u8], encryption_key: &[u8; 32]) -> crate::Result<Vec<u8>> {
     let mut nonce = [0; XChaCha20Poly1305::NONCE_LENGTH];
     record.read_exact(&mut nonce)?;
 
@@ -872,7 +1791,7 @@ pub(crate) fn decrypt_record(record: &str, encryption_key: &[u8; 32]) -> crate::
     )
     .map_err(|e| crate::Error::RecordDecrypt(format!("{:?}", e)))?;
 
-    Ok(String::from_utf8_lossy(&pt).to_string())
+    Ok(pt)
 }
 
 fn parse_accounts(storage_path: &Path, accounts: &[String]) -> crate::Result<Vec<Account>> {
@@ -896,7 +1815,7 @@ fn parse_accounts(storage_path: &Path, accounts: &[String]) -> crate::Result<Vec
 
 #[cfg(test)]
 mod tests {
-    use super::StorageAdapter;
+    use super::{RecordKind, StorageAdapter};
     use std::{collections::HashMap, path::PathBuf};
 
     #[tokio::test]
@@ -933,7 +1852,7 @@ mod tests {
     }
 
     async fn _create_account() -> (PathBuf, crate::account::AccountHandle) {
-        let manager = crate::test_utils::get_account_manager().await;
+        let manager = crate::testing::get_account_manager().await;
 
         let client_options = crate::client::ClientOptionsBuilder::new()
             .with_node("https://api.lb-0.h.chrysalis-devnet.iota.cafe")
@@ -947,7 +1866,7 @@ mod tests {
             .initialise()
             .await
             .unwrap();
-        (manager.storage_path().clone(), account_handle)
+        (manager.storage_path(), account_handle)
     }
 
     #[tokio::test]
@@ -963,12 +1882,170 @@ mod tests {
         assert_eq!(parsed_account, &*account_handle.read().await);
     }
 
+    #[tokio::test]
+    async fn binary_message_records() {
+        let manager = crate::testing::get_account_manager().await;
+        let message = crate::testing::GenerateMessageBuilder::default().build().await;
+        let account_handle = crate::testing::AccountCreator::new(&manager)
+            .messages(vec![message.clone()])
+            .create()
+            .await;
+        let account = account_handle.read().await;
+        let message_key = message.id().to_string();
+
+        let storage_handle = crate::storage::get(&manager.storage_path()).await.unwrap();
+        let mut storage_manager = storage_handle.lock().await;
+        let record = storage_manager
+            .storage
+            .inner
+            .get_record_bytes(RecordKind::Message, &message_key)
+            .await
+            .unwrap();
+        assert_eq!(record[0], super::BINARY_MESSAGE_RECORD);
+        let stored = storage_manager.get_message(&account, message.id()).await.unwrap();
+        assert_eq!(
+            serde_json::to_value(&stored).unwrap(),
+            serde_json::to_value(&message).unwrap()
+        );
+
+        // JSON records are rewritten on load
+        storage_manager
+            .storage
+            .inner
+            .set_record(
+                RecordKind::Message,
+                &message_key,
+                serde_json::to_string(&message).unwrap(),
+            )
+            .await
+            .unwrap();
+        storage_manager
+            .storage
+            .inner
+            .remove_record(RecordKind::Metadata, super::MESSAGE_RECORD_VERSION_KEY)
+            .await
+            .unwrap();
+        assert_eq!(
+            storage_manager.get_message(&account, message.id()).await.unwrap().id(),
+            message.id()
+        );
+        storage_manager.get_accounts().await.unwrap();
+        let record = storage_manager
+            .storage
+            .inner
+            .get_record_bytes(RecordKind::Message, &message_key)
+            .await
+            .unwrap();
+        assert_eq!(record[0], super::BINARY_MESSAGE_RECORD);
+    }
+
+    #[tokio::test]
+    async fn binary_transaction_message_records() {
+        use crate::message::MessagePayload;
+        use iota_client::bee_message::prelude::IndexationPayload;
+
+        for encrypted in [false, true] {
+            let manager = crate::testing::get_account_manager().await;
+            if encrypted {
+                manager.set_storage_password("password").await.unwrap();
+            }
+            let address = crate::testing::generate_random_address();
+            let transaction = crate::testing::GenerateMessageBuilder::default()
+                .address(address.clone())
+                .account_addresses(vec![address.clone()])
+                .input_address(Some(crate::testing::generate_random_iota_address()))
+                .indexation(Some(IndexationPayload::new(b"index", &[1, 2, 3]).unwrap()))
+                .build()
+                .await;
+            let mut indexation = crate::testing::GenerateMessageBuilder::default().build().await;
+            indexation.payload = Some(MessagePayload::Indexation(Box::new(
+                IndexationPayload::new(b"index", b"data").unwrap(),
+            )));
+            let account_handle = crate::testing::AccountCreator::new(&manager)
+                .addresses(vec![address])
+                .messages(vec![transaction.clone(), indexation.clone()])
+                .create()
+                .await;
+            let account = account_handle.read().await;
+
+            let storage_handle = crate::storage::get(&manager.storage_path()).await.unwrap();
+            let storage_manager = storage_handle.lock().await;
+            for message in &[transaction, indexation] {
+                let record = storage_manager
+                    .storage
+                    .inner
+                    .get_record_bytes(RecordKind::Message, &message.id().to_string())
+                    .await
+                    .unwrap();
+                let expected_record = if encrypted {
+                    super::ENCRYPTED_BINARY_MESSAGE_RECORD
+                } else {
+                    super::BINARY_MESSAGE_RECORD
+                };
+                assert_eq!(record[0], expected_record);
+                let stored = storage_manager.get_message(&account, message.id()).await.unwrap();
+                assert_eq!(
+                    serde_json::to_value(&stored).unwrap(),
+                    serde_json::to_value(message).unwrap()
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn message_record_migration_skips_unreadable_records() {
+        let manager = crate::testing::get_account_manager().await;
+        let message = crate::testing::GenerateMessageBuilder::default().build().await;
+        let unreadable = crate::testing::GenerateMessageBuilder::default().build().await;
+        crate::testing::AccountCreator::new(&manager)
+            .messages(vec![message.clone(), unreadable.clone()])
+            .create()
+            .await;
+
+        let storage_handle = crate::storage::get(&manager.storage_path()).await.unwrap();
+        let mut storage_manager = storage_handle.lock().await;
+        for (message, record) in &[
+            (&message, serde_json::to_string(&message).unwrap()),
+            (&unreadable, "{\"unreadable\":true}".to_string()),
+        ] {
+            storage_manager
+                .storage
+                .inner
+                .set_record(RecordKind::Message, &message.id().to_string(), record.clone())
+                .await
+                .unwrap();
+        }
+        storage_manager
+            .storage
+            .inner
+            .remove_record(RecordKind::Metadata, super::MESSAGE_RECORD_VERSION_KEY)
+            .await
+            .unwrap();
+
+        // the readable record is migrated and the unreadable one is left as it is
+        storage_manager.get_accounts().await.unwrap();
+        let record = storage_manager
+            .storage
+            .inner
+            .get_record_bytes(RecordKind::Message, &message.id().to_string())
+            .await
+            .unwrap();
+        assert_eq!(record[0], super::BINARY_MESSAGE_RECORD);
+        let record = storage_manager
+            .storage
+            .inner
+            .get_record_bytes(RecordKind::Message, &unreadable.id().to_string())
+            .await
+            .unwrap();
+        assert_eq!(record, b"{\"unreadable\":true}".to_vec());
+    }
+
     #[tokio::test]
     async fn remove_encryption_key_checksum() {
-        let manager = crate::test_utils::get_account_manager().await;
+        let manager = crate::testing::get_account_manager().await;
         manager.set_storage_password("password").await.unwrap();
 
-        let storage_handle = crate::storage::get(manager.storage_path()).await.unwrap();
+        let storage_handle = crate::storage::get(&manager.storage_path()).await.unwrap();
         storage_handle
             .lock()
             .await
@@ -999,14 +2076,14 @@ mod tests {
 
     #[tokio::test]
     async fn wrong_storage_password_without_kcv() {
-        let manager = crate::test_utils::get_account_manager().await;
+        let manager = crate::testing::get_account_manager().await;
         manager.set_storage_password("password").await.unwrap();
 
-        let _ = crate::test_utils::AccountCreator::new(&manager).create().await;
+        let _ = crate::testing::AccountCreator::new(&manager).create().await;
         assert_eq!(manager.get_accounts().await.unwrap().len(), 1);
 
         manager.clear_storage_password().await.unwrap();
-        let storage_handle = crate::storage::get(manager.storage_path()).await.unwrap();
+        let storage_handle = crate::storage::get(&manager.storage_path()).await.unwrap();
         storage_handle
             .lock()
             .await
@@ -1023,14 +2100,14 @@ mod tests {
 
     #[tokio::test]
     async fn correct_storage_password_without_kcv() {
-        let manager = crate::test_utils::get_account_manager().await;
+        let manager = crate::testing::get_account_manager().await;
         manager.set_storage_password("password").await.unwrap();
 
-        let _ = crate::test_utils::AccountCreator::new(&manager).create().await;
+        let _ = crate::testing::AccountCreator::new(&manager).create().await;
         assert_eq!(manager.get_accounts().await.unwrap().len(), 1);
 
         manager.clear_storage_password().await.unwrap();
-        let storage_handle = crate::storage::get(manager.storage_path()).await.unwrap();
+        let storage_handle = crate::storage::get(&manager.storage_path()).await.unwrap();
         storage_handle
             .lock()
             .await
@@ -1044,10 +2121,10 @@ mod tests {
 
     #[tokio::test]
     async fn wrong_storage_password_without_kcv_and_all_accounts_deleted() {
-        let manager = crate::test_utils::get_account_manager().await;
+        let manager = crate::testing::get_account_manager().await;
         manager.set_storage_password("password").await.unwrap();
 
-        let account_handle = crate::test_utils::AccountCreator::new(&manager).create().await;
+        let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
         assert_eq!(manager.get_accounts().await.unwrap().len(), 1);
 
         manager
@@ -1055,7 +2132,7 @@ mod tests {
             .await
             .expect("failed to remove account");
         manager.clear_storage_password().await.unwrap();
-        let storage_handle = crate::storage::get(manager.storage_path()).await.unwrap();
+        let storage_handle = crate::storage::get(&manager.storage_path()).await.unwrap();
         storage_handle
             .lock()
             .await
@@ -1072,10 +2149,10 @@ mod tests {
 
     #[tokio::test]
     async fn correct_storage_password_without_kcv_and_all_accounts_deleted() {
-        let manager = crate::test_utils::get_account_manager().await;
+        let manager = crate::testing::get_account_manager().await;
         manager.set_storage_password("password").await.unwrap();
 
-        let account_handle = crate::test_utils::AccountCreator::new(&manager).create().await;
+        let account_handle = crate::testing::AccountCreator::new(&manager).create().await;
         assert_eq!(manager.get_accounts().await.unwrap().len(), 1);
 
         manager
@@ -1083,7 +2160,7 @@ mod tests {
             .await
             .expect("failed to remove account");
         manager.clear_storage_password().await.unwrap();
-        let storage_handle = crate::storage::get(manager.storage_path()).await.unwrap();
+        let storage_handle = crate::storage::get(&manager.storage_path()).await.unwrap();
         storage_handle
             .lock()
             .await
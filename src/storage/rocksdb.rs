@@ -1,34 +1,122 @@
 // Copyright 2020 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
-use super::StorageAdapter;
-use rocksdb::{DBCompressionType, Options, WriteBatch, DB};
-use std::{collections::HashMap, path::Path, sync::Arc};
-use tokio::sync::Mutex;
+use super::{RecordKind, StorageAdapter};
+use rocksdb::{ColumnFamily, ColumnFamilyDescriptor, DBCompressionType, Options, WriteBatch, DB};
+use std::{collections::HashMap, path::Path};
 
 /// The storage id.
 pub const STORAGE_ID: &str = "RocksDB";
 
+/// The column families of the record kinds. The records written before the column families, and the records written
+/// without a kind, live in the default column family.
+const COLUMN_FAMILIES: [(RecordKind, &str); 5] = [
+    (RecordKind::Metadata, "metadata"),
+    (RecordKind::Account, "accounts"),
+    (RecordKind::Message, "messages"),
+    (RecordKind::Event, "events"),
+    (RecordKind::Participation, "participation"),
+];
+
 /// Key value storage adapter.
 pub struct RocksdbStorageAdapter {
-    db: Arc<Mutex<DB>>,
+    db: DB,
 }
 
 fn storage_err<E: ToString>(error: E) -> crate::Error {
     crate::Error::Storage(error.to_string())
 }
 
+fn column_family_name(kind: RecordKind) -> &'static str {
+    // safe to unwrap: every kind has a column family
+    COLUMN_FAMILIES.iter().find(|(k, _)| *k == kind).unwrap().1
+}
+
+fn column_family_options(kind: RecordKind) -> Options {
+    let mut opts = Options::default();
+    opts.set_compression_type(DBCompressionType::Lz4);
+    match kind {
+        // read by id, one record at a time or in batches
+        RecordKind::Message | RecordKind::Event => opts.optimize_for_point_lookup(32),
+        // few small records
+        RecordKind::Account | RecordKind::Metadata | RecordKind::Participation => opts.optimize_for_point_lookup(8),
+    }
+    opts
+}
+
 impl RocksdbStorageAdapter {
     /// Initialises the storage adapter.
     pub fn new(path: impl AsRef<Path>) -> crate::Result<Self> {
+        Self::open(path, None)
+    }
+
+    /// Initialises the storage adapter keeping at most `max_open_files` files open, for deployments with a tight file
+    /// descriptor limit. The table files over the limit are reopened when they're read. The limit is at least 16.
+    pub fn with_max_open_files(path: impl AsRef<Path>, max_open_files: usize) -> crate::Result<Self> {
+        Self::open(path, Some(max_open_files))
+    }
+
+    fn open(path: impl AsRef<Path>, max_open_files: Option<usize>) -> crate::Result<Self> {
         let mut opts = Options::default();
         opts.set_compression_type(DBCompressionType::Lz4);
         opts.create_if_missing(true);
         opts.create_missing_column_families(true);
-        let db = DB::open(&opts, path).map_err(storage_err)?;
-        Ok(Self {
-            db: Arc::new(Mutex::new(db)),
-        })
+        if let Some(max_open_files) = max_open_files {
+            // rocksdb needs a few files for its logs and manifest on top of the table files
+            opts.set_max_open_files(max_open_files.clamp(16, i32::MAX as usize) as i32);
+        }
+        let column_families = COLUMN_FAMILIES
+            .iter()
+            .map(|(kind, name)| ColumnFamilyDescriptor::new(*name, column_family_options(*kind)));
+        let db = DB::open_cf_descriptors(&opts, path, column_families).map_err(storage_err)?;
+        Ok(Self { db })
+    }
+
+    fn column_family(&self, kind: RecordKind) -> &ColumnFamily {
+        // safe to unwrap: the column families are created on open
+        self.db.cf_handle(column_family_name(kind)).unwrap()
+    }
+
+    /// Reads the record from the kind column family, falling back to the default column family where the records
+    /// written before the column families live.
+    fn get_from(&self, kind: RecordKind, key: &str) -> crate::Result<Vec<u8>> {
+        match self.db.get_cf(self.column_family(kind), key.as_bytes()) {
+            Ok(Some(r)) => Ok(r),
+            Ok(None) => match self.db.get(key.as_bytes()) {
+                Ok(Some(r)) => Ok(r),
+                Ok(None) => Err(crate::Error::RecordNotFound),
+                Err(e) => Err(storage_err(e)),
+            },
+            Err(e) => Err(storage_err(e)),
+        }
+    }
+
+    /// Reads the record from the default column family, then from the kind column families.
+    fn get_any(&self, key: &str) -> crate::Result<Vec<u8>> {
+        if let Some(r) = self.db.get(key.as_bytes()).map_err(storage_err)? {
+            return Ok(r);
+        }
+        for (kind, _) in COLUMN_FAMILIES.iter() {
+            if let Some(r) = self
+                .db
+                .get_cf(self.column_family(*kind), key.as_bytes())
+                .map_err(storage_err)?
+            {
+                return Ok(r);
+            }
+        }
+        Err(crate::Error::RecordNotFound)
+    }
+
+    /// Writes the records to the kind column family, removing their copy from the default column family.
+    fn write_to(&self, kind: RecordKind, records: impl IntoIterator<Item = (String, Vec<u8>)>) -> crate::Result<()> {
+        let cf = self.column_family(kind);
+        let mut batch = WriteBatch::default();
+        for (key, value) in records {
+            batch.delete(key.as_bytes());
+            batch.put_cf(cf, key.as_bytes(), value);
+        }
+        self.db.write(batch).map_err(storage_err)
     }
 }
 
@@ -39,19 +127,11 @@ impl StorageAdapter for RocksdbStorageAdapter {
     }
 
     async fn get(&self, key: &str) -> crate::Result<String> {
-        match self.db.lock().await.get(key.as_bytes()) {
-            Ok(Some(r)) => Ok(String::from_utf8_lossy(&r).to_string()),
-            Ok(None) => Err(crate::Error::RecordNotFound),
-            Err(e) => Err(storage_err(e)),
-        }
+        self.get_any(key).map(|r| String::from_utf8_lossy(&r).to_string())
     }
 
     async fn set(&mut self, key: &str, record: String) -> crate::Result<()> {
-        self.db
-            .lock()
-            .await
-            .put(key.as_bytes(), record.as_bytes())
-            .map_err(storage_err)?;
+        self.db.put(key.as_bytes(), record.as_bytes()).map_err(storage_err)?;
         Ok(())
     }
 
@@ -60,12 +140,118 @@ impl StorageAdapter for RocksdbStorageAdapter {
         for (key, value) in records {
             batch.put(key.as_bytes(), value.as_bytes());
         }
-        self.db.lock().await.write(batch).map_err(storage_err)?;
+        self.db.write(batch).map_err(storage_err)?;
+        Ok(())
+    }
+
+    async fn get_bytes(&self, key: &str) -> crate::Result<Vec<u8>> {
+        self.get_any(key)
+    }
+
+    async fn batch_set_bytes(&mut self, records: HashMap<String, Vec<u8>>) -> crate::Result<()> {
+        let mut batch = WriteBatch::default();
+        for (key, value) in records {
+            batch.put(key.as_bytes(), value);
+        }
+        self.db.write(batch).map_err(storage_err)?;
         Ok(())
     }
 
     async fn remove(&mut self, key: &str) -> crate::Result<()> {
-        self.db.lock().await.delete(key.as_bytes()).map_err(storage_err)?;
+        self.db.delete(key.as_bytes()).map_err(storage_err)?;
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> crate::Result<()> {
+        self.db.flush().map_err(storage_err)?;
+        for (kind, _) in COLUMN_FAMILIES.iter() {
+            self.db.flush_cf(self.column_family(*kind)).map_err(storage_err)?;
+        }
         Ok(())
     }
+
+    async fn get_record(&self, kind: RecordKind, key: &str) -> crate::Result<String> {
+        self.get_from(kind, key)
+            .map(|r| String::from_utf8_lossy(&r).to_string())
+    }
+
+    async fn set_record(&mut self, kind: RecordKind, key: &str, record: String) -> crate::Result<()> {
+        self.write_to(kind, std::iter::once((key.to_string(), record.into_bytes())))
+    }
+
+    async fn remove_record(&mut self, kind: RecordKind, key: &str) -> crate::Result<()> {
+        let mut batch = WriteBatch::default();
+        batch.delete(key.as_bytes());
+        batch.delete_cf(self.column_family(kind), key.as_bytes());
+        self.db.write(batch).map_err(storage_err)
+    }
+
+    async fn get_record_bytes(&self, kind: RecordKind, key: &str) -> crate::Result<Vec<u8>> {
+        self.get_from(kind, key)
+    }
+
+    async fn get_records_bytes(&self, kind: RecordKind, keys: &[String]) -> Vec<crate::Result<Vec<u8>>> {
+        let cf = self.column_family(kind);
+        self.db
+            .multi_get_cf(keys.iter().map(|key| (cf, key.as_bytes())))
+            .into_iter()
+            .zip(keys)
+            .map(|(record, key)| match record {
+                Ok(Some(r)) => Ok(r),
+                // written before the column families
+                Ok(None) => self.get_from(kind, key),
+                Err(e) => Err(storage_err(e)),
+            })
+            .collect()
+    }
+
+    async fn batch_set_record_bytes(
+        &mut self,
+        kind: RecordKind,
+        records: HashMap<String, Vec<u8>>,
+    ) -> crate::Result<()> {
+        self.write_to(kind, records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RecordKind, RocksdbStorageAdapter, StorageAdapter};
+
+    #[tokio::test]
+    async fn column_families() {
+        let path = "./test-storage/rocksdb-column-families";
+        let _ = std::fs::remove_dir_all(path);
+        let mut adapter = RocksdbStorageAdapter::new(path).unwrap();
+
+        // a record written before the column families is moved by the next write
+        adapter.set("key", "legacy".to_string()).await.unwrap();
+        assert_eq!(adapter.get_record(RecordKind::Event, "key").await.unwrap(), "legacy");
+        adapter
+            .set_record(RecordKind::Event, "key", "event".to_string())
+            .await
+            .unwrap();
+        assert_eq!(adapter.get_record(RecordKind::Event, "key").await.unwrap(), "event");
+        assert_eq!(adapter.get("key").await.unwrap(), "event");
+
+        // the kinds have separate keyspaces
+        adapter
+            .set_record(RecordKind::Message, "key", "message".to_string())
+            .await
+            .unwrap();
+        assert_eq!(adapter.get_record(RecordKind::Event, "key").await.unwrap(), "event");
+        let records = adapter
+            .get_records_bytes(RecordKind::Message, &["key".to_string(), "missing".to_string()])
+            .await;
+        assert_eq!(records[0].as_ref().unwrap(), b"message");
+        assert!(matches!(records[1], Err(crate::Error::RecordNotFound)));
+
+        adapter.remove_record(RecordKind::Event, "key").await.unwrap();
+        assert!(matches!(
+            adapter.get_record(RecordKind::Event, "key").await,
+            Err(crate::Error::RecordNotFound)
+        ));
+        drop(adapter);
+        let _ = std::fs::remove_dir_all(path);
+    }
 }
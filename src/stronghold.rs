@@ -569,12 +569,12 @@ async fn get_public_key(runtime: &mut ActorRuntime, derived_location: Location)
     }
 }
 
-pub async fn generate_address(
+pub async fn get_address_public_key(
     snapshot_path: &Path,
     account_index: usize,
     address_index: usize,
     internal: bool,
-) -> Result<Address> {
+) -> Result<[u8; 32]> {
     let mut runtime = actor_runtime().lock().await;
     check_snapshot(&mut runtime, snapshot_path, None).await?;
     load_private_data_actor(&mut runtime, snapshot_path, None).await?;
@@ -588,7 +588,16 @@ pub async fn generate_address(
     ]);
 
     let derived_location = derive(&mut runtime, chain).await?;
-    let public_key = get_public_key(&mut runtime, derived_location).await?;
+    get_public_key(&mut runtime, derived_location).await
+}
+
+pub async fn generate_address(
+    snapshot_path: &Path,
+    account_index: usize,
+    address_index: usize,
+    internal: bool,
+) -> Result<Address> {
+    let public_key = get_address_public_key(snapshot_path, account_index, address_index, internal).await?;
 
     // Hash the public key to get the address
     let hash = Blake2b256::digest(&public_key);
@@ -0,0 +1,578 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! Helpers to write wallet tests without a node: random addresses and outputs, generated messages, account managers
+//! with a signer that doesn't need a mnemonic, an in-memory storage and a clock that only moves when told to.
+//! Nothing here is meant for production wallets: the test signer generates random addresses and can't sign.
+
+use crate::{
+    account::AccountHandle,
+    account_manager::{AccountManager, AccountStore},
+    address::{Address, AddressBuilder, AddressOutput, AddressWrapper, OutputKind},
+    client::ClientOptionsBuilder,
+    message::{Message, MessagePayload, TransactionBuilderMetadata, TransactionEssence},
+    signing::SignerType,
+};
+
+use chrono::prelude::{DateTime, TimeZone, Utc};
+use iota_client::{
+    bee_message::prelude::{
+        Address as IotaAddress, Ed25519Address, Ed25519Signature, Essence, IndexationPayload, MessageId, Payload,
+        SignatureLockedSingleOutput, SignatureUnlock, TransactionId, TransactionPayloadBuilder, UnlockBlock,
+        UnlockBlocks, UtxoInput,
+    },
+    pow::providers::{NonceProvider, NonceProviderBuilder},
+};
+use once_cell::sync::OnceCell;
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use tokio::sync::Mutex;
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+type GeneratedAddressMap = HashMap<(String, usize, bool), iota_client::bee_message::address::Ed25519Address>;
+static TEST_SIGNER_GENERATED_ADDRESSES: OnceCell<Mutex<GeneratedAddressMap>> = OnceCell::new();
+
+/// The bech32 human readable part of the generated addresses.
+const TEST_BECH32_HRP: &str = "atoi";
+
+/// The signer type registered by the test managers.
+pub fn signer_type() -> SignerType {
+    SignerType::Custom("".to_string())
+}
+
+#[derive(Default)]
+struct TestSigner;
+
+#[async_trait::async_trait]
+impl crate::signing::Signer for TestSigner {
+    async fn get_ledger_status(&self, _is_simulator: bool) -> crate::LedgerStatus {
+        // dummy status
+        crate::LedgerStatus {
+            connected: false,
+            locked: false,
+            app: None,
+        }
+    }
+
+    async fn store_mnemonic(&mut self, _: &Path, _mnemonic: String) -> crate::Result<()> {
+        Ok(())
+    }
+
+    async fn generate_address(
+        &mut self,
+        account: &crate::account::Account,
+        address_index: usize,
+        internal: bool,
+        _metadata: crate::signing::GenerateAddressMetadata,
+    ) -> crate::Result<iota_client::bee_message::address::Address> {
+        // store and read the generated addresses from the static map so the generation is deterministic
+        let generated_addresses = TEST_SIGNER_GENERATED_ADDRESSES.get_or_init(Default::default);
+        let mut generated_addresses = generated_addresses.lock().await;
+        let key = (account.id().clone(), address_index, internal);
+        if let Some(address) = generated_addresses.get(&key) {
+            Ok(iota_client::bee_message::address::Address::Ed25519(*address))
+        } else {
+            let mut address = [0; iota_client::bee_message::address::ED25519_ADDRESS_LENGTH];
+            crypto::utils::rand::fill(&mut address).unwrap();
+            let address = iota_client::bee_message::address::Ed25519Address::new(address);
+            generated_addresses.insert(key, address);
+            Ok(iota_client::bee_message::address::Address::Ed25519(address))
+        }
+    }
+
+    async fn sign_message<'a>(
+        &mut self,
+        _account: &crate::account::Account,
+        _essence: &iota_client::bee_message::prelude::Essence,
+        _inputs: &mut Vec<crate::signing::TransactionInput>,
+        _metadata: crate::signing::SignMessageMetadata<'a>,
+    ) -> crate::Result<Vec<iota_client::bee_message::prelude::UnlockBlock>> {
+        Ok(Vec::new())
+    }
+}
+
+/// Picks a storage folder that doesn't exist yet under `parent`.
+fn random_storage_path(parent: &Path) -> PathBuf {
+    loop {
+        let storage_path: String = thread_rng()
+            .sample_iter(&Alphanumeric)
+            .map(char::from)
+            .take(10)
+            .collect();
+        let storage_path = parent.join(storage_path);
+        if !storage_path.exists() {
+            break storage_path;
+        }
+    }
+}
+
+/// Creates a manager persisting to a random folder under `./test-storage`, with the test signer registered as
+/// [signer_type](fn.signer_type.html) and, with the `stronghold` feature, a Stronghold mnemonic.
+pub async fn get_account_manager() -> AccountManager {
+    let storage_path = random_storage_path(Path::new("./test-storage"));
+
+    let manager = AccountManager::builder()
+        .with_storage(storage_path, None)
+        .unwrap()
+        .with_skip_polling()
+        .finish()
+        .await
+        .unwrap();
+
+    let signer_type = signer_type();
+    crate::signing::set_signer(signer_type.clone(), TestSigner::default()).await;
+    manager.store_mnemonic(signer_type, None).await.unwrap();
+
+    #[cfg(feature = "stronghold")]
+    manager.set_stronghold_password("password").await.unwrap();
+
+    #[cfg(feature = "stronghold")]
+    manager.store_mnemonic(SignerType::Stronghold, None).await.unwrap();
+
+    manager
+}
+
+/// Creates a manager keeping its records in memory, with the test signer registered as
+/// [signer_type](fn.signer_type.html). Nothing is written to disk.
+pub async fn get_in_memory_account_manager() -> AccountManager {
+    let storage_path = random_storage_path(&std::env::temp_dir().join("iota-wallet-test-storage"));
+
+    let manager = AccountManager::builder()
+        .with_storage(storage_path, None)
+        .unwrap()
+        .with_memory_storage()
+        .with_skip_polling()
+        .finish()
+        .await
+        .unwrap();
+
+    let signer_type = signer_type();
+    crate::signing::set_signer(signer_type.clone(), TestSigner::default()).await;
+    manager.store_mnemonic(signer_type, None).await.unwrap();
+
+    manager
+}
+
+struct StorageTestCase {
+    storage_password: Option<String>,
+}
+
+enum ManagerTestCase {
+    Signer(SignerType),
+    Storage(StorageTestCase),
+}
+
+/// The manager configurations [with_account_manager](fn.with_account_manager.html) runs the test with.
+#[derive(Eq, PartialEq)]
+pub enum TestType {
+    /// Each signer type.
+    Signing,
+    /// Unencrypted and encrypted storage.
+    Storage,
+    /// Each signer type and storage encryption.
+    SigningAndStorage,
+}
+
+/// Runs the test once per manager configuration of the test type.
+pub async fn with_account_manager<R: futures::Future<Output = ()>, F: Fn(AccountManager, SignerType) -> R>(
+    test_type: TestType,
+    cb: F,
+) {
+    let mut test_cases: Vec<ManagerTestCase> = Vec::new();
+
+    if test_type == TestType::Signing || test_type == TestType::SigningAndStorage {
+        test_cases.push(ManagerTestCase::Signer(signer_type()));
+        #[cfg(feature = "stronghold")]
+        {
+            test_cases.push(ManagerTestCase::Signer(SignerType::Stronghold));
+        }
+    }
+
+    if test_type == TestType::Storage || test_type == TestType::SigningAndStorage {
+        test_cases.push(ManagerTestCase::Storage(StorageTestCase { storage_password: None }));
+        test_cases.push(ManagerTestCase::Storage(StorageTestCase {
+            storage_password: Some("password".to_string()),
+        }));
+    }
+
+    for test_case in test_cases {
+        let storage_path = random_storage_path(Path::new("./test-storage"));
+        std::fs::create_dir_all(&storage_path).unwrap();
+
+        let mut manager_builder = AccountManager::builder();
+
+        let signer_type = match test_case {
+            ManagerTestCase::Signer(signer_type) => {
+                crate::signing::set_signer(signer_type.clone(), TestSigner::default()).await;
+                manager_builder = manager_builder.with_storage(storage_path, None).unwrap();
+                signer_type
+            }
+            ManagerTestCase::Storage(config) => {
+                manager_builder = manager_builder
+                    .with_storage(storage_path, config.storage_password.as_deref())
+                    .unwrap();
+                #[cfg(feature = "stronghold")]
+                let signer_type = SignerType::Stronghold;
+                #[cfg(not(feature = "stronghold"))]
+                let signer_type = {
+                    let signer_type = crate::testing::signer_type();
+                    crate::signing::set_signer(signer_type.clone(), TestSigner::default()).await;
+                    signer_type
+                };
+                signer_type
+            }
+        };
+
+        let manager = manager_builder.with_skip_polling().finish().await.unwrap();
+
+        #[cfg(feature = "stronghold")]
+        manager.set_stronghold_password("password").await.unwrap();
+
+        manager.store_mnemonic(signer_type.clone(), None).await.unwrap();
+
+        cb(manager, signer_type).await;
+    }
+}
+
+/// The miner builder.
+#[derive(Default)]
+pub struct NoopNonceProviderBuilder;
+
+impl NonceProviderBuilder for NoopNonceProviderBuilder {
+    type Provider = NoopNonceProvider;
+
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn finish(self) -> NoopNonceProvider {
+        NoopNonceProvider {}
+    }
+}
+
+/// The miner used for PoW
+pub struct NoopNonceProvider;
+
+impl NonceProvider for NoopNonceProvider {
+    type Builder = NoopNonceProviderBuilder;
+    type Error = crate::Error;
+
+    fn nonce(&self, _bytes: &[u8], _target_score: f64) -> std::result::Result<u64, Self::Error> {
+        Ok(0)
+    }
+}
+
+/// Creates an account with the given addresses and messages, without syncing it.
+pub struct AccountCreator<'a> {
+    manager: &'a AccountManager,
+    addresses: Vec<Address>,
+    messages: Vec<Message>,
+    signer_type: Option<SignerType>,
+}
+
+impl<'a> AccountCreator<'a> {
+    /// Initialises the creator for the manager.
+    pub fn new(manager: &'a AccountManager) -> Self {
+        Self {
+            manager,
+            addresses: Vec::new(),
+            messages: Vec::new(),
+            signer_type: None,
+        }
+    }
+
+    /// Sets the account addresses.
+    pub fn addresses(mut self, addresses: Vec<Address>) -> Self {
+        self.addresses = addresses;
+        self
+    }
+
+    /// Sets the account messages.
+    pub fn messages(mut self, messages: Vec<Message>) -> Self {
+        self.messages = messages;
+        self
+    }
+
+    /// Sets the account signer type.
+    pub fn signer_type(mut self, signer_type: SignerType) -> Self {
+        self.signer_type.replace(signer_type);
+        self
+    }
+
+    /// Creates the account.
+    pub async fn create(self) -> AccountHandle {
+        let client_options = ClientOptionsBuilder::new()
+            .with_node("https://api.lb-0.h.chrysalis-devnet.iota.cafe")
+            .expect("invalid node URL")
+            .build()
+            .unwrap();
+
+        let mut account_initialiser = self.manager.create_account(client_options).unwrap();
+        if let Some(signer_type) = self.signer_type {
+            account_initialiser = account_initialiser.signer_type(signer_type);
+        }
+        account_initialiser
+            .alias("alias")
+            .messages(self.messages)
+            .addresses(self.addresses)
+            .initialise()
+            .await
+            .expect("failed to add account")
+    }
+}
+
+/// Generates a random testnet address.
+pub fn generate_random_iota_address() -> AddressWrapper {
+    AddressWrapper::new(
+        IotaAddress::Ed25519(Ed25519Address::new(rand::random::<[u8; 32]>())),
+        TEST_BECH32_HRP.to_string(),
+    )
+}
+
+/// Generates a random public address at key index 0, without outputs.
+pub fn generate_random_address() -> Address {
+    AddressBuilder::new()
+        .key_index(0)
+        .address(generate_random_iota_address())
+        .outputs(Vec::new())
+        .build()
+        .unwrap()
+}
+
+/// Generates an unspent output of the address, created by a random transaction.
+pub fn generate_random_output(address: AddressWrapper, amount: u64) -> AddressOutput {
+    AddressOutput {
+        transaction_id: TransactionId::new(rand::random::<[u8; 32]>()),
+        message_id: MessageId::new(rand::random::<[u8; 32]>()),
+        index: 0,
+        amount,
+        is_spent: false,
+        address,
+        kind: OutputKind::SignatureLockedSingle,
+    }
+}
+
+/// A clock that only moves when advanced, to make timestamps in tests deterministic.
+/// Clones share the same time.
+#[derive(Debug, Clone)]
+pub struct MockClock {
+    now: Arc<std::sync::Mutex<DateTime<Utc>>>,
+}
+
+impl Default for MockClock {
+    /// A clock starting at 2021-01-01T00:00:00Z.
+    fn default() -> Self {
+        Self::new(Utc.ymd(2021, 1, 1).and_hms(0, 0, 0))
+    }
+}
+
+impl MockClock {
+    /// Creates a clock starting at the given time.
+    pub fn new(start: DateTime<Utc>) -> Self {
+        Self {
+            now: Arc::new(std::sync::Mutex::new(start)),
+        }
+    }
+
+    /// Gets the current time of the clock.
+    pub fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().unwrap()
+    }
+
+    /// Moves the clock forward, returning the new time.
+    pub fn advance(&self, duration: chrono::Duration) -> DateTime<Utc> {
+        let mut now = self.now.lock().unwrap();
+        *now = *now + duration;
+        *now
+    }
+}
+
+macro_rules! builder_setters {
+    ($ty:ident, $($x:ident => $type:ty),*) => {
+        impl $ty {
+            $(
+                #[doc = concat!("Sets the `", stringify!($x), "` of the message.")]
+                pub fn $x(mut self, value: $type) -> Self {
+                    self.$x = value;
+                    self
+                }
+            )*
+        }
+    }
+}
+
+/// Generates a transaction message sending `value` to `address`, without a node.
+pub struct GenerateMessageBuilder {
+    value: u64,
+    address: Address,
+    confirmed: Option<bool>,
+    broadcasted: bool,
+    input_transaction_id: TransactionId,
+    input_address: Option<AddressWrapper>,
+    account_addresses: Vec<Address>,
+    timestamp: DateTime<Utc>,
+    indexation: Option<IndexationPayload>,
+}
+
+impl Default for GenerateMessageBuilder {
+    fn default() -> Self {
+        Self {
+            value: rand::thread_rng().gen_range(1..50000),
+            address: generate_random_address(),
+            confirmed: Some(false),
+            broadcasted: false,
+            input_transaction_id: TransactionId::new([0; 32]),
+            input_address: None,
+            account_addresses: Vec::new(),
+            timestamp: Utc::now(),
+            indexation: None,
+        }
+    }
+}
+
+builder_setters!(
+    GenerateMessageBuilder,
+    value => u64,
+    address => Address,
+    confirmed => Option<bool>,
+    broadcasted => bool,
+    input_transaction_id => TransactionId,
+    input_address => Option<AddressWrapper>,
+    account_addresses => Vec<Address>,
+    timestamp => DateTime<Utc>,
+    indexation => Option<IndexationPayload>
+);
+
+impl GenerateMessageBuilder {
+    /// Builds the message.
+    pub async fn build(self) -> Message {
+        let bech32_hrp = self.address.address().bech32_hrp().to_string();
+        let mut id = [0; 32];
+        crypto::utils::rand::fill(&mut id).unwrap();
+        let id = MessageId::new(id);
+        let tx_metadata = TransactionBuilderMetadata {
+            id: &id,
+            bech32_hrp,
+            account_id: "",
+            accounts: AccountStore::new(Default::default()),
+            account_addresses: &self.account_addresses,
+            client_options: &ClientOptionsBuilder::new().build().unwrap(),
+        };
+
+        let mut essence_builder = iota_client::bee_message::prelude::RegularEssence::builder()
+            .add_output(
+                SignatureLockedSingleOutput::new(*self.address.address().as_ref(), self.value)
+                    .unwrap()
+                    .into(),
+            )
+            .add_input(UtxoInput::new(self.input_transaction_id, 0).unwrap().into());
+        if let Some(indexation) = self.indexation {
+            essence_builder = essence_builder.with_payload(Payload::Indexation(Box::new(indexation)));
+        }
+        let mut payload = MessagePayload::new(
+            Payload::Transaction(Box::new(
+                TransactionPayloadBuilder::new()
+                    .with_essence(Essence::Regular(essence_builder.finish().unwrap()))
+                    .with_unlock_blocks(
+                        UnlockBlocks::new(vec![UnlockBlock::Signature(SignatureUnlock::Ed25519(
+                            Ed25519Signature::new([0; 32], [0; 64]),
+                        ))])
+                        .unwrap(),
+                    )
+                    .finish()
+                    .unwrap(),
+            )),
+            &tx_metadata,
+        )
+        .await
+        .unwrap();
+        if let MessagePayload::Transaction(ref mut tx) = payload {
+            let TransactionEssence::Regular(ref mut essence) = tx.essence_mut();
+            if let Some(address) = self.input_address {
+                let input = essence.inputs_mut().iter_mut().next().unwrap();
+                if let crate::message::TransactionInput::Utxo(ref mut utxo) = input {
+                    utxo.metadata.replace(crate::address::AddressOutput {
+                        transaction_id: self.input_transaction_id,
+                        message_id: iota_client::bee_message::MessageId::from([0; 32]),
+                        index: 0,
+                        amount: 10000000,
+                        is_spent: false,
+                        address,
+                        kind: crate::address::OutputKind::SignatureLockedSingle,
+                    });
+                    essence.incoming = essence.is_incoming(&self.account_addresses);
+                }
+            }
+        }
+
+        Message {
+            id,
+            version: 1,
+            parents: vec![MessageId::new([0; 32])],
+            payload_length: 0,
+            payload: Some(payload),
+            timestamp: self.timestamp,
+            nonce: 0,
+            confirmed: self.confirmed,
+            broadcasted: self.broadcasted,
+            reattachment_message_id: None,
+            reattachment_of: None,
+            reattachments: Vec::new(),
+            funding_sources: Vec::new(),
+            custom_payload: None,
+            transfer_timings: None,
+            transfer_context: None,
+            raw: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MockClock;
+
+    #[tokio::test]
+    async fn in_memory_account_manager() {
+        let manager = super::get_in_memory_account_manager().await;
+        assert!(manager.get_accounts().await.unwrap().is_empty());
+        // no database was opened
+        assert!(!manager.storage_path().exists());
+    }
+
+    #[tokio::test]
+    async fn memory_storage_snapshot() {
+        let manager = super::get_in_memory_account_manager().await;
+        let account_handle = super::AccountCreator::new(&manager).create().await;
+        let account_id = account_handle.id().await;
+        let snapshot_path = std::env::temp_dir().join(format!("iota-wallet-snapshot-{}.json", account_id));
+        manager.save_storage_snapshot(&snapshot_path).await.unwrap();
+
+        let storage_path = super::random_storage_path(&std::env::temp_dir().join("iota-wallet-test-storage"));
+        let restored = super::AccountManager::builder()
+            .with_storage(storage_path, None)
+            .unwrap()
+            .with_memory_storage_snapshot(&snapshot_path)
+            .with_skip_polling()
+            .finish()
+            .await
+            .unwrap();
+        assert!(restored.get_account(&account_id).await.is_ok());
+        let _ = std::fs::remove_file(snapshot_path);
+    }
+
+    #[test]
+    fn mock_clock() {
+        let clock = MockClock::default();
+        let start = clock.now();
+        let shared = clock.clone();
+        assert_eq!(
+            shared.advance(chrono::Duration::days(1)) - start,
+            chrono::Duration::days(1)
+        );
+        assert_eq!(clock.now(), shared.now());
+    }
+}
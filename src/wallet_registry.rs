@@ -0,0 +1,227 @@
+// Copyright 2021 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    account::AccountBalance,
+    account_manager::AccountManager,
+    event::{BalanceEvent, EventId, TransactionConfirmationChangeEvent, TransactionEvent},
+};
+
+use getset::Getters;
+use serde::Serialize;
+use tokio::sync::RwLock;
+
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
+
+type Wallets = Arc<RwLock<HashMap<String, Arc<AccountManager>>>>;
+
+/// The balance summary of a wallet of the registry.
+#[derive(Debug, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct WalletSummary {
+    /// The wallet name.
+    name: String,
+    /// The wallet storage path.
+    #[serde(rename = "storagePath")]
+    storage_path: PathBuf,
+    /// Number of accounts of the wallet, excluding rehearsal accounts.
+    #[serde(rename = "accountCount")]
+    account_count: usize,
+    /// The summed balance of the wallet accounts.
+    balance: AccountBalance,
+}
+
+/// The balance summary of all wallets of the registry.
+#[derive(Debug, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct RegistrySummary {
+    /// The summary of each wallet, sorted by name.
+    wallets: Vec<WalletSummary>,
+    /// The summed balance of all wallets.
+    balance: AccountBalance,
+}
+
+fn empty_balance() -> AccountBalance {
+    AccountBalance {
+        total: 0,
+        available: 0,
+        incoming: 0,
+        outgoing: 0,
+    }
+}
+
+fn add_balance(sum: &mut AccountBalance, balance: &AccountBalance) {
+    sum.total += balance.total;
+    sum.available += balance.available;
+    sum.incoming += balance.incoming;
+    sum.outgoing += balance.outgoing;
+}
+
+/// Finds the name of the wallet holding the account.
+async fn wallet_of(wallets: &Wallets, account_id: &str) -> Option<String> {
+    for (name, manager) in wallets.read().await.iter() {
+        if manager.accounts().read().await.contains_key(account_id) {
+            return Some(name.clone());
+        }
+    }
+    None
+}
+
+/// Holds several account managers, e.g. one per user profile, each with its own storage and Stronghold.
+/// Events are routed to listeners with the name of the wallet the account belongs to.
+#[derive(Default)]
+pub struct WalletRegistry {
+    wallets: Wallets,
+}
+
+impl WalletRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an account manager to the registry under the given name.
+    pub async fn register(&self, name: impl Into<String>, manager: AccountManager) -> crate::Result<()> {
+        let name = name.into();
+        let mut wallets = self.wallets.write().await;
+        if wallets.contains_key(&name) {
+            return Err(crate::Error::WalletAlreadyRegistered(name));
+        }
+        wallets.insert(name, Arc::new(manager));
+        Ok(())
+    }
+
+    /// Removes the wallet with the given name from the registry, returning its account manager.
+    pub async fn unregister(&self, name: &str) -> Option<Arc<AccountManager>> {
+        self.wallets.write().await.remove(name)
+    }
+
+    /// Gets the account manager of the wallet with the given name.
+    pub async fn get(&self, name: &str) -> Option<Arc<AccountManager>> {
+        self.wallets.read().await.get(name).cloned()
+    }
+
+    /// Gets the names of the registered wallets, sorted.
+    pub async fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.wallets.read().await.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Gets the name of the wallet holding the account with the given identifier.
+    pub async fn wallet_of(&self, account_id: &str) -> Option<String> {
+        wallet_of(&self.wallets, account_id).await
+    }
+
+    /// Sums the balances of the accounts of each wallet, from the local account state.
+    pub async fn summary(&self) -> crate::Result<RegistrySummary> {
+        let mut summary = RegistrySummary {
+            wallets: Vec::new(),
+            balance: empty_balance(),
+        };
+        for (name, manager) in self.wallets.read().await.iter() {
+            let mut wallet = WalletSummary {
+                name: name.clone(),
+                storage_path: manager.storage_path(),
+                account_count: 0,
+                balance: empty_balance(),
+            };
+            for account_handle in manager.get_accounts().await? {
+                if account_handle.read().await.rehearsal_of().is_some() {
+                    continue;
+                }
+                wallet.account_count += 1;
+                add_balance(&mut wallet.balance, &account_handle.balance().await?);
+            }
+            add_balance(&mut summary.balance, &wallet.balance);
+            summary.wallets.push(wallet);
+        }
+        summary.wallets.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(summary)
+    }
+
+    /// Listen to balance changes on the accounts of the registered wallets.
+    /// The listener is called with the wallet name; remove it with
+    /// [remove_balance_change_listener](../event/fn.remove_balance_change_listener.html).
+    pub async fn on_balance_change<F: Fn(&str, &BalanceEvent) + Send + Sync + 'static>(&self, cb: F) -> EventId {
+        let wallets = self.wallets.clone();
+        let cb = Arc::new(cb);
+        crate::event::on_balance_change(move |event| {
+            let (wallets, cb, event) = (wallets.clone(), cb.clone(), event.clone());
+            crate::spawn(async move {
+                if let Some(name) = wallet_of(&wallets, event.account_id()).await {
+                    cb(&name, &event);
+                }
+            });
+        })
+        .await
+    }
+
+    /// Listen to new transactions on the accounts of the registered wallets.
+    /// The listener is called with the wallet name; remove it with
+    /// [remove_new_transaction_listener](../event/fn.remove_new_transaction_listener.html).
+    pub async fn on_new_transaction<F: Fn(&str, &TransactionEvent) + Send + Sync + 'static>(&self, cb: F) -> EventId {
+        let wallets = self.wallets.clone();
+        let cb = Arc::new(cb);
+        crate::event::on_new_transaction(move |event| {
+            let (wallets, cb, event) = (wallets.clone(), cb.clone(), event.clone());
+            crate::spawn(async move {
+                if let Some(name) = wallet_of(&wallets, event.account_id()).await {
+                    cb(&name, &event);
+                }
+            });
+        })
+        .await
+    }
+
+    /// Listen to transaction confirmation state changes on the accounts of the registered wallets.
+    /// The listener is called with the wallet name; remove it with
+    /// [remove_confirmation_state_change_listener](../event/fn.remove_confirmation_state_change_listener.html).
+    pub async fn on_confirmation_state_change<
+        F: Fn(&str, &TransactionConfirmationChangeEvent) + Send + Sync + 'static,
+    >(
+        &self,
+        cb: F,
+    ) -> EventId {
+        let wallets = self.wallets.clone();
+        let cb = Arc::new(cb);
+        crate::event::on_confirmation_state_change(move |event| {
+            let (wallets, cb, event) = (wallets.clone(), cb.clone(), event.clone());
+            crate::spawn(async move {
+                if let Some(name) = wallet_of(&wallets, event.account_id()).await {
+                    cb(&name, &event);
+                }
+            });
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[tokio::test]
+    async fn wallet_registry() {
+        let registry = super::WalletRegistry::new();
+        let first = crate::testing::get_account_manager().await;
+        let second = crate::testing::get_account_manager().await;
+        let account_handle = crate::testing::AccountCreator::new(&second).create().await;
+        let account_id = account_handle.id().await;
+
+        registry.register("personal", first).await.unwrap();
+        registry.register("business", second).await.unwrap();
+        let duplicate = crate::testing::get_account_manager().await;
+        assert!(matches!(
+            registry.register("personal", duplicate).await,
+            Err(crate::Error::WalletAlreadyRegistered(_))
+        ));
+
+        assert_eq!(registry.names().await, vec!["business", "personal"]);
+        assert_eq!(registry.wallet_of(&account_id).await.as_deref(), Some("business"));
+        let summary = registry.summary().await.unwrap();
+        assert_eq!(summary.wallets().len(), 2);
+        assert_eq!(*summary.wallets()[0].account_count(), 1);
+
+        assert!(registry.unregister("business").await.is_some());
+        assert!(registry.wallet_of(&account_id).await.is_none());
+    }
+}